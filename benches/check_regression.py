@@ -0,0 +1,55 @@
+#!/usr/bin/env python3
+"""检查 criterion 基准与基线的对比结果，任一基准变慢超过阈值时返回非零退出码。
+
+先运行 `cargo bench --bench <name> -- --baseline <baseline>`，criterion 会在
+target/criterion/**/change/estimates.json 中写入相对基线的变化，本脚本读取这些结果。
+"""
+
+import argparse
+import json
+import sys
+from pathlib import Path
+
+
+def collect_changes(criterion_dir: Path):
+    """返回 [(基准名, 平均耗时变化比例)]"""
+    changes = []
+    for estimates in sorted(criterion_dir.glob("**/change/estimates.json")):
+        bench_dir = estimates.parent.parent
+        name = "/".join(bench_dir.relative_to(criterion_dir).parts)
+        with estimates.open(encoding="utf-8") as f:
+            data = json.load(f)
+        changes.append((name, data["mean"]["point_estimate"]))
+    return changes
+
+
+def main() -> int:
+    parser = argparse.ArgumentParser(description="检查基准测试的性能回归")
+    parser.add_argument("--criterion-dir", default="target/criterion", help="criterion 输出目录")
+    parser.add_argument("--threshold", type=float, default=10.0, help="允许的最大变慢百分比")
+    parser.add_argument("--filter", default="", help="只检查名称包含该字符串的基准")
+    args = parser.parse_args()
+
+    criterion_dir = Path(args.criterion_dir)
+    changes = [(name, change) for name, change in collect_changes(criterion_dir) if args.filter in name]
+    if not changes:
+        print(f"未在 {criterion_dir} 中找到对比结果，请先使用 --baseline 运行基准", file=sys.stderr)
+        return 2
+
+    regressions = []
+    for name, change in changes:
+        percent = change * 100.0
+        marker = "❌" if percent > args.threshold else "✅"
+        print(f"{marker} {name:<50} {percent:+7.2f}%")
+        if percent > args.threshold:
+            regressions.append(name)
+
+    if regressions:
+        print(f"\n{len(regressions)} 个基准变慢超过 {args.threshold}%", file=sys.stderr)
+        return 1
+    print(f"\n全部 {len(changes)} 个基准在 {args.threshold}% 阈值内")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,80 @@
+//! 混合搜索候选打分：串行与 rayon 并行的对比
+//!
+//! 运行：`cargo bench --bench hybrid_scoring`，与基线对比的流程见 `benches/vector_store.rs`
+//!
+//! 候选集模拟 1k / 10k / 100k 文档的向量库，内容和标题按固定规则生成，结果可复现。
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use std::collections::{HashMap, HashSet};
+
+use grape_mcp_devtools::tools::hybrid_scoring::HybridScorer;
+use grape_mcp_devtools::tools::vector_docs_tool::SearchResult;
+
+const PACKAGES: &[(&str, &str)] = &[
+    ("rust", "tokio"),
+    ("rust", "serde"),
+    ("python", "requests"),
+    ("javascript", "express"),
+    ("go", "gin"),
+];
+
+const SENTENCES: &[&str] = &[
+    "Spawns a new asynchronous task, returning a JoinHandle for it.",
+    "Serialize this value into the given serializer and return the output.",
+    "Sends a GET request to the given URL and returns the response body.",
+    "Creates an application router and registers middleware for every route.",
+    "The runtime schedules tasks cooperatively across worker threads.",
+    "Returns an error if the connection is closed before the handshake completes.",
+];
+
+fn candidates(count: usize) -> Vec<SearchResult> {
+    (0..count)
+        .map(|i| {
+            let (language, package) = PACKAGES[i % PACKAGES.len()];
+            let content: Vec<&str> = (0..8).map(|j| SENTENCES[(i + j * 7) % SENTENCES.len()]).collect();
+            SearchResult {
+                id: format!("{}/{}/1.0/doc-{}", language, package, i),
+                content: content.join(" "),
+                title: format!("{} {} section {}", package, if i % 4 == 0 { "API" } else { "Guide" }, i),
+                language: language.to_string(),
+                package_name: package.to_string(),
+                version: "1.0".to_string(),
+                doc_type: if i % 4 == 0 { "api_reference".to_string() } else { "documentation".to_string() },
+                metadata: HashMap::new(),
+                score: 1.0 / (1.0 + (i % 1000) as f32 / 100.0),
+            }
+        })
+        .collect()
+}
+
+fn bench_hybrid_scoring(c: &mut Criterion) {
+    let query = "how to spawn a tokio task on the runtime";
+    let keywords = ["spawn", "tokio", "task", "runtime"].map(String::from);
+    let scorer = HybridScorer::new(query, keywords);
+    let exclude = HashSet::new();
+
+    let mut group = c.benchmark_group("hybrid_scoring");
+    group.sample_size(10);
+    for size in [1_000, 10_000, 100_000] {
+        let input = candidates(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("serial", size), &input, |b, input| {
+            b.iter_batched(
+                || input.clone(),
+                |candidates| black_box(scorer.rank_serial(candidates, &exclude)),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", size), &input, |b, input| {
+            b.iter_batched(
+                || input.clone(),
+                |candidates| black_box(scorer.rank_parallel(candidates, &exclude)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_hybrid_scoring);
+criterion_main!(benches);
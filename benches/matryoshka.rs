@@ -0,0 +1,119 @@
+//! Matryoshka 截断维度的搜索耗时与召回率
+//!
+//! 运行：`cargo bench --bench matryoshka`，与基线对比的流程见 `benches/vector_store.rs`
+//!
+//! 语料为 10k 个 768 维向量，按固定种子生成。Matryoshka 训练的模型把信息集中在前面的维度，
+//! 这里让第 i 维的幅度按 `1 / (1 + i / 32)` 衰减来模拟；查询是语料向量加噪声。
+//! 每个截断维度（768 / 512 / 256 / 128 / 64）建一个索引，测量 `search_similar` 的耗时，
+//! 并在开始测量前打印 recall@10：截断后的检索结果与全维度精确 top-10 的重合比例。
+//! 真实模型的召回率取决于模型本身，截断前应该用自己的语料复核。
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use grape_mcp_devtools::tools::cache_tiers::CacheTier;
+use grape_mcp_devtools::tools::embedder::truncate_embedding;
+use grape_mcp_devtools::tools::vector_docs_tool::{DocumentRecord, VectorDocsTool};
+
+const CORPUS_SIZE: usize = 10_000;
+const FULL_DIMENSION: usize = 768;
+const DIMENSIONS: &[usize] = &[768, 512, 256, 128, 64];
+const QUERIES: usize = 50;
+const TOP_K: usize = 10;
+
+/// splitmix64，生成可复现的伪随机数
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// -1 到 1 之间的伪随机数
+fn uniform(state: &mut u64) -> f32 {
+    (next_random(state) % 2000) as f32 / 1000.0 - 1.0
+}
+
+/// 前面的维度幅度大、后面的小，模拟 Matryoshka 嵌入
+fn embedding(seed: u64) -> Vec<f32> {
+    let mut state = seed;
+    let vector = (0..FULL_DIMENSION).map(|i| uniform(&mut state) / (1.0 + i as f32 / 32.0)).collect();
+    truncate_embedding(vector, FULL_DIMENSION)
+}
+
+/// 语料中第 `target` 个向量加噪声得到的查询
+fn query(target: usize, seed: u64) -> Vec<f32> {
+    let mut state = seed;
+    let vector = embedding(target as u64).iter().map(|x| x + 0.02 * uniform(&mut state)).collect();
+    truncate_embedding(vector, FULL_DIMENSION)
+}
+
+fn document(i: usize, vector: Vec<f32>) -> DocumentRecord {
+    DocumentRecord {
+        id: format!("rust/tokio/1.0/doc-{}", i),
+        content: format!("tokio section {}", i),
+        title: format!("tokio section {}", i),
+        language: "rust".to_string(),
+        package_name: "tokio".to_string(),
+        version: "1.0".to_string(),
+        doc_type: "documentation".to_string(),
+        metadata: HashMap::new(),
+        embedding: vector,
+    }
+}
+
+/// 全维度下的精确 top-k（暴力计算内积，向量均已归一化）
+fn exact_top_k(corpus: &[Vec<f32>], query: &[f32]) -> HashSet<String> {
+    let mut scored: Vec<(f32, usize)> = corpus
+        .iter()
+        .enumerate()
+        .map(|(i, vector)| (vector.iter().zip(query).map(|(a, b)| a * b).sum(), i))
+        .collect();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    scored.into_iter().take(TOP_K).map(|(_, i)| format!("rust/tokio/1.0/doc-{}", i)).collect()
+}
+
+fn bench_matryoshka(c: &mut Criterion) {
+    let corpus: Vec<Vec<f32>> = (0..CORPUS_SIZE).map(|i| embedding(i as u64)).collect();
+    let queries: Vec<Vec<f32>> = (0..QUERIES).map(|q| query(q * 97 % CORPUS_SIZE, u64::MAX - q as u64)).collect();
+    let ground_truth: Vec<HashSet<String>> = queries.iter().map(|q| exact_top_k(&corpus, q)).collect();
+
+    let mut group = c.benchmark_group("matryoshka");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+
+    for &dimension in DIMENSIONS {
+        let dir = tempfile::TempDir::new().expect("创建临时目录失败");
+        let tool = VectorDocsTool::open_local(dir.path().to_path_buf()).expect("打开向量存储失败");
+        let documents = corpus.iter().enumerate().map(|(i, v)| document(i, truncate_embedding(v.clone(), dimension))).collect();
+        tool.add_documents(CacheTier::Global, documents).expect("写入语料失败");
+        let truncated: Vec<Vec<f32>> = queries.iter().map(|q| truncate_embedding(q.clone(), dimension)).collect();
+
+        let hits: usize = truncated
+            .iter()
+            .zip(&ground_truth)
+            .map(|(q, truth)| tool.search_similar(q, TOP_K).unwrap().iter().filter(|r| truth.contains(&r.id)).count())
+            .sum();
+        println!(
+            "matryoshka/{}: recall@{} = {:.3}，向量内存 {:.1} MB",
+            dimension,
+            TOP_K,
+            hits as f64 / (QUERIES * TOP_K) as f64,
+            (CORPUS_SIZE * dimension * 4) as f64 / (1024.0 * 1024.0)
+        );
+
+        group.bench_with_input(BenchmarkId::new("search_similar", dimension), &dimension, |b, _| {
+            let mut next = 0;
+            b.iter(|| {
+                next = (next + 1) % truncated.len();
+                black_box(tool.search_similar(&truncated[next], TOP_K).unwrap())
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_matryoshka);
+criterion_main!(benches);
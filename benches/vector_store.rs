@@ -0,0 +1,124 @@
+//! 向量存储的性能回归基准：写入、向量搜索、混合搜索、索引重建
+//!
+//! 语料规模为 1k / 10k / 100k 个片段，嵌入向量和内容按固定种子生成，结果可复现。
+//! 设置 `GRAPE_BENCH_MAX_DOCS` 可跳过更大的规模（例如本地快速验证时设为 10000）。
+//!
+//! 修改存储或索引实现前后的对比流程：
+//!
+//! ```text
+//! cargo bench --bench vector_store -- --save-baseline main     # 在改动前的代码上保存基线
+//! cargo bench --bench vector_store -- --baseline main          # 在改动后的代码上与基线对比
+//! python3 benches/check_regression.py --threshold 10           # 任一基准变慢超过 10% 时失败
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use grape_mcp_devtools::tools::cache_tiers::CacheTier;
+use grape_mcp_devtools::tools::vector_docs_tool::{DocumentRecord, VectorDocsTool};
+
+const CORPUS_SIZES: &[usize] = &[1_000, 10_000, 100_000];
+const DIMENSION: usize = 128;
+/// 写入基准每次迭代写入的片段数
+const ADD_BATCH: usize = 100;
+
+const PACKAGES: &[(&str, &str)] = &[
+    ("rust", "tokio"),
+    ("rust", "serde"),
+    ("python", "requests"),
+    ("javascript", "express"),
+    ("go", "gin"),
+];
+
+const SENTENCES: &[&str] = &[
+    "Spawns a new asynchronous task, returning a JoinHandle for it.",
+    "Serialize this value into the given serializer and return the output.",
+    "Sends a GET request to the given URL and returns the response body.",
+    "Creates an application router and registers middleware for every route.",
+    "The runtime schedules tasks cooperatively across worker threads.",
+    "Returns an error if the connection is closed before the handshake completes.",
+];
+
+/// splitmix64，生成可复现的伪随机数
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn embedding(seed: u64) -> Vec<f32> {
+    let mut state = seed;
+    (0..DIMENSION).map(|_| (next_random(&mut state) % 2000) as f32 / 1000.0 - 1.0).collect()
+}
+
+fn document(i: usize) -> DocumentRecord {
+    let (language, package) = PACKAGES[i % PACKAGES.len()];
+    let content: Vec<&str> = (0..6).map(|j| SENTENCES[(i + j * 7) % SENTENCES.len()]).collect();
+    DocumentRecord {
+        id: format!("{}/{}/1.0/doc-{}", language, package, i),
+        content: content.join(" "),
+        title: format!("{} section {}", package, i),
+        language: language.to_string(),
+        package_name: package.to_string(),
+        version: "1.0".to_string(),
+        doc_type: if i % 4 == 0 { "api_reference".to_string() } else { "documentation".to_string() },
+        metadata: HashMap::new(),
+        embedding: embedding(i as u64),
+    }
+}
+
+fn corpus_sizes() -> Vec<usize> {
+    let max_docs = std::env::var("GRAPE_BENCH_MAX_DOCS")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(usize::MAX);
+    CORPUS_SIZES.iter().copied().filter(|size| *size <= max_docs).collect()
+}
+
+/// 在临时目录中建立指定规模的语料（目录随返回值一起释放）
+fn build_corpus(size: usize) -> (tempfile::TempDir, VectorDocsTool) {
+    let dir = tempfile::TempDir::new().expect("创建临时目录失败");
+    let tool = VectorDocsTool::open_local(dir.path().to_path_buf()).expect("打开向量存储失败");
+    tool.add_documents(CacheTier::Global, (0..size).map(document).collect())
+        .expect("写入语料失败");
+    (dir, tool)
+}
+
+fn bench_vector_store(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vector_store");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(20));
+
+    for size in corpus_sizes() {
+        let (_dir, tool) = build_corpus(size);
+        let query = embedding(u64::MAX / 3);
+
+        group.bench_with_input(BenchmarkId::new("search_similar", size), &size, |b, _| {
+            b.iter(|| black_box(tool.search_similar(&query, 10).unwrap()))
+        });
+        group.bench_with_input(BenchmarkId::new("hybrid_search", size), &size, |b, _| {
+            b.iter(|| black_box(tool.hybrid_search(&query, "spawn a tokio task", 10).unwrap()))
+        });
+        group.bench_with_input(BenchmarkId::new("rebuild", size), &size, |b, _| {
+            b.iter(|| tool.rebuild_indexes().unwrap())
+        });
+
+        // 每次迭代写入一批新片段（含索引重建和落盘），语料随迭代缓慢增长
+        let next_id = AtomicUsize::new(size);
+        group.bench_with_input(BenchmarkId::new("add_batch", size), &size, |b, _| {
+            b.iter(|| {
+                let start = next_id.fetch_add(ADD_BATCH, Ordering::Relaxed);
+                tool.add_documents(CacheTier::Global, (start..start + ADD_BATCH).map(document).collect())
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_vector_store);
+criterion_main!(benches);
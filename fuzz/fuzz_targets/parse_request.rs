@@ -0,0 +1,18 @@
+//! stdio 循环的请求解析：任意输入都必须得到请求或可序列化的错误响应
+//!
+//! 运行：`cargo +nightly fuzz run parse_request`
+
+#![no_main]
+
+use grape_mcp_devtools::mcp::protocol::parse_request;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(line) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Err(response) = parse_request(line) {
+        assert!(response.error.is_some());
+        serde_json::to_string(&response).expect("错误响应必须能够序列化");
+    }
+});
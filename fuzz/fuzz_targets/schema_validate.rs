@@ -0,0 +1,19 @@
+//! 工具参数 Schema 校验：输入第一行是 Schema，其余部分是待校验的参数
+//!
+//! 运行：`cargo +nightly fuzz run schema_validate`
+
+#![no_main]
+
+use grape_mcp_devtools::tools::base::Schema;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (schema, params) = text.split_once('\n').unwrap_or((text, "null"));
+    let (Ok(schema), Ok(params)) = (serde_json::from_str::<Schema>(schema), serde_json::from_str::<serde_json::Value>(params)) else {
+        return;
+    };
+    let _ = schema.validate(&params);
+});
@@ -8,6 +8,7 @@ use regex;
 use url;
 
 use crate::ai::ai_service::{AIService, AIRequest, AIServiceConfig};
+use crate::tools::site_auth::SiteAuthenticated;
 use crate::ai::intelligent_web_analyzer::{CrawlTask, ContentType, PageRelevanceAnalysis, ExtractedLink};
 use crate::ai::smart_url_crawler::{CrawlerConfig, TaskResult as BasicTaskResult}; // Renaming to avoid conflict
 
@@ -459,7 +460,7 @@ impl ContentExtractionAgent {
 
         let mut attempts = 0;
         while attempts < self.config.max_retries {
-            match client.get(url).send().await {
+            match client.get(url).site_auth(url).send().await {
                 Ok(response) => {
                     if response.status().is_success() {
                         let content = response.text().await?;
@@ -1,9 +1,15 @@
 use anyhow::Result;
+use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::{json, Value};
 use std::env;
+use std::sync::Arc;
 use tracing::{info, warn, debug};
 use tokio::time::{timeout, Duration};
+use crate::mcp::sampling::{self, SamplingRequest};
+
+/// 采样请求未指定 max_tokens 时的上限
+const DEFAULT_SAMPLING_MAX_TOKENS: u32 = 1024;
 
 /// AI服务配置
 #[derive(Debug, Clone)]
@@ -84,11 +90,118 @@ pub struct AIResponse {
     pub from_cache: bool,
 }
 
+/// 对话补全后端（默认为 OpenAI 兼容的 HTTP 接口，测试中替换为预置回复）
+#[async_trait]
+pub trait ChatBackend: Send + Sync {
+    /// 发送一次补全请求，`model` 为已确定的模型名称
+    async fn complete(&self, request: &AIRequest, model: &str) -> Result<AIResponse>;
+}
+
+/// 调用 `{api_base}/chat/completions` 的后端
+pub struct HttpChatBackend {
+    client: Client,
+    api_base: String,
+    api_key: String,
+    timeout_secs: u64,
+}
+
+impl HttpChatBackend {
+    pub fn new(config: &AIServiceConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+        Ok(Self {
+            client,
+            api_base: config.api_base.clone(),
+            api_key: config.api_key.clone(),
+            timeout_secs: config.timeout_secs,
+        })
+    }
+}
+
+#[async_trait]
+impl ChatBackend for HttpChatBackend {
+    async fn complete(&self, request: &AIRequest, model: &str) -> Result<AIResponse> {
+        let mut messages = Vec::new();
+        
+        // 添加系统消息
+        if let Some(system_prompt) = &request.system_prompt {
+            messages.push(json!({
+                "role": "system",
+                "content": system_prompt
+            }));
+        }
+        
+        // 添加用户消息
+        messages.push(json!({
+            "role": "user",
+            "content": request.user_message
+        }));
+
+        let mut request_body = json!({
+            "model": model,
+            "messages": messages,
+            "stream": request.stream
+        });
+
+        // 添加可选参数
+        if let Some(temperature) = request.temperature {
+            request_body["temperature"] = json!(temperature);
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            request_body["max_tokens"] = json!(max_tokens);
+        }
+
+        let response = timeout(
+            Duration::from_secs(self.timeout_secs),
+            self.client
+                .post(&format!("{}/chat/completions", self.api_base))
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Content-Type", "application/json")
+                .json(&request_body)
+                .send()
+        ).await??;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("AI API调用失败: {} - {}", status, error_text));
+        }
+
+        let response_json: Value = response.json().await?;
+        
+        // 解析响应
+        let content = response_json
+            .get("choices")
+            .and_then(|choices| choices.as_array())
+            .and_then(|arr| arr.first())
+            .and_then(|choice| choice.get("message"))
+            .and_then(|message| message.get("content"))
+            .and_then(|content| content.as_str())
+            .ok_or_else(|| anyhow::anyhow!("无效的AI响应格式"))?
+            .to_string();
+
+        let tokens_used = response_json
+            .get("usage")
+            .and_then(|usage| usage.get("total_tokens"))
+            .and_then(|tokens| tokens.as_u64())
+            .map(|t| t as u32);
+
+        Ok(AIResponse {
+            content,
+            model: model.to_string(),
+            tokens_used,
+            response_time_ms: 0, // 将在上层设置
+            from_cache: false,
+        })
+    }
+}
+
 /// AI服务核心实现
 #[derive(Clone)]
 pub struct AIService {
     config: AIServiceConfig,
-    client: Client,
+    backend: Arc<dyn ChatBackend>,
     cache: std::sync::Arc<tokio::sync::RwLock<std::collections::HashMap<String, CachedResponse>>>,
 }
 
@@ -102,20 +215,22 @@ struct CachedResponse {
 impl AIService {
     /// 创建新的AI服务实例
     pub fn new(config: AIServiceConfig) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()?;
+        let backend = Arc::new(HttpChatBackend::new(&config)?);
+        Ok(Self::with_backend(config, backend))
+    }
 
+    /// 使用指定的补全后端创建AI服务
+    pub fn with_backend(config: AIServiceConfig, backend: Arc<dyn ChatBackend>) -> Self {
         info!("🤖 初始化AI服务");
         info!("API Base: {}", config.api_base);
         info!("默认模型: {}", config.default_model);
         info!("缓存启用: {}", config.enable_cache);
 
-        Ok(Self {
+        Self {
             config,
-            client,
+            backend,
             cache: std::sync::Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new())),
-        })
+        }
     }
 
     /// 从环境变量创建AI服务
@@ -137,8 +252,11 @@ impl AIService {
             }
         }
 
-        // 发送请求
-        let response = self.send_request_with_retry(&request).await?;
+        // 客户端支持 MCP 采样时优先借用宿主的 LLM，失败后再调用配置的 API
+        let response = match self.request_via_sampling(&request).await {
+            Some(response) => response,
+            None => self.send_request_with_retry(&request).await?,
+        };
         
         // 缓存响应
         if self.config.enable_cache {
@@ -156,6 +274,34 @@ impl AIService {
         })
     }
 
+    /// 通过当前 MCP 会话的采样客户端发送请求；会话不支持采样或客户端拒绝时返回 None
+    async fn request_via_sampling(&self, request: &AIRequest) -> Option<AIResponse> {
+        let client = sampling::current()?;
+        let sampling_request = SamplingRequest {
+            system_prompt: request.system_prompt.clone(),
+            user_message: request.user_message.clone(),
+            max_tokens: request.max_tokens.unwrap_or(DEFAULT_SAMPLING_MAX_TOKENS),
+            temperature: request.temperature,
+            model_hint: request.model.clone(),
+        };
+        match client.create_message(&sampling_request).await {
+            Ok(response) => {
+                debug!("🤝 通过客户端采样完成AI请求，模型: {}", response.model);
+                Some(AIResponse {
+                    content: response.content,
+                    model: response.model,
+                    tokens_used: None,
+                    response_time_ms: 0,
+                    from_cache: false,
+                })
+            }
+            Err(e) => {
+                warn!("⚠️ 客户端采样失败，改用配置的AI API: {}", e);
+                None
+            }
+        }
+    }
+
     /// 带重试的请求发送
     async fn send_request_with_retry(&self, request: &AIRequest) -> Result<AIResponse> {
         let mut last_error = None;
@@ -185,79 +331,7 @@ impl AIService {
     async fn send_single_request(&self, request: &AIRequest) -> Result<AIResponse> {
         let model = request.model.as_ref()
             .unwrap_or(&self.config.default_model);
-
-        let mut messages = Vec::new();
-        
-        // 添加系统消息
-        if let Some(system_prompt) = &request.system_prompt {
-            messages.push(json!({
-                "role": "system",
-                "content": system_prompt
-            }));
-        }
-        
-        // 添加用户消息
-        messages.push(json!({
-            "role": "user",
-            "content": request.user_message
-        }));
-
-        let mut request_body = json!({
-            "model": model,
-            "messages": messages,
-            "stream": request.stream
-        });
-
-        // 添加可选参数
-        if let Some(temperature) = request.temperature {
-            request_body["temperature"] = json!(temperature);
-        }
-        if let Some(max_tokens) = request.max_tokens {
-            request_body["max_tokens"] = json!(max_tokens);
-        }
-
-        let response = timeout(
-            Duration::from_secs(self.config.timeout_secs),
-            self.client
-                .post(&format!("{}/chat/completions", self.config.api_base))
-                .header("Authorization", format!("Bearer {}", self.config.api_key))
-                .header("Content-Type", "application/json")
-                .json(&request_body)
-                .send()
-        ).await??;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            return Err(anyhow::anyhow!("AI API调用失败: {} - {}", status, error_text));
-        }
-
-        let response_json: Value = response.json().await?;
-        
-        // 解析响应
-        let content = response_json
-            .get("choices")
-            .and_then(|choices| choices.as_array())
-            .and_then(|arr| arr.first())
-            .and_then(|choice| choice.get("message"))
-            .and_then(|message| message.get("content"))
-            .and_then(|content| content.as_str())
-            .ok_or_else(|| anyhow::anyhow!("无效的AI响应格式"))?
-            .to_string();
-
-        let tokens_used = response_json
-            .get("usage")
-            .and_then(|usage| usage.get("total_tokens"))
-            .and_then(|tokens| tokens.as_u64())
-            .map(|t| t as u32);
-
-        Ok(AIResponse {
-            content,
-            model: model.clone(),
-            tokens_used,
-            response_time_ms: 0, // 将在上层设置
-            from_cache: false,
-        })
+        self.backend.complete(request, model).await
     }
 
     /// 生成缓存键
@@ -354,4 +428,104 @@ impl AIService {
             }
         }
     }
-} 
\ No newline at end of file
+} 
+
+/// 测试替身
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// 按顺序返回预置回复的补全后端，并记录收到的用户消息
+    #[derive(Default)]
+    pub struct ScriptedChatBackend {
+        replies: Mutex<VecDeque<Result<String, String>>>,
+        prompts: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedChatBackend {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn reply(self, content: &str) -> Self {
+            self.replies.lock().unwrap().push_back(Ok(content.to_string()));
+            self
+        }
+
+        /// 下一次调用返回错误
+        pub fn fail(self, message: &str) -> Self {
+            self.replies.lock().unwrap().push_back(Err(message.to_string()));
+            self
+        }
+
+        pub fn prompts(&self) -> Vec<String> {
+            self.prompts.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl ChatBackend for ScriptedChatBackend {
+        async fn complete(&self, request: &AIRequest, model: &str) -> Result<AIResponse> {
+            self.prompts.lock().unwrap().push(request.user_message.clone());
+            let reply = self.replies.lock().unwrap().pop_front()
+                .unwrap_or_else(|| Err("没有预置的回复".to_string()));
+            let content = reply.map_err(|e| anyhow::anyhow!(e))?;
+            Ok(AIResponse {
+                content,
+                model: model.to_string(),
+                tokens_used: None,
+                response_time_ms: 0,
+                from_cache: false,
+            })
+        }
+    }
+
+    /// 不读取环境变量的测试配置（只尝试一次、关闭缓存）
+    pub fn test_config() -> AIServiceConfig {
+        AIServiceConfig {
+            api_base: "http://127.0.0.1:0".to_string(),
+            api_key: "test-key".to_string(),
+            default_model: "test-model".to_string(),
+            timeout_secs: 5,
+            max_retries: 1,
+            enable_cache: false,
+            cache_ttl_secs: 60,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{test_config, ScriptedChatBackend};
+    use super::*;
+
+    fn ask(message: &str) -> AIRequest {
+        AIRequest {
+            model: None,
+            system_prompt: None,
+            user_message: message.to_string(),
+            temperature: None,
+            max_tokens: None,
+            stream: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_backend_and_cache() {
+        let backend = Arc::new(ScriptedChatBackend::new().reply("tokio 是异步运行时").fail("503"));
+        let service = AIService::with_backend(AIServiceConfig { enable_cache: true, ..test_config() }, backend.clone());
+
+        let first = service.request(ask("什么是 tokio")).await.unwrap();
+        assert_eq!(first.content, "tokio 是异步运行时");
+        assert_eq!(first.model, "test-model");
+
+        // 第二次命中缓存，不会消耗预置的失败回复
+        let cached = service.request(ask("什么是 tokio")).await.unwrap();
+        assert!(cached.from_cache);
+        assert_eq!(backend.prompts().len(), 1);
+
+        assert!(service.request(ask("另一个问题")).await.is_err());
+    }
+}
@@ -6,6 +6,7 @@ use regex;
 
 use super::ai_service::{AIService, AIRequest};
 use super::prompt_templates::DocumentPrompts;
+use crate::tools::content_language;
 
 /// AI增强的文档处理器
 #[derive(Clone)]
@@ -279,6 +280,13 @@ impl DocumentAI {
     pub async fn translate_content(&self, content: &str, target_language: &str) -> Result<String> {
         info!("🌐 开始内容翻译");
 
+        // 内容已是目标语言时无需翻译
+        let source = content_language::detect_natural_language(content);
+        if source.is_some() && source == content_language::normalize_natural_language(target_language) {
+            debug!("内容已是目标语言 {}，跳过翻译", target_language);
+            return Ok(content.to_string());
+        }
+
         let system_prompt = self.prompts.get_translation_system_prompt();
         let user_message = self.prompts.get_translation_user_prompt(content, target_language);
 
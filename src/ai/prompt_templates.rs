@@ -437,4 +437,51 @@ URLs: {:?}
 
 请提供URL建议。"#, query, target_language, preferences)
     }
-} 
\ No newline at end of file
+} 
+/// 开发工具提示词模板（通过 MCP prompts 暴露给客户端）
+#[derive(Clone)]
+pub struct DevToolPrompts;
+
+impl DevToolPrompts {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 获取包讲解系统提示词
+    pub fn get_explain_package_system_prompt(&self) -> String {
+        r#"你是一个熟悉各语言生态的资深开发者，擅长向其他开发者讲解第三方库。
+
+讲解要求：
+1. 说明库解决的问题和典型使用场景
+2. 介绍核心类型、模块和入口API
+3. 给出最小可运行示例
+4. 指出常见陷阱、可选特性和替代方案
+5. 优先使用 search_docs、check_latest_version 等工具获取准确信息，不要臆测API"#.to_string()
+    }
+
+    /// 获取包讲解用户提示词
+    pub fn get_explain_package_user_prompt(&self, package: &str, language: &str, version: Option<&str>) -> String {
+        format!(r#"请讲解{}生态中的包 {}（版本：{}）。
+
+请先查询它的文档和最新版本，再按要求组织讲解。"#, language, package, version.unwrap_or("最新稳定版"))
+    }
+
+    /// 获取版本比较系统提示词
+    pub fn get_compare_versions_system_prompt(&self) -> String {
+        r#"你是一个依赖升级顾问，负责评估同一个包两个版本之间的差异。
+
+比较要求：
+1. 列出破坏性变更及迁移方法
+2. 列出新增功能和弃用项
+3. 评估升级风险（低/中/高）并说明理由
+4. 给出升级步骤建议
+5. 基于 changelog、发布说明和文档，无法确认的内容明确标注"#.to_string()
+    }
+
+    /// 获取版本比较用户提示词
+    pub fn get_compare_versions_user_prompt(&self, package: &str, language: &str, from_version: &str, to_version: &str) -> String {
+        format!(r#"请比较{}包 {} 从 {} 升级到 {} 的差异。
+
+请先查询两个版本的文档和发布信息，再给出比较结果。"#, language, package, from_version, to_version)
+    }
+}
@@ -6,6 +6,7 @@ use chrono::{DateTime, Utc, Duration};
 use tokio::time::sleep;
 use std::sync::Arc;
 
+use crate::tools::site_auth::SiteAuthenticated;
 use super::ai_service::AIService;
 use super::intelligent_web_analyzer::{
     IntelligentWebAnalyzer, CrawlTask, PageRelevanceAnalysis, 
@@ -147,9 +148,11 @@ impl SmartUrlCrawler {
     pub async fn new(ai_service: AIService, config: CrawlerConfig) -> Result<Self> {
         let web_analyzer = IntelligentWebAnalyzer::new(ai_service).await?;
         
+        // 保留站点登录后下发的会话 Cookie
         let http_client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(config.timeout_secs))
             .user_agent(config.user_agent.clone())
+            .cookie_store(true)
             .build()?;
 
         let crawl_state = Arc::new(tokio::sync::RwLock::new(CrawlState {
@@ -339,7 +342,7 @@ impl SmartUrlCrawler {
 
         let mut attempts = 0;
         while attempts < config.max_retries {
-            match self.http_client.get(url).send().await {
+            match self.http_client.get(url).site_auth(url).send().await {
                 Ok(response) => {
                     if response.status().is_success() {
                         let content = response.text().await?;
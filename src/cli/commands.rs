@@ -0,0 +1,460 @@
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::cli::soak::{self, SoakArgs};
+use crate::config::HttpTransportConfig;
+use crate::mcp::ServerTransport;
+use crate::tools::cache_sync::{self, SyncRemote};
+use crate::tools::cache_tiers::CacheTier;
+use crate::tools::corpus_io::CorpusFormat;
+use crate::tools::doc_packs::{self, DocPackTrust};
+use crate::tools::provenance;
+use crate::tools::{EnhancedDocumentProcessor, MCPTool, VectorDocsTool};
+
+/// Grape MCP DevTools 命令行
+///
+/// 不带子命令时以 MCP 服务器模式运行；子命令用于在没有 MCP 客户端的情况下管理向量缓存。
+#[derive(Debug, Parser)]
+#[command(name = "grape-mcp-devtools", version, about = "多语言文档 MCP 服务器")]
+pub struct Cli {
+    /// 以交互式 REPL 模式运行，从 stdin 读取查询并打印结果
+    #[arg(long)]
+    pub repl: bool,
+
+    /// 守护进程模式：空闲一段时间后释放向量索引和缓存，下次请求时自动重新加载
+    #[arg(long)]
+    pub daemon: bool,
+
+    /// 守护进程模式下的空闲休眠时间（秒）
+    #[arg(long, default_value_t = 600)]
+    pub idle_timeout: u64,
+
+    /// 收到 SIGINT / SIGTERM 后等待进行中的请求和后台缓存任务结束的最长时间（秒）
+    #[arg(long, default_value_t = 30)]
+    pub shutdown_timeout: u64,
+
+    /// 传输方式：stdio（默认）、http（Streamable HTTP）或 ws（WebSocket），监听地址和TLS见 config/system_config.toml
+    #[arg(long, value_enum, default_value_t = TransportKind::Stdio)]
+    pub transport: TransportKind,
+
+    /// HTTP/WebSocket 传输的监听地址，覆盖配置文件中的 bind_address
+    #[arg(long)]
+    pub bind: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// 服务器传输方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TransportKind {
+    Stdio,
+    Http,
+    Ws,
+}
+
+impl Cli {
+    /// 根据命令行参数确定服务器传输
+    pub fn server_transport(&self) -> ServerTransport {
+        match self.transport {
+            TransportKind::Stdio => ServerTransport::Stdio,
+            TransportKind::Http => ServerTransport::Http(self.network_config()),
+            TransportKind::Ws => ServerTransport::WebSocket(self.network_config()),
+        }
+    }
+
+    fn network_config(&self) -> HttpTransportConfig {
+        let mut config = HttpTransportConfig::load();
+        if let Some(bind) = &self.bind {
+            config.bind_address = bind.clone();
+        }
+        config
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 抓取并索引某个包的文档
+    Index {
+        /// 包名
+        package: String,
+        /// 编程语言
+        #[arg(short, long, default_value = "rust")]
+        language: String,
+        /// 包版本，默认 latest
+        #[arg(short, long)]
+        version: Option<String>,
+    },
+    /// 在向量缓存中搜索
+    Search {
+        query: String,
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+        /// 搜索层级: global 或 workspace，默认合并所有层级
+        #[arg(long)]
+        scope: Option<String>,
+    },
+    /// 缓存管理
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// 将某个包版本的缓存文档导出为文档包
+    Export {
+        #[arg(short, long)]
+        language: String,
+        #[arg(short, long)]
+        package: String,
+        #[arg(short, long)]
+        version: String,
+        /// 输出文件路径
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// 导入文档包（本地路径或URL）
+    Import {
+        source: String,
+        /// 把 source 视为 DevDocs 文档集（slug 如 python~3.12，或含 index.json/db.json 的目录）
+        #[arg(long)]
+        devdocs: bool,
+    },
+    /// 通过共享位置（目录、WebDAV 或 S3）与团队同步向量缓存，只传输有变化的包版本
+    Sync {
+        #[command(subcommand)]
+        action: SyncCommand,
+    },
+    /// 向量库快照：创建或恢复包含所有层级和集合的单个快照文件
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotCommand,
+    },
+    /// 文档语料：把文档和嵌入向量导出为 JSONL/Parquet 文件，或从中导入
+    Corpus {
+        #[command(subcommand)]
+        action: CorpusCommand,
+    },
+    /// 长时间浸泡测试：持续写入、搜索、清除，检测内存泄漏和延迟漂移
+    #[command(hide = true)]
+    Soak(SoakArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// 显示缓存统计信息
+    Stats,
+    /// 清除缓存中的包版本
+    Purge {
+        #[arg(short, long)]
+        language: Option<String>,
+        #[arg(short, long)]
+        package: Option<String>,
+        #[arg(short, long)]
+        version: Option<String>,
+        /// 只清除指定层级: global 或 workspace
+        #[arg(long)]
+        scope: Option<String>,
+        /// 未指定过滤条件时必须显式确认清除全部缓存
+        #[arg(long)]
+        all: bool,
+    },
+    /// 清除来自指定来源地址的文档（响应内容删除请求），地址以 * 结尾时按前缀匹配
+    PurgeSource {
+        source_url: String,
+        /// 只清除指定层级: global 或 workspace，默认清除所有层级和集合
+        #[arg(long)]
+        scope: Option<String>,
+    },
+    /// 用当前配置的嵌入模型重新生成所有层级和集合的向量（更换嵌入模型后运行）
+    ReembedAll,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SyncCommand {
+    /// 上传本地有变化的包版本
+    Push {
+        /// 共享位置: 本地路径、file://、http(s)://（WebDAV）或 s3://桶/前缀
+        remote: String,
+    },
+    /// 下载远端有变化的包版本
+    Pull {
+        /// 共享位置: 本地路径、file://、http(s)://（WebDAV）或 s3://桶/前缀
+        remote: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SnapshotCommand {
+    /// 把文档、向量和已处理包版本标记写入快照文件
+    Create {
+        path: PathBuf,
+    },
+    /// 从快照文件恢复，替换快照中各层级和集合的现有内容
+    Restore {
+        path: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CorpusCommand {
+    /// 导出满足过滤条件的文档（含全文和嵌入向量）
+    Export {
+        /// 输出文件路径，格式按扩展名推断（.jsonl 或 .parquet）
+        output: PathBuf,
+        /// 文件格式: jsonl 或 parquet，覆盖按扩展名推断的结果
+        #[arg(long)]
+        format: Option<String>,
+        #[arg(short, long)]
+        language: Option<String>,
+        #[arg(short, long)]
+        package: Option<String>,
+        #[arg(short, long)]
+        version: Option<String>,
+        /// 只导出指定层级: global 或 workspace
+        #[arg(long)]
+        scope: Option<String>,
+    },
+    /// 导入语料文件中的文档，已存在的ID跳过
+    Import {
+        path: PathBuf,
+        /// 文件格式: jsonl 或 parquet，覆盖按扩展名推断的结果
+        #[arg(long)]
+        format: Option<String>,
+        /// 写入指定层级: global 或 workspace，默认按包名推断
+        #[arg(long)]
+        scope: Option<String>,
+    },
+}
+
+/// 语料文件格式：显式指定的优先，否则按扩展名推断
+fn corpus_format(path: &std::path::Path, format: Option<&str>) -> Result<CorpusFormat> {
+    match format {
+        Some(format) => CorpusFormat::parse(format).ok_or_else(|| anyhow!("无效的语料格式: {} (可选 jsonl/parquet)", format)),
+        None => CorpusFormat::from_path(path)
+            .ok_or_else(|| anyhow!("无法从 {} 推断语料格式，请用 --format 指定 jsonl 或 parquet", path.display())),
+    }
+}
+
+fn parse_scope(scope: Option<&str>) -> Result<Option<CacheTier>> {
+    scope
+        .map(|s| CacheTier::parse(s).ok_or_else(|| anyhow!("无效的缓存层级: {} (可选 global/workspace)", s)))
+        .transpose()
+}
+
+fn print_json(value: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// 执行命令行子命令
+pub async fn run_command(command: Command) -> Result<()> {
+    // 浸泡测试使用独立的数据目录和离线嵌入，不打开用户缓存
+    if let Command::Soak(args) = command {
+        return soak::run(args).await;
+    }
+    let vector_tool = Arc::new(VectorDocsTool::new()?);
+
+    match command {
+        Command::Index { package, language, version } => {
+            let processor = EnhancedDocumentProcessor::new(Arc::clone(&vector_tool)).await?;
+            let results = processor
+                .process_documentation_request_enhanced(&language, &package, version.as_deref(), &package)
+                .await?;
+            println!("✅ 已索引 {}/{}@{}: {} 个相关文档片段",
+                     language, package, version.as_deref().unwrap_or("latest"), results.len());
+        }
+        Command::Search { query, limit, scope } => {
+            let mut params = json!({ "action": "search", "query": query, "limit": limit.to_string() });
+            if let Some(tier) = parse_scope(scope.as_deref())? {
+                params["scope"] = json!(tier.as_str());
+            }
+            let result = vector_tool.execute(params).await?;
+            let hits = result["results"].as_array().cloned().unwrap_or_default();
+            if hits.is_empty() {
+                println!("未找到相关文档");
+            }
+            for (index, hit) in hits.iter().enumerate() {
+                println!("{}. {} [{}/{}@{}] 得分 {:.4}",
+                         index + 1,
+                         hit["title"].as_str().unwrap_or(""),
+                         hit["language"].as_str().unwrap_or(""),
+                         hit["package_name"].as_str().unwrap_or(""),
+                         hit["version"].as_str().unwrap_or(""),
+                         hit["score"].as_f64().unwrap_or(0.0));
+                if let Some(source_url) = hit["metadata"][provenance::SOURCE_URL_METADATA_KEY].as_str() {
+                    match hit["metadata"][provenance::LICENSE_METADATA_KEY].as_str() {
+                        Some(license) => println!("   来源: {} ({})", source_url, license),
+                        None => println!("   来源: {}", source_url),
+                    }
+                }
+                let preview: String = hit["content"].as_str().unwrap_or("").chars().take(200).collect();
+                println!("   {}\n", preview.replace('\n', " "));
+            }
+        }
+        Command::Cache { action: CacheCommand::Stats } => {
+            print_json(&vector_tool.get_system_status())?;
+        }
+        Command::Cache { action: CacheCommand::Purge { language, package, version, scope, all } } => {
+            if language.is_none() && package.is_none() && version.is_none() && !all {
+                return Err(anyhow!("未指定过滤条件，如需清除全部缓存请加 --all"));
+            }
+            let tier = parse_scope(scope.as_deref())?;
+            let (package_versions, documents, bytes) = vector_tool.purge_packages(
+                language.as_deref(), package.as_deref(), version.as_deref(), tier,
+            )?;
+            println!("🗑️ 已清除 {} 个包版本，{} 个文档，释放 {} 字节", package_versions, documents, bytes);
+        }
+        Command::Cache { action: CacheCommand::PurgeSource { source_url, scope } } => {
+            let tier = parse_scope(scope.as_deref())?;
+            let (documents, bytes) = vector_tool.purge_source(&source_url, tier, false)?;
+            println!("🗑️ 已清除来源 {} 的 {} 个文档，释放 {} 字节", source_url, documents, bytes);
+        }
+        Command::Cache { action: CacheCommand::ReembedAll } => {
+            let report = vector_tool.reembed_all().await?;
+            print_json(&serde_json::to_value(report)?)?;
+        }
+        Command::Export { language, package, version, output } => {
+            let signing_key = doc_packs::signing_key_from_env()?;
+            let pack = vector_tool.export_doc_pack(&language, &package, &version, signing_key.as_ref())?;
+            if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&output, pack)?;
+            println!("📦 已导出 {}/{}@{} 到 {}", language, package, version, output.display());
+        }
+        Command::Import { source, devdocs: false } => {
+            let report = vector_tool.import_doc_pack(&source).await?;
+            print_json(&serde_json::to_value(report)?)?;
+        }
+        Command::Import { source, devdocs: true } => {
+            let report = vector_tool.import_devdocs(&source).await?;
+            print_json(&serde_json::to_value(report)?)?;
+        }
+        Command::Sync { action: SyncCommand::Push { remote } } => {
+            let signing_key = doc_packs::signing_key_from_env()?;
+            let report = cache_sync::push(&vector_tool, &SyncRemote::parse(&remote)?, &remote, signing_key.as_ref()).await?;
+            print_json(&serde_json::to_value(report)?)?;
+        }
+        Command::Sync { action: SyncCommand::Pull { remote } } => {
+            let report = cache_sync::pull(&vector_tool, &SyncRemote::parse(&remote)?, &remote, &DocPackTrust::from_env()?).await?;
+            print_json(&serde_json::to_value(report)?)?;
+        }
+        Command::Snapshot { action: SnapshotCommand::Create { path } } => {
+            let report = vector_tool.create_snapshot(&path)?;
+            print_json(&serde_json::to_value(report)?)?;
+        }
+        Command::Snapshot { action: SnapshotCommand::Restore { path } } => {
+            let report = vector_tool.restore_snapshot(&path)?;
+            print_json(&serde_json::to_value(report)?)?;
+        }
+        Command::Corpus { action: CorpusCommand::Export { output, format, language, package, version, scope } } => {
+            let format = corpus_format(&output, format.as_deref())?;
+            let tier = parse_scope(scope.as_deref())?;
+            let report = vector_tool.export_corpus(
+                &output, format, language.as_deref(), package.as_deref(), version.as_deref(), tier,
+            )?;
+            print_json(&serde_json::to_value(report)?)?;
+        }
+        Command::Corpus { action: CorpusCommand::Import { path, format, scope } } => {
+            let format = corpus_format(&path, format.as_deref())?;
+            let tier = parse_scope(scope.as_deref())?;
+            let report = vector_tool.import_corpus(&path, format, tier)?;
+            print_json(&serde_json::to_value(report)?)?;
+        }
+        Command::Soak(_) => unreachable!("soak 子命令已在前面处理"),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cache_purge_command() {
+        let cli = Cli::try_parse_from([
+            "grape-mcp-devtools", "cache", "purge", "--language", "rust", "--package", "serde",
+        ]).unwrap();
+        match cli.command {
+            Some(Command::Cache { action: CacheCommand::Purge { language, package, version, all, .. } }) => {
+                assert_eq!(language.as_deref(), Some("rust"));
+                assert_eq!(package.as_deref(), Some("serde"));
+                assert!(version.is_none());
+                assert!(!all);
+            }
+            other => panic!("解析结果不符合预期: {:?}", other),
+        }
+
+        let cli = Cli::try_parse_from([
+            "grape-mcp-devtools", "cache", "purge-source", "https://docs.example.com/*",
+        ]).unwrap();
+        match cli.command {
+            Some(Command::Cache { action: CacheCommand::PurgeSource { source_url, scope } }) => {
+                assert_eq!(source_url, "https://docs.example.com/*");
+                assert!(scope.is_none());
+            }
+            other => panic!("解析结果不符合预期: {:?}", other),
+        }
+
+        let cli = Cli::try_parse_from(["grape-mcp-devtools", "cache", "reembed-all"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Cache { action: CacheCommand::ReembedAll })));
+
+        let cli = Cli::try_parse_from(["grape-mcp-devtools", "sync", "pull", "s3://team-cache/grape"]).unwrap();
+        match cli.command {
+            Some(Command::Sync { action: SyncCommand::Pull { remote } }) => assert_eq!(remote, "s3://team-cache/grape"),
+            other => panic!("解析结果不符合预期: {:?}", other),
+        }
+
+        let cli = Cli::try_parse_from(["grape-mcp-devtools", "snapshot", "restore", "ci/docs.snapshot.gz"]).unwrap();
+        match cli.command {
+            Some(Command::Snapshot { action: SnapshotCommand::Restore { path } }) => assert_eq!(path, PathBuf::from("ci/docs.snapshot.gz")),
+            other => panic!("解析结果不符合预期: {:?}", other),
+        }
+
+        let cli = Cli::try_parse_from(["grape-mcp-devtools"]).unwrap();
+        assert!(cli.command.is_none());
+        assert!(!cli.repl);
+
+        let cli = Cli::try_parse_from(["grape-mcp-devtools", "--repl"]).unwrap();
+        assert!(cli.repl);
+
+        let cli = Cli::try_parse_from(["grape-mcp-devtools", "--daemon", "--idle-timeout", "120"]).unwrap();
+        assert!(cli.daemon);
+        assert_eq!(cli.idle_timeout, 120);
+        assert_eq!(cli.shutdown_timeout, 30);
+
+        let cli = Cli::try_parse_from(["grape-mcp-devtools", "soak", "--cycles", "5", "--max-rss-growth-mb", "64"]).unwrap();
+        match cli.command {
+            Some(Command::Soak(args)) => {
+                assert_eq!(args.cycles, Some(5));
+                assert_eq!(args.max_rss_growth_mb, 64);
+                assert_eq!(args.duration_secs, 3600);
+            }
+            other => panic!("解析结果不符合预期: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_corpus_command() {
+        let cli = Cli::try_parse_from(["grape-mcp-devtools", "corpus", "export", "out/docs.parquet", "--package", "serde"]).unwrap();
+        match cli.command {
+            Some(Command::Corpus { action: CorpusCommand::Export { output, format, package, .. } }) => {
+                assert_eq!(corpus_format(&output, format.as_deref()).unwrap(), CorpusFormat::Parquet);
+                assert_eq!(package.as_deref(), Some("serde"));
+            }
+            other => panic!("解析结果不符合预期: {:?}", other),
+        }
+        assert_eq!(corpus_format(&PathBuf::from("docs.txt"), Some("jsonl")).unwrap(), CorpusFormat::Jsonl);
+        assert!(corpus_format(&PathBuf::from("docs.txt"), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_scope() {
+        assert_eq!(parse_scope(Some("workspace")).unwrap(), Some(CacheTier::Workspace));
+        assert_eq!(parse_scope(None).unwrap(), None);
+        assert!(parse_scope(Some("bogus")).is_err());
+    }
+}
@@ -1,6 +1,9 @@
 pub mod detector;
 pub mod registry;
 pub mod tool_installer;
+pub mod commands;
+pub mod repl;
+pub mod soak;
 
 pub use detector::{CliDetector, CliToolInfo};
 pub use registry::{DynamicToolRegistry, RegistrationStrategy, RegistrationReport};
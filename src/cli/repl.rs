@@ -0,0 +1,197 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::mcp::builder::GrapeServerBuilder;
+use crate::mcp::server::MCPServer;
+use crate::tools::VectorDocsTool;
+
+const REPL_HELP: &str = "\
+可用命令:
+  search <语言> <查询>       搜索文档 (search_docs)
+  version <包类型> <包名>    检查最新版本 (check_latest_version)，包类型如 cargo/npm/pip/maven/go/pub
+  docs <查询>                搜索本地向量缓存 (vector_docs)
+  tools                      列出已注册的工具
+  call <工具名> <JSON参数>   直接调用任意工具
+  help                       显示帮助
+  quit / exit                退出";
+
+/// REPL 中的一条命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplCommand {
+    Search { language: String, query: String },
+    Version { package_type: String, name: String },
+    Docs { query: String },
+    Tools,
+    Call { tool: String, params: Value },
+    Help,
+    Quit,
+    Empty,
+}
+
+/// 解析一行输入
+pub fn parse_repl_line(line: &str) -> Result<ReplCommand> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(ReplCommand::Empty);
+    }
+    let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+
+    match command.to_lowercase().as_str() {
+        "search" | "s" => {
+            let (language, query) = rest.split_once(char::is_whitespace)
+                .ok_or_else(|| anyhow!("用法: search <语言> <查询>"))?;
+            Ok(ReplCommand::Search { language: language.to_string(), query: query.trim().to_string() })
+        }
+        "version" | "v" => {
+            let mut parts = rest.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some(package_type), Some(name)) => Ok(ReplCommand::Version {
+                    package_type: package_type.to_string(),
+                    name: name.to_string(),
+                }),
+                _ => Err(anyhow!("用法: version <包类型> <包名>")),
+            }
+        }
+        "docs" | "d" if !rest.is_empty() => Ok(ReplCommand::Docs { query: rest.to_string() }),
+        "docs" | "d" => Err(anyhow!("用法: docs <查询>")),
+        "tools" => Ok(ReplCommand::Tools),
+        "call" => {
+            let (tool, params) = rest.split_once(char::is_whitespace).unwrap_or((rest, "{}"));
+            if tool.is_empty() {
+                return Err(anyhow!("用法: call <工具名> <JSON参数>"));
+            }
+            let params = serde_json::from_str(params.trim())
+                .map_err(|e| anyhow!("JSON参数解析失败: {}", e))?;
+            Ok(ReplCommand::Call { tool: tool.to_string(), params })
+        }
+        "help" | "h" | "?" => Ok(ReplCommand::Help),
+        "quit" | "exit" | "q" => Ok(ReplCommand::Quit),
+        other => Err(anyhow!("未知命令: {} (输入 help 查看帮助)", other)),
+    }
+}
+
+/// 格式化搜索结果
+fn render_search_results(result: &Value) -> String {
+    let hits = result["results"].as_array().cloned().unwrap_or_default();
+    if hits.is_empty() {
+        return "未找到相关文档".to_string();
+    }
+    let mut output = String::new();
+    for (index, hit) in hits.iter().enumerate() {
+        let score = hit["relevance"].as_f64().or_else(|| hit["score"].as_f64()).unwrap_or(0.0);
+        output.push_str(&format!("{}. {} (得分 {:.3})\n", index + 1, hit["title"].as_str().unwrap_or("无标题"), score));
+        if let Some(url) = hit["url"].as_str() {
+            output.push_str(&format!("   🔗 {}\n", url));
+        }
+        let preview: String = hit["content"].as_str().unwrap_or("").chars().take(240).collect();
+        output.push_str(&format!("   {}\n", preview.replace('\n', " ")));
+    }
+    output
+}
+
+async fn execute_repl_command(server: &MCPServer, command: ReplCommand) -> Result<Option<String>> {
+    let output = match command {
+        ReplCommand::Empty => return Ok(Some(String::new())),
+        ReplCommand::Quit => return Ok(None),
+        ReplCommand::Help => REPL_HELP.to_string(),
+        ReplCommand::Tools => server.list_tools().await?
+            .iter()
+            .map(|tool| format!("🔧 {} - {}", tool.name, tool.description))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ReplCommand::Search { language, query } => {
+            let result = server.execute_tool("search_docs", json!({ "query": query, "language": language })).await?;
+            render_search_results(&result)
+        }
+        ReplCommand::Docs { query } => {
+            let result = server.execute_tool("vector_docs", json!({ "action": "search", "query": query })).await?;
+            render_search_results(&result)
+        }
+        ReplCommand::Version { package_type, name } => {
+            let result = server.execute_tool("check_latest_version", json!({ "type": package_type, "name": name })).await?;
+            serde_json::to_string_pretty(&result)?
+        }
+        ReplCommand::Call { tool, params } => {
+            serde_json::to_string_pretty(&server.execute_tool(&tool, params).await?)?
+        }
+    };
+    Ok(Some(output))
+}
+
+/// 运行交互式 REPL，从 stdin 读取命令直到 EOF 或 quit
+pub async fn run_repl() -> Result<()> {
+    // REPL 需要直接查询本地向量缓存，因此同时注册 vector_docs 工具
+    let vector_tool = Arc::new(VectorDocsTool::new()?);
+    let grape_server = GrapeServerBuilder::new()
+        .with_dynamic_registration(false)
+        .with_background_caching(None)
+        .with_upgrade_check(false)
+        .with_vector_tool(Arc::clone(&vector_tool))
+        .add_tool(vector_tool)
+        .build()
+        .await?;
+    let server = grape_server.mcp_server();
+
+    let mut stdout = tokio::io::stdout();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    stdout.write_all(format!("Grape MCP DevTools REPL\n{}\n", REPL_HELP).as_bytes()).await?;
+
+    loop {
+        stdout.write_all(b"grape> ").await?;
+        stdout.flush().await?;
+
+        let line = match lines.next_line().await? {
+            Some(line) => line,
+            None => break,
+        };
+
+        let output = match parse_repl_line(&line) {
+            Ok(command) => match execute_repl_command(server, command).await {
+                Ok(Some(output)) => output,
+                Ok(None) => break,
+                Err(e) => format!("❌ {}", e),
+            },
+            Err(e) => format!("❌ {}", e),
+        };
+        if !output.is_empty() {
+            stdout.write_all(format!("{}\n", output).as_bytes()).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repl_commands() {
+        assert_eq!(
+            parse_repl_line("search rust async runtime").unwrap(),
+            ReplCommand::Search { language: "rust".to_string(), query: "async runtime".to_string() }
+        );
+        assert_eq!(
+            parse_repl_line("version cargo tokio").unwrap(),
+            ReplCommand::Version { package_type: "cargo".to_string(), name: "tokio".to_string() }
+        );
+        assert_eq!(parse_repl_line("  ").unwrap(), ReplCommand::Empty);
+        assert_eq!(parse_repl_line("exit").unwrap(), ReplCommand::Quit);
+        assert!(parse_repl_line("version cargo").is_err());
+        assert!(parse_repl_line("unknown").is_err());
+    }
+
+    #[test]
+    fn test_parse_call_with_json() {
+        match parse_repl_line(r#"call search_docs {"query": "serde", "language": "rust"}"#).unwrap() {
+            ReplCommand::Call { tool, params } => {
+                assert_eq!(tool, "search_docs");
+                assert_eq!(params["query"], "serde");
+            }
+            other => panic!("解析结果不符合预期: {:?}", other),
+        }
+        assert!(parse_repl_line("call search_docs {invalid").is_err());
+    }
+}
@@ -0,0 +1,366 @@
+//! 长时间浸泡测试（隐藏子命令 `soak`）
+//!
+//! 在临时目录中用离线哈希嵌入持续执行“写入一批片段 → 若干次搜索 → 清除最旧的包版本”的循环，
+//! 模拟连续多天的后台缓存。文档总量保持稳定，因此常驻内存、索引文件大小或搜索延迟的持续
+//! 增长都说明存在泄漏或退化。每隔一段时间记录一个采样点，结束时把最后的采样与预热后的
+//! 第一个采样比较，超出阈值时报告并以非零状态退出。
+//!
+//! ```text
+//! grape-mcp-devtools soak --duration-secs 14400 --report soak.json
+//! ```
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use serde::Serialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::tools::base::MCPTool;
+use crate::tools::embedder::HashEmbeddingProvider;
+use crate::tools::VectorDocsTool;
+
+/// 浸泡测试使用的嵌入维度
+const SOAK_DIMENSION: usize = 256;
+
+/// 同时保留的包版本数，超过后清除最旧的版本
+const RETAINED_VERSIONS: usize = 8;
+
+const PACKAGES: &[(&str, &str)] = &[
+    ("rust", "tokio"),
+    ("rust", "serde"),
+    ("python", "requests"),
+    ("javascript", "express"),
+    ("go", "gin"),
+];
+
+const SENTENCES: &[&str] = &[
+    "Spawns a new asynchronous task and returns a handle that can be awaited.",
+    "Serialize this value into the given serializer and return the output.",
+    "Sends a request to the given URL and returns the decoded response body.",
+    "Creates a router and registers middleware that runs before every handler.",
+    "The runtime schedules tasks cooperatively across a pool of worker threads.",
+    "Returns an error if the connection closes before the handshake completes.",
+    "Buffers are flushed automatically when the writer is dropped.",
+];
+
+/// `soak` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct SoakArgs {
+    /// 运行时长（秒）
+    #[arg(long, default_value_t = 3600)]
+    pub duration_secs: u64,
+    /// 达到指定循环数后提前结束（用于快速冒烟）
+    #[arg(long)]
+    pub cycles: Option<u64>,
+    /// 每个循环写入的片段数
+    #[arg(long, default_value_t = 50)]
+    pub docs_per_cycle: usize,
+    /// 每个循环执行的搜索次数
+    #[arg(long, default_value_t = 20)]
+    pub searches_per_cycle: usize,
+    /// 采样间隔（秒）
+    #[arg(long, default_value_t = 60)]
+    pub sample_interval_secs: u64,
+    /// 数据目录，默认使用临时目录（结束后删除）
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+    /// 把采样和结论写入 JSON 报告
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+    /// 搜索 p95 延迟相对基线的最大倍数
+    #[arg(long, default_value_t = 1.5)]
+    pub max_latency_drift: f64,
+    /// 常驻内存相对基线的最大增长（MB）
+    #[arg(long, default_value_t = 256)]
+    pub max_rss_growth_mb: u64,
+    /// 平均每个文档占用的索引字节数相对基线的最大倍数
+    #[arg(long, default_value_t = 1.5)]
+    pub max_index_drift: f64,
+}
+
+impl Default for SoakArgs {
+    fn default() -> Self {
+        Self {
+            duration_secs: 3600,
+            cycles: None,
+            docs_per_cycle: 50,
+            searches_per_cycle: 20,
+            sample_interval_secs: 60,
+            data_dir: None,
+            report: None,
+            max_latency_drift: 1.5,
+            max_rss_growth_mb: 256,
+            max_index_drift: 1.5,
+        }
+    }
+}
+
+/// 一个采样点
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakSample {
+    pub elapsed_secs: u64,
+    pub cycles: u64,
+    pub documents: u64,
+    pub index_bytes: u64,
+    /// 常驻内存（仅 Linux 可用）
+    pub rss_bytes: Option<u64>,
+    pub ingest_p50_ms: f64,
+    pub search_p50_ms: f64,
+    pub search_p95_ms: f64,
+}
+
+impl SoakSample {
+    fn index_bytes_per_document(&self) -> f64 {
+        self.index_bytes as f64 / self.documents.max(1) as f64
+    }
+}
+
+/// 浸泡测试报告
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakReport {
+    pub samples: Vec<SoakSample>,
+    /// 超出阈值的指标，为空表示通过
+    pub findings: Vec<String>,
+}
+
+impl SoakReport {
+    pub fn passed(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+/// 执行 `soak` 子命令，发现退化时返回错误
+pub async fn run(args: SoakArgs) -> Result<()> {
+    let report = run_soak(&args).await?;
+    if let Some(path) = &args.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        println!("📄 报告已写入 {}", path.display());
+    }
+    if report.passed() {
+        println!("✅ 浸泡测试通过，共 {} 个采样点", report.samples.len());
+        Ok(())
+    } else {
+        for finding in &report.findings {
+            println!("❌ {}", finding);
+        }
+        Err(anyhow!("浸泡测试发现 {} 项退化", report.findings.len()))
+    }
+}
+
+/// 运行浸泡循环并返回采样结果
+pub async fn run_soak(args: &SoakArgs) -> Result<SoakReport> {
+    let temp_dir;
+    let data_dir = match &args.data_dir {
+        Some(dir) => dir.clone(),
+        None => {
+            temp_dir = tempfile::tempdir()?;
+            temp_dir.path().to_path_buf()
+        }
+    };
+    let tool = VectorDocsTool::open_local(data_dir.clone())?
+        .with_embedder(Arc::new(HashEmbeddingProvider::new(SOAK_DIMENSION)));
+
+    let started = Instant::now();
+    let deadline = Duration::from_secs(args.duration_secs);
+    let sample_interval = Duration::from_secs(args.sample_interval_secs);
+    let mut last_sample = Instant::now();
+    let mut samples = Vec::new();
+    let mut ingest_latencies = Vec::new();
+    let mut search_latencies = Vec::new();
+    let mut versions = std::collections::VecDeque::new();
+    let mut cycle = 0u64;
+
+    loop {
+        cycle += 1;
+        let (language, package) = PACKAGES[cycle as usize % PACKAGES.len()];
+        let version = format!("1.{}.0", cycle);
+
+        for i in 0..args.docs_per_cycle {
+            let started = Instant::now();
+            tool.execute(json!({
+                "action": "store",
+                "title": format!("{} {} 第 {} 节", package, version, i),
+                "content": fragment_content(cycle, i),
+                "language": language,
+                "package_name": package,
+                "version": version,
+                "doc_type": "api",
+            })).await?;
+            ingest_latencies.push(started.elapsed());
+        }
+        versions.push_back((language, package, version));
+
+        for i in 0..args.searches_per_cycle {
+            // 查询带上循环序号，避免全部命中嵌入缓存
+            let query = format!("{} {} {}", package, SENTENCES[i % SENTENCES.len()], cycle);
+            let started = Instant::now();
+            tool.execute(json!({ "action": "search", "query": query, "limit": "10" })).await?;
+            search_latencies.push(started.elapsed());
+        }
+
+        while versions.len() > RETAINED_VERSIONS {
+            if let Some((language, package, version)) = versions.pop_front() {
+                tool.purge_packages(Some(language), Some(package), Some(&version), None)?;
+            }
+        }
+
+        let finished = args.cycles.map_or(false, |max| cycle >= max) || started.elapsed() >= deadline;
+        if finished || last_sample.elapsed() >= sample_interval {
+            let sample = SoakSample {
+                elapsed_secs: started.elapsed().as_secs(),
+                cycles: cycle,
+                documents: tool.get_system_status()["database"]["total_documents"].as_u64().unwrap_or(0),
+                index_bytes: directory_size(&data_dir),
+                rss_bytes: resident_memory_bytes(),
+                ingest_p50_ms: percentile_ms(&mut ingest_latencies, 0.5),
+                search_p50_ms: percentile_ms(&mut search_latencies, 0.5),
+                search_p95_ms: percentile_ms(&mut search_latencies, 0.95),
+            };
+            println!(
+                "⏱️ {}s 循环 {}: {} 个文档, 索引 {} KB, RSS {}, 搜索 p95 {:.2}ms",
+                sample.elapsed_secs,
+                sample.cycles,
+                sample.documents,
+                sample.index_bytes / 1024,
+                sample.rss_bytes.map_or_else(|| "未知".to_string(), |rss| format!("{} MB", rss / 1024 / 1024)),
+                sample.search_p95_ms,
+            );
+            samples.push(sample);
+            ingest_latencies.clear();
+            search_latencies.clear();
+            last_sample = Instant::now();
+        }
+        if finished {
+            break;
+        }
+    }
+
+    let findings = detect_drift(&samples, args);
+    Ok(SoakReport { samples, findings })
+}
+
+/// 把最后一个采样与基线比较，返回超出阈值的指标
+///
+/// 采样多于两个时基线取第二个（第一个采样期间索引和缓存仍在预热）；采样少于两个时不做判断。
+pub fn detect_drift(samples: &[SoakSample], args: &SoakArgs) -> Vec<String> {
+    if samples.len() < 2 {
+        return Vec::new();
+    }
+    let baseline = if samples.len() > 2 { &samples[1] } else { &samples[0] };
+    let last = &samples[samples.len() - 1];
+    let mut findings = Vec::new();
+
+    if baseline.search_p95_ms > 0.0 {
+        let drift = last.search_p95_ms / baseline.search_p95_ms;
+        if drift > args.max_latency_drift {
+            findings.push(format!(
+                "搜索 p95 延迟从 {:.2}ms 增长到 {:.2}ms（{:.2} 倍，阈值 {:.2}）",
+                baseline.search_p95_ms, last.search_p95_ms, drift, args.max_latency_drift
+            ));
+        }
+    }
+    if let (Some(base_rss), Some(last_rss)) = (baseline.rss_bytes, last.rss_bytes) {
+        let growth_mb = last_rss.saturating_sub(base_rss) / 1024 / 1024;
+        if growth_mb > args.max_rss_growth_mb {
+            findings.push(format!("常驻内存增长 {} MB（阈值 {} MB）", growth_mb, args.max_rss_growth_mb));
+        }
+    }
+    let base_per_doc = baseline.index_bytes_per_document();
+    if base_per_doc > 0.0 {
+        let drift = last.index_bytes_per_document() / base_per_doc;
+        if drift > args.max_index_drift {
+            findings.push(format!(
+                "每个文档的索引占用从 {:.0} 字节增长到 {:.0} 字节（{:.2} 倍，阈值 {:.2}），清除的文档可能未释放",
+                base_per_doc, last.index_bytes_per_document(), drift, args.max_index_drift
+            ));
+        }
+    }
+    findings
+}
+
+fn fragment_content(cycle: u64, index: usize) -> String {
+    (0..4)
+        .map(|j| SENTENCES[(index + j * 3 + cycle as usize) % SENTENCES.len()])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn percentile_ms(latencies: &mut [Duration], quantile: f64) -> f64 {
+    if latencies.is_empty() {
+        return 0.0;
+    }
+    latencies.sort_unstable();
+    let index = ((latencies.len() - 1) as f64 * quantile).round() as usize;
+    latencies[index].as_secs_f64() * 1000.0
+}
+
+/// 目录下所有文件的总大小
+fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => directory_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// 当前进程的常驻内存，从 `/proc/self/status` 读取（非 Linux 平台返回 None）
+fn resident_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(search_p95_ms: f64, rss_mb: u64, documents: u64, index_bytes: u64) -> SoakSample {
+        SoakSample {
+            elapsed_secs: 0,
+            cycles: 0,
+            documents,
+            index_bytes,
+            rss_bytes: Some(rss_mb * 1024 * 1024),
+            ingest_p50_ms: 1.0,
+            search_p50_ms: search_p95_ms / 2.0,
+            search_p95_ms,
+        }
+    }
+
+    #[test]
+    fn test_detect_drift_against_warmed_up_baseline() {
+        let args = SoakArgs::default();
+        // 第一个采样是预热期，不作为基线
+        let stable = [sample(50.0, 100, 400, 400_000), sample(5.0, 120, 400, 400_000), sample(6.0, 150, 400, 420_000)];
+        assert!(detect_drift(&stable, &args).is_empty());
+
+        let degraded = [sample(5.0, 100, 400, 400_000), sample(5.0, 120, 400, 400_000), sample(20.0, 500, 400, 1_200_000)];
+        assert_eq!(detect_drift(&degraded, &args).len(), 3);
+        assert!(detect_drift(&degraded[..1], &args).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_short_soak_run_keeps_document_count_bounded() {
+        let args = SoakArgs {
+            cycles: Some(RETAINED_VERSIONS as u64 + 3),
+            docs_per_cycle: 3,
+            searches_per_cycle: 2,
+            sample_interval_secs: 0,
+            ..SoakArgs::default()
+        };
+        let report = run_soak(&args).await.unwrap();
+        assert_eq!(report.samples.len(), RETAINED_VERSIONS + 3);
+        let last = report.samples.last().unwrap();
+        assert_eq!(last.documents, (RETAINED_VERSIONS * 3) as u64);
+        assert!(last.index_bytes > 0);
+    }
+}
@@ -1,9 +1,9 @@
 use std::collections::HashMap;
-use std::process::Command;
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn, error};
 use crate::cli::detector::CliToolInfo;
+use crate::tools::platform;
 
 /// Windows管理员权限检测
 #[cfg(target_os = "windows")]
@@ -198,7 +198,7 @@ impl SystemDetector {
     }
 
     fn command_exists(command: &str) -> bool {
-        Command::new(command)
+        platform::std_command(command)
             .arg("--version")
             .output()
             .is_ok()
@@ -506,12 +506,12 @@ impl ToolInstaller {
 
     /// 检查工具是否已安装
     pub async fn is_tool_installed(&self, check_command: &str) -> bool {
-        let parts: Vec<&str> = check_command.split_whitespace().collect();
+        let parts = platform::split_command_line(check_command);
         if parts.is_empty() {
             return false;
         }
 
-        let output = Command::new(parts[0])
+        let output = platform::std_command(&parts[0])
             .args(&parts[1..])
             .output();
 
@@ -686,18 +686,18 @@ impl ToolInstaller {
             return Err(anyhow!("需要手动安装"));
         }
 
-        let parts: Vec<&str> = command.split_whitespace().collect();
+        let parts = platform::split_command_line(command);
         if parts.is_empty() {
             return Err(anyhow!("无效的安装命令"));
         }
 
-        let output = Command::new(parts[0])
+        let output = platform::std_command(&parts[0])
             .args(&parts[1..])
             .output()
             .map_err(|e| anyhow!("执行安装命令失败: {}", e))?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = platform::decode_output(&output.stderr);
             return Err(anyhow!("安装命令执行失败: {}", stderr));
         }
 
@@ -751,18 +751,18 @@ impl ToolInstaller {
     async fn upgrade_tool(&self, tool_info: &ToolInstallInfo, upgrade_command: &str) -> Result<()> {
         info!("⬆️ 升级工具: {}", tool_info.tool_name);
         
-        let parts: Vec<&str> = upgrade_command.split_whitespace().collect();
+        let parts = platform::split_command_line(upgrade_command);
         if parts.is_empty() {
             return Err(anyhow!("无效的升级命令"));
         }
 
-        let output = Command::new(parts[0])
+        let output = platform::std_command(&parts[0])
             .args(&parts[1..])
             .output()
             .map_err(|e| anyhow!("执行升级命令失败: {}", e))?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = platform::decode_output(&output.stderr);
             return Err(anyhow!("升级命令执行失败: {}", stderr));
         }
 
@@ -0,0 +1,152 @@
+//! 类型化的 Grape MCP DevTools 客户端
+//!
+//! 通过 stdio（启动服务器子进程）或 HTTP 与服务器通信，
+//! 供集成测试以及嵌入本工具的其他 Rust 程序使用。
+
+pub mod transport;
+pub mod types;
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::mcp::MCP_VERSION;
+use crate::mcp::server::ToolInfo;
+
+pub use transport::{ClientTransport, HttpTransport, StdioTransport};
+pub use types::{AnswerContext, AnswerValidationResponse, ContextFragment, SearchDocsResponse, SearchHit, VersionCheckResponse};
+
+/// Grape MCP 客户端
+pub struct GrapeClient<T: ClientTransport> {
+    transport: T,
+    next_id: AtomicU64,
+    client_name: String,
+}
+
+impl<T: ClientTransport> GrapeClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            next_id: AtomicU64::new(1),
+            client_name: "grape-rust-client".to_string(),
+        }
+    }
+
+    /// 设置初始化时上报的客户端名称
+    pub fn with_client_name(mut self, client_name: impl Into<String>) -> Self {
+        self.client_name = client_name.into();
+        self
+    }
+
+    /// 发送原始请求，返回 result 字段
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst).to_string();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "version": MCP_VERSION,
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+
+        let response = self.transport.send(request).await?;
+        if let Some(error) = response.get("error").filter(|e| !e.is_null()) {
+            return Err(anyhow!(
+                "服务器返回错误 {}: {}",
+                error["code"].as_i64().unwrap_or_default(),
+                error["message"].as_str().unwrap_or("未知错误")
+            ));
+        }
+        response.get("result")
+            .cloned()
+            .ok_or_else(|| anyhow!("响应缺少result字段: {}", response))
+    }
+
+    /// 初始化会话
+    pub async fn initialize(&self) -> Result<Value> {
+        self.request("initialize", json!({
+            "protocolVersion": MCP_VERSION,
+            "clientInfo": {
+                "name": self.client_name,
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "client_name": self.client_name,
+            "client_version": env!("CARGO_PKG_VERSION"),
+            "capabilities": [],
+        })).await
+    }
+
+    /// 列出服务器工具
+    pub async fn list_tools(&self) -> Result<Vec<ToolInfo>> {
+        let result = self.request("tools/list", json!({})).await?;
+        Ok(serde_json::from_value(result["tools"].clone())?)
+    }
+
+    /// 调用工具并返回结构化结果
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<Value> {
+        let result = self.request("tools/call", json!({
+            "name": name,
+            "arguments": arguments,
+        })).await?;
+
+        if let Some(structured) = result.get("structuredContent").filter(|v| !v.is_null()) {
+            return Ok(structured.clone());
+        }
+
+        // 兼容只返回文本内容的服务器：尝试把文本解析为JSON
+        let text = result["content"]
+            .as_array()
+            .and_then(|items| items.iter().find_map(|item| item["text"].as_str()))
+            .ok_or_else(|| anyhow!("工具 {} 的响应中没有内容", name))?;
+        Ok(serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())))
+    }
+
+    /// 调用工具并解析为指定类型
+    pub async fn call_tool_typed<R: DeserializeOwned>(&self, name: &str, arguments: Value) -> Result<R> {
+        let value = self.call_tool(name, arguments).await?;
+        serde_json::from_value(value).map_err(|e| anyhow!("解析工具 {} 的结果失败: {}", name, e))
+    }
+
+    /// 搜索文档
+    pub async fn search_docs(&self, query: &str, language: &str, max_results: Option<usize>) -> Result<SearchDocsResponse> {
+        let mut arguments = json!({ "query": query, "language": language });
+        if let Some(max_results) = max_results {
+            arguments["max_results"] = json!(max_results);
+        }
+        self.call_tool_typed("search_docs", arguments).await
+    }
+
+    /// 检查包的最新版本
+    pub async fn check_version(&self, package_type: &str, name: &str) -> Result<VersionCheckResponse> {
+        self.call_tool_typed("check_latest_version", json!({ "type": package_type, "name": name })).await
+    }
+
+    /// 为问题收集向量库中的上下文片段
+    pub async fn answer_question(&self, question: &str, limit: usize) -> Result<AnswerContext> {
+        let result = self.call_tool("vector_docs", json!({
+            "action": "search",
+            "query": question,
+            "limit": limit.to_string(),
+        })).await?;
+
+        let fragments: Vec<ContextFragment> = serde_json::from_value(result["results"].clone())
+            .map_err(|e| anyhow!("解析上下文片段失败: {}", e))?;
+        Ok(AnswerContext {
+            question: question.to_string(),
+            fragments,
+        })
+    }
+
+    /// 返回回答前按已缓存的 API 文档校验其中提到的符号，`version` 为空时使用项目锁文件中的版本
+    pub async fn validate_answer(&self, answer: &str, language: Option<&str>, version: Option<&str>) -> Result<AnswerValidationResponse> {
+        let mut arguments = json!({ "answer": answer });
+        if let Some(language) = language {
+            arguments["language"] = json!(language);
+        }
+        if let Some(version) = version {
+            arguments["version"] = json!(version);
+        }
+        self.call_tool_typed("validate_answer", arguments).await
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+/// 客户端传输层
+#[async_trait]
+pub trait ClientTransport: Send + Sync {
+    /// 发送一个JSON请求并等待对应的响应
+    async fn send(&self, request: Value) -> Result<Value>;
+}
+
+/// 通过子进程 stdin/stdout 通信的传输层（每行一个JSON消息）
+pub struct StdioTransport {
+    _child: Child,
+    io: Mutex<(ChildStdin, BufReader<ChildStdout>)>,
+}
+
+impl StdioTransport {
+    /// 启动服务器进程
+    pub fn spawn(program: &str, args: &[&str]) -> Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("启动服务器进程失败: {} - {}", program, e))?;
+
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("无法获取服务器stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("无法获取服务器stdout"))?;
+
+        Ok(Self {
+            _child: child,
+            io: Mutex::new((stdin, BufReader::new(stdout))),
+        })
+    }
+}
+
+#[async_trait]
+impl ClientTransport for StdioTransport {
+    async fn send(&self, request: Value) -> Result<Value> {
+        let request_id = request["id"].clone();
+        let mut io = self.io.lock().await;
+        let (stdin, stdout) = &mut *io;
+
+        stdin.write_all(serde_json::to_string(&request)?.as_bytes()).await?;
+        stdin.write_all(b"\n").await?;
+        stdin.flush().await?;
+
+        loop {
+            let mut line = String::new();
+            if stdout.read_line(&mut line).await? == 0 {
+                return Err(anyhow!("服务器已关闭连接"));
+            }
+            // 跳过非JSON的日志行和不属于本请求的响应
+            match serde_json::from_str::<Value>(line.trim()) {
+                Ok(response) if response["id"] == request_id => return Ok(response),
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// 通过 HTTP POST 通信的传输层
+pub struct HttpTransport {
+    client: reqwest::Client,
+    endpoint: String,
+    auth_token: Option<String>,
+    /// 服务器在 initialize 响应中分配的会话ID
+    session_id: parking_lot::Mutex<Option<String>>,
+}
+
+impl HttpTransport {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.into(),
+            auth_token: None,
+            session_id: parking_lot::Mutex::new(None),
+        }
+    }
+
+    /// 设置 Bearer 认证令牌
+    pub fn with_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(token.into());
+        self
+    }
+}
+
+#[async_trait]
+impl ClientTransport for HttpTransport {
+    async fn send(&self, request: Value) -> Result<Value> {
+        let mut builder = self.client
+            .post(&self.endpoint)
+            .header("Accept", "application/json")
+            .json(&request);
+        if let Some(token) = &self.auth_token {
+            builder = builder.bearer_auth(token);
+        }
+        if let Some(session_id) = self.session_id.lock().clone() {
+            builder = builder.header("Mcp-Session-Id", session_id);
+        }
+
+        let response = builder.send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("HTTP请求失败: {}", response.status()));
+        }
+        if let Some(session_id) = response.headers().get("mcp-session-id").and_then(|v| v.to_str().ok()) {
+            *self.session_id.lock() = Some(session_id.to_string());
+        }
+        Ok(response.json().await?)
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 单条文档搜索命中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub relevance: Option<f64>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// search_docs 工具结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchDocsResponse {
+    pub results: Vec<SearchHit>,
+    pub total_hits: usize,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+/// check_latest_version 工具结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionCheckResponse {
+    pub latest_stable: String,
+    #[serde(default)]
+    pub latest_preview: Option<String>,
+    pub release_date: String,
+    #[serde(default)]
+    pub eol_date: Option<String>,
+    #[serde(default)]
+    pub download_url: Option<String>,
+    pub package_type: String,
+    #[serde(default)]
+    pub available_versions: Vec<String>,
+    #[serde(default)]
+    pub dependencies: Option<Value>,
+    #[serde(default)]
+    pub repository_url: Option<String>,
+}
+
+/// 回答问题时使用的上下文片段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextFragment {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub language: String,
+    pub package_name: String,
+    pub version: String,
+    pub score: f32,
+}
+
+/// 问题及其上下文片段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerContext {
+    pub question: String,
+    pub fragments: Vec<ContextFragment>,
+}
+
+/// validate_answer 工具结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerValidationResponse {
+    /// 没有不存在、版本不符或参数个数不符的 API
+    pub valid: bool,
+    pub checked: usize,
+    pub flagged: usize,
+    /// 每个符号的校验结果（symbol、status、found_in_versions 等）
+    #[serde(default)]
+    pub symbols: Vec<Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_version_check_response() {
+        let value = json!({
+            "latest_stable": "1.38.0",
+            "latest_preview": null,
+            "release_date": "2024-05-30T12:00:00Z",
+            "eol_date": null,
+            "download_url": "https://crates.io/crates/tokio",
+            "package_type": "cargo",
+            "available_versions": ["1.38.0", "1.37.0"],
+            "dependencies": null,
+            "repository_url": "https://github.com/tokio-rs/tokio"
+        });
+        let response: VersionCheckResponse = serde_json::from_value(value).unwrap();
+        assert_eq!(response.latest_stable, "1.38.0");
+        assert_eq!(response.available_versions.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_search_response_with_optional_fields() {
+        let value = json!({
+            "results": [{"title": "Rust std::vec", "content": "Vec<T>"}],
+            "total_hits": 1
+        });
+        let response: SearchDocsResponse = serde_json::from_value(value).unwrap();
+        assert_eq!(response.results[0].title, "Rust std::vec");
+        assert!(response.results[0].url.is_none());
+    }
+}
@@ -1,9 +1,11 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+use crate::tools::cache_eviction::EvictionPolicy;
+
 static SYSTEM_CONFIG: OnceLock<SystemConfig> = OnceLock::new();
 
 /// 系统配置结构
@@ -15,6 +17,27 @@ pub struct SystemConfig {
     pub similarity_detection: SimilarityDetectionConfig,
     pub performance: PerformanceConfig,
     pub ai_integration: AiIntegrationConfig,
+    /// HTTP 传输配置（旧配置文件没有该段时使用默认值）
+    #[serde(default)]
+    pub http_transport: HttpTransportConfig,
+    /// 工具执行超时与并发限制（旧配置文件没有该段时使用默认值）
+    #[serde(default)]
+    pub tool_execution: ToolExecutionConfig,
+    /// 关键词打分的分词配置（旧配置文件没有该段时使用默认值）
+    #[serde(default)]
+    pub text_analysis: TextAnalysisConfig,
+    /// 工具调用审计日志（旧配置文件没有该段时不启用）
+    #[serde(default)]
+    pub audit: AuditConfig,
+    /// 后台缓存完成通知（旧配置文件没有该段时不启用）
+    #[serde(default)]
+    pub webhook: WebhookConfig,
+    /// 需要登录的文档站点的 Cookie 和请求头（旧配置文件没有该段时不附加）
+    #[serde(default)]
+    pub site_auth: SiteAuthConfig,
+    /// 项目移除依赖后如何处理其缓存文档（旧配置文件没有该段时只报告）
+    #[serde(default)]
+    pub dependency_cleanup: DependencyCleanupConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +47,339 @@ pub struct VectorSearchConfig {
     pub search_timeout_ms: u64,
     pub max_results_per_query: usize,
     pub embedding_cache_ttl_hours: u64,
+    /// 嵌入缓存的最大条目数（旧配置文件没有该项时为 10000）
+    #[serde(default = "default_embedding_cache_max_entries")]
+    pub embedding_cache_max_entries: usize,
+    /// 向量距离度量（旧配置文件没有该项时使用欧氏距离）
+    #[serde(default)]
+    pub distance_metric: DistanceMetric,
+    /// 文档元数据后端（旧配置文件没有该项时使用内存）
+    #[serde(default)]
+    pub metadata_backend: MetadataBackend,
+    /// 索引向量量化（旧配置文件没有该段时不量化）
+    #[serde(default)]
+    pub quantization: QuantizationConfig,
+    /// 向量和 HNSW 索引以可内存映射的格式落盘，启动时直接映射而不必反序列化和重建索引
+    #[serde(default = "default_mmap_index")]
+    pub mmap_index: bool,
+    /// 向量索引分片（旧配置文件没有该段时不分片）
+    #[serde(default)]
+    pub sharding: ShardingConfig,
+}
+
+fn default_mmap_index() -> bool {
+    true
+}
+
+fn default_embedding_cache_max_entries() -> usize {
+    crate::tools::embedding_cache::DEFAULT_MAX_ENTRIES
+}
+
+impl VectorSearchConfig {
+    /// 从系统配置读取距离度量，`GRAPE_DISTANCE_METRIC` 可覆盖
+    pub fn distance_metric() -> DistanceMetric {
+        match std::env::var("GRAPE_DISTANCE_METRIC") {
+            Ok(value) if !value.trim().is_empty() => DistanceMetric::parse(&value).unwrap_or_else(|| {
+                tracing::warn!("忽略无法识别的 GRAPE_DISTANCE_METRIC={}（可选 cosine、dot、l2）", value);
+                SystemConfig::load().vector_search.distance_metric
+            }),
+            _ => SystemConfig::load().vector_search.distance_metric,
+        }
+    }
+
+    /// 从系统配置读取元数据后端，`GRAPE_METADATA_BACKEND` 可覆盖
+    pub fn metadata_backend() -> MetadataBackend {
+        match std::env::var("GRAPE_METADATA_BACKEND") {
+            Ok(value) if !value.trim().is_empty() => MetadataBackend::parse(&value).unwrap_or_else(|| {
+                tracing::warn!("忽略无法识别的 GRAPE_METADATA_BACKEND={}（可选 memory、sqlite）", value);
+                SystemConfig::load().vector_search.metadata_backend
+            }),
+            _ => SystemConfig::load().vector_search.metadata_backend,
+        }
+    }
+
+    /// 从系统配置读取量化配置，`GRAPE_QUANTIZATION` 可覆盖量化方式
+    pub fn quantization() -> QuantizationConfig {
+        let mut config = SystemConfig::load().vector_search.quantization;
+        if let Ok(value) = std::env::var("GRAPE_QUANTIZATION") {
+            if !value.trim().is_empty() {
+                match QuantizationMode::parse(&value) {
+                    Some(mode) => config.mode = mode,
+                    None => tracing::warn!("忽略无法识别的 GRAPE_QUANTIZATION={}（可选 none、int8、pq）", value),
+                }
+            }
+        }
+        config
+    }
+
+    /// 从系统配置读取是否使用内存映射格式，`GRAPE_MMAP_INDEX` 可覆盖
+    pub fn mmap_index() -> bool {
+        match std::env::var("GRAPE_MMAP_INDEX") {
+            Ok(value) if !value.trim().is_empty() => match value.trim().to_lowercase().as_str() {
+                "on" | "true" | "1" => true,
+                "off" | "false" | "0" => false,
+                _ => {
+                    tracing::warn!("忽略无法识别的 GRAPE_MMAP_INDEX={}（可选 true、false）", value);
+                    SystemConfig::load().vector_search.mmap_index
+                }
+            },
+            _ => SystemConfig::load().vector_search.mmap_index,
+        }
+    }
+
+    /// 从系统配置读取分片配置，`GRAPE_INDEX_SHARDING` 可覆盖分片方式，`GRAPE_INDEX_SHARDS` 可覆盖分片数
+    pub fn sharding() -> ShardingConfig {
+        let mut config = SystemConfig::load().vector_search.sharding;
+        if let Ok(value) = std::env::var("GRAPE_INDEX_SHARDING") {
+            if !value.trim().is_empty() {
+                match ShardStrategy::parse(&value) {
+                    Some(strategy) => config.strategy = strategy,
+                    None => tracing::warn!("忽略无法识别的 GRAPE_INDEX_SHARDING={}（可选 none、language、hash）", value),
+                }
+            }
+        }
+        if let Ok(value) = std::env::var("GRAPE_INDEX_SHARDS") {
+            match value.trim().parse() {
+                Ok(shards) => config.shards = shards,
+                Err(_) => tracing::warn!("忽略无法解析的 GRAPE_INDEX_SHARDS={}", value),
+            }
+        }
+        config
+    }
+}
+
+/// 索引向量的量化方式
+///
+/// 量化只作用于内存中的 HNSW 索引：索引按压缩后的向量建图和取候选，
+/// 再用原始向量对候选重新计算距离排序，因此返回的分数不受量化影响。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuantizationMode {
+    /// 索引保存完整的 f32 向量
+    #[default]
+    None,
+    /// 按维度缩放到 int8，索引内存约为 1/4
+    Int8,
+    /// 乘积量化：每个子空间用 256 个中心点的码本编码为 1 字节
+    Pq,
+}
+
+impl QuantizationMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "none" | "off" | "f32" => Some(QuantizationMode::None),
+            "int8" | "scalar" | "sq8" => Some(QuantizationMode::Int8),
+            "pq" | "product" => Some(QuantizationMode::Pq),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuantizationMode::None => "none",
+            QuantizationMode::Int8 => "int8",
+            QuantizationMode::Pq => "pq",
+        }
+    }
+}
+
+/// 量化配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuantizationConfig {
+    pub mode: QuantizationMode,
+    /// PQ 子空间数（每个向量编码后的字节数），越大召回越高、内存越多
+    pub pq_subvectors: usize,
+    /// 从量化索引中取 `limit * rerank_factor` 个候选，再用原始向量重排
+    pub rerank_factor: usize,
+    /// 向量数少于该值时不量化
+    pub min_vectors: usize,
+}
+
+impl Default for QuantizationConfig {
+    fn default() -> Self {
+        Self {
+            mode: QuantizationMode::None,
+            pq_subvectors: 64,
+            rerank_factor: 4,
+            min_vectors: 1000,
+        }
+    }
+}
+
+/// 向量索引的分片方式
+///
+/// 分片后每个子索引独立构建，搜索时并行查询各分片再按分数合并；写入或删除文档只重建成员有变化的分片。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShardStrategy {
+    /// 单个索引
+    #[default]
+    None,
+    /// 按编程语言分片，带语言过滤的搜索只查询对应分片
+    Language,
+    /// 按文档ID哈希均匀分片
+    Hash,
+}
+
+impl ShardStrategy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "none" | "off" => Some(ShardStrategy::None),
+            "language" | "lang" => Some(ShardStrategy::Language),
+            "hash" | "id" => Some(ShardStrategy::Hash),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShardStrategy::None => "none",
+            ShardStrategy::Language => "language",
+            ShardStrategy::Hash => "hash",
+        }
+    }
+}
+
+/// 分片配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShardingConfig {
+    pub strategy: ShardStrategy,
+    /// 分片数（按语言分片时多个语言可能落在同一分片）
+    pub shards: usize,
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        Self {
+            strategy: ShardStrategy::None,
+            shards: 8,
+        }
+    }
+}
+
+impl ShardingConfig {
+    /// 分片数；不分片（或分片数不足 2）时返回 None
+    pub fn shard_count(&self) -> Option<usize> {
+        (self.strategy != ShardStrategy::None && self.shards > 1).then_some(self.shards)
+    }
+
+    /// 文档所在的分片；不分片时返回 0
+    pub fn shard_of(&self, language: &str, doc_id: &str) -> usize {
+        let Some(shards) = self.shard_count() else {
+            return 0;
+        };
+        let key = match self.strategy {
+            ShardStrategy::Language => language.to_lowercase(),
+            _ => doc_id.to_string(),
+        };
+        // FNV-1a，分片归属不随进程或编译器版本变化
+        let hash = key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+        (hash % shards as u64) as usize
+    }
+}
+
+/// 文档元数据后端
+///
+/// 向量始终保存在 HNSW 索引中。`sqlite` 额外把元数据写入数据目录下的 SQLite 数据库，
+/// 过滤搜索需要扫描时先用 SQL 筛出候选文档（需要启用 `database` feature）。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataBackend {
+    /// 只在内存中逐条匹配
+    #[default]
+    Memory,
+    Sqlite,
+}
+
+impl MetadataBackend {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "memory" | "in_memory" => Some(MetadataBackend::Memory),
+            "sqlite" => Some(MetadataBackend::Sqlite),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetadataBackend::Memory => "memory",
+            MetadataBackend::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// 向量距离度量
+///
+/// 归一化的嵌入模型（大多数 OpenAI 兼容模型）应使用 `cosine` 或 `dot`；欧氏距离 `l2`
+/// 是历史默认值。距离越小越相似，[`DistanceMetric::similarity`] 把距离换算为越大越相似的分数。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceMetric {
+    /// 余弦距离 `1 - cos`，取值 [0, 2]
+    Cosine,
+    /// 点积距离 `1 - a·b`，对归一化向量等价于余弦距离
+    Dot,
+    /// 欧氏距离
+    #[default]
+    #[serde(alias = "euclidean")]
+    L2,
+}
+
+impl DistanceMetric {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "cosine" | "cos" => Some(DistanceMetric::Cosine),
+            "dot" | "dot_product" | "inner_product" => Some(DistanceMetric::Dot),
+            "l2" | "euclidean" => Some(DistanceMetric::L2),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DistanceMetric::Cosine => "cosine",
+            DistanceMetric::Dot => "dot",
+            DistanceMetric::L2 => "l2",
+        }
+    }
+
+    /// 两个向量之间的距离（长度不同时按较短的部分计算）
+    pub fn distance(&self, a: &[f32], b: &[f32]) -> f32 {
+        self.distance_pairs(a.iter().copied().zip(b.iter().copied()))
+    }
+
+    /// 按逐维取值对计算距离，用于不必先还原成 `Vec<f32>` 的量化向量
+    pub fn distance_pairs(&self, pairs: impl Iterator<Item = (f32, f32)>) -> f32 {
+        match self {
+            DistanceMetric::L2 => pairs.map(|(x, y)| (x - y).powi(2)).sum::<f32>().sqrt(),
+            DistanceMetric::Dot => 1.0 - pairs.map(|(x, y)| x * y).sum::<f32>(),
+            DistanceMetric::Cosine => {
+                let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+                for (x, y) in pairs {
+                    dot += x * y;
+                    norm_a += x * x;
+                    norm_b += y * y;
+                }
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    return 1.0;
+                }
+                1.0 - dot / (norm_a.sqrt() * norm_b.sqrt())
+            }
+        }
+    }
+
+    /// 把距离换算为相似度分数，归一化向量的分数在 [0, 1] 内
+    ///
+    /// - `l2`: `1 / (1 + d)`
+    /// - `cosine` / `dot`: `1 - d / 2`，即 `(1 + cos) / 2`
+    pub fn similarity(&self, distance: f32) -> f32 {
+        match self {
+            DistanceMetric::L2 => 1.0 / (1.0 + distance),
+            DistanceMetric::Cosine | DistanceMetric::Dot => 1.0 - distance / 2.0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +444,29 @@ pub struct VectorDbConfig {
     
     /// 查询配置
     pub query: QueryConfig,
+
+    /// 向量距离度量（索引构建、查询和分数换算共用）
+    #[serde(default)]
+    pub distance_metric: DistanceMetric,
+
+    /// 文档过期和容量淘汰（旧配置文件没有该段时不过期、不限制大小）
+    #[serde(default)]
+    pub retention: RetentionConfig,
+
+    /// 每个文档保留的历史修订数（更新和回滚前的版本），0 表示不保留
+    #[serde(default = "default_max_revisions")]
+    pub max_revisions: usize,
+
+    /// 后台索引优化和存储压缩（旧配置文件没有该段时不启用）
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    /// 嵌入前按 token 数切分长文档（旧配置文件没有该段时使用默认值）
+    #[serde(default)]
+    pub chunking: ChunkingConfig,
+
+    /// 稀疏嵌入与稠密向量的混合检索
+    #[serde(default)]
+    pub sparse: SparseConfig,
 }
 
 /// HNSW 索引配置
@@ -109,7 +488,7 @@ pub struct HnswConfig {
 /// 嵌入配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingConfig {
-    /// 提供者类型 (nvidia, openai, azure, ollama, mock)
+    /// 提供者类型 (nvidia, openai, azure, ollama, cohere, jina, voyage, local, mock, hash)
     pub provider: String,
     
     /// API端点
@@ -118,7 +497,7 @@ pub struct EmbeddingConfig {
     /// API 密钥
     pub api_key: Option<String>,
     
-    /// 模型名称
+    /// 模型名称（`local` 时为 Hugging Face 模型ID或本地模型目录）
     pub model: String,
     
     /// API版本（Azure专用）
@@ -138,9 +517,51 @@ pub struct EmbeddingConfig {
     
     /// 批量大小
     pub batch_size: usize,
+
+    /// 批量写入时同时请求的嵌入批次数
+    #[serde(default = "default_embedding_parallelism")]
+    pub parallelism: usize,
     
     /// 请求超时（秒）
     pub timeout_seconds: u64,
+
+    /// 本地模型缓存目录（`local` 专用），默认 `~/.grape-mcp-devtools/models`
+    #[serde(default)]
+    pub model_cache_dir: Option<PathBuf>,
+
+    /// 完全离线（`local` 专用）：只使用缓存目录中已有的模型，不下载
+    #[serde(default)]
+    pub offline: bool,
+
+    /// 备用提供商，按顺序在主提供商限流（429）、服务端错误（5xx）或网络故障时依次尝试；
+    /// 备用项自身的 `fallbacks` 被忽略，嵌入维度必须与主提供商一致
+    #[serde(default)]
+    pub fallbacks: Vec<EmbeddingConfig>,
+
+    /// 故障转移链中各提供商的熔断设置
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+
+    /// Matryoshka 截断维度：模型按 Matryoshka 表示学习训练时，把向量截断到前若干维（如 256）并重新归一化后再写入索引，
+    /// `vector_dimension` 应设为同一值；None 表示不截断
+    #[serde(default)]
+    pub truncate_dimension: Option<usize>,
+}
+
+/// 嵌入提供商熔断配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// 连续失败多少次后熔断
+    pub failure_threshold: u32,
+    /// 熔断持续时间（秒），到期后放行一次试探请求，成功则恢复
+    pub open_seconds: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self { failure_threshold: 3, open_seconds: 30 }
+    }
 }
 
 /// 缓存配置
@@ -154,6 +575,10 @@ pub struct CacheConfig {
     
     /// 缓存TTL（秒）
     pub cache_ttl_seconds: u64,
+
+    /// 查询向量与缓存查询的余弦相似度达到该值时复用缓存结果（1.0 只复用完全相同的查询）
+    #[serde(default = "default_query_cache_similarity")]
+    pub query_cache_similarity: f32,
 }
 
 /// 持久化配置
@@ -188,6 +613,465 @@ pub struct QueryConfig {
     pub similarity_threshold: f32,
 }
 
+/// 文档元数据中单独指定保留时间（秒）的键，0 表示永不过期
+pub const DOCUMENT_TTL_METADATA_KEY: &str = "ttl_seconds";
+
+/// 文档保留配置：过期时间和容量淘汰
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// 文档默认保留时间（秒，从最后更新算起），None 表示不过期
+    pub ttl_seconds: Option<u64>,
+    /// 每个集合的总大小上限（字节），None 表示不限制
+    pub max_total_bytes: Option<u64>,
+    /// 超出上限时的淘汰策略
+    pub eviction_policy: EvictionPolicy,
+    /// 后台清理间隔（秒）
+    pub sweep_interval_seconds: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            ttl_seconds: None,
+            max_total_bytes: None,
+            eviction_policy: EvictionPolicy::Lru,
+            sweep_interval_seconds: 3600,
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// 是否需要后台清理
+    pub fn is_enabled(&self) -> bool {
+        self.ttl_seconds.is_some() || self.max_total_bytes.is_some()
+    }
+
+    /// 文档的保留时间：元数据 `ttl_seconds` 优先（0 表示不过期），否则使用默认值
+    pub fn document_ttl(&self, metadata: &HashMap<String, String>) -> Option<u64> {
+        match metadata.get(DOCUMENT_TTL_METADATA_KEY).and_then(|v| v.trim().parse::<u64>().ok()) {
+            Some(0) => None,
+            Some(ttl) => Some(ttl),
+            None => self.ttl_seconds,
+        }
+    }
+}
+
+/// 后台维护配置：空闲时压缩存储、重建删除较多的索引并落盘
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    /// 是否启动后台维护任务
+    pub enabled: bool,
+    /// 检查间隔（秒）
+    pub interval_seconds: u64,
+    /// 最近这么久（秒）没有查询和写入时才执行，避免与前台请求争用资源
+    pub idle_seconds: u64,
+    /// 单次维护的时间预算（秒），用完后剩余集合留到下次
+    pub max_duration_seconds: u64,
+    /// 上次重建后删除的向量占文档数的比例达到该值时重建索引（清除已删除的向量并重新平衡 HNSW 图）
+    pub rebuild_deleted_ratio: f32,
+    /// 是否压缩存储文件
+    pub compact_storage: bool,
+}
+
+/// 长文档分块配置：超过 `max_tokens` 的文档按 token 数切分（相邻块重叠 `overlap_tokens`）后逐块嵌入
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkingConfig {
+    pub enabled: bool,
+    /// 每块的最大 token 数，应不超过嵌入模型的输入上限
+    pub max_tokens: usize,
+    /// 相邻块重叠的 token 数
+    pub overlap_tokens: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self { enabled: true, max_tokens: 512, overlap_tokens: 64 }
+    }
+}
+
+/// 稀疏与稠密检索结果的融合方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SparseFusion {
+    /// 倒数排名融合：只看两路结果中的名次，不受分数刻度影响
+    #[default]
+    Rrf,
+    /// 两路分数（稀疏分数按本次最高分归一化）按 `dense_weight` 加权
+    Weighted,
+}
+
+/// 稀疏嵌入配置：启用后为每个文档生成按词项加权的稀疏向量（与稠密向量并存），搜索时两路结果融合，
+/// 提升 `tokio::select!` 这类精确标识符查询的召回
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SparseConfig {
+    pub enabled: bool,
+    pub fusion: SparseFusion,
+    /// 稠密结果的权重（0-1），稀疏结果为 `1 - dense_weight`
+    pub dense_weight: f32,
+    /// RRF 的平滑常数，越大名次差异的影响越小
+    pub rrf_k: f32,
+}
+
+impl Default for SparseConfig {
+    fn default() -> Self {
+        Self { enabled: false, fusion: SparseFusion::Rrf, dense_weight: 0.5, rrf_k: 60.0 }
+    }
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: 3600,
+            idle_seconds: 300,
+            max_duration_seconds: 600,
+            rebuild_deleted_ratio: 0.2,
+            compact_storage: true,
+        }
+    }
+}
+
+/// Streamable HTTP 传输配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpTransportConfig {
+    /// 监听地址，例如 `127.0.0.1:8808`
+    pub bind_address: String,
+    /// MCP 端点路径
+    pub endpoint_path: String,
+    /// TLS 配置，未设置时使用明文 HTTP
+    pub tls: Option<TlsConfig>,
+    /// 允许的 Origin（防止 DNS 重绑定），为空时只允许无 Origin 或本机来源
+    pub allowed_origins: Vec<String>,
+    /// 会话空闲超时（秒），超时后会话被回收
+    pub session_idle_timeout_secs: u64,
+    /// API 密钥，为空时不启用认证
+    pub api_keys: Vec<ApiKeyConfig>,
+}
+
+/// 网络传输的 API 密钥
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    /// 密钥名称，用于日志和统计
+    pub name: String,
+    /// Bearer 令牌
+    pub token: String,
+    /// 授权范围：`read`（搜索、查询）、`write`（写入、删除、导入，包含 `read`）或 `admin`（管理工具，包含 `write`）
+    #[serde(default = "default_api_key_scopes")]
+    pub scopes: Vec<String>,
+    /// 私有文档命名空间：写入的文档只有同一命名空间的密钥可见；未设置时读写共享集合
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// 每分钟最多的工具调用次数，未设置时不限制
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+fn default_api_key_scopes() -> Vec<String> {
+    vec!["read".to_string()]
+}
+
+impl ApiKeyConfig {
+    /// 解析 `名称:令牌[:范围+范围[:命名空间]]`，未指定范围时为只读
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut parts = spec.trim().splitn(4, ':');
+        let name = parts.next().filter(|n| !n.is_empty())?.to_string();
+        let token = parts.next().filter(|t| !t.is_empty())?.to_string();
+        let scopes = parts
+            .next()
+            .map(|scopes| scopes.split('+').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect::<Vec<_>>())
+            .filter(|scopes| !scopes.is_empty())
+            .unwrap_or_else(default_api_key_scopes);
+        let namespace = parts.next().map(str::trim).filter(|ns| !ns.is_empty()).map(str::to_string);
+        Some(Self { name, token, scopes, namespace, rate_limit_per_minute: None })
+    }
+}
+
+/// TLS 证书配置（PEM 格式）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+}
+
+impl Default for HttpTransportConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: "127.0.0.1:8808".to_string(),
+            endpoint_path: "/mcp".to_string(),
+            tls: None,
+            allowed_origins: Vec::new(),
+            session_idle_timeout_secs: 1800,
+            api_keys: Vec::new(),
+        }
+    }
+}
+
+impl HttpTransportConfig {
+    /// 从系统配置加载，再用环境变量覆盖：
+    /// `GRAPE_HTTP_BIND`、`GRAPE_HTTP_PATH`、`GRAPE_TLS_CERT`、`GRAPE_TLS_KEY`、`GRAPE_HTTP_ALLOWED_ORIGINS`（逗号分隔）、
+    /// `GRAPE_API_KEYS`（逗号分隔的 `名称:令牌[:read+write[:命名空间]]`）
+    pub fn load() -> Self {
+        let mut config = SystemConfig::load().http_transport.clone();
+        config.apply_env();
+        config
+    }
+
+    fn apply_env(&mut self) {
+        if let Ok(bind) = std::env::var("GRAPE_HTTP_BIND") {
+            self.bind_address = bind;
+        }
+        if let Ok(path) = std::env::var("GRAPE_HTTP_PATH") {
+            self.endpoint_path = path;
+        }
+        if let (Ok(cert_path), Ok(key_path)) = (std::env::var("GRAPE_TLS_CERT"), std::env::var("GRAPE_TLS_KEY")) {
+            self.tls = Some(TlsConfig { cert_path, key_path });
+        }
+        if let Ok(origins) = std::env::var("GRAPE_HTTP_ALLOWED_ORIGINS") {
+            self.allowed_origins = origins.split(',').map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect();
+        }
+        if let Ok(keys) = std::env::var("GRAPE_API_KEYS") {
+            self.api_keys = keys.split(',').filter_map(ApiKeyConfig::parse).collect();
+        }
+    }
+}
+
+/// 工具执行限制：超时和并发数，避免慢速数据源（如 Maven Central）拖垮其他工具调用
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolExecutionConfig {
+    /// 默认超时（毫秒），包括等待并发名额的时间
+    pub default_timeout_ms: u64,
+    /// 所有工具同时执行的最大调用数
+    pub max_concurrent_calls: usize,
+    /// 只读模式：从工具 Schema 中隐藏修改缓存的操作（store、delete、导入等），调用时直接拒绝
+    pub read_only: bool,
+    /// 按工具名覆盖的限制
+    pub tools: HashMap<String, ToolLimitConfig>,
+}
+
+/// 单个工具的限制，未设置的项使用全局默认值
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolLimitConfig {
+    pub timeout_ms: Option<u64>,
+    /// 该工具同时执行的最大调用数
+    pub max_concurrent: Option<usize>,
+}
+
+impl Default for ToolExecutionConfig {
+    fn default() -> Self {
+        Self {
+            default_timeout_ms: 30_000,
+            max_concurrent_calls: 16,
+            read_only: false,
+            tools: HashMap::new(),
+        }
+    }
+}
+
+impl ToolExecutionConfig {
+    /// 从系统配置加载，再用环境变量覆盖：`GRAPE_TOOL_TIMEOUT_MS`、`GRAPE_TOOL_MAX_CONCURRENCY`、
+    /// `GRAPE_READ_ONLY`（`true`/`1` 开启，`false`/`0` 关闭）
+    pub fn load() -> Self {
+        let mut config = SystemConfig::load().tool_execution.clone();
+        if let Some(timeout_ms) = std::env::var("GRAPE_TOOL_TIMEOUT_MS").ok().and_then(|v| v.parse().ok()) {
+            config.default_timeout_ms = timeout_ms;
+        }
+        if let Some(max) = std::env::var("GRAPE_TOOL_MAX_CONCURRENCY").ok().and_then(|v| v.parse().ok()) {
+            config.max_concurrent_calls = max;
+        }
+        if let Ok(read_only) = std::env::var("GRAPE_READ_ONLY") {
+            match read_only.trim().to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => config.read_only = true,
+                "0" | "false" | "no" | "off" => config.read_only = false,
+                other => tracing::warn!("⚠️ 无法识别的 GRAPE_READ_ONLY 值: {}", other),
+            }
+        }
+        config
+    }
+
+    /// 某个工具的超时
+    pub fn timeout_for(&self, tool_name: &str) -> std::time::Duration {
+        let timeout_ms = self.tools
+            .get(tool_name)
+            .and_then(|limits| limits.timeout_ms)
+            .unwrap_or(self.default_timeout_ms);
+        std::time::Duration::from_millis(timeout_ms)
+    }
+
+    /// 某个工具单独的并发上限
+    pub fn concurrency_for(&self, tool_name: &str) -> Option<usize> {
+        self.tools.get(tool_name).and_then(|limits| limits.max_concurrent)
+    }
+}
+
+/// 工具调用审计日志配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// 是否记录每次工具调用
+    pub enabled: bool,
+    /// 审计文件路径，未设置时为数据目录下的 `audit.jsonl`
+    pub path: Option<String>,
+}
+
+impl AuditConfig {
+    /// 从系统配置加载；设置 `GRAPE_AUDIT_LOG`（审计文件路径）时启用并覆盖路径
+    pub fn load() -> Self {
+        let mut config = SystemConfig::load().audit.clone();
+        if let Ok(path) = std::env::var("GRAPE_AUDIT_LOG") {
+            if !path.trim().is_empty() {
+                config.enabled = true;
+                config.path = Some(path.trim().to_string());
+            }
+        }
+        config
+    }
+}
+
+/// 后台缓存完成时的 webhook 通知配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// 接收通知的地址，未设置时不发送
+    pub url: Option<String>,
+    /// 签名密钥；设置后请求带 `X-Grape-Signature: sha256=<HMAC-SHA256(请求体)>`
+    pub secret: Option<String>,
+    /// 包缓存失败时是否也发送通知
+    pub notify_failures: bool,
+    /// 单次请求超时（秒）
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            secret: None,
+            notify_failures: true,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// 从系统配置加载，`GRAPE_WEBHOOK_URL`、`GRAPE_WEBHOOK_SECRET` 可覆盖
+    pub fn load() -> Self {
+        let mut config = SystemConfig::load().webhook.clone();
+        if let Ok(url) = std::env::var("GRAPE_WEBHOOK_URL") {
+            config.url = Some(url.trim().to_string()).filter(|u| !u.is_empty());
+        }
+        if let Ok(secret) = std::env::var("GRAPE_WEBHOOK_SECRET") {
+            config.secret = Some(secret).filter(|s| !s.is_empty());
+        }
+        config
+    }
+}
+
+/// 文档站点认证配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SiteAuthConfig {
+    pub domains: Vec<DomainAuthConfig>,
+}
+
+/// 单个域名的认证信息，值中的 `${变量名}` 在使用时替换为环境变量，避免把凭据写进配置文件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DomainAuthConfig {
+    /// 域名，同时匹配其子域名（`docs.example.com` 匹配 `api.docs.example.com`）
+    pub domain: String,
+    /// 以 `Cookie` 请求头发送的 Cookie
+    pub cookies: std::collections::BTreeMap<String, String>,
+    /// 额外的请求头，如 `Authorization`
+    pub headers: std::collections::BTreeMap<String, String>,
+    /// 是否也对 http（非 https）请求附加，默认只对 https 附加
+    pub allow_insecure: bool,
+}
+
+/// 项目不再依赖的包的缓存处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyCleanupMode {
+    /// 不检查
+    Off,
+    /// 记录可回收的空间（见缓存统计），由用户决定是否清除
+    #[default]
+    Report,
+    /// 重新扫描发现依赖被移除后立即清除其文档和已处理标记
+    Purge,
+}
+
+impl DependencyCleanupMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "off" | "none" | "disabled" => Some(DependencyCleanupMode::Off),
+            "report" => Some(DependencyCleanupMode::Report),
+            "purge" | "auto" => Some(DependencyCleanupMode::Purge),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyCleanupMode::Off => "off",
+            DependencyCleanupMode::Report => "report",
+            DependencyCleanupMode::Purge => "purge",
+        }
+    }
+}
+
+/// 项目移除依赖后的缓存清理配置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DependencyCleanupConfig {
+    pub mode: DependencyCleanupMode,
+}
+
+impl DependencyCleanupConfig {
+    /// 从系统配置加载，`GRAPE_DEPENDENCY_CLEANUP` 可覆盖
+    pub fn load() -> Self {
+        let mut config = SystemConfig::load().dependency_cleanup;
+        if let Ok(value) = std::env::var("GRAPE_DEPENDENCY_CLEANUP") {
+            match DependencyCleanupMode::parse(&value) {
+                Some(mode) => config.mode = mode,
+                None => tracing::warn!("忽略无效的 GRAPE_DEPENDENCY_CLEANUP: {} (可选 off/report/purge)", value),
+            }
+        }
+        config
+    }
+}
+
+/// 关键词打分的分词配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextAnalysisConfig {
+    /// 无法判定文本语言时使用的分词语言
+    pub default_language: String,
+    /// 非中日韩词条的最短字符数
+    pub min_token_length: usize,
+    /// 是否拆分 `snake_case` / `CamelCase` 标识符（完整标识符始终保留）
+    pub split_identifiers: bool,
+    /// 按语言追加的停用词，键为语言代码或名称（如 `en`、`zh`、`中文`）
+    pub extra_stopwords: HashMap<String, Vec<String>>,
+}
+
+impl Default for TextAnalysisConfig {
+    fn default() -> Self {
+        Self {
+            default_language: "en".to_string(),
+            min_token_length: 2,
+            split_identifiers: true,
+            extra_stopwords: HashMap::new(),
+        }
+    }
+}
+
 /// 混合搜索权重
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HybridWeights {
@@ -219,6 +1103,12 @@ impl Default for SystemConfig {
                 search_timeout_ms: 1000,
                 max_results_per_query: 100,
                 embedding_cache_ttl_hours: 24,
+                embedding_cache_max_entries: default_embedding_cache_max_entries(),
+                distance_metric: DistanceMetric::default(),
+                metadata_backend: MetadataBackend::default(),
+                quantization: QuantizationConfig::default(),
+                mmap_index: true,
+                sharding: ShardingConfig::default(),
             },
             api_limits: ApiLimitsConfig {
                 github_per_page: 100,
@@ -252,6 +1142,13 @@ impl Default for SystemConfig {
                 enable_real_ai_analysis: true,
                 api_timeout_seconds: 30,
             },
+            http_transport: HttpTransportConfig::default(),
+            tool_execution: ToolExecutionConfig::default(),
+            text_analysis: TextAnalysisConfig::default(),
+            audit: AuditConfig::default(),
+            webhook: WebhookConfig::default(),
+            site_auth: SiteAuthConfig::default(),
+            dependency_cleanup: DependencyCleanupConfig::default(),
         }
     }
 }
@@ -319,10 +1216,20 @@ impl Default for VectorDbConfig {
             cache: CacheConfig::default(),
             persistence: PersistenceConfig::default(),
             query: QueryConfig::default(),
+            distance_metric: DistanceMetric::default(),
+            retention: RetentionConfig::default(),
+            max_revisions: default_max_revisions(),
+            maintenance: MaintenanceConfig::default(),
+            chunking: ChunkingConfig::default(),
+            sparse: SparseConfig::default(),
         }
     }
 }
 
+fn default_max_revisions() -> usize {
+    10
+}
+
 impl Default for HnswConfig {
     fn default() -> Self {
         Self {
@@ -347,21 +1254,36 @@ impl Default for EmbeddingConfig {
             headers: HashMap::new(),
             retry_attempts: 3,
             batch_size: 100,
+            parallelism: default_embedding_parallelism(),
             timeout_seconds: 30,
+            model_cache_dir: None,
+            offline: false,
+            fallbacks: Vec::new(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            truncate_dimension: None,
         }
     }
 }
 
+fn default_embedding_parallelism() -> usize {
+    4
+}
+
 impl Default for CacheConfig {
     fn default() -> Self {
         Self {
             embedding_cache_size: 10000,
             query_cache_size: 1000,
             cache_ttl_seconds: 86400, // 24小时
+            query_cache_similarity: default_query_cache_similarity(),
         }
     }
 }
 
+fn default_query_cache_similarity() -> f32 {
+    0.98
+}
+
 impl Default for PersistenceConfig {
     fn default() -> Self {
         Self {
@@ -442,4 +1364,77 @@ impl VectorDbConfig {
         config.vector_dimension = 768; // Ollama常用维度
         config
     }
-} 
\ No newline at end of file
+
+    /// 使用本地 sentence-transformer 模型创建配置（需要 `local-embeddings` 特性），`model` 为空时使用 bge-small
+    pub fn with_local_model(model: Option<String>, offline: bool) -> Self {
+        let mut config = Self::default();
+        config.embedding = EmbeddingConfig {
+            provider: "local".to_string(),
+            model: model.unwrap_or_else(|| crate::embeddings::DEFAULT_LOCAL_MODEL.to_string()),
+            dimension: Some(crate::embeddings::DEFAULT_LOCAL_DIMENSION),
+            offline,
+            ..Default::default()
+        };
+        config.vector_dimension = crate::embeddings::DEFAULT_LOCAL_DIMENSION;
+        config
+    }
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_metrics() {
+        let (a, b) = ([1.0, 0.0], [0.0, 2.0]);
+        assert!((DistanceMetric::L2.distance(&a, &b) - 5f32.sqrt()).abs() < 1e-6);
+        assert!((DistanceMetric::Cosine.distance(&a, &b) - 1.0).abs() < 1e-6);
+        assert!(DistanceMetric::Cosine.distance(&a, &[3.0, 0.0]).abs() < 1e-6);
+        assert!((DistanceMetric::Dot.distance(&a, &[0.5, 0.0]) - 0.5).abs() < 1e-6);
+
+        assert_eq!(DistanceMetric::Cosine.similarity(0.0), 1.0);
+        assert_eq!(DistanceMetric::Cosine.similarity(2.0), 0.0);
+        assert_eq!(DistanceMetric::L2.similarity(1.0), 0.5);
+
+        assert_eq!(DistanceMetric::parse("Euclidean"), Some(DistanceMetric::L2));
+        assert_eq!(DistanceMetric::parse("manhattan"), None);
+        let config: VectorSearchConfig = toml::from_str(
+            "cache_limit = 1\nsimilarity_threshold = 0.5\nsearch_timeout_ms = 1\nmax_results_per_query = 1\nembedding_cache_ttl_hours = 1\ndistance_metric = \"cosine\"",
+        ).unwrap();
+        assert_eq!(config.distance_metric, DistanceMetric::Cosine);
+        assert_eq!(config.metadata_backend, MetadataBackend::Memory);
+        assert_eq!(MetadataBackend::parse("SQLite"), Some(MetadataBackend::Sqlite));
+        assert_eq!(config.quantization, QuantizationConfig::default());
+        assert_eq!(QuantizationMode::parse("PQ"), Some(QuantizationMode::Pq));
+        assert_eq!(config.sharding.shard_count(), None);
+    }
+
+    #[test]
+    fn test_shard_assignment() {
+        let by_language = ShardingConfig { strategy: ShardStrategy::Language, shards: 4 };
+        assert_eq!(by_language.shard_count(), Some(4));
+        assert_eq!(by_language.shard_of("Rust", "a"), by_language.shard_of("rust", "b"));
+        assert!(by_language.shard_of("python", "a") < 4);
+
+        let by_hash = ShardingConfig { strategy: ShardStrategy::Hash, shards: 4 };
+        let shards: std::collections::HashSet<usize> = (0..100).map(|i| by_hash.shard_of("rust", &format!("doc-{}", i))).collect();
+        assert_eq!(shards.len(), 4);
+
+        assert_eq!(ShardingConfig { strategy: ShardStrategy::Hash, shards: 1 }.shard_count(), None);
+        assert_eq!(ShardStrategy::parse("LANG"), Some(ShardStrategy::Language));
+    }
+
+    #[test]
+    fn test_local_embedding_config() {
+        let config = VectorDbConfig::with_local_model(None, true);
+        assert_eq!(config.embedding.provider, "local");
+        assert_eq!(config.embedding.model, crate::embeddings::DEFAULT_LOCAL_MODEL);
+        assert_eq!(config.embedding.dimension, Some(config.vector_dimension));
+        assert!(config.embedding.offline);
+
+        // 旧配置没有本地模型字段
+        let mut value = serde_json::to_value(&EmbeddingConfig::default()).unwrap();
+        value.as_object_mut().unwrap().retain(|key, _| key != "offline" && key != "model_cache_dir");
+        let parsed: EmbeddingConfig = serde_json::from_value(value).unwrap();
+        assert!(!parsed.offline && parsed.model_cache_dir.is_none());
+    }
+}
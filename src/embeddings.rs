@@ -1,8 +1,22 @@
-use crate::{config::EmbeddingConfig, errors::{Result, VectorDbError}};
+use crate::{config::{CircuitBreakerConfig, EmbeddingConfig}, errors::{Result, VectorDbError}};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use reqwest::Client;
 use async_trait::async_trait;
+use tracing::Instrument;
+use crate::mcp::correlation::Correlated;
+use crate::tools::embedder::{truncate_embedding, InputType, TextEmbedder};
+pub use crate::tools::embedder::{HashEmbeddingProvider, DEFAULT_HASH_DIMENSION};
+#[cfg(feature = "local-embeddings")]
+pub use local::LocalProvider;
+
+/// 本地嵌入的默认模型（Hugging Face 模型ID）
+pub const DEFAULT_LOCAL_MODEL: &str = "BAAI/bge-small-en-v1.5";
+
+/// 默认本地模型的嵌入维度
+pub const DEFAULT_LOCAL_DIMENSION: usize = 384;
 
 /// 嵌入提供商trait
 #[async_trait]
@@ -10,6 +24,16 @@ pub trait EmbeddingProvider: Send + Sync {
     async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>>;
     async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
     fn dimensions(&self) -> usize;
+
+    /// 为搜索查询生成嵌入；区分文档和查询编码的服务（Cohere、Jina、Voyage）覆盖此方法
+    async fn generate_query_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.generate_embedding(text).await
+    }
+
+    /// 各提供商的请求统计（只有故障转移链提供）
+    fn provider_metrics(&self) -> Vec<EmbeddingProviderMetrics> {
+        Vec::new()
+    }
 }
 
 /// OpenAI API响应结构
@@ -128,7 +152,9 @@ impl OpenAICompatibleProvider {
         while retry_count < max_retries {
             match self.client.post(url)
                 .json(body)
+                .correlated()
                 .send()
+                .instrument(tracing::debug_span!("embedding_request", url = %url, attempt = retry_count + 1))
                 .await
             {
                 Ok(response) => {
@@ -143,6 +169,7 @@ impl OpenAICompatibleProvider {
 
                         return Ok(embeddings);
                     } else {
+                        let status = response.status().as_u16();
                         let error_text = response.text().await.unwrap_or_default();
                         if retry_count < max_retries - 1 {
                             tracing::warn!("嵌入请求失败，重试中... ({}/{}): {}", retry_count + 1, max_retries, error_text);
@@ -150,7 +177,7 @@ impl OpenAICompatibleProvider {
                             tokio::time::sleep(std::time::Duration::from_millis(1000 * retry_count)).await;
                             continue;
                         } else {
-                            return Err(VectorDbError::embedding_error(format!("嵌入请求失败: {}", error_text)));
+                            return Err(VectorDbError::EmbeddingStatus { status, message: error_text });
                         }
                     }
                 },
@@ -161,7 +188,7 @@ impl OpenAICompatibleProvider {
                         tokio::time::sleep(std::time::Duration::from_millis(1000 * retry_count)).await;
                         continue;
                     } else {
-                        return Err(VectorDbError::embedding_error(format!("网络请求失败: {}", e)));
+                        return Err(VectorDbError::Http(e));
                     }
                 }
             }
@@ -171,6 +198,294 @@ impl OpenAICompatibleProvider {
     }
 }
 
+/// 托管嵌入服务的接口差异（请求格式、输入类型取值、单次请求的文本上限）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostedApi {
+    /// Cohere embed v3：`texts` + `input_type`（search_document / search_query），单次最多 96 条
+    Cohere,
+    /// Jina：OpenAI 风格的 `input` + `task`（retrieval.passage / retrieval.query）
+    Jina,
+    /// Voyage：`input` + `input_type`（document / query），单次最多 128 条
+    Voyage,
+}
+
+impl HostedApi {
+    pub fn from_provider(provider: &str) -> Option<Self> {
+        match provider {
+            "cohere" => Some(Self::Cohere),
+            "jina" => Some(Self::Jina),
+            "voyage" => Some(Self::Voyage),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cohere => "cohere",
+            Self::Jina => "jina",
+            Self::Voyage => "voyage",
+        }
+    }
+
+    pub fn default_endpoint(&self) -> &'static str {
+        match self {
+            Self::Cohere => "https://api.cohere.com/v1/embed",
+            Self::Jina => "https://api.jina.ai/v1/embeddings",
+            Self::Voyage => "https://api.voyageai.com/v1/embeddings",
+        }
+    }
+
+    pub fn default_model(&self) -> &'static str {
+        match self {
+            Self::Cohere => "embed-english-v3.0",
+            Self::Jina => "jina-embeddings-v3",
+            Self::Voyage => "voyage-3",
+        }
+    }
+
+    /// 默认模型的输出维度
+    pub fn default_dimension(&self) -> usize {
+        1024
+    }
+
+    /// 单次请求的文本数上限
+    pub fn max_batch(&self) -> usize {
+        match self {
+            Self::Cohere => 96,
+            Self::Jina => 2048,
+            Self::Voyage => 128,
+        }
+    }
+
+    fn input_type(&self, input_type: InputType) -> &'static str {
+        match (self, input_type) {
+            (Self::Cohere, InputType::Passage) => "search_document",
+            (Self::Cohere, InputType::Query) => "search_query",
+            (Self::Jina, InputType::Passage) => "retrieval.passage",
+            (Self::Jina, InputType::Query) => "retrieval.query",
+            (Self::Voyage, InputType::Passage) => "document",
+            (Self::Voyage, InputType::Query) => "query",
+        }
+    }
+
+    fn request_body(&self, texts: &[String], model: &str, input_type: InputType, dimension: Option<usize>) -> serde_json::Value {
+        let input_type = self.input_type(input_type);
+        match self {
+            Self::Cohere => serde_json::json!({
+                "texts": texts,
+                "model": model,
+                "input_type": input_type,
+                "embedding_types": ["float"],
+                "truncate": "END",
+            }),
+            Self::Jina => {
+                let mut body = serde_json::json!({ "input": texts, "model": model, "task": input_type });
+                if let Some(dimension) = dimension {
+                    body["dimensions"] = serde_json::Value::from(dimension);
+                }
+                body
+            }
+            Self::Voyage => {
+                let mut body = serde_json::json!({ "input": texts, "model": model, "input_type": input_type, "truncation": true });
+                if let Some(dimension) = dimension {
+                    body["output_dimension"] = serde_json::Value::from(dimension);
+                }
+                body
+            }
+        }
+    }
+
+    /// 解析响应：Cohere 为 `embeddings.float`，Jina 和 Voyage 为带 `index` 的 `data` 数组（按 `index` 排序）
+    fn parse_response(&self, response: serde_json::Value) -> Result<Vec<Vec<f32>>> {
+        let parse_error = |e: serde_json::Error| VectorDbError::embedding_error(format!("解析 {} 响应失败: {}", self.name(), e));
+        match self {
+            Self::Cohere => serde_json::from_value(response["embeddings"]["float"].clone()).map_err(parse_error),
+            Self::Jina | Self::Voyage => {
+                let mut data: Vec<OpenAIEmbeddingData> = serde_json::from_value(response["data"].clone()).map_err(parse_error)?;
+                data.sort_by_key(|item| item.index);
+                Ok(data.into_iter().map(|item| item.embedding).collect())
+            }
+        }
+    }
+}
+
+/// 限流（429）后未给出 `Retry-After` 时的最长退避时间
+const MAX_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Cohere、Jina、Voyage 的嵌入提供商
+///
+/// 文档和查询按各服务要求的输入类型分别编码；批量请求按 [`HostedApi::max_batch`] 拆分。
+/// 收到 429 时按 `Retry-After`（没有时指数退避）等待后重试，并在等待结束前暂停该提供商的其他请求，
+/// 避免并行批次继续撞限流；重试用完后返回 [`VectorDbError::EmbeddingStatus`]，故障转移链据此切换提供商。
+pub struct HostedProvider {
+    api: HostedApi,
+    client: Client,
+    endpoint: String,
+    model: String,
+    dimension: usize,
+    /// 是否在请求中指定输出维度（配置了 `dimension` 时）
+    request_dimension: Option<usize>,
+    retry_attempts: usize,
+    /// 限流冷却结束时间，期间本提供商的请求先等待
+    cooldown_until: Mutex<Option<Instant>>,
+}
+
+impl HostedProvider {
+    pub fn new(api: HostedApi, config: &EmbeddingConfig) -> Result<Self> {
+        let api_key = config.api_key.as_deref()
+            .ok_or_else(|| VectorDbError::config_error(format!("{} 嵌入需要配置 api_key", api.name())))?;
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            reqwest::header::AUTHORIZATION,
+            reqwest::header::HeaderValue::from_str(&format!("Bearer {}", api_key))
+                .map_err(|e| VectorDbError::config_error(format!("无效的API密钥: {}", e)))?,
+        );
+        for (key, value) in &config.headers {
+            let header_name = reqwest::header::HeaderName::from_bytes(key.as_bytes())
+                .map_err(|e| VectorDbError::config_error(format!("无效的头部名称 {}: {}", key, e)))?;
+            let header_value = reqwest::header::HeaderValue::from_str(value)
+                .map_err(|e| VectorDbError::config_error(format!("无效的头部值 {}: {}", value, e)))?;
+            headers.insert(header_name, header_value);
+        }
+        let client = Client::builder()
+            .default_headers(headers)
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .map_err(|e| VectorDbError::config_error(format!("创建HTTP客户端失败: {}", e)))?;
+
+        let model = if config.model.is_empty() { api.default_model().to_string() } else { config.model.clone() };
+        Ok(Self {
+            api,
+            client,
+            endpoint: config.endpoint.clone().unwrap_or_else(|| api.default_endpoint().to_string()),
+            model,
+            dimension: config.dimension.unwrap_or_else(|| api.default_dimension()),
+            // Cohere v3 的维度由模型决定，不接受维度参数
+            request_dimension: config.dimension.filter(|_| api != HostedApi::Cohere),
+            retry_attempts: config.retry_attempts.max(1),
+            cooldown_until: Mutex::new(None),
+        })
+    }
+
+    pub fn api(&self) -> HostedApi {
+        self.api
+    }
+
+    /// 按输入类型生成嵌入，超过单次上限时分批请求
+    pub async fn embed_as(&self, texts: &[String], input_type: InputType) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.api.max_batch()) {
+            let body = self.api.request_body(batch, &self.model, input_type, self.request_dimension);
+            let batch_embeddings = self.api.parse_response(self.send(&body).await?)?;
+            if batch_embeddings.len() != batch.len() {
+                return Err(VectorDbError::embedding_error(format!(
+                    "{} 返回的嵌入数量与请求文本数量不一致: 文本 {}，嵌入 {}",
+                    self.api.name(), batch.len(), batch_embeddings.len()
+                )));
+            }
+            embeddings.extend(batch_embeddings);
+        }
+        Ok(embeddings)
+    }
+
+    async fn wait_for_cooldown(&self) {
+        let until = *self.cooldown_until.lock().unwrap();
+        if let Some(until) = until {
+            tokio::time::sleep_until(until.into()).await;
+        }
+    }
+
+    /// 进入限流冷却（已有更晚的冷却时保留）
+    fn cool_down(&self, wait: Duration) {
+        let until = Instant::now() + wait;
+        let mut cooldown = self.cooldown_until.lock().unwrap();
+        if cooldown.map_or(true, |current| current < until) {
+            *cooldown = Some(until);
+        }
+    }
+
+    async fn send(&self, body: &serde_json::Value) -> Result<serde_json::Value> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.wait_for_cooldown().await;
+            let result = self.client.post(&self.endpoint)
+                .json(body)
+                .correlated()
+                .send()
+                .instrument(tracing::debug_span!("embedding_request", provider = self.api.name(), attempt))
+                .await;
+            let mut backoff = Duration::from_millis(1000 * (1u64 << (attempt - 1).min(6)));
+            let error = match result {
+                Ok(response) if response.status().is_success() => {
+                    return response.json().await
+                        .map_err(|e| VectorDbError::embedding_error(format!("解析 {} 响应失败: {}", self.api.name(), e)));
+                }
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    let retry_after = response.headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.trim().parse::<u64>().ok())
+                        .map(Duration::from_secs);
+                    let message = response.text().await.unwrap_or_default();
+                    if status == 429 {
+                        // 冷却期间本提供商的所有请求（包括这次重试）都在发送前等待
+                        self.cool_down(retry_after.unwrap_or(backoff).min(MAX_RATE_LIMIT_BACKOFF));
+                        backoff = Duration::ZERO;
+                    } else if status < 500 {
+                        // 请求本身有误（密钥、模型名、输入过长），重试没有意义
+                        return Err(VectorDbError::EmbeddingStatus { status, message });
+                    }
+                    VectorDbError::EmbeddingStatus { status, message }
+                }
+                Err(e) => VectorDbError::Http(e),
+            };
+            if attempt >= self.retry_attempts {
+                return Err(error);
+            }
+            tracing::warn!("{} 嵌入请求失败，重试中... ({}/{}): {}", self.api.name(), attempt, self.retry_attempts, error);
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HostedProvider {
+    async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        let embeddings = self.generate_embeddings(&[text.to_string()]).await?;
+        Ok(embeddings.into_iter().next().unwrap_or_default())
+    }
+
+    async fn generate_query_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        let embeddings = self.embed_as(&[text.to_string()], InputType::Query).await?;
+        Ok(embeddings.into_iter().next().unwrap_or_default())
+    }
+
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.embed_as(texts, InputType::Passage).await
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimension
+    }
+}
+
+#[async_trait]
+impl TextEmbedder for HostedProvider {
+    fn provider(&self) -> &str {
+        self.api.name()
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String], input_type: InputType) -> anyhow::Result<Vec<Vec<f32>>> {
+        Ok(self.embed_as(texts, input_type).await?)
+    }
+}
+
 /// Mock嵌入提供商（用于测试）
 pub struct MockProvider {
     dimension: usize,
@@ -221,16 +536,657 @@ impl EmbeddingProvider for MockProvider {
     }
 }
 
-/// 创建嵌入提供商工厂函数
+#[async_trait]
+impl EmbeddingProvider for HashEmbeddingProvider {
+    async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        Ok(self.vector(text))
+    }
+
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.vector(text)).collect())
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimension()
+    }
+}
+
+/// 本地 sentence-transformer 嵌入（`local-embeddings` 特性）
+///
+/// 模型文件（`config.json`、`tokenizer.json`、`model.safetensors`）在第一次生成嵌入时才加载：`model` 是本地目录时
+/// 直接读取，否则按 Hugging Face 模型ID从缓存目录取，缓存中没有且未开启离线模式时下载到缓存目录。池化方式按
+/// sentence-transformers 的 `1_Pooling/config.json`（bge 系列为 CLS，缺省为平均池化），输出向量做 L2 归一化。
+/// 推理在阻塞线程池中用 CPU 执行，不需要 API 密钥和网络（模型已缓存时）。
+#[cfg(feature = "local-embeddings")]
+mod local {
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use candle_core::{DType, Device, IndexOp, Tensor};
+    use candle_nn::VarBuilder;
+    use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+    use hf_hub::api::tokio::{ApiBuilder, ApiRepo};
+    use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+    use tokio::sync::OnceCell;
+
+    use super::{EmbeddingProvider, DEFAULT_LOCAL_DIMENSION};
+    use crate::config::EmbeddingConfig;
+    use crate::errors::{Result, VectorDbError};
+    use crate::tools::cache_tiers::CacheTierPaths;
+    use crate::tools::embedder::{InputType, TextEmbedder};
+
+    /// 输入截断长度（BERT 类模型的位置编码上限）
+    const MAX_TOKENS: usize = 512;
+
+    /// 单次前向计算的文本数，限制填充后张量的内存占用
+    const INFERENCE_BATCH: usize = 32;
+
+    fn model_error(context: &str, e: impl std::fmt::Display) -> VectorDbError {
+        VectorDbError::embedding_error(format!("{}: {}", context, e))
+    }
+
+    /// 默认模型缓存目录：与全局向量缓存同级的 `~/.grape-mcp-devtools/models`
+    fn default_cache_dir() -> PathBuf {
+        CacheTierPaths::default_global_dir().with_file_name("models")
+    }
+
+    struct ModelFiles {
+        config: PathBuf,
+        tokenizer: PathBuf,
+        weights: PathBuf,
+        pooling: Option<PathBuf>,
+    }
+
+    impl ModelFiles {
+        fn in_dir(dir: &Path) -> Result<Self> {
+            let require = |name: &str| {
+                let path = dir.join(name);
+                if path.exists() {
+                    Ok(path)
+                } else {
+                    Err(VectorDbError::config_error(format!("模型目录 {} 缺少 {}", dir.display(), name)))
+                }
+            };
+            let pooling = dir.join("1_Pooling").join("config.json");
+            Ok(Self {
+                config: require("config.json")?,
+                tokenizer: require("tokenizer.json")?,
+                weights: require("model.safetensors")?,
+                pooling: pooling.exists().then_some(pooling),
+            })
+        }
+    }
+
+    async fn download(repo: &ApiRepo, name: &str) -> Result<PathBuf> {
+        repo.get(name).await.map_err(|e| model_error(&format!("下载模型文件 {} 失败", name), e))
+    }
+
+    struct LoadedModel {
+        model: BertModel,
+        tokenizer: Tokenizer,
+        device: Device,
+        cls_pooling: bool,
+    }
+
+    impl LoadedModel {
+        fn load(files: &ModelFiles, dimension: usize) -> Result<Self> {
+            let device = Device::Cpu;
+            let raw_config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&files.config)?)?;
+            let hidden_size = raw_config["hidden_size"].as_u64().unwrap_or_default() as usize;
+            if hidden_size != dimension {
+                return Err(VectorDbError::config_error(format!(
+                    "本地模型输出维度为 {}，与配置的嵌入维度 {} 不一致", hidden_size, dimension
+                )));
+            }
+            let config: Config = serde_json::from_value(raw_config)?;
+
+            let cls_pooling = match &files.pooling {
+                Some(path) => {
+                    let pooling: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+                    pooling["pooling_mode_cls_token"].as_bool().unwrap_or(false)
+                }
+                None => false,
+            };
+
+            // SAFETY: 权重文件位于模型缓存目录，加载和推理期间不会被修改
+            let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[files.weights.clone()], DTYPE, &device) }
+                .map_err(|e| model_error("加载模型权重失败", e))?;
+            let model = BertModel::load(vb, &config).map_err(|e| model_error("构建模型失败", e))?;
+
+            let mut tokenizer = Tokenizer::from_file(&files.tokenizer).map_err(|e| model_error("加载分词器失败", e))?;
+            tokenizer.with_padding(Some(PaddingParams::default()));
+            tokenizer
+                .with_truncation(Some(TruncationParams { max_length: MAX_TOKENS, ..Default::default() }))
+                .map_err(|e| model_error("设置分词截断失败", e))?;
+
+            Ok(Self { model, tokenizer, device, cls_pooling })
+        }
+
+        fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            let encodings = self.tokenizer.encode_batch(texts.to_vec(), true).map_err(|e| model_error("分词失败", e))?;
+            let stack = |rows: Vec<&[u32]>| -> candle_core::Result<Tensor> {
+                let rows = rows.into_iter().map(|row| Tensor::new(row, &self.device)).collect::<candle_core::Result<Vec<_>>>()?;
+                Tensor::stack(&rows, 0)
+            };
+            let infer = || -> candle_core::Result<Vec<Vec<f32>>> {
+                let input_ids = stack(encodings.iter().map(|e| e.get_ids()).collect())?;
+                let attention_mask = stack(encodings.iter().map(|e| e.get_attention_mask()).collect())?;
+                let token_type_ids = input_ids.zeros_like()?;
+                let hidden = self.model.forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+                let pooled = if self.cls_pooling {
+                    hidden.i((.., 0))?
+                } else {
+                    // 按注意力掩码平均，忽略填充位置
+                    let mask = attention_mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+                    hidden.broadcast_mul(&mask)?.sum(1)?.broadcast_div(&mask.sum(1)?)?
+                };
+                let norms = pooled.sqr()?.sum_keepdim(1)?.sqrt()?.clamp(1e-12f32, f32::MAX)?;
+                pooled.broadcast_div(&norms)?.to_vec2::<f32>()
+            };
+            infer().map_err(|e| model_error("本地模型推理失败", e))
+        }
+    }
+
+    /// 本地 sentence-transformer 嵌入提供商
+    pub struct LocalProvider {
+        model_id: String,
+        dimension: usize,
+        cache_dir: PathBuf,
+        offline: bool,
+        model: OnceCell<Arc<LoadedModel>>,
+    }
+
+    impl LocalProvider {
+        /// 按配置创建，不加载模型；`offline` 或环境变量 `HF_HUB_OFFLINE=1` 时不下载
+        pub fn new(config: &EmbeddingConfig) -> Self {
+            Self {
+                model_id: config.model.clone(),
+                dimension: config.dimension.unwrap_or(DEFAULT_LOCAL_DIMENSION),
+                cache_dir: config.model_cache_dir.clone().unwrap_or_else(default_cache_dir),
+                offline: config.offline || std::env::var("HF_HUB_OFFLINE").is_ok_and(|v| v == "1"),
+                model: OnceCell::new(),
+            }
+        }
+
+        /// 模型是否已加载
+        pub fn is_loaded(&self) -> bool {
+            self.model.initialized()
+        }
+
+        async fn loaded(&self) -> Result<Arc<LoadedModel>> {
+            self.model
+                .get_or_try_init(|| async {
+                    let files = self.resolve_files().await?;
+                    let dimension = self.dimension;
+                    let model = tokio::task::spawn_blocking(move || LoadedModel::load(&files, dimension)).await??;
+                    tracing::info!("本地嵌入模型已加载: {}", self.model_id);
+                    Ok(Arc::new(model))
+                })
+                .await
+                .cloned()
+        }
+
+        async fn resolve_files(&self) -> Result<ModelFiles> {
+            let local_dir = Path::new(&self.model_id);
+            if local_dir.is_dir() {
+                return ModelFiles::in_dir(local_dir);
+            }
+
+            if self.offline {
+                let repo = hf_hub::Cache::new(self.cache_dir.clone()).model(self.model_id.clone());
+                let cached = |name: &str| {
+                    repo.get(name).ok_or_else(|| {
+                        VectorDbError::config_error(format!(
+                            "离线模式下缓存目录 {} 中没有模型文件 {}/{}，请先联网加载一次或将 model 配置为本地模型目录",
+                            self.cache_dir.display(),
+                            self.model_id,
+                            name
+                        ))
+                    })
+                };
+                return Ok(ModelFiles {
+                    config: cached("config.json")?,
+                    tokenizer: cached("tokenizer.json")?,
+                    weights: cached("model.safetensors")?,
+                    pooling: repo.get("1_Pooling/config.json"),
+                });
+            }
+
+            tracing::info!("获取本地嵌入模型 {}（缓存目录 {}）", self.model_id, self.cache_dir.display());
+            let api = ApiBuilder::new()
+                .with_cache_dir(self.cache_dir.clone())
+                .with_progress(false)
+                .build()
+                .map_err(|e| model_error("创建模型下载客户端失败", e))?;
+            let repo = api.model(self.model_id.clone());
+            Ok(ModelFiles {
+                config: download(&repo, "config.json").await?,
+                tokenizer: download(&repo, "tokenizer.json").await?,
+                weights: download(&repo, "model.safetensors").await?,
+                pooling: download(&repo, "1_Pooling/config.json").await.ok(),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for LocalProvider {
+        async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+            let embeddings = self.generate_embeddings(&[text.to_string()]).await?;
+            Ok(embeddings.into_iter().next().unwrap_or_default())
+        }
+
+        async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            if texts.is_empty() {
+                return Ok(vec![]);
+            }
+            let model = self.loaded().await?;
+            let texts = texts.to_vec();
+            tokio::task::spawn_blocking(move || {
+                let mut vectors = Vec::with_capacity(texts.len());
+                for chunk in texts.chunks(INFERENCE_BATCH) {
+                    vectors.extend(model.embed(chunk)?);
+                }
+                Ok(vectors)
+            })
+            .await?
+        }
+
+        fn dimensions(&self) -> usize {
+            self.dimension
+        }
+    }
+
+    #[async_trait]
+    impl TextEmbedder for LocalProvider {
+        fn provider(&self) -> &str {
+            "local"
+        }
+
+        fn model_name(&self) -> &str {
+            &self.model_id
+        }
+
+        async fn embed(&self, texts: &[String], _input_type: InputType) -> anyhow::Result<Vec<Vec<f32>>> {
+            Ok(self.generate_embeddings(texts).await?)
+        }
+    }
+}
+
+/// 故障转移链中单个提供商的统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingProviderMetrics {
+    /// 提供商（`provider/model`）
+    pub provider: String,
+    pub requests: u64,
+    pub successes: u64,
+    pub failures: u64,
+    /// 熔断期间被跳过的请求数
+    pub skipped: u64,
+    /// 进入熔断的次数
+    pub circuit_opens: u64,
+    /// 当前是否处于熔断状态
+    pub circuit_open: bool,
+    pub average_latency_ms: f64,
+}
+
+struct BreakerState {
+    metrics: EmbeddingProviderMetrics,
+    total_latency_ms: f64,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+struct ChainMember {
+    provider: Box<dyn EmbeddingProvider>,
+    state: Mutex<BreakerState>,
+}
+
+impl ChainMember {
+    fn name(&self) -> String {
+        self.state.lock().unwrap().metrics.provider.clone()
+    }
+
+    /// 是否放行请求：未熔断时放行；熔断到期后放行一次试探请求（期间其他请求仍被跳过）
+    fn admit(&self, open_for: Duration) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.open_until {
+            Some(until) if Instant::now() < until => {
+                state.metrics.skipped += 1;
+                false
+            }
+            Some(_) => {
+                state.open_until = Some(Instant::now() + open_for);
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// 记录一次请求结果；`trips_breaker` 为 true 的失败计入连续失败次数
+    fn record(&self, latency_ms: f64, outcome: std::result::Result<(), bool>, breaker: &CircuitBreakerConfig) {
+        let mut state = self.state.lock().unwrap();
+        state.metrics.requests += 1;
+        state.total_latency_ms += latency_ms;
+        state.metrics.average_latency_ms = state.total_latency_ms / state.metrics.requests as f64;
+        match outcome {
+            Ok(()) => {
+                state.metrics.successes += 1;
+                state.consecutive_failures = 0;
+                state.open_until = None;
+            }
+            Err(trips_breaker) => {
+                state.metrics.failures += 1;
+                if !trips_breaker {
+                    return;
+                }
+                state.consecutive_failures += 1;
+                if state.consecutive_failures >= breaker.failure_threshold.max(1) {
+                    if state.open_until.is_none() {
+                        state.metrics.circuit_opens += 1;
+                    }
+                    state.open_until = Some(Instant::now() + Duration::from_secs(breaker.open_seconds));
+                }
+            }
+        }
+    }
+
+    fn metrics(&self) -> EmbeddingProviderMetrics {
+        let state = self.state.lock().unwrap();
+        let mut metrics = state.metrics.clone();
+        metrics.circuit_open = state.open_until.is_some_and(|until| Instant::now() < until);
+        metrics
+    }
+}
+
+/// 按顺序故障转移的嵌入提供商
+///
+/// 依次尝试链中的提供商：限流（429）、服务端错误（5xx）或网络故障时转到下一个，其他错误（如请求参数错误）
+/// 直接返回。连续失败达到阈值的提供商熔断一段时间，期间直接跳过，到期后放行一次试探请求，成功即恢复。
+pub struct FailoverProvider {
+    members: Vec<ChainMember>,
+    breaker: CircuitBreakerConfig,
+}
+
+impl FailoverProvider {
+    /// 按顺序（主提供商在前）创建，各提供商的嵌入维度必须一致
+    pub fn new(providers: Vec<(String, Box<dyn EmbeddingProvider>)>, breaker: CircuitBreakerConfig) -> Result<Self> {
+        let dimension = providers.first()
+            .map(|(_, provider)| provider.dimensions())
+            .ok_or_else(|| VectorDbError::config_error("故障转移链至少需要一个嵌入提供商".to_string()))?;
+        if let Some((name, provider)) = providers.iter().find(|(_, provider)| provider.dimensions() != dimension) {
+            return Err(VectorDbError::config_error(format!(
+                "嵌入提供商 {} 的维度 {} 与主提供商的维度 {} 不一致", name, provider.dimensions(), dimension
+            )));
+        }
+        let members = providers.into_iter()
+            .map(|(name, provider)| ChainMember {
+                provider,
+                state: Mutex::new(BreakerState {
+                    metrics: EmbeddingProviderMetrics { provider: name, ..Default::default() },
+                    total_latency_ms: 0.0,
+                    consecutive_failures: 0,
+                    open_until: None,
+                }),
+            })
+            .collect();
+        Ok(Self { members, breaker })
+    }
+
+    /// 按顺序把请求交给第一个未熔断的提供商，可转移的失败换下一个
+    async fn first_available<'a, T, F, Fut>(&'a self, request: F) -> Result<T>
+    where
+        F: Fn(&'a dyn EmbeddingProvider) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let open_for = Duration::from_secs(self.breaker.open_seconds);
+        let mut last_error = None;
+        for member in &self.members {
+            if !member.admit(open_for) {
+                continue;
+            }
+            let started = Instant::now();
+            let result = request(&*member.provider).await;
+            let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+            match result {
+                Ok(embeddings) => {
+                    member.record(latency_ms, Ok(()), &self.breaker);
+                    return Ok(embeddings);
+                }
+                Err(e) if e.is_embedding_failover() => {
+                    member.record(latency_ms, Err(true), &self.breaker);
+                    tracing::warn!("嵌入提供商 {} 不可用，转移到下一个: {}", member.name(), e);
+                    last_error = Some(e);
+                }
+                Err(e) => {
+                    member.record(latency_ms, Err(false), &self.breaker);
+                    return Err(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| VectorDbError::embedding_error("所有嵌入提供商均处于熔断状态".to_string())))
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for FailoverProvider {
+    async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        let embeddings = self.generate_embeddings(&[text.to_string()]).await?;
+        Ok(embeddings.into_iter().next().unwrap_or_default())
+    }
+
+    async fn generate_query_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.first_available(|provider| provider.generate_query_embedding(text)).await
+    }
+
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.first_available(|provider| provider.generate_embeddings(texts)).await
+    }
+
+    fn dimensions(&self) -> usize {
+        self.members[0].provider.dimensions()
+    }
+
+    fn provider_metrics(&self) -> Vec<EmbeddingProviderMetrics> {
+        self.members.iter().map(ChainMember::metrics).collect()
+    }
+}
+
+/// 把内层提供商的向量截断到前若干维并重新归一化（Matryoshka）
+pub struct TruncatedProvider {
+    inner: Box<dyn EmbeddingProvider>,
+    dimension: usize,
+}
+
+impl TruncatedProvider {
+    /// 截断维度必须在 1 和内层提供商的维度之间
+    pub fn new(inner: Box<dyn EmbeddingProvider>, dimension: usize) -> Result<Self> {
+        if dimension == 0 || dimension > inner.dimensions() {
+            return Err(VectorDbError::config_error(format!(
+                "截断维度 {} 无效，应在 1 到模型维度 {} 之间",
+                dimension,
+                inner.dimensions()
+            )));
+        }
+        Ok(Self { inner, dimension })
+    }
+
+    fn truncate(&self, embedding: Vec<f32>) -> Result<Vec<f32>> {
+        if embedding.len() < self.dimension {
+            return Err(VectorDbError::InvalidVectorDimension { expected: self.dimension, actual: embedding.len() });
+        }
+        Ok(truncate_embedding(embedding, self.dimension))
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for TruncatedProvider {
+    async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.truncate(self.inner.generate_embedding(text).await?)
+    }
+
+    async fn generate_query_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.truncate(self.inner.generate_query_embedding(text).await?)
+    }
+
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.inner.generate_embeddings(texts).await?.into_iter().map(|embedding| self.truncate(embedding)).collect()
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimension
+    }
+
+    fn provider_metrics(&self) -> Vec<EmbeddingProviderMetrics> {
+        self.inner.provider_metrics()
+    }
+}
+
+/// 创建嵌入提供商工厂函数；配置了 `fallbacks` 时返回 [`FailoverProvider`]，
+/// 配置了 `truncate_dimension` 时外层再包一层 [`TruncatedProvider`]
 pub fn create_embedding_provider(config: &EmbeddingConfig) -> Result<Box<dyn EmbeddingProvider>> {
+    let provider: Box<dyn EmbeddingProvider> = if config.fallbacks.is_empty() {
+        create_single_provider(config)?
+    } else {
+        let providers = std::iter::once(config)
+            .chain(&config.fallbacks)
+            .map(|member| Ok((format!("{}/{}", member.provider, member.model), create_single_provider(member)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Box::new(FailoverProvider::new(providers, config.circuit_breaker.clone())?)
+    };
+    match config.truncate_dimension {
+        Some(dimension) if dimension != provider.dimensions() => Ok(Box::new(TruncatedProvider::new(provider, dimension)?)),
+        _ => Ok(provider),
+    }
+}
+
+fn create_single_provider(config: &EmbeddingConfig) -> Result<Box<dyn EmbeddingProvider>> {
     match config.provider.as_str() {
         "openai" | "azure" | "ollama" | "nvidia" | "huggingface" => {
             Ok(Box::new(OpenAICompatibleProvider::new(config.clone())?))
         },
+        #[cfg(feature = "local-embeddings")]
+        "local" => Ok(Box::new(LocalProvider::new(config))),
+        #[cfg(not(feature = "local-embeddings"))]
+        "local" => Err(VectorDbError::config_error("本地嵌入需要启用 local-embeddings 特性编译".to_string())),
+        "cohere" | "jina" | "voyage" => {
+            let api = HostedApi::from_provider(&config.provider).expect("已匹配的托管嵌入服务");
+            Ok(Box::new(HostedProvider::new(api, config)?))
+        },
         "mock" => {
             let dimension = config.dimension.unwrap_or(1536);
             Ok(Box::new(MockProvider::new(dimension)))
         },
+        "hash" => {
+            let dimension = config.dimension.unwrap_or(DEFAULT_HASH_DIMENSION);
+            Ok(Box::new(HashEmbeddingProvider::new(dimension)))
+        },
         _ => Err(VectorDbError::config_error(format!("不支持的嵌入提供商: {}", config.provider)))
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// 按预设状态码失败的提供商
+    struct FailingProvider {
+        status: u16,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for FailingProvider {
+        async fn generate_embedding(&self, _text: &str) -> Result<Vec<f32>> {
+            unreachable!()
+        }
+
+        async fn generate_embeddings(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(VectorDbError::EmbeddingStatus { status: self.status, message: "unavailable".to_string() })
+        }
+
+        fn dimensions(&self) -> usize {
+            8
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover_chain_trips_circuit_breaker() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let chain = FailoverProvider::new(
+            vec![
+                ("nvidia".to_string(), Box::new(FailingProvider { status: 503, calls: calls.clone() }) as Box<dyn EmbeddingProvider>),
+                ("mock".to_string(), Box::new(MockProvider::new(8))),
+            ],
+            CircuitBreakerConfig { failure_threshold: 2, open_seconds: 60 },
+        )
+        .unwrap();
+
+        for _ in 0..4 {
+            assert_eq!(chain.generate_embedding("tokio spawn").await.unwrap().len(), 8);
+        }
+        // 两次失败后熔断，后续请求不再调用主提供商
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let metrics = chain.provider_metrics();
+        assert_eq!((metrics[0].failures, metrics[0].skipped, metrics[0].circuit_opens), (2, 2, 1));
+        assert!(metrics[0].circuit_open);
+        assert_eq!(metrics[1].successes, 4);
+
+        // 4xx 不转移
+        let chain = FailoverProvider::new(
+            vec![
+                ("bad".to_string(), Box::new(FailingProvider { status: 400, calls }) as Box<dyn EmbeddingProvider>),
+                ("mock".to_string(), Box::new(MockProvider::new(8))),
+            ],
+            CircuitBreakerConfig::default(),
+        )
+        .unwrap();
+        assert!(chain.generate_embedding("x").await.is_err());
+        assert!(FailoverProvider::new(
+            vec![("mock".to_string(), Box::new(MockProvider::new(16)) as Box<dyn EmbeddingProvider>), ("hash".to_string(), Box::new(HashEmbeddingProvider::new(8)))],
+            CircuitBreakerConfig::default(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_hosted_api_request_and_response_formats() {
+        let texts = vec!["tokio spawn".to_string(), "异步任务".to_string()];
+
+        let cohere = HostedApi::Cohere.request_body(&texts, "embed-english-v3.0", InputType::Query, None);
+        assert_eq!(cohere["texts"][1], "异步任务");
+        assert_eq!(cohere["input_type"], "search_query");
+        let response = serde_json::json!({ "embeddings": { "float": [[0.1, 0.2], [0.3, 0.4]] } });
+        assert_eq!(HostedApi::Cohere.parse_response(response).unwrap()[1], vec![0.3, 0.4]);
+
+        let jina = HostedApi::Jina.request_body(&texts, "jina-embeddings-v3", InputType::Passage, Some(512));
+        assert_eq!((jina["task"].as_str(), jina["dimensions"].as_u64()), (Some("retrieval.passage"), Some(512)));
+        let voyage = HostedApi::Voyage.request_body(&texts, "voyage-3", InputType::Passage, None);
+        assert_eq!(voyage["input_type"], "document");
+        assert!(voyage.get("output_dimension").is_none());
+
+        // 按 index 还原输入顺序
+        let response = serde_json::json!({ "data": [
+            { "embedding": [0.3, 0.4], "index": 1 },
+            { "embedding": [0.1, 0.2], "index": 0 },
+        ] });
+        assert_eq!(HostedApi::Voyage.parse_response(response).unwrap(), vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+        assert!(HostedProvider::new(HostedApi::Jina, &EmbeddingConfig { provider: "jina".to_string(), ..Default::default() }).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_truncate_dimension_wraps_provider() {
+        let config = EmbeddingConfig { provider: "hash".to_string(), dimension: Some(128), truncate_dimension: Some(32), ..Default::default() };
+        let provider = create_embedding_provider(&config).unwrap();
+        assert_eq!(provider.dimensions(), 32);
+        let embeddings = provider.generate_embeddings(&["tokio spawn task".to_string()]).await.unwrap();
+        assert_eq!(embeddings[0].len(), 32);
+        let norm: f32 = embeddings[0].iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+
+        let invalid = EmbeddingConfig { truncate_dimension: Some(256), ..config };
+        assert!(create_embedding_provider(&invalid).is_err());
+    }
+}
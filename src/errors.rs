@@ -1,5 +1,6 @@
 use thiserror::Error;
 use anyhow;
+use serde::Serialize;
 
 pub type MCPResult<T> = anyhow::Result<T>;
 pub type DocGenResult<T> = anyhow::Result<T>;
@@ -54,6 +55,78 @@ pub enum MCPError {
 
     #[error("工具未找到: {0}")]
     ToolNotFound(String),
+
+    #[error("{provider} 请求失败: {message}")]
+    ProviderError {
+        /// 出错的外部服务，如 `embedding`、`stackexchange`
+        provider: String,
+        /// HTTP 状态码
+        status: Option<u16>,
+        /// 服务端要求的重试等待秒数（`Retry-After`）
+        retry_after_secs: Option<u64>,
+        message: String,
+    },
+}
+
+/// 错误响应 `data` 字段中的结构化信息，供客户端据此自行修正请求
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorPayload {
+    /// 错误类别，与 [`MCPError::error_code`] 一致
+    pub kind: String,
+    pub recoverable: bool,
+    /// 出错的外部服务
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+    /// 建议的修复方式，如“设置 EMBEDDING_API_KEY 环境变量”
+    pub suggested_fix: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub resolution_steps: Vec<String>,
+}
+
+impl ErrorPayload {
+    /// 无法归类的内部错误
+    pub fn internal() -> Self {
+        Self {
+            kind: "INTERNAL_ERROR".to_string(),
+            recoverable: false,
+            provider: None,
+            retry_after_secs: None,
+            suggested_fix: "请稍后重试，如果问题持续存在请查看服务器日志".to_string(),
+            resolution_steps: Vec::new(),
+        }
+    }
+
+    /// 从错误链中找到 `MCPError` 生成结构化信息，找不到时视为内部错误
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        find_mcp_error(err).map_or_else(Self::internal, MCPError::payload)
+    }
+}
+
+/// 在错误链（包括 `context` 包装）中查找 `MCPError`
+pub fn find_mcp_error(err: &anyhow::Error) -> Option<&MCPError> {
+    err.chain().find_map(|cause| cause.downcast_ref::<MCPError>())
+}
+
+/// 包装为带上下文的服务器错误；错误链中已有 `MCPError`（如外部服务错误）时原样保留，
+/// 以便错误响应带上准确的类别和修复建议
+pub fn server_error(context: &str, err: anyhow::Error) -> anyhow::Error {
+    match err.downcast::<MCPError>() {
+        Ok(mcp_error) => mcp_error.into(),
+        Err(err) => MCPError::ServerError(format!("{}: {}", context, err)).into(),
+    }
+}
+
+/// 外部服务对应的凭据环境变量
+fn provider_credential_env(provider: &str) -> Option<&'static str> {
+    match provider {
+        "embedding" => Some("EMBEDDING_API_KEY"),
+        "llm" => Some("LLM_API_KEY"),
+        "stackexchange" => Some("STACKEXCHANGE_KEY"),
+        "github" => Some("GITHUB_TOKEN"),
+        _ => None,
+    }
 }
 
 #[derive(Error, Debug)]
@@ -102,6 +175,9 @@ impl MCPError {
             MCPError::DocumentationError(_) => "DOCUMENTATION_ERROR",
             MCPError::ToolExecutionFailed(_) => "TOOL_EXECUTION_FAILED",
             MCPError::ToolNotFound(_) => "TOOL_NOT_FOUND",
+            MCPError::ProviderError { status: Some(401 | 403), .. } => "PROVIDER_AUTH_ERROR",
+            MCPError::ProviderError { status: Some(429), .. } => "RATE_LIMIT",
+            MCPError::ProviderError { .. } => "PROVIDER_ERROR",
         }
     }
 
@@ -123,6 +199,9 @@ impl MCPError {
             MCPError::DocumentationError(_) => "文档生成失败，请检查源文件和配置",
             MCPError::ToolExecutionFailed(_) => "工具执行失败，请检查工具和配置",
             MCPError::ToolNotFound(_) => "工具未找到，请检查工具路径和名称",
+            MCPError::ProviderError { status: Some(401 | 403), .. } => "外部服务拒绝了凭据，请检查对应的API密钥",
+            MCPError::ProviderError { status: Some(429), .. } => "外部服务限流，请等待后重试",
+            MCPError::ProviderError { .. } => "外部服务暂时不可用，请稍后重试",
         }
     }
 
@@ -151,6 +230,7 @@ impl MCPError {
             MCPError::Timeout(_) |
             MCPError::RateLimitError(_) |
             MCPError::CacheError(_) => true,
+            MCPError::ProviderError { status, .. } => !matches!(status, Some(401 | 403)),
             _ => false,
         }
     }
@@ -176,6 +256,51 @@ impl MCPError {
             _ => vec![self.suggestion().to_string()],
         }
     }
+
+    /// 外部服务返回非成功状态时的错误，带上状态码和 `Retry-After`
+    pub async fn from_provider_response(provider: &str, response: reqwest::Response) -> Self {
+        let status = response.status();
+        let retry_after_secs = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok());
+        let body = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        MCPError::ProviderError {
+            provider: provider.to_string(),
+            status: Some(status.as_u16()),
+            retry_after_secs,
+            message: format!("{} {}", status, body),
+        }
+    }
+
+    /// 生成错误响应中的结构化信息
+    pub fn payload(&self) -> ErrorPayload {
+        let (provider, retry_after_secs) = match self {
+            MCPError::ProviderError { provider, retry_after_secs, .. } => (Some(provider.clone()), *retry_after_secs),
+            _ => (None, None),
+        };
+        let suggested_fix = match self {
+            MCPError::ProviderError { provider, status: Some(401 | 403), .. } => match provider_credential_env(provider) {
+                Some(env) => format!("设置有效的 {} 环境变量后重启服务器", env),
+                None => self.suggestion().to_string(),
+            },
+            MCPError::ProviderError { retry_after_secs: Some(secs), .. } => format!("等待 {} 秒后重试", secs),
+            _ => self.suggestion().to_string(),
+        };
+        let resolution_steps = match self {
+            MCPError::InvalidVersion(_) | MCPError::UnsupportedLanguage(_) | MCPError::ChangelogParseError(_) => self.resolution_steps(),
+            _ => Vec::new(),
+        };
+        ErrorPayload {
+            kind: self.error_code().to_string(),
+            recoverable: self.is_recoverable(),
+            provider,
+            retry_after_secs,
+            suggested_fix,
+            resolution_steps,
+        }
+    }
 }
 
 /// 向量数据库错误类型
@@ -189,6 +314,9 @@ pub enum VectorDbError {
     
     #[error("嵌入错误: {0}")]
     Embedding(String),
+
+    #[error("嵌入服务返回 HTTP {status}: {message}")]
+    EmbeddingStatus { status: u16, message: String },
     
     #[error("配置错误: {0}")]
     Config(String),
@@ -198,6 +326,9 @@ pub enum VectorDbError {
     
     #[error("无效的向量维度: 期望 {expected}, 实际 {actual}")]
     InvalidVectorDimension { expected: usize, actual: usize },
+
+    #[error("库中向量由嵌入模型 {stored_model}（{stored_dimension} 维）生成，与当前配置的 {model}（{dimension} 维）不一致，请先运行 reembed_all 重新生成向量")]
+    EmbeddingModelMismatch { stored_model: String, stored_dimension: usize, model: String, dimension: usize },
     
     #[error("I/O 错误: {0}")]
     Io(#[from] std::io::Error),
@@ -217,6 +348,9 @@ pub enum VectorDbError {
     #[error("任务错误: {0}")]
     Task(#[from] tokio::task::JoinError),
     
+    #[error("集合错误: {0}")]
+    Collection(String),
+    
     #[error("其他错误: {0}")]
     Other(String),
 }
@@ -245,4 +379,13 @@ impl VectorDbError {
     pub fn other_error(msg: String) -> Self {
         Self::Other(msg)
     }
+
+    /// 嵌入失败是否应转移到备用提供商：限流（429）、服务端错误（5xx）和网络故障
+    pub fn is_embedding_failover(&self) -> bool {
+        match self {
+            Self::EmbeddingStatus { status, .. } => *status == 429 || *status >= 500,
+            Self::Http(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            _ => false,
+        }
+    }
 }
@@ -33,7 +33,7 @@ pub fn search(&self, query: &[f32], k: usize) -> Result<Vec<SearchResult>> {
         results.push(SearchResult {
             document_id: point.document_id.clone(),
             distance: item.distance,
-            similarity: 1.0 / (1.0 + item.distance), // 转换为相似度分数
+            similarity: self.metric.similarity(item.distance), // 按索引的距离度量换算相似度分数
         });
     }
 
@@ -15,8 +15,8 @@
 //! use grape_mcp_devtools::*;
 //! 
 //! #[tokio::main]
-//! async fn main() -> Result<(), MCPError> {
-//!     // 创建MCP服务器
+//! async fn main() -> anyhow::Result<()> {
+//!     // 使用默认配置创建MCP服务器
 //!     let server = mcp::create_server().await?;
 //!     
 //!     // 启动服务器
@@ -25,9 +25,27 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! 需要自定义组装时使用 [`mcp::GrapeServerBuilder`]:
+//!
+//! ```rust,no_run
+//! use grape_mcp_devtools::mcp::GrapeServerBuilder;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let server = GrapeServerBuilder::new()
+//!     .with_data_dir("/tmp/grape_cache".into())
+//!     .with_background_caching(None)
+//!     .with_upgrade_check(false)
+//!     .build()
+//!     .await?;
+//! server.run().await?;
+//! # Ok(())
+//! # }
+//! ```
 
 pub mod errors;
 pub mod mcp;
+pub mod client;
 pub mod tools;
 pub mod versioning;
 pub mod cli;
@@ -40,6 +58,7 @@ pub mod index;
 pub mod query;
 pub mod metrics;
 pub mod embeddings;
+pub mod vectorization;
 
 // 新增：智能MCP服务器模块（同进程多Agent架构）
 // pub mod intelligent_mcp_server;
@@ -49,7 +68,8 @@ pub use config::*;
 pub use types::*;
 pub use storage::*;
 pub use index::HnswIndex;
-pub use query::{QueryEngine, IndexStats as QueryIndexStats};
+pub use query::{QueryEngine, SearchResultBatches, IndexStats as QueryIndexStats};
+pub use tools::search_filter::SearchFilter;
 pub use metrics::*;
 pub use embeddings::*;
 pub use errors::*;
@@ -57,19 +77,180 @@ pub use errors::*;
 // 明确指定SearchResult类型，避免冲突
 pub use types::SearchResult;
 
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// 集合名规则和目录布局与 vector_docs 工具一致：默认集合的数据直接位于数据目录下，
+// 其他集合位于 `<数据目录>/collections/<集合名>`
+pub use tools::vector_docs_tool::DEFAULT_COLLECTION;
+use tools::vector_docs_tool::{is_valid_collection_name, COLLECTIONS_DIR};
+pub use tools::snapshot::SnapshotReport;
+use tools::snapshot::{Snapshot, StoreSnapshot, COLLECTION_STORE_PREFIX};
+use tools::cache_eviction::{select_eviction_victims, PackageUsage};
+use futures::{StreamExt, TryStreamExt};
+
+/// 集合：独立的存储和索引，不同项目的文档互不影响搜索结果
+struct Collection {
+    storage: Box<dyn VectorStore>,
+    query_engine: QueryEngine,
+    history: Mutex<RevisionHistory>,
+}
+
+impl Collection {
+    async fn open(data_dir: PathBuf, config: &VectorDbConfig, metrics: Arc<MetricsCollector>) -> Result<Self> {
+        Ok(Self {
+            history: Mutex::new(RevisionHistory::open(&data_dir, config.max_revisions)?),
+            storage: Box::new(SledVectorStore::new(data_dir, config).await?),
+            query_engine: QueryEngine::new(config, metrics)?,
+        })
+    }
+}
+
+/// 集合信息
+#[derive(Debug, Clone)]
+pub struct CollectionInfo {
+    pub name: String,
+    pub stats: DatabaseStats,
+    pub index: QueryIndexStats,
+}
+
+/// 一次保留清理（见 [`RetentionConfig`]）的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    /// 过期删除的文档数
+    pub expired_documents: usize,
+    /// 因容量上限淘汰的文档数
+    pub evicted_documents: usize,
+    /// 淘汰释放的字节数
+    pub evicted_bytes: u64,
+}
+
+/// 一次后台维护（见 [`MaintenanceConfig`]）的结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaintenanceReport {
+    /// 压缩了存储的集合数
+    pub collections_compacted: usize,
+    /// 重建了索引的集合数
+    pub indexes_rebuilt: usize,
+    /// 重建索引时清除的已删除向量数
+    pub pruned_vectors: usize,
+    /// 时间预算用完，剩余集合留到下次
+    pub budget_exhausted: bool,
+    /// 耗时（毫秒）
+    pub duration_ms: f64,
+}
+
+/// 一次重新嵌入（见 [`VectorDatabase::reembed_all`]）的结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReembedReport {
+    /// 重新嵌入前库中向量的模型和维度
+    pub previous_model: String,
+    pub previous_dimension: usize,
+    pub model: String,
+    pub dimension: usize,
+    /// 处理的集合数
+    pub collections: usize,
+    /// 重新嵌入的文档数（不含分块记录）
+    pub documents: usize,
+    /// 重新生成的分块记录数
+    pub chunks: usize,
+    /// 耗时（毫秒）
+    pub duration_ms: f64,
+}
+
+/// 按修订搜索时结果元数据中的修订号
+pub const REVISION_METADATA_KEY: &str = "revision";
+
+/// 分块记录元数据中的父文档ID
+pub const PARENT_ID_METADATA_KEY: &str = "parent_id";
+
+/// 分块记录元数据中的块序号；搜索结果归并到父文档后标明命中的块
+pub const CHUNK_INDEX_METADATA_KEY: &str = "chunk_index";
+
+/// 被切分的父文档（及其各块）元数据中的块数
+pub const CHUNK_COUNT_METADATA_KEY: &str = "chunk_count";
+
+/// 启用分块时搜索多取的候选倍数，归并同一父文档的命中后仍能凑满结果数
+const CHUNK_SEARCH_OVERFETCH: usize = 3;
+
+/// 分块记录的文档ID
+pub fn chunk_id(parent_id: &str, index: usize) -> String {
+    format!("{}#chunk-{}", parent_id, index)
+}
+
+/// 把分块命中归并到父文档：结果已按得分降序，同一父文档只保留第一条（文档ID换成父文档ID，
+/// 元数据的 `chunk_index` 标明命中的块），最多返回 `limit` 条
+pub fn collapse_chunks(results: Vec<SearchResult>, limit: usize) -> Vec<SearchResult> {
+    let mut seen = HashSet::new();
+    results.into_iter()
+        .filter_map(|mut result| {
+            if let Some(parent_id) = result.metadata.get(PARENT_ID_METADATA_KEY) {
+                result.document_id = parent_id.clone();
+            }
+            seen.insert(result.document_id.clone()).then_some(result)
+        })
+        .take(limit)
+        .collect()
+}
+
+/// 各块向量的均值，作为父文档的向量
+fn mean_embedding(embeddings: &[Vec<f32>]) -> Vec<f32> {
+    let dimension = embeddings.first().map_or(0, Vec::len);
+    let mut mean = vec![0.0f32; dimension];
+    for embedding in embeddings {
+        mean.iter_mut().zip(embedding).for_each(|(m, v)| *m += v);
+    }
+    mean.iter_mut().for_each(|m| *m /= embeddings.len() as f32);
+    mean
+}
+
+/// 按修订搜索时结果摘要的最大字符数
+const REVISION_SNIPPET_CHARS: usize = 200;
+
+/// 估算文档占用的字节数（内容、标题、元数据和向量）
+fn record_bytes(record: &DocumentRecord) -> u64 {
+    let metadata_bytes: usize = record.metadata.iter().map(|(k, v)| k.len() + v.len()).sum();
+    (record.id.len()
+        + record.title.len()
+        + record.content.len()
+        + metadata_bytes
+        + record.embedding.len() * std::mem::size_of::<f32>()) as u64
+}
+
+/// 集合名只允许字母、数字、`-` 和 `_`，最长 64 个字符
+pub fn validate_collection_name(name: &str) -> Result<()> {
+    if is_valid_collection_name(name) {
+        Ok(())
+    } else {
+        Err(VectorDbError::Collection(format!("无效的集合名: {:?}（只允许字母、数字、- 和 _，最长 64 个字符）", name)))
+    }
+}
 
 /// 向量数据库主结构
 pub struct VectorDatabase {
+    data_dir: PathBuf,
+    /// 默认集合
     storage: Box<dyn VectorStore>,
     query_engine: QueryEngine,
+    /// 默认集合的文档修订历史
+    history: Mutex<RevisionHistory>,
+    /// 其他集合，按名称排序
+    collections: BTreeMap<String, Collection>,
     metrics: Arc<MetricsCollector>,
     config: VectorDbConfig,
+    /// 嵌入提供商，在实例生命周期内复用（故障转移链的熔断状态和统计跨请求保留）
+    embedding_provider: Box<dyn EmbeddingProvider>,
+    /// 库中向量的嵌入模型和维度，与配置不一致时拒绝写入，直到 [`Self::reembed_all`] 完成
+    embedding_meta: Mutex<EmbeddingMeta>,
+    /// 文档访问记录（集合名 -> 文档ID -> 访问情况），用于 LRU 淘汰，不持久化
+    access: Mutex<HashMap<String, HashMap<String, PackageUsage>>>,
+    /// 上次重建索引后各集合从索引删除（含更新替换）的向量数，后台维护据此决定是否重建
+    deletions: Mutex<HashMap<String, usize>>,
 }
 
 impl VectorDatabase {
-    /// 创建新的向量数据库实例
+    /// 创建新的向量数据库实例，并打开数据目录下已有的集合
     pub async fn new(data_dir: PathBuf, config: VectorDbConfig) -> Result<Self> {
         let metrics = Arc::new(MetricsCollector::new());
         
@@ -78,15 +259,177 @@ impl VectorDatabase {
         
         // 创建查询引擎
         let query_engine = QueryEngine::new(&config, metrics.clone())?;
+        let history = Mutex::new(RevisionHistory::open(&data_dir, config.max_revisions)?);
+        let embedding_provider = create_embedding_provider(&config.embedding)?;
+
+        let mut collections = BTreeMap::new();
+        for name in Self::existing_collections(&data_dir)? {
+            let collection = Collection::open(data_dir.join(COLLECTIONS_DIR).join(&name), &config, metrics.clone()).await?;
+            collections.insert(name, collection);
+        }
+
+        let stores: Vec<&dyn VectorStore> = std::iter::once(&*storage as &dyn VectorStore)
+            .chain(collections.values().map(|collection| &*collection.storage))
+            .collect();
+        let embedding_meta = Self::load_embedding_meta(
+            &data_dir, &config.embedding.model, embedding_provider.dimensions(), &stores,
+        ).await?;
+        if !embedding_meta.matches(&config.embedding.model, embedding_provider.dimensions()) {
+            tracing::warn!(
+                "库中向量由 {}（{} 维）生成，当前配置为 {}（{} 维），运行 reembed_all 之前拒绝写入",
+                embedding_meta.model, embedding_meta.dimension, config.embedding.model, embedding_provider.dimensions()
+            );
+        }
 
         Ok(Self {
+            data_dir,
             storage,
             query_engine,
+            history,
+            collections,
             metrics,
             config,
+            embedding_provider,
+            embedding_meta: Mutex::new(embedding_meta),
+            access: Mutex::new(HashMap::new()),
+            deletions: Mutex::new(HashMap::new()),
         })
     }
 
+    /// 读取数据目录的嵌入模型记录；没有记录（新库或旧版本创建的库）时按现有向量推断并写入：
+    /// 空库或向量维度与配置一致时记为当前模型，否则记为未知模型
+    async fn load_embedding_meta(
+        data_dir: &Path,
+        model: &str,
+        dimension: usize,
+        stores: &[&dyn VectorStore],
+    ) -> Result<EmbeddingMeta> {
+        if let Some(meta) = EmbeddingMeta::load(data_dir)? {
+            return Ok(meta);
+        }
+        let mut stored_dimension = None;
+        for store in stores {
+            if let Some(record) = store.list_documents_after(None, 1).await?.pop() {
+                stored_dimension = Some(record.embedding.len());
+                break;
+            }
+        }
+        let meta = match stored_dimension {
+            Some(stored) if stored != dimension => EmbeddingMeta::new(storage::embedding_meta::UNKNOWN_EMBEDDING_MODEL, stored),
+            _ => EmbeddingMeta::new(model, dimension),
+        };
+        meta.save(data_dir)?;
+        Ok(meta)
+    }
+
+    /// 配置的嵌入模型与库中向量不一致时拒绝写入
+    fn ensure_embedding_model(&self) -> Result<()> {
+        let meta = self.embedding_meta.lock().unwrap();
+        let dimension = self.embedding_provider.dimensions();
+        if meta.matches(&self.config.embedding.model, dimension) {
+            return Ok(());
+        }
+        Err(VectorDbError::EmbeddingModelMismatch {
+            stored_model: meta.model.clone(),
+            stored_dimension: meta.dimension,
+            model: self.config.embedding.model.clone(),
+            dimension,
+        })
+    }
+
+    /// 数据目录下已有的集合名
+    fn existing_collections(data_dir: &Path) -> Result<Vec<String>> {
+        let dir = data_dir.join(COLLECTIONS_DIR);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type()?.is_dir() && validate_collection_name(&name).is_ok() {
+                names.push(name);
+            }
+        }
+        Ok(names)
+    }
+
+    /// 创建集合
+    pub async fn create_collection(&mut self, name: &str) -> Result<()> {
+        validate_collection_name(name)?;
+        if name == DEFAULT_COLLECTION || self.collections.contains_key(name) {
+            return Err(VectorDbError::Collection(format!("集合 {} 已存在", name)));
+        }
+        let dir = self.data_dir.join(COLLECTIONS_DIR).join(name);
+        std::fs::create_dir_all(&dir)?;
+        let collection = Collection::open(dir, &self.config, self.metrics.clone()).await?;
+        self.collections.insert(name.to_string(), collection);
+        Ok(())
+    }
+
+    /// 删除集合及其全部数据，默认集合不能删除；集合不存在时返回 false
+    pub async fn drop_collection(&mut self, name: &str) -> Result<bool> {
+        if name == DEFAULT_COLLECTION {
+            return Err(VectorDbError::Collection("默认集合不能删除".to_string()));
+        }
+        let Some(collection) = self.collections.remove(name) else {
+            return Ok(false);
+        };
+        // 先关闭存储再删除目录
+        drop(collection);
+        self.deletions.lock().unwrap().remove(name);
+        let dir = self.data_dir.join(COLLECTIONS_DIR).join(name);
+        if dir.exists() {
+            std::fs::remove_dir_all(dir)?;
+        }
+        Ok(true)
+    }
+
+    /// 列出所有集合（默认集合在前）及各自的统计信息
+    pub fn list_collections(&self) -> Vec<CollectionInfo> {
+        let default = CollectionInfo {
+            name: DEFAULT_COLLECTION.to_string(),
+            stats: self.storage.stats(),
+            index: self.query_engine.get_index_stats(),
+        };
+        std::iter::once(default)
+            .chain(self.collections.iter().map(|(name, collection)| CollectionInfo {
+                name: name.clone(),
+                stats: collection.storage.stats(),
+                index: collection.query_engine.get_index_stats(),
+            }))
+            .collect()
+    }
+
+    /// 取得集合的存储和索引
+    fn collection(&self, name: &str) -> Result<(&dyn VectorStore, &QueryEngine)> {
+        if name == DEFAULT_COLLECTION {
+            return Ok((&*self.storage, &self.query_engine));
+        }
+        self.collections
+            .get(name)
+            .map(|collection| (&*collection.storage, &collection.query_engine))
+            .ok_or_else(|| VectorDbError::Collection(format!("集合 {} 不存在", name)))
+    }
+
+    /// 记录从集合索引删除的向量数
+    fn record_deletions(&self, collection: &str, count: usize) {
+        if count > 0 {
+            *self.deletions.lock().unwrap().entry(collection.to_string()).or_insert(0) += count;
+        }
+    }
+
+    /// 取得集合的文档修订历史
+    fn history(&self, name: &str) -> Result<&Mutex<RevisionHistory>> {
+        if name == DEFAULT_COLLECTION {
+            return Ok(&self.history);
+        }
+        self.collections
+            .get(name)
+            .map(|collection| &collection.history)
+            .ok_or_else(|| VectorDbError::Collection(format!("集合 {} 不存在", name)))
+    }
+
     /// 使用OpenAI兼容API创建向量数据库
     pub async fn with_openai_compatible(
         data_dir: PathBuf,
@@ -125,48 +468,215 @@ impl VectorDatabase {
         Self::new(data_dir, config).await
     }
 
-    /// 添加文档
+    /// 添加文档到默认集合
     pub async fn add_document(&mut self, document: Document) -> Result<String> {
+        self.add_document_to(DEFAULT_COLLECTION, document).await
+    }
+
+    /// 添加文档到指定集合；超过 `chunking.max_tokens` 的文档切块后逐块嵌入，见 [`Self::embed_documents`]
+    pub async fn add_document_to(&mut self, collection: &str, document: Document) -> Result<String> {
+        self.ensure_embedding_model()?;
+        let (storage, query_engine) = self.collection(collection)?;
         let _timer = QueryTimer::new(self.metrics.clone());
 
-        // 生成嵌入向量
-        let embedding_provider = create_embedding_provider(&self.config.embedding)?;
-        let embedding = embedding_provider.generate_embedding(&document.content).await?;
+        // 生成嵌入向量并创建文档记录
+        let id = document.id.clone();
+        let (record, chunks) = self.embed_documents(vec![document], chrono::Utc::now()).await?
+            .pop()
+            .expect("每个文档对应一条记录");
+
+        // 保存到存储
+        storage.add_document(record.clone()).await?;
+        if !chunks.is_empty() {
+            storage.add_documents_batch(chunks.clone()).await?;
+        }
         
-        // 创建文档记录
-        let record = DocumentRecord {
-            id: document.id.clone(),
+        // 添加到索引
+        query_engine.add_document(&record).await?;
+        query_engine.add_documents(&chunks).await?;
+        query_engine.invalidate_result_cache();
+
+        // 更新指标
+        self.metrics.update_document_count(self.total_document_count() as u64);
+
+        Ok(id)
+    }
+
+    /// 批量添加文档到默认集合
+    pub async fn add_documents_batch(&mut self, documents: Vec<Document>) -> Result<Vec<String>> {
+        self.add_documents_batch_to(DEFAULT_COLLECTION, documents).await
+    }
+
+    /// 批量添加文档到指定集合
+    ///
+    /// 全部嵌入成功后（见 [`Self::embed_documents`]）在一次事务中写入存储，最后只刷新一次索引。
+    /// 任一批次失败时不写入任何文档。
+    pub async fn add_documents_batch_to(&mut self, collection: &str, documents: Vec<Document>) -> Result<Vec<String>> {
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.ensure_embedding_model()?;
+        let (storage, query_engine) = self.collection(collection)?;
+        let _timer = QueryTimer::new(self.metrics.clone());
+
+        let ids: Vec<String> = documents.iter().map(|document| document.id.clone()).collect();
+        let records: Vec<DocumentRecord> = self.embed_documents(documents, chrono::Utc::now()).await?
+            .into_iter()
+            .flat_map(|(record, chunks)| std::iter::once(record).chain(chunks))
+            .collect();
+
+        storage.add_documents_batch(records.clone()).await?;
+        query_engine.add_documents(&records).await?;
+        query_engine.invalidate_result_cache();
+
+        self.metrics.update_document_count(self.total_document_count() as u64);
+        Ok(ids)
+    }
+
+    /// 为文档生成嵌入向量，返回每个文档的记录及其分块记录（未切分时为空）
+    ///
+    /// 启用分块时超过 `chunking.max_tokens` 的文档按 token 数切块（相邻块重叠），每块作为独立记录
+    /// （ID 见 [`chunk_id`]，元数据带 `parent_id`、`chunk_index`、`chunk_count`）嵌入和索引；父文档保留全文，
+    /// 向量取各块向量的均值。所有待嵌入文本按 `embedding.batch_size` 分批调用批量嵌入接口，
+    /// 最多 `embedding.parallelism` 个批次同时请求。
+    async fn embed_documents(
+        &self,
+        documents: Vec<Document>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<(DocumentRecord, Vec<DocumentRecord>)>> {
+        let chunking = &self.config.chunking;
+        let pieces: Vec<Vec<vectorization::file_chunker::TextChunk>> = documents.iter()
+            .map(|document| {
+                if chunking.enabled {
+                    vectorization::file_chunker::chunk_by_tokens(&document.content, chunking.max_tokens, chunking.overlap_tokens)
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+        let texts: Vec<String> = documents.iter()
+            .zip(&pieces)
+            .flat_map(|(document, chunks)| match chunks.len() {
+                0 | 1 => vec![document.content.clone()],
+                _ => chunks.iter().map(|chunk| chunk.text.clone()).collect(),
+            })
+            .collect();
+
+        let embedding_provider = &self.embedding_provider;
+        let batch_size = self.config.embedding.batch_size.max(1);
+        let parallelism = self.config.embedding.parallelism.max(1);
+        let batches: Vec<Vec<Vec<f32>>> = futures::stream::iter(texts.chunks(batch_size))
+            .map(|batch| embedding_provider.generate_embeddings(batch))
+            .buffered(parallelism)
+            .try_collect()
+            .await?;
+        let embeddings: Vec<Vec<f32>> = batches.into_iter().flatten().collect();
+        if embeddings.len() != texts.len() {
+            return Err(VectorDbError::Embedding(format!(
+                "嵌入数量与文本数量不一致: 文本 {}，嵌入 {}",
+                texts.len(), embeddings.len()
+            )));
+        }
+        let dimension = embedding_provider.dimensions();
+        if let Some(embedding) = embeddings.iter().find(|embedding| embedding.len() != dimension) {
+            return Err(VectorDbError::InvalidVectorDimension { expected: dimension, actual: embedding.len() });
+        }
+
+        let mut embeddings = embeddings.into_iter();
+        Ok(documents.into_iter()
+            .zip(pieces)
+            .map(|(document, chunks)| {
+                if chunks.len() <= 1 {
+                    let embedding = embeddings.next().unwrap_or_default();
+                    return (Self::document_record(document, embedding, now), Vec::new());
+                }
+                let chunk_embeddings: Vec<Vec<f32>> = embeddings.by_ref().take(chunks.len()).collect();
+                let mut parent = Self::document_record(document, mean_embedding(&chunk_embeddings), now);
+                parent.metadata.insert(CHUNK_COUNT_METADATA_KEY.to_string(), chunks.len().to_string());
+                let children = chunks.into_iter()
+                    .zip(chunk_embeddings)
+                    .map(|(chunk, embedding)| {
+                        let mut metadata = parent.metadata.clone();
+                        metadata.insert(PARENT_ID_METADATA_KEY.to_string(), parent.id.clone());
+                        metadata.insert(CHUNK_INDEX_METADATA_KEY.to_string(), chunk.index.to_string());
+                        DocumentRecord {
+                            id: chunk_id(&parent.id, chunk.index),
+                            content: chunk.text,
+                            embedding,
+                            metadata,
+                            ..parent.clone()
+                        }
+                    })
+                    .collect();
+                (parent, children)
+            })
+            .collect())
+    }
+
+    /// 删除文档的分块记录（按元数据中的块数），返回从索引删除的数量
+    async fn remove_chunks(&self, collection: &str, parent: &DocumentRecord) -> Result<usize> {
+        let Some(count) = parent.metadata.get(CHUNK_COUNT_METADATA_KEY).and_then(|count| count.parse::<usize>().ok()) else {
+            return Ok(0);
+        };
+        if parent.metadata.contains_key(PARENT_ID_METADATA_KEY) {
+            return Ok(0);
+        }
+        let (storage, query_engine) = self.collection(collection)?;
+        let mut removed = 0;
+        for index in 0..count {
+            let id = chunk_id(&parent.id, index);
+            storage.delete_document(&id).await?;
+            if query_engine.remove_document(&id).await? {
+                removed += 1;
+            }
+        }
+        self.record_deletions(collection, removed);
+        Ok(removed)
+    }
+
+    /// 启用分块时多取候选，归并后仍能凑满 `limit` 条
+    fn search_fetch_limit(&self, limit: usize) -> usize {
+        if self.config.chunking.enabled {
+            limit.saturating_mul(CHUNK_SEARCH_OVERFETCH)
+        } else {
+            limit
+        }
+    }
+
+    fn document_record(document: Document, embedding: Vec<f32>, now: chrono::DateTime<chrono::Utc>) -> DocumentRecord {
+        DocumentRecord {
+            id: document.id,
             title: document.title.unwrap_or_else(|| "无标题".to_string()),
-            content: document.content.clone(),
+            content: document.content,
             embedding,
             package_name: document.package_name.unwrap_or_else(|| "unknown".to_string()),
             doc_type: document.doc_type.unwrap_or_else(|| "unknown".to_string()),
             language: document.language.unwrap_or_else(|| "unknown".to_string()),
             version: document.version.unwrap_or_else(|| "1.0".to_string()),
-            metadata: document.metadata.clone(),
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-        };
-
-        // 保存到存储
-        self.storage.add_document(record.clone()).await?;
-        
-        // 添加到索引
-        self.query_engine.add_document(&record).await?;
-
-        // 更新指标
-        let stats = self.storage.stats();
-        self.metrics.update_document_count(stats.document_count as u64);
+            metadata: document.metadata,
+            created_at: now,
+            updated_at: now,
+        }
+    }
 
-        Ok(document.id)
+    /// 所有集合的文档总数
+    fn total_document_count(&self) -> usize {
+        self.list_collections().iter().map(|info| info.stats.document_count).sum()
     }
 
-    /// 获取文档
+    /// 从默认集合获取文档
     pub async fn get_document(&self, id: &str) -> Result<Option<Document>> {
+        self.get_document_from(DEFAULT_COLLECTION, id).await
+    }
+
+    /// 从指定集合获取文档
+    pub async fn get_document_from(&self, collection: &str, id: &str) -> Result<Option<Document>> {
+        let (storage, _) = self.collection(collection)?;
         let _timer = QueryTimer::new(self.metrics.clone());
 
-        if let Some(record) = self.storage.get_document(id).await? {
+        if let Some(record) = storage.get_document(id).await? {
             self.metrics.record_cache_hit();
+            self.record_access(collection, [id], false);
             Ok(Some(Document {
                 id: record.id,
                 title: Some(record.title),
@@ -183,69 +693,259 @@ impl VectorDatabase {
         }
     }
 
-    /// 删除文档
+    /// 从默认集合删除文档
     pub async fn delete_document(&mut self, id: &str) -> Result<bool> {
+        self.delete_document_from(DEFAULT_COLLECTION, id).await
+    }
+
+    /// 从指定集合删除文档（连同其分块记录）
+    pub async fn delete_document_from(&mut self, collection: &str, id: &str) -> Result<bool> {
+        let (storage, query_engine) = self.collection(collection)?;
         let _timer = QueryTimer::new(self.metrics.clone());
 
+        if let Some(record) = storage.get_document(id).await? {
+            self.remove_chunks(collection, &record).await?;
+        }
+
         // 从存储删除
-        let deleted_from_storage = self.storage.delete_document(id).await?;
+        let deleted_from_storage = storage.delete_document(id).await?;
         
         // 从索引删除
-        let deleted_from_index = self.query_engine.remove_document(id).await?;
+        let deleted_from_index = query_engine.remove_document(id).await?;
+        query_engine.invalidate_result_cache();
+        if deleted_from_index {
+            self.record_deletions(collection, 1);
+        }
 
         if deleted_from_storage || deleted_from_index {
             // 更新指标
-            let stats = self.storage.stats();
-            self.metrics.update_document_count(stats.document_count as u64);
+            self.metrics.update_document_count(self.total_document_count() as u64);
         }
 
         Ok(deleted_from_storage || deleted_from_index)
     }
 
-    /// 更新文档
+    /// 从默认集合删除语言、包名和版本都匹配（None 表示不限）的所有文档，返回删除数量
+    pub async fn delete_by_filter(
+        &mut self,
+        language: Option<&str>,
+        package_name: Option<&str>,
+        version: Option<&str>,
+    ) -> Result<usize> {
+        self.delete_by_filter_in(DEFAULT_COLLECTION, language, package_name, version).await
+    }
+
+    /// 从指定集合按条件删除文档，同时清理索引条目；至少需要指定一个条件
+    pub async fn delete_by_filter_in(
+        &mut self,
+        collection: &str,
+        language: Option<&str>,
+        package_name: Option<&str>,
+        version: Option<&str>,
+    ) -> Result<usize> {
+        if language.is_none() && package_name.is_none() && version.is_none() {
+            return Err(VectorDbError::Query("按条件删除至少需要指定语言、包名或版本之一".to_string()));
+        }
+        let (storage, query_engine) = self.collection(collection)?;
+        let _timer = QueryTimer::new(self.metrics.clone());
+
+        let removed: HashSet<String> = storage.scan(SCAN_BATCH_SIZE)
+            .try_filter_map(|record| async move {
+                let matches = language.map_or(true, |l| record.language == l)
+                    && package_name.map_or(true, |p| record.package_name == p)
+                    && version.map_or(true, |v| record.version == v);
+                Ok(matches.then_some(record.id))
+            })
+            .try_collect()
+            .await?;
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        for id in &removed {
+            storage.delete_document(id).await?;
+            query_engine.remove_document(id).await?;
+        }
+        query_engine.invalidate_result_cache();
+        self.record_deletions(collection, removed.len());
+        if let Some(collection_access) = self.access.lock().unwrap().get_mut(collection) {
+            collection_access.retain(|id, _| !removed.contains(id));
+        }
+        self.metrics.update_document_count(self.total_document_count() as u64);
+        self.save().await?;
+        Ok(removed.len())
+    }
+
+    /// 更新默认集合中的文档
     pub async fn update_document(&mut self, document: Document) -> Result<()> {
+        self.update_document_in(DEFAULT_COLLECTION, document).await
+    }
+
+    /// 更新指定集合中的文档；保留原始创建时间，被覆盖的版本记入修订历史
+    pub async fn update_document_in(&mut self, collection: &str, document: Document) -> Result<()> {
+        self.ensure_embedding_model()?;
         let _timer = QueryTimer::new(self.metrics.clone());
 
-        // 生成新的嵌入向量
-        let embedding_provider = create_embedding_provider(&self.config.embedding)?;
-        let embedding = embedding_provider.generate_embedding(&document.content).await?;
-        
-        // 创建更新的文档记录
-        let record = DocumentRecord {
-            id: document.id.clone(),
-            title: document.title.unwrap_or_else(|| "无标题".to_string()),
-            content: document.content.clone(),
-            embedding,
-            package_name: document.package_name.unwrap_or_else(|| "unknown".to_string()),
-            doc_type: document.doc_type.unwrap_or_else(|| "unknown".to_string()),
-            language: document.language.unwrap_or_else(|| "unknown".to_string()),
-            version: document.version.unwrap_or_else(|| "1.0".to_string()),
-            metadata: document.metadata.clone(),
-            created_at: chrono::Utc::now(), // 这里应该保留原始创建时间，但简化实现
-            updated_at: chrono::Utc::now(),
-        };
+        // 生成新的嵌入向量（过长时重新分块）
+        let (record, chunks) = self.embed_documents(vec![document], chrono::Utc::now()).await?
+            .pop()
+            .expect("每个文档对应一条记录");
+        self.replace_record(collection, record).await?;
+
+        if !chunks.is_empty() {
+            let (storage, query_engine) = self.collection(collection)?;
+            storage.add_documents_batch(chunks.clone()).await?;
+            query_engine.add_documents(&chunks).await?;
+            query_engine.invalidate_result_cache();
+            self.metrics.update_document_count(self.total_document_count() as u64);
+        }
+        Ok(())
+    }
+
+    /// 写入文档的新版本：已存在时沿用创建时间、删除旧版本的分块记录并把旧版本记入修订历史，然后刷新索引
+    async fn replace_record(&self, collection: &str, mut record: DocumentRecord) -> Result<()> {
+        let (storage, query_engine) = self.collection(collection)?;
+        let previous = storage.get_document(&record.id).await?;
+
+        match previous {
+            Some(previous) => {
+                self.remove_chunks(collection, &previous).await?;
+                record.created_at = previous.created_at;
+                storage.update_document(record.clone()).await?;
+                let mut history = self.history(collection)?.lock().unwrap();
+                history.record(previous);
+                history.save()?;
+            }
+            // 文档已删除（从历史回滚恢复）时重新写入
+            None => storage.add_document(record.clone()).await?,
+        }
 
-        // 更新存储
-        self.storage.update_document(record.clone()).await?;
-        
         // 更新索引（先删除再添加）
-        self.query_engine.remove_document(&document.id).await?;
-        self.query_engine.add_document(&record).await?;
+        if query_engine.remove_document(&record.id).await? {
+            self.record_deletions(collection, 1);
+        }
+        query_engine.add_document(&record).await?;
+        query_engine.invalidate_result_cache();
+        self.metrics.update_document_count(self.total_document_count() as u64);
 
         Ok(())
     }
 
-    /// 向量搜索
+    /// 默认集合中文档的历史修订
+    pub fn get_document_history(&self, id: &str) -> Result<Vec<DocumentRevision>> {
+        self.get_document_history_in(DEFAULT_COLLECTION, id)
+    }
+
+    /// 指定集合中文档的历史修订，按修订号升序；没有历史时为空
+    pub fn get_document_history_in(&self, collection: &str, id: &str) -> Result<Vec<DocumentRevision>> {
+        Ok(self.history(collection)?.lock().unwrap().list(id))
+    }
+
+    /// 把默认集合中的文档回滚到指定修订
+    pub async fn rollback_document(&mut self, id: &str, revision: u64) -> Result<()> {
+        self.rollback_document_in(DEFAULT_COLLECTION, id, revision).await
+    }
+
+    /// 把文档回滚到指定修订：修订内容（含嵌入向量，无需重新生成）成为当前版本，
+    /// 回滚前的版本同样记入历史，因此回滚本身也可以撤销。文档已删除时重新写入
+    pub async fn rollback_document_in(&mut self, collection: &str, id: &str, revision: u64) -> Result<()> {
+        let _timer = QueryTimer::new(self.metrics.clone());
+        let target = self.history(collection)?.lock().unwrap()
+            .get(id, revision)
+            .map(|revision| revision.record.clone())
+            .ok_or_else(|| VectorDbError::Query(format!("文档 {} 没有修订 {}", id, revision)))?;
+
+        // 重新嵌入之前的修订向量来自旧模型，不能与当前向量混用
+        self.ensure_embedding_model()?;
+        let dimension = self.embedding_provider.dimensions();
+        if target.embedding.len() != dimension {
+            return Err(VectorDbError::InvalidVectorDimension { expected: dimension, actual: target.embedding.len() });
+        }
+
+        // 修订只保存父文档，旧的分块记录已不存在，回滚后以父文档的均值向量参与检索
+        let mut record = DocumentRecord { updated_at: chrono::Utc::now(), ..target };
+        record.metadata.remove(CHUNK_COUNT_METADATA_KEY);
+        self.replace_record(collection, record).await
+    }
+
+    /// 在默认集合中向量搜索
     pub async fn vector_search(&self, query_vector: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
-        self.query_engine.vector_search(&*self.storage, query_vector, limit).await
+        self.vector_search_in(DEFAULT_COLLECTION, query_vector, limit).await
+    }
+
+    /// 在指定集合中向量搜索，分块命中归并到父文档（见 [`collapse_chunks`]）
+    pub async fn vector_search_in(&self, collection: &str, query_vector: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
+        let (storage, query_engine) = self.collection(collection)?;
+        let results = query_engine.vector_search(storage, query_vector, self.search_fetch_limit(limit)).await?;
+        let results = collapse_chunks(results, limit);
+        self.record_access(collection, results.iter().map(|r| r.document_id.as_str()), true);
+        Ok(results)
+    }
+
+    /// 在指定集合中向量搜索，`pins` 中的文档（文档ID -> 修订号）按指定修订而不是当前版本参与打分和返回
+    ///
+    /// 用于复现某个时间点的检索结果：固定修订的文档不会以当前版本出现，结果元数据带 `revision`。
+    pub async fn vector_search_pinned_in(
+        &self,
+        collection: &str,
+        query_vector: &[f32],
+        limit: usize,
+        pins: &HashMap<String, u64>,
+    ) -> Result<Vec<SearchResult>> {
+        if pins.is_empty() {
+            return self.vector_search_in(collection, query_vector, limit).await;
+        }
+        let pinned: Vec<DocumentRecord> = {
+            let history = self.history(collection)?.lock().unwrap();
+            pins.iter()
+                .map(|(id, revision)| {
+                    history.get(id, *revision)
+                        .map(|revision| revision.record.clone())
+                        .ok_or_else(|| VectorDbError::Query(format!("文档 {} 没有修订 {}", id, revision)))
+                })
+                .collect::<Result<_>>()?
+        };
+
+        let (storage, query_engine) = self.collection(collection)?;
+        let metric = self.config.distance_metric;
+        let mut results: Vec<SearchResult> = query_engine.vector_search(storage, query_vector, limit + pins.len()).await?
+            .into_iter()
+            .filter(|result| !pins.contains_key(&result.document_id))
+            .collect();
+        results.extend(pins.iter().zip(pinned).map(|((_, revision), record)| {
+            let mut metadata = record.metadata;
+            metadata.insert(REVISION_METADATA_KEY.to_string(), revision.to_string());
+            SearchResult {
+                similarity_score: metric.similarity(metric.distance(query_vector, &record.embedding)),
+                document_id: record.id,
+                title: record.title,
+                content_snippet: record.content.chars().take(REVISION_SNIPPET_CHARS).collect(),
+                package_name: record.package_name,
+                doc_type: record.doc_type,
+                metadata,
+            }
+        }));
+        results.sort_by(|a, b| b.similarity_score.partial_cmp(&a.similarity_score).unwrap_or(std::cmp::Ordering::Equal));
+        results.truncate(limit);
+        self.record_access(collection, results.iter().map(|r| r.document_id.as_str()), true);
+        Ok(results)
     }
 
-    /// 文本搜索
+    /// 在默认集合中文本搜索
     pub async fn text_search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        self.query_engine.text_search(&*self.storage, query, limit).await
+        self.text_search_in(DEFAULT_COLLECTION, query, limit).await
+    }
+
+    /// 在指定集合中文本搜索，分块命中归并到父文档
+    pub async fn text_search_in(&self, collection: &str, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let (storage, query_engine) = self.collection(collection)?;
+        let results = query_engine.text_search(storage, query, self.search_fetch_limit(limit)).await?;
+        let results = collapse_chunks(results, limit);
+        self.record_access(collection, results.iter().map(|r| r.document_id.as_str()), true);
+        Ok(results)
     }
 
-    /// 混合搜索（向量 + 文本）
+    /// 在默认集合中混合搜索（向量 + 文本）
     pub async fn hybrid_search(
         &self,
         query_text: &str,
@@ -253,26 +953,56 @@ impl VectorDatabase {
         vector_weight: f32,
         text_weight: f32,
     ) -> Result<Vec<SearchResult>> {
+        self.hybrid_search_in(DEFAULT_COLLECTION, query_text, limit, vector_weight, text_weight).await
+    }
+
+    /// 在指定集合中混合搜索（向量 + 文本）；启用 `sparse` 时再与稀疏检索结果融合
+    pub async fn hybrid_search_in(
+        &self,
+        collection: &str,
+        query_text: &str,
+        limit: usize,
+        vector_weight: f32,
+        text_weight: f32,
+    ) -> Result<Vec<SearchResult>> {
+        let (storage, query_engine) = self.collection(collection)?;
+
         // 生成查询向量
-        let embedding_provider = create_embedding_provider(&self.config.embedding)?;
-        let query_vector = embedding_provider.generate_embedding(query_text).await?;
+        let query_vector = self.embedding_provider.generate_query_embedding(query_text).await?;
 
-        self.query_engine.search(
-            &*self.storage,
+        let results = query_engine.search(
+            storage,
             Some(&query_vector),
             Some(query_text),
-            limit,
+            self.search_fetch_limit(limit),
             vector_weight,
             text_weight,
-        ).await
+        ).await?;
+        let results = query_engine.fuse_sparse(storage, query_text, results, self.search_fetch_limit(limit)).await?;
+        let results = collapse_chunks(results, limit);
+        self.record_access(collection, results.iter().map(|r| r.document_id.as_str()), true);
+        Ok(results)
     }
 
-    /// 语义搜索（基于文本生成向量）
+    /// 在默认集合中语义搜索（基于文本生成向量）
     pub async fn semantic_search(&self, query_text: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        let embedding_provider = create_embedding_provider(&self.config.embedding)?;
-        let query_vector = embedding_provider.generate_embedding(query_text).await?;
-        
-        self.vector_search(&query_vector, limit).await
+        self.semantic_search_in(DEFAULT_COLLECTION, query_text, limit).await
+    }
+
+    /// 在指定集合中语义搜索；启用 `sparse` 时与稀疏检索结果融合
+    pub async fn semantic_search_in(&self, collection: &str, query_text: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let query_vector = self.embedding_provider.generate_query_embedding(query_text).await?;
+        if !self.config.sparse.enabled {
+            return self.vector_search_in(collection, &query_vector, limit).await;
+        }
+
+        let (storage, query_engine) = self.collection(collection)?;
+        let fetch_limit = self.search_fetch_limit(limit);
+        let dense = query_engine.vector_search(storage, &query_vector, fetch_limit).await?;
+        let results = query_engine.fuse_sparse(storage, query_text, dense, fetch_limit).await?;
+        let results = collapse_chunks(results, limit);
+        self.record_access(collection, results.iter().map(|r| r.document_id.as_str()), true);
+        Ok(results)
     }
 
     /// 简化的搜索方法（主要用于测试）
@@ -280,11 +1010,17 @@ impl VectorDatabase {
         self.semantic_search(query_text, limit).await
     }
 
-    /// 列出文档
+    /// 列出默认集合中的文档
     pub async fn list_documents(&self, offset: usize, limit: usize) -> Result<Vec<Document>> {
+        self.list_documents_in(DEFAULT_COLLECTION, offset, limit).await
+    }
+
+    /// 列出指定集合中的文档
+    pub async fn list_documents_in(&self, collection: &str, offset: usize, limit: usize) -> Result<Vec<Document>> {
+        let (storage, _) = self.collection(collection)?;
         let _timer = QueryTimer::new(self.metrics.clone());
 
-        let records = self.storage.list_documents(offset, limit).await?;
+        let records = storage.list_documents(offset, limit).await?;
         let documents = records.into_iter().map(|record| Document {
             id: record.id,
             title: Some(record.title),
@@ -299,22 +1035,387 @@ impl VectorDatabase {
         Ok(documents)
     }
 
-    /// 重建索引
+    /// 按游标分页列出文档，`cursor` 为上一页返回的 `next_cursor`
+    ///
+    /// 游标记录上一页最后一个文档ID，按键集分页读取，翻到多深都不需要跳过前面的文档。
+    pub async fn list_documents_page(&self, cursor: Option<&str>, page_size: usize) -> Result<DocumentPage> {
+        let (offset, after) = match cursor {
+            Some(cursor) => {
+                let (offset, last_id) = tools::pagination::decode_cursor(cursor)?;
+                (offset, Some(last_id))
+            }
+            None => (0, None),
+        };
+        let page_size = page_size.clamp(1, tools::pagination::MAX_PAGE_SIZE);
+        let _timer = QueryTimer::new(self.metrics.clone());
+
+        // 多取一条判断是否还有下一页
+        let records = self.storage.list_documents_after(after.as_deref(), page_size + 1).await?;
+        let mut documents: Vec<Document> = records.into_iter().map(|record| Document {
+            id: record.id,
+            title: Some(record.title),
+            content: record.content,
+            package_name: Some(record.package_name),
+            doc_type: Some(record.doc_type),
+            language: Some(record.language),
+            version: Some(record.version),
+            metadata: record.metadata,
+        }).collect();
+        let has_more = documents.len() > page_size;
+        documents.truncate(page_size);
+        let next_cursor = if has_more {
+            documents.last().map(|doc| tools::pagination::encode_cursor(offset + documents.len(), &doc.id))
+        } else {
+            None
+        };
+
+        Ok(DocumentPage { documents, next_cursor })
+    }
+
+    /// 重建所有集合的索引
     pub async fn rebuild_index(&self) -> Result<()> {
-        self.query_engine.rebuild_index().await
+        self.query_engine.rebuild_index().await?;
+        self.query_engine.invalidate_result_cache();
+        for collection in self.collections.values() {
+            collection.query_engine.rebuild_index().await?;
+            collection.query_engine.invalidate_result_cache();
+        }
+        self.deletions.lock().unwrap().clear();
+        Ok(())
     }
 
-    /// 保存数据库
+    /// 保存所有集合
     pub async fn save(&self) -> Result<()> {
-        self.storage.save().await
+        self.storage.save().await?;
+        for collection in self.collections.values() {
+            collection.storage.save().await?;
+        }
+        Ok(())
     }
 
-    /// 压缩数据库
+    /// 把所有集合的文档和向量写入单个快照文件（格式与 vector_docs 工具的快照相同）
+    pub async fn create_snapshot(&self, path: &Path) -> Result<SnapshotReport> {
+        let mut stores = Vec::new();
+        for info in self.list_collections() {
+            let (storage, _) = self.collection(&info.name)?;
+            let documents = storage.scan(SCAN_BATCH_SIZE).try_collect().await?;
+            let name = if info.name == DEFAULT_COLLECTION {
+                DEFAULT_COLLECTION.to_string()
+            } else {
+                format!("{}{}", COLLECTION_STORE_PREFIX, info.name)
+            };
+            stores.push(StoreSnapshot { name, documents, processed_package_versions: Default::default() });
+        }
+        let snapshot = Snapshot::new(&self.config.embedding.model, stores);
+        let bytes = snapshot.write(path).map_err(|e| VectorDbError::Storage(e.to_string()))?;
+        Ok(snapshot.report(path, bytes))
+    }
+
+    /// 从快照恢复：快照中每个集合的现有文档被替换，缺少的集合会创建
+    pub async fn restore_snapshot(&mut self, path: &Path) -> Result<SnapshotReport> {
+        let snapshot = Snapshot::<DocumentRecord>::read(path).map_err(|e| VectorDbError::Storage(e.to_string()))?;
+        snapshot.ensure_compatible(&self.config.embedding.model).map_err(|e| VectorDbError::Config(e.to_string()))?;
+        let report = snapshot.report(path, std::fs::metadata(path)?.len());
+
+        for store in snapshot.stores {
+            let collection = store.name.strip_prefix(COLLECTION_STORE_PREFIX).unwrap_or(&store.name).to_string();
+            if collection != DEFAULT_COLLECTION && !self.collections.contains_key(&collection) {
+                self.create_collection(&collection).await?;
+            }
+            let (storage, query_engine) = self.collection(&collection)?;
+            let existing: Vec<String> = storage.scan(SCAN_BATCH_SIZE).map_ok(|record| record.id).try_collect().await?;
+            for id in &existing {
+                storage.delete_document(id).await?;
+                query_engine.remove_document(id).await?;
+            }
+            for record in store.documents {
+                storage.add_document(record.clone()).await?;
+                query_engine.add_document(&record).await?;
+            }
+            query_engine.invalidate_result_cache();
+        }
+        self.metrics.update_document_count(self.total_document_count() as u64);
+        self.save().await?;
+        Ok(report)
+    }
+
+    /// 记录文档被读取或出现在搜索结果中
+    fn record_access<'a>(&self, collection: &str, ids: impl IntoIterator<Item = &'a str>, search_hit: bool) {
+        let now = std::time::SystemTime::now();
+        let mut access = self.access.lock().unwrap();
+        let collection_access = access.entry(collection.to_string()).or_default();
+        for id in ids {
+            let usage = collection_access.entry(id.to_string()).or_default();
+            usage.last_access = now;
+            if search_hit {
+                usage.search_hits += 1;
+            }
+        }
+    }
+
+    /// 删除过期文档，再把每个集合按 [`RetentionConfig::max_total_bytes`] 淘汰到上限以内，同时更新索引和指标
+    pub async fn apply_retention(&self) -> Result<RetentionReport> {
+        self.apply_retention_at(chrono::Utc::now()).await
+    }
+
+    async fn apply_retention_at(&self, now: chrono::DateTime<chrono::Utc>) -> Result<RetentionReport> {
+        let retention = &self.config.retention;
+        let mut report = RetentionReport::default();
+        if !retention.is_enabled() {
+            return Ok(report);
+        }
+
+        for info in self.list_collections() {
+            let (storage, query_engine) = self.collection(&info.name)?;
+            // 流式扫描，只保留每个文档的大小和更新时间
+            let mut expired = HashSet::new();
+            let mut bytes: HashMap<String, u64> = HashMap::new();
+            let mut updated_at = HashMap::new();
+            let mut records = storage.scan(SCAN_BATCH_SIZE);
+            while let Some(record) = records.try_next().await? {
+                let is_expired = retention.document_ttl(&record.metadata).map_or(false, |ttl| {
+                    record.updated_at + chrono::Duration::seconds(ttl.min(i64::MAX as u64) as i64) <= now
+                });
+                if is_expired {
+                    expired.insert(record.id);
+                } else {
+                    bytes.insert(record.id.clone(), record_bytes(&record));
+                    updated_at.insert(record.id, record.updated_at);
+                }
+            }
+            drop(records);
+
+            let mut evicted = Vec::new();
+            if let Some(max_total_bytes) = retention.max_total_bytes {
+                // 没有访问记录的文档按最后更新时间计
+                let usage: HashMap<String, PackageUsage> = {
+                    let access = self.access.lock().unwrap();
+                    let collection_access = access.get(&info.name);
+                    updated_at.iter()
+                        .map(|(id, updated_at)| {
+                            let usage = collection_access.and_then(|a| a.get(id)).cloned().unwrap_or(PackageUsage {
+                                last_access: (*updated_at).into(),
+                                search_hits: 0,
+                            });
+                            (id.clone(), usage)
+                        })
+                        .collect()
+                };
+                evicted = select_eviction_victims(&bytes, &usage, max_total_bytes, retention.eviction_policy, &[]);
+                report.evicted_bytes += evicted.iter().map(|id| bytes[id]).sum::<u64>();
+            }
+
+            let removed: HashSet<&String> = expired.iter().chain(&evicted).collect();
+            for id in &removed {
+                storage.delete_document(id).await?;
+                query_engine.remove_document(id).await?;
+            }
+            query_engine.invalidate_result_cache();
+            self.record_deletions(&info.name, removed.len());
+            if let Some(collection_access) = self.access.lock().unwrap().get_mut(&info.name) {
+                collection_access.retain(|id, _| !removed.contains(id));
+            }
+            report.expired_documents += expired.len();
+            report.evicted_documents += evicted.len();
+        }
+
+        if report.expired_documents + report.evicted_documents > 0 {
+            self.metrics.record_retention(report.expired_documents as u64, report.evicted_documents as u64, report.evicted_bytes);
+            self.metrics.update_document_count(self.total_document_count() as u64);
+            self.save().await?;
+            tracing::info!(
+                "保留清理: 过期 {} 个文档，淘汰 {} 个文档（{} 字节）",
+                report.expired_documents, report.evicted_documents, report.evicted_bytes
+            );
+        }
+        Ok(report)
+    }
+
+    /// 启动后台保留清理任务，每隔 `retention.sweep_interval_seconds` 执行一次 [`Self::apply_retention`]；
+    /// 未配置过期时间和大小上限时不启动
+    pub async fn spawn_retention_task(db: Arc<tokio::sync::RwLock<VectorDatabase>>) -> Option<tokio::task::JoinHandle<()>> {
+        let retention = db.read().await.config.retention.clone();
+        if !retention.is_enabled() {
+            return None;
+        }
+        let interval = std::time::Duration::from_secs(retention.sweep_interval_seconds.max(1));
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = db.read().await.apply_retention().await {
+                    tracing::warn!("保留清理失败: {}", e);
+                }
+            }
+        }))
+    }
+
+    /// 执行一次后台维护：逐个集合重建删除较多的索引（清除已删除的向量、重新平衡 HNSW 图）、
+    /// 压缩存储并落盘；超出 `maintenance.max_duration_seconds` 时剩余集合留到下次
+    pub async fn run_maintenance(&self) -> Result<MaintenanceReport> {
+        let maintenance = &self.config.maintenance;
+        let started = std::time::Instant::now();
+        let budget = std::time::Duration::from_secs(maintenance.max_duration_seconds);
+        let mut report = MaintenanceReport::default();
+
+        for info in self.list_collections() {
+            if started.elapsed() >= budget {
+                report.budget_exhausted = true;
+                break;
+            }
+            let (storage, query_engine) = self.collection(&info.name)?;
+            let deleted = self.deletions.lock().unwrap().get(&info.name).copied().unwrap_or(0);
+            let threshold = maintenance.rebuild_deleted_ratio * info.stats.document_count.max(1) as f32;
+            if deleted > 0 && deleted as f32 >= threshold {
+                query_engine.rebuild_index().await?;
+                query_engine.invalidate_result_cache();
+                self.deletions.lock().unwrap().remove(&info.name);
+                report.indexes_rebuilt += 1;
+                report.pruned_vectors += deleted;
+            }
+            if maintenance.compact_storage {
+                storage.compact().await?;
+                report.collections_compacted += 1;
+            }
+            storage.save().await?;
+        }
+
+        report.duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        self.metrics.record_maintenance(report.pruned_vectors as u64, report.duration_ms);
+        tracing::info!(
+            "后台维护: 重建 {} 个索引（清除 {} 个已删除向量），压缩 {} 个集合，耗时 {:.0}ms",
+            report.indexes_rebuilt, report.pruned_vectors, report.collections_compacted, report.duration_ms
+        );
+        Ok(report)
+    }
+
+    /// 启动后台维护任务，每隔 `maintenance.interval_seconds` 检查一次，最近 `maintenance.idle_seconds`
+    /// 内没有查询和写入时执行 [`Self::run_maintenance`]；`maintenance.enabled` 为 false 时不启动
+    pub async fn spawn_maintenance_task(db: Arc<tokio::sync::RwLock<VectorDatabase>>) -> Option<tokio::task::JoinHandle<()>> {
+        let maintenance = db.read().await.config.maintenance.clone();
+        if !maintenance.enabled {
+            return None;
+        }
+        let interval = std::time::Duration::from_secs(maintenance.interval_seconds.max(1));
+        let idle = std::time::Duration::from_secs(maintenance.idle_seconds);
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let db = db.read().await;
+                if db.metrics.idle_for() < idle {
+                    db.metrics.record_maintenance_skipped();
+                    continue;
+                }
+                if let Err(e) = db.run_maintenance().await {
+                    tracing::warn!("后台维护失败: {}", e);
+                }
+            }
+        }))
+    }
+
+    /// 用当前配置的嵌入模型重新生成所有集合的全部向量，完成后更新数据目录的模型记录
+    ///
+    /// 按文档ID分页处理，每页的父文档重新分块、嵌入后原地替换（保留创建和更新时间，不记入修订历史），
+    /// 旧的分块记录随之替换；最后重建索引。中途失败时已处理的文档保持新向量，模型记录不变，
+    /// 再次运行会从头处理。换模型后写入会被拒绝，直到本方法完成。
+    pub async fn reembed_all(&self) -> Result<ReembedReport> {
+        let started = std::time::Instant::now();
+        let previous = self.embedding_meta.lock().unwrap().clone();
+        let mut report = ReembedReport {
+            previous_model: previous.model,
+            previous_dimension: previous.dimension,
+            model: self.config.embedding.model.clone(),
+            dimension: self.embedding_provider.dimensions(),
+            ..Default::default()
+        };
+
+        for info in self.list_collections() {
+            let (storage, query_engine) = self.collection(&info.name)?;
+            let mut after: Option<String> = None;
+            loop {
+                let records = storage.list_documents_after(after.as_deref(), SCAN_BATCH_SIZE).await?;
+                let Some(last) = records.last() else { break };
+                after = Some(last.id.clone());
+
+                // 分块记录随父文档重新生成
+                let parents: Vec<DocumentRecord> = records.into_iter()
+                    .filter(|record| !record.metadata.contains_key(PARENT_ID_METADATA_KEY))
+                    .collect();
+                let documents: Vec<Document> = parents.iter().map(|record| {
+                    let mut metadata = record.metadata.clone();
+                    metadata.remove(CHUNK_COUNT_METADATA_KEY);
+                    Document {
+                        id: record.id.clone(),
+                        title: Some(record.title.clone()),
+                        content: record.content.clone(),
+                        package_name: Some(record.package_name.clone()),
+                        doc_type: Some(record.doc_type.clone()),
+                        language: Some(record.language.clone()),
+                        version: Some(record.version.clone()),
+                        metadata,
+                    }
+                }).collect();
+                let embedded = self.embed_documents(documents, chrono::Utc::now()).await?;
+
+                for (previous, (mut record, chunks)) in parents.into_iter().zip(embedded) {
+                    self.remove_chunks(&info.name, &previous).await?;
+                    record.created_at = previous.created_at;
+                    record.updated_at = previous.updated_at;
+                    storage.update_document(record.clone()).await?;
+                    query_engine.remove_document(&record.id).await?;
+                    query_engine.add_document(&record).await?;
+                    if !chunks.is_empty() {
+                        storage.add_documents_batch(chunks.clone()).await?;
+                        query_engine.add_documents(&chunks).await?;
+                    }
+                    report.documents += 1;
+                    report.chunks += chunks.len();
+                }
+            }
+            query_engine.rebuild_index().await?;
+            query_engine.invalidate_result_cache();
+            storage.save().await?;
+            report.collections += 1;
+            tracing::info!("集合 {} 重新嵌入完成（累计 {} 个文档）", info.name, report.documents);
+        }
+
+        self.deletions.lock().unwrap().clear();
+        let meta = EmbeddingMeta::new(&report.model, report.dimension);
+        meta.save(&self.data_dir)?;
+        *self.embedding_meta.lock().unwrap() = meta;
+        self.metrics.update_document_count(self.total_document_count() as u64);
+
+        report.duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        tracing::info!(
+            "重新嵌入完成: {}（{} 维）-> {}（{} 维），{} 个集合 {} 个文档，耗时 {:.0}ms",
+            report.previous_model, report.previous_dimension, report.model, report.dimension,
+            report.collections, report.documents, report.duration_ms
+        );
+        Ok(report)
+    }
+
+    /// 在后台执行 [`Self::reembed_all`]；期间持有读锁，搜索照常进行，写入等待迁移完成
+    pub fn spawn_reembed_task(db: Arc<tokio::sync::RwLock<VectorDatabase>>) -> tokio::task::JoinHandle<Result<ReembedReport>> {
+        tokio::spawn(async move {
+            let result = db.read().await.reembed_all().await;
+            if let Err(e) = &result {
+                tracing::warn!("重新嵌入失败: {}", e);
+            }
+            result
+        })
+    }
+
+    /// 压缩所有集合
     pub async fn compact(&self) -> Result<()> {
-        self.storage.compact().await
+        self.storage.compact().await?;
+        for collection in self.collections.values() {
+            collection.storage.compact().await?;
+        }
+        Ok(())
     }
 
-    /// 获取数据库统计信息
+    /// 获取默认集合的统计信息，其他集合见 [`Self::list_collections`]
     pub fn get_stats(&self) -> DatabaseStats {
         self.storage.stats()
     }
@@ -329,6 +1430,16 @@ impl VectorDatabase {
         self.query_engine.get_index_stats()
     }
 
+    /// 库中向量的嵌入模型和维度
+    pub fn get_embedding_meta(&self) -> EmbeddingMeta {
+        self.embedding_meta.lock().unwrap().clone()
+    }
+
+    /// 获取故障转移链中各嵌入提供商的统计（未配置备用提供商时为空）
+    pub fn get_embedding_provider_metrics(&self) -> Vec<EmbeddingProviderMetrics> {
+        self.embedding_provider.provider_metrics()
+    }
+
     /// 重置指标
     pub fn reset_metrics(&self) {
         self.metrics.reset();
@@ -386,6 +1497,210 @@ mod tests {
         assert_eq!(stats.document_count, 0);
     }
 
+    #[tokio::test]
+    async fn test_add_documents_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = VectorDbConfig::default();
+        config.embedding.batch_size = 2;
+        config.embedding.parallelism = 2;
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), config).await.unwrap();
+
+        let documents: Vec<Document> = (0..5)
+            .map(|i| Document { id: format!("batch-{}", i), content: format!("批量写入的第{}个文档", i), ..Default::default() })
+            .collect();
+        let ids = db.add_documents_batch(documents).await.unwrap();
+        assert_eq!(ids, (0..5).map(|i| format!("batch-{}", i)).collect::<Vec<_>>());
+        assert_eq!(db.get_stats().document_count, 5);
+        assert!(db.get_document("batch-4").await.unwrap().is_some());
+        assert!(!db.text_search("批量写入", 5).await.unwrap().is_empty());
+        assert!(db.add_documents_batch(Vec::new()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_filter() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), VectorDbConfig::default()).await.unwrap();
+        for (id, version) in [("serde-1", "1.0.0"), ("serde-1b", "1.0.0"), ("serde-2", "2.0.0")] {
+            let doc = Document {
+                id: id.to_string(),
+                content: format!("serde {} 序列化文档", version),
+                package_name: Some("serde".to_string()),
+                language: Some("rust".to_string()),
+                version: Some(version.to_string()),
+                ..Default::default()
+            };
+            db.add_document(doc).await.unwrap();
+        }
+
+        assert!(db.delete_by_filter(None, None, None).await.is_err());
+        assert_eq!(db.delete_by_filter(Some("rust"), Some("serde"), Some("1.0.0")).await.unwrap(), 2);
+        assert!(db.get_document("serde-1").await.unwrap().is_none());
+        assert!(db.get_document("serde-2").await.unwrap().is_some());
+        assert_eq!(db.delete_by_filter(Some("python"), None, None).await.unwrap(), 0);
+        assert_eq!(db.get_stats().document_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_scan_and_pages_use_keyset_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), VectorDbConfig::default()).await.unwrap();
+        for id in ["c", "a", "e", "b", "d"] {
+            db.add_document(Document { id: id.to_string(), content: format!("扫描文档 {}", id), ..Default::default() }).await.unwrap();
+        }
+
+        // 批大小不整除文档数时最后一批也能取到
+        let ids: Vec<String> = db.storage.scan(2).map_ok(|record| record.id).try_collect().await.unwrap();
+        assert_eq!(ids, vec!["a", "b", "c", "d", "e"]);
+
+        let first = db.list_documents_page(None, 3).await.unwrap();
+        assert_eq!(first.documents.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+        let second = db.list_documents_page(first.next_cursor.as_deref(), 3).await.unwrap();
+        assert_eq!(second.documents.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), vec!["d", "e"]);
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_long_documents_are_chunked_and_collapsed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = VectorDbConfig::default();
+        config.chunking.max_tokens = 16;
+        config.chunking.overlap_tokens = 4;
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), config).await.unwrap();
+        let content = (0..40).map(|i| format!("tok{}", i)).collect::<Vec<_>>().join(" ");
+        db.add_document(Document { id: "long".to_string(), content: content.clone(), ..Default::default() }).await.unwrap();
+        db.add_document(Document { id: "short".to_string(), content: "短文档".to_string(), ..Default::default() }).await.unwrap();
+
+        let parent = db.get_document("long").await.unwrap().unwrap();
+        assert_eq!(parent.content, content);
+        let chunk_count: usize = parent.metadata[CHUNK_COUNT_METADATA_KEY].parse().unwrap();
+        assert!(chunk_count > 1);
+        let first = db.get_document(&chunk_id("long", 0)).await.unwrap().unwrap();
+        assert_eq!(first.metadata[PARENT_ID_METADATA_KEY], "long");
+        assert!(db.get_document("short").await.unwrap().unwrap().metadata.get(CHUNK_COUNT_METADATA_KEY).is_none());
+
+        let results = db.search(&content, 10).await.unwrap();
+        assert_eq!(results.iter().filter(|r| r.document_id == "long").count(), 1);
+        assert!(results.iter().all(|r| !r.document_id.contains("#chunk-")));
+
+        db.delete_document("long").await.unwrap();
+        assert!(db.get_document(&chunk_id("long", chunk_count - 1)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sparse_fusion_finds_exact_identifiers() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = VectorDbConfig::default();
+        config.sparse.enabled = true;
+        config.sparse.fusion = SparseFusion::Weighted;
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), config).await.unwrap();
+        for (id, content) in [
+            ("select", "The tokio::select! macro waits on multiple branches"),
+            ("spawn", "tokio::spawn runs a task on the runtime"),
+            ("channel", "mpsc channels send values between tasks"),
+            ("thread", "std::thread::spawn starts an OS thread"),
+        ] {
+            db.add_document(Document { id: id.to_string(), content: content.to_string(), ..Default::default() }).await.unwrap();
+        }
+
+        let results = db.search("tokio::select!", 3).await.unwrap();
+        assert_eq!(results[0].document_id, "select");
+        assert!(results.iter().all(|r| r.similarity_score <= 1.0));
+
+        // 写入后稀疏索引随之更新
+        db.add_document(Document { id: "join".to_string(), content: "tokio::join! polls futures concurrently".to_string(), ..Default::default() }).await.unwrap();
+        assert_eq!(db.search("tokio::join!", 3).await.unwrap()[0].document_id, "join");
+    }
+
+    #[tokio::test]
+    async fn test_model_change_blocks_writes_until_reembedded() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = VectorDbConfig::default();
+        config.embedding.dimension = Some(8);
+        {
+            let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), config.clone()).await.unwrap();
+            db.add_document(Document { id: "a".to_string(), content: "异步运行时".to_string(), ..Default::default() }).await.unwrap();
+            db.add_document(Document { id: "b".to_string(), content: "tokio spawn".to_string(), ..Default::default() }).await.unwrap();
+            db.save().await.unwrap();
+        }
+
+        config.embedding.model = "bge-small-en-v1.5".to_string();
+        config.embedding.dimension = Some(16);
+        let db = Arc::new(tokio::sync::RwLock::new(
+            VectorDatabase::new(temp_dir.path().to_path_buf(), config).await.unwrap(),
+        ));
+        let error = db.write().await
+            .add_document(Document { id: "c".to_string(), content: "新文档".to_string(), ..Default::default() })
+            .await
+            .unwrap_err();
+        assert!(matches!(error, VectorDbError::EmbeddingModelMismatch { stored_dimension: 8, dimension: 16, .. }));
+
+        let report = VectorDatabase::spawn_reembed_task(db.clone()).await.unwrap().unwrap();
+        assert_eq!((report.previous_dimension, report.dimension, report.documents), (8, 16, 2));
+
+        let mut db = db.write().await;
+        assert_eq!(db.get_embedding_meta().model, "bge-small-en-v1.5");
+        assert_eq!(EmbeddingMeta::load(temp_dir.path()).unwrap().unwrap().dimension, 16);
+        db.add_document(Document { id: "c".to_string(), content: "新文档".to_string(), ..Default::default() }).await.unwrap();
+        assert!(db.search("tokio spawn", 3).await.unwrap().iter().any(|r| r.document_id == "b"));
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_rebuilds_indexes_with_many_deletions() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = VectorDbConfig::default();
+        config.maintenance.rebuild_deleted_ratio = 0.5;
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), config).await.unwrap();
+        for i in 0..4 {
+            db.add_document(Document { id: format!("doc-{}", i), content: format!("维护测试文档 {}", i), ..Default::default() }).await.unwrap();
+        }
+
+        // 删除比例未达到阈值时只压缩
+        db.delete_document("doc-0").await.unwrap();
+        let report = db.run_maintenance().await.unwrap();
+        assert_eq!(report.indexes_rebuilt, 0);
+        assert_eq!(report.collections_compacted, 1);
+
+        db.delete_document("doc-1").await.unwrap();
+        let report = db.run_maintenance().await.unwrap();
+        assert_eq!(report.indexes_rebuilt, 1);
+        assert_eq!(report.pruned_vectors, 2);
+        assert!(!report.budget_exhausted);
+        assert_eq!(db.run_maintenance().await.unwrap().indexes_rebuilt, 0);
+
+        let metrics = db.get_metrics();
+        assert_eq!(metrics.maintenance_runs, 3);
+        assert_eq!(metrics.pruned_vectors, 2);
+        assert!(!db.text_search("维护测试", 5).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_keeps_history_and_rolls_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), VectorDbConfig::default()).await.unwrap();
+        let version = |content: &str| Document { id: "doc".to_string(), content: content.to_string(), ..Default::default() };
+        db.add_document(version("第一版内容")).await.unwrap();
+        db.update_document(version("第二版内容")).await.unwrap();
+        db.update_document(version("第三版内容")).await.unwrap();
+
+        let history = db.get_document_history("doc").unwrap();
+        assert_eq!(history.iter().map(|r| r.revision).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(history[0].record.content, "第一版内容");
+        // 更新保留原始创建时间
+        assert_eq!(history[0].record.created_at, history[1].record.created_at);
+
+        // 按修订搜索返回固定的版本
+        let pins = HashMap::from([("doc".to_string(), 2)]);
+        let results = db.vector_search_pinned_in(DEFAULT_COLLECTION, &history[1].record.embedding, 5, &pins).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content_snippet, "第二版内容");
+        assert_eq!(results[0].metadata.get(REVISION_METADATA_KEY).map(String::as_str), Some("2"));
+
+        db.rollback_document("doc", 1).await.unwrap();
+        assert_eq!(db.get_document("doc").await.unwrap().unwrap().content, "第一版内容");
+        assert_eq!(db.get_document_history("doc").unwrap().last().unwrap().record.content, "第三版内容");
+        assert!(db.rollback_document("doc", 99).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_semantic_search() {
         let temp_dir = TempDir::new().unwrap();
@@ -428,6 +1743,97 @@ mod tests {
         let results = db.hybrid_search("编程", 5, 0.7, 0.3).await.unwrap();
         assert!(!results.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_collections_are_isolated() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), VectorDbConfig::default()).await.unwrap();
+
+        db.create_collection("project-a").await.unwrap();
+        assert!(db.create_collection("project-a").await.is_err());
+        assert!(db.create_collection("../escape").await.is_err());
+
+        let doc = Document {
+            id: "a1".to_string(),
+            content: "项目A专用的内部文档".to_string(),
+            ..Default::default()
+        };
+        db.add_document_to("project-a", doc).await.unwrap();
+        assert!(db.get_document("a1").await.unwrap().is_none());
+        assert!(db.get_document_from("project-a", "a1").await.unwrap().is_some());
+        assert!(db.text_search("内部文档", 5).await.unwrap().is_empty());
+        assert!(db.add_document_to("missing", Document::default()).await.is_err());
+
+        let collections = db.list_collections();
+        assert_eq!(collections.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec![DEFAULT_COLLECTION, "project-a"]);
+        assert_eq!(collections[1].stats.document_count, 1);
+
+        // 重新打开时恢复已有集合
+        db.save().await.unwrap();
+        drop(db);
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), VectorDbConfig::default()).await.unwrap();
+        assert_eq!(db.list_collections().len(), 2);
+        assert!(db.drop_collection("project-a").await.unwrap());
+        assert!(!db.drop_collection("project-a").await.unwrap());
+        assert!(db.drop_collection(DEFAULT_COLLECTION).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let source_dir = TempDir::new().unwrap();
+        let mut db = VectorDatabase::new(source_dir.path().to_path_buf(), VectorDbConfig::default()).await.unwrap();
+        db.create_collection("project-a").await.unwrap();
+        db.add_document(Document { id: "d1".to_string(), content: "默认集合中的文档".to_string(), ..Default::default() }).await.unwrap();
+        db.add_document_to("project-a", Document { id: "a1".to_string(), content: "项目A的文档".to_string(), ..Default::default() }).await.unwrap();
+
+        let snapshot_path = source_dir.path().join("backup.snapshot.gz");
+        let report = db.create_snapshot(&snapshot_path).await.unwrap();
+        assert_eq!(report.documents, 2);
+
+        let target_dir = TempDir::new().unwrap();
+        let mut restored = VectorDatabase::new(target_dir.path().to_path_buf(), VectorDbConfig::default()).await.unwrap();
+        restored.add_document(Document { id: "stale".to_string(), content: "恢复前的文档".to_string(), ..Default::default() }).await.unwrap();
+        restored.restore_snapshot(&snapshot_path).await.unwrap();
+        assert!(restored.get_document("d1").await.unwrap().is_some());
+        assert!(restored.get_document("stale").await.unwrap().is_none());
+        assert!(restored.get_document_from("project-a", "a1").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_retention_expires_and_evicts_documents() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = VectorDbConfig::default();
+        config.retention.ttl_seconds = Some(3600);
+        let mut db = VectorDatabase::new(temp_dir.path().to_path_buf(), config).await.unwrap();
+        let pinned = Document {
+            id: "pinned".to_string(),
+            content: "长期保留的文档".to_string(),
+            metadata: HashMap::from([(DOCUMENT_TTL_METADATA_KEY.to_string(), "0".to_string())]),
+            ..Default::default()
+        };
+        db.add_document(pinned).await.unwrap();
+        db.add_document(Document { id: "old".to_string(), content: "旧版本的文档".to_string(), ..Default::default() }).await.unwrap();
+        db.add_document(Document { id: "draft".to_string(), content: "草稿文档".to_string(), ..Default::default() }).await.unwrap();
+
+        assert_eq!(db.apply_retention().await.unwrap(), RetentionReport::default());
+        let report = db.apply_retention_at(chrono::Utc::now() + chrono::Duration::hours(2)).await.unwrap();
+        assert_eq!(report.expired_documents, 2);
+        assert!(db.get_document("pinned").await.unwrap().is_some());
+        assert!(db.get_document("old").await.unwrap().is_none());
+
+        // 超出大小上限时淘汰最久未访问的文档
+        db.config.retention.ttl_seconds = None;
+        db.add_document(Document { id: "old".to_string(), content: "旧版本的文档".to_string(), ..Default::default() }).await.unwrap();
+        db.get_document("pinned").await.unwrap();
+        let records = db.storage.list_documents(0, usize::MAX).await.unwrap();
+        db.config.retention.max_total_bytes = records.iter().find(|r| r.id == "pinned").map(record_bytes);
+        let report = db.apply_retention().await.unwrap();
+        assert_eq!((report.expired_documents, report.evicted_documents), (0, 1));
+        assert!(db.get_document("old").await.unwrap().is_none());
+        assert!(db.get_document("pinned").await.unwrap().is_some());
+        assert_eq!(db.get_metrics().evicted_documents, 1);
+        assert_eq!(db.get_metrics().expired_documents, 2);
+    }
 }
 
 // Re-export commonly used types
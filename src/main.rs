@@ -1,20 +1,11 @@
 use anyhow::Result;
-use tracing::{info, error, warn, debug};
+use clap::Parser;
+use tracing::{info, error, warn};
 use tracing_subscriber;
 use dotenv;
-use std::sync::Arc;
-use std::path::PathBuf;
-use std::{collections::HashMap, fs};
 
-mod errors;
-mod mcp;
-mod tools;
-mod versioning;
-mod cli;
-
-use mcp::server::MCPServer;
-use tools::{VectorDocsTool, EnhancedDocumentProcessor, DynamicRegistryBuilder, EnvironmentDetectionTool};
-use tools::background_cacher::{BackgroundDocCacher, DocCacherConfig};
+use grape_mcp_devtools::{cli, mcp};
+use grape_mcp_devtools::tools::background_cacher::DocCacherConfig;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -26,151 +17,33 @@ async fn main() -> Result<()> {
         .with_env_filter(std::env::var("RUST_LOG").unwrap_or_else(|_| "grape_mcp_devtools=info,background_cacher=debug".to_string()))
         .init();
 
-    info!("🚀 启动 Grape MCP DevTools 服务器...");
-
-    let base_data_path = std::env::current_dir()?.join(".mcp_cache");
-    let vector_store_path = base_data_path.join("vector_store");
-    fs::create_dir_all(&vector_store_path).map_err(|e| anyhow::anyhow!("创建向量存储目录失败: {:?} - {}", vector_store_path, e))?;
-
-    let vector_tool = Arc::new(
-        VectorDocsTool::new()
-            .map_err(|e| anyhow::anyhow!("初始化 VectorDocsTool 失败: {}", e))?
-    );
-    let enhanced_processor = Arc::new(
-        EnhancedDocumentProcessor::new(Arc::clone(&vector_tool)).await
-            .map_err(|e| anyhow::anyhow!("初始化 EnhancedDocumentProcessor 失败: {}", e))?
-    );
-
-    // 创建工具安装配置
-    let install_config = cli::ToolInstallConfig::default();
-
-    // 创建动态工具注册器
-    let mut registry = DynamicRegistryBuilder::new()
-        .with_policy(tools::RegistrationPolicy::Adaptive { score_threshold: 0.3 })
-        .add_scan_path(std::env::current_dir()?)
-        .with_shared_doc_processor(Arc::clone(&enhanced_processor))
-        .with_config_path(base_data_path.join("registry_config.json")) // 为registry指定配置路径
-        .build();
-
-    // 启用工具自动安装功能
-    registry.enable_auto_install(install_config);
-
-    info!("🔍 执行环境检测和动态工具注册...");
-    
-    // 执行动态注册（包含自动工具安装）
-    let (registration_report, detection_report_option) = match registry.auto_register().await {
-        Ok((report, detection_opt)) => {
-            info!("✅ 动态注册完成！");
-            info!("📊 注册报告:");
-            info!("   - 注册工具: {} 个", report.registered_tools.len());
-            info!("   - 失败注册: {} 个", report.failed_registrations.len());
-            info!("   - 注册评分: {:.1}%", report.registration_score * 100.0);
-            info!("   - 注册耗时: {}ms", report.registration_duration_ms);
-            info!("   - 自动安装: {}", if report.auto_install_enabled { "启用" } else { "禁用" });
-            
-            for tool in &report.registered_tools {
-                info!("   ✅ {}", tool);
-            }
-            
-            for (tool, error) in &report.failed_registrations {
-                warn!("   ❌ {} - {}", tool, error);
-            }
-
-            // 显示缺失工具信息
-            if !report.missing_tools_detected.is_empty() {
-                info!("🔧 检测到缺失的文档工具:");
-                for (language, tools) in &report.missing_tools_detected {
-                    info!("   {} -> [{}]", language, tools.join(", "));
-                }
-            }
-
-            // 显示工具安装报告
-            if let Some(install_report) = &report.tool_installation_report {
-                info!("📦 工具安装报告:");
-                if !install_report.installed.is_empty() {
-                    info!("   ✅ 成功安装: [{}]", install_report.installed.join(", "));
-                }
-                if !install_report.failed.is_empty() {
-                    info!("   ❌ 安装失败:");
-                    for (tool, error) in &install_report.failed {
-                        info!("      {} - {}", tool, error);
-                    }
-                }
-                if !install_report.skipped.is_empty() {
-                    info!("   ⏭️ 跳过安装: [{}]", install_report.skipped.join(", "));
-                }
-            }
-
-            (report, detection_opt)
-        }
-        Err(e) => {
-            error!("❌ 动态注册失败: {}", e);
-            return Err(e);
-        }
-    };
-
-    if let Some(detection_report) = detection_report_option {
-        if !detection_report.detected_languages.is_empty() {
-            info!("ℹ️ 环境检测到项目依赖，准备启动后台文档缓存...");
-            let cacher_config = DocCacherConfig { enabled: true, concurrent_tasks: 2 }; // 示例配置
-            let doc_cacher = BackgroundDocCacher::new(
-                cacher_config,
-                Arc::clone(&enhanced_processor),
-                Arc::clone(&vector_tool),
-            );
-            
-            // 直接将 detection_report.detected_languages (HashMap<String, tools::environment_detector::LanguageInfo>) 传递
-            if let Err(e) = doc_cacher.queue_dependencies_for_caching(&detection_report.detected_languages).await {
-                warn!("启动后台文档缓存失败: {}", e);
-            }
-        } else {
-            info!("环境检测未发现任何语言的依赖，跳过后台文档缓存。");
-        }
-    } else {
-        info!("动态注册未返回环境检测报告，无法启动后台文档缓存。");
-    }
-
-    // 检查工具升级
-    info!("⬆️ 检查工具升级...");
-    if let Err(e) = registry.check_and_upgrade_tools().await {
-        warn!("⚠️ 升级检查失败: {}", e);
+    // 带子命令时执行离线缓存管理，不启动服务器
+    let cli_args = cli::commands::Cli::parse();
+    if let Some(command) = cli_args.command {
+        return cli::commands::run_command(command).await;
     }
-
-    // 创建MCP服务器实例
-    let mcp_server = MCPServer::new();
-
-    // 从注册器获取已注册的工具并添加到服务器
-    info!("🔧 将动态注册的工具添加到MCP服务器...");
-    let mut dynamic_tools_count = 0;
-    
-    for (tool_name, tool_arc) in registry.get_registered_tools() {
-        if mcp_server.register_tool_arc(Arc::clone(tool_arc)).await.is_ok() {
-            info!("✅ 工具已添加到MCP服务器: {}", tool_name);
-            dynamic_tools_count += 1;
-        } else {
-            warn!("⚠️ 添加动态工具 {} 到MCP服务器失败", tool_name);
-        }
+    if cli_args.repl {
+        return cli::repl::run_repl().await;
     }
 
-    // 手动注册基础工具
-    let base_tools: Vec<Box<dyn tools::MCPTool>> = vec![
-        Box::new(tools::SearchDocsTool::new()),
-        Box::new(EnvironmentDetectionTool::new()), // Ensure this is tools::EnvironmentDetectionTool
-        Box::new(tools::CheckVersionTool::new()),
-        // VectorDocsTool本身也可以是一个MCP工具，如果它的execute方法被设计为如此
-        // 但我们这里主要通过 BackgroundCacher 和 EnhancedDocumentProcessor 间接使用其功能
-        // 如果需要MCP接口直接操作VectorStore，可以取消注释下面这行，并确保它实现了MCPTool
-        // Box::new(VectorDocsTool::new(vector_store_path.clone())?), 
-    ];
+    info!("🚀 启动 Grape MCP DevTools 服务器...");
 
-    for tool in base_tools {
-        let name = tool.name().to_string();
-        if mcp_server.register_tool(tool).await.is_ok() {
-            info!("✅ 基础工具已添加到服务器: {}", name);
-        } else {
-            warn!("⚠️ 添加基础工具 {} 到MCP服务器失败", name);
-        }
-    }
+    // 使用默认数据目录和两级向量缓存，与离线子命令和 REPL 管理同一份缓存
+    let grape_server = mcp::GrapeServerBuilder::new()
+        .with_background_caching(Some(DocCacherConfig { enabled: true, concurrent_tasks: 2 }))
+        .with_idle_hibernation(cli_args.daemon.then(|| std::time::Duration::from_secs(cli_args.idle_timeout)))
+        .with_shutdown_timeout(std::time::Duration::from_secs(cli_args.shutdown_timeout))
+        .with_transport(cli_args.server_transport())
+        .build()
+        .await
+        .map_err(|e| {
+            error!("❌ 服务器初始化失败: {}", e);
+            e
+        })?;
+    let mcp_server = grape_server.mcp_server();
+    let dynamic_tools_count = grape_server.registry()
+        .map(|registry| registry.get_registered_tools().len())
+        .unwrap_or(0);
 
     let tool_count = mcp_server.get_tool_count().await?;
     info!("📋 服务器工具总数: {} (动态注册: {}, 基础工具: {})", 
@@ -228,21 +101,16 @@ async fn main() -> Result<()> {
     }
 
     // 显示动态注册统计信息
-    let stats = registry.get_statistics().await;
-    info!("📈 动态注册统计:");
-    for (key, value) in stats {
-        info!("   - {}: {}", key, value);
+    if let Some(registry) = grape_server.registry() {
+        let stats = registry.get_statistics().await;
+        info!("📈 动态注册统计:");
+        for (key, value) in stats {
+            info!("   - {}: {}", key, value);
+        }
     }
 
-    // 创建并运行完整的MCP服务器
-    let mut server = mcp::server::Server::new(
-        "grape-mcp-devtools".to_string(),
-        env!("CARGO_PKG_VERSION").to_string(),
-        mcp_server,
-    );
-
     info!("🌐 启动MCP服务器...");
-    server.run().await?;
+    grape_server.run().await?;
 
     Ok(())
 } 
\ No newline at end of file
@@ -0,0 +1,213 @@
+//! 工具调用审计日志
+//!
+//! 启用后每次工具调用追加一行 JSON 到审计文件（只追加，不改写已有记录），记录工具名、
+//! 参数哈希、调用会话与调用方、关联ID、耗时和结果。参数只保存 SHA-256 哈希，避免把查询内容
+//! 或文档正文写入审计文件。
+//!
+//! 记录通过管理工具 `audit_log` 查询（网络传输启用认证时需要 `admin` 范围）。
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+/// 审计文件的默认文件名（位于数据目录下）
+pub const DEFAULT_AUDIT_FILE: &str = "audit.jsonl";
+
+/// 查询未指定数量时返回的记录数
+pub const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// 工具调用结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Error,
+    Timeout,
+    /// 授权失败或只读模式拒绝，工具未执行
+    Denied,
+}
+
+impl AuditOutcome {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "success" => Some(AuditOutcome::Success),
+            "error" => Some(AuditOutcome::Error),
+            "timeout" => Some(AuditOutcome::Timeout),
+            "denied" => Some(AuditOutcome::Denied),
+            _ => None,
+        }
+    }
+}
+
+/// 一条审计记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub tool: String,
+    /// 参数的 SHA-256（十六进制）
+    pub params_hash: String,
+    /// 调用会话
+    pub session: String,
+    /// 认证通过的密钥名称（未启用认证时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    pub duration_ms: u64,
+    pub outcome: AuditOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    pub fn new(tool: &str, params: &Value, session: &str, duration: Duration, outcome: AuditOutcome) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            tool: tool.to_string(),
+            params_hash: params_hash(params),
+            session: session.to_string(),
+            principal: None,
+            correlation_id: super::correlation::current(),
+            duration_ms: duration.as_millis() as u64,
+            outcome,
+            error: None,
+        }
+    }
+
+    pub fn with_principal(mut self, principal: Option<&str>) -> Self {
+        self.principal = principal.map(str::to_string);
+        self
+    }
+
+    pub fn with_error(mut self, error: Option<String>) -> Self {
+        self.error = error;
+        self
+    }
+}
+
+/// 参数的 SHA-256（serde_json 按键排序输出对象，相同参数得到相同哈希）
+pub fn params_hash(params: &Value) -> String {
+    format!("{:x}", Sha256::digest(params.to_string().as_bytes()))
+}
+
+/// 查询条件，未设置的条件不过滤
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub tool: Option<String>,
+    pub session: Option<String>,
+    pub outcome: Option<AuditOutcome>,
+    pub since: Option<DateTime<Utc>>,
+    /// 最多返回的记录数，默认 [`DEFAULT_QUERY_LIMIT`]
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.tool.as_ref().map_or(true, |tool| &record.tool == tool)
+            && self.session.as_ref().map_or(true, |session| &record.session == session)
+            && self.outcome.map_or(true, |outcome| record.outcome == outcome)
+            && self.since.map_or(true, |since| record.timestamp >= since)
+    }
+}
+
+/// 追加写入的 JSONL 审计日志
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// 打开（不存在时创建）审计文件
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { path, file: Mutex::new(file) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条记录；写入失败只记录警告，不影响工具调用
+    pub fn record(&self, record: &AuditRecord) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("序列化审计记录失败: {}", e);
+                return;
+            }
+        };
+        let mut file = self.file.lock();
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            warn!("写入审计日志 {:?} 失败: {}", self.path, e);
+        }
+    }
+
+    /// 按条件查询，最新的记录在前；无法解析的行会跳过
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>> {
+        // 持有写锁，避免读到写了一半的行
+        let _guard = self.file.lock();
+        let reader = BufReader::new(File::open(&self.path)?);
+        let mut records: Vec<AuditRecord> = reader
+            .lines()
+            .map_while(|line| line.ok())
+            .filter_map(|line| serde_json::from_str::<AuditRecord>(&line).ok())
+            .filter(|record| query.matches(record))
+            .collect();
+        records.reverse();
+        records.truncate(query.limit.unwrap_or(DEFAULT_QUERY_LIMIT));
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_records_are_appended_and_queryable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit").join(DEFAULT_AUDIT_FILE);
+        let log = AuditLog::open(&path).unwrap();
+
+        let params = json!({ "action": "search", "query": "tokio" });
+        log.record(&AuditRecord::new("vector_docs", &params, "s1", Duration::from_millis(12), AuditOutcome::Success));
+        log.record(
+            &AuditRecord::new("vector_docs", &json!({ "action": "store" }), "s2", Duration::ZERO, AuditOutcome::Denied)
+                .with_principal(Some("ci")),
+        );
+        log.record(
+            &AuditRecord::new("check_latest_version", &json!({}), "s1", Duration::from_secs(15), AuditOutcome::Timeout)
+                .with_error(Some("超时".to_string())),
+        );
+
+        // 重新打开后继续追加，已有记录保留
+        drop(log);
+        let log = AuditLog::open(&path).unwrap();
+        let all = log.query(&AuditQuery::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].tool, "check_latest_version");
+        assert_eq!(all[2].params_hash, params_hash(&params));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("tokio"));
+
+        let session = log.query(&AuditQuery { session: Some("s1".to_string()), limit: Some(1), ..Default::default() }).unwrap();
+        assert_eq!(session.len(), 1);
+        assert_eq!(session[0].outcome, AuditOutcome::Timeout);
+
+        let denied = log.query(&AuditQuery { outcome: Some(AuditOutcome::Denied), ..Default::default() }).unwrap();
+        assert_eq!(denied[0].principal.as_deref(), Some("ci"));
+    }
+}
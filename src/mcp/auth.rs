@@ -0,0 +1,440 @@
+//! 网络传输的 API 密钥认证
+//!
+//! HTTP / WebSocket 传输在配置了 API 密钥后，每个请求都必须携带
+//! `Authorization: Bearer <token>`。每个密钥有一组授权范围：
+//! - `read`：搜索、查询、读取资源；
+//! - `write`：写入、删除、导入文档（包含 `read`）；
+//! - `admin`：管理工具，例如查询审计日志（包含 `write`）。
+//!
+//! 团队共享部署时每个成员使用自己的密钥：密钥可以配置私有命名空间（见 [`super::namespace`]）
+//! 和每分钟调用上限，超过上限的调用以 `RATE_LIMITED` 错误拒绝。
+//!
+//! 认证失败按原因计数，每个密钥的调用次数、错误数和各工具调用数按密钥名称统计，
+//! 通过 `get_stats` 的 `auth` 字段查看。stdio 传输不做认证。
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::config::ApiKeyConfig;
+
+/// 授权范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AuthScope {
+    Read,
+    Write,
+    Admin,
+}
+
+impl AuthScope {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "read" | "search" => Some(AuthScope::Read),
+            "write" | "store" => Some(AuthScope::Write),
+            "admin" => Some(AuthScope::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthScope::Read => "read",
+            AuthScope::Write => "write",
+            AuthScope::Admin => "admin",
+        }
+    }
+}
+
+/// 认证通过的调用方
+#[derive(Debug, Clone, PartialEq)]
+pub struct Principal {
+    /// 密钥名称
+    pub name: String,
+    scopes: Vec<AuthScope>,
+    /// 私有文档命名空间，None 表示使用共享集合
+    pub namespace: Option<String>,
+    rate_limit_per_minute: Option<u32>,
+}
+
+impl Principal {
+    pub fn new(name: impl Into<String>, scopes: Vec<AuthScope>) -> Self {
+        Self { name: name.into(), scopes, namespace: None, rate_limit_per_minute: None }
+    }
+
+    pub fn with_namespace(mut self, namespace: Option<String>) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    /// 每分钟调用上限，0 视为不限制
+    pub fn with_rate_limit(mut self, per_minute: Option<u32>) -> Self {
+        self.rate_limit_per_minute = per_minute.filter(|limit| *limit > 0);
+        self
+    }
+
+    pub fn rate_limit_per_minute(&self) -> Option<u32> {
+        self.rate_limit_per_minute
+    }
+
+    /// 是否拥有指定范围，`admin` 包含 `write`，`write` 包含 `read`
+    pub fn allows(&self, scope: AuthScope) -> bool {
+        self.scopes.iter().any(|granted| *granted >= scope)
+    }
+}
+
+/// 一次请求的认证上下文：校验器和认证通过的调用方
+pub type AuthContext = (Arc<Authenticator>, Principal);
+
+/// 认证失败原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthFailure {
+    /// 未提供 Bearer 令牌
+    MissingToken,
+    /// 令牌不匹配任何密钥
+    InvalidToken,
+    /// 密钥缺少所需范围
+    InsufficientScope,
+    /// 超过密钥的每分钟调用上限
+    RateLimited,
+}
+
+impl AuthFailure {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthFailure::MissingToken => "missing_token",
+            AuthFailure::InvalidToken => "invalid_token",
+            AuthFailure::InsufficientScope => "insufficient_scope",
+            AuthFailure::RateLimited => "rate_limited",
+        }
+    }
+}
+
+/// 认证计数
+#[derive(Debug, Default)]
+struct AuthMetrics {
+    accepted: AtomicU64,
+    missing_token: AtomicU64,
+    invalid_token: AtomicU64,
+    insufficient_scope: AtomicU64,
+    rate_limited: AtomicU64,
+}
+
+impl AuthMetrics {
+    fn counter(&self, failure: AuthFailure) -> &AtomicU64 {
+        match failure {
+            AuthFailure::MissingToken => &self.missing_token,
+            AuthFailure::InvalidToken => &self.invalid_token,
+            AuthFailure::InsufficientScope => &self.insufficient_scope,
+            AuthFailure::RateLimited => &self.rate_limited,
+        }
+    }
+}
+
+/// 令牌桶：容量为每分钟上限，按上限/60 每秒匀速补充
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(per_minute: u32) -> Self {
+        Self { tokens: per_minute as f64, refilled_at: Instant::now() }
+    }
+
+    fn try_take(&mut self, per_minute: u32) -> bool {
+        let now = Instant::now();
+        let capacity = per_minute as f64;
+        let refill = now.duration_since(self.refilled_at).as_secs_f64() * capacity / 60.0;
+        self.tokens = (self.tokens + refill).min(capacity);
+        self.refilled_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// 单个密钥的使用统计
+#[derive(Debug, Default)]
+struct UserUsage {
+    calls: u64,
+    errors: u64,
+    rate_limited: u64,
+    total_duration: Duration,
+    tools: HashMap<String, u64>,
+    last_call: Option<DateTime<Utc>>,
+}
+
+impl UserUsage {
+    fn snapshot(&self) -> Value {
+        json!({
+            "calls": self.calls,
+            "errors": self.errors,
+            "rate_limited": self.rate_limited,
+            "total_duration_ms": self.total_duration.as_millis() as u64,
+            "tools": self.tools,
+            "last_call": self.last_call,
+        })
+    }
+}
+
+/// Bearer 令牌校验器
+#[derive(Debug)]
+pub struct Authenticator {
+    keys: Vec<(String, Principal)>,
+    metrics: AuthMetrics,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+    usage: Mutex<HashMap<String, UserUsage>>,
+}
+
+/// 从 `Authorization` 头中取出 Bearer 令牌
+pub fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// 逐字节比较，耗时与第一个不同字节的位置无关
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+impl Authenticator {
+    /// 按配置创建；没有配置任何密钥时返回 None（不启用认证）
+    pub fn from_config(keys: &[ApiKeyConfig]) -> Option<Self> {
+        let keys: Vec<(String, Principal)> = keys
+            .iter()
+            .filter(|key| !key.token.is_empty())
+            .map(|key| {
+                let scopes = key
+                    .scopes
+                    .iter()
+                    .filter_map(|scope| {
+                        let parsed = AuthScope::parse(scope);
+                        if parsed.is_none() {
+                            warn!("API密钥 {} 的授权范围 {} 无效，已忽略", key.name, scope);
+                        }
+                        parsed
+                    })
+                    .collect();
+                let principal = Principal::new(key.name.clone(), scopes)
+                    .with_namespace(key.namespace.clone().filter(|ns| !ns.trim().is_empty()))
+                    .with_rate_limit(key.rate_limit_per_minute);
+                (key.token.clone(), principal)
+            })
+            .collect();
+        (!keys.is_empty()).then(|| Self {
+            keys,
+            metrics: AuthMetrics::default(),
+            buckets: Mutex::new(HashMap::new()),
+            usage: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 校验 `Authorization` 头，返回对应的调用方
+    pub fn authenticate(&self, authorization: Option<&str>) -> std::result::Result<Principal, AuthFailure> {
+        let Some(token) = authorization.and_then(bearer_token) else {
+            return Err(self.reject(AuthFailure::MissingToken, None));
+        };
+        // 比较所有密钥，避免通过耗时推断命中位置
+        let matched = self
+            .keys
+            .iter()
+            .fold(None, |found, (key, principal)| {
+                if constant_time_eq(key.as_bytes(), token.as_bytes()) {
+                    Some(principal)
+                } else {
+                    found
+                }
+            });
+        match matched {
+            Some(principal) => {
+                self.metrics.accepted.fetch_add(1, Ordering::Relaxed);
+                Ok(principal.clone())
+            }
+            None => Err(self.reject(AuthFailure::InvalidToken, None)),
+        }
+    }
+
+    /// 检查调用方是否拥有所需范围
+    pub fn authorize(&self, principal: &Principal, scope: AuthScope, target: &str) -> std::result::Result<(), AuthFailure> {
+        if principal.allows(scope) {
+            return Ok(());
+        }
+        Err(self.reject(AuthFailure::InsufficientScope, Some((principal, scope, target))))
+    }
+
+    /// 按密钥的每分钟上限消耗一次调用名额，没有上限时总是通过
+    pub fn check_rate_limit(&self, principal: &Principal, target: &str) -> std::result::Result<(), AuthFailure> {
+        let Some(per_minute) = principal.rate_limit_per_minute else {
+            return Ok(());
+        };
+        let allowed = self
+            .buckets
+            .lock()
+            .entry(principal.name.clone())
+            .or_insert_with(|| TokenBucket::full(per_minute))
+            .try_take(per_minute);
+        if allowed {
+            return Ok(());
+        }
+        self.usage.lock().entry(principal.name.clone()).or_default().rate_limited += 1;
+        self.metrics.rate_limited.fetch_add(1, Ordering::Relaxed);
+        warn!("🔒 密钥 {} 超过每分钟 {} 次的调用上限，目标 {}", principal.name, per_minute, target);
+        Err(AuthFailure::RateLimited)
+    }
+
+    /// 记录一次已执行的工具调用
+    pub fn record_usage(&self, principal: &Principal, tool_name: &str, success: bool, duration: Duration) {
+        let mut usage = self.usage.lock();
+        let user = usage.entry(principal.name.clone()).or_default();
+        user.calls += 1;
+        if !success {
+            user.errors += 1;
+        }
+        user.total_duration += duration;
+        *user.tools.entry(tool_name.to_string()).or_default() += 1;
+        user.last_call = Some(Utc::now());
+    }
+
+    fn reject(&self, failure: AuthFailure, denied: Option<(&Principal, AuthScope, &str)>) -> AuthFailure {
+        self.metrics.counter(failure).fetch_add(1, Ordering::Relaxed);
+        match denied {
+            Some((principal, scope, target)) => {
+                warn!("🔒 认证失败 ({}): 密钥 {} 缺少 {} 范围，目标 {}", failure.as_str(), principal.name, scope.as_str(), target)
+            }
+            None => warn!("🔒 认证失败 ({})", failure.as_str()),
+        }
+        failure
+    }
+
+    /// 认证计数和各密钥使用统计快照
+    pub fn metrics(&self) -> Value {
+        let usage = self.usage.lock();
+        let users: serde_json::Map<String, Value> = self
+            .keys
+            .iter()
+            .map(|(_, principal)| {
+                let mut snapshot = usage.get(&principal.name).map(UserUsage::snapshot).unwrap_or_else(|| UserUsage::default().snapshot());
+                snapshot["namespace"] = json!(principal.namespace);
+                snapshot["rate_limit_per_minute"] = json!(principal.rate_limit_per_minute);
+                (principal.name.clone(), snapshot)
+            })
+            .collect();
+        json!({
+            "keys": self.keys.len(),
+            "accepted": self.metrics.accepted.load(Ordering::Relaxed),
+            "failures": {
+                "missing_token": self.metrics.missing_token.load(Ordering::Relaxed),
+                "invalid_token": self.metrics.invalid_token.load(Ordering::Relaxed),
+                "insufficient_scope": self.metrics.insufficient_scope.load(Ordering::Relaxed),
+                "rate_limited": self.metrics.rate_limited.load(Ordering::Relaxed),
+            },
+            "users": users,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authenticator() -> Authenticator {
+        Authenticator::from_config(&[
+            ApiKeyConfig {
+                name: "ide".to_string(),
+                token: "read-token".to_string(),
+                scopes: vec!["read".to_string()],
+                namespace: None,
+                rate_limit_per_minute: None,
+            },
+            ApiKeyConfig {
+                name: "ci".to_string(),
+                token: "write-token".to_string(),
+                scopes: vec!["write".to_string()],
+                namespace: None,
+                rate_limit_per_minute: None,
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_authenticate_bearer_tokens() {
+        let auth = authenticator();
+        assert_eq!(auth.authenticate(Some("Bearer read-token")).unwrap().name, "ide");
+        assert_eq!(auth.authenticate(Some("bearer write-token")).unwrap().name, "ci");
+        assert_eq!(auth.authenticate(None), Err(AuthFailure::MissingToken));
+        assert_eq!(auth.authenticate(Some("Basic read-token")), Err(AuthFailure::MissingToken));
+        assert_eq!(auth.authenticate(Some("Bearer wrong")), Err(AuthFailure::InvalidToken));
+
+        let metrics = auth.metrics();
+        assert_eq!(metrics["accepted"], 2);
+        assert_eq!(metrics["failures"]["missing_token"], 2);
+        assert_eq!(metrics["failures"]["invalid_token"], 1);
+    }
+
+    #[test]
+    fn test_scopes() {
+        let auth = authenticator();
+        let reader = auth.authenticate(Some("Bearer read-token")).unwrap();
+        let writer = auth.authenticate(Some("Bearer write-token")).unwrap();
+        assert!(auth.authorize(&reader, AuthScope::Read, "search_docs").is_ok());
+        assert_eq!(auth.authorize(&reader, AuthScope::Write, "vector_docs"), Err(AuthFailure::InsufficientScope));
+        assert!(auth.authorize(&writer, AuthScope::Read, "search_docs").is_ok());
+        assert!(auth.authorize(&writer, AuthScope::Write, "vector_docs").is_ok());
+        assert_eq!(auth.metrics()["failures"]["insufficient_scope"], 1);
+
+        assert!(Authenticator::from_config(&[]).is_none());
+
+        // 环境变量格式，未指定范围时只读
+        let key = ApiKeyConfig::parse("bot:tok").unwrap();
+        assert_eq!(key.scopes, vec!["read"]);
+        assert_eq!(ApiKeyConfig::parse("ci:tok:read+write").unwrap().scopes, vec!["read", "write"]);
+        assert!(ApiKeyConfig::parse("no-token").is_none());
+    }
+
+    #[test]
+    fn test_namespaces_rate_limits_and_usage() {
+        let key = ApiKeyConfig::parse("alice:alice-token:write:alice").unwrap();
+        assert_eq!(key.namespace.as_deref(), Some("alice"));
+        let auth = Authenticator::from_config(&[
+            ApiKeyConfig { rate_limit_per_minute: Some(2), ..key },
+            ApiKeyConfig::parse("bob:bob-token").unwrap(),
+        ])
+        .unwrap();
+
+        let alice = auth.authenticate(Some("Bearer alice-token")).unwrap();
+        let bob = auth.authenticate(Some("Bearer bob-token")).unwrap();
+        assert_eq!(alice.namespace.as_deref(), Some("alice"));
+        assert_eq!(bob.namespace, None);
+
+        // 容量为每分钟上限，第三次立即调用被拒绝；没有上限的密钥不受影响
+        assert!(auth.check_rate_limit(&alice, "search_docs").is_ok());
+        assert!(auth.check_rate_limit(&alice, "search_docs").is_ok());
+        assert_eq!(auth.check_rate_limit(&alice, "search_docs"), Err(AuthFailure::RateLimited));
+        for _ in 0..10 {
+            assert!(auth.check_rate_limit(&bob, "search_docs").is_ok());
+        }
+
+        auth.record_usage(&alice, "search_docs", true, Duration::from_millis(20));
+        auth.record_usage(&alice, "vector_docs", false, Duration::from_millis(5));
+        let metrics = auth.metrics();
+        assert_eq!(metrics["failures"]["rate_limited"], 1);
+        let usage = &metrics["users"]["alice"];
+        assert_eq!(usage["calls"], 2);
+        assert_eq!(usage["errors"], 1);
+        assert_eq!(usage["rate_limited"], 1);
+        assert_eq!(usage["tools"]["search_docs"], 1);
+        assert_eq!(usage["namespace"], "alice");
+        assert_eq!(metrics["users"]["bob"]["calls"], 0);
+    }
+}
@@ -0,0 +1,535 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use tokio::sync::RwLock;
+
+use crate::cli::ToolInstallConfig;
+use crate::config::{AuditConfig, HttpTransportConfig, ToolExecutionConfig, WebhookConfig};
+use crate::mcp::audit::{AuditLog, DEFAULT_AUDIT_FILE};
+use crate::mcp::http::{self, HttpTransportState};
+use crate::mcp::resources::DocResources;
+use crate::mcp::ws::{self, WsTransportState};
+use crate::mcp::server::{MCPServer, Server};
+use crate::mcp::shutdown::{self, ShutdownCoordinator, DEFAULT_SHUTDOWN_TIMEOUT};
+use crate::tools::{self, DynamicRegistryBuilder, DynamicToolRegistry, EnhancedDocumentProcessor, MCPTool, RegistrationPolicy, VectorDocsTool};
+use crate::tools::background_cacher::{BackgroundDocCacher, DocCacherConfig};
+use crate::tools::crawl_report::CrawlReportStore;
+use crate::tools::data_lock::LockPolicy;
+use crate::tools::cache_webhook::CacheWebhook;
+use crate::tools::dynamic_registry::RegistrationReport;
+use crate::tools::embedder::DEFAULT_HEALTH_CHECK_INTERVAL;
+use crate::tools::project_context::ProjectProfile;
+
+/// 服务器传输方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerTransport {
+    /// 通过 stdin/stdout 交换每行一个的JSON消息
+    Stdio,
+    /// Streamable HTTP（可选TLS），多个客户端共享同一实例
+    Http(HttpTransportConfig),
+    /// WebSocket（可选TLS），每个连接独立会话，共享同一组工具
+    WebSocket(HttpTransportConfig),
+}
+
+/// 服务器构建器
+///
+/// 以编程方式组装向量存储、文档处理器、动态注册器、后台缓存和传输层，
+/// 默认配置与 `grape-mcp-devtools` 可执行文件保持一致。
+pub struct GrapeServerBuilder {
+    name: String,
+    version: String,
+    data_dir: Option<PathBuf>,
+    scan_paths: Vec<PathBuf>,
+    policy: RegistrationPolicy,
+    dynamic_registration: bool,
+    auto_install: Option<ToolInstallConfig>,
+    check_upgrades: bool,
+    background_caching: Option<DocCacherConfig>,
+    include_base_tools: bool,
+    extra_tools: Vec<Arc<dyn MCPTool>>,
+    vector_tool: Option<Arc<VectorDocsTool>>,
+    tool_timeout: Option<Duration>,
+    read_only: Option<bool>,
+    idle_hibernation: Option<Duration>,
+    embedding_health_interval: Option<Duration>,
+    shutdown_timeout: Duration,
+    transport: ServerTransport,
+}
+
+impl Default for GrapeServerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GrapeServerBuilder {
+    pub fn new() -> Self {
+        Self {
+            name: "grape-mcp-devtools".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            data_dir: None,
+            scan_paths: Vec::new(),
+            policy: RegistrationPolicy::Adaptive { score_threshold: 0.3 },
+            dynamic_registration: true,
+            auto_install: Some(ToolInstallConfig::default()),
+            check_upgrades: true,
+            background_caching: Some(DocCacherConfig::default()),
+            include_base_tools: true,
+            extra_tools: Vec::new(),
+            vector_tool: None,
+            tool_timeout: None,
+            read_only: None,
+            idle_hibernation: None,
+            embedding_health_interval: Some(DEFAULT_HEALTH_CHECK_INTERVAL),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            transport: ServerTransport::Stdio,
+        }
+    }
+
+    /// 设置服务器名称和版本（在 initialize 响应中返回）
+    pub fn with_server_info(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.name = name.into();
+        self.version = version.into();
+        self
+    }
+
+    /// 设置数据目录，默认 `<当前目录>/.mcp_cache`
+    ///
+    /// 显式设置时向量存储位于 `<数据目录>/vector_store`（单层缓存）；
+    /// 未设置时向量存储按 `GRAPE_GLOBAL_CACHE_DIR` / `VECTOR_STORAGE_PATH` 使用两级缓存。
+    pub fn with_data_dir(mut self, data_dir: PathBuf) -> Self {
+        self.data_dir = Some(data_dir);
+        self
+    }
+
+    /// 添加环境检测的扫描路径，未设置时扫描当前目录
+    pub fn add_scan_path(mut self, path: PathBuf) -> Self {
+        self.scan_paths.push(path);
+        self
+    }
+
+    pub fn with_policy(mut self, policy: RegistrationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// 是否执行环境检测和动态工具注册
+    pub fn with_dynamic_registration(mut self, enabled: bool) -> Self {
+        self.dynamic_registration = enabled;
+        self
+    }
+
+    /// 设置缺失文档工具的自动安装配置，None 表示禁用
+    pub fn with_auto_install(mut self, config: Option<ToolInstallConfig>) -> Self {
+        self.auto_install = config;
+        self
+    }
+
+    /// 启动时是否检查工具升级
+    pub fn with_upgrade_check(mut self, enabled: bool) -> Self {
+        self.check_upgrades = enabled;
+        self
+    }
+
+    /// 设置后台文档缓存配置，None 表示禁用
+    pub fn with_background_caching(mut self, config: Option<DocCacherConfig>) -> Self {
+        self.background_caching = config;
+        self
+    }
+
+    /// 是否注册基础工具（search_docs、check_latest_version 等）
+    pub fn with_base_tools(mut self, enabled: bool) -> Self {
+        self.include_base_tools = enabled;
+        self
+    }
+
+    /// 注册额外的自定义工具
+    pub fn add_tool(mut self, tool: Arc<dyn MCPTool>) -> Self {
+        self.extra_tools.push(tool);
+        self
+    }
+
+    /// 使用已有的向量文档工具（例如与宿主程序共享）
+    pub fn with_vector_tool(mut self, vector_tool: Arc<VectorDocsTool>) -> Self {
+        self.vector_tool = Some(vector_tool);
+        self
+    }
+
+    /// 设置工具执行的默认超时
+    pub fn with_tool_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
+    /// 是否以只读模式运行，覆盖配置文件和 `GRAPE_READ_ONLY`
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = Some(read_only);
+        self
+    }
+
+    /// 守护进程模式：空闲超过指定时间后释放向量索引和缓存，下次请求时自动重新加载
+    pub fn with_idle_hibernation(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_hibernation = idle_timeout;
+        self
+    }
+
+    /// 运行期间嵌入服务健康检查的间隔，默认 5 分钟，None 表示只在启动时检查
+    pub fn with_embedding_health_check(mut self, interval: Option<Duration>) -> Self {
+        self.embedding_health_interval = interval;
+        self
+    }
+
+    /// 关闭时等待进行中的请求和后台缓存任务的最长时间，默认 30 秒
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    pub fn with_transport(mut self, transport: ServerTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// 组装服务器
+    pub async fn build(self) -> Result<GrapeServer> {
+        let explicit_data_dir = self.data_dir.is_some();
+        let data_dir = match self.data_dir {
+            Some(dir) => dir,
+            None => std::env::current_dir()?.join(".mcp_cache"),
+        };
+        // 审计日志、注册配置和抓取报告写在数据目录下
+        std::fs::create_dir_all(&data_dir)
+            .map_err(|e| anyhow::anyhow!("创建数据目录失败: {:?} - {}", data_dir, e))?;
+
+        let vector_tool = match self.vector_tool {
+            Some(tool) => tool,
+            None => {
+                let opened = if explicit_data_dir {
+                    let vector_store_path = data_dir.join("vector_store");
+                    std::fs::create_dir_all(&vector_store_path)
+                        .map_err(|e| anyhow::anyhow!("创建向量存储目录失败: {:?} - {}", vector_store_path, e))?;
+                    VectorDocsTool::open_data_dir(vector_store_path)
+                } else {
+                    VectorDocsTool::new()
+                };
+                Arc::new(opened.map_err(|e| anyhow::anyhow!("初始化 VectorDocsTool 失败: {}", e))?)
+            }
+        };
+        let doc_processor = Arc::new(
+            EnhancedDocumentProcessor::new(Arc::clone(&vector_tool)).await
+                .map_err(|e| anyhow::anyhow!("初始化 EnhancedDocumentProcessor 失败: {}", e))?
+        );
+
+        // 启动时验证嵌入服务的密钥、模型和维度，结果显示在系统状态中
+        if let Some(health) = vector_tool.check_embedding_health().await {
+            if !health.is_healthy() {
+                warn!("⚠️ 嵌入服务检查未通过，向量搜索和文档入库可能失败，详见系统状态");
+            }
+            if let Some(interval) = self.embedding_health_interval {
+                vector_tool.spawn_embedding_health_checks(interval);
+            }
+        }
+
+        if let Some(idle_timeout) = self.idle_hibernation {
+            info!("💤 启用空闲休眠: 空闲 {:?} 后释放向量索引", idle_timeout);
+            vector_tool.spawn_idle_hibernation(idle_timeout);
+        }
+
+        let mut limits = ToolExecutionConfig::load();
+        if let Some(timeout) = self.tool_timeout {
+            limits.default_timeout_ms = timeout.as_millis() as u64;
+        }
+        if let Some(read_only) = self.read_only {
+            limits.read_only = read_only;
+        }
+        // 只读副本由中心写入实例负责抓取和入库，本实例只提供查询
+        let replica = LockPolicy::from_env() == LockPolicy::Replica;
+        if replica {
+            info!("📖 只读副本模式: 定期从共享缓存目录重新加载，不执行后台缓存");
+            limits.read_only = true;
+        }
+        if limits.read_only {
+            info!("🔒 只读模式: 已禁用写入、删除和导入操作");
+        }
+        let mut mcp_server = MCPServer::with_limits(limits);
+        let shutdown = ShutdownCoordinator::new();
+        mcp_server.set_shutdown(shutdown.clone());
+        // 已缓存文档通过 resources/* 暴露
+        mcp_server.set_resources(Arc::new(DocResources::new(Arc::clone(&vector_tool))));
+
+        let audit_config = AuditConfig::load();
+        let audit = if audit_config.enabled {
+            let path = audit_config.path.map_or_else(|| data_dir.join(DEFAULT_AUDIT_FILE), PathBuf::from);
+            let audit = Arc::new(AuditLog::open(&path)
+                .map_err(|e| anyhow::anyhow!("打开审计日志失败: {:?} - {}", path, e))?);
+            info!("📝 工具调用审计日志: {:?}", path);
+            mcp_server.set_audit_log(Arc::clone(&audit));
+            Some(audit)
+        } else {
+            None
+        };
+
+        let mut registry = None;
+        let mut registration_report = None;
+        if self.dynamic_registration {
+            let scan_paths = if self.scan_paths.is_empty() {
+                vec![std::env::current_dir()?]
+            } else {
+                self.scan_paths
+            };
+            let mut builder = DynamicRegistryBuilder::new()
+                .with_policy(self.policy)
+                .with_shared_doc_processor(Arc::clone(&doc_processor))
+                .with_config_path(data_dir.join("registry_config.json"));
+            for path in scan_paths {
+                builder = builder.add_scan_path(path);
+            }
+            let mut dynamic_registry = builder.build();
+            if let Some(install_config) = self.auto_install {
+                dynamic_registry.enable_auto_install(install_config);
+            }
+
+            info!("🔍 执行环境检测和动态工具注册...");
+            let (report, detection_report) = dynamic_registry.auto_register().await?;
+            log_registration_report(&report);
+
+            if let Some(detection_report) = &detection_report {
+                let profile = ProjectProfile::from_report(detection_report);
+                if !profile.is_empty() {
+                    info!("📌 按项目依赖调整搜索排序");
+                    vector_tool.set_project_profile(Some(profile));
+                }
+            }
+
+            if let (Some(cacher_config), Some(detection_report)) = (self.background_caching, detection_report) {
+                if cacher_config.enabled && !replica && !detection_report.detected_languages.is_empty() {
+                    info!("ℹ️ 环境检测到项目依赖，准备启动后台文档缓存...");
+                    let doc_cacher = BackgroundDocCacher::new(
+                        cacher_config,
+                        Arc::clone(&doc_processor),
+                        Arc::clone(&vector_tool),
+                    )
+                    .with_shutdown(shutdown.clone())
+                    .with_reports(CrawlReportStore::new(data_dir.join("reports")))
+                    .with_webhook(CacheWebhook::from_config(&WebhookConfig::load()).map(Arc::new));
+                    if let Err(e) = doc_cacher.queue_dependencies_for_caching(&detection_report.detected_languages).await {
+                        warn!("启动后台文档缓存失败: {}", e);
+                    }
+                }
+            }
+
+            if self.check_upgrades {
+                info!("⬆️ 检查工具升级...");
+                if let Err(e) = dynamic_registry.check_and_upgrade_tools().await {
+                    warn!("⚠️ 升级检查失败: {}", e);
+                }
+            }
+
+            for (tool_name, tool_arc) in dynamic_registry.get_registered_tools() {
+                if mcp_server.register_tool_arc(Arc::clone(tool_arc)).await.is_ok() {
+                    info!("✅ 工具已添加到MCP服务器: {}", tool_name);
+                } else {
+                    warn!("⚠️ 添加动态工具 {} 到MCP服务器失败", tool_name);
+                }
+            }
+
+            registry = Some(dynamic_registry);
+            registration_report = Some(report);
+        }
+
+        let mut static_tools: Vec<Arc<dyn MCPTool>> = Vec::new();
+        if self.include_base_tools {
+            static_tools.push(Arc::new(tools::SearchDocsTool::new().with_doc_processor(Arc::clone(&doc_processor))));
+            static_tools.push(Arc::new(tools::EnvironmentDetectionTool::new()));
+            static_tools.push(Arc::new(tools::CheckVersionTool::new()));
+            static_tools.push(Arc::new(tools::ExportContextBundleTool::new(Arc::clone(&vector_tool))));
+            static_tools.push(Arc::new(tools::ExplainErrorTool::new(Arc::clone(&vector_tool))));
+            static_tools.push(Arc::new(tools::GetCrawlReportTool::new(CrawlReportStore::new(data_dir.join("reports")))));
+            static_tools.push(Arc::new(tools::VectorSnapshotTool::new(Arc::clone(&vector_tool))));
+            static_tools.push(Arc::new(tools::DocCoverageTool::new(Arc::clone(&vector_tool))));
+            static_tools.push(Arc::new(tools::AnswerValidationTool::new(Arc::clone(&vector_tool))));
+        }
+        if let Some(audit) = audit {
+            static_tools.push(Arc::new(tools::AuditLogTool::new(audit)));
+        }
+        static_tools.extend(self.extra_tools);
+
+        for tool in static_tools {
+            let name = tool.name().to_string();
+            if mcp_server.register_tool_arc(tool).await.is_ok() {
+                info!("✅ 基础工具已添加到服务器: {}", name);
+            } else {
+                warn!("⚠️ 添加基础工具 {} 到MCP服务器失败", name);
+            }
+        }
+
+        Ok(GrapeServer {
+            name: self.name,
+            version: self.version,
+            transport: self.transport,
+            mcp_server,
+            vector_tool,
+            doc_processor,
+            registry,
+            registration_report,
+            shutdown,
+            shutdown_timeout: self.shutdown_timeout,
+        })
+    }
+}
+
+/// 组装完成的服务器
+pub struct GrapeServer {
+    name: String,
+    version: String,
+    transport: ServerTransport,
+    mcp_server: MCPServer,
+    vector_tool: Arc<VectorDocsTool>,
+    doc_processor: Arc<EnhancedDocumentProcessor>,
+    registry: Option<DynamicToolRegistry>,
+    registration_report: Option<RegistrationReport>,
+    shutdown: ShutdownCoordinator,
+    shutdown_timeout: Duration,
+}
+
+impl GrapeServer {
+    /// 工具服务器，可在进程内直接调用工具
+    pub fn mcp_server(&self) -> &MCPServer {
+        &self.mcp_server
+    }
+
+    pub fn vector_tool(&self) -> Arc<VectorDocsTool> {
+        Arc::clone(&self.vector_tool)
+    }
+
+    pub fn doc_processor(&self) -> Arc<EnhancedDocumentProcessor> {
+        Arc::clone(&self.doc_processor)
+    }
+
+    /// 动态工具注册器（未启用动态注册时为 None）
+    pub fn registry(&self) -> Option<&DynamicToolRegistry> {
+        self.registry.as_ref()
+    }
+
+    pub fn registration_report(&self) -> Option<&RegistrationReport> {
+        self.registration_report.as_ref()
+    }
+
+    /// 关闭协调器，调用 `trigger` 可在进程内发起优雅关闭
+    pub fn shutdown(&self) -> ShutdownCoordinator {
+        self.shutdown.clone()
+    }
+
+    /// 按配置的传输方式运行服务器，直到连接关闭或收到 SIGINT / SIGTERM
+    ///
+    /// 关闭时停止接受新请求，在截止时间内等待进行中的请求和后台缓存任务结束，
+    /// 然后将向量存储落盘。
+    pub async fn run(self) -> Result<()> {
+        let shutdown = self.shutdown.clone();
+        let shutdown_timeout = self.shutdown_timeout;
+        let vector_tool = Arc::clone(&self.vector_tool);
+
+        let signal_shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            shutdown::wait_for_signal().await;
+            signal_shutdown.trigger();
+        });
+
+        let mut serving = Box::pin(self.serve_transport());
+        let finished = tokio::select! {
+            result = &mut serving => Some(result),
+            _ = shutdown.triggered() => None,
+        };
+        let deadline = tokio::time::Instant::now() + shutdown_timeout;
+        let result = match finished {
+            Some(result) => result,
+            None => {
+                info!("🛑 开始优雅关闭，最多等待 {:?}", shutdown_timeout);
+                match tokio::time::timeout_at(deadline, &mut serving).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        warn!("⚠️ 传输层在 {:?} 内未结束，放弃仍在进行的请求", shutdown_timeout);
+                        Ok(())
+                    }
+                }
+            }
+        };
+        drop(serving);
+
+        // 传输层自行结束（如 stdio 客户端断开）时同样停止排队中的后台任务
+        shutdown.trigger();
+        if shutdown.in_flight() > 0 {
+            info!("⏳ 等待 {} 项进行中的工作结束", shutdown.in_flight());
+            if !shutdown.drain(deadline.saturating_duration_since(tokio::time::Instant::now())).await {
+                warn!("⚠️ 关闭截止时间已到，仍有 {} 项工作未结束", shutdown.in_flight());
+            }
+        }
+
+        match vector_tool.flush() {
+            Ok(()) => info!("💾 向量存储已落盘"),
+            Err(e) => error!("❌ 关闭时保存向量存储失败: {}", e),
+        }
+        info!("👋 服务器已关闭");
+        result
+    }
+
+    async fn serve_transport(self) -> Result<()> {
+        match self.transport {
+            ServerTransport::Stdio => {
+                let mut server = Server::new(self.name, self.version, self.mcp_server);
+                server.run().await
+            }
+            ServerTransport::Http(config) => {
+                let state = HttpTransportState::new(self.name, self.version, Arc::new(RwLock::new(self.mcp_server)), config);
+                http::serve(state).await
+            }
+            ServerTransport::WebSocket(config) => {
+                let state = WsTransportState::new(self.name, self.version, Arc::new(RwLock::new(self.mcp_server)), config);
+                ws::serve(state).await
+            }
+        }
+    }
+}
+
+fn log_registration_report(report: &RegistrationReport) {
+    info!("✅ 动态注册完成！");
+    info!("📊 注册报告:");
+    info!("   - 注册工具: {} 个", report.registered_tools.len());
+    info!("   - 失败注册: {} 个", report.failed_registrations.len());
+    info!("   - 注册评分: {:.1}%", report.registration_score * 100.0);
+    info!("   - 注册耗时: {}ms", report.registration_duration_ms);
+    info!("   - 自动安装: {}", if report.auto_install_enabled { "启用" } else { "禁用" });
+
+    for tool in &report.registered_tools {
+        info!("   ✅ {}", tool);
+    }
+    for (tool, error) in &report.failed_registrations {
+        warn!("   ❌ {} - {}", tool, error);
+    }
+
+    if !report.missing_tools_detected.is_empty() {
+        info!("🔧 检测到缺失的文档工具:");
+        for (language, tools) in &report.missing_tools_detected {
+            info!("   {} -> [{}]", language, tools.join(", "));
+        }
+    }
+
+    if let Some(install_report) = &report.tool_installation_report {
+        info!("📦 工具安装报告:");
+        if !install_report.installed.is_empty() {
+            info!("   ✅ 成功安装: [{}]", install_report.installed.join(", "));
+        }
+        if !install_report.failed.is_empty() {
+            info!("   ❌ 安装失败:");
+            for (tool, error) in &install_report.failed {
+                info!("      {} - {}", tool, error);
+            }
+        }
+        if !install_report.skipped.is_empty() {
+            info!("   ⏭️ 跳过安装: [{}]", install_report.skipped.join(", "));
+        }
+    }
+}
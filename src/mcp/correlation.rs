@@ -0,0 +1,83 @@
+//! 请求关联ID
+//!
+//! 每个 MCP 请求分配一个关联ID：记录在 `mcp_request` tracing span 上（工具执行、嵌入调用、
+//! 提供商 HTTP 请求的 span 都嵌套在其中），通过 `X-Correlation-ID` 请求头转发给上游，
+//! 并在响应的 `correlationId` 字段中返回，方便把客户端看到的失败和服务端日志对应起来。
+//!
+//! 客户端可以在 `params._meta.correlationId` 中自带关联ID，否则由服务器生成。
+//! 关联ID保存在 tokio task-local 中，`tokio::spawn` 出去的任务不会继承。
+
+use serde_json::Value;
+use std::future::Future;
+
+/// 转发给上游服务的请求头
+pub const HEADER: &str = "X-Correlation-ID";
+
+/// 客户端自带关联ID的最大长度
+const MAX_CLIENT_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: String;
+}
+
+/// 生成新的关联ID
+pub fn generate() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// 读取客户端在 `params._meta.correlationId` 中提供的关联ID（只接受可打印 ASCII 且不超长的值）
+pub fn from_params(params: &Value) -> Option<String> {
+    let id = params.get("_meta")?.get("correlationId")?.as_str()?.trim();
+    let valid = !id.is_empty() && id.len() <= MAX_CLIENT_ID_LEN && id.chars().all(|c| c.is_ascii_graphic());
+    valid.then(|| id.to_string())
+}
+
+/// 当前任务所属请求的关联ID
+pub fn current() -> Option<String> {
+    CURRENT.try_with(|id| id.clone()).ok()
+}
+
+/// 在指定关联ID下执行 `future`
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    CURRENT.scope(id, future).await
+}
+
+/// 给发往上游的 HTTP 请求附加关联ID请求头
+pub trait Correlated {
+    fn correlated(self) -> Self;
+}
+
+impl Correlated for reqwest::RequestBuilder {
+    fn correlated(self) -> Self {
+        match current() {
+            Some(id) => self.header(HEADER, id),
+            None => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_supplied_id_is_validated() {
+        let params = serde_json::json!({ "_meta": { "correlationId": "ide-42" } });
+        assert_eq!(from_params(&params).as_deref(), Some("ide-42"));
+
+        let invalid = serde_json::json!({ "_meta": { "correlationId": "has space\n" } });
+        assert!(from_params(&invalid).is_none());
+        let too_long = serde_json::json!({ "_meta": { "correlationId": "x".repeat(MAX_CLIENT_ID_LEN + 1) } });
+        assert!(from_params(&too_long).is_none());
+        assert!(from_params(&serde_json::json!({})).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scope_exposes_current_id() {
+        assert!(current().is_none());
+        let id = generate();
+        let seen = scope(id.clone(), async { current() }).await;
+        assert_eq!(seen, Some(id));
+        assert!(current().is_none());
+    }
+}
@@ -0,0 +1,500 @@
+//! Streamable HTTP 传输
+//!
+//! 按 MCP 2025-03-26 的 Streamable HTTP 约定，在单个端点（默认 `/mcp`）上：
+//! - `POST` 接收 JSON-RPC 请求（单个或批量），根据 `Accept` 返回 JSON 或 SSE 流；
+//! - `GET` 打开 SSE 流，接收服务器推送的通知；
+//! - `DELETE` 结束会话。
+//!
+//! 以 SSE 返回的 `tools/call` 若在 `_meta.progressToken` 中提供了令牌，
+//! 结果列表会先以 `notifications/tools/partialResult` 分块写入同一个流。
+//!
+//! `initialize` 请求创建会话并通过 `Mcp-Session-Id` 响应头返回会话ID，
+//! 后续请求必须携带该头。多个 IDE 客户端共享同一组工具，各自拥有独立的会话状态。
+//!
+//! 配置了 API 密钥时，每个请求都要携带 `Authorization: Bearer <token>`，
+//! 缺少或无效时返回 401，密钥缺少工具所需的授权范围时工具调用返回错误。
+//!
+//! 服务器开始关闭时停止接受新连接并关闭所有会话的通知流，进行中的请求处理完后退出。
+
+use anyhow::{anyhow, Result};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response as HttpResponse};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::stream;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
+use tracing::{debug, info, warn};
+
+use crate::config::HttpTransportConfig;
+use super::auth::{AuthContext, Authenticator};
+use super::resources::{next_update, ResourceNotifier, ResourceSubscriptions};
+use super::server::{MCPServer, Server};
+use super::{protocol, Response};
+
+/// 会话ID请求/响应头
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// 单个客户端会话
+struct HttpSession {
+    server: Mutex<Server>,
+    last_seen: parking_lot::Mutex<Instant>,
+    notifications: broadcast::Sender<String>,
+    subscriptions: ResourceSubscriptions,
+}
+
+impl HttpSession {
+    fn touch(&self) {
+        *self.last_seen.lock() = Instant::now();
+    }
+}
+
+/// HTTP 传输共享状态
+#[derive(Clone)]
+pub struct HttpTransportState {
+    name: String,
+    version: String,
+    mcp_server: Arc<RwLock<MCPServer>>,
+    sessions: Arc<RwLock<HashMap<String, Arc<HttpSession>>>>,
+    config: Arc<HttpTransportConfig>,
+    /// API 密钥校验器，未配置密钥时为 None
+    auth: Option<Arc<Authenticator>>,
+}
+
+impl HttpTransportState {
+    pub fn new(name: String, version: String, mcp_server: Arc<RwLock<MCPServer>>, config: HttpTransportConfig) -> Self {
+        Self {
+            name,
+            version,
+            mcp_server,
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            auth: Authenticator::from_config(&config.api_keys).map(Arc::new),
+            config: Arc::new(config),
+        }
+    }
+
+    /// 校验请求的 Bearer 令牌，返回会话处理请求时使用的认证上下文
+    fn authenticate(&self, headers: &HeaderMap) -> std::result::Result<Option<AuthContext>, HttpResponse> {
+        let Some(auth) = &self.auth else {
+            return Ok(None);
+        };
+        let authorization = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+        match auth.authenticate(authorization) {
+            Ok(principal) => Ok(Some((auth.clone(), principal))),
+            Err(failure) => {
+                let mut response = json_rpc_error(StatusCode::UNAUTHORIZED, -32001, &format!("认证失败: {}", failure.as_str()));
+                response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+                Err(response)
+            }
+        }
+    }
+
+    pub async fn session_count(&self) -> usize {
+        self.sessions.read().await.len()
+    }
+
+    /// 移除所有会话，会话的 GET 通知流随之结束
+    async fn close_sessions(&self) {
+        let closed = std::mem::take(&mut *self.sessions.write().await);
+        if !closed.is_empty() {
+            info!("🔌 关闭 {} 个HTTP会话", closed.len());
+        }
+    }
+
+    /// 向所有会话的 GET 流推送通知
+    pub async fn broadcast_notification(&self, notification: &Value) {
+        let message = notification.to_string();
+        for session in self.sessions.read().await.values() {
+            // 没有打开 GET 流的会话会返回错误，忽略即可
+            let _ = session.notifications.send(message.clone());
+        }
+    }
+
+    async fn create_session(&self) -> (String, Arc<HttpSession>) {
+        let session_id = uuid::Uuid::new_v4().simple().to_string();
+        let (notifications, _) = broadcast::channel(64);
+        let mut server = Server::with_shared(self.name.clone(), self.version.clone(), self.mcp_server.clone());
+        server.set_session_id(session_id.clone());
+        let session = Arc::new(HttpSession {
+            subscriptions: server.resource_subscriptions(),
+            server: Mutex::new(server),
+            last_seen: parking_lot::Mutex::new(Instant::now()),
+            notifications,
+        });
+        self.sessions.write().await.insert(session_id.clone(), session.clone());
+        info!("🔗 新建HTTP会话: {}", session_id);
+        (session_id, session)
+    }
+
+    async fn session(&self, headers: &HeaderMap) -> std::result::Result<Arc<HttpSession>, HttpResponse> {
+        let session_id = headers
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| json_rpc_error(StatusCode::BAD_REQUEST, -32600, "缺少 Mcp-Session-Id 请求头，请先发送 initialize"))?;
+        let session = self.sessions.read().await.get(session_id).cloned();
+        let session = session.ok_or_else(|| json_rpc_error(StatusCode::NOT_FOUND, -32001, "会话不存在或已过期，请重新 initialize"))?;
+        session.touch();
+        Ok(session)
+    }
+
+    /// 把缓存更新转发为资源通知：列表变化发给所有会话，更新只发给订阅者
+    async fn forward_resource_updates(&self) {
+        let Some(resources) = self.mcp_server.read().await.resources() else {
+            return;
+        };
+        let mut notifier = match ResourceNotifier::new(&resources) {
+            Ok(notifier) => notifier,
+            Err(e) => {
+                warn!("读取已缓存包版本失败，不转发资源通知: {}", e);
+                return;
+            }
+        };
+        let mut updates = Some(resources.subscribe_updates());
+        loop {
+            let update = next_update(&mut updates).await;
+            let (uri, list_changed) = notifier.observe(&update);
+            for session in self.sessions.read().await.values() {
+                let subscriptions = session.subscriptions.lock().clone();
+                for notification in ResourceNotifier::notifications(&uri, list_changed, &subscriptions) {
+                    let _ = session.notifications.send(notification.to_string());
+                }
+            }
+        }
+    }
+
+    /// 回收空闲超时的会话
+    async fn expire_idle_sessions(&self) {
+        let timeout = Duration::from_secs(self.config.session_idle_timeout_secs);
+        let mut sessions = self.sessions.write().await;
+        let before = sessions.len();
+        sessions.retain(|_, session| session.last_seen.lock().elapsed() < timeout);
+        if sessions.len() < before {
+            info!("🧹 回收 {} 个空闲HTTP会话", before - sessions.len());
+        }
+    }
+}
+
+fn json_rpc_error(status: StatusCode, code: i32, message: &str) -> HttpResponse {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": Value::Null,
+        "error": { "code": code, "message": message },
+    });
+    (status, Json(body)).into_response()
+}
+
+/// 校验 Origin，防止 DNS 重绑定攻击
+pub(super) fn origin_allowed(config: &HttpTransportConfig, headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN).and_then(|o| o.to_str().ok()) else {
+        return true;
+    };
+    if config.allowed_origins.iter().any(|allowed| allowed == "*" || allowed == origin) {
+        return true;
+    }
+    config.allowed_origins.is_empty() && is_local_origin(origin)
+}
+
+fn is_local_origin(origin: &str) -> bool {
+    let host = origin.split_once("://").map_or(origin, |(_, rest)| rest);
+    let host = host
+        .rsplit_once(':')
+        .filter(|(_, port)| port.chars().all(|c| c.is_ascii_digit()))
+        .map_or(host, |(host, _)| host);
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |accept| accept.contains("text/event-stream"))
+}
+
+fn accepts_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map_or(true, |accept| accept.contains("application/json") || accept.contains("*/*"))
+}
+
+async fn process_messages(session: Arc<HttpSession>, messages: Vec<Value>, auth: Option<AuthContext>) -> Vec<Response> {
+    let mut server = session.server.lock().await;
+    server.set_auth_context(auth);
+    dispatch_messages(&mut server, messages).await
+}
+
+/// 依次处理一个会话中的请求消息；通知和客户端响应（没有 id 或 method）不产生响应
+pub(super) async fn dispatch_messages(server: &mut Server, messages: Vec<Value>) -> Vec<Response> {
+    let mut responses = Vec::new();
+    for message in messages {
+        if message.get("id").is_none() || message.get("method").is_none() {
+            continue;
+        }
+        match protocol::parse_request_value(message) {
+            Ok(request) => responses.push(server.handle_request(request).await),
+            Err(error_response) => responses.push(error_response),
+        }
+    }
+    responses
+}
+
+fn with_session_header(mut response: HttpResponse, session_id: Option<&str>) -> HttpResponse {
+    if let Some(value) = session_id.and_then(|id| HeaderValue::from_str(id).ok()) {
+        response.headers_mut().insert(SESSION_HEADER, value);
+    }
+    response
+}
+
+async fn handle_post(State(state): State<HttpTransportState>, headers: HeaderMap, body: String) -> HttpResponse {
+    if !origin_allowed(&state.config, &headers) {
+        return json_rpc_error(StatusCode::FORBIDDEN, -32600, "Origin 不被允许");
+    }
+    let auth = match state.authenticate(&headers) {
+        Ok(auth) => auth,
+        Err(response) => return response,
+    };
+
+    let value: Value = match serde_json::from_str(&body) {
+        Ok(value) => value,
+        Err(e) => return json_rpc_error(StatusCode::BAD_REQUEST, -32700, &format!("Parse error: {}", e)),
+    };
+    let (messages, is_batch) = match value {
+        Value::Array(items) => (items, true),
+        other => (vec![other], false),
+    };
+
+    let is_initialize = messages.iter().any(|m| m["method"] == "initialize");
+    let (session_id, session) = if is_initialize {
+        let (session_id, session) = state.create_session().await;
+        (Some(session_id), session)
+    } else {
+        match state.session(&headers).await {
+            Ok(session) => (None, session),
+            Err(response) => return response,
+        }
+    };
+
+    if !messages.iter().any(|m| m.get("id").is_some() && m.get("method").is_some()) {
+        // 只有通知或响应时返回 202
+        return with_session_header(StatusCode::ACCEPTED.into_response(), session_id.as_deref());
+    }
+
+    // 工具调用可能耗时较长，客户端接受 SSE 时以流的形式返回，期间发送保活
+    let has_tool_call = messages.iter().any(|m| m["method"].as_str().map_or(false, |method| method.starts_with("tools/")));
+    let use_stream = accepts_event_stream(&headers) && (has_tool_call || !accepts_json(&headers));
+    debug!("HTTP请求: {} 条消息, 流式响应: {}", messages.len(), use_stream);
+
+    let response = if use_stream {
+        // 部分结果通知和最终响应经同一通道按顺序写入 SSE 流
+        let (sender, receiver) = mpsc::unbounded_channel::<Value>();
+        tokio::spawn(async move {
+            let mut server = session.server.lock().await;
+            server.set_auth_context(auth);
+            server.set_partial_result_sender(Some(sender.clone()));
+            let responses = dispatch_messages(&mut server, messages).await;
+            server.set_partial_result_sender(None);
+            for response in responses {
+                let _ = sender.send(serde_json::to_value(&response).unwrap_or_default());
+            }
+        });
+        let events = stream::unfold(receiver, |mut receiver| async move {
+            let message = receiver.recv().await?;
+            Some((Ok::<_, Infallible>(Event::default().event("message").data(message.to_string())), receiver))
+        });
+        Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+    } else {
+        let mut responses = process_messages(session, messages, auth).await;
+        if is_batch {
+            Json(responses).into_response()
+        } else {
+            match responses.pop() {
+                Some(response) => Json(response).into_response(),
+                None => StatusCode::ACCEPTED.into_response(),
+            }
+        }
+    };
+    with_session_header(response, session_id.as_deref())
+}
+
+async fn handle_get(State(state): State<HttpTransportState>, headers: HeaderMap) -> HttpResponse {
+    if !origin_allowed(&state.config, &headers) {
+        return json_rpc_error(StatusCode::FORBIDDEN, -32600, "Origin 不被允许");
+    }
+    if let Err(response) = state.authenticate(&headers) {
+        return response;
+    }
+    if !accepts_event_stream(&headers) {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+    let session = match state.session(&headers).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    let receiver = session.notifications.subscribe();
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(message) => return Some((Ok::<_, Infallible>(Event::default().event("message").data(message)), receiver)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("SSE通知流落后，丢弃 {} 条通知", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+async fn handle_delete(State(state): State<HttpTransportState>, headers: HeaderMap) -> HttpResponse {
+    if let Err(response) = state.authenticate(&headers) {
+        return response;
+    }
+    let Some(session_id) = headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok()) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    match state.sessions.write().await.remove(session_id) {
+        Some(_) => {
+            info!("🔌 HTTP会话已结束: {}", session_id);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// 构建 HTTP 路由
+pub fn router(state: HttpTransportState) -> Router {
+    let endpoint = state.config.endpoint_path.clone();
+    Router::new()
+        .route(&endpoint, get(handle_get).post(handle_post).delete(handle_delete))
+        .route("/health", get(|| async { "ok" }))
+        .with_state(state)
+}
+
+/// 启动 HTTP(S) 传输并阻塞直到服务器开始关闭且进行中的请求全部结束
+pub async fn serve(state: HttpTransportState) -> Result<()> {
+    let config = state.config.clone();
+    let addr: SocketAddr = config
+        .bind_address
+        .parse()
+        .map_err(|e| anyhow!("无效的监听地址 {}: {}", config.bind_address, e))?;
+    if config.tls.is_none() && !addr.ip().is_loopback() {
+        warn!("⚠️ HTTP传输监听在非本机地址 {} 且未启用TLS，请求内容将以明文传输", addr);
+    }
+
+    let reaper = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            reaper.expire_idle_sessions().await;
+        }
+    });
+
+    let forwarder = state.clone();
+    tokio::spawn(async move { forwarder.forward_resource_updates().await });
+
+    let coordinator = state.mcp_server.read().await.shutdown();
+    let shutdown_state = state.clone();
+    let shutdown_signal = async move {
+        coordinator.triggered().await;
+        info!("🛑 服务器正在关闭，停止接受新的HTTP连接");
+        shutdown_state.close_sessions().await;
+    };
+
+    let app = router(state);
+    match &config.tls {
+        Some(tls) => {
+            let rustls = axum_server::tls_rustls::RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+                .await
+                .map_err(|e| anyhow!("加载TLS证书失败 ({}, {}): {}", tls.cert_path, tls.key_path, e))?;
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown_signal.await;
+                shutdown_handle.graceful_shutdown(None);
+            });
+            info!("🔒 MCP HTTPS 传输已启动: https://{}{}", addr, config.endpoint_path);
+            axum_server::bind_rustls(addr, rustls).handle(handle).serve(app.into_make_service()).await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            info!("🌐 MCP HTTP 传输已启动: http://{}{}", addr, config.endpoint_path);
+            axum::serve(listener, app).with_graceful_shutdown(shutdown_signal).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_validation() {
+        let mut headers = HeaderMap::new();
+        let config = HttpTransportConfig::default();
+        assert!(origin_allowed(&config, &headers));
+
+        headers.insert(header::ORIGIN, HeaderValue::from_static("http://localhost:3000"));
+        assert!(origin_allowed(&config, &headers));
+
+        headers.insert(header::ORIGIN, HeaderValue::from_static("https://evil.example"));
+        assert!(!origin_allowed(&config, &headers));
+
+        let config = HttpTransportConfig {
+            allowed_origins: vec!["https://evil.example".to_string()],
+            ..Default::default()
+        };
+        assert!(origin_allowed(&config, &headers));
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let state = HttpTransportState::new(
+            "test".to_string(),
+            "1.0.0".to_string(),
+            Arc::new(RwLock::new(MCPServer::new())),
+            HttpTransportConfig::default(),
+        );
+        let (session_id, _) = state.create_session().await;
+        assert_eq!(state.session_count().await, 1);
+
+        let mut headers = HeaderMap::new();
+        assert!(state.session(&headers).await.is_err());
+        headers.insert(SESSION_HEADER, HeaderValue::from_str(&session_id).unwrap());
+        assert!(state.session(&headers).await.is_ok());
+
+        let response = handle_delete(State(state.clone()), headers).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(state.session_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_requests_require_bearer_token_when_keys_configured() {
+        let config = HttpTransportConfig {
+            api_keys: vec![crate::config::ApiKeyConfig::parse("ide:secret").unwrap()],
+            ..Default::default()
+        };
+        let state = HttpTransportState::new("test".to_string(), "1.0.0".to_string(), Arc::new(RwLock::new(MCPServer::new())), config);
+        let body = json!({ "jsonrpc": "2.0", "id": "1", "method": "initialize", "params": {} }).to_string();
+
+        let response = handle_post(State(state.clone()), HeaderMap::new(), body.clone()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+        assert_eq!(state.session_count().await, 0);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        let response = handle_post(State(state.clone()), headers, body).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(SESSION_HEADER));
+    }
+}
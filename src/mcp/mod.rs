@@ -1,8 +1,8 @@
 use serde::{Serialize, Deserialize};
 
 
-/// MCP 协议版本
-pub const MCP_VERSION: &str = "2025-03-26";
+/// MCP 协议版本（支持的最新版本，旧版本见 [`negotiation::ProtocolVersion`]）
+pub const MCP_VERSION: &str = "2025-06-18";
 
 /// MCP 服务器的功能列表
 pub const SERVER_CAPABILITIES: &[&str] = &[
@@ -10,6 +10,9 @@ pub const SERVER_CAPABILITIES: &[&str] = &[
     "apiExamples",        // API 示例
     "versionInfo",        // 版本信息
     "compatibilityCheck", // 兼容性检查
+    "resources",          // 已缓存文档资源（列出、读取、订阅）
+    "prompts",            // 可复用的提示词模板
+    "partialResults",     // 搜索结果分块推送（tools/call 提供 _meta.progressToken 时）
 ];
 
 /// MCP 请求
@@ -17,7 +20,8 @@ pub const SERVER_CAPABILITIES: &[&str] = &[
 pub struct Request {
     /// JSON-RPC 版本
     pub jsonrpc: String,
-    /// 协议版本号
+    /// 协议版本号（标准 MCP 客户端不发送该字段）
+    #[serde(default)]
     pub version: String,
     /// 请求 ID
     pub id: String,
@@ -42,6 +46,9 @@ pub struct Response {
     /// 错误信息
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<ErrorResponse>,
+    /// 请求关联ID（与服务端日志中的 `correlation_id` 字段对应）
+    #[serde(rename = "correlationId", skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
 }
 
 /// MCP 错误响应
@@ -57,28 +64,56 @@ pub struct ErrorResponse {
 }
 
 /// MCP 初始化参数
-#[derive(Debug, Deserialize)]
+///
+/// 同时接受标准字段（`protocolVersion`、`clientInfo`）和旧版字段（`client_name`、`client_version`）。
+#[derive(Debug, Default, Deserialize)]
 pub struct InitializeParams {
-    /// 客户端名称
-    pub client_name: String,
-    /// 客户端版本
-    pub client_version: String,
-    /// 请求的功能列表
+    /// 客户端请求的协议版本
+    #[serde(default, rename = "protocolVersion", alias = "protocol_version")]
+    pub protocol_version: Option<String>,
+    /// 客户端信息
+    #[serde(default, rename = "clientInfo")]
+    pub client_info: Option<PeerInfo>,
+    /// 客户端名称（旧版字段）
+    #[serde(default)]
+    pub client_name: Option<String>,
+    /// 客户端版本（旧版字段）
     #[serde(default)]
-    pub capabilities: Vec<String>,
+    pub client_version: Option<String>,
+    /// 客户端能力（标准客户端为对象，旧版客户端为功能列表）
+    #[serde(default)]
+    pub capabilities: serde_json::Value,
+}
+
+impl InitializeParams {
+    /// 客户端名称，优先使用 `clientInfo`
+    pub fn client_name(&self) -> Option<&str> {
+        self.client_info.as_ref().map(|info| info.name.as_str()).or(self.client_name.as_deref())
+    }
+}
+
+/// 客户端或服务器的名称和版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerInfo {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
 }
 
 /// MCP 初始化结果
 #[derive(Debug, Serialize)]
 pub struct InitializeResult {
-    /// 服务器名称
-    pub server_name: String,
-    /// 服务器版本
-    pub server_version: String,
-    /// 协议版本号
+    /// 协商后的协议版本号
+    #[serde(rename = "protocolVersion")]
     pub protocol_version: String,
-    /// 服务器支持的功能列表
-    pub capabilities: Vec<String>,
+    /// 服务器能力声明
+    pub capabilities: serde_json::Value,
+    /// 服务器信息
+    #[serde(rename = "serverInfo")]
+    pub server_info: PeerInfo,
+    /// 给客户端的使用说明（2025-03-26 起支持）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
 }
 
 impl Response {
@@ -90,6 +125,7 @@ impl Response {
             id,
             result: Some(result),
             error: None,
+            correlation_id: None,
         }
     }
 
@@ -105,8 +141,18 @@ impl Response {
                 message,
                 data: None,
             }),
+            correlation_id: None,
         }
     }
+
+    /// 创建带结构化 `data` 的错误响应（错误类别、修复建议等）
+    pub fn error_with_data(id: String, code: i32, message: String, data: &crate::errors::ErrorPayload) -> Self {
+        let mut response = Self::error(id, code, message);
+        if let Some(error) = response.error.as_mut() {
+            error.data = serde_json::to_value(data).ok();
+        }
+        response
+    }
 }
 
 // 错误代码定义
@@ -123,6 +169,14 @@ pub mod error_codes {
     pub const INCOMPATIBLE_VERSION: i32 = -33002;
     pub const SEARCH_FAILED: i32 = -33003;
     pub const VECTORIZATION_FAILED: i32 = -33004;
+    /// 工具执行超时（包括等待并发名额）
+    pub const TOOL_TIMEOUT: i32 = -33005;
+    /// API 密钥缺少所需的授权范围
+    pub const PERMISSION_DENIED: i32 = -33006;
+    /// 服务器正在关闭，不再接受新请求
+    pub const SERVER_SHUTTING_DOWN: i32 = -33007;
+    /// 超过 API 密钥的每分钟调用上限
+    pub const RATE_LIMITED: i32 = -33008;
 }
 
 #[cfg(test)]
@@ -147,8 +201,81 @@ mod tests {
         assert!(err_resp.result.is_none());
         assert!(err_resp.error.is_some());
     }
+
+    #[test]
+    fn test_initialize_params_accept_standard_and_legacy_fields() {
+        let standard: InitializeParams = serde_json::from_value(serde_json::json!({
+            "protocolVersion": "2024-11-05",
+            "clientInfo": { "name": "old-ide", "version": "0.9" },
+            "capabilities": {}
+        })).unwrap();
+        assert_eq!(standard.protocol_version.as_deref(), Some("2024-11-05"));
+        assert_eq!(standard.client_name(), Some("old-ide"));
+
+        let legacy: InitializeParams = serde_json::from_value(serde_json::json!({
+            "client_name": "grape-rust-client",
+            "client_version": "0.1.0",
+            "capabilities": []
+        })).unwrap();
+        assert!(legacy.protocol_version.is_none());
+        assert_eq!(legacy.client_name(), Some("grape-rust-client"));
+
+        let request: Request = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0", "id": "1", "method": "initialize", "params": {}
+        })).unwrap();
+        assert!(request.version.is_empty());
+    }
+
+    #[test]
+    fn test_error_response_carries_remediation_data() {
+        let err = crate::errors::MCPError::ProviderError {
+            provider: "embedding".to_string(),
+            status: Some(401),
+            retry_after_secs: None,
+            message: "Unauthorized".to_string(),
+        };
+        let resp = Response::error_with_data("test-3".to_string(), error_codes::INTERNAL_ERROR, err.to_string(), &err.payload());
+        let data = serde_json::to_value(&resp).unwrap()["error"]["data"].clone();
+        assert_eq!(data["kind"], "PROVIDER_AUTH_ERROR");
+        assert_eq!(data["provider"], "embedding");
+        assert_eq!(data["recoverable"], false);
+        assert!(data["suggested_fix"].as_str().unwrap().contains("EMBEDDING_API_KEY"));
+        assert!(data.get("retry_after_secs").is_none());
+
+        let limited = crate::errors::MCPError::ProviderError {
+            provider: "stackexchange".to_string(),
+            status: Some(429),
+            retry_after_secs: Some(30),
+            message: "throttled".to_string(),
+        };
+        let payload = limited.payload();
+        assert_eq!(payload.kind, "RATE_LIMIT");
+        assert_eq!(payload.retry_after_secs, Some(30));
+        assert!(payload.recoverable);
+    }
 }
 
 pub mod server;
 pub mod protocol;
+pub mod builder;
+pub mod http;
+pub mod ws;
+pub mod resources;
+pub mod prompts;
+pub mod streaming;
+pub mod negotiation;
+pub mod auth;
+pub mod session;
+pub mod shutdown;
+pub mod correlation;
+pub mod sampling;
+pub mod audit;
+pub mod namespace;
+
+pub use builder::{GrapeServer, GrapeServerBuilder, ServerTransport};
+
+/// 使用默认配置组装服务器（与 `grape-mcp-devtools` 可执行文件一致）
+pub async fn create_server() -> anyhow::Result<GrapeServer> {
+    GrapeServerBuilder::new().build().await
+}
 
@@ -0,0 +1,92 @@
+//! 调用方的文档命名空间
+//!
+//! 团队共享部署时，每个 API 密钥可以配置一个私有命名空间。工具调用期间命名空间保存在
+//! tokio task-local 中，向量存储据此给写入的文档打上命名空间标记，并在搜索、读取和删除时
+//! 只暴露共享文档和调用方自己命名空间的文档。未认证的调用（stdio）没有命名空间，
+//! 只能看到共享文档。
+
+use std::collections::HashMap;
+use std::future::Future;
+
+/// 文档元数据中记录所属命名空间的键
+pub const NAMESPACE_METADATA_KEY: &str = "namespace";
+
+tokio::task_local! {
+    static CURRENT: Option<String>;
+}
+
+/// 当前工具调用所属的命名空间
+pub fn current() -> Option<String> {
+    CURRENT.try_with(|namespace| namespace.clone()).ok().flatten()
+}
+
+/// 在指定命名空间下执行 `future`
+pub async fn scope<F: Future>(namespace: Option<String>, future: F) -> F::Output {
+    CURRENT.scope(namespace, future).await
+}
+
+/// 文档属于 `owner` 命名空间（None 为共享）时，当前调用方能否看到
+pub fn is_visible(owner: Option<&str>) -> bool {
+    is_visible_to(owner, current().as_deref())
+}
+
+/// 文档属于 `owner` 命名空间时，`caller` 命名空间的调用方能否看到
+///
+/// task-local 只在 tokio 任务内可见，在 rayon 等其他线程上判断时先用 [`current`] 取出调用方。
+pub fn is_visible_to(owner: Option<&str>, caller: Option<&str>) -> bool {
+    match owner {
+        None => true,
+        Some(owner) => caller == Some(owner),
+    }
+}
+
+/// 按文档元数据判断当前调用方能否看到
+pub fn is_visible_metadata(metadata: &HashMap<String, String>) -> bool {
+    is_metadata_visible_to(metadata, current().as_deref())
+}
+
+/// 按文档元数据判断 `caller` 命名空间的调用方能否看到
+pub fn is_metadata_visible_to(metadata: &HashMap<String, String>, caller: Option<&str>) -> bool {
+    is_visible_to(metadata.get(NAMESPACE_METADATA_KEY).map(String::as_str), caller)
+}
+
+/// 给即将写入的文档打上当前命名空间，覆盖调用方自带的同名元数据
+pub fn tag_metadata(metadata: &mut HashMap<String, String>) {
+    match current() {
+        Some(namespace) => metadata.insert(NAMESPACE_METADATA_KEY.to_string(), namespace),
+        None => metadata.remove(NAMESPACE_METADATA_KEY),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_visibility_follows_scope() {
+        assert!(current().is_none());
+        assert!(is_visible(None));
+        assert!(!is_visible(Some("alice")));
+
+        scope(Some("alice".to_string()), async {
+            assert_eq!(current().as_deref(), Some("alice"));
+            assert!(is_visible(Some("alice")));
+            assert!(!is_visible(Some("bob")));
+            assert!(is_visible(None));
+
+            let mut metadata = HashMap::from([(NAMESPACE_METADATA_KEY.to_string(), "bob".to_string())]);
+            tag_metadata(&mut metadata);
+            assert_eq!(metadata[NAMESPACE_METADATA_KEY], "alice");
+            assert!(is_visible_metadata(&metadata));
+        }).await;
+    }
+
+    #[test]
+    fn test_visibility_for_explicit_caller() {
+        let metadata = HashMap::from([(NAMESPACE_METADATA_KEY.to_string(), "alice".to_string())]);
+        assert!(is_metadata_visible_to(&metadata, Some("alice")));
+        assert!(!is_metadata_visible_to(&metadata, Some("bob")));
+        assert!(!is_metadata_visible_to(&metadata, None));
+        assert!(is_metadata_visible_to(&HashMap::new(), None));
+    }
+}
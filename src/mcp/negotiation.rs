@@ -0,0 +1,123 @@
+//! MCP 协议版本协商
+//!
+//! `initialize` 时按客户端请求的 `protocolVersion` 选择版本：支持则原样采用，
+//! 否则回复服务器支持的最新版本，由客户端决定是否继续。之后该会话的响应按
+//! 协商出的版本做序列化兼容（去掉旧版本不认识的字段），请求解析则对所有版本保持宽松。
+
+use serde_json::{json, Value};
+
+/// 服务器支持的协议版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    /// 初版协议：HTTP+SSE 传输，无工具注解
+    V2024_11_05,
+    /// 增加 Streamable HTTP、JSON-RPC 批量请求、工具注解和 `instructions`
+    V2025_03_26,
+    /// 增加结构化工具输出（`structuredContent`/`outputSchema`）
+    V2025_06_18,
+}
+
+impl ProtocolVersion {
+    /// 最新支持的版本
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V2025_06_18;
+
+    /// 所有支持的版本（从新到旧）
+    pub const SUPPORTED: &'static [ProtocolVersion] = &[
+        ProtocolVersion::V2025_06_18,
+        ProtocolVersion::V2025_03_26,
+        ProtocolVersion::V2024_11_05,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V2024_11_05 => "2024-11-05",
+            ProtocolVersion::V2025_03_26 => "2025-03-26",
+            ProtocolVersion::V2025_06_18 => "2025-06-18",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::SUPPORTED.iter().copied().find(|v| v.as_str() == value.trim())
+    }
+
+    /// 协商版本：支持客户端请求的版本时采用它，否则（包括未提供时）使用最新版本
+    pub fn negotiate(requested: Option<&str>) -> Self {
+        requested.and_then(Self::parse).unwrap_or(Self::LATEST)
+    }
+
+    /// 工具结果中是否可以携带 `structuredContent`，工具列表中是否可以携带输出Schema
+    pub fn supports_structured_content(&self) -> bool {
+        *self >= ProtocolVersion::V2025_06_18
+    }
+
+    /// 初始化结果中是否可以携带 `instructions`
+    pub fn supports_instructions(&self) -> bool {
+        *self >= ProtocolVersion::V2025_03_26
+    }
+
+    /// 服务器能力声明，服务器自定义的功能列表放在 `experimental.grape` 中
+    pub fn server_capabilities(&self, features: &[&str]) -> Value {
+        json!({
+            "tools": { "listChanged": false },
+            "resources": { "subscribe": true, "listChanged": true },
+            "prompts": { "listChanged": false },
+            "experimental": { "grape": features },
+        })
+    }
+
+    /// 按版本调整工具列表中的单个工具描述
+    pub fn adapt_tool_info(&self, tool: &mut Value) {
+        if !self.supports_structured_content() {
+            if let Some(tool) = tool.as_object_mut() {
+                tool.remove("output_schema");
+                tool.remove("outputSchema");
+            }
+        }
+    }
+
+    /// 按版本调整 `tools/call` 的结果
+    pub fn adapt_tool_result(&self, result: &mut Value) {
+        if !self.supports_structured_content() {
+            if let Some(result) = result.as_object_mut() {
+                result.remove("structuredContent");
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_versions() {
+        assert_eq!(ProtocolVersion::negotiate(Some("2024-11-05")), ProtocolVersion::V2024_11_05);
+        assert_eq!(ProtocolVersion::negotiate(Some("2025-03-26")), ProtocolVersion::V2025_03_26);
+        // 不认识的版本和未提供版本都回复最新版本
+        assert_eq!(ProtocolVersion::negotiate(Some("2023-01-01")), ProtocolVersion::LATEST);
+        assert_eq!(ProtocolVersion::negotiate(None), ProtocolVersion::LATEST);
+        assert_eq!(ProtocolVersion::LATEST.as_str(), super::super::MCP_VERSION);
+    }
+
+    #[test]
+    fn test_older_versions_drop_structured_fields() {
+        let mut result = json!({ "content": [{ "type": "text", "text": "ok" }], "structuredContent": { "ok": true } });
+        ProtocolVersion::V2025_06_18.adapt_tool_result(&mut result);
+        assert!(result.get("structuredContent").is_some());
+        ProtocolVersion::V2025_03_26.adapt_tool_result(&mut result);
+        assert!(result.get("structuredContent").is_none());
+
+        let mut tool = json!({ "name": "search_docs", "output_schema": { "type": "object" } });
+        ProtocolVersion::V2024_11_05.adapt_tool_info(&mut tool);
+        assert!(tool.get("output_schema").is_none());
+
+        let capabilities = ProtocolVersion::V2024_11_05.server_capabilities(&["prompts"]);
+        assert_eq!(capabilities["experimental"]["grape"], json!(["prompts"]));
+    }
+}
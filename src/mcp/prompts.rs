@@ -0,0 +1,153 @@
+//! MCP 提示词：把 `ai::prompt_templates` 中的模板暴露为 `prompts/list` 和 `prompts/get`
+//!
+//! MCP 的提示词消息只有 user/assistant 角色，模板的系统提示词合并到第一条 user 消息中。
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::ai::prompt_templates::{DevToolPrompts, DocumentPrompts};
+use crate::errors::MCPError;
+
+/// 提示词参数
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptArgument {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+}
+
+/// 提示词定义
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub arguments: Vec<PromptArgument>,
+}
+
+const fn argument(name: &'static str, description: &'static str, required: bool) -> PromptArgument {
+    PromptArgument { name, description, required }
+}
+
+/// 可用的提示词
+pub fn list_prompts() -> Vec<PromptDefinition> {
+    vec![
+        PromptDefinition {
+            name: "explain_package",
+            description: "讲解某个包的用途、核心API、示例和常见陷阱",
+            arguments: vec![
+                argument("package", "包名，例如 tokio、react", true),
+                argument("language", "语言或生态，例如 rust、javascript，默认 rust", false),
+                argument("version", "版本，默认最新稳定版", false),
+            ],
+        },
+        PromptDefinition {
+            name: "compare_versions",
+            description: "比较同一个包两个版本的差异并评估升级风险",
+            arguments: vec![
+                argument("package", "包名", true),
+                argument("from_version", "当前版本", true),
+                argument("to_version", "目标版本", true),
+                argument("language", "语言或生态，默认 rust", false),
+            ],
+        },
+        PromptDefinition {
+            name: "summarize_docs",
+            description: "为一段技术文档生成摘要",
+            arguments: vec![
+                argument("content", "文档内容", true),
+                argument("max_length", "摘要最大字符数，默认 500", false),
+            ],
+        },
+        PromptDefinition {
+            name: "translate_docs",
+            description: "翻译技术文档，保留术语和代码示例",
+            arguments: vec![
+                argument("content", "文档内容", true),
+                argument("target_language", "目标语言，默认 中文", false),
+            ],
+        },
+    ]
+}
+
+/// `prompts/get`：按参数渲染提示词
+pub fn get_prompt(name: &str, arguments: &Map<String, Value>) -> Result<Value> {
+    let definition = list_prompts()
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| MCPError::NotFound(format!("提示词不存在: {}", name)))?;
+    for arg in definition.arguments.iter().filter(|a| a.required) {
+        if arguments.get(arg.name).and_then(|v| v.as_str()).map_or(true, str::is_empty) {
+            return Err(MCPError::InvalidParameter(format!("提示词 {} 缺少参数: {}", name, arg.name)).into());
+        }
+    }
+
+    let arg = |key: &str| arguments.get(key).and_then(|v| v.as_str());
+    let (system, user) = match name {
+        "explain_package" => {
+            let prompts = DevToolPrompts::new();
+            (
+                prompts.get_explain_package_system_prompt(),
+                prompts.get_explain_package_user_prompt(arg("package").unwrap_or_default(), arg("language").unwrap_or("rust"), arg("version")),
+            )
+        }
+        "compare_versions" => {
+            let prompts = DevToolPrompts::new();
+            (
+                prompts.get_compare_versions_system_prompt(),
+                prompts.get_compare_versions_user_prompt(
+                    arg("package").unwrap_or_default(),
+                    arg("language").unwrap_or("rust"),
+                    arg("from_version").unwrap_or_default(),
+                    arg("to_version").unwrap_or_default(),
+                ),
+            )
+        }
+        "summarize_docs" => {
+            let prompts = DocumentPrompts::new();
+            let max_length = arg("max_length").and_then(|v| v.parse().ok()).unwrap_or(500);
+            (prompts.get_summary_system_prompt(), prompts.get_summary_user_prompt(arg("content").unwrap_or_default(), max_length))
+        }
+        _ => {
+            let prompts = DocumentPrompts::new();
+            (
+                prompts.get_translation_system_prompt(),
+                prompts.get_translation_user_prompt(arg("content").unwrap_or_default(), arg("target_language").unwrap_or("中文")),
+            )
+        }
+    };
+
+    Ok(json!({
+        "description": definition.description,
+        "messages": [{
+            "role": "user",
+            "content": { "type": "text", "text": format!("{}\n\n{}", system, user) },
+        }],
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_prompt_renders_arguments() {
+        let mut arguments = Map::new();
+        arguments.insert("package".to_string(), json!("serde"));
+        let prompt = get_prompt("explain_package", &arguments).unwrap();
+        let text = prompt["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.contains("serde"));
+        assert!(text.contains("最新稳定版"));
+        assert_eq!(prompt["messages"][0]["role"], "user");
+    }
+
+    #[test]
+    fn test_get_prompt_validates() {
+        assert!(get_prompt("unknown", &Map::new()).is_err());
+        let mut arguments = Map::new();
+        arguments.insert("package".to_string(), json!("tokio"));
+        // 缺少 from_version/to_version
+        assert!(get_prompt("compare_versions", &arguments).is_err());
+        assert_eq!(list_prompts().len(), 4);
+    }
+}
@@ -1,5 +1,8 @@
 // MCP协议定义
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{error_codes, Request, Response};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPRequest {
@@ -19,4 +22,132 @@ pub struct MCPResponse {
 pub struct MCPError {
     pub code: i32,
     pub message: String,
-} 
\ No newline at end of file
+}
+
+/// 解析一行客户端输入；失败时返回可直接发给客户端的错误响应
+///
+/// 不是合法 JSON 时返回 `PARSE_ERROR`，JSON 结构不符合请求格式时返回 `INVALID_REQUEST`。
+/// 任意输入都不会 panic（见 `fuzz/` 下的模糊测试目标）。
+pub fn parse_request(line: &str) -> Result<Request, Response> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|e| Response::error(String::new(), error_codes::PARSE_ERROR, format!("Parse error: {}", e)))?;
+    parse_request_value(value)
+}
+
+/// 把已解析的 JSON 消息转换为请求，失败时的错误响应尽量带上原消息的 ID
+pub fn parse_request_value(value: Value) -> Result<Request, Response> {
+    let id = request_id(&value);
+    serde_json::from_value::<Request>(value)
+        .map_err(|e| Response::error(id, error_codes::INVALID_REQUEST, format!("Invalid request: {}", e)))
+}
+
+/// 消息中的请求 ID（数字等非字符串 ID 按 JSON 文本返回，缺失时为空字符串）
+pub fn request_id(value: &Value) -> String {
+    match value.get("id") {
+        Some(Value::String(id)) => id.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::base::{Schema, SchemaArray, SchemaBoolean, SchemaInteger, SchemaNumber, SchemaObject, SchemaString};
+    use proptest::prelude::*;
+
+    fn arb_json() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            (-1e12f64..1e12).prop_map(Value::from),
+            ".{0,16}".prop_map(Value::from),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..8).prop_map(Value::Array),
+                prop::collection::hash_map("[a-z_]{1,8}", inner, 0..8)
+                    .prop_map(|map| Value::Object(map.into_iter().collect())),
+            ]
+        })
+    }
+
+    fn arb_schema() -> impl Strategy<Value = Schema> {
+        let leaf = prop_oneof![
+            prop::option::of(prop::collection::vec("[a-z]{1,4}", 0..4))
+                .prop_map(|enum_values| Schema::String(SchemaString { description: None, enum_values })),
+            (prop::option::of(-100f64..100.0), prop::option::of(-100f64..100.0))
+                .prop_map(|(minimum, maximum)| Schema::Number(SchemaNumber { description: None, minimum, maximum })),
+            (prop::option::of(any::<i64>()), prop::option::of(any::<i64>()))
+                .prop_map(|(minimum, maximum)| Schema::Integer(SchemaInteger { description: None, minimum, maximum })),
+            Just(Schema::Boolean(SchemaBoolean::default())),
+        ];
+        leaf.prop_recursive(3, 24, 4, |inner| {
+            prop_oneof![
+                inner.clone().prop_map(|items| Schema::Array(SchemaArray { description: None, items: Box::new(items) })),
+                (prop::collection::hash_map("[a-z_]{1,8}", inner, 0..4), prop::collection::vec("[a-z_]{1,8}", 0..3))
+                    .prop_map(|(properties, required)| Schema::Object(SchemaObject { required, properties, description: None })),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_arbitrary_lines_never_panic(line in any::<String>()) {
+            if let Err(response) = parse_request(&line) {
+                prop_assert!(response.error.is_some());
+                prop_assert!(serde_json::to_string(&response).is_ok());
+            }
+        }
+
+        #[test]
+        fn prop_arbitrary_json_never_panics(value in arb_json()) {
+            let expected_id = request_id(&value);
+            match parse_request(&value.to_string()) {
+                Ok(request) => prop_assert_eq!(request.id, expected_id),
+                Err(response) => {
+                    prop_assert_eq!(response.id, expected_id);
+                    prop_assert_eq!(response.error.unwrap().code, error_codes::INVALID_REQUEST);
+                }
+            }
+        }
+
+        #[test]
+        fn prop_well_formed_requests_parse(id in ".{0,32}", method in "[a-z/_]{1,24}", params in arb_json()) {
+            let line = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string();
+            let request = parse_request(&line).unwrap();
+            prop_assert_eq!(request.id, id);
+            prop_assert_eq!(request.method, method);
+            prop_assert_eq!(request.params, params);
+        }
+
+        #[test]
+        fn prop_schema_validation_never_panics(schema in arb_schema(), value in arb_json()) {
+            let _ = schema.validate(&value);
+        }
+
+        #[test]
+        fn prop_error_response_round_trips(id in any::<String>(), code in any::<i32>(), message in any::<String>()) {
+            let json = serde_json::to_string(&Response::error(id.clone(), code, message.clone())).unwrap();
+            let parsed: Value = serde_json::from_str(&json).unwrap();
+            prop_assert_eq!(parsed["id"].as_str(), Some(id.as_str()));
+            prop_assert_eq!(parsed["error"]["code"].as_i64(), Some(code as i64));
+            prop_assert_eq!(parsed["error"]["message"].as_str(), Some(message.as_str()));
+            prop_assert!(parsed.get("result").is_none());
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_carry_codes_and_ids() {
+        let not_json = parse_request("{\"id\": ").unwrap_err();
+        assert_eq!(not_json.error.unwrap().code, error_codes::PARSE_ERROR);
+
+        let numeric_id = parse_request(r#"{"jsonrpc":"2.0","id":7,"method":"tools/list","params":{}}"#).unwrap_err();
+        assert_eq!(numeric_id.id, "7");
+        assert_eq!(numeric_id.error.unwrap().code, error_codes::INVALID_REQUEST);
+
+        let missing_params = parse_request(r#"{"jsonrpc":"2.0","id":"a","method":"tools/list"}"#).unwrap_err();
+        assert_eq!(missing_params.id, "a");
+    }
+}
@@ -0,0 +1,316 @@
+//! MCP 资源：把向量库中已缓存的文档暴露为 `resources/*`
+//!
+//! 资源 URI 形如 `grape://docs/{language}/{package}/{version}`，
+//! 带文档类型时为 `grape://docs/{language}/{package}/{version}/{doc_type}`。
+//! 包名中的 `/`（如 `@types/node`、Go 模块路径）按百分号编码为 `%2F`。
+//!
+//! 订阅某个包版本的 URI 后，后台缓存为该包版本新增文档时会收到
+//! `notifications/resources/updated`；订阅包版本 URI 同时覆盖其下所有文档类型。
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::errors::MCPError;
+use crate::tools::vector_docs_tool::{CachedDocsUpdate, CachedPackage};
+use crate::tools::VectorDocsTool;
+
+/// 文档资源 URI 前缀
+pub const DOCS_URI_PREFIX: &str = "grape://docs/";
+
+/// 文档资源的 MIME 类型
+const DOCS_MIME_TYPE: &str = "text/markdown";
+
+/// 会话的资源订阅集合，传输层据此过滤更新通知
+pub type ResourceSubscriptions = Arc<parking_lot::Mutex<HashSet<String>>>;
+
+/// 解析后的文档资源 URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocResourceUri {
+    pub language: String,
+    pub package_name: String,
+    pub version: String,
+    pub doc_type: Option<String>,
+}
+
+impl DocResourceUri {
+    pub fn package(language: &str, package_name: &str, version: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            package_name: package_name.to_string(),
+            version: version.to_string(),
+            doc_type: None,
+        }
+    }
+
+    pub fn parse(uri: &str) -> Result<Self> {
+        let path = uri
+            .strip_prefix(DOCS_URI_PREFIX)
+            .ok_or_else(|| anyhow!("不支持的资源URI: {}", uri))?;
+        let segments: Vec<String> = path.split('/').map(decode_segment).collect();
+        match segments.as_slice() {
+            [language, package_name, version] => Ok(Self::package(language, package_name, version)),
+            [language, package_name, version, doc_type] => Ok(Self {
+                doc_type: Some(doc_type.clone()),
+                ..Self::package(language, package_name, version)
+            }),
+            _ => Err(anyhow!("资源URI格式应为 {}{{language}}/{{package}}/{{version}}[/{{doc_type}}]: {}", DOCS_URI_PREFIX, uri)),
+        }
+    }
+
+    /// 对应包版本（不含文档类型）的 URI
+    pub fn package_uri(&self) -> String {
+        DocResourceUri { doc_type: None, ..self.clone() }.to_string()
+    }
+}
+
+impl fmt::Display for DocResourceUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}/{}/{}",
+            DOCS_URI_PREFIX,
+            encode_segment(&self.language),
+            encode_segment(&self.package_name),
+            encode_segment(&self.version)
+        )?;
+        if let Some(doc_type) = &self.doc_type {
+            write!(f, "/{}", encode_segment(doc_type))?;
+        }
+        Ok(())
+    }
+}
+
+fn encode_segment(segment: &str) -> String {
+    segment.replace('%', "%25").replace('/', "%2F")
+}
+
+fn decode_segment(segment: &str) -> String {
+    segment.replace("%2F", "/").replace("%2f", "/").replace("%25", "%")
+}
+
+/// 订阅集合是否覆盖某个包版本的更新
+pub fn is_subscribed(subscriptions: &HashSet<String>, update: &DocResourceUri) -> bool {
+    let package_uri = update.package_uri();
+    subscriptions.iter().any(|uri| {
+        uri == &package_uri
+            || DocResourceUri::parse(uri).map_or(false, |subscribed| subscribed.package_uri() == package_uri)
+    })
+}
+
+/// `notifications/resources/updated` 通知
+pub fn updated_notification(uri: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/resources/updated",
+        "params": { "uri": uri },
+    })
+}
+
+/// `notifications/resources/list_changed` 通知
+pub fn list_changed_notification() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/resources/list_changed",
+    })
+}
+
+/// 把缓存更新转换为通知，记录已知包版本以判断资源列表是否变化
+pub struct ResourceNotifier {
+    known: HashSet<String>,
+}
+
+impl ResourceNotifier {
+    pub fn new(resources: &DocResources) -> Result<Self> {
+        let known = resources.vector_tool
+            .list_cached_packages()?
+            .iter()
+            .map(|p| DocResourceUri::package(&p.language, &p.package_name, &p.version).to_string())
+            .collect();
+        Ok(Self { known })
+    }
+
+    /// 记录一次缓存更新，返回 (包版本URI, 是否新增了包版本)
+    pub fn observe(&mut self, update: &CachedDocsUpdate) -> (DocResourceUri, bool) {
+        let uri = DocResourceUri::package(&update.language, &update.package_name, &update.version);
+        let is_new = self.known.insert(uri.to_string());
+        (uri, is_new)
+    }
+
+    /// 某个会话应收到的通知：列表变化通知所有会话，更新通知只发给订阅者
+    pub fn notifications(uri: &DocResourceUri, list_changed: bool, subscriptions: &HashSet<String>) -> Vec<Value> {
+        let mut notifications = Vec::new();
+        if list_changed {
+            notifications.push(list_changed_notification());
+        }
+        if is_subscribed(subscriptions, uri) {
+            notifications.push(updated_notification(&uri.to_string()));
+        }
+        notifications
+    }
+}
+
+/// 接收下一条缓存更新；没有订阅（或通道已关闭）时一直挂起，便于在 `select!` 中使用
+pub async fn next_update(receiver: &mut Option<broadcast::Receiver<CachedDocsUpdate>>) -> CachedDocsUpdate {
+    loop {
+        let Some(rx) = receiver.as_mut() else {
+            return std::future::pending().await;
+        };
+        match rx.recv().await {
+            Ok(update) => return update,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("资源更新通知落后，丢弃 {} 条", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => *receiver = None,
+        }
+    }
+}
+
+/// 已缓存文档的资源提供者
+pub struct DocResources {
+    vector_tool: Arc<VectorDocsTool>,
+}
+
+impl DocResources {
+    pub fn new(vector_tool: Arc<VectorDocsTool>) -> Self {
+        Self { vector_tool }
+    }
+
+    /// `resources/list`：每个包版本一个资源，包含多种文档类型时再按类型列出
+    pub fn list(&self) -> Result<Vec<Value>> {
+        Ok(self.vector_tool
+            .list_cached_packages()?
+            .iter()
+            .flat_map(package_resources)
+            .collect())
+    }
+
+    /// `resources/templates/list`
+    pub fn templates(&self) -> Vec<Value> {
+        vec![
+            json!({
+                "uriTemplate": format!("{}{{language}}/{{package}}/{{version}}", DOCS_URI_PREFIX),
+                "name": "package-docs",
+                "description": "某个包版本的全部已缓存文档",
+                "mimeType": DOCS_MIME_TYPE,
+            }),
+            json!({
+                "uriTemplate": format!("{}{{language}}/{{package}}/{{version}}/{{doc_type}}", DOCS_URI_PREFIX),
+                "name": "package-docs-by-type",
+                "description": "某个包版本指定类型的已缓存文档",
+                "mimeType": DOCS_MIME_TYPE,
+            }),
+        ]
+    }
+
+    /// `resources/read`：把包版本的文档合并为一个 Markdown 文本
+    pub fn read(&self, uri: &str) -> Result<Value> {
+        let resource = DocResourceUri::parse(uri)?;
+        let documents = self.vector_tool.package_documents(
+            &resource.language,
+            &resource.package_name,
+            &resource.version,
+            resource.doc_type.as_deref(),
+        )?;
+        if documents.is_empty() {
+            return Err(MCPError::NotFound(format!("缓存中没有资源: {}", uri)).into());
+        }
+
+        let mut text = format!("# {} {}\n\n", resource.package_name, resource.version);
+        for doc in &documents {
+            text.push_str(&format!("## {}\n\n{}\n\n", doc.title, doc.content.trim()));
+        }
+        Ok(json!({
+            "contents": [{
+                "uri": uri,
+                "mimeType": DOCS_MIME_TYPE,
+                "text": text,
+            }]
+        }))
+    }
+
+    /// 订阅缓存更新
+    pub fn subscribe_updates(&self) -> broadcast::Receiver<CachedDocsUpdate> {
+        self.vector_tool.subscribe_updates()
+    }
+}
+
+fn package_resources(package: &CachedPackage) -> Vec<Value> {
+    let base = DocResourceUri::package(&package.language, &package.package_name, &package.version);
+    let mut resources = vec![json!({
+        "uri": base.to_string(),
+        "name": format!("{} {} ({})", package.package_name, package.version, package.language),
+        "description": format!("{} 篇已缓存文档", package.document_count),
+        "mimeType": DOCS_MIME_TYPE,
+    })];
+    if package.doc_types.len() > 1 {
+        for (doc_type, count) in &package.doc_types {
+            let uri = DocResourceUri { doc_type: Some(doc_type.clone()), ..base.clone() };
+            resources.push(json!({
+                "uri": uri.to_string(),
+                "name": format!("{} {} {}", package.package_name, package.version, doc_type),
+                "description": format!("{} 篇 {} 文档", count, doc_type),
+                "mimeType": DOCS_MIME_TYPE,
+            }));
+        }
+    }
+    resources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_uri_round_trip() {
+        let uri = DocResourceUri {
+            doc_type: Some("documentation".to_string()),
+            ..DocResourceUri::package("javascript", "@types/node", "20.11.0")
+        };
+        let text = uri.to_string();
+        assert_eq!(text, "grape://docs/javascript/@types%2Fnode/20.11.0/documentation");
+        assert_eq!(DocResourceUri::parse(&text).unwrap(), uri);
+        assert_eq!(uri.package_uri(), "grape://docs/javascript/@types%2Fnode/20.11.0");
+
+        assert!(DocResourceUri::parse("file:///tmp/x").is_err());
+        assert!(DocResourceUri::parse("grape://docs/rust/serde").is_err());
+    }
+
+    #[test]
+    fn test_subscription_matching() {
+        let update = DocResourceUri::package("rust", "serde", "1.0.190");
+        let mut subscriptions = HashSet::new();
+        assert!(!is_subscribed(&subscriptions, &update));
+
+        subscriptions.insert("grape://docs/rust/serde/1.0.190/documentation".to_string());
+        assert!(is_subscribed(&subscriptions, &update));
+        assert!(!is_subscribed(&subscriptions, &DocResourceUri::package("rust", "serde", "1.0.191")));
+
+        let notifications = ResourceNotifier::notifications(&update, true, &subscriptions);
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[1]["params"]["uri"], "grape://docs/rust/serde/1.0.190");
+        assert_eq!(ResourceNotifier::notifications(&update, false, &HashSet::new()).len(), 0);
+    }
+
+    #[test]
+    fn test_package_resources_split_by_doc_type() {
+        let mut doc_types = BTreeMap::new();
+        doc_types.insert("documentation".to_string(), 3);
+        doc_types.insert("source".to_string(), 2);
+        let package = CachedPackage {
+            language: "rust".to_string(),
+            package_name: "tokio".to_string(),
+            version: "1.38.0".to_string(),
+            doc_types,
+            document_count: 5,
+        };
+        let resources = package_resources(&package);
+        assert_eq!(resources.len(), 3);
+        assert_eq!(resources[0]["uri"], "grape://docs/rust/tokio/1.38.0");
+        assert_eq!(resources[2]["uri"], "grape://docs/rust/tokio/1.38.0/source");
+    }
+}
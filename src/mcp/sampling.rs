@@ -0,0 +1,259 @@
+//! MCP 采样：由服务器向客户端发起 `sampling/createMessage` 请求，借用宿主的 LLM
+//!
+//! 客户端在 `initialize` 的 `capabilities.sampling` 中声明支持后，stdio 和 WebSocket 传输
+//! 为会话创建 [`SamplingClient`]：请求经传输层的推送通道写出，传输层在处理请求期间继续读取
+//! 客户端消息，把对应 ID 的响应交给 [`SamplingClient::deliver`]。
+//!
+//! 工具执行期间可通过 [`current`] 取得当前会话的采样客户端（AI 服务优先走采样，失败时再调用
+//! 自己配置的 API）。客户端保存在 tokio task-local 中，`tokio::spawn` 出去的任务不会继承。
+//! Streamable HTTP 传输在处理请求时持有会话锁，无法接收客户端的回复，因此不支持采样。
+
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+use super::protocol;
+use super::streaming::PartialResultSender;
+
+/// 采样请求的方法名
+pub const SAMPLING_METHOD: &str = "sampling/createMessage";
+
+/// 等待客户端返回采样结果的默认时间（客户端通常需要用户确认）
+pub const DEFAULT_SAMPLING_TIMEOUT: Duration = Duration::from_secs(120);
+
+tokio::task_local! {
+    static CURRENT: Option<SamplingClient>;
+}
+
+type PendingRequests = Arc<Mutex<HashMap<String, oneshot::Sender<std::result::Result<Value, String>>>>>;
+
+/// 一次采样请求（单轮文本对话）
+#[derive(Debug, Clone, Default)]
+pub struct SamplingRequest {
+    pub system_prompt: Option<String>,
+    pub user_message: String,
+    pub max_tokens: u32,
+    pub temperature: Option<f32>,
+    /// 模型偏好提示，客户端可以忽略
+    pub model_hint: Option<String>,
+}
+
+impl SamplingRequest {
+    fn to_params(&self) -> Value {
+        let mut params = json!({
+            "messages": [{
+                "role": "user",
+                "content": { "type": "text", "text": self.user_message },
+            }],
+            "maxTokens": self.max_tokens,
+            "includeContext": "none",
+        });
+        if let Some(system_prompt) = &self.system_prompt {
+            params["systemPrompt"] = json!(system_prompt);
+        }
+        if let Some(temperature) = self.temperature {
+            params["temperature"] = json!(temperature);
+        }
+        if let Some(model) = &self.model_hint {
+            params["modelPreferences"] = json!({ "hints": [{ "name": model }] });
+        }
+        params
+    }
+}
+
+/// 客户端返回的采样结果
+#[derive(Debug, Clone)]
+pub struct SamplingResponse {
+    pub content: String,
+    pub model: String,
+    pub stop_reason: Option<String>,
+}
+
+impl SamplingResponse {
+    fn from_result(result: &Value) -> Result<Self> {
+        let content = result.get("content").ok_or_else(|| anyhow!("采样结果缺少 content"))?;
+        if content.get("type").and_then(|t| t.as_str()) != Some("text") {
+            return Err(anyhow!("采样结果不是文本内容"));
+        }
+        Ok(Self {
+            content: content.get("text").and_then(|t| t.as_str()).unwrap_or_default().to_string(),
+            model: result.get("model").and_then(|m| m.as_str()).unwrap_or("unknown").to_string(),
+            stop_reason: result.get("stopReason").and_then(|r| r.as_str()).map(str::to_string),
+        })
+    }
+}
+
+/// 一个会话的采样客户端
+#[derive(Debug, Clone)]
+pub struct SamplingClient {
+    outgoing: PartialResultSender,
+    pending: PendingRequests,
+    next_id: Arc<AtomicU64>,
+    timeout: Duration,
+}
+
+impl SamplingClient {
+    pub fn new(outgoing: PartialResultSender) -> Self {
+        Self {
+            outgoing,
+            pending: PendingRequests::default(),
+            next_id: Arc::new(AtomicU64::new(0)),
+            timeout: DEFAULT_SAMPLING_TIMEOUT,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 等待回复的请求数
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().len()
+    }
+
+    /// 向客户端发起采样请求并等待结果
+    pub async fn create_message(&self, request: &SamplingRequest) -> Result<SamplingResponse> {
+        let id = format!("sampling-{}", self.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().insert(id.clone(), sender);
+
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": SAMPLING_METHOD,
+            "params": request.to_params(),
+        });
+        if self.outgoing.send(message).is_err() {
+            self.pending.lock().remove(&id);
+            return Err(anyhow!("客户端连接已关闭，无法发起采样"));
+        }
+
+        let outcome = match tokio::time::timeout(self.timeout, receiver).await {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(_)) => Err("客户端连接已关闭".to_string()),
+            Err(_) => {
+                self.pending.lock().remove(&id);
+                Err(format!("等待客户端采样结果超过 {} 秒", self.timeout.as_secs()))
+            }
+        };
+        let result = outcome.map_err(|e| anyhow!("采样请求失败: {}", e))?;
+        SamplingResponse::from_result(&result)
+    }
+
+    /// 把客户端响应交给等待中的采样请求；ID 不属于本客户端时返回 false
+    pub fn deliver(&self, message: &Value) -> bool {
+        let id = protocol::request_id(message);
+        let Some(sender) = self.pending.lock().remove(&id) else {
+            return false;
+        };
+        let outcome = match (message.get("error"), message.get("result")) {
+            (Some(error), _) => Err(error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map_or_else(|| error.to_string(), str::to_string)),
+            (None, Some(result)) => Ok(result.clone()),
+            (None, None) => Err("响应缺少 result".to_string()),
+        };
+        let _ = sender.send(outcome);
+        true
+    }
+}
+
+/// 消息是否为客户端对服务器请求的响应（带 ID，没有 method，带 result 或 error）
+pub fn is_client_response(message: &Value) -> bool {
+    message.get("id").is_some()
+        && message.get("method").is_none()
+        && (message.get("result").is_some() || message.get("error").is_some())
+}
+
+/// 传输层收到一行客户端输入时调用：是客户端响应则交给采样客户端并返回 true，否则返回 false
+pub fn route_client_response(client: Option<&SamplingClient>, line: &str) -> bool {
+    let Ok(message) = serde_json::from_str::<Value>(line) else {
+        return false;
+    };
+    if !is_client_response(&message) {
+        return false;
+    }
+    if !client.is_some_and(|client| client.deliver(&message)) {
+        debug!("忽略没有对应请求的客户端响应: {}", protocol::request_id(&message));
+    }
+    true
+}
+
+/// 当前工具调用所属会话的采样客户端（客户端未声明采样能力时为 None）
+pub fn current() -> Option<SamplingClient> {
+    CURRENT.try_with(|client| client.clone()).ok().flatten()
+}
+
+/// 在指定采样客户端下执行 `future`
+pub async fn scope<F: Future>(client: Option<SamplingClient>, future: F) -> F::Output {
+    CURRENT.scope(client, future).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_create_message_round_trip() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let client = SamplingClient::new(sender);
+
+        let request = SamplingRequest {
+            system_prompt: Some("你是文档助手".to_string()),
+            user_message: "总结 tokio::spawn".to_string(),
+            max_tokens: 200,
+            ..Default::default()
+        };
+        let pending = tokio::spawn({
+            let client = client.clone();
+            async move { client.create_message(&request).await }
+        });
+
+        let outgoing = receiver.recv().await.unwrap();
+        assert_eq!(outgoing["method"], SAMPLING_METHOD);
+        assert_eq!(outgoing["params"]["maxTokens"], 200);
+        assert_eq!(outgoing["params"]["systemPrompt"], "你是文档助手");
+
+        let reply = json!({
+            "jsonrpc": "2.0",
+            "id": outgoing["id"],
+            "result": { "role": "assistant", "content": { "type": "text", "text": "spawn 启动异步任务" }, "model": "host-llm", "stopReason": "endTurn" },
+        });
+        assert!(route_client_response(Some(&client), &reply.to_string()));
+        let response = pending.await.unwrap().unwrap();
+        assert_eq!(response.content, "spawn 启动异步任务");
+        assert_eq!(response.model, "host-llm");
+        assert_eq!(client.pending_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_client_error_and_timeout() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let client = SamplingClient::new(sender).with_timeout(Duration::from_millis(50));
+
+        let rejected = tokio::spawn({
+            let client = client.clone();
+            async move { client.create_message(&SamplingRequest::default()).await }
+        });
+        let outgoing = receiver.recv().await.unwrap();
+        let reply = json!({ "jsonrpc": "2.0", "id": outgoing["id"], "error": { "code": -1, "message": "用户拒绝了采样请求" } });
+        assert!(client.deliver(&reply));
+        assert!(rejected.await.unwrap().unwrap_err().to_string().contains("用户拒绝"));
+
+        let timed_out = client.create_message(&SamplingRequest::default()).await;
+        assert!(timed_out.is_err());
+        assert_eq!(client.pending_count(), 0);
+
+        // 普通请求不是客户端响应
+        assert!(!route_client_response(Some(&client), r#"{"jsonrpc":"2.0","id":"1","method":"tools/list","params":{}}"#));
+    }
+}
@@ -1,17 +1,29 @@
 use std::io::Write;
 use anyhow::Result;
 use serde_json::Value;
-use tracing::{debug, info, warn, error};
+use tracing::{debug, info, info_span, warn, error, Instrument};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, Semaphore};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::time::timeout;
+use crate::config::ToolExecutionConfig;
+use crate::errors::{find_mcp_error, ErrorPayload, MCPError};
 use crate::tools::base::MCPTool;
-use super::protocol::MCPRequest;
+use super::protocol::{self, MCPRequest};
+use super::resources::{next_update, DocResources, ResourceNotifier, ResourceSubscriptions};
+use super::streaming::{PartialResultSender, PartialResultSink, PARTIAL_RESULT_METHOD};
 
-use super::{Request, Response, InitializeParams, InitializeResult, MCP_VERSION, SERVER_CAPABILITIES};
+use super::{error_codes, Request, Response, InitializeParams, InitializeResult, PeerInfo, SERVER_CAPABILITIES};
+use super::negotiation::ProtocolVersion;
+use super::auth::{AuthContext, AuthScope};
+use super::session::{self, SessionPreferences, SESSION_TOOL_NAME};
+use super::shutdown::ShutdownCoordinator;
+use super::correlation;
+use super::namespace;
+use super::sampling::{self, SamplingClient};
+use super::audit::{AuditLog, AuditOutcome, AuditRecord};
 
 /// 工具信息结构
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -19,6 +31,9 @@ pub struct ToolInfo {
     pub name: String,
     pub description: String,
     pub parameters: Value,
+    /// 工具声明的输出Schema（未声明时不输出该字段）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<Value>,
     pub language: Option<String>,
     pub category: Option<String>,
     pub version: Option<String>,
@@ -50,30 +65,140 @@ pub enum ToolHealth {
     Unhealthy { reason: String },
 }
 
+/// 会话未初始化时的错误响应
+fn not_initialized(id: String) -> Response {
+    let payload = ErrorPayload {
+        kind: "NOT_INITIALIZED".to_string(),
+        recoverable: true,
+        provider: None,
+        retry_after_secs: None,
+        suggested_fix: "先发送 initialize 请求，再调用其他方法".to_string(),
+        resolution_steps: Vec::new(),
+    };
+    Response::error_with_data(id, -32002, "服务器未初始化".to_string(), &payload)
+}
+
+/// 服务器关闭期间收到新请求时的错误响应
+fn shutting_down(id: String) -> Response {
+    let payload = ErrorPayload {
+        kind: "SERVER_SHUTTING_DOWN".to_string(),
+        recoverable: true,
+        provider: None,
+        retry_after_secs: None,
+        suggested_fix: "服务器正在关闭，请稍后重新连接".to_string(),
+        resolution_steps: Vec::new(),
+    };
+    Response::error_with_data(id, error_codes::SERVER_SHUTTING_DOWN, "服务器正在关闭".to_string(), &payload)
+}
+
 /// MCP 服务器
 pub struct MCPServer {
     tools: Arc<RwLock<Vec<Arc<dyn MCPTool>>>>,
-    default_timeout: Duration,
+    /// 超时与并发限制
+    limits: ToolExecutionConfig,
+    /// 全局并发名额
+    call_permits: Arc<Semaphore>,
+    /// 单独限流的工具各自的并发名额
+    tool_permits: HashMap<String, Arc<Semaphore>>,
     performance_metrics: Arc<RwLock<HashMap<String, Vec<Duration>>>>,
+    /// 已缓存文档的资源提供者（未设置时不支持 resources/*）
+    resources: Option<Arc<DocResources>>,
+    /// 关闭协调器：关闭开始后拒绝新请求，并记录进行中的请求
+    shutdown: ShutdownCoordinator,
+    /// 工具调用审计日志（未启用时为 None）
+    audit: Option<Arc<AuditLog>>,
 }
 
 impl MCPServer {
     pub fn new() -> Self {
-        Self {
-            tools: Arc::new(RwLock::new(Vec::new())),
-            default_timeout: Duration::from_secs(30),
-            performance_metrics: Arc::new(RwLock::new(HashMap::new())),
-        }
+        Self::with_limits(ToolExecutionConfig::default())
     }
 
     pub fn with_timeout(timeout: Duration) -> Self {
+        Self::with_limits(ToolExecutionConfig {
+            default_timeout_ms: timeout.as_millis() as u64,
+            ..ToolExecutionConfig::default()
+        })
+    }
+
+    /// 按配置的超时和并发限制创建
+    pub fn with_limits(limits: ToolExecutionConfig) -> Self {
+        let tool_permits = limits.tools
+            .keys()
+            .filter_map(|name| limits.concurrency_for(name).map(|max| (name.clone(), Arc::new(Semaphore::new(max.max(1))))))
+            .collect();
         Self {
             tools: Arc::new(RwLock::new(Vec::new())),
-            default_timeout: timeout,
+            call_permits: Arc::new(Semaphore::new(limits.max_concurrent_calls.max(1))),
+            tool_permits,
+            limits,
             performance_metrics: Arc::new(RwLock::new(HashMap::new())),
+            resources: None,
+            shutdown: ShutdownCoordinator::new(),
+            audit: None,
+        }
+    }
+
+    pub fn limits(&self) -> &ToolExecutionConfig {
+        &self.limits
+    }
+
+    /// 是否处于只读模式（修改缓存的操作被隐藏和拒绝）
+    pub fn is_read_only(&self) -> bool {
+        self.limits.read_only
+    }
+
+    /// 工具对外公布的参数Schema，只读模式下去掉修改缓存的操作
+    fn advertised_parameters(&self, tool: &dyn MCPTool) -> Value {
+        let schema = tool.parameters_schema();
+        let result = if self.limits.read_only && !tool.mutating_actions().is_empty() {
+            serde_json::to_value(schema.without_enum_values("action", tool.mutating_actions()))
+        } else {
+            serde_json::to_value(schema)
+        };
+        result.unwrap_or(serde_json::json!({}))
+    }
+
+    /// 调用是否会修改缓存：需要 `write` 的调用，以及需要更高范围且属于 `mutating_actions` 的操作
+    ///
+    /// 需要 `admin` 的查询（如审计日志、快照列表）不修改缓存，只读模式下仍然可用。
+    fn modifies_cache(tool: &dyn MCPTool, params: &Value) -> bool {
+        match tool.required_scope(params) {
+            AuthScope::Read => false,
+            AuthScope::Write => true,
+            _ => params.get("action")
+                .and_then(|v| v.as_str())
+                .is_some_and(|action| tool.mutating_actions().contains(&action)),
         }
     }
 
+    /// 设置资源提供者，启用 resources/*
+    pub fn set_resources(&mut self, resources: Arc<DocResources>) {
+        self.resources = Some(resources);
+    }
+
+    pub fn resources(&self) -> Option<Arc<DocResources>> {
+        self.resources.clone()
+    }
+
+    /// 使用共享的关闭协调器（与传输层、后台任务共用）
+    pub fn set_shutdown(&mut self, shutdown: ShutdownCoordinator) {
+        self.shutdown = shutdown;
+    }
+
+    pub fn shutdown(&self) -> ShutdownCoordinator {
+        self.shutdown.clone()
+    }
+
+    /// 启用工具调用审计
+    pub fn set_audit_log(&mut self, audit: Arc<AuditLog>) {
+        self.audit = Some(audit);
+    }
+
+    pub fn audit_log(&self) -> Option<Arc<AuditLog>> {
+        self.audit.clone()
+    }
+
     pub async fn register_tool(&self, tool: Box<dyn MCPTool>) -> Result<()> {
         let mut tools = self.tools.write().await;
         tools.push(Arc::from(tool));
@@ -90,22 +215,58 @@ impl MCPServer {
         Ok(())
     }
 
-    /// 带超时的工具执行
+    /// 带超时的工具执行，等待并发名额的时间也计入超时
     pub async fn execute_tool_with_timeout(&self, tool_name: &str, params: Value, timeout_duration: Duration) -> Result<Value> {
+        self.run_tool(tool_name, params, timeout_duration, None).await
+    }
+
+    /// 执行工具并把结果分块推送到 `sink`，超时和并发限制与普通调用相同
+    pub async fn execute_tool_streaming(&self, tool_name: &str, params: Value, sink: &PartialResultSink) -> Result<Value> {
+        self.run_tool(tool_name, params, self.limits.timeout_for(tool_name), Some(sink)).await
+    }
+
+    async fn run_tool(&self, tool_name: &str, params: Value, timeout_duration: Duration, sink: Option<&PartialResultSink>) -> Result<Value> {
         let start_time = Instant::now();
         
         let tools = self.tools.read().await;
         let tool = tools.iter()
             .find(|t| t.name() == tool_name)
-            .ok_or_else(|| anyhow::anyhow!("工具不存在: {}", tool_name))?
+            .ok_or_else(|| MCPError::ToolNotFound(tool_name.to_string()))?
             .clone();
         
         // 释放读锁
         drop(tools);
+
+        if self.limits.read_only && Self::modifies_cache(tool.as_ref(), &params) {
+            return Err(MCPError::AuthorizationError(format!("服务器处于只读模式，{} 的写入、删除和导入操作已禁用", tool_name)).into());
+        }
         
-        let result = timeout(timeout_duration, tool.execute(params))
-            .await
-            .map_err(|_| anyhow::anyhow!("工具执行超时: {}", tool_name))?;
+        let tool_permits = self.tool_permits.get(tool_name).cloned();
+        let run = async {
+            let _call_permit = self.call_permits.acquire().await?;
+            let _tool_permit = match &tool_permits {
+                Some(permits) => Some(permits.acquire().await?),
+                None => None,
+            };
+            match sink {
+                Some(sink) => tool.execute_streaming(params, sink).await,
+                None => tool.execute(params).await,
+            }
+        };
+        let result = match timeout(timeout_duration, run.instrument(info_span!("tool_call", tool = %tool_name))).await {
+            Ok(result) => result,
+            Err(_) => {
+                self.record_performance_metric(tool_name, start_time.elapsed()).await;
+                return Err(MCPError::Timeout(format!("工具 {} 执行超过 {} 毫秒", tool_name, timeout_duration.as_millis())).into());
+            }
+        };
+
+        // 校验结果是否符合工具声明的输出Schema
+        if let (Ok(value), Some(output_schema)) = (&result, tool.output_schema()) {
+            if let Err(e) = output_schema.validate(value) {
+                warn!("工具 {} 的输出不符合声明的输出Schema: {}", tool_name, e);
+            }
+        }
         
         let execution_time = start_time.elapsed();
         
@@ -116,14 +277,33 @@ impl MCPServer {
     }
 
     pub async fn execute_tool(&self, tool_name: &str, params: Value) -> Result<Value> {
-        self.execute_tool_with_timeout(tool_name, params, self.default_timeout).await
+        self.execute_tool_with_timeout(tool_name, params, self.limits.timeout_for(tool_name)).await
+    }
+
+    /// 调用工具所需的授权范围，工具不存在时按只读处理（执行时会报告工具未找到）
+    pub async fn required_scope(&self, tool_name: &str, params: &Value) -> AuthScope {
+        let tools = self.tools.read().await;
+        tools.iter()
+            .find(|t| t.name() == tool_name)
+            .map_or(AuthScope::Read, |tool| tool.required_scope(params))
+    }
+
+    /// 按会话偏好补全工具参数
+    pub async fn apply_session_defaults(&self, tool_name: &str, params: &mut Value, session: &SessionPreferences) {
+        if session.is_empty() {
+            return;
+        }
+        let tools = self.tools.read().await;
+        if let Some(tool) = tools.iter().find(|t| t.name() == tool_name) {
+            tool.apply_session_defaults(params, session);
+        }
     }
 
     /// 批量执行工具
     pub async fn batch_execute_tools(&self, requests: Vec<ToolRequest>) -> Result<Vec<ToolResult>> {
         let mut results = Vec::with_capacity(requests.len());
         let futures: Vec<_> = requests.into_iter().map(|req| {
-            let timeout_duration = req.timeout.unwrap_or(self.default_timeout);
+            let timeout_duration = req.timeout.unwrap_or_else(|| self.limits.timeout_for(&req.tool_name));
             async move {
                 let start_time = Instant::now();
                 let result = self.execute_tool_with_timeout(&req.tool_name, req.params, timeout_duration).await;
@@ -247,7 +427,8 @@ impl MCPServer {
             tool_list.push(ToolInfo {
                 name: tool.name().to_string(),
                 description: description.to_string(),
-                parameters: serde_json::to_value(tool.parameters_schema()).unwrap_or(serde_json::json!({})),
+                parameters: self.advertised_parameters(tool.as_ref()),
+                output_schema: tool.output_schema().and_then(|schema| serde_json::to_value(schema).ok()),
                 language,
                 category: Some("documentation".to_string()),
                 version: Some("1.0.0".to_string()),
@@ -266,7 +447,8 @@ impl MCPServer {
                 return Ok(Some(ToolInfo {
                     name: tool.name().to_string(),
                     description: tool.description().to_string(),
-                    parameters: serde_json::to_value(tool.parameters_schema()).unwrap_or(serde_json::json!({})),
+                    parameters: self.advertised_parameters(tool.as_ref()),
+                    output_schema: tool.output_schema().and_then(|schema| serde_json::to_value(schema).ok()),
                     language: None,
                     category: None,
                     version: None,
@@ -324,61 +506,251 @@ pub struct Server {
     initialized: bool,
     /// MCP 服务器实例
     mcp_server: Arc<RwLock<MCPServer>>,
+    /// 本会话订阅的资源 URI
+    subscriptions: ResourceSubscriptions,
+    /// 部分结果通知的出口（传输层不支持推送时为 None）
+    partial_results: Option<PartialResultSender>,
+    /// `initialize` 时协商出的协议版本
+    protocol_version: ProtocolVersion,
+    /// 网络传输启用认证时的校验器和当前请求的调用方
+    auth: Option<AuthContext>,
+    /// 本会话的默认偏好
+    session: SessionPreferences,
+    /// 向客户端发起采样请求的出口（传输层支持双向通信时设置）
+    sampling: Option<SamplingClient>,
+    /// 客户端是否在初始化时声明了采样能力
+    client_sampling: bool,
+    /// 会话标识（审计日志中区分调用方；HTTP 传输与 `Mcp-Session-Id` 一致）
+    session_id: String,
 }
 
 impl Server {
     /// 创建新的 MCP 服务器实例
     pub fn new(name: String, version: String, mcp_server: MCPServer) -> Self {
+        Self::with_shared(name, version, Arc::new(RwLock::new(mcp_server)))
+    }
+
+    /// 使用共享的工具集创建会话（HTTP 等多客户端传输中每个会话一个实例）
+    pub fn with_shared(name: String, version: String, mcp_server: Arc<RwLock<MCPServer>>) -> Self {
         Self {
             name,
             version,
             initialized: false,
-            mcp_server: Arc::new(RwLock::new(mcp_server)),
+            mcp_server,
+            subscriptions: ResourceSubscriptions::default(),
+            partial_results: None,
+            protocol_version: ProtocolVersion::LATEST,
+            auth: None,
+            session: SessionPreferences::default(),
+            sampling: None,
+            client_sampling: false,
+            session_id: uuid::Uuid::new_v4().simple().to_string(),
         }
     }
 
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// 使用传输层分配的会话标识
+    pub fn set_session_id(&mut self, session_id: String) {
+        self.session_id = session_id;
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// 本会话协商出的协议版本（未初始化时为最新版本）
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+
+    /// 本会话的资源订阅集合，传输层用来过滤 `notifications/resources/updated`
+    pub fn resource_subscriptions(&self) -> ResourceSubscriptions {
+        self.subscriptions.clone()
+    }
+
+    /// 设置部分结果通知的出口，传输层在请求处理期间按顺序写出收到的通知
+    pub fn set_partial_result_sender(&mut self, sender: Option<PartialResultSender>) {
+        self.partial_results = sender;
+    }
+
+    /// 启用采样：服务器发往客户端的请求写入 `outgoing`，返回的客户端供传输层转交客户端响应
+    pub fn enable_sampling(&mut self, outgoing: PartialResultSender) -> SamplingClient {
+        let client = SamplingClient::new(outgoing);
+        self.sampling = Some(client.clone());
+        client
+    }
+
+    /// 当前请求可用的采样客户端（传输层支持且客户端声明了采样能力时才有）
+    fn sampling_client(&self) -> Option<SamplingClient> {
+        self.sampling.clone().filter(|_| self.client_sampling)
+    }
+
+    /// 设置当前请求的调用方，之后的工具调用按其授权范围检查；None 表示不检查（stdio）
+    pub fn set_auth_context(&mut self, auth: Option<AuthContext>) {
+        self.auth = auth;
+    }
+
+    /// 本会话的默认偏好
+    pub fn session_preferences(&self) -> &SessionPreferences {
+        &self.session
+    }
+
+    /// 检查当前调用方能否调用指定工具
+    async fn authorize_tool(&self, tool_name: &str, params: &Value) -> std::result::Result<(), MCPError> {
+        let Some((authenticator, principal)) = &self.auth else {
+            return Ok(());
+        };
+        let scope = self.mcp_server.read().await.required_scope(tool_name, params).await;
+        authenticator.authorize(principal, scope, tool_name).map_err(|_| {
+            MCPError::AuthorizationError(format!("密钥 {} 缺少 {} 范围，无法调用 {}", principal.name, scope.as_str(), tool_name))
+        })
+    }
+
+    /// 按当前调用方的每分钟上限消耗一次调用名额
+    fn check_rate_limit(&self, tool_name: &str) -> std::result::Result<(), MCPError> {
+        let Some((authenticator, principal)) = &self.auth else {
+            return Ok(());
+        };
+        authenticator.check_rate_limit(principal, tool_name).map_err(|_| {
+            MCPError::RateLimitError(format!("密钥 {} 超过每分钟 {} 次的调用上限", principal.name, principal.rate_limit_per_minute().unwrap_or_default()))
+        })
+    }
+
+    /// 记录当前调用方的一次工具调用
+    fn record_usage(&self, tool_name: &str, success: bool, duration: Duration) {
+        if let Some((authenticator, principal)) = &self.auth {
+            authenticator.record_usage(principal, tool_name, success, duration);
+        }
+    }
+
+    /// 记录一次工具调用到审计日志
+    fn audit_call(&self, audit: &AuditLog, tool_name: &str, params: &Value, duration: Duration, outcome: AuditOutcome, error: Option<String>) {
+        let principal = self.auth.as_ref().map(|(_, principal)| principal.name.as_str());
+        audit.record(
+            &AuditRecord::new(tool_name, params, &self.session_id, duration, outcome)
+                .with_principal(principal)
+                .with_error(error),
+        );
+    }
+
     /// 运行服务器
     pub async fn run(&mut self) -> Result<()> {
         let stdin = tokio::io::stdin();
         let mut stdout = tokio::io::stdout();
-        let mut reader = BufReader::new(stdin);
+        let mut lines = BufReader::new(stdin).lines();
+
+        // 资源订阅：后台缓存新增文档时推送通知
+        let resources = self.mcp_server.read().await.resources();
+        let mut notifier = resources.as_deref().map(ResourceNotifier::new).transpose()?;
+        let mut updates = resources.map(|r| r.subscribe_updates());
+
+        // 工具调用期间产生的部分结果先于最终响应写出
+        let (partial_sender, mut partial_receiver) = tokio::sync::mpsc::unbounded_channel();
+        // 采样请求与部分结果共用同一个出口，客户端的回复在处理请求期间读取
+        let sampling = self.enable_sampling(partial_sender.clone());
+        self.set_partial_result_sender(Some(partial_sender));
+        // 处理请求期间读到的其他客户端请求，按顺序留到之后处理
+        let mut backlog: std::collections::VecDeque<String> = std::collections::VecDeque::new();
+        let mut stdin_closed = false;
+
+        let shutdown = self.mcp_server.read().await.shutdown();
 
         eprintln!("🔧 MCP服务器已启动，等待请求...");
 
         loop {
-            let mut request_line = String::new();
-            match reader.read_line(&mut request_line).await {
-                Ok(0) => {
-                    eprintln!("📡 客户端断开连接");
-                    break; // EOF
-                },
-                Ok(n) => {
-                    eprintln!("📥 收到 {} 字节数据: {}", n, request_line.trim());
-                },
-                Err(e) => {
-                    eprintln!("❌ 读取stdin错误: {}", e);
-                    break;
+            let request_line = if let Some(line) = backlog.pop_front() {
+                line
+            } else if stdin_closed {
+                eprintln!("📡 客户端断开连接");
+                break;
+            } else {
+                tokio::select! {
+                    _ = shutdown.triggered() => {
+                        eprintln!("🛑 服务器正在关闭，停止读取请求");
+                        break;
+                    }
+                    line = lines.next_line() => match line {
+                        Ok(Some(line)) => {
+                            eprintln!("📥 收到 {} 字节数据: {}", line.len(), line.trim());
+                            line
+                        }
+                        Ok(None) => {
+                            eprintln!("📡 客户端断开连接");
+                            break; // EOF
+                        }
+                        Err(e) => {
+                            eprintln!("❌ 读取stdin错误: {}", e);
+                            break;
+                        }
+                    },
+                    update = next_update(&mut updates) => {
+                        if let Some(notifier) = notifier.as_mut() {
+                            let (uri, list_changed) = notifier.observe(&update);
+                            let subscriptions = self.subscriptions.lock().clone();
+                            for notification in ResourceNotifier::notifications(&uri, list_changed, &subscriptions) {
+                                stdout.write_all(notification.to_string().as_bytes()).await?;
+                                stdout.write_all(b"\n").await?;
+                            }
+                            stdout.flush().await?;
+                        }
+                        continue;
+                    }
                 }
+            };
+
+            // 迟到的采样响应（请求已超时）不是新请求，直接丢弃
+            if sampling::route_client_response(Some(&sampling), &request_line) {
+                continue;
             }
 
             // 解析请求
-            let request: Request = match serde_json::from_str::<Request>(&request_line) {
+            let request: Request = match protocol::parse_request(&request_line) {
                 Ok(req) => {
                     eprintln!("✅ 请求解析成功: {} - {}", req.method, req.id);
                     req
                 },
-                Err(e) => {
-                    eprintln!("❌ 请求解析失败: {}", e);
-                    self.send_error_async(&mut stdout, "", -32700, &format!("Parse error: {}", e)).await?;
+                Err(error_response) => {
+                    eprintln!("❌ 请求解析失败: {:?}", error_response.error);
+                    self.send_response_async(&mut stdout, &error_response).await?;
                     continue;
                 }
             };
 
             debug!("Received request: {:?}", request);
 
-            // 处理请求
+            // 处理请求：期间写出部分结果和采样请求，并继续读取客户端的采样响应
             eprintln!("🔄 处理请求: {}", request.method);
-            let response = self.handle_request(request).await;
+            let handling = self.handle_request(request);
+            tokio::pin!(handling);
+            let response = loop {
+                tokio::select! {
+                    response = &mut handling => break response,
+                    Some(notification) = partial_receiver.recv() => {
+                        stdout.write_all(notification.to_string().as_bytes()).await?;
+                        stdout.write_all(b"\n").await?;
+                        stdout.flush().await?;
+                    }
+                    line = lines.next_line(), if !stdin_closed => match line {
+                        Ok(Some(line)) => {
+                            if !sampling::route_client_response(Some(&sampling), &line) {
+                                backlog.push_back(line);
+                            }
+                        }
+                        Ok(None) => stdin_closed = true,
+                        Err(e) => {
+                            eprintln!("❌ 读取stdin错误: {}", e);
+                            stdin_closed = true;
+                        }
+                    },
+                }
+            };
+            while let Ok(notification) = partial_receiver.try_recv() {
+                stdout.write_all(notification.to_string().as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+            }
             eprintln!("✅ 请求处理完成");
 
             // 发送响应
@@ -395,11 +767,32 @@ impl Server {
     }
 
     /// 处理 MCP 请求
-    async fn handle_request(&mut self, request: Request) -> Response {
-        // 检查版本兼容性
+    ///
+    /// 每个请求在 `mcp_request` span 和关联ID作用域中处理，响应中带回关联ID。
+    pub async fn handle_request(&mut self, request: Request) -> Response {
+        let correlation_id = correlation::from_params(&request.params).unwrap_or_else(correlation::generate);
+        let span = info_span!("mcp_request", correlation_id = %correlation_id, method = %request.method, request_id = %request.id);
+        let shutdown = self.mcp_server.read().await.shutdown();
+        let sampling = self.sampling_client();
+        let mut response = match shutdown.begin() {
+            Some(_in_flight) => {
+                let namespace = self.auth.as_ref().and_then(|(_, principal)| principal.namespace.clone());
+                let dispatch = namespace::scope(namespace, sampling::scope(sampling, self.dispatch_request(request)));
+                correlation::scope(correlation_id.clone(), dispatch)
+                    .instrument(span)
+                    .await
+            }
+            None => shutting_down(request.id),
+        };
+        response.version = self.protocol_version.as_str().to_string();
+        response.correlation_id = Some(correlation_id);
+        response
+    }
+
+    async fn dispatch_request(&mut self, request: Request) -> Response {
         match request.method.as_str() {
             "initialize" => {
-                match self.handle_initialize(&request.params) {
+                match self.handle_initialize(&request.params, &request.version) {
                     Ok(result) => {
                         self.initialized = true;
                         info!("服务器初始化成功");
@@ -414,35 +807,56 @@ impl Server {
             "tools/list" => {
                 if !self.initialized {
                     warn!("服务器未初始化，拒绝tools/list请求");
-                    return Response::error(request.id, -32002, "服务器未初始化".to_string());
+                    return not_initialized(request.id);
                 }
                 self.handle_list_tools(request.id).await
             }
             "tools/call" => {
                 if !self.initialized {
                     warn!("服务器未初始化，拒绝tools/call请求");
-                    return Response::error(request.id, -32002, "服务器未初始化".to_string());
+                    return not_initialized(request.id);
+                }
+                if request.params.get("name").and_then(|v| v.as_str()) == Some(SESSION_TOOL_NAME) {
+                    return self.handle_session_preferences(request.id, &request.params);
                 }
                 self.handle_tool_call(request.id, &request.params).await
             }
             "health_check" => {
                 if !self.initialized {
-                    return Response::error(request.id, -32002, "服务器未初始化".to_string());
+                    return not_initialized(request.id);
                 }
                 self.handle_health_check(request.id).await
             }
             "get_stats" => {
                 if !self.initialized {
-                    return Response::error(request.id, -32002, "服务器未初始化".to_string());
+                    return not_initialized(request.id);
                 }
                 self.handle_stats_request(request.id).await
             }
             "tools/batch_call" => {
                 if !self.initialized {
-                    return Response::error(request.id, -32002, "服务器未初始化".to_string());
+                    return not_initialized(request.id);
                 }
                 self.handle_batch_tool_call(request.id, &request.params).await
             }
+            "resources/list" | "resources/templates/list" | "resources/read" | "resources/subscribe" | "resources/unsubscribe" => {
+                if !self.initialized {
+                    return not_initialized(request.id);
+                }
+                self.handle_resources_request(request.id, &request.method, &request.params).await
+            }
+            "prompts/list" => {
+                if !self.initialized {
+                    return not_initialized(request.id);
+                }
+                Response::success(request.id, serde_json::json!({ "prompts": super::prompts::list_prompts() }))
+            }
+            "prompts/get" => {
+                if !self.initialized {
+                    return not_initialized(request.id);
+                }
+                self.handle_get_prompt(request.id, &request.params)
+            }
             _ => {
                 warn!("不支持的方法: {}", request.method);
                 Response::error(request.id, -32601, format!("不支持的方法: {}", request.method))
@@ -450,19 +864,42 @@ impl Server {
         }
     }
 
-    fn handle_initialize(&self, params: &Value) -> Result<InitializeResult> {
+    fn handle_initialize(&mut self, params: &Value, request_version: &str) -> Result<InitializeResult> {
         info!("处理初始化请求: {:?}", params);
         
-        // 解析初始化参数（如果需要）
-        if let Ok(_init_params) = serde_json::from_value::<InitializeParams>(params.clone()) {
-            debug!("初始化参数解析成功");
+        let init_params = serde_json::from_value::<InitializeParams>(params.clone()).unwrap_or_else(|e| {
+            debug!("初始化参数解析失败，按默认参数处理: {}", e);
+            InitializeParams::default()
+        });
+        // 旧版客户端只在请求的 version 字段中携带协议版本
+        let requested = init_params
+            .protocol_version
+            .as_deref()
+            .or(Some(request_version).filter(|v| !v.is_empty()));
+        let negotiated = ProtocolVersion::negotiate(requested);
+        match requested {
+            Some(requested) if requested != negotiated.as_str() => {
+                warn!("客户端请求的协议版本 {} 不受支持，回复 {}", requested, negotiated);
+            }
+            _ => info!(
+                "协商协议版本 {}（客户端: {}）",
+                negotiated,
+                init_params.client_name().unwrap_or("未知")
+            ),
         }
-        
+        self.protocol_version = negotiated;
+        self.client_sampling = init_params.capabilities.get("sampling").is_some();
+
         Ok(InitializeResult {
-            server_name: self.name.clone(),
-            server_version: self.version.clone(),
-            protocol_version: MCP_VERSION.to_string(),
-            capabilities: SERVER_CAPABILITIES.iter().map(|&s| s.to_string()).collect(),
+            protocol_version: negotiated.as_str().to_string(),
+            capabilities: negotiated.server_capabilities(SERVER_CAPABILITIES),
+            server_info: PeerInfo {
+                name: self.name.clone(),
+                version: self.version.clone(),
+            },
+            instructions: negotiated
+                .supports_instructions()
+                .then(|| format!("{} 提供多语言文档搜索、包版本查询和 API 参考工具，调用前可先通过 tools/list 查看参数。", self.name)),
         })
     }
 
@@ -473,6 +910,12 @@ impl Server {
         match server.list_tools().await {
             Ok(tools) => {
                 info!("成功获取工具列表，共 {} 个工具", tools.len());
+                let mut tools = tools;
+                tools.push(session::session_tool_info());
+                let mut tools = serde_json::to_value(tools).unwrap_or_else(|_| Value::Array(Vec::new()));
+                if let Some(tools) = tools.as_array_mut() {
+                    tools.iter_mut().for_each(|tool| self.protocol_version.adapt_tool_info(tool));
+                }
                 Response::success(id, serde_json::json!({
                     "tools": tools
                 }))
@@ -495,10 +938,52 @@ impl Server {
             }
         };
 
-        let tool_params = params.get("arguments").unwrap_or(&Value::Null).clone();
+        let started = Instant::now();
+        let mut tool_params = params.get("arguments").unwrap_or(&Value::Null).clone();
+        let audit = {
+            let server = self.mcp_server.read().await;
+            server.apply_session_defaults(tool_name, &mut tool_params, &self.session).await;
+            server.audit_log()
+        };
+        if let Err(e) = self.authorize_tool(tool_name, &tool_params).await {
+            if let Some(audit) = &audit {
+                self.audit_call(audit, tool_name, &tool_params, started.elapsed(), AuditOutcome::Denied, Some(e.to_string()));
+            }
+            return Response::error_with_data(id, error_codes::PERMISSION_DENIED, e.to_string(), &e.payload());
+        }
+        if let Err(e) = self.check_rate_limit(tool_name) {
+            if let Some(audit) = &audit {
+                self.audit_call(audit, tool_name, &tool_params, started.elapsed(), AuditOutcome::Denied, Some(e.to_string()));
+            }
+            return Response::error_with_data(id, error_codes::RATE_LIMITED, e.to_string(), &e.payload());
+        }
+        // 参数会移交给工具，启用审计时先保留一份用于计算哈希
+        let audited_params = audit.as_ref().map(|_| tool_params.clone());
+        let sink = self.partial_results
+            .as_ref()
+            .and_then(|sender| PartialResultSink::from_call_params(params, sender));
         
         let server = self.mcp_server.read().await;
-        match server.execute_tool(tool_name, tool_params).await {
+        let outcome = match &sink {
+            Some(sink) => server.execute_tool_streaming(tool_name, tool_params, sink).await,
+            None => server.execute_tool(tool_name, tool_params).await,
+        };
+        self.record_usage(tool_name, outcome.is_ok(), started.elapsed());
+        if let (Some(audit), Some(audited_params)) = (&audit, &audited_params) {
+            let (audit_outcome, error) = match &outcome {
+                Ok(_) => (AuditOutcome::Success, None),
+                Err(e) => {
+                    let audit_outcome = match find_mcp_error(e) {
+                        Some(MCPError::Timeout(_)) => AuditOutcome::Timeout,
+                        Some(MCPError::AuthorizationError(_)) => AuditOutcome::Denied,
+                        _ => AuditOutcome::Error,
+                    };
+                    (audit_outcome, Some(format!("{:#}", e)))
+                }
+            };
+            self.audit_call(audit, tool_name, audited_params, started.elapsed(), audit_outcome, error);
+        }
+        match outcome {
             Ok(result) => {
                 info!("工具 {} 执行成功", tool_name);
                 
@@ -514,6 +999,9 @@ impl Server {
                         if let Some(summary) = result.get("summary").and_then(|s| s.as_str()) {
                             formatted_content.push_str(&format!("{}\n\n", summary));
                         }
+                        if let Some(items) = result["streamed"]["items"].as_u64() {
+                            formatted_content.push_str(&format!("共 {} 条结果已通过 {} 分块推送\n\n", items, PARTIAL_RESULT_METHOD));
+                        }
                         
                         for (i, item) in results_array.iter().enumerate() {
                             if let (Some(title), Some(content)) = (
@@ -536,22 +1024,57 @@ impl Server {
                     }
                 };
                 
-                Response::success(id, serde_json::json!({
+                let mut call_result = serde_json::json!({
                     "content": [
                         {
                             "type": "text",
                             "text": content_text
                         }
                     ]
-                }))
+                });
+                // 对象结果同时以结构化形式返回，便于客户端按类型解析
+                if result.is_object() {
+                    call_result["structuredContent"] = result;
+                }
+                self.protocol_version.adapt_tool_result(&mut call_result);
+
+                Response::success(id, call_result)
             }
             Err(e) => {
                 error!("工具 {} 执行失败: {}", tool_name, e);
-                Response::error(id, -32603, format!("工具执行失败: {}", e))
+                let code = match find_mcp_error(&e) {
+                    Some(MCPError::Timeout(_)) => error_codes::TOOL_TIMEOUT,
+                    Some(MCPError::AuthorizationError(_)) => error_codes::PERMISSION_DENIED,
+                    Some(MCPError::InvalidParameter(_) | MCPError::ToolNotFound(_)) => error_codes::INVALID_PARAMS,
+                    _ => error_codes::INTERNAL_ERROR,
+                };
+                Response::error_with_data(id, code, format!("工具执行失败: {:#}", e), &ErrorPayload::from_error(&e))
             }
         }
     }
 
+    /// 处理 `set_session_preferences`：更新本会话偏好并返回更新后的偏好
+    fn handle_session_preferences(&mut self, id: String, params: &Value) -> Response {
+        let arguments = params.get("arguments").unwrap_or(&Value::Null);
+        if let Err(e) = self.session.update(arguments) {
+            return Response::error_with_data(id, error_codes::INVALID_PARAMS, e.to_string(), &e.payload());
+        }
+        info!("会话偏好已更新: {:?}", self.session);
+
+        let preferences = self.session.to_value();
+        let mut call_result = serde_json::json!({
+            "content": [
+                {
+                    "type": "text",
+                    "text": serde_json::to_string_pretty(&preferences).unwrap_or_else(|_| preferences.to_string())
+                }
+            ],
+            "structuredContent": preferences
+        });
+        self.protocol_version.adapt_tool_result(&mut call_result);
+        Response::success(id, call_result)
+    }
+
     async fn handle_health_check(&self, id: String) -> Response {
         debug!("处理健康检查请求");
         
@@ -592,6 +1115,7 @@ impl Server {
                 Response::success(id, serde_json::json!({
                     "tool_count": tool_count,
                     "performance_stats": stats,
+                    "auth": self.auth.as_ref().map(|(authenticator, _)| authenticator.metrics()),
                     "server_info": {
                         "name": self.name,
                         "version": self.version,
@@ -607,6 +1131,61 @@ impl Server {
         }
     }
 
+    fn handle_get_prompt(&self, id: String, params: &Value) -> Response {
+        let Some(name) = params.get("name").and_then(|v| v.as_str()) else {
+            return Response::error(id, -32602, "缺少name参数".to_string());
+        };
+        let arguments = params.get("arguments").and_then(|v| v.as_object()).cloned().unwrap_or_default();
+        match super::prompts::get_prompt(name, &arguments) {
+            Ok(prompt) => Response::success(id, prompt),
+            Err(e) => Response::error(id, -32602, e.to_string()),
+        }
+    }
+
+    async fn handle_resources_request(&self, id: String, method: &str, params: &Value) -> Response {
+        debug!("处理资源请求: {} {:?}", method, params);
+
+        let Some(resources) = self.mcp_server.read().await.resources() else {
+            return Response::error(id, -32601, format!("不支持的方法: {}", method));
+        };
+        let uri = params.get("uri").and_then(|v| v.as_str());
+
+        match method {
+            "resources/list" => match resources.list() {
+                Ok(list) => Response::success(id, serde_json::json!({ "resources": list })),
+                Err(e) => Response::error(id, -32603, format!("列出资源失败: {}", e)),
+            },
+            "resources/templates/list" => {
+                Response::success(id, serde_json::json!({ "resourceTemplates": resources.templates() }))
+            }
+            "resources/read" => {
+                let Some(uri) = uri else {
+                    return Response::error(id, -32602, "缺少uri参数".to_string());
+                };
+                match resources.read(uri) {
+                    Ok(result) => Response::success(id, result),
+                    Err(e) => Response::error(id, -32002, format!("读取资源失败: {}", e)),
+                }
+            }
+            _ => {
+                let Some(uri) = uri else {
+                    return Response::error(id, -32602, "缺少uri参数".to_string());
+                };
+                if let Err(e) = super::resources::DocResourceUri::parse(uri) {
+                    return Response::error(id, -32602, e.to_string());
+                }
+                let mut subscriptions = self.subscriptions.lock();
+                if method == "resources/subscribe" {
+                    subscriptions.insert(uri.to_string());
+                    info!("已订阅资源: {}", uri);
+                } else {
+                    subscriptions.remove(uri);
+                }
+                Response::success(id, serde_json::json!({}))
+            }
+        }
+    }
+
     async fn handle_batch_tool_call(&self, id: String, params: &Value) -> Response {
         debug!("处理批量工具调用请求: {:?}", params);
         
@@ -618,15 +1197,17 @@ impl Server {
             }
         };
 
+        let server = self.mcp_server.read().await;
         let mut tool_requests = Vec::new();
         for req in requests {
-            if let (Some(tool_name), tool_params) = (
+            if let (Some(tool_name), mut tool_params) = (
                 req.get("name").and_then(|v| v.as_str()),
                 req.get("arguments").unwrap_or(&Value::Null).clone()
             ) {
                 let timeout = req.get("timeout")
                     .and_then(|v| v.as_u64())
                     .map(|t| Duration::from_secs(t));
+                server.apply_session_defaults(tool_name, &mut tool_params, &self.session).await;
                 
                 tool_requests.push(ToolRequest {
                     tool_name: tool_name.to_string(),
@@ -636,13 +1217,46 @@ impl Server {
             }
         }
 
+        // 授权检查会再次获取读锁，先释放
+        drop(server);
+
         if tool_requests.is_empty() {
             return Response::error(id, -32602, "没有有效的工具请求".to_string());
         }
+        let audit = self.mcp_server.read().await.audit_log();
+        for request in &tool_requests {
+            if let Err(e) = self.authorize_tool(&request.tool_name, &request.params).await {
+                if let Some(audit) = &audit {
+                    self.audit_call(audit, &request.tool_name, &request.params, Duration::ZERO, AuditOutcome::Denied, Some(e.to_string()));
+                }
+                return Response::error_with_data(id, error_codes::PERMISSION_DENIED, e.to_string(), &e.payload());
+            }
+        }
+        for request in &tool_requests {
+            if let Err(e) = self.check_rate_limit(&request.tool_name) {
+                if let Some(audit) = &audit {
+                    self.audit_call(audit, &request.tool_name, &request.params, Duration::ZERO, AuditOutcome::Denied, Some(e.to_string()));
+                }
+                return Response::error_with_data(id, error_codes::RATE_LIMITED, e.to_string(), &e.payload());
+            }
+        }
+        let audited_params: Vec<Value> = match &audit {
+            Some(_) => tool_requests.iter().map(|request| request.params.clone()).collect(),
+            None => Vec::new(),
+        };
 
         let server = self.mcp_server.read().await;
         match server.batch_execute_tools(tool_requests).await {
             Ok(results) => {
+                for result in &results {
+                    self.record_usage(&result.tool_name, result.success, result.execution_time);
+                }
+                if let Some(audit) = &audit {
+                    for (result, params) in results.iter().zip(&audited_params) {
+                        let outcome = if result.success { AuditOutcome::Success } else { AuditOutcome::Error };
+                        self.audit_call(audit, &result.tool_name, params, result.execution_time, outcome, result.error.clone());
+                    }
+                }
                 info!("批量工具执行完成，共 {} 个结果", results.len());
                 Response::success(id, serde_json::json!({
                     "results": results
@@ -655,15 +1269,12 @@ impl Server {
         }
     }
 
-    async fn send_error_async(
+    async fn send_response_async(
         &self,
         writer: &mut tokio::io::Stdout,
-        id: &str,
-        code: i32,
-        message: &str,
+        response: &Response,
     ) -> Result<()> {
-        let error_response = Response::error(id.to_string(), code, message.to_string());
-        let response_json = serde_json::to_string(&error_response)?;
+        let response_json = serde_json::to_string(response)?;
         writer.write_all(response_json.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
@@ -686,4 +1297,390 @@ mod tests {
 
         assert!(!server.initialized);
     }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_older_protocol_version() {
+        let mcp_server = MCPServer::new();
+        mcp_server.register_tool(Box::new(ListTool)).await.unwrap();
+        let mut server = Server::new("Test Server".to_string(), "1.0.0".to_string(), mcp_server);
+
+        let request: Request = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "clientInfo": { "name": "old-ide", "version": "0.1" },
+                "capabilities": {}
+            }
+        })).unwrap();
+        let response = server.handle_request(request).await;
+        let result = response.result.unwrap();
+        assert_eq!(result["protocolVersion"], "2024-11-05");
+        assert_eq!(result["serverInfo"]["name"], "Test Server");
+        assert!(result.get("instructions").is_none());
+        assert_eq!(response.version, "2024-11-05");
+        assert_eq!(server.protocol_version(), ProtocolVersion::V2024_11_05);
+
+        // 旧版本客户端不认识 structuredContent
+        let response = server.handle_tool_call("2".to_string(), &serde_json::json!({ "name": "list_tool" })).await;
+        let result = response.result.unwrap();
+        assert!(result.get("structuredContent").is_none());
+        assert!(result["content"][0]["text"].as_str().unwrap().contains("list_tool"));
+
+        // 不支持的版本回复最新版本
+        let request: Request = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0", "id": "3", "method": "initialize",
+            "params": { "protocolVersion": "2030-01-01" }
+        })).unwrap();
+        let result = server.handle_request(request).await.result.unwrap();
+        assert_eq!(result["protocolVersion"], ProtocolVersion::LATEST.as_str());
+    }
+
+    struct SlowTool {
+        delay: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl MCPTool for SlowTool {
+        fn name(&self) -> &str {
+            "slow_tool"
+        }
+
+        fn description(&self) -> &str {
+            "测试用的慢速工具"
+        }
+
+        fn parameters_schema(&self) -> &crate::tools::base::Schema {
+            static SCHEMA: std::sync::OnceLock<crate::tools::base::Schema> = std::sync::OnceLock::new();
+            SCHEMA.get_or_init(|| crate::tools::base::Schema::Object(Default::default()))
+        }
+
+        async fn execute(&self, _params: Value) -> Result<Value> {
+            tokio::time::sleep(self.delay).await;
+            Ok(serde_json::json!({ "status": "ok" }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_timeout_maps_to_dedicated_error_code() {
+        let mut limits = ToolExecutionConfig::default();
+        limits.tools.insert("slow_tool".to_string(), crate::config::ToolLimitConfig {
+            timeout_ms: Some(20),
+            max_concurrent: Some(1),
+        });
+        let mcp_server = MCPServer::with_limits(limits);
+        mcp_server.register_tool(Box::new(SlowTool { delay: Duration::from_millis(200) })).await.unwrap();
+
+        let err = mcp_server.execute_tool("slow_tool", Value::Null).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<MCPError>(), Some(MCPError::Timeout(_))));
+
+        let server = Server::new("Test Server".to_string(), "1.0.0".to_string(), mcp_server);
+        let response = server.handle_tool_call("1".to_string(), &serde_json::json!({ "name": "slow_tool" })).await;
+        assert_eq!(response.error.unwrap().code, error_codes::TOOL_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_per_tool_concurrency_limit_counts_against_timeout() {
+        let mut limits = ToolExecutionConfig::default();
+        limits.tools.insert("slow_tool".to_string(), crate::config::ToolLimitConfig {
+            timeout_ms: Some(150),
+            max_concurrent: Some(1),
+        });
+        let mcp_server = MCPServer::with_limits(limits);
+        mcp_server.register_tool(Box::new(SlowTool { delay: Duration::from_millis(100) })).await.unwrap();
+
+        // 第二个调用需要等第一个释放名额，总耗时超过超时时间
+        let (first, second) = tokio::join!(
+            mcp_server.execute_tool("slow_tool", Value::Null),
+            mcp_server.execute_tool("slow_tool", Value::Null),
+        );
+        assert_eq!(first.is_ok() as u8 + second.is_ok() as u8, 1);
+    }
+
+    struct CacheTool;
+
+    #[async_trait::async_trait]
+    impl MCPTool for CacheTool {
+        fn name(&self) -> &str {
+            "cache_tool"
+        }
+
+        fn description(&self) -> &str {
+            "测试用的读写工具"
+        }
+
+        fn parameters_schema(&self) -> &crate::tools::base::Schema {
+            use crate::tools::base::{Schema, SchemaObject, SchemaString};
+            static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+            SCHEMA.get_or_init(|| Schema::Object(SchemaObject {
+                required: vec!["action".to_string()],
+                properties: HashMap::from([("action".to_string(), Schema::String(SchemaString {
+                    description: None,
+                    enum_values: Some(vec!["search".to_string(), "store".to_string(), "delete".to_string()]),
+                }))]),
+                description: None,
+            }))
+        }
+
+        fn mutating_actions(&self) -> &[&'static str] {
+            &["store", "delete"]
+        }
+
+        async fn execute(&self, params: Value) -> Result<Value> {
+            Ok(serde_json::json!({ "action": params["action"] }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_only_mode_hides_and_rejects_mutating_actions() {
+        let mcp_server = MCPServer::with_limits(ToolExecutionConfig { read_only: true, ..ToolExecutionConfig::default() });
+        mcp_server.register_tool(Box::new(CacheTool)).await.unwrap();
+        assert!(mcp_server.is_read_only());
+
+        let info = mcp_server.get_tool_info("cache_tool").await.unwrap().unwrap();
+        let advertised = info.parameters.to_string();
+        assert!(advertised.contains("search"));
+        assert!(!advertised.contains("store") && !advertised.contains("delete"));
+
+        assert!(mcp_server.execute_tool("cache_tool", serde_json::json!({ "action": "search" })).await.is_ok());
+        let server = Server::new("Test Server".to_string(), "1.0.0".to_string(), mcp_server);
+        let response = server
+            .handle_tool_call("1".to_string(), &serde_json::json!({ "name": "cache_tool", "arguments": { "action": "store" } }))
+            .await;
+        assert_eq!(response.error.unwrap().code, error_codes::PERMISSION_DENIED);
+
+        // 默认可写时 Schema 保留全部操作
+        let writable = MCPServer::new();
+        writable.register_tool(Box::new(CacheTool)).await.unwrap();
+        let info = writable.get_tool_info("cache_tool").await.unwrap().unwrap();
+        assert!(info.parameters.to_string().contains("store"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = Arc::new(AuditLog::open(dir.path().join("audit.jsonl")).unwrap());
+        let mut mcp_server = MCPServer::with_limits(ToolExecutionConfig { read_only: true, ..ToolExecutionConfig::default() });
+        mcp_server.set_audit_log(Arc::clone(&audit));
+        mcp_server.register_tool(Box::new(CacheTool)).await.unwrap();
+        let server = Server::new("Test Server".to_string(), "1.0.0".to_string(), mcp_server);
+
+        let search = serde_json::json!({ "action": "search" });
+        server.handle_tool_call("1".to_string(), &serde_json::json!({ "name": "cache_tool", "arguments": search })).await;
+        server.handle_tool_call("2".to_string(), &serde_json::json!({ "name": "cache_tool", "arguments": { "action": "store" } })).await;
+
+        let records = audit.query(&Default::default()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].outcome, AuditOutcome::Denied);
+        assert_eq!(records[1].outcome, AuditOutcome::Success);
+        assert_eq!(records[1].params_hash, crate::mcp::audit::params_hash(&search));
+        assert!(records.iter().all(|record| record.session == server.session_id()));
+    }
+
+    struct ListTool;
+
+    #[async_trait::async_trait]
+    impl MCPTool for ListTool {
+        fn name(&self) -> &str {
+            "list_tool"
+        }
+
+        fn description(&self) -> &str {
+            "测试用的列表结果工具"
+        }
+
+        fn parameters_schema(&self) -> &crate::tools::base::Schema {
+            static SCHEMA: std::sync::OnceLock<crate::tools::base::Schema> = std::sync::OnceLock::new();
+            SCHEMA.get_or_init(|| crate::tools::base::Schema::Object(Default::default()))
+        }
+
+        async fn execute(&self, _params: Value) -> Result<Value> {
+            Ok(serde_json::json!({
+                "results": [{ "title": "a" }, { "title": "b" }, { "title": "c" }],
+                "total_hits": 3,
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_streams_partial_results_with_progress_token() {
+        let mcp_server = MCPServer::new();
+        mcp_server.register_tool(Box::new(ListTool)).await.unwrap();
+        let mut server = Server::new("Test Server".to_string(), "1.0.0".to_string(), mcp_server);
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        server.set_partial_result_sender(Some(sender));
+
+        // 未提供 progressToken 时一次性返回
+        let response = server.handle_tool_call("1".to_string(), &serde_json::json!({ "name": "list_tool" })).await;
+        assert_eq!(response.result.unwrap()["structuredContent"]["results"].as_array().unwrap().len(), 3);
+        assert!(receiver.try_recv().is_err());
+
+        let params = serde_json::json!({
+            "name": "list_tool",
+            "_meta": { "progressToken": "search-1", "chunkSize": 2 },
+        });
+        let response = server.handle_tool_call("2".to_string(), &params).await;
+        let structured = &response.result.unwrap()["structuredContent"];
+        assert_eq!(structured["results"], serde_json::json!([]));
+        assert_eq!(structured["streamed"]["items"], 3);
+        assert_eq!(receiver.try_recv().unwrap()["params"]["sequence"], 0);
+        assert_eq!(receiver.try_recv().unwrap()["params"]["sequence"], 1);
+    }
+
+    struct EchoTool;
+
+    #[async_trait::async_trait]
+    impl MCPTool for EchoTool {
+        fn name(&self) -> &str {
+            "echo_tool"
+        }
+
+        fn description(&self) -> &str {
+            "测试用的参数回显工具"
+        }
+
+        fn parameters_schema(&self) -> &crate::tools::base::Schema {
+            static SCHEMA: std::sync::OnceLock<crate::tools::base::Schema> = std::sync::OnceLock::new();
+            SCHEMA.get_or_init(|| crate::tools::base::Schema::Object(Default::default()))
+        }
+
+        fn apply_session_defaults(&self, params: &mut Value, session: &SessionPreferences) {
+            if let Some(language) = session.primary_language() {
+                session::fill_default(params, "language", serde_json::json!(language));
+            }
+        }
+
+        async fn execute(&self, params: Value) -> Result<Value> {
+            Ok(serde_json::json!({ "params": params }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_preferences_fill_omitted_params() {
+        let mcp_server = MCPServer::new();
+        mcp_server.register_tool(Box::new(EchoTool)).await.unwrap();
+        let mut server = Server::new("Test Server".to_string(), "1.0.0".to_string(), mcp_server);
+        server.initialized = true;
+
+        let set_preferences: Request = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0", "id": "1", "method": "tools/call",
+            "params": { "name": SESSION_TOOL_NAME, "arguments": { "preferred_languages": ["rust"] } }
+        })).unwrap();
+        let result = server.handle_request(set_preferences).await.result.unwrap();
+        assert_eq!(result["structuredContent"]["preferred_languages"], serde_json::json!(["rust"]));
+
+        let response = server.handle_tool_call("2".to_string(), &serde_json::json!({ "name": "echo_tool" })).await;
+        assert_eq!(response.result.unwrap()["structuredContent"]["params"]["language"], "rust");
+
+        // 显式参数优先于会话偏好
+        let params = serde_json::json!({ "name": "echo_tool", "arguments": { "language": "go" } });
+        let response = server.handle_tool_call("3".to_string(), &params).await;
+        assert_eq!(response.result.unwrap()["structuredContent"]["params"]["language"], "go");
+
+        // 会话工具出现在工具列表中
+        let tools = server.handle_list_tools("4".to_string()).await.result.unwrap();
+        assert!(tools["tools"].as_array().unwrap().iter().any(|t| t["name"] == SESSION_TOOL_NAME));
+    }
+
+    struct SamplingTool;
+
+    #[async_trait::async_trait]
+    impl MCPTool for SamplingTool {
+        fn name(&self) -> &str {
+            "sampling_tool"
+        }
+
+        fn description(&self) -> &str {
+            "测试用的采样工具"
+        }
+
+        fn parameters_schema(&self) -> &crate::tools::base::Schema {
+            static SCHEMA: std::sync::OnceLock<crate::tools::base::Schema> = std::sync::OnceLock::new();
+            SCHEMA.get_or_init(|| crate::tools::base::Schema::Object(Default::default()))
+        }
+
+        async fn execute(&self, _params: Value) -> Result<Value> {
+            let Some(client) = sampling::current() else {
+                return Ok(serde_json::json!({ "answer": null }));
+            };
+            let request = sampling::SamplingRequest { user_message: "ping".to_string(), max_tokens: 10, ..Default::default() };
+            let response = client.create_message(&request).await?;
+            Ok(serde_json::json!({ "answer": response.content }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_delegates_llm_call_to_client_via_sampling() {
+        let mcp_server = MCPServer::new();
+        mcp_server.register_tool(Box::new(SamplingTool)).await.unwrap();
+        let mut server = Server::new("Test Server".to_string(), "1.0.0".to_string(), mcp_server);
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let sampling = server.enable_sampling(sender);
+
+        let call = |id: &str| -> Request {
+            serde_json::from_value(serde_json::json!({
+                "jsonrpc": "2.0", "id": id, "method": "tools/call", "params": { "name": "sampling_tool" }
+            })).unwrap()
+        };
+
+        // 客户端未声明采样能力时工具拿不到采样客户端
+        server.initialized = true;
+        let response = server.handle_request(call("1")).await;
+        assert!(response.result.unwrap()["structuredContent"]["answer"].is_null());
+
+        let initialize: Request = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0", "id": "2", "method": "initialize",
+            "params": { "protocolVersion": "2025-06-18", "capabilities": { "sampling": {} } }
+        })).unwrap();
+        server.handle_request(initialize).await;
+
+        let client = async {
+            let request = receiver.recv().await.unwrap();
+            assert_eq!(request["method"], sampling::SAMPLING_METHOD);
+            let reply = serde_json::json!({
+                "jsonrpc": "2.0", "id": request["id"],
+                "result": { "role": "assistant", "content": { "type": "text", "text": "pong" }, "model": "host" }
+            });
+            assert!(sampling.deliver(&reply));
+        };
+        let (response, _) = tokio::join!(server.handle_request(call("3")), client);
+        assert_eq!(response.result.unwrap()["structuredContent"]["answer"], "pong");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_requests_after_shutdown_starts() {
+        let mcp_server = MCPServer::new();
+        let shutdown = mcp_server.shutdown();
+        let mut server = Server::new("Test Server".to_string(), "1.0.0".to_string(), mcp_server);
+        server.initialized = true;
+
+        shutdown.trigger();
+        let request: Request = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0", "id": "1", "method": "tools/list", "params": {}
+        })).unwrap();
+        let response = server.handle_request(request).await;
+        assert_eq!(response.error.unwrap().code, error_codes::SERVER_SHUTTING_DOWN);
+        assert_eq!(shutdown.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_response_carries_correlation_id() {
+        let mut server = Server::new("Test Server".to_string(), "1.0.0".to_string(), MCPServer::new());
+        server.initialized = true;
+
+        let generated: Request = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0", "id": "1", "method": "tools/list", "params": {}
+        })).unwrap();
+        let response = server.handle_request(generated).await;
+        assert_eq!(response.correlation_id.as_ref().map(|id| id.len()), Some(32));
+
+        let supplied: Request = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0", "id": "2", "method": "no/such_method", "params": { "_meta": { "correlationId": "ide-req-7" } }
+        })).unwrap();
+        let response = server.handle_request(supplied).await;
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["correlationId"], "ide-req-7");
+        assert!(json["error"].is_object());
+    }
 }
@@ -0,0 +1,194 @@
+//! 会话级偏好
+//!
+//! 每个连接的客户端（stdio 进程、HTTP 会话、WebSocket 连接）各有一份偏好，通过
+//! `set_session_preferences` 工具设置：偏好语言、默认包注册表、结果数量上限。
+//! 之后的工具调用省略对应参数时由工具按偏好补全（见 `MCPTool::apply_session_defaults`），
+//! 调用方显式传入的参数始终优先。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::errors::MCPError;
+use crate::tools::base::{Schema, SchemaArray, SchemaBoolean, SchemaInteger, SchemaObject, SchemaString};
+use super::server::ToolInfo;
+
+/// 设置会话偏好的工具名
+pub const SESSION_TOOL_NAME: &str = "set_session_preferences";
+
+/// 结果数量上限的取值范围
+const MAX_RESULTS_LIMIT: u64 = 100;
+
+/// `default_registry` 可选的包注册表
+pub const KNOWN_REGISTRIES: &[&str] = &["cargo", "npm", "pip", "maven", "go", "pub", "flutter", "dart"];
+
+/// 单个会话的偏好
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionPreferences {
+    /// 偏好的编程语言，第一项作为省略 `language` 时的默认值
+    pub preferred_languages: Vec<String>,
+    /// 省略 `type` 时使用的包注册表
+    pub default_registry: Option<String>,
+    /// 省略 `max_results` 时的结果数量
+    pub max_results: Option<usize>,
+}
+
+impl SessionPreferences {
+    pub fn is_empty(&self) -> bool {
+        self.preferred_languages.is_empty() && self.default_registry.is_none() && self.max_results.is_none()
+    }
+
+    /// 省略语言参数时使用的语言
+    pub fn primary_language(&self) -> Option<&str> {
+        self.preferred_languages.first().map(String::as_str)
+    }
+
+    /// 按工具参数更新偏好：只修改传入的字段，`reset: true` 时先清空
+    pub fn update(&mut self, params: &Value) -> std::result::Result<(), MCPError> {
+        let mut updated = if params["reset"].as_bool().unwrap_or(false) {
+            Self::default()
+        } else {
+            self.clone()
+        };
+
+        if let Some(languages) = params.get("preferred_languages").filter(|v| !v.is_null()) {
+            let languages = languages
+                .as_array()
+                .ok_or_else(|| MCPError::InvalidParameter("preferred_languages 必须是字符串数组".to_string()))?;
+            updated.preferred_languages.clear();
+            for language in languages {
+                let language = language
+                    .as_str()
+                    .map(|l| l.trim().to_lowercase())
+                    .filter(|l| !l.is_empty())
+                    .ok_or_else(|| MCPError::InvalidParameter("preferred_languages 必须是非空字符串数组".to_string()))?;
+                if !updated.preferred_languages.contains(&language) {
+                    updated.preferred_languages.push(language);
+                }
+            }
+        }
+
+        if let Some(registry) = params.get("default_registry").filter(|v| !v.is_null()) {
+            let registry = registry.as_str().map(|r| r.trim().to_lowercase()).unwrap_or_default();
+            updated.default_registry = match registry.as_str() {
+                "" => None,
+                r if KNOWN_REGISTRIES.contains(&r) => Some(registry),
+                _ => {
+                    return Err(MCPError::InvalidParameter(format!(
+                        "无效的 default_registry: {}，可选 {}",
+                        registry,
+                        KNOWN_REGISTRIES.join("|")
+                    )))
+                }
+            };
+        }
+
+        if let Some(max_results) = params.get("max_results").filter(|v| !v.is_null()) {
+            let max_results = max_results
+                .as_u64()
+                .filter(|n| (1..=MAX_RESULTS_LIMIT).contains(n))
+                .ok_or_else(|| MCPError::InvalidParameter(format!("max_results 必须是 1-{} 的整数", MAX_RESULTS_LIMIT)))?;
+            updated.max_results = Some(max_results as usize);
+        }
+
+        *self = updated;
+        Ok(())
+    }
+
+    /// 工具返回的偏好快照
+    pub fn to_value(&self) -> Value {
+        json!({
+            "preferred_languages": self.preferred_languages,
+            "default_registry": self.default_registry,
+            "max_results": self.max_results,
+        })
+    }
+}
+
+/// 参数中缺少（或为 null）`key` 时填入 `value`；参数不是对象时视为空对象
+pub fn fill_default(params: &mut Value, key: &str, value: Value) {
+    if params.is_null() {
+        *params = json!({});
+    }
+    if let Some(params) = params.as_object_mut() {
+        if params.get(key).map_or(true, Value::is_null) {
+            params.insert(key.to_string(), value);
+        }
+    }
+}
+
+/// `set_session_preferences` 在工具列表中的描述
+pub fn session_tool_info() -> ToolInfo {
+    let mut properties = HashMap::new();
+    properties.insert("preferred_languages".to_string(), Schema::Array(SchemaArray {
+        description: Some("偏好的编程语言，第一项作为 search_docs 等工具省略 language 时的默认值".to_string()),
+        items: Box::new(Schema::String(SchemaString::default())),
+    }));
+    properties.insert("default_registry".to_string(), Schema::String(SchemaString {
+        description: Some("check_latest_version 省略 type 时使用的包注册表，传空字符串清除".to_string()),
+        enum_values: None,
+    }));
+    properties.insert("max_results".to_string(), Schema::Integer(SchemaInteger {
+        description: Some("搜索工具省略 max_results 时的结果数量".to_string()),
+        minimum: Some(1),
+        maximum: Some(MAX_RESULTS_LIMIT as i64),
+    }));
+    properties.insert("reset".to_string(), Schema::Boolean(SchemaBoolean {
+        description: Some("先清空当前会话的所有偏好".to_string()),
+    }));
+    let schema = Schema::Object(SchemaObject {
+        properties,
+        ..Default::default()
+    });
+
+    ToolInfo {
+        name: SESSION_TOOL_NAME.to_string(),
+        description: "设置当前会话的默认偏好（偏好语言、默认包注册表、结果数量），之后的调用省略对应参数时自动使用。不传参数时返回当前偏好。".to_string(),
+        parameters: serde_json::to_value(schema).unwrap_or_else(|_| json!({})),
+        output_schema: None,
+        language: None,
+        category: Some("session".to_string()),
+        version: Some("1.0.0".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_preferences() {
+        let mut prefs = SessionPreferences::default();
+        prefs
+            .update(&json!({"preferred_languages": ["Rust", "python", "rust"], "max_results": 5}))
+            .unwrap();
+        assert_eq!(prefs.preferred_languages, vec!["rust", "python"]);
+        assert_eq!(prefs.primary_language(), Some("rust"));
+        assert_eq!(prefs.max_results, Some(5));
+
+        // 只修改传入的字段
+        prefs.update(&json!({"default_registry": "cargo"})).unwrap();
+        assert_eq!(prefs.default_registry.as_deref(), Some("cargo"));
+        assert_eq!(prefs.max_results, Some(5));
+
+        // 无效参数不修改已有偏好
+        assert!(prefs.update(&json!({"default_registry": "cpan", "max_results": 3})).is_err());
+        assert!(prefs.update(&json!({"max_results": 0})).is_err());
+        assert_eq!(prefs.max_results, Some(5));
+
+        prefs.update(&json!({"reset": true})).unwrap();
+        assert!(prefs.is_empty());
+    }
+
+    #[test]
+    fn test_fill_default_keeps_explicit_params() {
+        let mut params = json!({"language": "go"});
+        fill_default(&mut params, "language", json!("rust"));
+        fill_default(&mut params, "max_results", json!(5));
+        assert_eq!(params, json!({"language": "go", "max_results": 5}));
+
+        let mut params = Value::Null;
+        fill_default(&mut params, "type", json!("npm"));
+        assert_eq!(params, json!({"type": "npm"}));
+    }
+}
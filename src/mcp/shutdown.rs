@@ -0,0 +1,169 @@
+//! 优雅关闭
+//!
+//! 进程收到 SIGINT / SIGTERM 后：
+//! 1. 停止接受新请求（新请求返回 `SERVER_SHUTTING_DOWN` 错误），传输层停止接受新连接；
+//! 2. 在截止时间内等待进行中的请求和后台缓存任务结束；
+//! 3. 将向量存储落盘后退出。
+//!
+//! 进行中的工作通过 [`ShutdownCoordinator::begin`] / [`ShutdownCoordinator::track`]
+//! 返回的守卫登记，守卫释放时自动注销。
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+use tracing::info;
+
+/// 默认的排空等待时间
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+struct ShutdownState {
+    triggered: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// 关闭协调器，克隆后共享同一状态
+#[derive(Debug, Clone)]
+pub struct ShutdownCoordinator {
+    state: Arc<ShutdownState>,
+}
+
+/// 进行中工作的守卫，释放时注销
+#[derive(Debug)]
+pub struct InFlightGuard {
+    state: Arc<ShutdownState>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.state.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.state.idle.notify_waiters();
+        }
+    }
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(ShutdownState {
+                triggered: watch::channel(false).0,
+                in_flight: AtomicUsize::new(0),
+                idle: Notify::new(),
+            }),
+        }
+    }
+
+    /// 开始关闭，重复调用无副作用
+    pub fn trigger(&self) {
+        self.state.triggered.send_if_modified(|triggered| !std::mem::replace(triggered, true));
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.state.triggered.borrow()
+    }
+
+    /// 等待关闭开始
+    pub async fn triggered(&self) {
+        let mut receiver = self.state.triggered.subscribe();
+        // 发送端由自身持有，不会关闭
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+
+    /// 登记一个新请求；关闭开始后返回 None，调用方应拒绝该请求
+    pub fn begin(&self) -> Option<InFlightGuard> {
+        if self.is_shutting_down() {
+            return None;
+        }
+        Some(self.track())
+    }
+
+    /// 登记一项后台工作（关闭开始后仍可登记，例如已排队任务的收尾）
+    pub fn track(&self) -> InFlightGuard {
+        self.state.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard { state: Arc::clone(&self.state) }
+    }
+
+    /// 进行中的工作数
+    pub fn in_flight(&self) -> usize {
+        self.state.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// 等待进行中的工作全部结束，超过 `timeout` 时返回 false
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        let wait_idle = async {
+            loop {
+                let idle = self.state.idle.notified();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+        tokio::time::timeout(timeout, wait_idle).await.is_ok()
+    }
+}
+
+/// 等待 SIGINT（Ctrl-C）或 SIGTERM
+pub async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => info!("🛑 收到 SIGINT"),
+                    _ = terminate.recv() => info!("🛑 收到 SIGTERM"),
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("无法监听 SIGTERM，仅处理 Ctrl-C: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+    info!("🛑 收到 Ctrl-C");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rejects_new_work_after_trigger() {
+        let shutdown = ShutdownCoordinator::new();
+        let guard = shutdown.begin().expect("关闭前应接受请求");
+        assert_eq!(shutdown.in_flight(), 1);
+
+        shutdown.trigger();
+        shutdown.triggered().await;
+        assert!(shutdown.begin().is_none());
+        // 后台收尾仍可登记
+        drop(shutdown.track());
+
+        drop(guard);
+        assert_eq!(shutdown.in_flight(), 0);
+        assert!(shutdown.drain(Duration::from_millis(10)).await);
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight_work() {
+        let shutdown = ShutdownCoordinator::new();
+        let guard = shutdown.track();
+        assert!(!shutdown.drain(Duration::from_millis(20)).await);
+
+        let draining = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.drain(Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(guard);
+        assert!(draining.await.unwrap());
+    }
+}
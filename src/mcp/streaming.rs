@@ -0,0 +1,204 @@
+//! 工具结果分块推送
+//!
+//! 客户端在 `tools/call` 参数的 `_meta.progressToken` 中提供令牌时启用：结果列表按
+//! `_meta.chunkSize`（默认 10）拆分，每块以 `notifications/tools/partialResult` 通知
+//! 推送，最终响应中的 `results` 为空数组，并附带 `streamed` 统计。
+//! 未提供令牌的调用保持原来的一次性返回。
+
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// 部分结果通知的方法名
+pub const PARTIAL_RESULT_METHOD: &str = "notifications/tools/partialResult";
+
+/// 进度通知的方法名（MCP 标准通知）
+pub const PROGRESS_METHOD: &str = "notifications/progress";
+
+/// 默认每块的结果数
+pub const DEFAULT_CHUNK_SIZE: usize = 10;
+
+/// 部分结果的发送端，传输层持有对应的接收端并按顺序写出通知
+pub type PartialResultSender = mpsc::UnboundedSender<Value>;
+
+/// 一次工具调用的部分结果输出
+#[derive(Debug, Clone)]
+pub struct PartialResultSink {
+    progress_token: Value,
+    chunk_size: usize,
+    sender: PartialResultSender,
+    sent_chunks: Arc<AtomicUsize>,
+    sent_items: Arc<AtomicUsize>,
+}
+
+impl PartialResultSink {
+    pub fn new(progress_token: Value, chunk_size: usize, sender: PartialResultSender) -> Self {
+        Self {
+            progress_token,
+            chunk_size: chunk_size.max(1),
+            sender,
+            sent_chunks: Arc::new(AtomicUsize::new(0)),
+            sent_items: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// 根据 `tools/call` 参数的 `_meta` 创建；未提供 progressToken 时返回 None
+    pub fn from_call_params(params: &Value, sender: &PartialResultSender) -> Option<Self> {
+        let meta = params.get("_meta")?;
+        let token = meta.get("progressToken").filter(|t| t.is_string() || t.is_number())?;
+        let chunk_size = meta
+            .get("chunkSize")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_CHUNK_SIZE, |v| v as usize);
+        Some(Self::new(token.clone(), chunk_size, sender.clone()))
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// 已推送的块数
+    pub fn sent_chunks(&self) -> usize {
+        self.sent_chunks.load(Ordering::SeqCst)
+    }
+
+    /// 已推送的结果条数
+    pub fn sent_items(&self) -> usize {
+        self.sent_items.load(Ordering::SeqCst)
+    }
+
+    /// 推送一块结果；客户端连接已断开时返回 false
+    pub fn send(&self, items: Vec<Value>) -> bool {
+        if items.is_empty() {
+            return true;
+        }
+        let sequence = self.sent_chunks.fetch_add(1, Ordering::SeqCst);
+        self.sent_items.fetch_add(items.len(), Ordering::SeqCst);
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": PARTIAL_RESULT_METHOD,
+            "params": {
+                "progressToken": self.progress_token,
+                "sequence": sequence,
+                "results": items,
+            }
+        });
+        self.sender.send(notification).is_ok()
+    }
+
+    /// 推送一条进度通知，用于耗时较长、暂时没有结果可推送的阶段
+    pub fn send_progress(&self, progress: f64, total: Option<f64>, message: &str) -> bool {
+        let mut params = json!({
+            "progressToken": self.progress_token,
+            "progress": progress,
+            "message": message,
+        });
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": PROGRESS_METHOD,
+            "params": params,
+        });
+        self.sender.send(notification).is_ok()
+    }
+
+    /// 按块大小依次推送结果列表
+    pub fn send_all(&self, items: Vec<Value>) -> bool {
+        let mut items = items.into_iter().peekable();
+        while items.peek().is_some() {
+            let chunk: Vec<Value> = items.by_ref().take(self.chunk_size).collect();
+            if !self.send(chunk) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 把结果对象中的 `results` 列表分块推送，原位置留下空数组和 `streamed` 统计
+    ///
+    /// 没有 `results` 列表的结果原样返回。
+    pub fn stream_results(&self, mut result: Value) -> Value {
+        let Some(items) = result.get_mut("results").and_then(Value::as_array_mut) else {
+            return result;
+        };
+        let items = std::mem::take(items);
+        self.send_all(items);
+        result["streamed"] = self.summary();
+        result
+    }
+
+    /// 最终响应中的推送统计
+    pub fn summary(&self) -> Value {
+        json!({
+            "progressToken": self.progress_token,
+            "chunks": self.sent_chunks(),
+            "items": self.sent_items(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sink_requires_progress_token() {
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        assert!(PartialResultSink::from_call_params(&json!({"name": "search_docs"}), &sender).is_none());
+
+        let sink = PartialResultSink::from_call_params(
+            &json!({"name": "search_docs", "_meta": {"progressToken": "t-1", "chunkSize": 2}}),
+            &sender,
+        )
+        .unwrap();
+        assert_eq!(sink.chunk_size(), 2);
+    }
+
+    #[test]
+    fn test_stream_results_in_chunks() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let sink = PartialResultSink::new(json!(7), 2, sender);
+
+        let result = sink.stream_results(json!({
+            "results": [{"title": "a"}, {"title": "b"}, {"title": "c"}],
+            "total_hits": 3,
+        }));
+        assert_eq!(result["results"], json!([]));
+        assert_eq!(result["total_hits"], 3);
+        assert_eq!(result["streamed"]["chunks"], 2);
+        assert_eq!(result["streamed"]["items"], 3);
+
+        let first = receiver.try_recv().unwrap();
+        assert_eq!(first["method"], PARTIAL_RESULT_METHOD);
+        assert_eq!(first["params"]["progressToken"], 7);
+        assert_eq!(first["params"]["sequence"], 0);
+        assert_eq!(first["params"]["results"].as_array().unwrap().len(), 2);
+        let second = receiver.try_recv().unwrap();
+        assert_eq!(second["params"]["sequence"], 1);
+        assert_eq!(second["params"]["results"], json!([{"title": "c"}]));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_progress_notification() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let sink = PartialResultSink::new(json!("t"), 10, sender);
+        assert!(sink.send_progress(3.0, Some(60.0), "抓取中"));
+        let notification = receiver.try_recv().unwrap();
+        assert_eq!(notification["method"], PROGRESS_METHOD);
+        assert_eq!(notification["params"]["total"], 60.0);
+        assert_eq!(sink.sent_chunks(), 0);
+    }
+
+    #[test]
+    fn test_results_without_list_are_unchanged() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let sink = PartialResultSink::new(json!("t"), 10, sender);
+        let result = sink.stream_results(json!({"status": "success"}));
+        assert_eq!(result, json!({"status": "success"}));
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,417 @@
+//! WebSocket 传输
+//!
+//! 每个 WebSocket 连接对应一个独立的 [`Server`] 会话（各自的 initialize 状态），
+//! 所有连接共享同一个 [`MCPServer`] 工具集，适合多个代理连接同一个长期运行的实例。
+//! 每个文本帧是一条 JSON-RPC 消息或一个批量数组，响应以文本帧返回；
+//! 服务器通知通过 [`WsTransportState::broadcast_notification`] 推送到所有连接。
+//!
+//! 监听地址、TLS、Origin 校验和 API 密钥复用 [`HttpTransportConfig`]，Bearer 令牌在
+//! 升级请求中校验，之后该连接的工具调用按密钥的授权范围检查。服务器开始关闭时
+//! （见 [`super::shutdown`]）各连接处理完当前消息后收到关闭帧（1001 Going Away），
+//! 等待连接结束后退出。
+//!
+//! 客户端声明采样能力后，工具发起的 `sampling/createMessage` 请求（见 [`super::sampling`]）
+//! 以文本帧发给客户端，处理当前消息期间收到的客户端响应直接交给等待中的请求。
+
+use anyhow::{anyhow, Result};
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response as HttpResponse};
+use axum::routing::get;
+use axum::Router;
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, watch, RwLock};
+use tracing::{debug, info, warn};
+
+use crate::config::HttpTransportConfig;
+use super::auth::{AuthContext, Authenticator};
+use super::http::{dispatch_messages, origin_allowed};
+use super::resources::{next_update, ResourceNotifier};
+use super::sampling;
+use super::server::{MCPServer, Server};
+
+/// 关闭码：服务器正在关闭
+const CLOSE_GOING_AWAY: u16 = 1001;
+/// 关闭码：消息无法解析
+const CLOSE_UNSUPPORTED_DATA: u16 = 1003;
+
+/// WebSocket 传输共享状态
+#[derive(Clone)]
+pub struct WsTransportState {
+    name: String,
+    version: String,
+    mcp_server: Arc<RwLock<MCPServer>>,
+    config: Arc<HttpTransportConfig>,
+    connections: Arc<AtomicUsize>,
+    notifications: broadcast::Sender<String>,
+    shutdown: watch::Sender<bool>,
+    /// API 密钥校验器，未配置密钥时为 None
+    auth: Option<Arc<Authenticator>>,
+}
+
+impl WsTransportState {
+    pub fn new(name: String, version: String, mcp_server: Arc<RwLock<MCPServer>>, config: HttpTransportConfig) -> Self {
+        let (notifications, _) = broadcast::channel(64);
+        let (shutdown, _) = watch::channel(false);
+        Self {
+            name,
+            version,
+            mcp_server,
+            auth: Authenticator::from_config(&config.api_keys).map(Arc::new),
+            config: Arc::new(config),
+            connections: Arc::new(AtomicUsize::new(0)),
+            notifications,
+            shutdown,
+        }
+    }
+
+    /// 当前活动连接数
+    pub fn connection_count(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// 向所有连接推送通知
+    pub fn broadcast_notification(&self, notification: &Value) {
+        // 没有连接时发送失败，忽略即可
+        let _ = self.notifications.send(notification.to_string());
+    }
+
+    /// 通知所有连接关闭
+    pub fn shutdown(&self) {
+        let _ = self.shutdown.send(true);
+    }
+
+    /// 等待所有连接结束，最多等待 `timeout`
+    async fn drain(&self, timeout: Duration) {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.connection_count() > 0 && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        if self.connection_count() > 0 {
+            warn!("⚠️ 仍有 {} 个WebSocket连接未结束，强制退出", self.connection_count());
+        }
+    }
+}
+
+/// 连接计数守卫，连接任务结束（包括异常退出）时递减
+struct ConnectionGuard(Arc<AtomicUsize>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 解析一个文本帧，返回消息列表和是否为批量
+fn parse_frame(text: &str) -> Result<(Vec<Value>, bool)> {
+    match serde_json::from_str(text)? {
+        Value::Array(items) => Ok((items, true)),
+        value @ Value::Object(_) => Ok((vec![value], false)),
+        _ => Err(anyhow!("消息必须是JSON对象或数组")),
+    }
+}
+
+/// 处理一个文本帧，返回需要回写的文本（只有通知时为 None）
+async fn handle_text(server: &mut Server, text: &str) -> Option<String> {
+    let (messages, is_batch) = match parse_frame(text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let error = json!({
+                "jsonrpc": "2.0",
+                "id": Value::Null,
+                "error": { "code": -32700, "message": format!("Parse error: {}", e) },
+            });
+            return Some(error.to_string());
+        }
+    };
+    let mut responses = dispatch_messages(server, messages).await;
+    if responses.is_empty() {
+        return None;
+    }
+    let payload = if is_batch {
+        serde_json::to_string(&responses)
+    } else {
+        serde_json::to_string(&responses.remove(0))
+    };
+    payload.ok()
+}
+
+async fn handle_upgrade(State(state): State<WsTransportState>, headers: HeaderMap, upgrade: WebSocketUpgrade) -> HttpResponse {
+    if !origin_allowed(&state.config, &headers) {
+        return (StatusCode::FORBIDDEN, "Origin 不被允许").into_response();
+    }
+    let auth = match &state.auth {
+        Some(authenticator) => {
+            let authorization = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+            match authenticator.authenticate(authorization) {
+                Ok(principal) => Some((authenticator.clone(), principal)),
+                Err(failure) => {
+                    return (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")], format!("认证失败: {}", failure.as_str()))
+                        .into_response();
+                }
+            }
+        }
+        None => None,
+    };
+    upgrade.on_upgrade(move |socket| handle_socket(state, socket, auth))
+}
+
+/// 单个连接的生命周期：读取请求、回写响应、转发通知，直到任一方关闭
+async fn handle_socket(state: WsTransportState, socket: WebSocket, auth: Option<AuthContext>) {
+    state.connections.fetch_add(1, Ordering::SeqCst);
+    let _guard = ConnectionGuard(state.connections.clone());
+    let connection_id = uuid::Uuid::new_v4().simple().to_string();
+    info!("🔗 新建WebSocket连接: {}", connection_id);
+
+    let (mut sink, mut stream) = socket.split();
+    let (outgoing, mut outgoing_rx) = mpsc::channel::<Message>(64);
+    let mut server = Server::with_shared(state.name.clone(), state.version.clone(), state.mcp_server.clone());
+    let (partial_sender, mut partial_receiver) = mpsc::unbounded_channel::<Value>();
+    let sampling = server.enable_sampling(partial_sender.clone());
+    server.set_partial_result_sender(Some(partial_sender));
+    // 处理请求期间收到的其他帧，按顺序留到之后处理
+    let mut backlog: VecDeque<Message> = VecDeque::new();
+    server.set_auth_context(auth);
+
+    // 资源更新按本连接的订阅过滤
+    let subscriptions = server.resource_subscriptions();
+    let resources = state.mcp_server.read().await.resources();
+    let mut notifier = resources.as_deref().map(ResourceNotifier::new).transpose().unwrap_or_else(|e| {
+        warn!("读取已缓存包版本失败，本连接不转发资源通知: {}", e);
+        None
+    });
+    let mut updates = resources.map(|r| r.subscribe_updates());
+
+    // 写任务：串行化响应、通知和关闭帧
+    let mut notifications = state.notifications.subscribe();
+    let writer = tokio::spawn(async move {
+        loop {
+            let message = tokio::select! {
+                message = outgoing_rx.recv() => match message {
+                    Some(message) => message,
+                    None => break,
+                },
+                notification = notifications.recv() => match notification {
+                    Ok(text) => Message::Text(text),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("WebSocket通知落后，丢弃 {} 条通知", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => continue,
+                },
+                update = next_update(&mut updates) => {
+                    if let Some(notifier) = notifier.as_mut() {
+                        let (uri, list_changed) = notifier.observe(&update);
+                        let subscribed = subscriptions.lock().clone();
+                        for notification in ResourceNotifier::notifications(&uri, list_changed, &subscribed) {
+                            if sink.send(Message::Text(notification.to_string())).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    continue;
+                }
+            };
+            let is_close = matches!(message, Message::Close(_));
+            if sink.send(message).await.is_err() || is_close {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let mut shutdown = state.shutdown.subscribe();
+    let idle_timeout = Duration::from_secs(state.config.session_idle_timeout_secs);
+
+    loop {
+        let frame = if let Some(message) = backlog.pop_front() {
+            Some(Ok(message))
+        } else {
+            tokio::select! {
+                _ = shutdown.changed() => {
+                    let _ = outgoing.send(close_frame(CLOSE_GOING_AWAY, "服务器正在关闭")).await;
+                    break;
+                }
+                frame = tokio::time::timeout(idle_timeout, stream.next()) => match frame {
+                    Ok(frame) => frame,
+                    Err(_) => {
+                        info!("⏱️ WebSocket连接空闲超时: {}", connection_id);
+                        let _ = outgoing.send(close_frame(CLOSE_GOING_AWAY, "空闲超时")).await;
+                        break;
+                    }
+                },
+            }
+        };
+
+        match frame {
+            Some(Ok(Message::Text(text))) => {
+                if sampling::route_client_response(Some(&sampling), &text) {
+                    continue;
+                }
+                debug!("WebSocket请求 [{}]: {} 字节", connection_id, text.len());
+                // 处理期间产生的部分结果和采样请求先于响应写出，客户端的采样响应在处理期间读取
+                let handling = handle_text(&mut server, &text);
+                tokio::pin!(handling);
+                let mut stream_ended = false;
+                let reply = loop {
+                    tokio::select! {
+                        reply = &mut handling => break reply,
+                        Some(notification) = partial_receiver.recv() => {
+                            let _ = outgoing.send(Message::Text(notification.to_string())).await;
+                        }
+                        frame = stream.next(), if !stream_ended => match frame {
+                            Some(Ok(Message::Text(text))) if sampling::route_client_response(Some(&sampling), &text) => {}
+                            Some(Ok(message)) => backlog.push_back(message),
+                            Some(Err(_)) | None => {
+                                stream_ended = true;
+                                backlog.push_back(Message::Close(None));
+                            }
+                        },
+                    }
+                };
+                while let Ok(notification) = partial_receiver.try_recv() {
+                    let _ = outgoing.send(Message::Text(notification.to_string())).await;
+                }
+                if let Some(reply) = reply {
+                    if outgoing.send(Message::Text(reply)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            Some(Ok(Message::Binary(_))) => {
+                let _ = outgoing.send(close_frame(CLOSE_UNSUPPORTED_DATA, "只支持文本帧")).await;
+                break;
+            }
+            Some(Ok(Message::Ping(payload))) => {
+                let _ = outgoing.send(Message::Pong(payload)).await;
+            }
+            Some(Ok(Message::Pong(_))) => {}
+            Some(Ok(Message::Close(_))) | None => break,
+            Some(Err(e)) => {
+                warn!("WebSocket连接 {} 出错: {}", connection_id, e);
+                break;
+            }
+        }
+    }
+
+    // 关闭发送通道后等待写任务把剩余消息和关闭帧写完
+    drop(outgoing);
+    let _ = writer.await;
+    info!("🔌 WebSocket连接已结束: {}", connection_id);
+}
+
+fn close_frame(code: u16, reason: &str) -> Message {
+    Message::Close(Some(CloseFrame { code, reason: reason.to_string().into() }))
+}
+
+/// 构建 WebSocket 路由
+pub fn router(state: WsTransportState) -> Router {
+    let endpoint = state.config.endpoint_path.clone();
+    Router::new()
+        .route(&endpoint, get(handle_upgrade))
+        .route("/health", get(|| async { "ok" }))
+        .with_state(state)
+}
+
+/// 启动 WebSocket 传输并阻塞直到服务器开始关闭且连接全部关闭
+pub async fn serve(state: WsTransportState) -> Result<()> {
+    let config = state.config.clone();
+    let addr: SocketAddr = config
+        .bind_address
+        .parse()
+        .map_err(|e| anyhow!("无效的监听地址 {}: {}", config.bind_address, e))?;
+    if config.tls.is_none() && !addr.ip().is_loopback() {
+        warn!("⚠️ WebSocket传输监听在非本机地址 {} 且未启用TLS，请求内容将以明文传输", addr);
+    }
+
+    let app = router(state.clone());
+    let shutdown_state = state.clone();
+    let coordinator = state.mcp_server.read().await.shutdown();
+    let shutdown_signal = async move {
+        coordinator.triggered().await;
+        info!("🛑 服务器正在关闭，正在关闭 {} 个WebSocket连接", shutdown_state.connection_count());
+        shutdown_state.shutdown();
+        shutdown_state.drain(Duration::from_secs(10)).await;
+    };
+
+    match &config.tls {
+        Some(tls) => {
+            let rustls = axum_server::tls_rustls::RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+                .await
+                .map_err(|e| anyhow!("加载TLS证书失败 ({}, {}): {}", tls.cert_path, tls.key_path, e))?;
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown_signal.await;
+                shutdown_handle.graceful_shutdown(Some(Duration::from_secs(1)));
+            });
+            info!("🔒 MCP WebSocket 传输已启动: wss://{}{}", addr, config.endpoint_path);
+            axum_server::bind_rustls(addr, rustls).handle(handle).serve(app.into_make_service()).await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            info!("🌐 MCP WebSocket 传输已启动: ws://{}{}", addr, config.endpoint_path);
+            axum::serve(listener, app).with_graceful_shutdown(shutdown_signal).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_state() -> WsTransportState {
+        WsTransportState::new(
+            "test".to_string(),
+            "1.0.0".to_string(),
+            Arc::new(RwLock::new(MCPServer::new())),
+            HttpTransportConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_parse_frame() {
+        let (messages, is_batch) = parse_frame(r#"{"jsonrpc":"2.0","id":"1","method":"ping"}"#).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(!is_batch);
+
+        let (messages, is_batch) = parse_frame(r#"[{"id":"1"},{"id":"2"}]"#).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(is_batch);
+
+        assert!(parse_frame("42").is_err());
+        assert!(parse_frame("not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_text_notifications_and_errors() {
+        let state = test_state();
+        let mut server = Server::with_shared(state.name.clone(), state.version.clone(), state.mcp_server.clone());
+
+        // 通知不产生响应
+        assert!(handle_text(&mut server, r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#).await.is_none());
+
+        let reply = handle_text(&mut server, "{broken").await.unwrap();
+        let reply: Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["error"]["code"], -32700);
+    }
+
+    #[test]
+    fn test_connection_guard() {
+        let state = test_state();
+        state.connections.fetch_add(1, Ordering::SeqCst);
+        {
+            let _guard = ConnectionGuard(state.connections.clone());
+            assert_eq!(state.connection_count(), 1);
+        }
+        assert_eq!(state.connection_count(), 0);
+    }
+}
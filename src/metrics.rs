@@ -42,6 +42,54 @@ pub struct PerformanceMetrics {
     pub memory_usage_mb: f64,
     pub disk_usage_mb: f64,
     pub error_rate: f64,
+    /// 累计过期删除的文档数
+    #[serde(default)]
+    pub expired_documents: u64,
+    /// 累计因容量上限淘汰的文档数
+    #[serde(default)]
+    pub evicted_documents: u64,
+    /// 累计淘汰释放的字节数
+    #[serde(default)]
+    pub evicted_bytes: u64,
+    /// 查询结果缓存命中次数
+    #[serde(default)]
+    pub query_cache_hits: u64,
+    /// 查询结果缓存未命中次数
+    #[serde(default)]
+    pub query_cache_misses: u64,
+    /// 已完成的后台维护次数
+    #[serde(default)]
+    pub maintenance_runs: u64,
+    /// 因不空闲而跳过的后台维护次数
+    #[serde(default)]
+    pub maintenance_skipped: u64,
+    /// 维护重建索引时清除的已删除向量数
+    #[serde(default)]
+    pub pruned_vectors: u64,
+    /// 最近一次维护的耗时
+    #[serde(default)]
+    pub last_maintenance_ms: f64,
+    /// 最近一次查询或写入距今的秒数
+    #[serde(default)]
+    pub idle_seconds: f64,
+    /// 成功的嵌入请求数
+    #[serde(default)]
+    pub embedding_requests: u64,
+    /// 已嵌入的文本数
+    #[serde(default)]
+    pub embedded_texts: u64,
+    /// 嵌入请求被限流（429）的次数
+    #[serde(default)]
+    pub embedding_rate_limited: u64,
+    /// 嵌入请求的重试次数（含限流）
+    #[serde(default)]
+    pub embedding_retries: u64,
+    /// 按请求耗时计算的嵌入吞吐量（文本/秒）
+    #[serde(default)]
+    pub embedding_texts_per_second: f64,
+    /// 当前的嵌入批大小
+    #[serde(default)]
+    pub embedding_batch_size: u64,
 }
 
 impl Default for PerformanceMetrics {
@@ -58,6 +106,22 @@ impl Default for PerformanceMetrics {
             memory_usage_mb: 0.0,
             disk_usage_mb: 0.0,
             error_rate: 0.0,
+            expired_documents: 0,
+            evicted_documents: 0,
+            evicted_bytes: 0,
+            query_cache_hits: 0,
+            query_cache_misses: 0,
+            maintenance_runs: 0,
+            maintenance_skipped: 0,
+            pruned_vectors: 0,
+            last_maintenance_ms: 0.0,
+            idle_seconds: 0.0,
+            embedding_requests: 0,
+            embedded_texts: 0,
+            embedding_rate_limited: 0,
+            embedding_retries: 0,
+            embedding_texts_per_second: 0.0,
+            embedding_batch_size: 0,
         }
     }
 }
@@ -178,6 +242,7 @@ impl QpsCalculator {
 pub struct MetricsCollector {
     query_times: Arc<RwLock<QueryTimeStats>>,
     cache_stats: Arc<CacheStats>,
+    query_cache_stats: Arc<CacheStats>,
     qps_calculator: Arc<RwLock<QpsCalculator>>,
     total_queries: AtomicU64,
     total_documents: AtomicU64,
@@ -185,6 +250,21 @@ pub struct MetricsCollector {
     index_build_time: AtomicF64,
     memory_usage: AtomicF64,
     disk_usage: AtomicF64,
+    expired_documents: AtomicU64,
+    evicted_documents: AtomicU64,
+    evicted_bytes: AtomicU64,
+    maintenance_runs: AtomicU64,
+    maintenance_skipped: AtomicU64,
+    pruned_vectors: AtomicU64,
+    last_maintenance_ms: AtomicF64,
+    last_activity: RwLock<Instant>,
+    embedding_requests: AtomicU64,
+    embedded_texts: AtomicU64,
+    embedding_rate_limited: AtomicU64,
+    embedding_retries: AtomicU64,
+    /// 成功的嵌入请求累计耗时
+    embedding_time_ms: AtomicF64,
+    embedding_batch_size: AtomicU64,
 }
 
 impl MetricsCollector {
@@ -192,6 +272,7 @@ impl MetricsCollector {
         Self {
             query_times: Arc::new(RwLock::new(QueryTimeStats::new(10000))),
             cache_stats: Arc::new(CacheStats::new()),
+            query_cache_stats: Arc::new(CacheStats::new()),
             qps_calculator: Arc::new(RwLock::new(QpsCalculator::new(Duration::from_secs(60)))),
             total_queries: AtomicU64::new(0),
             total_documents: AtomicU64::new(0),
@@ -199,6 +280,20 @@ impl MetricsCollector {
             index_build_time: AtomicF64::new(0.0),
             memory_usage: AtomicF64::new(0.0),
             disk_usage: AtomicF64::new(0.0),
+            expired_documents: AtomicU64::new(0),
+            evicted_documents: AtomicU64::new(0),
+            evicted_bytes: AtomicU64::new(0),
+            maintenance_runs: AtomicU64::new(0),
+            maintenance_skipped: AtomicU64::new(0),
+            pruned_vectors: AtomicU64::new(0),
+            last_maintenance_ms: AtomicF64::new(0.0),
+            last_activity: RwLock::new(Instant::now()),
+            embedding_requests: AtomicU64::new(0),
+            embedded_texts: AtomicU64::new(0),
+            embedding_rate_limited: AtomicU64::new(0),
+            embedding_retries: AtomicU64::new(0),
+            embedding_time_ms: AtomicF64::new(0.0),
+            embedding_batch_size: AtomicU64::new(0),
         }
     }
 
@@ -208,6 +303,47 @@ impl MetricsCollector {
         self.query_times.write().add_time(time_ms);
         self.qps_calculator.write().record_query();
         self.total_queries.fetch_add(1, Ordering::Relaxed);
+        *self.last_activity.write() = Instant::now();
+    }
+
+    /// 距最近一次查询或写入的时间
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity.read().elapsed()
+    }
+
+    /// 记录一次后台维护：清除的已删除向量数和耗时
+    pub fn record_maintenance(&self, pruned_vectors: u64, time_ms: f64) {
+        self.maintenance_runs.fetch_add(1, Ordering::Relaxed);
+        self.pruned_vectors.fetch_add(pruned_vectors, Ordering::Relaxed);
+        self.last_maintenance_ms.store(time_ms, Ordering::Relaxed);
+    }
+
+    /// 记录一次因不空闲而跳过的后台维护
+    pub fn record_maintenance_skipped(&self) {
+        self.maintenance_skipped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一次成功的嵌入请求：文本数和耗时
+    pub fn record_embedding_batch(&self, texts: u64, time_ms: f64) {
+        self.embedding_requests.fetch_add(1, Ordering::Relaxed);
+        self.embedded_texts.fetch_add(texts, Ordering::Relaxed);
+        let total = self.embedding_time_ms.load(Ordering::Relaxed);
+        self.embedding_time_ms.store(total + time_ms, Ordering::Relaxed);
+    }
+
+    /// 记录一次被限流的嵌入请求
+    pub fn record_embedding_rate_limited(&self) {
+        self.embedding_rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一次嵌入请求重试
+    pub fn record_embedding_retry(&self) {
+        self.embedding_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 更新当前的嵌入批大小
+    pub fn update_embedding_batch_size(&self, size: u64) {
+        self.embedding_batch_size.store(size, Ordering::Relaxed);
     }
 
     /// 记录缓存命中
@@ -220,6 +356,16 @@ impl MetricsCollector {
         self.cache_stats.record_miss();
     }
 
+    /// 记录查询结果缓存命中
+    pub fn record_query_cache_hit(&self) {
+        self.query_cache_stats.record_hit();
+    }
+
+    /// 记录查询结果缓存未命中
+    pub fn record_query_cache_miss(&self) {
+        self.query_cache_stats.record_miss();
+    }
+
     /// 记录错误
     pub fn record_error(&self) {
         self.total_errors.fetch_add(1, Ordering::Relaxed);
@@ -235,6 +381,13 @@ impl MetricsCollector {
         self.total_documents.store(count, Ordering::Relaxed);
     }
 
+    /// 记录一次保留清理：过期删除和容量淘汰的文档数、淘汰释放的字节数
+    pub fn record_retention(&self, expired: u64, evicted: u64, evicted_bytes: u64) {
+        self.expired_documents.fetch_add(expired, Ordering::Relaxed);
+        self.evicted_documents.fetch_add(evicted, Ordering::Relaxed);
+        self.evicted_bytes.fetch_add(evicted_bytes, Ordering::Relaxed);
+    }
+
     /// 更新内存使用量
     pub fn update_memory_usage(&self, mb: f64) {
         self.memory_usage.store(mb, Ordering::Relaxed);
@@ -257,6 +410,14 @@ impl MetricsCollector {
             0.0
         };
 
+        let embedded_texts = self.embedded_texts.load(Ordering::Relaxed);
+        let embedding_time_ms = self.embedding_time_ms.load(Ordering::Relaxed);
+        let embedding_texts_per_second = if embedding_time_ms > 0.0 {
+            embedded_texts as f64 * 1000.0 / embedding_time_ms
+        } else {
+            0.0
+        };
+
         PerformanceMetrics {
             queries_per_second: self.qps_calculator.read().current_qps(),
             average_query_time_ms: query_times.average(),
@@ -269,6 +430,22 @@ impl MetricsCollector {
             memory_usage_mb: self.memory_usage.load(Ordering::Relaxed),
             disk_usage_mb: self.disk_usage.load(Ordering::Relaxed),
             error_rate,
+            expired_documents: self.expired_documents.load(Ordering::Relaxed),
+            evicted_documents: self.evicted_documents.load(Ordering::Relaxed),
+            evicted_bytes: self.evicted_bytes.load(Ordering::Relaxed),
+            query_cache_hits: self.query_cache_stats.hits.load(Ordering::Relaxed),
+            query_cache_misses: self.query_cache_stats.misses.load(Ordering::Relaxed),
+            maintenance_runs: self.maintenance_runs.load(Ordering::Relaxed),
+            maintenance_skipped: self.maintenance_skipped.load(Ordering::Relaxed),
+            pruned_vectors: self.pruned_vectors.load(Ordering::Relaxed),
+            last_maintenance_ms: self.last_maintenance_ms.load(Ordering::Relaxed),
+            idle_seconds: self.idle_for().as_secs_f64(),
+            embedding_requests: self.embedding_requests.load(Ordering::Relaxed),
+            embedded_texts,
+            embedding_rate_limited: self.embedding_rate_limited.load(Ordering::Relaxed),
+            embedding_retries: self.embedding_retries.load(Ordering::Relaxed),
+            embedding_texts_per_second,
+            embedding_batch_size: self.embedding_batch_size.load(Ordering::Relaxed),
         }
     }
 
@@ -281,10 +458,24 @@ impl MetricsCollector {
         self.index_build_time.store(0.0, Ordering::Relaxed);
         self.memory_usage.store(0.0, Ordering::Relaxed);
         self.disk_usage.store(0.0, Ordering::Relaxed);
+        self.expired_documents.store(0, Ordering::Relaxed);
+        self.evicted_documents.store(0, Ordering::Relaxed);
+        self.evicted_bytes.store(0, Ordering::Relaxed);
+        self.maintenance_runs.store(0, Ordering::Relaxed);
+        self.maintenance_skipped.store(0, Ordering::Relaxed);
+        self.pruned_vectors.store(0, Ordering::Relaxed);
+        self.last_maintenance_ms.store(0.0, Ordering::Relaxed);
+        self.embedding_requests.store(0, Ordering::Relaxed);
+        self.embedded_texts.store(0, Ordering::Relaxed);
+        self.embedding_rate_limited.store(0, Ordering::Relaxed);
+        self.embedding_retries.store(0, Ordering::Relaxed);
+        self.embedding_time_ms.store(0.0, Ordering::Relaxed);
         
         // 重置缓存统计
         self.cache_stats.hits.store(0, Ordering::Relaxed);
         self.cache_stats.misses.store(0, Ordering::Relaxed);
+        self.query_cache_stats.hits.store(0, Ordering::Relaxed);
+        self.query_cache_stats.misses.store(0, Ordering::Relaxed);
         
         // 重置QPS计算器
         self.qps_calculator.write().query_times.clear();
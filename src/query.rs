@@ -1,33 +1,408 @@
 use crate::{
     types::*, 
     config::VectorDbConfig, 
-    storage::VectorStore, 
+    storage::{VectorStore, SCAN_BATCH_SIZE}, 
     index::HnswIndex,
     metrics::{MetricsCollector, QueryTimer},
-    errors::{Result, VectorDbError}
+    errors::{Result, VectorDbError},
+    tools::search_filter::SearchFilter,
+    vectorization::sparse::{self, LexicalSparseEncoder, SparseEncoder, SparseIndex},
 };
+use futures::TryStreamExt;
+use parking_lot::Mutex;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// 只被稀疏检索命中、从存储补入结果的文档摘要长度（字符）
+const SPARSE_SNIPPET_CHARS: usize = 200;
 
 /// 查询引擎
 pub struct QueryEngine {
     config: VectorDbConfig,
     hnsw_index: Arc<HnswIndex>,
     metrics: Arc<MetricsCollector>,
+    result_cache: QueryResultCache,
+    /// 稀疏编码器（`sparse.enabled` 时使用）
+    sparse_encoder: Box<dyn SparseEncoder>,
+    /// 稀疏倒排索引，写入后失效，下次检索时按存储重建
+    sparse_index: Mutex<Option<Arc<SparseIndex>>>,
+    /// 索引内容的版本号，每次失效加一；重建期间有写入时不保存重建结果
+    sparse_generation: AtomicU64,
 }
 
 impl QueryEngine {
     pub fn new(config: &VectorDbConfig, metrics: Arc<MetricsCollector>) -> Result<Self> {
-        // 创建HNSW索引
+        // 创建HNSW索引（距离度量与分数换算使用同一配置）
         let hnsw_index = Arc::new(HnswIndex::new(
             config.hnsw.clone(),
             config.vector_dimension,
+            config.distance_metric,
         ));
 
         Ok(Self {
             config: config.clone(),
             hnsw_index,
             metrics,
+            result_cache: QueryResultCache::new(
+                config.cache.query_cache_size,
+                Duration::from_secs(config.cache.cache_ttl_seconds),
+                config.cache.query_cache_similarity,
+            ),
+            sparse_encoder: Box::new(LexicalSparseEncoder),
+            sparse_index: Mutex::new(None),
+            sparse_generation: AtomicU64::new(0),
         })
     }
-} 
\ No newline at end of file
+
+    /// 清空查询结果缓存（连同稀疏索引），索引内容变化（写入、删除、重建）后调用
+    pub fn invalidate_result_cache(&self) {
+        self.result_cache.clear();
+        self.sparse_generation.fetch_add(1, Ordering::SeqCst);
+        *self.sparse_index.lock() = None;
+    }
+
+    /// 取得稀疏索引，失效后按存储中的文档（标题和正文）重建；编码只做分词计数，开销远小于嵌入
+    async fn sparse_index(&self, storage: &dyn VectorStore) -> Result<Arc<SparseIndex>> {
+        let cached = self.sparse_index.lock().clone();
+        if let Some(index) = cached {
+            return Ok(index);
+        }
+        let generation = self.sparse_generation.load(Ordering::SeqCst);
+        let mut index = SparseIndex::new();
+        let mut records = storage.scan(SCAN_BATCH_SIZE);
+        while let Some(record) = records.try_next().await? {
+            let vector = self.sparse_encoder.encode_document(&format!("{}\n{}", record.title, record.content));
+            index.insert(record.id, &vector);
+        }
+        let index = Arc::new(index);
+        let mut cached = self.sparse_index.lock();
+        if self.sparse_generation.load(Ordering::SeqCst) == generation {
+            *cached = Some(index.clone());
+        }
+        Ok(index)
+    }
+
+    /// 把稀疏检索的结果融合进稠密结果，按 `sparse.fusion`（RRF 或加权）重新打分排序；未启用时原样返回
+    ///
+    /// 在 `search` 和语义搜索得到稠密结果后调用。只被稀疏检索命中的文档从存储读取后补入结果，
+    /// 因此 `tokio::select!` 这类稠密向量排不到前面的精确标识符也能召回。
+    pub async fn fuse_sparse(
+        &self,
+        storage: &dyn VectorStore,
+        query_text: &str,
+        dense: Vec<SearchResult>,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        if !self.config.sparse.enabled {
+            return Ok(dense);
+        }
+        let query = self.sparse_encoder.encode_query(query_text);
+        if query.is_empty() {
+            return Ok(dense);
+        }
+        let sparse_hits = self.sparse_index(storage).await?.search(&query, limit);
+        let dense_scores: Vec<(String, f32)> = dense.iter().map(|r| (r.document_id.clone(), r.similarity_score)).collect();
+        let fused = sparse::fuse(&dense_scores, &sparse_hits, &self.config.sparse);
+
+        let mut by_id: HashMap<String, SearchResult> = dense.into_iter().map(|r| (r.document_id.clone(), r)).collect();
+        let mut results = Vec::with_capacity(limit.min(fused.len()));
+        for (id, score) in fused {
+            if results.len() >= limit {
+                break;
+            }
+            let result = match by_id.remove(&id) {
+                Some(result) => result,
+                None => match storage.get_document(&id).await? {
+                    Some(record) => SearchResult {
+                        document_id: record.id,
+                        title: record.title,
+                        content_snippet: record.content.chars().take(SPARSE_SNIPPET_CHARS).collect(),
+                        similarity_score: 0.0,
+                        package_name: record.package_name,
+                        doc_type: record.doc_type,
+                        metadata: record.metadata,
+                    },
+                    None => continue,
+                },
+            };
+            results.push(SearchResult { similarity_score: score, ..result });
+        }
+        Ok(results)
+    }
+
+    /// 向量搜索，结果按相似度顺序分批迭代
+    ///
+    /// 适合结果集较大时边取边处理（例如分块推送给客户端），每批最多 `batch_size` 条。
+    /// 只返回满足 `filter` 的结果，语言和版本取自结果元数据的 `language`、`version` 字段。
+    /// 相同或相近的查询向量（余弦相似度达到配置阈值）在缓存有效期内直接复用上次的结果。
+    pub fn search_batches(
+        &self,
+        query_vector: &[f32],
+        limit: usize,
+        batch_size: usize,
+        filter: &SearchFilter,
+    ) -> Result<SearchResultBatches> {
+        let _timer = QueryTimer::new(self.metrics.clone());
+        let filter_key = format!("{:?}", filter);
+        if let Some(results) = self.result_cache.get(query_vector, limit, &filter_key) {
+            self.metrics.record_query_cache_hit();
+            return Ok(SearchResultBatches::new(results, batch_size));
+        }
+        self.metrics.record_query_cache_miss();
+        let results = self.search_uncached(query_vector, limit, filter)?;
+        self.result_cache.insert(query_vector, limit, filter_key, results.clone());
+        Ok(SearchResultBatches::new(results, batch_size))
+    }
+
+    fn search_uncached(&self, query_vector: &[f32], limit: usize, filter: &SearchFilter) -> Result<Vec<SearchResult>> {
+        if filter.is_empty() {
+            return self.hnsw_index.search(query_vector, limit);
+        }
+        // 逐步扩大候选数，直到过滤后结果足够或候选已覆盖整个索引
+        let mut candidates = limit.max(1) * 4;
+        loop {
+            let results = self.hnsw_index.search(query_vector, candidates)?;
+            let exhausted = results.len() < candidates;
+            let mut filtered: Vec<SearchResult> = results.into_iter().filter(|r| matches_filter(filter, r)).collect();
+            if filtered.len() >= limit || exhausted {
+                filtered.truncate(limit);
+                return Ok(filtered);
+            }
+            candidates *= 4;
+        }
+    }
+}
+
+fn matches_filter(filter: &SearchFilter, result: &SearchResult) -> bool {
+    let field = |key: &str| result.metadata.get(key).map(String::as_str).unwrap_or("");
+    filter.matches(field("language"), &result.package_name, field("version"), &result.doc_type, &result.metadata)
+}
+
+/// 缓存中的一次查询
+struct CachedQuery {
+    vector: Vec<f32>,
+    norm: f32,
+    limit: usize,
+    filter_key: String,
+    results: Vec<SearchResult>,
+    inserted_at: Instant,
+}
+
+/// 按查询向量缓存搜索结果
+///
+/// 先按向量、条数和过滤条件的哈希精确匹配；未命中时在条数和过滤条件相同的缓存项中找余弦相似度
+/// 不低于 `similarity_threshold` 的最相近查询。条目超过 `ttl` 后失效，数量超过 `capacity`
+/// 时淘汰最早写入的条目；`capacity` 为 0 时不缓存。
+pub struct QueryResultCache {
+    entries: Mutex<HashMap<u64, CachedQuery>>,
+    capacity: usize,
+    ttl: Duration,
+    similarity_threshold: f32,
+}
+
+impl QueryResultCache {
+    pub fn new(capacity: usize, ttl: Duration, similarity_threshold: f32) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            capacity,
+            ttl,
+            similarity_threshold,
+        }
+    }
+
+    fn key(vector: &[f32], limit: usize, filter_key: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for value in vector {
+            value.to_bits().hash(&mut hasher);
+        }
+        limit.hash(&mut hasher);
+        filter_key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// 查找缓存的结果，顺带清理过期条目
+    pub fn get(&self, vector: &[f32], limit: usize, filter_key: &str) -> Option<Vec<SearchResult>> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut entries = self.entries.lock();
+        let ttl = self.ttl;
+        entries.retain(|_, entry| entry.inserted_at.elapsed() < ttl);
+
+        if let Some(entry) = entries.get(&Self::key(vector, limit, filter_key)) {
+            if entry.vector == vector {
+                return Some(entry.results.clone());
+            }
+        }
+
+        let norm = vector_norm(vector);
+        if norm == 0.0 {
+            return None;
+        }
+        entries
+            .values()
+            .filter(|entry| entry.limit == limit && entry.filter_key == filter_key && entry.vector.len() == vector.len())
+            .map(|entry| (cosine(vector, norm, &entry.vector, entry.norm), entry))
+            .filter(|(similarity, _)| *similarity >= self.similarity_threshold)
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(_, entry)| entry.results.clone())
+    }
+
+    /// 写入一次查询的结果
+    pub fn insert(&self, vector: &[f32], limit: usize, filter_key: String, results: Vec<SearchResult>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = Self::key(vector, limit, &filter_key);
+        let mut entries = self.entries.lock();
+        if !entries.contains_key(&key) && entries.len() >= self.capacity {
+            if let Some(oldest) = entries.iter().min_by_key(|(_, entry)| entry.inserted_at).map(|(key, _)| *key) {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key,
+            CachedQuery {
+                vector: vector.to_vec(),
+                norm: vector_norm(vector),
+                limit,
+                filter_key,
+                results,
+                inserted_at: Instant::now(),
+            },
+        );
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn vector_norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+
+fn cosine(a: &[f32], a_norm: f32, b: &[f32], b_norm: f32) -> f32 {
+    if b_norm == 0.0 {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>() / (a_norm * b_norm)
+}
+
+/// 分批产出搜索结果的迭代器
+pub struct SearchResultBatches {
+    results: std::vec::IntoIter<SearchResult>,
+    batch_size: usize,
+    remaining: usize,
+}
+
+impl SearchResultBatches {
+    pub fn new(results: Vec<SearchResult>, batch_size: usize) -> Self {
+        Self {
+            remaining: results.len(),
+            results: results.into_iter(),
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    /// 尚未产出的结果条数
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl Iterator for SearchResultBatches {
+    type Item = Vec<SearchResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch: Vec<SearchResult> = self.results.by_ref().take(self.batch_size).collect();
+        if batch.is_empty() {
+            return None;
+        }
+        self.remaining -= batch.len();
+        Some(batch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let batches = (self.remaining + self.batch_size - 1) / self.batch_size;
+        (batches, Some(batches))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(id: &str, score: f32) -> SearchResult {
+        SearchResult {
+            document_id: id.to_string(),
+            title: id.to_string(),
+            content_snippet: String::new(),
+            similarity_score: score,
+            package_name: "pkg".to_string(),
+            doc_type: "api".to_string(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_filter_uses_result_metadata() {
+        let mut tokio = result("a", 0.9);
+        tokio.package_name = "tokio".to_string();
+        tokio.metadata.insert("language".to_string(), "rust".to_string());
+        tokio.metadata.insert("version".to_string(), "1.38.0".to_string());
+
+        assert!(matches_filter(&SearchFilter::new().language("rust").version("1.x"), &tokio));
+        assert!(!matches_filter(&SearchFilter::new().version("0.2.x"), &tokio));
+        assert!(!matches_filter(&SearchFilter::new().package_name("serde"), &tokio));
+    }
+
+    #[test]
+    fn test_search_result_batches() {
+        let results = vec![result("a", 0.9), result("b", 0.8), result("c", 0.7)];
+        let mut batches = SearchResultBatches::new(results, 2);
+        assert_eq!(batches.size_hint(), (2, Some(2)));
+
+        let first = batches.next().unwrap();
+        assert_eq!(first.iter().map(|r| r.document_id.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(batches.remaining(), 1);
+        assert_eq!(batches.next().unwrap()[0].document_id, "c");
+        assert!(batches.next().is_none());
+    }
+
+    #[test]
+    fn test_result_cache_matches_exact_and_near_queries() {
+        let cache = QueryResultCache::new(2, Duration::from_secs(60), 0.99);
+        cache.insert(&[1.0, 0.0, 0.0], 5, String::new(), vec![result("a", 0.9)]);
+
+        assert_eq!(cache.get(&[1.0, 0.0, 0.0], 5, "").unwrap()[0].document_id, "a");
+        // 方向几乎相同的查询复用结果，条数或过滤条件不同则不复用
+        assert!(cache.get(&[1.0, 0.01, 0.0], 5, "").is_some());
+        assert!(cache.get(&[1.0, 0.01, 0.0], 10, "").is_none());
+        assert!(cache.get(&[1.0, 0.01, 0.0], 5, "rust").is_none());
+        assert!(cache.get(&[0.0, 1.0, 0.0], 5, "").is_none());
+
+        // 超过容量时淘汰最早的条目
+        cache.insert(&[0.0, 1.0, 0.0], 5, String::new(), vec![result("b", 0.8)]);
+        cache.insert(&[0.0, 0.0, 1.0], 5, String::new(), vec![result("c", 0.7)]);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&[1.0, 0.0, 0.0], 5, "").is_none());
+
+        let expired = QueryResultCache::new(2, Duration::ZERO, 0.99);
+        expired.insert(&[1.0, 0.0, 0.0], 5, String::new(), vec![result("a", 0.9)]);
+        assert!(expired.get(&[1.0, 0.0, 0.0], 5, "").is_none());
+        assert!(expired.is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+//! 向量库的嵌入模型记录
+//!
+//! 换了嵌入模型后，新向量和库中已有向量的维度或语义空间不同，混在同一个索引里打分没有意义。
+//! 数据目录下记录生成现有向量的模型和维度；配置的模型与记录不一致时拒绝写入，
+//! 直到 `reembed_all` 用当前模型重新生成全部向量并更新记录。
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Result;
+
+/// 嵌入模型记录文件名（位于数据目录下，所有集合共用）
+pub const EMBEDDING_META_FILE: &str = "embedding_meta.json";
+
+/// 没有记录的旧数据目录中向量维度与配置不符时，记为未知模型
+pub const UNKNOWN_EMBEDDING_MODEL: &str = "unknown";
+
+/// 生成库中向量的嵌入模型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingMeta {
+    pub model: String,
+    pub dimension: usize,
+    /// 记录时间（首次写入或重新嵌入完成的时间）
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl EmbeddingMeta {
+    pub fn new(model: &str, dimension: usize) -> Self {
+        Self { model: model.to_string(), dimension, recorded_at: Utc::now() }
+    }
+
+    /// 读取数据目录下的记录，文件不存在时返回 None
+    pub fn load(data_dir: &Path) -> Result<Option<Self>> {
+        let path = data_dir.join(EMBEDDING_META_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+    }
+
+    /// 保存到数据目录
+    pub fn save(&self, data_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(data_dir)?;
+        std::fs::write(data_dir.join(EMBEDDING_META_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 记录的模型和维度是否与当前配置一致
+    pub fn matches(&self, model: &str, dimension: usize) -> bool {
+        self.model == model && self.dimension == dimension
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(EmbeddingMeta::load(dir.path()).unwrap().is_none());
+
+        let meta = EmbeddingMeta::new("text-embedding-3-small", 1536);
+        meta.save(dir.path()).unwrap();
+        let loaded = EmbeddingMeta::load(dir.path()).unwrap().unwrap();
+        assert_eq!(loaded, meta);
+        assert!(loaded.matches("text-embedding-3-small", 1536));
+        assert!(!loaded.matches("text-embedding-3-small", 768));
+        assert!(!loaded.matches("bge-small-en-v1.5", 1536));
+    }
+}
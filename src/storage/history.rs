@@ -0,0 +1,126 @@
+//! 文档修订历史
+//!
+//! 更新文档会覆盖内容，这里按文档ID保存被覆盖前的版本（含嵌入向量），用于查看历史、回滚和
+//! 按修订号搜索。每个文档最多保留 `max_revisions` 个修订，超出时丢弃最旧的。历史随集合保存在
+//! 集合数据目录下，删除文档不清除历史，因此删除后仍可回滚恢复。
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Result;
+use crate::types::DocumentRecord;
+
+/// 修订历史文件名（位于集合数据目录下）
+pub const HISTORY_FILE: &str = "document_history.json";
+
+/// 文档的一个历史修订
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentRevision {
+    /// 修订号，同一文档内从 1 开始递增
+    pub revision: u64,
+    /// 被覆盖前的文档记录
+    pub record: DocumentRecord,
+    /// 该修订被新内容替换的时间
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// 按文档ID保存的有界修订历史
+#[derive(Debug)]
+pub struct RevisionHistory {
+    path: PathBuf,
+    max_revisions: usize,
+    revisions: HashMap<String, VecDeque<DocumentRevision>>,
+}
+
+impl RevisionHistory {
+    /// 打开集合数据目录下的修订历史，文件不存在时为空；`max_revisions` 为 0 时不保留历史
+    pub fn open(data_dir: &Path, max_revisions: usize) -> Result<Self> {
+        let path = data_dir.join(HISTORY_FILE);
+        let revisions = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)?
+        } else {
+            HashMap::new()
+        };
+        Ok(Self { path, max_revisions, revisions })
+    }
+
+    /// 记录即将被覆盖的文档，返回分配的修订号（不保留历史时返回 None）
+    pub fn record(&mut self, record: DocumentRecord) -> Option<u64> {
+        if self.max_revisions == 0 {
+            return None;
+        }
+        let history = self.revisions.entry(record.id.clone()).or_default();
+        let revision = history.back().map_or(1, |last| last.revision + 1);
+        history.push_back(DocumentRevision { revision, record, replaced_at: Utc::now() });
+        while history.len() > self.max_revisions {
+            history.pop_front();
+        }
+        Some(revision)
+    }
+
+    /// 文档的历史修订，按修订号升序
+    pub fn list(&self, doc_id: &str) -> Vec<DocumentRevision> {
+        self.revisions.get(doc_id).map(|history| history.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    /// 取得指定修订
+    pub fn get(&self, doc_id: &str, revision: u64) -> Option<&DocumentRevision> {
+        self.revisions.get(doc_id)?.iter().find(|r| r.revision == revision)
+    }
+
+    /// 清除文档的全部历史
+    pub fn remove(&mut self, doc_id: &str) -> bool {
+        self.revisions.remove(doc_id).is_some()
+    }
+
+    /// 保存到磁盘
+    pub fn save(&self) -> Result<()> {
+        std::fs::write(&self.path, serde_json::to_string(&self.revisions)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, content: &str) -> DocumentRecord {
+        DocumentRecord {
+            id: id.to_string(),
+            title: "title".to_string(),
+            content: content.to_string(),
+            embedding: vec![1.0, 0.0],
+            package_name: "pkg".to_string(),
+            doc_type: "api".to_string(),
+            language: "rust".to_string(),
+            version: "1.0".to_string(),
+            metadata: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut history = RevisionHistory::open(dir.path(), 2).unwrap();
+        for content in ["v1", "v2", "v3"] {
+            history.record(record("doc", content));
+        }
+        let revisions = history.list("doc");
+        assert_eq!(revisions.iter().map(|r| r.revision).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(history.get("doc", 1).is_none());
+        history.save().unwrap();
+
+        let reopened = RevisionHistory::open(dir.path(), 2).unwrap();
+        assert_eq!(reopened.get("doc", 3).unwrap().record.content, "v3");
+        assert!(reopened.list("other").is_empty());
+
+        let mut disabled = RevisionHistory::open(dir.path(), 0).unwrap();
+        assert_eq!(disabled.record(record("doc", "v4")), None);
+    }
+}
@@ -1,3 +1,7 @@
+pub mod embedding_meta;
+pub mod history;
 pub mod traits;
 
+pub use embedding_meta::{EmbeddingMeta, EMBEDDING_META_FILE};
+pub use history::{DocumentRevision, RevisionHistory};
 pub use traits::*; 
\ No newline at end of file
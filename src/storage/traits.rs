@@ -1,5 +1,8 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+
+use crate::types::DocumentRecord;
 
 use crate::tools::base::{
     DocumentVector, FileDocumentFragment, FileSearchResult, HierarchyFilter,
@@ -16,8 +19,37 @@ pub trait VectorStore: Send + Sync {
     
     /// 获取存储信息
     async fn get_info(&self) -> Result<VectorStoreInfo>;
+
+    /// 按文档ID升序返回ID大于 `after` 的最多 `limit` 个文档（键集分页，`after` 为 None 时从头开始）
+    ///
+    /// 与按偏移量分页不同，不需要先跳过前面的文档，翻到多深都只读取一批。
+    async fn list_documents_after(&self, after: Option<&str>, limit: usize) -> crate::errors::Result<Vec<DocumentRecord>>;
+
+    /// 以异步流按文档ID顺序产出全部文档，每次从存储取 `batch_size` 个，内存中只保留一批；
+    /// 导出、去重和重建索引等全量扫描应使用它，而不是一次性列出所有文档
+    fn scan(&self, batch_size: usize) -> BoxStream<'_, crate::errors::Result<DocumentRecord>> {
+        let batch_size = batch_size.max(1);
+        // 状态为下一批的起点，None 表示已取完
+        stream::try_unfold(Some(None::<String>), move |cursor| async move {
+            let Some(after) = cursor else {
+                return Ok(None);
+            };
+            let batch = self.list_documents_after(after.as_deref(), batch_size).await?;
+            if batch.is_empty() {
+                return Ok(None);
+            }
+            let next = (batch.len() == batch_size).then(|| batch.last().map(|record| record.id.clone()));
+            Ok(Some((batch, next)))
+        })
+        .map_ok(|batch| stream::iter(batch.into_iter().map(Ok)))
+        .try_flatten()
+        .boxed()
+    }
 }
 
+/// 全量扫描默认的批大小
+pub const SCAN_BATCH_SIZE: usize = 256;
+
 /// 文档向量存储接口
 #[async_trait]
 pub trait DocumentVectorStore: VectorStore {
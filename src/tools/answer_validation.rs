@@ -0,0 +1,523 @@
+//! 回答中 API 的校验
+//!
+//! 生成的回答可能提到并不存在的函数，或者只在其他版本中存在的 API。本模块从回答的行内代码和代码块中
+//! 提取符号及调用时的参数个数，与已缓存文档的符号索引比对，给出每个符号的结论：
+//! - `verified`：目标版本的文档中有该符号，参数个数也对得上
+//! - `version_mismatch`：只在其他版本的文档中找到
+//! - `signature_mismatch`：找到了该符号，但文档中的声明和用法的参数个数都与回答不一致
+//! - `not_found`：符号所属的包已缓存了 API 文档，其中却没有该符号，可能是编造的 API
+//! - `unverified`：符号所属的包没有 API 文档，无法判断
+//!
+//! 目标版本默认取项目锁文件中的版本（见 `ProjectProfile::locked_version`），没有时不限版本。
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use anyhow::Result;
+
+use crate::errors::MCPError;
+use crate::tools::base::{MCPTool, Schema, SchemaObject, SchemaString};
+use crate::tools::doc_coverage::CoverageLevel;
+use crate::tools::project_context::{canonical_language, normalize_package};
+use crate::tools::search_filter::version_matches;
+use crate::tools::symbol_index::{identifier_query, normalize_symbol};
+use crate::tools::vector_docs_tool::{SearchResult, VectorDocsTool};
+
+/// 每个符号最多列出的文档写法
+const MAX_DOCUMENTED_SIGNATURES: usize = 3;
+
+/// 不检查的关键字、内置函数和常见的接收者
+const IGNORED_NAMES: &[&str] = &[
+    "if", "for", "while", "match", "return", "fn", "def", "function", "func", "let", "const", "var", "new",
+    "print", "println", "eprintln", "format", "vec", "assert", "assert_eq", "panic", "len", "str", "int",
+    "list", "dict", "range", "console", "Some", "None", "Ok", "Err", "Box", "String", "Vec", "std",
+];
+
+/// 行内代码中出现时视为文件名而不是符号的扩展名
+const FILE_EXTENSIONS: &[&str] = &["rs", "toml", "json", "py", "js", "ts", "md", "yaml", "yml", "lock", "txt", "java", "go"];
+
+/// 符号前面出现这些关键字时是回答代码自己的声明，不检查
+const DECLARATION_KEYWORDS: &[&str] = &["fn", "def", "function", "func", "class", "struct", "enum", "trait", "let", "const", "var", "mod", "use", "import", "from"];
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("内置正则无效"))
+}
+
+/// 回答中提到的符号
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MentionedSymbol {
+    /// 原文写法，如 `tokio::spawn`、`df.merge`
+    pub symbol: String,
+    /// 调用时的参数个数，不是调用或无法确定（如 `*args` 展开）时为 None
+    pub arguments: Option<usize>,
+}
+
+/// 单个符号的校验结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolStatus {
+    Verified,
+    VersionMismatch,
+    SignatureMismatch,
+    NotFound,
+    Unverified,
+}
+
+impl SymbolStatus {
+    /// 需要提示用户的结论
+    pub fn is_flagged(self) -> bool {
+        matches!(self, SymbolStatus::VersionMismatch | SymbolStatus::SignatureMismatch | SymbolStatus::NotFound)
+    }
+}
+
+/// 单个符号的校验结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolCheck {
+    pub symbol: String,
+    pub status: SymbolStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_version: Option<String>,
+    /// 文档中有该符号的已缓存版本
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub found_in_versions: Vec<String>,
+    /// 文档中该符号的声明或用法
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub documented_signatures: Vec<String>,
+}
+
+/// 整个回答的校验结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AnswerValidation {
+    /// 没有需要提示的符号
+    pub valid: bool,
+    pub checked: usize,
+    pub flagged: usize,
+    pub symbols: Vec<SymbolCheck>,
+}
+
+/// 校验条件，未指定的字段从回答和项目画像推断
+#[derive(Debug, Clone, Default)]
+pub struct ValidationContext {
+    pub language: Option<String>,
+    pub package_name: Option<String>,
+    pub version: Option<String>,
+}
+
+/// 文档中 `名称(参数)` 形式的声明或用法
+#[derive(Debug, Clone, PartialEq)]
+struct Signature {
+    text: String,
+    min: usize,
+    /// None 表示参数个数不限（`*args`、`...rest`）
+    max: Option<usize>,
+    /// 声明带有 `self` 等接收者参数
+    has_receiver: bool,
+}
+
+impl Signature {
+    /// `via_path` 表示按路径调用（`Type::method(obj, ..)`），此时接收者也算一个参数
+    fn accepts(&self, arguments: usize, via_path: bool) -> bool {
+        let fits = |n: usize| n >= self.min && self.max.map_or(true, |max| n <= max);
+        fits(arguments) || (self.has_receiver && via_path && arguments > 0 && fits(arguments - 1))
+    }
+}
+
+/// 从回答的代码块和行内代码中提取符号；代码块中只取带路径的符号，单个名称只在行内代码中检查
+pub fn extract_mentioned_symbols(answer: &str) -> Vec<MentionedSymbol> {
+    static FENCED: OnceLock<Regex> = OnceLock::new();
+    static INLINE: OnceLock<Regex> = OnceLock::new();
+    let fenced = regex(&FENCED, r"(?s)```[^\n]*\n(.*?)```");
+
+    let mut mentions = Vec::new();
+    for capture in fenced.captures_iter(answer) {
+        collect_symbols(&capture[1], false, &mut mentions);
+    }
+    let prose = fenced.replace_all(answer, " ");
+    for capture in regex(&INLINE, r"`([^`\n]+)`").captures_iter(&prose) {
+        collect_symbols(&capture[1], true, &mut mentions);
+    }
+    let mut seen = HashSet::new();
+    mentions.retain(|mention| seen.insert(mention.clone()));
+    mentions
+}
+
+fn collect_symbols(code: &str, inline: bool, mentions: &mut Vec<MentionedSymbol>) {
+    static STRING: OnceLock<Regex> = OnceLock::new();
+    static COMMENT: OnceLock<Regex> = OnceLock::new();
+    static PATH: OnceLock<Regex> = OnceLock::new();
+
+    // 字符串字面量替换为空串，保留参数结构
+    let code = regex(&STRING, r#""(?:[^"\\\n]|\\.)*""#).replace_all(code, "\"\"");
+    let code = regex(&COMMENT, r"//[^\n]*").replace_all(&code, "");
+    for found in regex(&PATH, r"[A-Za-z_$][\w$]*(?:(?:::|\.)[A-Za-z_$][\w$]*)*!?").find_iter(&code) {
+        let before = code[..found.start()].trim_end();
+        if before.ends_with(|c: char| c == '.' || c == ':' || c == '\'')
+            || DECLARATION_KEYWORDS.iter().any(|keyword| before.split_whitespace().last() == Some(*keyword))
+        {
+            continue;
+        }
+        let mut symbol = found.as_str();
+        for receiver in ["self.", "this.", "self::", "crate::", "super::"] {
+            symbol = symbol.strip_prefix(receiver).unwrap_or(symbol);
+        }
+        let Some(segments) = normalize_symbol(symbol) else {
+            continue;
+        };
+        let rest = &code[found.end()..];
+        let called = rest.starts_with('(');
+        let first = symbol.split(|c: char| c == ':' || c == '.').next().unwrap_or(symbol).trim_end_matches('!');
+        if IGNORED_NAMES.contains(&first) {
+            continue;
+        }
+        if segments.len() == 1 && !(inline && (called || symbol.ends_with('!') || identifier_query(symbol).is_some())) {
+            continue;
+        }
+        if !called && symbol.contains('.') && FILE_EXTENSIONS.contains(&segments[segments.len() - 1].as_str()) {
+            continue;
+        }
+        mentions.push(MentionedSymbol {
+            symbol: symbol.to_string(),
+            arguments: if called { call_arguments(rest) } else { None },
+        });
+    }
+}
+
+/// 从 `(` 开始的调用中数出参数个数，括号不配对或带有 `*args`、`...rest` 展开时返回 None
+fn call_arguments(call: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut current = String::new();
+    let mut arguments = Vec::new();
+    for c in call.chars() {
+        match c {
+            '(' | '[' | '{' => {
+                depth += 1;
+                if depth > 1 {
+                    current.push(c);
+                }
+            }
+            ')' | ']' | '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    arguments.push(current);
+                    break;
+                }
+                current.push(c);
+            }
+            ',' if depth == 1 => arguments.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if depth != 0 {
+        return None;
+    }
+    let arguments: Vec<&str> = arguments.iter().map(|a| a.trim()).filter(|a| !a.is_empty()).collect();
+    if arguments.iter().any(|a| a.starts_with('*') || a.starts_with("...")) {
+        return None;
+    }
+    Some(arguments.len())
+}
+
+/// 文档中 `name(...)` 形式的声明或用法
+fn documented_signatures(content: &str, name: &str) -> Vec<Signature> {
+    static KEYWORD_ARGUMENT: OnceLock<Regex> = OnceLock::new();
+    let keyword_argument = regex(&KEYWORD_ARGUMENT, r"^[A-Za-z_]\w*\??\s*(?::[^=]*)?=[^=]|^[A-Za-z_]\w*\?\s*:");
+    let Ok(pattern) = Regex::new(&format!(r"(?i)(?:^|[^\w$]){}!?\s*(?:<[^()\n]*?>)?\(([^()\n]*)\)", regex::escape(name))) else {
+        return Vec::new();
+    };
+
+    let mut signatures: Vec<Signature> = Vec::new();
+    for capture in pattern.captures_iter(content) {
+        let (mut min, mut max, mut has_receiver) = (0, Some(0), false);
+        for parameter in split_top_level(&capture[1]) {
+            let parameter = parameter.trim();
+            let receiver = parameter.trim_start_matches('&').trim_start_matches("mut ");
+            if parameter.is_empty() || parameter == "/" || parameter == "*" {
+                continue;
+            }
+            if matches!(receiver, "self" | "cls") || receiver.starts_with("self:") || (parameter.starts_with("&'") && parameter.ends_with("self")) {
+                has_receiver = true;
+            } else if parameter.starts_with('*') || parameter.contains("...") {
+                max = None;
+            } else if keyword_argument.is_match(parameter) {
+                max = max.map(|m| m + 1);
+            } else {
+                min += 1;
+                max = max.map(|m| m + 1);
+            }
+        }
+        let text = capture[0].trim_start_matches(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$')).to_string();
+        if !signatures.iter().any(|s| s.text == text) {
+            signatures.push(Signature { text, min, max, has_receiver });
+        }
+    }
+    signatures
+}
+
+/// 按顶层逗号切分，忽略 `<>`、`[]`、`{}` 内的逗号
+fn split_top_level(parameters: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in parameters.char_indices() {
+        match c {
+            '<' | '[' | '{' => depth += 1,
+            '>' | ']' | '}' => depth -= 1,
+            ',' if depth <= 0 => {
+                parts.push(&parameters[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&parameters[start..]);
+    parts
+}
+
+/// 校验回答中提到的所有符号
+pub fn validate_answer(vector_tool: &VectorDocsTool, answer: &str, context: &ValidationContext) -> Result<AnswerValidation> {
+    // 归一化包名 -> [(规范语言名, 覆盖级别)]
+    let mut doc_types: HashMap<(String, String), BTreeSet<String>> = HashMap::new();
+    for package in vector_tool.list_cached_packages()? {
+        doc_types
+            .entry((normalize_package(&package.package_name), canonical_language(&package.language)))
+            .or_default()
+            .extend(package.doc_types.into_keys());
+    }
+    let mut cached: HashMap<String, Vec<(String, CoverageLevel)>> = HashMap::new();
+    for ((package, language), types) in doc_types {
+        cached.entry(package).or_default().push((language, CoverageLevel::from_doc_types(&types)));
+    }
+
+    let symbols: Vec<SymbolCheck> = extract_mentioned_symbols(answer)
+        .iter()
+        .map(|mention| check_symbol(vector_tool, mention, context, &cached))
+        .collect::<Result<_>>()?;
+    let flagged = symbols.iter().filter(|check| check.status.is_flagged()).count();
+    Ok(AnswerValidation { valid: flagged == 0, checked: symbols.len(), flagged, symbols })
+}
+
+fn check_symbol(
+    vector_tool: &VectorDocsTool,
+    mention: &MentionedSymbol,
+    context: &ValidationContext,
+    cached: &HashMap<String, Vec<(String, CoverageLevel)>>,
+) -> Result<SymbolCheck> {
+    let segments = normalize_symbol(&mention.symbol).unwrap_or_default();
+    let language = context.language.as_deref().map(canonical_language);
+    // 符号所属的包：调用方指定，否则路径第一段是已缓存的包名
+    let package = context.package_name.as_deref().map(normalize_package).or_else(|| {
+        segments.first().filter(|_| segments.len() > 1).map(|first| normalize_package(first)).filter(|p| cached.contains_key(p))
+    });
+    let relevant = |hit: &SearchResult| {
+        language.as_ref().map_or(true, |l| canonical_language(&hit.language) == *l)
+            && package.as_ref().map_or(true, |p| normalize_package(&hit.package_name) == *p)
+    };
+
+    // 完整路径找不到时依次去掉前缀（`client.get` 中的 client 多半是局部变量）
+    let mut hits: Vec<SearchResult> = Vec::new();
+    for start in 0..segments.len() {
+        hits = vector_tool.lookup_symbol(&segments[start..].join("::"))?.into_iter().filter(|hit| relevant(hit)).collect();
+        if !hits.is_empty() {
+            break;
+        }
+    }
+
+    let mut check = SymbolCheck {
+        symbol: mention.symbol.clone(),
+        status: SymbolStatus::Verified,
+        arguments: mention.arguments,
+        package_name: package.clone(),
+        target_version: None,
+        found_in_versions: Vec::new(),
+        documented_signatures: Vec::new(),
+    };
+    if hits.is_empty() {
+        let has_api_docs = package.as_ref().and_then(|p| cached.get(p)).map_or(false, |coverage| {
+            coverage.iter().any(|(l, level)| language.as_ref().map_or(true, |language| language == l) && *level >= CoverageLevel::ApiDocs)
+        });
+        check.status = if has_api_docs { SymbolStatus::NotFound } else { SymbolStatus::Unverified };
+        return Ok(check);
+    }
+
+    let packages: BTreeSet<(String, String)> = hits.iter()
+        .map(|hit| (canonical_language(&hit.language), normalize_package(&hit.package_name)))
+        .collect();
+    if check.package_name.is_none() && packages.len() == 1 {
+        check.package_name = packages.first().map(|(_, package)| package.clone());
+    }
+    check.target_version = context.version.clone().or_else(|| {
+        let (hit_language, hit_package) = packages.first().filter(|_| packages.len() == 1)?;
+        vector_tool.project_profile()?.locked_version(hit_language, hit_package).map(str::to_string)
+    });
+    check.found_in_versions = hits.iter().map(|hit| hit.version.clone()).collect::<BTreeSet<_>>().into_iter().collect();
+
+    let in_version: Vec<&SearchResult> = hits.iter()
+        .filter(|hit| check.target_version.as_deref().map_or(true, |target| version_matches(target, &hit.version)))
+        .collect();
+    if in_version.is_empty() {
+        check.status = SymbolStatus::VersionMismatch;
+        return Ok(check);
+    }
+
+    let name = mention.symbol.rsplit(|c: char| c == ':' || c == '.').next().unwrap_or(&mention.symbol).trim_end_matches('!');
+    let mut signatures: Vec<Signature> = Vec::new();
+    for hit in &in_version {
+        for signature in documented_signatures(&hit.content, name) {
+            if !signatures.contains(&signature) {
+                signatures.push(signature);
+            }
+        }
+    }
+    if let Some(arguments) = mention.arguments {
+        let via_path = mention.symbol.contains("::");
+        if !signatures.is_empty() && !signatures.iter().any(|signature| signature.accepts(arguments, via_path)) {
+            check.status = SymbolStatus::SignatureMismatch;
+        }
+    }
+    check.documented_signatures = signatures.into_iter().take(MAX_DOCUMENTED_SIGNATURES).map(|s| s.text).collect();
+    Ok(check)
+}
+
+/// 校验回答中提到的 API 是否存在于已缓存文档的目标版本中
+pub struct AnswerValidationTool {
+    vector_tool: Arc<VectorDocsTool>,
+    schema: Schema,
+}
+
+impl AnswerValidationTool {
+    pub fn new(vector_tool: Arc<VectorDocsTool>) -> Self {
+        Self {
+            vector_tool,
+            schema: Self::create_schema(),
+        }
+    }
+
+    fn create_schema() -> Schema {
+        let mut props = HashMap::new();
+        props.insert("answer".to_string(), Schema::String(SchemaString {
+            description: Some("要校验的回答（Markdown，符号写在行内代码或代码块中）".to_string()),
+            enum_values: None,
+        }));
+        props.insert("language".to_string(), Schema::String(SchemaString {
+            description: Some("编程语言（可选，只与该语言的文档比对）".to_string()),
+            enum_values: None,
+        }));
+        props.insert("package_name".to_string(), Schema::String(SchemaString {
+            description: Some("回答针对的包（可选，默认按符号路径的第一段推断）".to_string()),
+            enum_values: None,
+        }));
+        props.insert("version".to_string(), Schema::String(SchemaString {
+            description: Some("目标版本（可选，默认取项目锁文件中的版本，支持 1.x、^1.2 等写法）".to_string()),
+            enum_values: None,
+        }));
+        Schema::Object(SchemaObject {
+            required: vec!["answer".to_string()],
+            properties: props,
+            description: Some("按已缓存的 API 文档校验回答".to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl MCPTool for AnswerValidationTool {
+    fn name(&self) -> &str {
+        "validate_answer"
+    }
+
+    fn description(&self) -> &str {
+        "返回回答前的可选校验步骤：提取回答中提到的函数、方法和宏及调用参数个数，与已缓存的 API 文档比对，标出文档中不存在（not_found）、只在其他版本中存在（version_mismatch）或参数个数不符（signature_mismatch）的 API。"
+    }
+
+    fn parameters_schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let answer = params["answer"].as_str()
+            .filter(|s| !s.trim().is_empty())
+            .ok_or_else(|| MCPError::InvalidParameter("缺少answer参数".to_string()))?
+            .to_string();
+        let text = |key: &str| params[key].as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+        let context = ValidationContext {
+            language: text("language"),
+            package_name: text("package_name"),
+            version: text("version"),
+        };
+
+        let vector_tool = Arc::clone(&self.vector_tool);
+        let validation = tokio::task::spawn_blocking(move || validate_answer(&vector_tool, &answer, &context)).await??;
+        Ok(json!({
+            "status": "success",
+            "valid": validation.valid,
+            "checked": validation.checked,
+            "flagged": validation.flagged,
+            "symbols": validation.symbols,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::embedder::testing::MockEmbedder;
+
+    #[test]
+    fn test_extract_symbols_and_signatures() {
+        let answer = "用 `tokio::spawn(async { work().await })` 启动任务，配置写在 `Cargo.toml`。\n\n```rust\nfn main() {\n    let client = reqwest::Client::new();\n    client.get(\"https://docs.rs/a.b\", 3);\n}\n```\n";
+        let mentions = extract_mentioned_symbols(answer);
+        let found: Vec<(&str, Option<usize>)> = mentions.iter().map(|m| (m.symbol.as_str(), m.arguments)).collect();
+        assert!(found.contains(&("tokio::spawn", Some(1))));
+        assert!(found.contains(&("reqwest::Client::new", Some(0))));
+        assert!(found.contains(&("client.get", Some(2))));
+        assert!(!found.iter().any(|(symbol, _)| *symbol == "Cargo.toml" || *symbol == "main"));
+
+        let signatures = documented_signatures("pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder", "get");
+        assert_eq!(signatures.len(), 1);
+        assert!(signatures[0].accepts(1, false));
+        assert!(signatures[0].accepts(2, true));
+        assert!(!signatures[0].accepts(2, false));
+
+        let python = documented_signatures("DataFrame.merge(right, how='inner', on=None, **kwargs)", "merge");
+        assert_eq!((python[0].min, python[0].max), (1, None));
+    }
+
+    #[tokio::test]
+    async fn test_validate_answer_flags_unknown_and_mismatched_apis() {
+        let dir = tempfile::tempdir().unwrap();
+        let vector_tool = Arc::new(
+            VectorDocsTool::open_local(dir.path().to_path_buf()).unwrap().with_embedder(Arc::new(MockEmbedder::new(32))),
+        );
+        for (version, title, content) in [
+            ("1.38.0", "tokio::spawn", "```rust\npub fn spawn<F>(future: F) -> JoinHandle<F::Output>\n```"),
+            ("0.1.22", "tokio::run", "```rust\npub fn run<F>(future: F)\n```"),
+        ] {
+            vector_tool.execute(json!({
+                "action": "store", "title": title, "content": content, "doc_type": "api",
+                "language": "rust", "package_name": "tokio", "version": version
+            })).await.unwrap();
+        }
+        let tool = AnswerValidationTool::new(Arc::clone(&vector_tool));
+
+        let answer = "```rust\ntokio::spawn(fut);\ntokio::spawn(a, b);\ntokio::run(fut);\ntokio::spawn_detached(fut);\nserde_json::to_string(&value);\n```";
+        let result = tool.execute(json!({ "answer": answer, "version": "1.38.0" })).await.unwrap();
+        let status = |symbol: &str, arguments: u64| {
+            result["symbols"].as_array().unwrap().iter()
+                .find(|check| check["symbol"] == symbol && check["arguments"] == arguments)
+                .map(|check| check["status"].as_str().unwrap().to_string())
+        };
+        assert_eq!(status("tokio::spawn", 1).as_deref(), Some("verified"));
+        assert_eq!(status("tokio::spawn", 2).as_deref(), Some("signature_mismatch"));
+        assert_eq!(status("tokio::run", 1).as_deref(), Some("version_mismatch"));
+        assert_eq!(status("tokio::spawn_detached", 1).as_deref(), Some("not_found"));
+        assert_eq!(status("serde_json::to_string", 1).as_deref(), Some("unverified"));
+        assert_eq!((result["valid"].as_bool(), result["flagged"].as_u64()), (Some(false), Some(3)));
+
+        assert!(tool.execute(json!({ "answer": " " })).await.is_err());
+    }
+}
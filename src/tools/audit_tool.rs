@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use anyhow::Result;
+
+use crate::errors::MCPError;
+use crate::mcp::audit::{AuditLog, AuditOutcome, AuditQuery, DEFAULT_QUERY_LIMIT};
+use crate::mcp::auth::AuthScope;
+use crate::tools::base::{MCPTool, Schema, SchemaObject, SchemaString};
+
+/// 查询工具调用审计日志的管理工具（启用审计时注册）
+pub struct AuditLogTool {
+    audit: Arc<AuditLog>,
+    schema: Schema,
+}
+
+impl AuditLogTool {
+    pub fn new(audit: Arc<AuditLog>) -> Self {
+        Self {
+            audit,
+            schema: Self::create_schema(),
+        }
+    }
+
+    fn create_schema() -> Schema {
+        let mut props = HashMap::new();
+        props.insert("tool".to_string(), Schema::String(SchemaString {
+            description: Some("只返回指定工具的调用".to_string()),
+            enum_values: None,
+        }));
+        props.insert("session".to_string(), Schema::String(SchemaString {
+            description: Some("只返回指定会话的调用".to_string()),
+            enum_values: None,
+        }));
+        props.insert("outcome".to_string(), Schema::String(SchemaString {
+            description: Some("只返回指定结果的调用".to_string()),
+            enum_values: Some(vec!["success".to_string(), "error".to_string(), "timeout".to_string(), "denied".to_string()]),
+        }));
+        props.insert("since".to_string(), Schema::String(SchemaString {
+            description: Some("只返回该时间（RFC 3339）之后的调用".to_string()),
+            enum_values: None,
+        }));
+        props.insert("limit".to_string(), Schema::String(SchemaString {
+            description: Some(format!("最多返回的记录数，默认{}", DEFAULT_QUERY_LIMIT)),
+            enum_values: None,
+        }));
+        Schema::Object(SchemaObject {
+            required: Vec::new(),
+            properties: props,
+            description: Some("查询工具调用审计日志".to_string()),
+        })
+    }
+
+    fn parse_query(params: &Value) -> Result<AuditQuery> {
+        let text = |key: &str| params[key].as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+        let outcome = match text("outcome") {
+            Some(outcome) => Some(AuditOutcome::parse(&outcome)
+                .ok_or_else(|| MCPError::InvalidParameter(format!("未知的调用结果: {}", outcome)))?),
+            None => None,
+        };
+        let since = match text("since") {
+            Some(since) => Some(DateTime::parse_from_rfc3339(&since)
+                .map_err(|e| MCPError::InvalidParameter(format!("since 不是有效的 RFC 3339 时间: {}", e)))?
+                .with_timezone(&Utc)),
+            None => None,
+        };
+        let limit = params["limit"].as_u64()
+            .or_else(|| params["limit"].as_str().and_then(|s| s.parse().ok()))
+            .map(|limit| limit as usize);
+        Ok(AuditQuery {
+            tool: text("tool"),
+            session: text("session"),
+            outcome,
+            since,
+            limit,
+        })
+    }
+}
+
+#[async_trait]
+impl MCPTool for AuditLogTool {
+    fn name(&self) -> &str {
+        "audit_log"
+    }
+
+    fn description(&self) -> &str {
+        "管理工具：按工具、会话、结果和时间查询工具调用审计记录（参数只记录哈希），最新的记录在前。"
+    }
+
+    fn parameters_schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn required_scope(&self, _params: &Value) -> AuthScope {
+        AuthScope::Admin
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let query = Self::parse_query(&params)?;
+        let audit = Arc::clone(&self.audit);
+        let records = tokio::task::spawn_blocking(move || audit.query(&query)).await??;
+        Ok(json!({
+            "path": self.audit.path(),
+            "count": records.len(),
+            "records": records,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::audit::AuditRecord;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_queries_records_with_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = Arc::new(AuditLog::open(dir.path().join("audit.jsonl")).unwrap());
+        audit.record(&AuditRecord::new("search_docs", &json!({ "query": "a" }), "s1", Duration::from_millis(5), AuditOutcome::Success));
+        audit.record(&AuditRecord::new("vector_docs", &json!({ "action": "store" }), "s1", Duration::ZERO, AuditOutcome::Denied));
+
+        let tool = AuditLogTool::new(audit);
+        assert_eq!(tool.required_scope(&Value::Null), AuthScope::Admin);
+
+        let result = tool.execute(json!({ "outcome": "denied" })).await.unwrap();
+        assert_eq!(result["count"], 1);
+        assert_eq!(result["records"][0]["tool"], "vector_docs");
+
+        let result = tool.execute(json!({ "session": "s1", "limit": "5" })).await.unwrap();
+        assert_eq!(result["count"], 2);
+
+        assert!(tool.execute(json!({ "since": "yesterday" })).await.is_err());
+    }
+}
@@ -1,9 +1,13 @@
 use crate::tools::enhanced_doc_processor::EnhancedDocumentProcessor;
 use crate::tools::vector_docs_tool::VectorDocsTool;
+use crate::tools::qa_enrichment::QaEnrichmentConfig;
+use crate::tools::crawl_report::{CrawlReport, CrawlReportStore};
+use crate::tools::cache_webhook::CacheWebhook;
+use crate::tools::doc_coverage::{self, CoverageLevel};
+use crate::mcp::shutdown::ShutdownCoordinator;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Semaphore;
-use tokio::task::JoinSet;
 use tracing::{info, warn, error, debug};
 use anyhow::Result;
 
@@ -40,6 +44,12 @@ pub struct BackgroundDocCacher {
     config: DocCacherConfig,
     doc_processor: Arc<EnhancedDocumentProcessor>,
     vector_tool: Arc<VectorDocsTool>, 
+    /// 关闭协调器：关闭时等待进行中的任务，不再开始排队中的任务
+    shutdown: Option<ShutdownCoordinator>,
+    /// 每个包版本处理结束后写入抓取报告
+    reports: CrawlReportStore,
+    /// 每个包版本处理结束后发送通知（未配置时为None）
+    webhook: Option<Arc<CacheWebhook>>,
 }
 
 impl BackgroundDocCacher {
@@ -52,9 +62,30 @@ impl BackgroundDocCacher {
             config,
             doc_processor,
             vector_tool,
+            shutdown: None,
+            reports: CrawlReportStore::default(),
+            webhook: None,
         }
     }
 
+    /// 设置缓存完成通知
+    pub fn with_webhook(mut self, webhook: Option<Arc<CacheWebhook>>) -> Self {
+        self.webhook = webhook;
+        self
+    }
+
+    /// 设置抓取报告目录（默认 `.mcp_cache/reports`）
+    pub fn with_reports(mut self, reports: CrawlReportStore) -> Self {
+        self.reports = reports;
+        self
+    }
+
+    /// 登记到关闭协调器，服务器关闭时在截止时间内等待缓存任务结束
+    pub fn with_shutdown(mut self, shutdown: ShutdownCoordinator) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
     /// 将检测到的依赖项加入后台缓存队列
     /// 处理检测到的语言信息，为每种语言的标准库和常用包创建缓存任务
     pub async fn queue_dependencies_for_caching(
@@ -68,7 +99,6 @@ impl BackgroundDocCacher {
 
         info!("启动后台文档缓存任务，并发数: {}", self.config.concurrent_tasks);
         let semaphore = Arc::new(Semaphore::new(self.config.concurrent_tasks));
-        let mut task_set = JoinSet::new();
 
         for (language_name, lang_info) in detected_languages_map {
             // 检查是否已经处理过这个语言
@@ -82,7 +112,7 @@ impl BackgroundDocCacher {
 
             for package_info in language_packages {
                 // 检查向量数据库中是否已经存在该包的文档
-                if self.is_package_already_cached(language_name, &package_info.name).await {
+                if self.is_package_already_cached(language_name, &package_info.name).await? {
                     debug!("包 {}/{} 已缓存，跳过处理", language_name, package_info.name);
                     continue;
                 }
@@ -90,26 +120,57 @@ impl BackgroundDocCacher {
                 let lang_clone = language_name.clone();
                 let pkg_name_clone = package_info.name.clone();
                 let pkg_version_clone = package_info.version.clone().unwrap_or_else(|| "latest".to_string());
+
+                // 认领处理权：已完成或其他任务正在处理的包版本直接跳过，中断过的会从已有进度恢复
+                match self.vector_tool.try_claim_package_version(&lang_clone, &pkg_name_clone, &pkg_version_clone) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        debug!("包 {}/{}/{} 已完成或正在处理，跳过", lang_clone, pkg_name_clone, pkg_version_clone);
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("无法认领包 {}/{}/{} 的处理: {}", lang_clone, pkg_name_clone, pkg_version_clone, e);
+                        continue;
+                    }
+                }
                 
                 let doc_processor_clone = Arc::clone(&self.doc_processor);
                 let vector_tool_clone = Arc::clone(&self.vector_tool);
                 let semaphore_clone = Arc::clone(&semaphore);
+                let shutdown_clone = self.shutdown.clone();
+                let reports_clone = self.reports.clone();
+                let webhook_clone = self.webhook.clone();
+                let in_flight = self.shutdown.as_ref().map(ShutdownCoordinator::track);
 
-                task_set.spawn(async move {
+                tokio::spawn(async move {
                     let permit = semaphore_clone.acquire().await.expect("信号量获取失败");
+                    // 关闭期间不再开始排队中的任务，释放处理权以便下次启动时继续
+                    if shutdown_clone.as_ref().map_or(false, ShutdownCoordinator::is_shutting_down) {
+                        info!("服务器正在关闭，跳过 {}/{}/{} 的缓存", lang_clone, pkg_name_clone, pkg_version_clone);
+                        if let Err(e) = vector_tool_clone.fail_package_progress(
+                            &lang_clone, &pkg_name_clone, &pkg_version_clone, "服务器关闭，未开始处理",
+                        ) {
+                            warn!("释放包处理权时出错: {}", e);
+                        }
+                        return;
+                    }
                     info!("开始处理文档缓存: {}/{}/{}...", lang_clone, pkg_name_clone, pkg_version_clone);
+                    let started = std::time::Instant::now();
+                    let mut report = CrawlReport::started(&lang_clone, &pkg_name_clone, &pkg_version_clone);
                     
                     match Self::cache_single_package(
                         doc_processor_clone,
-                        vector_tool_clone,
+                        Arc::clone(&vector_tool_clone),
                         &lang_clone,
                         &pkg_name_clone,
                         &pkg_version_clone,
+                        &mut report,
                     ).await {
                         Ok(stats) => {
+                            report.succeed(started.elapsed());
                             info!(
-                                "成功缓存包 {}/{}/{}: {} 个文档片段已处理，{} 个新片段已添加。", 
-                                lang_clone, pkg_name_clone, pkg_version_clone, stats.fragments_processed, stats.fragments_added
+                                "成功缓存包 {}/{}/{}: {} 个文档片段已处理，{} 个新片段已添加，{} 个因质量不达标被拒绝。", 
+                                lang_clone, pkg_name_clone, pkg_version_clone, stats.fragments_processed, stats.fragments_added, stats.fragments_rejected
                             );
                         }
                         Err(e) => {
@@ -117,9 +178,24 @@ impl BackgroundDocCacher {
                                 "缓存包 {}/{}/{} 文档失败: {:?}", 
                                 lang_clone, pkg_name_clone, pkg_version_clone, e
                             );
+                            report.fail(started.elapsed(), e.to_string());
+                            if let Err(progress_err) = vector_tool_clone.fail_package_progress(
+                                &lang_clone, &pkg_name_clone, &pkg_version_clone, &e.to_string(),
+                            ) {
+                                warn!("记录包处理失败状态时出错: {}", progress_err);
+                            }
+                        }
+                    }
+                    if let Err(e) = reports_clone.save(&report) {
+                        warn!("写入 {}/{}/{} 的抓取报告失败: {}", lang_clone, pkg_name_clone, pkg_version_clone, e);
+                    }
+                    if let Some(webhook) = &webhook_clone {
+                        if let Err(e) = webhook.notify(&report).await {
+                            warn!("发送 {}/{}/{} 的缓存通知失败: {}", lang_clone, pkg_name_clone, pkg_version_clone, e);
                         }
                     }
                     drop(permit); 
+                    drop(in_flight);
                 });
             }
         }
@@ -133,6 +209,7 @@ impl BackgroundDocCacher {
         language: &str,
         package_name: &str,
         version: &str,
+        crawl_report: &mut CrawlReport,
     ) -> Result<CacheStats> {
         debug!("获取包 {}/{}/(version: {}) 的文档片段...", language, package_name, version);
 
@@ -142,23 +219,42 @@ impl BackgroundDocCacher {
             Ok(results) => {
                 if results.is_empty() {
                     info!("未找到包 {}/{}/(version: {}) 的文档片段。", language, package_name, version);
-                    return Ok(CacheStats::default());
+                    return Err(anyhow::anyhow!("未找到任何文档片段"));
                 }
 
                 debug!("为包 {}/{}/(version: {}) 获取到 {} 个文档片段，准备批量添加到向量库...", language, package_name, version, results.len());
                 
                 // 将 EnhancedSearchResult 转换为 FileDocumentFragment 进行存储
                 let fragments: Vec<_> = results.into_iter().map(|result| result.fragment).collect();
-                let added_ids = vector_tool.add_file_fragments_batch(&fragments).await?;
+                crawl_report.pages_fetched = fragments.len();
+                let report = vector_tool.ingest_fragments(&fragments).await?;
+                crawl_report.skipped = report.skipped;
+                crawl_report.failed = report.failed;
+                crawl_report.fragments_stored = report.stored;
+                crawl_report.fragments_rejected = report.rejected.clone();
+                crawl_report.embedding_tokens = report.embedding_tokens;
+                vector_tool.record_package_progress(language, package_name, version, fragments.len(), report.added_ids.len(), Some(fragments.len()))?;
+                vector_tool.record_rejected_fragments(language, package_name, version, &report.rejected)?;
+
+                // 只有整个流水线结束后才标记完成，中途失败的包下次会从已有进度恢复
+                vector_tool.complete_package_progress(language, package_name, version)?;
+
+                // 问答补充是可选的附加来源，失败不影响包的缓存结果
+                if QaEnrichmentConfig::from_env().enabled {
+                    if let Err(e) = vector_tool.enrich_with_qa(language, package_name, version, &[]).await {
+                        warn!("包 {}/{} 的问答补充失败: {}", language, package_name, e);
+                    }
+                }
                 
                 Ok(CacheStats {
                     fragments_processed: fragments.len(),
-                    fragments_added: added_ids.len(),
+                    fragments_added: report.added_ids.len(),
+                    fragments_rejected: report.rejected.total(),
                 })
             }
             Err(e) => {
                 warn!("后台文档缓存暂不支持语言或获取失败: {} - {}", language, e);
-                Ok(CacheStats::default())
+                Err(e)
             }
         }
     }
@@ -227,30 +323,9 @@ impl BackgroundDocCacher {
         }
     }
     
-    /// 检查包是否已在向量数据库中缓存
-    async fn is_package_already_cached(&self, language: &str, package_name: &str) -> bool {
-        // 通过搜索向量数据库来检查是否已有该包的文档
-        let search_query = format!("{} {} documentation", language, package_name);
-        
-        // 生成查询的嵌入向量并搜索
-        match self.vector_tool.generate_embedding(&search_query).await {
-            Ok(query_embedding) => {
-                // 在向量数据库中搜索相似文档（search_similar是同步方法）
-                match self.vector_tool.search_similar(&query_embedding, 1) {
-                    Ok(results) => {
-                        // 如果找到相似结果，检查是否匹配语言和包名
-                        !results.is_empty() && results.iter().any(|r| {
-                            let score_threshold = 0.8; // 相似度阈值
-                            r.score >= score_threshold &&
-                            r.package_name.to_lowercase() == package_name.to_lowercase() &&
-                            r.language.to_lowercase() == language.to_lowercase()
-                        })
-                    }
-                    Err(_) => false,
-                }
-            }
-            Err(_) => false,
-        }
+    /// 检查包是否已缓存 API 文档（只有问答等元数据的包仍需抓取）
+    async fn is_package_already_cached(&self, language: &str, package_name: &str) -> Result<bool> {
+        Ok(doc_coverage::package_coverage(&self.vector_tool, language, package_name)? >= CoverageLevel::ApiDocs)
     }
 
     pub async fn save_config(&self) -> Result<()> {
@@ -263,4 +338,5 @@ impl BackgroundDocCacher {
 struct CacheStats {
     fragments_processed: usize,
     fragments_added: usize,
+    fragments_rejected: usize,
 } 
\ No newline at end of file
@@ -2,6 +2,9 @@ use async_trait::async_trait;
 use serde_json::Value;
 use std::collections::HashMap;
 use crate::errors::MCPError;
+use crate::mcp::auth::AuthScope;
+use crate::mcp::session::SessionPreferences;
+use crate::mcp::streaming::PartialResultSink;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -21,6 +24,17 @@ pub enum Schema {
 }
 
 impl Schema {
+    /// 复制 Schema，并从对象属性 `property` 的枚举值中去掉 `removed`（只读模式隐藏写操作用）
+    pub fn without_enum_values(&self, property: &str, removed: &[&str]) -> Schema {
+        let mut schema = self.clone();
+        if let Schema::Object(obj) = &mut schema {
+            if let Some(Schema::String(SchemaString { enum_values: Some(values), .. })) = obj.properties.get_mut(property) {
+                values.retain(|value| !removed.contains(&value.as_str()));
+            }
+        }
+        schema
+    }
+
     pub fn validate(&self, value: &Value) -> Result<()> {
         match self {
             Schema::Object(obj) => obj.validate(value),
@@ -254,9 +268,45 @@ pub trait MCPTool: Send + Sync {
     /// 获取工具参数Schema
     fn parameters_schema(&self) -> &Schema;
 
+    /// 获取工具输出Schema（可选）
+    ///
+    /// 声明了输出Schema的工具保证返回结果符合该结构，客户端可以据此按类型解析结果。
+    fn output_schema(&self) -> Option<&Schema> {
+        None
+    }
+
+    /// 会修改缓存的 `action` 取值（写入、删除、导入等），默认没有
+    ///
+    /// 用于默认的 [`MCPTool::required_scope`]，以及只读模式下从 Schema 中隐藏这些操作。
+    fn mutating_actions(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// 调用所需的授权范围（网络传输启用认证时检查，只读模式拒绝 `write`）
+    ///
+    /// 默认在 `action` 参数属于 [`MCPTool::mutating_actions`] 时返回 `write`，否则只需 `read`。
+    fn required_scope(&self, params: &Value) -> AuthScope {
+        match params.get("action").and_then(|v| v.as_str()) {
+            Some(action) if self.mutating_actions().contains(&action) => AuthScope::Write,
+            _ => AuthScope::Read,
+        }
+    }
+
+    /// 按会话偏好补全调用方省略的参数，默认不处理
+    fn apply_session_defaults(&self, _params: &mut Value, _session: &SessionPreferences) {}
+
     /// 执行工具
     async fn execute(&self, params: Value) -> Result<Value>;
 
+    /// 以分块推送的方式执行工具（客户端提供了 progressToken 时调用）
+    ///
+    /// 默认实现在执行完成后把结果中的 `results` 列表分块推送；
+    /// 能够边计算边产出结果的工具可以重写此方法。
+    async fn execute_streaming(&self, params: Value, sink: &PartialResultSink) -> Result<Value> {
+        let result = self.execute(params).await?;
+        Ok(sink.stream_results(result))
+    }
+
     /// 验证输入参数
     fn validate_params(&self, params: &Value) -> Result<()> {
         let schema = self.parameters_schema();
@@ -284,8 +334,16 @@ pub struct FileDocumentFragment {
     /// 文件类型
     pub file_type: FileType,
     
-    /// 创建时间
+    /// 创建时间（即抓取时间）
     pub created_at: SystemTime,
+
+    /// 来源地址，由本地 CLI 生成的文档为 None
+    #[serde(default)]
+    pub source_url: Option<String>,
+
+    /// 包注册表声明的许可证，为 None 时入库时从内容检测（见 `provenance`）
+    #[serde(default)]
+    pub license: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -312,6 +370,16 @@ pub enum FileType {
     Other(String), // 其他类型
 }
 
+impl FileType {
+    /// 入库时使用的文档类型名，如 "source"、"documentation"；`Other` 使用自身名称（如 "qa"）
+    pub fn doc_type(&self) -> String {
+        match self {
+            FileType::Other(name) => name.to_lowercase(),
+            other => format!("{:?}", other).to_lowercase(),
+        }
+    }
+}
+
 impl Default for FileMetadata {
     fn default() -> Self {
         Self {
@@ -352,8 +420,16 @@ impl FileDocumentFragment {
             hierarchy_path,
             file_type,
             created_at: SystemTime::now(),
+            source_url: None,
+            license: None,
         }
     }
+
+    /// 记录片段的来源地址
+    pub fn with_source_url(mut self, url: impl Into<String>) -> Self {
+        self.source_url = Some(url.into());
+        self
+    }
     
     /// 根据文件路径判断文件类型
     fn determine_file_type(file_path: &str) -> FileType {
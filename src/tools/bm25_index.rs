@@ -0,0 +1,179 @@
+//! BM25 全文倒排索引
+//!
+//! 关键词搜索原先对每个候选文档做子串匹配，既要扫描全部文档，也无法区分常见词和罕见词。
+//! 这里按词条维护倒排表，随向量索引一起增量更新，查询时只访问包含查询词条的文档，按 BM25 打分。
+//! 分词由调用方完成（见 [`super::text_analyzer`]），中日韩文字按二元组切分，因此中文内容同样可检索。
+//! 标题词条按 [`TITLE_BOOST`] 倍计入词频。
+
+use std::collections::HashMap;
+
+/// BM25 词频饱和参数
+const K1: f32 = 1.2;
+
+/// BM25 文档长度归一化参数
+const B: f32 = 0.75;
+
+/// 标题词条计入词频的倍数
+pub const TITLE_BOOST: u32 = 3;
+
+/// 已索引的文档
+#[derive(Debug, Clone)]
+struct IndexedDoc {
+    id: String,
+    /// 词条数（含标题加权）
+    len: u32,
+    /// 文档包含的不同词条，删除时据此清理倒排表
+    terms: Vec<String>,
+}
+
+/// BM25 倒排索引
+#[derive(Debug, Default)]
+pub struct Bm25Index {
+    /// 词条 -> (文档序号, 词频)
+    postings: HashMap<String, Vec<(u32, u32)>>,
+    docs: HashMap<u32, IndexedDoc>,
+    ordinals: HashMap<String, u32>,
+    next_ordinal: u32,
+    total_len: u64,
+}
+
+impl Bm25Index {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 索引一个文档（已存在时先移除旧内容），`title_tokens`/`content_tokens` 为分词结果
+    pub fn insert(&mut self, doc_id: &str, title_tokens: &[String], content_tokens: &[String]) {
+        self.remove(doc_id);
+        let mut frequencies: HashMap<&str, u32> = HashMap::new();
+        for token in title_tokens {
+            *frequencies.entry(token).or_insert(0) += TITLE_BOOST;
+        }
+        for token in content_tokens {
+            *frequencies.entry(token).or_insert(0) += 1;
+        }
+        if frequencies.is_empty() {
+            return;
+        }
+
+        let ordinal = self.next_ordinal;
+        self.next_ordinal += 1;
+        let len: u32 = frequencies.values().sum();
+        for (term, tf) in &frequencies {
+            self.postings.entry(term.to_string()).or_default().push((ordinal, *tf));
+        }
+        self.total_len += len as u64;
+        self.ordinals.insert(doc_id.to_string(), ordinal);
+        self.docs.insert(ordinal, IndexedDoc {
+            id: doc_id.to_string(),
+            len,
+            terms: frequencies.into_keys().map(str::to_string).collect(),
+        });
+    }
+
+    /// 移除文档，返回是否存在
+    pub fn remove(&mut self, doc_id: &str) -> bool {
+        let Some(ordinal) = self.ordinals.remove(doc_id) else {
+            return false;
+        };
+        let Some(doc) = self.docs.remove(&ordinal) else {
+            return false;
+        };
+        for term in &doc.terms {
+            if let Some(postings) = self.postings.get_mut(term) {
+                postings.retain(|(o, _)| *o != ordinal);
+                if postings.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_len -= doc.len as u64;
+        true
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    /// 按 BM25 分数降序返回 (文档ID, 分数)，只保留 `accept` 接受的文档
+    pub fn search(&self, query_tokens: &[String], limit: usize, accept: impl Fn(&str) -> bool) -> Vec<(String, f32)> {
+        if self.docs.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+        let doc_count = self.docs.len() as f32;
+        let avg_len = self.total_len as f32 / doc_count;
+
+        let mut terms: Vec<&String> = query_tokens.iter().collect();
+        terms.sort();
+        terms.dedup();
+
+        let mut scores: HashMap<u32, f32> = HashMap::new();
+        for term in terms {
+            let Some(postings) = self.postings.get(term.as_str()) else {
+                continue;
+            };
+            let df = postings.len() as f32;
+            let idf = (1.0 + (doc_count - df + 0.5) / (df + 0.5)).ln();
+            for (ordinal, tf) in postings {
+                let len = self.docs[ordinal].len as f32;
+                let tf = *tf as f32;
+                let norm = tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * len / avg_len));
+                *scores.entry(*ordinal).or_insert(0.0) += idf * norm;
+            }
+        }
+
+        let mut ranked: Vec<(String, f32)> = scores
+            .into_iter()
+            .map(|(ordinal, score)| (self.docs[&ordinal].id.as_str(), score))
+            .filter(|(id, _)| accept(id))
+            .map(|(id, score)| (id.to_string(), score))
+            .collect();
+        // 同分时按ID排序，保证结果稳定
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::text_analyzer::AnalyzerRegistry;
+
+    #[test]
+    fn test_bm25_ranks_rare_terms_and_titles_higher() {
+        let analyzers = AnalyzerRegistry::default();
+        let mut index = Bm25Index::new();
+        let mut add = |id: &str, title: &str, content: &str| {
+            index.insert(id, &analyzers.tokenize(title), &analyzers.tokenize(content));
+        };
+        add("spawn", "tokio::spawn", "Spawns a new asynchronous task on the runtime");
+        add("runtime", "Runtime", "The tokio runtime drives asynchronous tasks");
+        add("mutex", "互斥锁", "异步互斥锁在持有期间可以跨越等待点，适合保护异步任务之间共享的状态");
+        add("channel", "通道", "多生产者单消费者通道，用于异步任务之间传递消息");
+
+        let search = |query: &str| index.search(&analyzers.tokenize(query), 10, |_| true);
+        let results = search("spawn task");
+        assert_eq!(results[0].0, "spawn");
+
+        // 中文按二元组检索
+        let results = search("异步互斥锁");
+        assert_eq!(results[0].0, "mutex");
+        assert!(results.iter().any(|(id, _)| id == "channel"));
+
+        // 过滤和删除
+        assert!(index.search(&analyzers.tokenize("异步互斥锁"), 10, |id| id != "mutex").iter().all(|(id, _)| id != "mutex"));
+        assert!(index.remove("mutex"));
+        assert!(!index.remove("mutex"));
+        assert_eq!(index.len(), 3);
+        assert!(index.search(&analyzers.tokenize("互斥"), 10, |_| true).is_empty());
+    }
+}
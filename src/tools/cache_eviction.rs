@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+
+/// 缓存淘汰策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    /// 最近最少访问的包版本优先淘汰
+    Lru,
+    /// 搜索命中次数最少的包版本优先淘汰（次数相同时按访问时间）
+    LeastSearched,
+}
+
+impl EvictionPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "lru" => Some(EvictionPolicy::Lru),
+            "least_searched" | "lfu" => Some(EvictionPolicy::LeastSearched),
+            _ => None,
+        }
+    }
+}
+
+/// 缓存容量配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEvictionConfig {
+    /// 缓存总大小上限（字节），None表示不限制
+    pub max_total_bytes: Option<u64>,
+    /// 淘汰策略
+    pub policy: EvictionPolicy,
+}
+
+impl Default for CacheEvictionConfig {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: None,
+            policy: EvictionPolicy::Lru,
+        }
+    }
+}
+
+impl CacheEvictionConfig {
+    /// 从环境变量读取配置
+    ///
+    /// - `VECTOR_CACHE_MAX_MB`: 缓存总大小上限（MB）
+    /// - `VECTOR_CACHE_EVICTION_POLICY`: `lru` 或 `least_searched`
+    pub fn from_env() -> Self {
+        let max_total_bytes = std::env::var("VECTOR_CACHE_MAX_MB")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|mb| *mb > 0)
+            .map(|mb| mb * 1024 * 1024);
+        let policy = std::env::var("VECTOR_CACHE_EVICTION_POLICY")
+            .ok()
+            .and_then(|v| EvictionPolicy::parse(&v))
+            .unwrap_or(EvictionPolicy::Lru);
+
+        Self { max_total_bytes, policy }
+    }
+}
+
+/// 单个包版本的使用情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageUsage {
+    /// 最近一次被写入或搜索命中的时间
+    pub last_access: SystemTime,
+    /// 搜索命中次数
+    pub search_hits: u64,
+}
+
+impl Default for PackageUsage {
+    fn default() -> Self {
+        Self {
+            last_access: SystemTime::now(),
+            search_hits: 0,
+        }
+    }
+}
+
+/// 淘汰统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvictionStats {
+    /// 累计淘汰的包版本数
+    pub evicted_package_versions: u64,
+    /// 累计淘汰的文档数
+    pub evicted_documents: u64,
+    /// 累计释放的字节数
+    pub evicted_bytes: u64,
+    /// 最近一次淘汰的包版本
+    pub last_evicted: Vec<String>,
+    /// 最近一次淘汰的时间
+    pub last_eviction_at: Option<SystemTime>,
+}
+
+/// 包版本键：`语言/包名/版本`
+pub fn package_version_key(language: &str, package_name: &str, version: &str) -> String {
+    format!("{}/{}/{}", language, package_name, version)
+}
+
+/// 拆分包版本键为 (语言, 包名, 版本)，包名中可以含 `/`（如 `@types/node`）
+pub fn split_package_version_key(key: &str) -> Option<(&str, &str, &str)> {
+    let (language, rest) = key.split_once('/')?;
+    let (package_name, version) = rest.rsplit_once('/')?;
+    Some((language, package_name, version))
+}
+
+/// 选择需要淘汰的包版本，使剩余总大小不超过上限
+///
+/// `protected` 中的包版本（例如刚写入的）不会被选中。
+pub fn select_eviction_victims(
+    package_bytes: &HashMap<String, u64>,
+    usage: &HashMap<String, PackageUsage>,
+    max_total_bytes: u64,
+    policy: EvictionPolicy,
+    protected: &[String],
+) -> Vec<String> {
+    let mut total_bytes: u64 = package_bytes.values().sum();
+    if total_bytes <= max_total_bytes {
+        return Vec::new();
+    }
+
+    let mut candidates: Vec<(&String, u64, SystemTime, u64)> = package_bytes
+        .iter()
+        .filter(|(key, _)| !protected.contains(key))
+        .map(|(key, bytes)| {
+            let (last_access, hits) = usage
+                .get(key)
+                .map(|u| (u.last_access, u.search_hits))
+                .unwrap_or((SystemTime::UNIX_EPOCH, 0));
+            (key, *bytes, last_access, hits)
+        })
+        .collect();
+
+    match policy {
+        EvictionPolicy::Lru => candidates.sort_by(|a, b| a.2.cmp(&b.2).then(a.0.cmp(b.0))),
+        EvictionPolicy::LeastSearched => candidates.sort_by(|a, b| a.3.cmp(&b.3).then(a.2.cmp(&b.2)).then(a.0.cmp(b.0))),
+    }
+
+    let mut victims = Vec::new();
+    for (key, bytes, _, _) in candidates {
+        if total_bytes <= max_total_bytes {
+            break;
+        }
+        total_bytes = total_bytes.saturating_sub(bytes);
+        victims.push(key.clone());
+    }
+    victims
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn usage_at(seconds: u64, hits: u64) -> PackageUsage {
+        PackageUsage {
+            last_access: SystemTime::UNIX_EPOCH + Duration::from_secs(seconds),
+            search_hits: hits,
+        }
+    }
+
+    #[test]
+    fn test_lru_evicts_oldest_until_under_limit() {
+        let bytes = HashMap::from([
+            ("rust/a/1".to_string(), 100),
+            ("rust/b/1".to_string(), 100),
+            ("rust/c/1".to_string(), 100),
+        ]);
+        let usage = HashMap::from([
+            ("rust/a/1".to_string(), usage_at(30, 0)),
+            ("rust/b/1".to_string(), usage_at(10, 5)),
+            ("rust/c/1".to_string(), usage_at(20, 1)),
+        ]);
+
+        let victims = select_eviction_victims(&bytes, &usage, 150, EvictionPolicy::Lru, &[]);
+        assert_eq!(victims, vec!["rust/b/1".to_string(), "rust/c/1".to_string()]);
+
+        let victims = select_eviction_victims(&bytes, &usage, 200, EvictionPolicy::LeastSearched, &[]);
+        assert_eq!(victims, vec!["rust/a/1".to_string()]);
+    }
+
+    #[test]
+    fn test_protected_and_under_limit() {
+        let bytes = HashMap::from([("rust/a/1".to_string(), 100), ("rust/b/1".to_string(), 100)]);
+        let usage = HashMap::new();
+
+        assert!(select_eviction_victims(&bytes, &usage, 500, EvictionPolicy::Lru, &[]).is_empty());
+
+        let victims = select_eviction_victims(&bytes, &usage, 100, EvictionPolicy::Lru, &["rust/a/1".to_string()]);
+        assert_eq!(victims, vec!["rust/b/1".to_string()]);
+    }
+}
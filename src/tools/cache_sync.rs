@@ -0,0 +1,619 @@
+//! 向量缓存的差量同步
+//!
+//! 团队成员通过共享位置（文件共享目录、WebDAV 或 S3）同步各自的向量缓存，避免每台机器重复抓取文档。
+//! 共享位置的布局：
+//! - `manifest.json`：包版本 -> 内容摘要、文档数、更新时间；
+//! - `packs/<摘要>.json`：按内容寻址的文档包（格式见 `doc_packs`）。
+//!
+//! 内容摘要只由文档本身计算（不含抓取时间等随机器变化的元数据），两边内容一致的包版本不会传输。
+//! `sync push` 上传本地有变化的包版本，`sync pull` 下载远端有变化的包版本并替换本地全局层中的旧文档。
+//! 同步只增改不删除：本地清除的包版本不会从远端删除。同一个包版本两边都有修改时以最后推送的为准。
+//!
+//! 远端凭据从环境变量读取：WebDAV 使用 `GRAPE_SYNC_TOKEN`（Bearer）或 `GRAPE_SYNC_USERNAME` /
+//! `GRAPE_SYNC_PASSWORD`（Basic）；S3 使用 `AWS_ACCESS_KEY_ID`、`AWS_SECRET_ACCESS_KEY`、
+//! `AWS_SESSION_TOKEN`、`AWS_REGION`，兼容 S3 的服务（如 MinIO）用 `AWS_ENDPOINT_URL` 指定地址。
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use reqwest::{Method, RequestBuilder, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::errors::MCPError;
+use crate::tools::cache_eviction::package_version_key;
+use crate::tools::crypto::{hmac_sha256, to_hex};
+use crate::tools::doc_packs::{DocPack, DocPackTrust};
+use ed25519_dalek::SigningKey;
+use crate::tools::provenance::FETCHED_AT_METADATA_KEY;
+use crate::tools::search_filter::CREATED_AT_METADATA_KEY;
+use crate::tools::vector_docs_tool::{DocumentRecord, VectorDocsTool};
+
+/// 同步清单格式版本
+pub const SYNC_MANIFEST_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_KEY: &str = "manifest.json";
+
+/// 不参与内容摘要的元数据：同样的文档在不同机器上入库时取值不同
+const VOLATILE_METADATA_KEYS: &[&str] = &[CREATED_AT_METADATA_KEY, FETCHED_AT_METADATA_KEY];
+
+/// 共享位置上的同步清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncManifest {
+    pub format_version: u32,
+    /// 向量与嵌入模型绑定，使用不同模型的缓存不能同步
+    pub embedding_model: String,
+    pub updated_at: DateTime<Utc>,
+    /// 包版本键（语言/包名/版本）-> 条目
+    #[serde(default)]
+    pub packages: BTreeMap<String, SyncEntry>,
+}
+
+/// 同步清单中的包版本
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncEntry {
+    pub language: String,
+    pub package_name: String,
+    pub version: String,
+    /// 内容摘要，同时是文档包在共享位置上的文件名
+    pub digest: String,
+    pub document_count: usize,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SyncManifest {
+    fn new(embedding_model: &str) -> Self {
+        Self {
+            format_version: SYNC_MANIFEST_FORMAT_VERSION,
+            embedding_model: embedding_model.to_string(),
+            updated_at: Utc::now(),
+            packages: BTreeMap::new(),
+        }
+    }
+
+    fn ensure_compatible(&self, embedding_model: &str) -> Result<()> {
+        if self.format_version > SYNC_MANIFEST_FORMAT_VERSION {
+            return Err(anyhow!(
+                "不支持的同步清单格式版本: {} (当前支持 {})",
+                self.format_version, SYNC_MANIFEST_FORMAT_VERSION
+            ));
+        }
+        if self.embedding_model != embedding_model {
+            return Err(anyhow!(
+                "共享位置的缓存使用嵌入模型 {}，与当前模型 {} 不一致，无法同步",
+                self.embedding_model, embedding_model
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 同步结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub remote: String,
+    /// 已上传或下载的包版本
+    pub transferred: Vec<String>,
+    /// 两边内容一致、跳过的包版本数
+    pub unchanged: usize,
+    /// 同步失败的包版本及原因
+    pub failed: Vec<String>,
+    pub bytes_transferred: u64,
+}
+
+/// 包版本的内容摘要：按文档ID排序，依次计入ID、标题、类型、内容和元数据（不含嵌入向量）
+pub fn package_digest(documents: &[DocumentRecord]) -> String {
+    let mut sorted: Vec<&DocumentRecord> = documents.iter().collect();
+    sorted.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut hasher = Sha256::new();
+    let mut field = |value: &str| {
+        hasher.update((value.len() as u64).to_le_bytes());
+        hasher.update(value.as_bytes());
+    };
+    for doc in sorted {
+        field(&doc.id);
+        field(&doc.title);
+        field(&doc.doc_type);
+        field(&doc.content);
+        let metadata: BTreeMap<&String, &String> = doc.metadata.iter()
+            .filter(|(key, _)| !VOLATILE_METADATA_KEYS.contains(&key.as_str()))
+            .collect();
+        for (key, value) in metadata {
+            field(key);
+            field(value);
+        }
+    }
+    to_hex(&hasher.finalize())
+}
+
+fn pack_key(digest: &str) -> String {
+    format!("packs/{}.json", digest)
+}
+
+/// 同步的共享位置
+pub enum SyncRemote {
+    /// 本地目录或挂载的文件共享
+    Directory(PathBuf),
+    WebDav(WebDavRemote),
+    S3(S3Remote),
+}
+
+impl SyncRemote {
+    /// 解析共享位置：`s3://桶/前缀`、`http(s)://`（WebDAV）、`file://` 或本地路径
+    pub fn parse(location: &str) -> Result<Self> {
+        let location = location.trim();
+        if location.is_empty() {
+            return Err(MCPError::InvalidParameter("同步位置不能为空".to_string()).into());
+        }
+        if let Some(rest) = location.strip_prefix("s3://") {
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            return Ok(SyncRemote::S3(S3Remote::from_env(bucket, prefix)?));
+        }
+        if location.starts_with("http://") || location.starts_with("https://") {
+            return Ok(SyncRemote::WebDav(WebDavRemote::new(location)?));
+        }
+        if location.starts_with("file://") {
+            let path = Url::parse(location).ok()
+                .and_then(|url| url.to_file_path().ok())
+                .ok_or_else(|| MCPError::InvalidParameter(format!("无效的文件地址: {}", location)))?;
+            return Ok(SyncRemote::Directory(path));
+        }
+        Ok(SyncRemote::Directory(PathBuf::from(location)))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self {
+            SyncRemote::Directory(root) => match tokio::fs::read(root.join(key)).await {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(anyhow!("读取 {} 失败: {}", root.join(key).display(), e)),
+            },
+            SyncRemote::WebDav(remote) => http_get(remote.request(Method::GET, key)?).await,
+            SyncRemote::S3(remote) => http_get(remote.request(Method::GET, key, &[])?).await,
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        match self {
+            SyncRemote::Directory(root) => Ok(tokio::fs::try_exists(root.join(key)).await?),
+            SyncRemote::WebDav(remote) => http_exists(remote.request(Method::HEAD, key)?).await,
+            SyncRemote::S3(remote) => http_exists(remote.request(Method::HEAD, key, &[])?).await,
+        }
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        match self {
+            SyncRemote::Directory(root) => {
+                let path = root.join(key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                // 先写临时文件再改名，其他机器不会读到写了一半的文件
+                let tmp = path.with_extension("tmp");
+                tokio::fs::write(&tmp, body).await?;
+                tokio::fs::rename(&tmp, &path).await?;
+                Ok(())
+            }
+            SyncRemote::WebDav(remote) => {
+                if key.contains('/') {
+                    remote.ensure_collections().await?;
+                }
+                http_put(remote.request(Method::PUT, key)?.body(body)).await
+            }
+            SyncRemote::S3(remote) => {
+                let request = remote.request(Method::PUT, key, &body)?;
+                http_put(request.body(body)).await
+            }
+        }
+    }
+
+    async fn read_manifest(&self) -> Result<Option<SyncManifest>> {
+        match self.get(MANIFEST_KEY).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes).map_err(|e| anyhow!("同步清单格式无效: {}", e))?)),
+            None => Ok(None),
+        }
+    }
+}
+
+async fn http_get(request: RequestBuilder) -> Result<Option<Vec<u8>>> {
+    let response = request.send().await?;
+    match response.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => Ok(Some(response.bytes().await?.to_vec())),
+        status => Err(anyhow!("读取共享位置失败: {} ({})", response.url(), status)),
+    }
+}
+
+async fn http_exists(request: RequestBuilder) -> Result<bool> {
+    let response = request.send().await?;
+    match response.status() {
+        StatusCode::NOT_FOUND => Ok(false),
+        status if status.is_success() => Ok(true),
+        status => Err(anyhow!("查询共享位置失败: {} ({})", response.url(), status)),
+    }
+}
+
+async fn http_put(request: RequestBuilder) -> Result<()> {
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("写入共享位置失败: {} ({})", response.url(), response.status()));
+    }
+    Ok(())
+}
+
+/// WebDAV 共享位置
+pub struct WebDavRemote {
+    base: Url,
+    client: reqwest::Client,
+    collections_ready: AtomicBool,
+}
+
+impl WebDavRemote {
+    fn new(location: &str) -> Result<Self> {
+        let mut base = Url::parse(location).map_err(|e| MCPError::InvalidParameter(format!("无效的 WebDAV 地址 {}: {}", location, e)))?;
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        Ok(Self { base, client: reqwest::Client::new(), collections_ready: AtomicBool::new(false) })
+    }
+
+    fn request(&self, method: Method, key: &str) -> Result<RequestBuilder> {
+        let url = self.base.join(key)?;
+        let request = self.client.request(method, url);
+        Ok(match (std::env::var("GRAPE_SYNC_TOKEN"), std::env::var("GRAPE_SYNC_USERNAME")) {
+            (Ok(token), _) if !token.is_empty() => request.bearer_auth(token),
+            (_, Ok(username)) if !username.is_empty() => request.basic_auth(username, std::env::var("GRAPE_SYNC_PASSWORD").ok()),
+            _ => request,
+        })
+    }
+
+    /// 创建存放文档包的集合（目录），已存在时服务器返回 405
+    async fn ensure_collections(&self) -> Result<()> {
+        if self.collections_ready.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let mkcol = Method::from_bytes(b"MKCOL").expect("MKCOL 是有效的方法名");
+        let response = self.request(mkcol, "packs/")?.send().await?;
+        if !(response.status().is_success() || response.status() == StatusCode::METHOD_NOT_ALLOWED) {
+            return Err(anyhow!("创建 WebDAV 目录失败: {} ({})", response.url(), response.status()));
+        }
+        self.collections_ready.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// S3（或兼容 S3 的对象存储）共享位置，请求使用 AWS Signature V4 签名
+pub struct S3Remote {
+    bucket: String,
+    prefix: String,
+    region: String,
+    endpoint: Option<Url>,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl S3Remote {
+    fn from_env(bucket: &str, prefix: &str) -> Result<Self> {
+        if bucket.is_empty() {
+            return Err(MCPError::InvalidParameter("S3 同步位置缺少桶名".to_string()).into());
+        }
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let (Some(access_key_id), Some(secret_access_key)) = (env("AWS_ACCESS_KEY_ID"), env("AWS_SECRET_ACCESS_KEY")) else {
+            return Err(MCPError::AuthorizationError("S3 同步需要设置 AWS_ACCESS_KEY_ID 和 AWS_SECRET_ACCESS_KEY".to_string()).into());
+        };
+        let endpoint = env("AWS_ENDPOINT_URL")
+            .map(|url| Url::parse(&url).map_err(|e| MCPError::InvalidParameter(format!("无效的 AWS_ENDPOINT_URL {}: {}", url, e))))
+            .transpose()?;
+        Ok(Self {
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            region: env("AWS_REGION").or_else(|| env("AWS_DEFAULT_REGION")).unwrap_or_else(|| "us-east-1".to_string()),
+            endpoint,
+            access_key_id,
+            secret_access_key,
+            session_token: env("AWS_SESSION_TOKEN"),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// 对象地址：指定了服务地址时使用路径风格，否则使用 AWS 的虚拟主机风格
+    fn object_url(&self, key: &str) -> Result<Url> {
+        let mut url = match &self.endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => Url::parse(&format!("https://{}.s3.{}.amazonaws.com/", self.bucket, self.region))?,
+        };
+        {
+            let mut segments = url.path_segments_mut().map_err(|_| anyhow!("无效的 S3 服务地址"))?;
+            segments.pop_if_empty();
+            if self.endpoint.is_some() {
+                segments.push(&self.bucket);
+            }
+            segments.extend(self.prefix.split('/').filter(|s| !s.is_empty()));
+            segments.extend(key.split('/'));
+        }
+        Ok(url)
+    }
+
+    fn request(&self, method: Method, key: &str, payload: &[u8]) -> Result<RequestBuilder> {
+        let url = self.object_url(key)?;
+        let headers = self.sign(method.as_str(), &url, payload, Utc::now());
+        let mut request = self.client.request(method, url);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        Ok(request)
+    }
+
+    /// 计算 Signature V4 请求头（不含 host，由 HTTP 客户端按地址设置）
+    fn sign(&self, method: &str, url: &Url, payload: &[u8], now: DateTime<Utc>) -> Vec<(String, String)> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = to_hex(&Sha256::digest(payload));
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let mut headers = vec![
+            ("host".to_string(), host),
+            ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+            ("x-amz-date".to_string(), amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+        let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, url.path(), url.query().unwrap_or(""), canonical_headers, signed_headers, payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, to_hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&self.secret_access_key, &date, &self.region, "s3");
+        let signature = to_hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        headers.remove(0);
+        headers.push((
+            "authorization".to_string(),
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.access_key_id, scope, signed_headers, signature
+            ),
+        ));
+        headers
+    }
+}
+
+/// Signature V4 的派生签名密钥
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> [u8; 32] {
+    let k_date = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let k_region = hmac_sha256(&k_date, region.as_bytes());
+    let k_service = hmac_sha256(&k_region, service.as_bytes());
+    hmac_sha256(&k_service, b"aws4_request")
+}
+
+/// 本地已缓存包版本的内容摘要
+fn local_entries(tool: &VectorDocsTool) -> Result<HashMap<String, SyncEntry>> {
+    let mut entries = HashMap::new();
+    for package in tool.list_cached_packages()? {
+        let documents = tool.package_documents(&package.language, &package.package_name, &package.version, None)?;
+        if documents.is_empty() {
+            continue;
+        }
+        let entry = SyncEntry {
+            digest: package_digest(&documents),
+            document_count: documents.len(),
+            updated_at: Utc::now(),
+            language: package.language,
+            package_name: package.package_name,
+            version: package.version,
+        };
+        entries.insert(package_version_key(&entry.language, &entry.package_name, &entry.version), entry);
+    }
+    Ok(entries)
+}
+
+/// 上传本地有变化的包版本，最后更新清单；`signer` 用于给上传的文档包签名
+pub async fn push(tool: &VectorDocsTool, remote: &SyncRemote, remote_name: &str, signer: Option<&SigningKey>) -> Result<SyncReport> {
+    let model = tool.model_name().to_string();
+    let manifest = remote.read_manifest().await?.unwrap_or_else(|| SyncManifest::new(&model));
+    manifest.ensure_compatible(&model)?;
+
+    let mut report = SyncReport { remote: remote_name.to_string(), ..SyncReport::default() };
+    let mut changed = Vec::new();
+    let mut local: Vec<(String, SyncEntry)> = local_entries(tool)?.into_iter().collect();
+    local.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (key, entry) in local {
+        if manifest.packages.get(&key).map_or(false, |remote_entry| remote_entry.digest == entry.digest) {
+            report.unchanged += 1;
+            continue;
+        }
+        let blob = pack_key(&entry.digest);
+        let uploaded = async {
+            // 同样内容的文档包可能已由其他机器上传
+            if !remote.exists(&blob).await? {
+                let pack = tool.export_doc_pack(&entry.language, &entry.package_name, &entry.version, signer)?;
+                report.bytes_transferred += pack.len() as u64;
+                remote.put(&blob, pack.into_bytes()).await?;
+            }
+            Ok::<(), anyhow::Error>(())
+        }
+        .await;
+        match uploaded {
+            Ok(()) => {
+                report.transferred.push(key.clone());
+                changed.push((key, entry));
+            }
+            Err(e) => report.failed.push(format!("{}: {}", key, e)),
+        }
+    }
+
+    if !changed.is_empty() {
+        // 写入前重新读取清单再合并，尽量保留其他机器在此期间推送的条目
+        let mut latest = remote.read_manifest().await?.unwrap_or(manifest);
+        latest.packages.extend(changed);
+        latest.updated_at = Utc::now();
+        remote.put(MANIFEST_KEY, serde_json::to_vec_pretty(&latest)?).await?;
+    }
+
+    tracing::info!(
+        "缓存同步推送到 {}: 上传 {} 个包版本，{} 个未变化，{} 个失败",
+        remote_name, report.transferred.len(), report.unchanged, report.failed.len()
+    );
+    Ok(report)
+}
+
+/// 下载远端有变化的包版本，替换本地全局层中的旧文档；文档包按 `trust` 校验签名
+pub async fn pull(tool: &VectorDocsTool, remote: &SyncRemote, remote_name: &str, trust: &DocPackTrust) -> Result<SyncReport> {
+    let Some(manifest) = remote.read_manifest().await? else {
+        return Err(MCPError::NotFound(format!("{} 上没有同步清单，请先执行 sync push", remote_name)).into());
+    };
+    manifest.ensure_compatible(tool.model_name())?;
+
+    let local: HashMap<String, String> = local_entries(tool)?.into_iter().map(|(key, entry)| (key, entry.digest)).collect();
+    let mut report = SyncReport { remote: remote_name.to_string(), ..SyncReport::default() };
+
+    for (key, entry) in &manifest.packages {
+        if local.get(key) == Some(&entry.digest) {
+            report.unchanged += 1;
+            continue;
+        }
+        let imported = async {
+            let raw = remote.get(&pack_key(&entry.digest)).await?
+                .ok_or_else(|| anyhow!("共享位置缺少文档包 {}", pack_key(&entry.digest)))?;
+            let pack = DocPack::from_json(&String::from_utf8(raw)?, trust)?;
+            let manifest = &pack.manifest;
+            if (&manifest.language, &manifest.package_name, &manifest.version) != (&entry.language, &entry.package_name, &entry.version) {
+                return Err(anyhow!("文档包内容与清单条目不一致"));
+            }
+            report.bytes_transferred += pack.documents.iter().map(|doc| doc.content.len() as u64).sum::<u64>();
+            tool.import_loaded_pack(pack, true)
+        }
+        .await;
+        match imported {
+            Ok(_) => report.transferred.push(key.clone()),
+            Err(e) => report.failed.push(format!("{}: {}", key, e)),
+        }
+    }
+
+    tracing::info!(
+        "缓存同步从 {} 拉取: 下载 {} 个包版本，{} 个未变化，{} 个失败",
+        remote_name, report.transferred.len(), report.unchanged, report.failed.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::base::MCPTool;
+    use crate::tools::embedder::testing::MockEmbedder;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn record(id: &str, content: &str, created_at: &str) -> DocumentRecord {
+        DocumentRecord {
+            id: id.to_string(),
+            content: content.to_string(),
+            title: id.to_string(),
+            language: "rust".to_string(),
+            package_name: "serde".to_string(),
+            version: "1.0.0".to_string(),
+            doc_type: "api".to_string(),
+            metadata: HashMap::from([(CREATED_AT_METADATA_KEY.to_string(), created_at.to_string())]),
+            embedding: vec![0.1, 0.2],
+        }
+    }
+
+    #[test]
+    fn test_package_digest_ignores_order_and_ingest_time() {
+        let a = vec![record("a", "Serialize", "2024-01-01T00:00:00Z"), record("b", "Deserialize", "2024-01-01T00:00:00Z")];
+        let b = vec![record("b", "Deserialize", "2025-06-01T00:00:00Z"), record("a", "Serialize", "2025-06-01T00:00:00Z")];
+        assert_eq!(package_digest(&a), package_digest(&b));
+
+        let changed = vec![record("a", "Serialize trait", "2024-01-01T00:00:00Z"), record("b", "Deserialize", "2024-01-01T00:00:00Z")];
+        assert_ne!(package_digest(&a), package_digest(&changed));
+    }
+
+    #[test]
+    fn test_s3_signature() {
+        // AWS Signature V4 文档中的派生密钥示例
+        assert_eq!(
+            to_hex(&signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam")),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+
+        let remote = S3Remote {
+            bucket: "team-cache".to_string(),
+            prefix: "grape/".to_string(),
+            region: "eu-west-1".to_string(),
+            endpoint: None,
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".to_string(),
+            session_token: None,
+            client: reqwest::Client::new(),
+        };
+        let url = remote.object_url("packs/abc.json").unwrap();
+        assert_eq!(url.as_str(), "https://team-cache.s3.eu-west-1.amazonaws.com/grape/packs/abc.json");
+
+        let now = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let headers: HashMap<String, String> = remote.sign("GET", &url, b"", now).into_iter().collect();
+        assert_eq!(headers["x-amz-date"], "20240501T120000Z");
+        assert_eq!(headers["x-amz-content-sha256"], "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert!(headers["authorization"].starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240501/eu-west-1/s3/aws4_request, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature="
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_push_and_pull_transfer_only_changed_packages() -> Result<()> {
+        let (dir_a, dir_b, shared) = (tempfile::TempDir::new()?, tempfile::TempDir::new()?, tempfile::TempDir::new()?);
+        let laptop_a = VectorDocsTool::open_local(dir_a.path().to_path_buf())?.with_embedder(Arc::new(MockEmbedder::new(32)));
+        let laptop_b = VectorDocsTool::open_local(dir_b.path().to_path_buf())?.with_embedder(Arc::new(MockEmbedder::new(32)));
+        let remote = SyncRemote::parse(shared.path().to_str().unwrap())?;
+        let name = "shared";
+        let signer = SigningKey::from_bytes(&[3u8; 32]);
+        let trust = DocPackTrust::trusting(signer.verifying_key());
+
+        let store = |id: &str, package: &str, content: &str| json!({
+            "action": "store", "id": id, "title": id, "content": content,
+            "language": "rust", "package_name": package, "version": "1.0.0"
+        });
+        laptop_a.execute(store("serde-1", "serde", "Serialize 把数据结构序列化。")).await?;
+        laptop_a.execute(store("tokio-1", "tokio", "tokio::spawn 启动异步任务。")).await?;
+
+        let report = push(&laptop_a, &remote, name, Some(&signer)).await?;
+        assert_eq!(report.transferred, vec!["rust/serde/1.0.0", "rust/tokio/1.0.0"]);
+        assert!(report.failed.is_empty());
+        assert_eq!(push(&laptop_a, &remote, name, Some(&signer)).await?.unchanged, 2);
+
+        // 不信任签名者时拒绝导入
+        let report = pull(&laptop_b, &remote, name, &DocPackTrust::default()).await?;
+        assert_eq!(report.failed.len(), 2);
+
+        let report = pull(&laptop_b, &remote, name, &trust).await?;
+        assert_eq!(report.transferred.len(), 2);
+        assert_eq!(laptop_b.package_documents("rust", "serde", "1.0.0", None).unwrap().len(), 1);
+        assert_eq!(pull(&laptop_b, &remote, name, &trust).await?.unchanged, 2);
+
+        // 只有变化的包版本会再次传输，拉取后替换旧内容
+        laptop_a.execute(store("serde-2", "serde", "Deserialize 从数据格式反序列化。")).await?;
+        let report = push(&laptop_a, &remote, name, Some(&signer)).await?;
+        assert_eq!((report.transferred, report.unchanged), (vec!["rust/serde/1.0.0".to_string()], 1));
+        let report = pull(&laptop_b, &remote, name, &trust).await?;
+        assert_eq!((report.transferred, report.unchanged), (vec!["rust/serde/1.0.0".to_string()], 1));
+        assert_eq!(laptop_b.package_documents("rust", "serde", "1.0.0", None).unwrap().len(), 2);
+        Ok(())
+    }
+}
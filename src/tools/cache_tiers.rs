@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+/// 缓存层级
+///
+/// - `Global`: 按用户共享的全局文档缓存（按 包名/版本 区分），跨项目复用，避免每个仓库重复抓取同一个包的文档
+/// - `Workspace`: 当前工作区的本地覆盖层，保存项目本地文档及手工存入的文档
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheTier {
+    Global,
+    Workspace,
+}
+
+impl CacheTier {
+    /// 从参数字符串解析缓存层级
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "global" | "shared" | "user" => Some(CacheTier::Global),
+            "workspace" | "local" | "project" => Some(CacheTier::Workspace),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheTier::Global => "global",
+            CacheTier::Workspace => "workspace",
+        }
+    }
+
+    /// 根据文档的包信息推断默认层级：属于某个具体包的文档进入全局缓存，其余留在工作区
+    pub fn infer(package_name: &str) -> Self {
+        let trimmed = package_name.trim();
+        if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("unknown") {
+            CacheTier::Workspace
+        } else {
+            CacheTier::Global
+        }
+    }
+}
+
+/// 两级缓存的存储路径
+#[derive(Debug, Clone)]
+pub struct CacheTierPaths {
+    /// 全局（按用户）缓存目录
+    pub global_dir: PathBuf,
+    /// 工作区覆盖层目录
+    pub workspace_dir: PathBuf,
+}
+
+impl CacheTierPaths {
+    /// 从环境变量解析缓存路径
+    ///
+    /// - `GRAPE_GLOBAL_CACHE_DIR`: 全局缓存目录，默认 `~/.grape-mcp-devtools/global_cache`
+    /// - `VECTOR_STORAGE_PATH`: 工作区缓存目录，默认 `.mcp_vector_data`
+    pub fn from_env() -> Self {
+        let workspace_dir = std::env::var("VECTOR_STORAGE_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(".mcp_vector_data"));
+
+        let global_dir = std::env::var("GRAPE_GLOBAL_CACHE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| Self::default_global_dir());
+
+        Self { global_dir, workspace_dir }
+    }
+
+    /// 全局层和工作区层共用同一目录的单层缓存
+    pub fn single(dir: PathBuf) -> Self {
+        Self { global_dir: dir.clone(), workspace_dir: dir }
+    }
+
+    /// 默认的用户级全局缓存目录
+    pub fn default_global_dir() -> PathBuf {
+        let home = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| std::env::temp_dir());
+        home.join(".grape-mcp-devtools").join("global_cache")
+    }
+
+    /// 两个层级是否指向同一目录（此时退化为单层缓存）
+    pub fn is_single_tier(&self) -> bool {
+        let global = self.global_dir.canonicalize().unwrap_or_else(|_| self.global_dir.clone());
+        let workspace = self.workspace_dir.canonicalize().unwrap_or_else(|_| self.workspace_dir.clone());
+        global == workspace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tier_parse_and_infer() {
+        assert_eq!(CacheTier::parse("global"), Some(CacheTier::Global));
+        assert_eq!(CacheTier::parse("Local"), Some(CacheTier::Workspace));
+        assert_eq!(CacheTier::parse("other"), None);
+
+        assert_eq!(CacheTier::infer("tokio"), CacheTier::Global);
+        assert_eq!(CacheTier::infer("unknown"), CacheTier::Workspace);
+        assert_eq!(CacheTier::infer("  "), CacheTier::Workspace);
+    }
+
+    #[test]
+    fn test_single_tier_detection() {
+        let paths = CacheTierPaths {
+            global_dir: PathBuf::from("/tmp/grape_same_dir"),
+            workspace_dir: PathBuf::from("/tmp/grape_same_dir"),
+        };
+        assert!(paths.is_single_tier());
+    }
+}
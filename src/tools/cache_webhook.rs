@@ -0,0 +1,137 @@
+//! 后台缓存完成通知
+//!
+//! 后台缓存每处理完一个包版本，向配置的地址 POST 一个 JSON 事件（包含完整的抓取报告），
+//! 便于外部自动化或聊天机器人在新文档可用（或抓取失败）时做出反应：
+//!
+//! ```json
+//! {"event": "package_cached", "timestamp": "...", "report": {"language": "rust", ...}}
+//! ```
+//!
+//! 配置了密钥时请求带 `X-Grape-Signature: sha256=<hex>`，为请求体的 HMAC-SHA256，
+//! 接收方可据此校验来源。发送失败只记录警告，不影响缓存流程。
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::config::WebhookConfig;
+use crate::mcp::correlation::Correlated;
+use crate::tools::crawl_report::{CrawlOutcome, CrawlReport};
+use crate::tools::crypto::{hmac_sha256, to_hex};
+
+/// 事件类型请求头
+pub const EVENT_HEADER: &str = "X-Grape-Event";
+
+/// 签名请求头
+pub const SIGNATURE_HEADER: &str = "X-Grape-Signature";
+
+/// 包版本缓存完成
+pub const PACKAGE_CACHED_EVENT: &str = "package_cached";
+
+/// 包版本缓存失败
+pub const PACKAGE_FAILED_EVENT: &str = "package_failed";
+
+/// 缓存事件 webhook
+pub struct CacheWebhook {
+    url: String,
+    secret: Option<String>,
+    notify_failures: bool,
+    client: Client,
+}
+
+impl CacheWebhook {
+    /// 按配置创建，未配置地址时返回 None
+    pub fn from_config(config: &WebhookConfig) -> Option<Self> {
+        let url = config.url.clone()?;
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        Some(Self {
+            url,
+            secret: config.secret.clone(),
+            notify_failures: config.notify_failures,
+            client,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn event_name(report: &CrawlReport) -> &'static str {
+        match report.outcome {
+            CrawlOutcome::Success => PACKAGE_CACHED_EVENT,
+            CrawlOutcome::Failed => PACKAGE_FAILED_EVENT,
+        }
+    }
+
+    /// 事件请求体
+    pub fn payload(report: &CrawlReport) -> Value {
+        json!({
+            "event": Self::event_name(report),
+            "timestamp": Utc::now(),
+            "report": report,
+        })
+    }
+
+    /// 请求体签名，未配置密钥时为 None
+    pub fn signature(&self, body: &[u8]) -> Option<String> {
+        self.secret.as_ref().map(|secret| format!("sha256={}", to_hex(&hmac_sha256(secret.as_bytes(), body))))
+    }
+
+    /// 发送一个包版本的缓存事件；关闭失败通知时失败的报告直接跳过
+    pub async fn notify(&self, report: &CrawlReport) -> Result<()> {
+        if report.outcome == CrawlOutcome::Failed && !self.notify_failures {
+            return Ok(());
+        }
+        let body = serde_json::to_vec(&Self::payload(report))?;
+        let mut request = self.client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, Self::event_name(report))
+            .correlated();
+        if let Some(signature) = self.signature(&body) {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+        let response = request.body(body).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("webhook {} 返回 {}", self.url, response.status()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_and_signature() {
+        assert!(CacheWebhook::from_config(&WebhookConfig::default()).is_none());
+
+        let webhook = CacheWebhook::from_config(&WebhookConfig {
+            url: Some("http://127.0.0.1:9/hooks".to_string()),
+            secret: Some("key".to_string()),
+            ..WebhookConfig::default()
+        }).unwrap();
+
+        let mut report = CrawlReport::started("rust", "tokio", "1.38.0");
+        report.fragments_stored = 42;
+        report.succeed(Duration::from_secs(2));
+        let payload = CacheWebhook::payload(&report);
+        assert_eq!(payload["event"], PACKAGE_CACHED_EVENT);
+        assert_eq!(payload["report"]["fragments_stored"], 42);
+
+        report.fail(Duration::from_secs(2), "超时".to_string());
+        assert_eq!(CacheWebhook::payload(&report)["event"], PACKAGE_FAILED_EVENT);
+
+        // 常用的 HMAC-SHA256 示例向量
+        assert_eq!(
+            webhook.signature(b"The quick brown fox jumps over the lazy dog").unwrap(),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+}
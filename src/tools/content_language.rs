@@ -0,0 +1,242 @@
+//! 入库时的语言检测
+//!
+//! 为文档片段识别自然语言（en/zh/ja/ko/ru/de/fr/es）和代码块的编程语言，
+//! 写入元数据后检索可以按语言过滤，翻译层也能据此判断是否需要翻译。
+
+use std::collections::{BTreeSet, HashMap};
+
+/// 元数据键：正文的自然语言（ISO 639-1）
+pub const NATURAL_LANGUAGE_KEY: &str = "natural_language";
+/// 元数据键：代码块的编程语言，逗号分隔
+pub const CODE_LANGUAGES_KEY: &str = "code_languages";
+
+/// 参与判定的最少字母数，过短的文本不做判定
+const MIN_LETTERS: usize = 12;
+
+/// 拉丁字母语言的高频词，用于区分 en/de/fr/es
+const LATIN_STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "is", "to", "of", "this", "that", "with", "for", "returns", "you", "are"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "mit", "ein", "eine", "wird", "für", "sie"]),
+    ("fr", &["le", "la", "les", "et", "est", "des", "une", "pour", "dans", "qui", "pas", "vous"]),
+    ("es", &["el", "los", "las", "y", "es", "una", "para", "que", "con", "por", "del", "se"]),
+];
+
+/// 检测文本（不含代码块）的自然语言，无法判定时返回 `None`
+pub fn detect_natural_language(text: &str) -> Option<&'static str> {
+    let prose = strip_code_blocks(text);
+
+    let (mut han, mut kana, mut hangul, mut cyrillic, mut latin) = (0usize, 0usize, 0usize, 0usize, 0usize);
+    for c in prose.chars() {
+        match c {
+            '\u{3040}'..='\u{30FF}' => kana += 1,
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => han += 1,
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => hangul += 1,
+            '\u{0400}'..='\u{04FF}' => cyrillic += 1,
+            c if c.is_ascii_alphabetic() || ('\u{00C0}'..='\u{024F}').contains(&c) => latin += 1,
+            _ => {}
+        }
+    }
+
+    // 中日韩文字单字信息量高，按 3 倍权重与拉丁字母比较
+    let cjk = han + kana + hangul;
+    if cjk * 3 >= latin.max(MIN_LETTERS / 3) {
+        return if kana > 0 && kana * 10 >= cjk {
+            Some("ja")
+        } else if hangul > han {
+            Some("ko")
+        } else if han > 0 {
+            Some("zh")
+        } else {
+            None
+        };
+    }
+    if cyrillic > latin && cyrillic >= MIN_LETTERS {
+        return Some("ru");
+    }
+    if latin < MIN_LETTERS {
+        return None;
+    }
+
+    let words: Vec<String> = prose
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    LATIN_STOPWORDS
+        .iter()
+        .map(|(lang, stopwords)| (*lang, words.iter().filter(|w| stopwords.contains(&w.as_str())).count()))
+        .max_by_key(|(_, hits)| *hits)
+        .map(|(lang, hits)| if hits == 0 { "en" } else { lang })
+}
+
+/// 检测文本中代码块的编程语言（去重、排序）
+///
+/// 优先使用围栏代码块的信息串（```rust），没有信息串时按关键字推断。
+pub fn detect_code_languages(text: &str) -> Vec<String> {
+    let mut languages = BTreeSet::new();
+    for (info, body) in fenced_code_blocks(text) {
+        let language = info
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+            .next()
+            .filter(|tag| !tag.is_empty())
+            .map(normalize_code_language)
+            .or_else(|| guess_code_language(&body));
+        if let Some(language) = language {
+            languages.insert(language);
+        }
+    }
+    languages.into_iter().collect()
+}
+
+/// 生成语言相关的元数据项
+pub fn language_metadata(text: &str) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    if let Some(language) = detect_natural_language(text) {
+        metadata.insert(NATURAL_LANGUAGE_KEY.to_string(), language.to_string());
+    }
+    let code_languages = detect_code_languages(text);
+    if !code_languages.is_empty() {
+        metadata.insert(CODE_LANGUAGES_KEY.to_string(), code_languages.join(","));
+    }
+    metadata
+}
+
+/// 元数据是否满足语言过滤条件（条件为 `None` 时不过滤）
+pub fn matches_language_filter(
+    metadata: &HashMap<String, String>,
+    natural_language: Option<&str>,
+    code_language: Option<&str>,
+) -> bool {
+    let natural_ok = natural_language.map_or(true, |wanted| {
+        metadata.get(NATURAL_LANGUAGE_KEY).map_or(false, |l| l.eq_ignore_ascii_case(wanted))
+    });
+    let code_ok = code_language.map_or(true, |wanted| {
+        let wanted = normalize_code_language(wanted);
+        metadata
+            .get(CODE_LANGUAGES_KEY)
+            .map_or(false, |langs| langs.split(',').any(|l| l == wanted))
+    });
+    natural_ok && code_ok
+}
+
+/// 把翻译目标语言（如 `中文`、`English`、`ja`）映射为 ISO 639-1 代码
+pub fn normalize_natural_language(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_lowercase();
+    let code = match name.as_str() {
+        "zh" | "zh-cn" | "zh-tw" | "中文" | "简体中文" | "繁體中文" | "chinese" => "zh",
+        "en" | "英文" | "英语" | "english" => "en",
+        "ja" | "日文" | "日语" | "日本語" | "japanese" => "ja",
+        "ko" | "韩文" | "韩语" | "한국어" | "korean" => "ko",
+        "ru" | "俄文" | "俄语" | "русский" | "russian" => "ru",
+        "de" | "德文" | "德语" | "deutsch" | "german" => "de",
+        "fr" | "法文" | "法语" | "français" | "french" => "fr",
+        "es" | "西班牙文" | "西班牙语" | "español" | "spanish" => "es",
+        _ => return None,
+    };
+    Some(code)
+}
+
+fn normalize_code_language(tag: &str) -> String {
+    let tag = tag.trim().to_lowercase();
+    let normalized = match tag.as_str() {
+        "rs" => "rust",
+        "py" | "python3" => "python",
+        "js" | "jsx" | "node" | "mjs" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "sh" | "bash" | "zsh" | "shell" | "console" => "shell",
+        "golang" => "go",
+        "c++" | "cc" | "hpp" => "cpp",
+        "kt" => "kotlin",
+        "yml" => "yaml",
+        "cs" | "c#" => "csharp",
+        other => other,
+    };
+    normalized.to_string()
+}
+
+/// 没有信息串时按特征关键字推断
+fn guess_code_language(code: &str) -> Option<String> {
+    const SIGNATURES: &[(&str, &[&str])] = &[
+        ("rust", &["fn ", "let mut ", "impl ", "pub struct ", "::new(", "-> Result<"]),
+        ("python", &["def ", "import ", "self.", "elif ", "print("]),
+        ("go", &["func ", "package ", ":= ", "fmt."]),
+        ("typescript", &["interface ", ": string", ": number", "export type "]),
+        ("javascript", &["const ", "function ", "=> {", "require(", "console.log"]),
+        ("java", &["public class ", "public static void", "System.out", "private final "]),
+        ("shell", &["$ ", "cargo ", "npm ", "pip install", "sudo "]),
+    ];
+    SIGNATURES
+        .iter()
+        .map(|(lang, keywords)| (*lang, keywords.iter().filter(|k| code.contains(*k)).count()))
+        .filter(|(_, hits)| *hits >= 2)
+        .max_by_key(|(_, hits)| *hits)
+        .map(|(lang, _)| lang.to_string())
+}
+
+/// 提取围栏代码块 (信息串, 代码)
+fn fenced_code_blocks(text: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("```").or_else(|| trimmed.strip_prefix("~~~")) {
+            match current.take() {
+                Some(block) => blocks.push(block),
+                None => current = Some((rest.trim().to_string(), String::new())),
+            }
+        } else if let Some((_, body)) = current.as_mut() {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    blocks
+}
+
+fn strip_code_blocks(text: &str) -> String {
+    let mut prose = String::with_capacity(text.len());
+    let mut in_code = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        } else if !in_code {
+            prose.push_str(line);
+            prose.push('\n');
+        }
+    }
+    prose
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_natural_language() {
+        assert_eq!(detect_natural_language("Returns the number of elements in the vector, also referred to as its length."), Some("en"));
+        assert_eq!(detect_natural_language("返回向量中元素的数量，也称为向量的长度。"), Some("zh"));
+        assert_eq!(detect_natural_language("ベクター内の要素の数を返します。"), Some("ja"));
+        assert_eq!(detect_natural_language("Gibt die Anzahl der Elemente zurück, die nicht leer ist und mit der Liste"), Some("de"));
+        assert_eq!(detect_natural_language("ok"), None);
+        // 代码块内容不参与自然语言判定
+        assert_eq!(detect_natural_language("示例：\n```rust\nlet v = vec![1, 2, 3];\nassert_eq!(v.len(), 3);\n```\n"), Some("zh"));
+    }
+
+    #[test]
+    fn test_detect_code_languages() {
+        let text = "Example:\n```rs\nfn main() {}\n```\n\n```\ndef handler(event):\n    import json\n```\n\n```js\nconsole.log(1)\n```\n";
+        assert_eq!(detect_code_languages(text), vec!["javascript", "python", "rust"]);
+
+        let metadata = language_metadata(text);
+        assert_eq!(metadata.get(NATURAL_LANGUAGE_KEY), None);
+        assert!(matches_language_filter(&metadata, None, Some("py")));
+        assert!(!matches_language_filter(&metadata, Some("en"), None));
+    }
+
+    #[test]
+    fn test_normalize_natural_language() {
+        assert_eq!(normalize_natural_language("中文"), Some("zh"));
+        assert_eq!(normalize_natural_language("English"), Some("en"));
+        assert_eq!(normalize_natural_language("Klingon"), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! 文档全文的分层存储
+//!
+//! 大型语料的全文全部常驻内存代价很高。内容超过阈值的文档在内存中只保留摘要和标题，
+//! 供候选打分使用；全文写入数据目录下的 `content/`，只有进入最终结果（top-k）或被
+//! 单独读取时才从磁盘加载。
+
+use anyhow::Result;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// 默认的落盘阈值（字节）
+pub const DEFAULT_OFFLOAD_MIN_BYTES: usize = 4096;
+/// 默认的内存摘要长度（字符）
+pub const DEFAULT_SUMMARY_CHARS: usize = 512;
+
+/// 全文分层配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentTierConfig {
+    /// 内容超过该字节数的文档全文落盘，None 表示不落盘（全部常驻内存）
+    pub offload_min_bytes: Option<usize>,
+    /// 落盘文档在内存中保留的摘要长度（字符）
+    pub summary_chars: usize,
+}
+
+impl Default for ContentTierConfig {
+    fn default() -> Self {
+        Self {
+            offload_min_bytes: Some(DEFAULT_OFFLOAD_MIN_BYTES),
+            summary_chars: DEFAULT_SUMMARY_CHARS,
+        }
+    }
+}
+
+impl ContentTierConfig {
+    /// 从环境变量读取配置
+    ///
+    /// - `VECTOR_CONTENT_OFFLOAD_BYTES`: 落盘阈值（字节），0 表示关闭分层存储
+    /// - `VECTOR_CONTENT_SUMMARY_CHARS`: 内存摘要长度（字符）
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let offload_min_bytes = match std::env::var("VECTOR_CONTENT_OFFLOAD_BYTES").ok().and_then(|v| v.trim().parse::<usize>().ok()) {
+            Some(0) => None,
+            Some(bytes) => Some(bytes),
+            None => defaults.offload_min_bytes,
+        };
+        let summary_chars = std::env::var("VECTOR_CONTENT_SUMMARY_CHARS")
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|chars| *chars > 0)
+            .unwrap_or(defaults.summary_chars);
+
+        Self { offload_min_bytes, summary_chars }
+    }
+
+    /// 该内容是否应落盘：超过阈值且比摘要长
+    pub fn should_offload(&self, content: &str) -> bool {
+        self.offload_min_bytes.map_or(false, |min| content.len() > min)
+            && content.chars().count() > self.summary_chars
+    }
+}
+
+/// 截取内容开头作为摘要，尽量在空白处断开，不切断多字节字符
+pub fn summarize(content: &str, max_chars: usize) -> String {
+    let Some((cut, _)) = content.char_indices().nth(max_chars) else {
+        return content.to_string();
+    };
+    let head = &content[..cut];
+    // 只在摘要末尾 1/5 范围内寻找空白，避免摘要过短
+    let min_len = head.len() - head.len() / 5;
+    match head.rfind(char::is_whitespace) {
+        Some(pos) if pos >= min_len => head[..pos].trim_end().to_string(),
+        _ => head.to_string(),
+    }
+}
+
+/// 磁盘上的全文存储，按文档ID的哈希分目录存放
+#[derive(Debug, Clone)]
+pub struct ContentStore {
+    dir: PathBuf,
+}
+
+impl ContentStore {
+    pub fn new(data_dir: &Path) -> Self {
+        Self { dir: data_dir.join("content") }
+    }
+
+    fn path_for(&self, doc_id: &str) -> PathBuf {
+        let digest = format!("{:x}", md5::compute(doc_id.as_bytes()));
+        self.dir.join(&digest[..2]).join(digest)
+    }
+
+    /// 写入全文（先写临时文件再替换，避免中断时留下半截内容）
+    pub fn write(&self, doc_id: &str, content: &str) -> Result<()> {
+        let path = self.path_for(doc_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn read(&self, doc_id: &str) -> Result<String> {
+        let path = self.path_for(doc_id);
+        fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("读取文档 {} 的全文失败 ({:?}): {}", doc_id, path, e))
+    }
+
+    /// 删除全文，文件不存在时忽略
+    pub fn remove(&self, doc_id: &str) -> Result<()> {
+        match fs::remove_file(self.path_for(doc_id)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_read_remove() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let store = ContentStore::new(temp_dir.path());
+        store.write("rust/serde/1.0/lib.rs", "全文内容").unwrap();
+        assert_eq!(store.read("rust/serde/1.0/lib.rs").unwrap(), "全文内容");
+
+        store.remove("rust/serde/1.0/lib.rs").unwrap();
+        assert!(store.read("rust/serde/1.0/lib.rs").is_err());
+        // 重复删除不报错
+        store.remove("rust/serde/1.0/lib.rs").unwrap();
+    }
+
+    #[test]
+    fn test_summarize_respects_char_boundaries() {
+        assert_eq!(summarize("short", 10), "short");
+        assert_eq!(summarize("hello wonderful world", 18), "hello wonderful");
+        // 没有合适的空白时按字符截断
+        assert_eq!(summarize("序列化与反序列化框架", 3), "序列化");
+    }
+
+    #[test]
+    fn test_should_offload() {
+        let config = ContentTierConfig { offload_min_bytes: Some(8), summary_chars: 4 };
+        assert!(config.should_offload("0123456789"));
+        assert!(!config.should_offload("0123"));
+        let disabled = ContentTierConfig { offload_min_bytes: None, summary_chars: 4 };
+        assert!(!disabled.should_offload("0123456789"));
+    }
+}
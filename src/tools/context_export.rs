@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use anyhow::Result;
+
+use crate::errors::{server_error, MCPError};
+use crate::tools::base::{MCPTool, Schema, SchemaObject, SchemaString};
+use crate::tools::vector_docs_tool::{SearchResult, VectorDocsTool};
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleFormat {
+    Markdown,
+    Zip,
+}
+
+impl BundleFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "markdown" | "md" => Some(BundleFormat::Markdown),
+            "zip" => Some(BundleFormat::Zip),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            BundleFormat::Markdown => "md",
+            BundleFormat::Zip => "zip",
+        }
+    }
+}
+
+/// 上下文包导出工具
+///
+/// 将回答某个查询时使用的文档片段（连同来源URL和得分）导出为 Markdown 文件或 zip 包，
+/// 便于开发者审计 LLM 实际看到的内容。
+pub struct ExportContextBundleTool {
+    vector_tool: Arc<VectorDocsTool>,
+    export_dir: PathBuf,
+    schema: Schema,
+}
+
+impl ExportContextBundleTool {
+    pub fn new(vector_tool: Arc<VectorDocsTool>) -> Self {
+        let export_dir = std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join(".mcp_cache")
+            .join("exports");
+        Self::with_export_dir(vector_tool, export_dir)
+    }
+
+    pub fn with_export_dir(vector_tool: Arc<VectorDocsTool>, export_dir: PathBuf) -> Self {
+        Self {
+            vector_tool,
+            export_dir,
+            schema: Self::create_schema(),
+        }
+    }
+
+    fn create_schema() -> Schema {
+        let mut props = HashMap::new();
+        props.insert("query".to_string(), Schema::String(SchemaString {
+            description: Some("要导出上下文的查询".to_string()),
+            enum_values: None,
+        }));
+        props.insert("format".to_string(), Schema::String(SchemaString {
+            description: Some("导出格式: markdown(单个Markdown文件) 或 zip(每个片段一个文件)，默认markdown".to_string()),
+            enum_values: Some(vec!["markdown".to_string(), "zip".to_string()]),
+        }));
+        props.insert("limit".to_string(), Schema::String(SchemaString {
+            description: Some("导出的片段数量，默认10".to_string()),
+            enum_values: None,
+        }));
+        props.insert("output_path".to_string(), Schema::String(SchemaString {
+            description: Some("输出文件的相对路径（可选，相对于导出目录 .mcp_cache/exports，不能包含 ..）".to_string()),
+            enum_values: None,
+        }));
+        Schema::Object(SchemaObject {
+            required: vec!["query".to_string()],
+            properties: props,
+            description: Some("导出查询上下文包".to_string()),
+        })
+    }
+
+    /// 片段的来源地址
+    fn source_of(result: &SearchResult) -> String {
+        ["url", "source_url", "file_path"]
+            .iter()
+            .find_map(|key| result.metadata.get(*key).cloned())
+            .unwrap_or_else(|| result.id.clone())
+    }
+
+    /// 生成汇总 Markdown
+    pub fn render_markdown(query: &str, results: &[SearchResult]) -> String {
+        let mut markdown = String::new();
+        markdown.push_str(&format!("# 上下文导出: {}\n\n", query));
+        markdown.push_str(&format!("- 导出时间: {}\n", chrono::Utc::now().to_rfc3339()));
+        markdown.push_str(&format!("- 片段数量: {}\n\n", results.len()));
+
+        markdown.push_str("| # | 标题 | 包 | 得分 | 来源 |\n|---|---|---|---|---|\n");
+        for (index, result) in results.iter().enumerate() {
+            markdown.push_str(&format!(
+                "| {} | {} | {}/{}@{} | {:.4} | {} |\n",
+                index + 1, result.title, result.language, result.package_name, result.version,
+                result.score, Self::source_of(result)
+            ));
+        }
+        markdown.push('\n');
+
+        for (index, result) in results.iter().enumerate() {
+            markdown.push_str(&Self::render_fragment(index + 1, result));
+        }
+        markdown
+    }
+
+    fn render_fragment(index: usize, result: &SearchResult) -> String {
+        format!(
+            "## {}. {}\n\n- ID: `{}`\n- 包: {}/{}@{}\n- 类型: {}\n- 得分: {:.4}\n- 来源: {}\n\n{}\n\n",
+            index, result.title, result.id, result.language, result.package_name, result.version,
+            result.doc_type, result.score, Self::source_of(result), result.content
+        )
+    }
+
+    fn default_output_path(&self, format: BundleFormat) -> PathBuf {
+        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+        self.export_dir.join(format!("context_{}.{}", timestamp, format.extension()))
+    }
+
+    fn write_bundle(path: &Path, format: BundleFormat, query: &str, results: &[SearchResult]) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        match format {
+            BundleFormat::Markdown => {
+                std::fs::write(path, Self::render_markdown(query, results))?;
+            }
+            BundleFormat::Zip => {
+                let mut entries = vec![("index.md".to_string(), Self::render_markdown(query, results).into_bytes())];
+                for (index, result) in results.iter().enumerate() {
+                    entries.push((
+                        format!("fragments/{:03}.md", index + 1),
+                        Self::render_fragment(index + 1, result).into_bytes(),
+                    ));
+                }
+                entries.push(("results.json".to_string(), serde_json::to_vec_pretty(results)?));
+                write_zip(path, &entries)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MCPTool for ExportContextBundleTool {
+    fn name(&self) -> &str {
+        "export_context_bundle"
+    }
+
+    fn description(&self) -> &str {
+        "在需要审计回答依据时，导出用于回答某个查询的文档片段（含来源URL和得分）为Markdown文件或zip包。"
+    }
+
+    fn parameters_schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let query = params["query"]
+            .as_str()
+            .ok_or_else(|| MCPError::InvalidParameter("缺少query参数".to_string()))?;
+        let format = match params["format"].as_str() {
+            Some(value) => BundleFormat::parse(value)
+                .ok_or_else(|| MCPError::InvalidParameter(format!("不支持的导出格式: {}", value)))?,
+            None => BundleFormat::Markdown,
+        };
+        let limit = params["limit"].as_u64()
+            .or_else(|| params["limit"].as_str().and_then(|s| s.parse().ok()))
+            .unwrap_or(10) as usize;
+        let output_path = match params["output_path"].as_str() {
+            Some(requested) => resolve_output_path(&self.export_dir, requested)?,
+            None => self.default_output_path(format),
+        };
+
+        let query_embedding = self.vector_tool.generate_embedding(query).await
+            .map_err(|e| server_error("生成查询嵌入向量失败", e))?;
+        let results = self.vector_tool.hybrid_search(&query_embedding, query, limit)?;
+
+        Self::write_bundle(&output_path, format, query, &results)
+            .map_err(|e| MCPError::ServerError(format!("写入导出文件失败: {}", e)))?;
+
+        Ok(json!({
+            "status": "success",
+            "query": query,
+            "format": format.extension(),
+            "output_path": output_path.to_string_lossy(),
+            "fragments_exported": results.len(),
+            "sources": results.iter().map(|r| json!({
+                "id": r.id,
+                "source": Self::source_of(r),
+                "score": r.score,
+            })).collect::<Vec<_>>(),
+        }))
+    }
+}
+
+/// 把调用方给出的输出路径解析到导出目录下
+///
+/// 输出路径来自工具参数，只接受不含 `..` 的相对路径，避免写到导出目录之外。
+fn resolve_output_path(export_dir: &Path, requested: &str) -> Result<PathBuf> {
+    let relative = Path::new(requested.trim());
+    if relative.as_os_str().is_empty() {
+        return Err(MCPError::InvalidParameter("output_path不能为空".to_string()).into());
+    }
+    if !relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(MCPError::InvalidParameter(format!(
+            "output_path必须是导出目录下的相对路径，不能是绝对路径或包含 ..: {}",
+            requested
+        )).into());
+    }
+    Ok(export_dir.join(relative))
+}
+
+/// 写入 deflate 压缩的 zip 归档
+fn write_zip(path: &Path, entries: &[(String, Vec<u8>)]) -> Result<()> {
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(path)?);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (name, data) in entries {
+        writer.start_file(name.as_str(), options)?;
+        writer.write_all(data)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result() -> SearchResult {
+        let mut metadata = HashMap::new();
+        metadata.insert("url".to_string(), "https://docs.rs/tokio".to_string());
+        SearchResult {
+            id: "rust/tokio/1.0/lib.rs".to_string(),
+            content: "Tokio is an asynchronous runtime.".to_string(),
+            title: "lib".to_string(),
+            language: "rust".to_string(),
+            package_name: "tokio".to_string(),
+            version: "1.0".to_string(),
+            doc_type: "documentation".to_string(),
+            metadata,
+            score: 0.87,
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_includes_sources_and_scores() {
+        let markdown = ExportContextBundleTool::render_markdown("tokio runtime", &[sample_result()]);
+        assert!(markdown.contains("https://docs.rs/tokio"));
+        assert!(markdown.contains("0.8700"));
+        assert!(markdown.contains("Tokio is an asynchronous runtime."));
+    }
+
+    #[test]
+    fn test_zip_bundle_contains_index_and_fragments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.zip");
+        ExportContextBundleTool::write_bundle(&path, BundleFormat::Zip, "tokio runtime", &[sample_result()]).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(names, vec!["fragments/001.md", "index.md", "results.json"]);
+
+        let mut index = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("index.md").unwrap(), &mut index).unwrap();
+        assert!(index.contains("https://docs.rs/tokio"));
+    }
+
+    #[test]
+    fn test_output_path_stays_under_export_dir() {
+        let export_dir = Path::new("/data/exports");
+        assert_eq!(
+            resolve_output_path(export_dir, "audit/tokio.md").unwrap(),
+            export_dir.join("audit/tokio.md")
+        );
+        assert!(resolve_output_path(export_dir, "/etc/passwd").is_err());
+        assert!(resolve_output_path(export_dir, "../outside.md").is_err());
+        assert!(resolve_output_path(export_dir, "audit/../../outside.md").is_err());
+        assert!(resolve_output_path(export_dir, "").is_err());
+    }
+}
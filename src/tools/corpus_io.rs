@@ -0,0 +1,310 @@
+//! 文档语料的导出和导入
+//!
+//! 把已缓存的文档（含全文和嵌入向量）导出为 JSONL 或 Parquet 文件，在其他机器上导入，或在更换存储后端时
+//! 作为中转格式。每条记录带生成向量的嵌入模型名，只能导入到使用相同模型的向量库中。
+//! Parquet 格式需要启用 `parquet-export` feature。
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::tools::vector_docs_tool::DocumentRecord;
+
+/// 导出文件的默认存放目录（MCP 操作只读写该目录下的文件）
+pub const EXPORTS_DIR: &str = "exports";
+
+/// 语料文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CorpusFormat {
+    Jsonl,
+    Parquet,
+}
+
+impl CorpusFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            "parquet" => Some(Self::Parquet),
+            _ => None,
+        }
+    }
+
+    /// 按文件扩展名推断格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension().and_then(|ext| ext.to_str()).and_then(Self::parse)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Parquet => "parquet",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        self.as_str()
+    }
+}
+
+/// 导出或导入结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusReport {
+    pub path: String,
+    pub format: CorpusFormat,
+    pub embedding_model: String,
+    /// 导出的文档数，或文件中的文档数
+    pub documents: usize,
+    /// 导入时新增的文档数（已存在的ID跳过）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported: Option<usize>,
+    pub bytes: u64,
+}
+
+/// JSONL 中的一行：文档记录加嵌入模型名
+#[derive(Debug, Serialize, Deserialize)]
+struct CorpusRow<D> {
+    #[serde(flatten)]
+    document: D,
+    embedding_model: String,
+}
+
+/// 把文档写入语料文件，返回文件字节数
+pub fn write_corpus(path: &Path, format: CorpusFormat, embedding_model: &str, documents: &[DocumentRecord]) -> Result<u64> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    match format {
+        CorpusFormat::Jsonl => {
+            let mut writer = BufWriter::new(std::fs::File::create(path)?);
+            for document in documents {
+                serde_json::to_writer(&mut writer, &CorpusRow { document, embedding_model: embedding_model.to_string() })?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+        CorpusFormat::Parquet => parquet_format::write(path, embedding_model, documents)?,
+    }
+    Ok(std::fs::metadata(path)?.len())
+}
+
+/// 读取语料文件，返回文档和嵌入模型名；文件中混有不同模型生成的向量时报错
+pub fn read_corpus(path: &Path, format: CorpusFormat) -> Result<(Vec<DocumentRecord>, Option<String>)> {
+    let rows = match format {
+        CorpusFormat::Jsonl => {
+            let reader = BufReader::new(std::fs::File::open(path)?);
+            let mut rows = Vec::new();
+            for (line_no, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let row: CorpusRow<DocumentRecord> = serde_json::from_str(&line)
+                    .map_err(|e| anyhow!("语料文件第 {} 行格式错误: {}", line_no + 1, e))?;
+                rows.push((row.document, row.embedding_model));
+            }
+            rows
+        }
+        CorpusFormat::Parquet => parquet_format::read(path)?,
+    };
+
+    let mut model: Option<String> = None;
+    let mut documents = Vec::with_capacity(rows.len());
+    for (document, row_model) in rows {
+        match &model {
+            Some(model) if *model != row_model => {
+                return Err(anyhow!("语料文件混有不同嵌入模型生成的向量: {} 和 {}", model, row_model));
+            }
+            Some(_) => {}
+            None => model = Some(row_model),
+        }
+        documents.push(document);
+    }
+    Ok((documents, model))
+}
+
+#[cfg(feature = "parquet-export")]
+mod parquet_format {
+    use anyhow::{anyhow, Result};
+    use arrow_array::builder::{Float32Builder, ListBuilder};
+    use arrow_array::{Array, ArrayRef, Float32Array, ListArray, RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::arrow::ArrowWriter;
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use crate::tools::vector_docs_tool::DocumentRecord;
+
+    /// 字符串列（元数据以 JSON 字符串保存），最后一列为嵌入向量
+    const STRING_COLUMNS: [&str; 9] =
+        ["id", "title", "content", "language", "package_name", "version", "doc_type", "metadata", "embedding_model"];
+
+    fn schema() -> Arc<Schema> {
+        let mut fields: Vec<Field> = STRING_COLUMNS.iter().map(|name| Field::new(*name, DataType::Utf8, false)).collect();
+        fields.push(Field::new("embedding", DataType::List(Arc::new(Field::new("item", DataType::Float32, true))), false));
+        Arc::new(Schema::new(fields))
+    }
+
+    pub fn write(path: &Path, embedding_model: &str, documents: &[DocumentRecord]) -> Result<()> {
+        let column = |value: &dyn Fn(&DocumentRecord) -> String| -> ArrayRef {
+            Arc::new(StringArray::from(documents.iter().map(value).collect::<Vec<String>>()))
+        };
+        let mut embeddings = ListBuilder::new(Float32Builder::new());
+        for doc in documents {
+            embeddings.values().append_slice(&doc.embedding);
+            embeddings.append(true);
+        }
+        let columns: Vec<ArrayRef> = vec![
+            column(&|doc| doc.id.clone()),
+            column(&|doc| doc.title.clone()),
+            column(&|doc| doc.content.clone()),
+            column(&|doc| doc.language.clone()),
+            column(&|doc| doc.package_name.clone()),
+            column(&|doc| doc.version.clone()),
+            column(&|doc| doc.doc_type.clone()),
+            column(&|doc| serde_json::to_string(&doc.metadata).unwrap_or_default()),
+            column(&|_| embedding_model.to_string()),
+            Arc::new(embeddings.finish()),
+        ];
+        let schema = schema();
+        let batch = RecordBatch::try_new(schema.clone(), columns)?;
+        let mut writer = ArrowWriter::try_new(std::fs::File::create(path)?, schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<Vec<(DocumentRecord, String)>> {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(path)?)?.build()?;
+        let mut rows = Vec::new();
+        for batch in reader {
+            let batch = batch?;
+            let strings = |name: &str| -> Result<&StringArray> {
+                batch.column_by_name(name)
+                    .and_then(|column| column.as_any().downcast_ref::<StringArray>())
+                    .ok_or_else(|| anyhow!("Parquet 语料文件缺少字符串列 {}", name))
+            };
+            let columns = STRING_COLUMNS.iter().map(|name| strings(name)).collect::<Result<Vec<_>>>()?;
+            let embeddings = batch.column_by_name("embedding")
+                .and_then(|column| column.as_any().downcast_ref::<ListArray>())
+                .ok_or_else(|| anyhow!("Parquet 语料文件缺少 embedding 列"))?;
+            for row in 0..batch.num_rows() {
+                let value = |column: usize| columns[column].value(row).to_string();
+                let embedding = embeddings.value(row);
+                let embedding = embedding.as_any().downcast_ref::<Float32Array>()
+                    .ok_or_else(|| anyhow!("Parquet 语料文件的 embedding 列不是 float32 列表"))?;
+                let metadata: HashMap<String, String> = serde_json::from_str(&value(7))
+                    .map_err(|e| anyhow!("Parquet 语料文件第 {} 行元数据格式错误: {}", row + 1, e))?;
+                rows.push((
+                    DocumentRecord {
+                        id: value(0),
+                        title: value(1),
+                        content: value(2),
+                        language: value(3),
+                        package_name: value(4),
+                        version: value(5),
+                        doc_type: value(6),
+                        metadata,
+                        embedding: embedding.values().to_vec(),
+                    },
+                    value(8),
+                ));
+            }
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(not(feature = "parquet-export"))]
+mod parquet_format {
+    use anyhow::{anyhow, Result};
+    use std::path::Path;
+
+    use crate::tools::vector_docs_tool::DocumentRecord;
+
+    pub fn write(_path: &Path, _embedding_model: &str, _documents: &[DocumentRecord]) -> Result<()> {
+        Err(anyhow!("Parquet 格式需要启用 parquet-export feature，可改用 jsonl"))
+    }
+
+    pub fn read(_path: &Path) -> Result<Vec<(DocumentRecord, String)>> {
+        Err(anyhow!("Parquet 格式需要启用 parquet-export feature，可改用 jsonl"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn document(id: &str) -> DocumentRecord {
+        DocumentRecord {
+            id: id.to_string(),
+            content: format!("{} 的全文", id),
+            title: id.to_string(),
+            language: "rust".to_string(),
+            package_name: "serde".to_string(),
+            version: "1.0.0".to_string(),
+            doc_type: "documentation".to_string(),
+            metadata: HashMap::from([("source_url".to_string(), "https://docs.rs/serde".to_string())]),
+            embedding: vec![0.25, -0.5, 1.0],
+        }
+    }
+
+    #[test]
+    fn test_jsonl_round_trip_keeps_embeddings_and_model() {
+        assert_eq!(CorpusFormat::from_path(Path::new("out/corpus.JSONL")), Some(CorpusFormat::Jsonl));
+        assert_eq!(CorpusFormat::from_path(Path::new("corpus.parquet")), Some(CorpusFormat::Parquet));
+        assert_eq!(CorpusFormat::from_path(Path::new("corpus.csv")), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/corpus.jsonl");
+        let bytes = write_corpus(&path, CorpusFormat::Jsonl, "test-model", &[document("a"), document("b")]).unwrap();
+        assert!(bytes > 0);
+
+        let (documents, model) = read_corpus(&path, CorpusFormat::Jsonl).unwrap();
+        assert_eq!(model.as_deref(), Some("test-model"));
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1].embedding, vec![0.25, -0.5, 1.0]);
+        assert_eq!(documents[1].metadata["source_url"], "https://docs.rs/serde");
+
+        let mixed = dir.path().join("mixed.jsonl");
+        let line = |model: &str| serde_json::to_string(&CorpusRow { document: document("a"), embedding_model: model.to_string() }).unwrap();
+        std::fs::write(&mixed, format!("{}\n{}\n", line("m1"), line("m2"))).unwrap();
+        assert!(read_corpus(&mixed, CorpusFormat::Jsonl).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import_actions_move_corpus_between_stores() {
+        use crate::tools::base::MCPTool;
+        use crate::tools::embedder::testing::MockEmbedder;
+        use crate::tools::vector_docs_tool::VectorDocsTool;
+        use serde_json::json;
+        use std::sync::Arc;
+
+        let open = |dir: &Path| VectorDocsTool::open_local(dir.to_path_buf()).unwrap().with_embedder(Arc::new(MockEmbedder::new(32)));
+        let (source_dir, target_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let source = open(source_dir.path());
+        for (package_name, content) in [("serde", "Serialize 序列化"), ("tokio", "spawn 异步任务")] {
+            source.execute(json!({
+                "action": "store", "title": package_name, "content": content,
+                "language": "rust", "package_name": package_name, "version": "1.0.0"
+            })).await.unwrap();
+        }
+
+        let exported = source.execute(json!({ "action": "export", "name": "team", "package_name": "serde" })).await.unwrap();
+        assert_eq!(exported["report"]["documents"], 1);
+        assert!(source.execute(json!({ "action": "export", "name": "../team" })).await.is_err());
+
+        let path = source_dir.path().join(EXPORTS_DIR).join("team.jsonl");
+        let target = open(target_dir.path());
+        let report = target.import_corpus(&path, CorpusFormat::Jsonl, None).unwrap();
+        assert_eq!((report.documents, report.imported), (1, Some(1)));
+        // 再次导入时已存在的文档跳过
+        assert_eq!(target.import_corpus(&path, CorpusFormat::Jsonl, None).unwrap().imported, Some(0));
+        let found = target.execute(json!({ "action": "search", "query": "Serialize 序列化", "package_name": "serde" })).await.unwrap();
+        assert_eq!(found["results"][0]["package_name"], "serde");
+    }
+}
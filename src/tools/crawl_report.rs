@@ -0,0 +1,284 @@
+//! 抓取报告
+//!
+//! 每次后台缓存一个包版本结束后（无论成功与否），把抓取统计写成 JSON 文件保存到
+//! `.mcp_cache/reports`，同一包版本只保留最近一次的报告。文档覆盖率不理想时可以通过
+//! `get_crawl_report` 工具查看抓取了多少页面、有多少片段被跳过或拒绝、花了多少嵌入 token。
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::errors::MCPError;
+use crate::tools::base::{MCPTool, Schema, SchemaObject, SchemaString};
+use crate::tools::quality_gate::RejectionCounts;
+
+/// 抓取结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlOutcome {
+    Success,
+    Failed,
+}
+
+/// 单个包版本的一次抓取报告
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlReport {
+    pub language: String,
+    pub package_name: String,
+    pub version: String,
+    pub outcome: CrawlOutcome,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// 抓取到的页面/文档数
+    pub pages_fetched: usize,
+    /// 已在缓存中或内容为空而跳过的片段数
+    pub skipped: usize,
+    /// 生成嵌入向量失败的片段数
+    pub failed: usize,
+    /// 新写入向量库的片段数
+    pub fragments_stored: usize,
+    /// 被质量闸门拒绝的片段数（按原因）
+    #[serde(default)]
+    pub fragments_rejected: RejectionCounts,
+    /// 嵌入请求消耗的 token 数（按文本长度估算）
+    pub embedding_tokens: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CrawlReport {
+    /// 以失败状态开始一份报告，抓取结束后由调用方补充统计
+    pub fn started(language: &str, package_name: &str, version: &str) -> Self {
+        Self {
+            language: language.to_string(),
+            package_name: package_name.to_string(),
+            version: version.to_string(),
+            outcome: CrawlOutcome::Failed,
+            started_at: Utc::now(),
+            duration_ms: 0,
+            pages_fetched: 0,
+            skipped: 0,
+            failed: 0,
+            fragments_stored: 0,
+            fragments_rejected: RejectionCounts::default(),
+            embedding_tokens: 0,
+            error: None,
+        }
+    }
+
+    pub fn succeed(&mut self, duration: Duration) {
+        self.outcome = CrawlOutcome::Success;
+        self.duration_ms = duration.as_millis() as u64;
+        self.error = None;
+    }
+
+    pub fn fail(&mut self, duration: Duration, error: String) {
+        self.outcome = CrawlOutcome::Failed;
+        self.duration_ms = duration.as_millis() as u64;
+        self.error = Some(error);
+    }
+}
+
+/// 嵌入 token 数的粗略估算（约 4 个字符一个 token）
+pub fn estimate_tokens(text: &str) -> usize {
+    (text.chars().count() + 3) / 4
+}
+
+/// 抓取报告目录，每个包版本一个 JSON 文件
+#[derive(Debug, Clone)]
+pub struct CrawlReportStore {
+    dir: PathBuf,
+}
+
+impl Default for CrawlReportStore {
+    /// 默认位于 `<当前目录>/.mcp_cache/reports`
+    fn default() -> Self {
+        Self::new(
+            std::env::current_dir()
+                .unwrap_or_else(|_| PathBuf::from("."))
+                .join(".mcp_cache")
+                .join("reports"),
+        )
+    }
+}
+
+impl CrawlReportStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_for(&self, language: &str, package_name: &str, version: &str) -> PathBuf {
+        let name: String = format!("{}__{}__{}", language, package_name, version)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// 保存报告，覆盖同一包版本的旧报告
+    pub fn save(&self, report: &CrawlReport) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path_for(&report.language, &report.package_name, &report.version);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(report)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(path)
+    }
+
+    pub fn load(&self, language: &str, package_name: &str, version: &str) -> Result<Option<CrawlReport>> {
+        let path = self.path_for(language, package_name, version);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&fs::read(path)?)?))
+    }
+
+    /// 列出所有报告，最近开始的在前；无法解析的文件会跳过
+    pub fn list(&self) -> Result<Vec<CrawlReport>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut reports = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(true, |ext| ext != "json") {
+                continue;
+            }
+            match fs::read(&path).map_err(anyhow::Error::from).and_then(|bytes| Ok(serde_json::from_slice(&bytes)?)) {
+                Ok(report) => reports.push(report),
+                Err(e) => warn!("跳过无法解析的抓取报告 {:?}: {}", path, e),
+            }
+        }
+        reports.sort_by(|a: &CrawlReport, b: &CrawlReport| b.started_at.cmp(&a.started_at));
+        Ok(reports)
+    }
+}
+
+/// 查询抓取报告的调试工具
+pub struct GetCrawlReportTool {
+    reports: CrawlReportStore,
+    schema: Schema,
+}
+
+impl GetCrawlReportTool {
+    pub fn new(reports: CrawlReportStore) -> Self {
+        Self {
+            reports,
+            schema: Self::create_schema(),
+        }
+    }
+
+    fn create_schema() -> Schema {
+        let mut props = HashMap::new();
+        props.insert("language".to_string(), Schema::String(SchemaString {
+            description: Some("编程语言，如 rust、python（可选，按语言过滤）".to_string()),
+            enum_values: None,
+        }));
+        props.insert("package_name".to_string(), Schema::String(SchemaString {
+            description: Some("包名（可选，按包名过滤）".to_string()),
+            enum_values: None,
+        }));
+        props.insert("version".to_string(), Schema::String(SchemaString {
+            description: Some("版本（可选，与语言和包名一起指定时返回该版本的报告）".to_string()),
+            enum_values: None,
+        }));
+        Schema::Object(SchemaObject {
+            required: Vec::new(),
+            properties: props,
+            description: Some("查询文档抓取报告".to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl MCPTool for GetCrawlReportTool {
+    fn name(&self) -> &str {
+        "get_crawl_report"
+    }
+
+    fn description(&self) -> &str {
+        "查看后台文档缓存的抓取报告（抓取页面数、跳过/失败/拒绝的片段、入库片段数、嵌入 token 和耗时），用于排查文档覆盖率不足的包。"
+    }
+
+    fn parameters_schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let text = |key: &str| params[key].as_str().map(str::trim).filter(|s| !s.is_empty());
+        let (language, package_name, version) = (text("language"), text("package_name"), text("version"));
+
+        if let (Some(language), Some(package_name), Some(version)) = (language, package_name, version) {
+            return match self.reports.load(language, package_name, version)? {
+                Some(report) => Ok(json!({ "status": "success", "report": report })),
+                None => Ok(json!({
+                    "status": "not_found",
+                    "message": format!("{}/{}/{} 还没有抓取报告", language, package_name, version),
+                })),
+            };
+        }
+        if version.is_some() {
+            return Err(MCPError::InvalidParameter("指定version时还需要language和package_name".to_string()).into());
+        }
+
+        let reports: Vec<CrawlReport> = self.reports.list()?
+            .into_iter()
+            .filter(|r| language.map_or(true, |l| r.language == l))
+            .filter(|r| package_name.map_or(true, |p| r.package_name == p))
+            .collect();
+        Ok(json!({
+            "status": "success",
+            "count": reports.len(),
+            "reports": reports,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reports_are_saved_and_queried() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CrawlReportStore::new(dir.path().join("reports"));
+
+        let mut report = CrawlReport::started("rust", "tokio", "1.38.0");
+        report.pages_fetched = 12;
+        report.fragments_stored = 10;
+        report.skipped = 2;
+        report.embedding_tokens = estimate_tokens("spawn a task");
+        report.succeed(Duration::from_millis(1500));
+        store.save(&report).unwrap();
+
+        let mut failed = CrawlReport::started("python", "requests", "latest");
+        failed.fail(Duration::from_secs(3), "未找到任何文档片段".to_string());
+        store.save(&failed).unwrap();
+
+        assert_eq!(store.load("rust", "tokio", "1.38.0").unwrap(), Some(report));
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        let tool = GetCrawlReportTool::new(store);
+        let result = tool.execute(json!({ "language": "rust", "package_name": "tokio", "version": "1.38.0" })).await.unwrap();
+        assert_eq!(result["report"]["pages_fetched"], 12);
+        assert_eq!(result["report"]["outcome"], "success");
+
+        let result = tool.execute(json!({ "language": "python" })).await.unwrap();
+        assert_eq!(result["count"], 1);
+        assert_eq!(result["reports"][0]["error"], "未找到任何文档片段");
+
+        let result = tool.execute(json!({ "language": "go", "package_name": "gin", "version": "1.9.0" })).await.unwrap();
+        assert_eq!(result["status"], "not_found");
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use thiserror::Error;
+
+use crate::tools::data_lock::write_atomic;
+
+/// 版本化数据文件的魔数
+pub const FORMAT_MAGIC: &[u8; 4] = b"GRPV";
+
+/// 数据文件头长度：魔数(4) + 格式版本(u32 LE)
+const HEADER_LEN: usize = 8;
+
+/// 单步迁移函数：输入旧版本负载，输出下一版本负载
+pub type MigrateFn = fn(&[u8]) -> Result<Vec<u8>>;
+
+/// 一次格式迁移
+#[derive(Clone)]
+pub struct Migration {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub description: &'static str,
+    pub migrate: MigrateFn,
+}
+
+/// 数据文件版本与当前程序不匹配
+///
+/// 与文件损坏不同，这类数据本身是完好的，不能当作损坏文件丢弃。
+#[derive(Error, Debug)]
+pub enum VersionMismatch {
+    #[error("数据文件格式版本 {found} 高于当前支持的版本 {supported}，请升级 grape-mcp-devtools")]
+    Newer { found: u32, supported: u32 },
+    #[error("缺少从版本 {0} 开始的数据迁移")]
+    MissingMigration(u32),
+}
+
+/// 错误是否由数据版本不匹配引起
+pub fn is_version_mismatch(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<VersionMismatch>().is_some()
+}
+
+/// 数据文件内容无法解码：文件头无法识别，或负载不能按当前格式解析
+///
+/// 只有这类错误说明文件本身已损坏；读取、内存映射等 I/O 失败不属于此类，不能据此丢弃数据。
+#[derive(Error, Debug)]
+#[error("{0}")]
+pub struct CorruptData(pub String);
+
+/// 错误是否由数据文件内容损坏引起
+pub fn is_corrupt_data(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<CorruptData>().is_some()
+}
+
+/// 旧版无文件头数据的识别函数：能按该版本解析时返回 true
+pub type LegacyProbe = fn(&[u8]) -> bool;
+
+/// 迁移注册表
+///
+/// 磁盘数据以 `魔数 + 格式版本 + 负载` 的形式保存。读取时按注册的迁移逐版本升级到
+/// 当前版本；没有文件头的旧数据通过探测函数识别其版本后同样走迁移链。
+pub struct MigrationRegistry {
+    current_version: u32,
+    migrations: Vec<Migration>,
+    legacy_probes: Vec<(u32, LegacyProbe)>,
+}
+
+/// 读取结果
+#[derive(Debug)]
+pub struct LoadedData {
+    /// 当前版本的负载
+    pub payload: Vec<u8>,
+    /// 磁盘上的原始版本
+    pub source_version: u32,
+    /// 依次执行的迁移说明
+    pub applied_migrations: Vec<String>,
+}
+
+impl LoadedData {
+    pub fn was_migrated(&self) -> bool {
+        !self.applied_migrations.is_empty()
+    }
+}
+
+impl MigrationRegistry {
+    pub fn new(current_version: u32) -> Self {
+        Self {
+            current_version,
+            migrations: Vec::new(),
+            legacy_probes: Vec::new(),
+        }
+    }
+
+    pub fn current_version(&self) -> u32 {
+        self.current_version
+    }
+
+    /// 注册从 `from_version` 到 `from_version + 1` 的迁移
+    pub fn register(mut self, from_version: u32, description: &'static str, migrate: MigrateFn) -> Self {
+        self.migrations.push(Migration {
+            from_version,
+            to_version: from_version + 1,
+            description,
+            migrate,
+        });
+        self
+    }
+
+    /// 注册无文件头旧数据的探测函数，按注册顺序依次尝试
+    pub fn register_legacy(mut self, version: u32, probe: LegacyProbe) -> Self {
+        self.legacy_probes.push((version, probe));
+        self
+    }
+
+    /// 给负载加上当前版本的文件头
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(HEADER_LEN + payload.len());
+        data.extend_from_slice(FORMAT_MAGIC);
+        data.extend_from_slice(&self.current_version.to_le_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// 识别数据版本
+    fn detect(&self, data: &[u8]) -> Result<(u32, Vec<u8>)> {
+        if data.len() >= HEADER_LEN && &data[..4] == FORMAT_MAGIC {
+            let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+            return Ok((version, data[HEADER_LEN..].to_vec()));
+        }
+        self.legacy_probes
+            .iter()
+            .find(|(_, probe)| probe(data))
+            .map(|(version, _)| (*version, data.to_vec()))
+            .ok_or_else(|| CorruptData("无法识别的数据文件格式".to_string()).into())
+    }
+
+    /// 解码数据并升级到当前版本
+    pub fn decode(&self, data: &[u8]) -> Result<LoadedData> {
+        let (source_version, mut payload) = self.detect(data)?;
+        if source_version > self.current_version {
+            return Err(VersionMismatch::Newer { found: source_version, supported: self.current_version }.into());
+        }
+
+        let mut version = source_version;
+        let mut applied_migrations = Vec::new();
+        while version < self.current_version {
+            let migration = self.migrations
+                .iter()
+                .find(|m| m.from_version == version)
+                .ok_or(VersionMismatch::MissingMigration(version))?;
+            payload = (migration.migrate)(&payload)
+                .map_err(|e| anyhow!("数据迁移 v{} -> v{} 失败: {}", migration.from_version, migration.to_version, e))?;
+            applied_migrations.push(format!("v{} -> v{}: {}", migration.from_version, migration.to_version, migration.description));
+            version = migration.to_version;
+        }
+
+        Ok(LoadedData { payload, source_version, applied_migrations })
+    }
+
+    /// 读取数据文件；发生迁移时先备份原文件并以当前版本重写
+    pub fn load_file(&self, path: &Path) -> Result<LoadedData> {
+        let data = std::fs::read(path)?;
+        let loaded = self.decode(&data)?;
+        if loaded.was_migrated() {
+            let backup = path.with_extension(format!("v{}.bak", loaded.source_version));
+            std::fs::write(&backup, &data)?;
+            write_atomic(path, &self.encode(&loaded.payload))?;
+            tracing::info!(
+                "数据文件 {:?} 已从 v{} 迁移到 v{}（原文件备份为 {:?}）: {:?}",
+                path, loaded.source_version, self.current_version, backup, loaded.applied_migrations
+            );
+        }
+        Ok(loaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn append_one(payload: &[u8]) -> Result<Vec<u8>> {
+        let mut data = payload.to_vec();
+        data.push(1);
+        Ok(data)
+    }
+
+    fn append_two(payload: &[u8]) -> Result<Vec<u8>> {
+        let mut data = payload.to_vec();
+        data.push(2);
+        Ok(data)
+    }
+
+    fn registry() -> MigrationRegistry {
+        MigrationRegistry::new(3)
+            .register(1, "追加1", append_one)
+            .register(2, "追加2", append_two)
+            .register_legacy(1, |data| data.first() == Some(&0))
+    }
+
+    #[test]
+    fn test_legacy_data_migrates_through_chain() {
+        let loaded = registry().decode(&[0]).unwrap();
+        assert_eq!(loaded.source_version, 1);
+        assert_eq!(loaded.payload, vec![0, 1, 2]);
+        assert_eq!(loaded.applied_migrations.len(), 2);
+    }
+
+    #[test]
+    fn test_current_and_future_versions() {
+        let registry = registry();
+        let encoded = registry.encode(&[9, 9]);
+        let loaded = registry.decode(&encoded).unwrap();
+        assert!(!loaded.was_migrated());
+        assert_eq!(loaded.payload, vec![9, 9]);
+
+        let future = MigrationRegistry::new(4).encode(&[1]);
+        assert!(is_version_mismatch(&registry.decode(&future).unwrap_err()));
+        assert!(!is_version_mismatch(&registry.decode(&[7]).unwrap_err()));
+        assert!(is_corrupt_data(&registry.decode(&[7]).unwrap_err()));
+        assert!(!is_corrupt_data(&registry.decode(&future).unwrap_err()));
+    }
+}
@@ -0,0 +1,221 @@
+use anyhow::{anyhow, Result};
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 锁文件名
+pub const LOCK_FILE_NAME: &str = ".grape.lock";
+
+/// 只读副本默认的数据刷新间隔（秒）
+pub const DEFAULT_REPLICA_REFRESH_SECS: u64 = 30;
+
+/// 数据目录加锁策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockPolicy {
+    /// 优先获取独占锁，已被其他实例占用时以只读跟随模式打开
+    Auto,
+    /// 必须获取独占锁，否则启动失败
+    Exclusive,
+    /// 不加锁（仅用于调试或确定只有单实例的场景）
+    Disabled,
+    /// 只读副本：不获取锁，始终只读打开，定期重新加载写入实例保存的数据
+    ///
+    /// 用于 CI 机器挂载由中心写入实例维护的共享缓存目录（网络文件系统上的咨询锁不一定可靠）。
+    Replica,
+}
+
+impl LockPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "auto" => Some(LockPolicy::Auto),
+            "exclusive" => Some(LockPolicy::Exclusive),
+            "none" | "disabled" | "off" => Some(LockPolicy::Disabled),
+            "replica" | "read_only" | "readonly" => Some(LockPolicy::Replica),
+            _ => None,
+        }
+    }
+
+    /// 从 `GRAPE_DATA_LOCK` 环境变量读取（`auto`、`exclusive`、`none`、`replica`），默认 `auto`
+    pub fn from_env() -> Self {
+        std::env::var("GRAPE_DATA_LOCK")
+            .ok()
+            .and_then(|v| Self::parse(&v))
+            .unwrap_or(LockPolicy::Auto)
+    }
+}
+
+/// 数据目录的访问模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessMode {
+    /// 持有独占锁，可读写
+    Owner,
+    /// 其他实例持有独占锁，只读跟随
+    ReadOnlyFollower,
+    /// 未加锁
+    Unlocked,
+    /// 只读副本，按刷新间隔重新加载
+    Replica,
+}
+
+impl AccessMode {
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, AccessMode::ReadOnlyFollower | AccessMode::Replica)
+    }
+
+    /// 检查数据文件是否更新的最小间隔：跟随实例每次访问都检查，只读副本按
+    /// `GRAPE_REPLICA_REFRESH_SECS`（默认 30 秒）定期检查，避免频繁访问共享存储
+    pub fn refresh_interval(&self) -> Duration {
+        match self {
+            AccessMode::Replica => Duration::from_secs(
+                std::env::var("GRAPE_REPLICA_REFRESH_SECS")
+                    .ok()
+                    .and_then(|v| v.trim().parse().ok())
+                    .unwrap_or(DEFAULT_REPLICA_REFRESH_SECS),
+            ),
+            _ => Duration::ZERO,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessMode::Owner => "owner",
+            AccessMode::ReadOnlyFollower => "read_only_follower",
+            AccessMode::Unlocked => "unlocked",
+            AccessMode::Replica => "read_only_replica",
+        }
+    }
+}
+
+/// 数据目录的咨询锁
+///
+/// 多个 IDE 窗口可能同时启动服务器并指向同一个缓存目录，
+/// 同时写 bincode 存储会互相覆盖。第一个实例持有独占锁负责写入，
+/// 后续实例以只读跟随模式打开，只读取磁盘上的数据。
+#[derive(Debug)]
+pub struct DataDirLock {
+    lock_path: PathBuf,
+    mode: AccessMode,
+    /// 持有锁的文件句柄，句柄关闭时锁自动释放
+    file: Option<File>,
+}
+
+impl DataDirLock {
+    /// 按策略获取数据目录锁
+    pub fn acquire(data_dir: &Path, policy: LockPolicy) -> Result<Self> {
+        let lock_path = data_dir.join(LOCK_FILE_NAME);
+        match policy {
+            LockPolicy::Disabled => return Ok(Self { lock_path, mode: AccessMode::Unlocked, file: None }),
+            // 副本可能挂载在只读文件系统上，不创建目录和锁文件
+            LockPolicy::Replica => return Ok(Self { lock_path, mode: AccessMode::Replica, file: None }),
+            LockPolicy::Auto | LockPolicy::Exclusive => {}
+        }
+
+        std::fs::create_dir_all(data_dir)?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&lock_path)
+            .map_err(|e| anyhow!("打开锁文件失败: {:?} - {}", lock_path, e))?;
+
+        match file.try_lock_exclusive() {
+            Ok(()) => {
+                // 写入持有者PID便于排查
+                file.set_len(0)?;
+                file.seek(SeekFrom::Start(0))?;
+                writeln!(file, "{}", std::process::id())?;
+                file.flush()?;
+                Ok(Self { lock_path, mode: AccessMode::Owner, file: Some(file) })
+            }
+            Err(_) if policy == LockPolicy::Auto => {
+                let holder = std::fs::read_to_string(&lock_path).unwrap_or_default();
+                tracing::warn!(
+                    "数据目录 {:?} 已被其他实例 (PID {}) 锁定，以只读跟随模式打开",
+                    data_dir, holder.trim()
+                );
+                Ok(Self { lock_path, mode: AccessMode::ReadOnlyFollower, file: None })
+            }
+            Err(e) => Err(anyhow!("数据目录 {:?} 已被其他实例锁定: {}", data_dir, e)),
+        }
+    }
+
+    pub fn mode(&self) -> AccessMode {
+        self.mode
+    }
+
+    pub fn lock_path(&self) -> &Path {
+        &self.lock_path
+    }
+}
+
+/// 先写同目录下的临时文件再重命名替换，其他实例读取时不会看到写了一半的文件
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+impl Drop for DataDirLock {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            let _ = file.unlock();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_instance_becomes_follower() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+
+        let owner = DataDirLock::acquire(temp_dir.path(), LockPolicy::Auto).unwrap();
+        assert_eq!(owner.mode(), AccessMode::Owner);
+
+        let follower = DataDirLock::acquire(temp_dir.path(), LockPolicy::Auto).unwrap();
+        assert_eq!(follower.mode(), AccessMode::ReadOnlyFollower);
+        assert!(DataDirLock::acquire(temp_dir.path(), LockPolicy::Exclusive).is_err());
+
+        drop(owner);
+        let next_owner = DataDirLock::acquire(temp_dir.path(), LockPolicy::Exclusive).unwrap();
+        assert_eq!(next_owner.mode(), AccessMode::Owner);
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file_without_leftovers() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("cache_accounting.json");
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert!(!temp_dir.path().join("cache_accounting.json.tmp").exists());
+    }
+
+    #[test]
+    fn test_lock_policy_parse() {
+        assert_eq!(LockPolicy::parse("Exclusive"), Some(LockPolicy::Exclusive));
+        assert_eq!(LockPolicy::parse("off"), Some(LockPolicy::Disabled));
+        assert_eq!(LockPolicy::parse("maybe"), None);
+        assert_eq!(LockPolicy::parse("replica"), Some(LockPolicy::Replica));
+    }
+
+    #[test]
+    fn test_replica_never_takes_the_lock() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let replica = DataDirLock::acquire(temp_dir.path(), LockPolicy::Replica).unwrap();
+        assert_eq!(replica.mode(), AccessMode::Replica);
+        assert!(replica.mode().is_read_only());
+        assert!(!temp_dir.path().join(LOCK_FILE_NAME).exists());
+
+        // 写入实例照常获取独占锁
+        let writer = DataDirLock::acquire(temp_dir.path(), LockPolicy::Exclusive).unwrap();
+        assert_eq!(writer.mode(), AccessMode::Owner);
+    }
+}
@@ -0,0 +1,204 @@
+//! 语料级近似重复检测
+//!
+//! 入库时的相似度检查只比较同ID的新旧文档，不同来源抓取到的同一段内容（镜像站点、重复发布的包版本等）
+//! 会以不同ID各存一份。这里对全部嵌入向量计算 SimHash 签名（随机超平面投影的符号位），签名按段分桶，
+//! 只有落在同一个桶里的文档才计算余弦相似度，避免两两比较；相似度达到阈值的文档用并查集合并为簇。
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// 默认的近似重复判定阈值（余弦相似度）
+pub const DEFAULT_DEDUP_THRESHOLD: f32 = 0.95;
+
+/// 签名位数
+const SIGNATURE_BITS: usize = 64;
+
+/// 签名分段数，任意一段完全相同的文档进入同一个桶
+const BANDS: usize = 8;
+
+/// 参与去重的文档
+#[derive(Debug, Clone, Copy)]
+pub struct DedupItem<'a> {
+    pub id: &'a str,
+    pub vector: &'a [f32],
+    /// 全文长度，簇内保留最长的文档
+    pub content_len: usize,
+}
+
+/// 一组近似重复的文档
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateCluster {
+    /// 保留的文档（全文最长，同长时ID最小）
+    pub keep: String,
+    /// 与之重复的其他文档，按ID排序
+    pub duplicates: Vec<String>,
+    /// 簇内相连文档对的最低相似度
+    pub min_similarity: f32,
+}
+
+/// 某个缓存层级中的近似重复簇
+#[derive(Debug, Clone, Serialize)]
+pub struct ScopedCluster {
+    pub scope: String,
+    #[serde(flatten)]
+    pub cluster: DuplicateCluster,
+}
+
+/// 去重报告
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DedupReport {
+    pub threshold: f32,
+    pub clusters: Vec<ScopedCluster>,
+    /// 各簇中除保留文档外的文档总数
+    pub duplicate_documents: usize,
+    /// 自动合并时删除的文档数（只报告时为 0）
+    pub merged_documents: usize,
+    pub bytes_freed: u64,
+}
+
+/// 随机超平面 SimHash；超平面由固定种子生成，同一维度的签名在不同进程间一致
+struct SimHasher {
+    hyperplanes: Vec<Vec<f32>>,
+}
+
+impl SimHasher {
+    fn new(dimension: usize) -> Self {
+        let mut state = 0x9E37_79B9_7F4A_7C15u64 ^ dimension as u64;
+        let mut next = || {
+            // splitmix64，映射到 [-1, 1)
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+            (z >> 11) as f32 / (1u64 << 53) as f32 * 2.0 - 1.0
+        };
+        let hyperplanes = (0..SIGNATURE_BITS).map(|_| (0..dimension).map(|_| next()).collect()).collect();
+        Self { hyperplanes }
+    }
+
+    fn signature(&self, vector: &[f32]) -> u64 {
+        self.hyperplanes.iter().enumerate().fold(0u64, |signature, (bit, plane)| {
+            let projection: f32 = plane.iter().zip(vector).map(|(p, v)| p * v).sum();
+            if projection >= 0.0 { signature | (1 << bit) } else { signature }
+        })
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// 找出余弦相似度不低于 `threshold` 的近似重复簇（按保留文档ID排序），维度不同的向量互不比较
+pub fn find_clusters(items: &[DedupItem<'_>], threshold: f32) -> Vec<DuplicateCluster> {
+    let mut parents: Vec<usize> = (0..items.len()).collect();
+    let mut min_similarity: HashMap<usize, f32> = HashMap::new();
+    let mut hashers: HashMap<usize, SimHasher> = HashMap::new();
+    let mut buckets: HashMap<(usize, usize, u64), Vec<usize>> = HashMap::new();
+
+    let band_bits = SIGNATURE_BITS / BANDS;
+    for (i, item) in items.iter().enumerate() {
+        if item.vector.is_empty() {
+            continue;
+        }
+        let dimension = item.vector.len();
+        let signature = hashers.entry(dimension).or_insert_with(|| SimHasher::new(dimension)).signature(item.vector);
+        for band in 0..BANDS {
+            let key = (signature >> (band * band_bits)) & ((1 << band_bits) - 1);
+            buckets.entry((dimension, band, key)).or_default().push(i);
+        }
+    }
+
+    let mut compared = std::collections::HashSet::new();
+    let mut edges = Vec::new();
+    for members in buckets.values() {
+        for (n, &a) in members.iter().enumerate() {
+            for &b in &members[n + 1..] {
+                if !compared.insert((a, b)) {
+                    continue;
+                }
+                let similarity = cosine_similarity(items[a].vector, items[b].vector);
+                if similarity >= threshold {
+                    edges.push((a, b, similarity));
+                }
+            }
+        }
+    }
+
+    for (a, b, similarity) in edges {
+        let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+        let merged = [root_a, root_b].iter()
+            .filter_map(|root| min_similarity.remove(root))
+            .fold(similarity, f32::min);
+        let root = root_a.min(root_b);
+        parents[root_a] = root;
+        parents[root_b] = root;
+        min_similarity.insert(root, merged);
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..items.len() {
+        let root = find(&mut parents, i);
+        groups.entry(root).or_default().push(i);
+    }
+    let mut clusters: Vec<DuplicateCluster> = groups.into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| {
+            let keep = *members.iter()
+                .min_by(|&&a, &&b| items[b].content_len.cmp(&items[a].content_len).then_with(|| items[a].id.cmp(items[b].id)))
+                .expect("簇至少有两个文档");
+            let mut duplicates: Vec<String> = members.iter().filter(|&&i| i != keep).map(|&i| items[i].id.to_string()).collect();
+            duplicates.sort();
+            DuplicateCluster {
+                keep: items[keep].id.to_string(),
+                duplicates,
+                min_similarity: min_similarity[&root],
+            }
+        })
+        .collect();
+    clusters.sort_by(|a, b| a.keep.cmp(&b.keep));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_near_duplicates_are_clustered() {
+        let vectors: Vec<(&str, Vec<f32>, usize)> = vec![
+            ("a", vec![1.0, 0.0, 0.0, 0.2], 100),
+            ("a-mirror", vec![0.98, 0.01, 0.0, 0.21], 120),
+            ("a-copy", vec![1.0, 0.0, 0.01, 0.2], 80),
+            ("b", vec![0.0, 1.0, 0.0, 0.0], 100),
+            ("c", vec![0.0, 0.0, 1.0, 0.0], 100),
+            ("other-dim", vec![1.0, 0.0, 0.0], 100),
+        ];
+        let items: Vec<DedupItem> = vectors.iter()
+            .map(|(id, vector, content_len)| DedupItem { id: *id, vector: vector.as_slice(), content_len: *content_len })
+            .collect();
+
+        let clusters = find_clusters(&items, DEFAULT_DEDUP_THRESHOLD);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].keep, "a-mirror");
+        assert_eq!(clusters[0].duplicates, vec!["a".to_string(), "a-copy".to_string()]);
+        assert!(clusters[0].min_similarity >= DEFAULT_DEDUP_THRESHOLD);
+
+        assert!(find_clusters(&items, 1.01).is_empty());
+    }
+}
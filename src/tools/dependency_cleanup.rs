@@ -0,0 +1,124 @@
+//! 项目已移除依赖的缓存清理
+//!
+//! 定期重新扫描时，如果某个依赖已从项目的所有清单中移除，它的缓存文档和已处理版本标记就不再有用。
+//! 这些依赖记录在工作区缓存目录的 `removed_dependencies.json` 中（命令行 `cache stats` 等
+//! 其他进程也能看到），按配置 `[dependency_cleanup]` 只报告可回收空间或直接清除。
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::cache_eviction::split_package_version_key;
+use super::project_context::{canonical_language, normalize_package};
+
+/// 已移除依赖记录文件名
+pub const REMOVED_DEPENDENCIES_FILE: &str = "removed_dependencies.json";
+
+/// 一个已从项目清单中移除的依赖
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovedDependency {
+    pub language: String,
+    pub package_name: String,
+    /// 首次发现被移除的时间
+    pub detected_at: DateTime<Utc>,
+}
+
+/// 依赖键：`规范语言名/归一化包名`，与文档的语言、包名写法无关
+pub fn dependency_key(language: &str, package_name: &str) -> String {
+    format!("{}/{}", canonical_language(language), normalize_package(package_name))
+}
+
+/// 从包版本键（`语言/包名/版本`）得到依赖键，包名中可以含 `/`（如 `@types/node`）
+pub fn dependency_key_of_package_version(key: &str) -> Option<String> {
+    let (language, package_name, _version) = split_package_version_key(key)?;
+    Some(dependency_key(language, package_name))
+}
+
+/// 读取已移除依赖记录（依赖键 -> 依赖），文件不存在或损坏时返回空
+pub fn load(data_dir: &Path) -> BTreeMap<String, RemovedDependency> {
+    let path = data_dir.join(REMOVED_DEPENDENCIES_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            tracing::warn!("忽略损坏的已移除依赖记录 {}: {}", path.display(), e);
+            BTreeMap::new()
+        }),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+/// 保存已移除依赖记录，记录为空时删除文件
+pub fn save(data_dir: &Path, removed: &BTreeMap<String, RemovedDependency>) -> Result<()> {
+    let path = data_dir.join(REMOVED_DEPENDENCIES_FILE);
+    if removed.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    std::fs::create_dir_all(data_dir)?;
+    std::fs::write(&path, serde_json::to_string_pretty(removed)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependency_keys_and_persistence() {
+        assert_eq!(dependency_key("cargo", "Serde_Json"), "rust/serde-json");
+        assert_eq!(dependency_key_of_package_version("typescript/@types/node/20.1.0").as_deref(), Some("javascript/@types/node"));
+        assert_eq!(dependency_key_of_package_version("rust"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load(dir.path()).is_empty());
+        let removed = BTreeMap::from([(
+            dependency_key("rust", "tokio"),
+            RemovedDependency { language: "rust".to_string(), package_name: "tokio".to_string(), detected_at: Utc::now() },
+        )]);
+        save(dir.path(), &removed).unwrap();
+        assert_eq!(load(dir.path()), removed);
+        save(dir.path(), &BTreeMap::new()).unwrap();
+        assert!(!dir.path().join(REMOVED_DEPENDENCIES_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn test_removed_dependency_is_reported_and_purged() {
+        use crate::tools::base::MCPTool;
+        use crate::tools::embedder::testing::MockEmbedder;
+        use crate::tools::project_context::ProjectProfile;
+        use crate::tools::vector_docs_tool::VectorDocsTool;
+        use serde_json::json;
+        use std::collections::BTreeSet;
+        use std::sync::Arc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tool = VectorDocsTool::open_local(dir.path().to_path_buf()).unwrap().with_embedder(Arc::new(MockEmbedder::new(32)));
+        for (package_name, content) in [("tokio", "异步运行时 spawn 任务"), ("serde", "Serialize 序列化")] {
+            tool.execute(json!({
+                "action": "store", "title": package_name, "content": content,
+                "language": "rust", "package_name": package_name, "version": "1.0.0"
+            })).await.unwrap();
+            tool.mark_package_version_as_processed("rust", package_name, "1.0.0").unwrap();
+        }
+        // 只有已处理标记、没有文档的版本也要清除
+        tool.mark_package_version_as_processed("rust", "tokio", "0.2.0").unwrap();
+
+        let removed = BTreeSet::from([("rust".to_string(), "tokio".to_string())]);
+        assert_eq!(tool.record_removed_dependencies(&removed, &ProjectProfile::default()).unwrap(), 1);
+        let status = tool.get_system_status();
+        let dependencies = &status["removed_dependencies"]["dependencies"];
+        assert_eq!(dependencies[0]["package_name"], "tokio");
+        assert_eq!(dependencies[0]["package_versions"], json!(["rust/tokio/0.2.0", "rust/tokio/1.0.0"]));
+        assert!(status["removed_dependencies"]["reclaimable_bytes"].as_u64().unwrap() > 0);
+
+        let purged = tool.execute(json!({ "action": "purge_removed_dependencies" })).await.unwrap();
+        assert_eq!((purged["package_versions_removed"].as_u64(), purged["documents_removed"].as_u64()), (Some(2), Some(1)));
+        assert!(!tool.has_processed_package_version("rust", "tokio", "1.0.0").unwrap());
+        assert!(!tool.has_processed_package_version("rust", "tokio", "0.2.0").unwrap());
+        assert!(tool.has_processed_package_version("rust", "serde", "1.0.0").unwrap());
+        assert_eq!(tool.get_system_status()["removed_dependencies"]["reclaimable_bytes"], 0);
+    }
+}
@@ -0,0 +1,300 @@
+//! DevDocs 文档集导入
+//!
+//! DevDocs (<https://devdocs.io>) 为各语言参考手册提供了整理好的离线数据：
+//! `index.json` 列出条目（名称、路径、分类），`db.json` 为 路径 -> HTML 页面。
+//! 直接导入这些数据即可获得完整的语言参考（JS、CSS、Python 标准库等），无需逐页爬取。
+//!
+//! 数据源可以是文档集 slug（如 `javascript`、`python~3.12`），从 DevDocs 下载；
+//! 也可以是包含 `index.json` 和 `db.json` 的本地目录，用于完全离线导入。
+
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use scraper::{ElementRef, Html};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::tools::base::{FileDocumentFragment, FileType};
+
+/// DevDocs 文档数据下载地址
+pub const DEVDOCS_DOCUMENTS_URL: &str = "https://documents.devdocs.io";
+
+/// 文档集中的条目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DevDocsEntry {
+    pub name: String,
+    /// 页面路径，可能带有 `#锚点`
+    pub path: String,
+    /// 条目分类，如 `Array`、`Built-in Functions`
+    #[serde(rename = "type", default)]
+    pub entry_type: String,
+}
+
+/// `index.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DevDocsIndex {
+    #[serde(default)]
+    pub entries: Vec<DevDocsEntry>,
+}
+
+/// 已加载的 DevDocs 文档集
+#[derive(Debug, Clone)]
+pub struct DevDocsSet {
+    pub slug: String,
+    pub index: DevDocsIndex,
+    /// 页面路径 -> HTML
+    pub pages: HashMap<String, String>,
+}
+
+/// DevDocs 导入报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevDocsImportReport {
+    pub slug: String,
+    pub package: String,
+    pub pages_in_set: usize,
+    pub fragments_stored: usize,
+    pub fragments_rejected: usize,
+}
+
+impl DevDocsSet {
+    /// 从 slug 或本地目录加载文档集
+    pub async fn load(client: &Client, source: &str) -> Result<Self> {
+        let local = Path::new(source);
+        if local.is_dir() {
+            let index = tokio::fs::read_to_string(local.join("index.json")).await
+                .map_err(|e| anyhow!("读取 DevDocs index.json 失败: {} - {}", source, e))?;
+            let db = tokio::fs::read_to_string(local.join("db.json")).await
+                .map_err(|e| anyhow!("读取 DevDocs db.json 失败: {} - {}", source, e))?;
+            let slug = local.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(source)
+                .to_string();
+            return Self::from_json(&slug, &index, &db);
+        }
+
+        if !is_valid_slug(source) {
+            return Err(anyhow!("无效的 DevDocs 文档集: {}（应为 slug，如 python~3.12，或本地目录）", source));
+        }
+        let index = fetch_text(client, &format!("{}/{}/index.json", DEVDOCS_DOCUMENTS_URL, source)).await?;
+        let db = fetch_text(client, &format!("{}/{}/db.json", DEVDOCS_DOCUMENTS_URL, source)).await?;
+        Self::from_json(source, &index, &db)
+    }
+
+    pub fn from_json(slug: &str, index_json: &str, db_json: &str) -> Result<Self> {
+        let index: DevDocsIndex = serde_json::from_str(index_json)
+            .map_err(|e| anyhow!("DevDocs index.json 格式无效: {}", e))?;
+        let pages: HashMap<String, String> = serde_json::from_str(db_json)
+            .map_err(|e| anyhow!("DevDocs db.json 格式无效: {}", e))?;
+        if pages.is_empty() {
+            return Err(anyhow!("DevDocs 文档集 {} 不包含任何页面", slug));
+        }
+        Ok(Self { slug: slug.to_string(), index, pages })
+    }
+
+    /// 文档集对应的 (语言, 包名, 版本)
+    ///
+    /// `python~3.12` -> (`python`, `python`, `3.12`)；没有版本后缀时版本为 `latest`。
+    pub fn identity(&self) -> (String, String, String) {
+        let (name, version) = match self.slug.split_once('~') {
+            Some((name, version)) => (name, version.replace('_', "-")),
+            None => (self.slug.as_str(), "latest".to_string()),
+        };
+        let language = match name {
+            "javascript" | "node" | "dom" | "express" | "react" | "vue" => "javascript",
+            "typescript" => "typescript",
+            "python" | "django" | "flask" | "numpy" | "pandas" => "python",
+            "rust" => "rust",
+            "go" => "go",
+            "openjdk" | "spring_boot" => "java",
+            "dart" | "flutter" => "dart",
+            "css" | "html" | "http" => "web",
+            other => other,
+        };
+        (language.to_string(), name.to_string(), version)
+    }
+
+    /// 每个页面转换为一个文档片段，标题和分类来自 `index.json`
+    pub fn to_fragments(&self) -> Vec<FileDocumentFragment> {
+        let (language, package_name, version) = self.identity();
+
+        // 页面路径 -> 第一个指向该页面的条目
+        let mut entries_by_page: HashMap<&str, &DevDocsEntry> = HashMap::new();
+        for entry in &self.index.entries {
+            let page = entry.path.split('#').next().unwrap_or(&entry.path);
+            entries_by_page.entry(page).or_insert(entry);
+        }
+
+        let mut paths: Vec<&String> = self.pages.keys().collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let text = html_to_text(&self.pages[path]);
+                if text.trim().is_empty() {
+                    return None;
+                }
+                let entry = entries_by_page.get(path.as_str());
+                let title = entry.map_or(path.as_str(), |e| e.name.as_str());
+                let mut fragment = FileDocumentFragment::new(
+                    language.clone(),
+                    package_name.clone(),
+                    version.clone(),
+                    format!("{}.md", path),
+                    format!("# {}\n\n{}", title, text),
+                ).with_source_url(format!("https://devdocs.io/{}/{}", self.slug, path));
+                fragment.file_type = FileType::Documentation;
+                if let Some(entry) = entry.filter(|e| !e.entry_type.is_empty()) {
+                    fragment.hierarchy_path.push(entry.entry_type.clone());
+                }
+                Some(fragment)
+            })
+            .collect()
+    }
+}
+
+async fn fetch_text(client: &Client, url: &str) -> Result<String> {
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("下载 DevDocs 数据失败: {} ({})", url, response.status()));
+    }
+    Ok(response.text().await?)
+}
+
+fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty() && slug.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '~' | '_' | '.' | '-'))
+}
+
+/// 把 DevDocs 页面 HTML 转为带标题和代码块的纯文本
+pub fn html_to_text(html: &str) -> String {
+    let document = Html::parse_fragment(html);
+    let mut out = String::new();
+    render_children(document.root_element(), &mut out);
+
+    // 合并多余空行
+    let mut text = String::with_capacity(out.len());
+    let mut blank_lines = 0;
+    for line in out.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        text.push_str(line);
+        text.push('\n');
+    }
+    text.trim().to_string()
+}
+
+fn render_element(element: ElementRef, out: &mut String) {
+    let name = element.value().name();
+    match name {
+        "script" | "style" | "nav" => {}
+        "pre" => {
+            let code: String = element.text().collect();
+            let language = element.value().attr("data-language").unwrap_or("");
+            out.push_str(&format!("\n\n```{}\n{}\n```\n\n", language, code.trim_end()));
+        }
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse::<usize>().unwrap_or(1);
+            let mut heading = String::new();
+            render_children(element, &mut heading);
+            out.push_str(&format!("\n\n{} {}\n\n", "#".repeat(level + 1), heading.trim()));
+        }
+        "code" => {
+            let code: String = element.text().collect();
+            out.push('`');
+            out.push_str(code.trim());
+            out.push('`');
+        }
+        "li" => {
+            out.push_str("\n- ");
+            render_children(element, out);
+        }
+        "br" => out.push('\n'),
+        "p" | "div" | "section" | "dl" | "dt" | "dd" | "ul" | "ol" | "table" | "tr" | "blockquote" => {
+            out.push_str("\n\n");
+            render_children(element, out);
+            out.push_str("\n\n");
+        }
+        _ => render_children(element, out),
+    }
+}
+
+fn render_children(element: ElementRef, out: &mut String) {
+    for child in element.children() {
+        if let Some(child_element) = ElementRef::wrap(child) {
+            render_element(child_element, out);
+        } else if let Some(text) = child.value().as_text() {
+            let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if collapsed.is_empty() {
+                continue;
+            }
+            if text.starts_with(char::is_whitespace) && !out.ends_with(['\n', ' ']) {
+                out.push(' ');
+            }
+            out.push_str(&collapsed);
+            if text.ends_with(char::is_whitespace) {
+                out.push(' ');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INDEX: &str = r#"{
+        "entries": [
+            {"name": "Array.prototype.map()", "path": "global_objects/array/map", "type": "Array"},
+            {"name": "Array.prototype.map() callback", "path": "global_objects/array/map#callback", "type": "Array"},
+            {"name": "JSON.parse()", "path": "global_objects/json/parse", "type": "JSON"}
+        ],
+        "types": []
+    }"#;
+
+    const DB: &str = r#"{
+        "global_objects/array/map": "<h1>Array.prototype.map()</h1><p>The <code>map()</code> method creates a new array.</p><pre data-language=\"js\">const doubled = [1, 2].map((x) =&gt; x * 2);</pre><ul><li>callbackFn</li><li>thisArg</li></ul>",
+        "global_objects/json/parse": "<h1>JSON.parse()</h1><p>Parses a JSON string.</p><script>alert(1)</script>",
+        "index": ""
+    }"#;
+
+    #[test]
+    fn test_html_to_text_keeps_code_and_lists() {
+        let set = DevDocsSet::from_json("javascript", INDEX, DB).unwrap();
+        let text = html_to_text(&set.pages["global_objects/array/map"]);
+        assert!(text.starts_with("## Array.prototype.map()"));
+        assert!(text.contains("The `map()` method creates a new array."));
+        assert!(text.contains("```js\nconst doubled = [1, 2].map((x) => x * 2);\n```"));
+        assert!(text.contains("- callbackFn\n- thisArg"));
+        assert!(!html_to_text(&set.pages["global_objects/json/parse"]).contains("alert"));
+    }
+
+    #[test]
+    fn test_to_fragments_uses_index_titles() {
+        let set = DevDocsSet::from_json("javascript", INDEX, DB).unwrap();
+        let fragments = set.to_fragments();
+        // 空页面被跳过
+        assert_eq!(fragments.len(), 2);
+        let map = &fragments[0];
+        assert_eq!(map.language, "javascript");
+        assert_eq!(map.version, "latest");
+        assert_eq!(map.file_path, "global_objects/array/map.md");
+        assert!(map.content.starts_with("# Array.prototype.map()"));
+        assert_eq!(map.hierarchy_path.last().map(String::as_str), Some("Array"));
+        assert!(matches!(map.file_type, FileType::Documentation));
+    }
+
+    #[test]
+    fn test_identity_and_slug_validation() {
+        let set = DevDocsSet::from_json("python~3.12", INDEX, DB).unwrap();
+        assert_eq!(set.identity(), ("python".to_string(), "python".to_string(), "3.12".to_string()));
+        assert!(is_valid_slug("node~20_lts"));
+        assert!(!is_valid_slug("../etc/passwd"));
+        assert!(DevDocsSet::from_json("css", INDEX, "{}").is_err());
+    }
+}
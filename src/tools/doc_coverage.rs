@@ -0,0 +1,311 @@
+//! 项目依赖的文档覆盖图
+//!
+//! 对当前项目清单中的每个依赖，按已缓存文档的类型给出覆盖级别：没有文档、只有元数据
+//! （问答、配置等）、有 API 文档、API 文档和示例都有。用户据此知道哪些依赖还缺文档，
+//! 后台缓存据此跳过已有 API 文档的包。
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use anyhow::Result;
+
+use crate::errors::MCPError;
+use crate::tools::base::{MCPTool, Schema, SchemaObject, SchemaString};
+use crate::tools::dependency_cleanup::dependency_key;
+use crate::tools::project_context::canonical_language;
+use crate::tools::vector_docs_tool::{CachedPackage, VectorDocsTool};
+
+/// 视为 API 文档的文档类型
+const API_DOC_TYPES: &[&str] = &["api", "documentation", "reference", "source"];
+
+/// 视为示例的文档类型
+const EXAMPLE_DOC_TYPES: &[&str] = &["example", "examples", "tutorial"];
+
+/// 文档覆盖级别，按完整程度排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageLevel {
+    /// 没有任何缓存文档
+    None,
+    /// 只有元数据类文档（问答、配置等），没有 API 文档
+    MetadataOnly,
+    /// 有 API 文档，没有示例
+    ApiDocs,
+    /// API 文档和示例都有
+    Examples,
+}
+
+impl CoverageLevel {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "none" => Some(CoverageLevel::None),
+            "metadata_only" | "metadata" => Some(CoverageLevel::MetadataOnly),
+            "api_docs" | "api" => Some(CoverageLevel::ApiDocs),
+            "examples" => Some(CoverageLevel::Examples),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CoverageLevel::None => "none",
+            CoverageLevel::MetadataOnly => "metadata_only",
+            CoverageLevel::ApiDocs => "api_docs",
+            CoverageLevel::Examples => "examples",
+        }
+    }
+
+    /// 按已缓存的文档类型判断覆盖级别
+    pub fn from_doc_types<'a>(doc_types: impl IntoIterator<Item = &'a String>) -> Self {
+        let (mut has_docs, mut has_api, mut has_examples) = (false, false, false);
+        for doc_type in doc_types {
+            let doc_type = doc_type.to_lowercase();
+            has_docs = true;
+            has_api |= API_DOC_TYPES.contains(&doc_type.as_str());
+            has_examples |= EXAMPLE_DOC_TYPES.contains(&doc_type.as_str());
+        }
+        match (has_docs, has_api, has_examples) {
+            (false, _, _) => CoverageLevel::None,
+            (true, true, true) => CoverageLevel::Examples,
+            (true, true, false) => CoverageLevel::ApiDocs,
+            (true, false, _) => CoverageLevel::MetadataOnly,
+        }
+    }
+
+    /// 达到完整覆盖还缺少的内容
+    pub fn missing(&self) -> Vec<&'static str> {
+        match self {
+            CoverageLevel::None | CoverageLevel::MetadataOnly => vec!["api_docs", "examples"],
+            CoverageLevel::ApiDocs => vec!["examples"],
+            CoverageLevel::Examples => Vec::new(),
+        }
+    }
+}
+
+/// 单个已缓存版本的覆盖情况
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionCoverage {
+    pub version: String,
+    pub level: CoverageLevel,
+    pub document_count: usize,
+    /// 文档类型 -> 文档数
+    pub doc_types: BTreeMap<String, usize>,
+}
+
+/// 单个依赖的覆盖情况
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyCoverage {
+    pub language: String,
+    pub package_name: String,
+    /// 所有已缓存版本合计的覆盖级别
+    pub level: CoverageLevel,
+    pub missing: Vec<&'static str>,
+    pub versions: Vec<VersionCoverage>,
+}
+
+/// 已缓存包按依赖键（规范语言名/归一化包名）分组
+fn group_by_dependency(packages: Vec<CachedPackage>) -> HashMap<String, Vec<CachedPackage>> {
+    let mut grouped: HashMap<String, Vec<CachedPackage>> = HashMap::new();
+    for package in packages {
+        grouped.entry(dependency_key(&package.language, &package.package_name)).or_default().push(package);
+    }
+    grouped
+}
+
+fn dependency_coverage(language: &str, package_name: &str, packages: &[CachedPackage]) -> DependencyCoverage {
+    let versions: Vec<VersionCoverage> = packages.iter()
+        .map(|package| VersionCoverage {
+            version: package.version.clone(),
+            level: CoverageLevel::from_doc_types(package.doc_types.keys()),
+            document_count: package.document_count,
+            doc_types: package.doc_types.clone(),
+        })
+        .collect();
+    let level = CoverageLevel::from_doc_types(packages.iter().flat_map(|package| package.doc_types.keys()));
+    DependencyCoverage {
+        language: language.to_string(),
+        package_name: package_name.to_string(),
+        level,
+        missing: level.missing(),
+        versions,
+    }
+}
+
+/// 计算一组依赖 (语言, 包名) 的覆盖情况，顺序与输入相同
+pub fn coverage_map(vector_tool: &VectorDocsTool, dependencies: &[(String, String)]) -> Result<Vec<DependencyCoverage>> {
+    let grouped = group_by_dependency(vector_tool.list_cached_packages()?);
+    Ok(dependencies.iter()
+        .map(|(language, package_name)| {
+            let packages = grouped.get(&dependency_key(language, package_name)).map(Vec::as_slice).unwrap_or(&[]);
+            dependency_coverage(language, package_name, packages)
+        })
+        .collect())
+}
+
+/// 单个包（所有已缓存版本合计）的覆盖级别
+pub fn package_coverage(vector_tool: &VectorDocsTool, language: &str, package_name: &str) -> Result<CoverageLevel> {
+    Ok(coverage_map(vector_tool, &[(language.to_string(), package_name.to_string())])?
+        .first()
+        .map_or(CoverageLevel::None, |coverage| coverage.level))
+}
+
+/// 列出当前项目每个依赖的文档覆盖情况
+pub struct DocCoverageTool {
+    vector_tool: Arc<VectorDocsTool>,
+    schema: Schema,
+}
+
+impl DocCoverageTool {
+    pub fn new(vector_tool: Arc<VectorDocsTool>) -> Self {
+        Self {
+            vector_tool,
+            schema: Self::create_schema(),
+        }
+    }
+
+    fn create_schema() -> Schema {
+        let mut props = HashMap::new();
+        props.insert("language".to_string(), Schema::String(SchemaString {
+            description: Some("编程语言，如 rust、python（可选，按语言过滤）".to_string()),
+            enum_values: None,
+        }));
+        props.insert("level".to_string(), Schema::String(SchemaString {
+            description: Some("只列出该覆盖级别的依赖（可选）".to_string()),
+            enum_values: Some(vec!["none".to_string(), "metadata_only".to_string(), "api_docs".to_string(), "examples".to_string()]),
+        }));
+        Schema::Object(SchemaObject {
+            required: Vec::new(),
+            properties: props,
+            description: Some("项目依赖的文档覆盖图".to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl MCPTool for DocCoverageTool {
+    fn name(&self) -> &str {
+        "doc_coverage"
+    }
+
+    fn description(&self) -> &str {
+        "列出当前项目清单中的每个依赖及其已缓存文档的覆盖级别：none（没有文档）、metadata_only（只有问答等元数据）、api_docs（有 API 文档）、examples（API 文档和示例都有），以及每个已缓存版本的文档类型统计。"
+    }
+
+    fn parameters_schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let language = params["language"].as_str().map(str::trim).filter(|s| !s.is_empty());
+        let level = match params["level"].as_str().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(value) => Some(CoverageLevel::parse(value)
+                .ok_or_else(|| MCPError::InvalidParameter(format!("未知的覆盖级别: {}", value)))?),
+            None => None,
+        };
+
+        let Some(profile) = self.vector_tool.project_profile().filter(|profile| profile.has_dependencies()) else {
+            return Ok(json!({
+                "status": "no_project",
+                "message": "没有检测到项目清单中的依赖",
+            }));
+        };
+        let dependencies: Vec<(String, String)> = profile.dependency_list()
+            .into_iter()
+            .filter(|(dep_language, _)| language.map_or(true, |l| canonical_language(l) == *dep_language))
+            .collect();
+
+        let vector_tool = Arc::clone(&self.vector_tool);
+        let coverage = tokio::task::spawn_blocking(move || coverage_map(&vector_tool, &dependencies)).await??;
+
+        let mut summary: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for dependency in &coverage {
+            *summary.entry(dependency.level.as_str()).or_insert(0) += 1;
+        }
+        let dependencies: Vec<&DependencyCoverage> = coverage.iter()
+            .filter(|dependency| level.map_or(true, |l| dependency.level == l))
+            .collect();
+        Ok(json!({
+            "status": "success",
+            "total_dependencies": coverage.len(),
+            "summary": summary,
+            "count": dependencies.len(),
+            "dependencies": dependencies,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::embedder::testing::MockEmbedder;
+    use crate::tools::environment_detector::DetectionReport;
+    use crate::tools::project_context::ProjectProfile;
+
+    #[test]
+    fn test_coverage_level_from_doc_types() {
+        let level = |types: &[&str]| {
+            let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+            CoverageLevel::from_doc_types(&types)
+        };
+        assert_eq!(level(&[]), CoverageLevel::None);
+        assert_eq!(level(&["qa"]), CoverageLevel::MetadataOnly);
+        assert_eq!(level(&["example"]), CoverageLevel::MetadataOnly);
+        assert_eq!(level(&["documentation", "qa"]), CoverageLevel::ApiDocs);
+        assert_eq!(level(&["api", "example"]), CoverageLevel::Examples);
+        assert_eq!(CoverageLevel::ApiDocs.missing(), vec!["examples"]);
+    }
+
+    #[tokio::test]
+    async fn test_doc_coverage_lists_project_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let vector_tool = Arc::new(
+            VectorDocsTool::open_local(dir.path().to_path_buf()).unwrap().with_embedder(Arc::new(MockEmbedder::new(32))),
+        );
+        let tool = DocCoverageTool::new(Arc::clone(&vector_tool));
+        assert_eq!(tool.execute(json!({})).await.unwrap()["status"], "no_project");
+
+        let mut report = DetectionReport {
+            detected_languages: HashMap::new(),
+            scan_duration_ms: 0,
+            scan_paths: Vec::new(),
+            total_files_scanned: 0,
+            dependencies: HashMap::new(),
+            locked_versions: HashMap::new(),
+        };
+        report.dependencies.insert(
+            "rust".to_string(),
+            vec!["tokio".to_string(), "serde_json".to_string(), "reqwest".to_string()],
+        );
+        vector_tool.set_project_profile(Some(ProjectProfile::from_report(&report)));
+
+        for (package_name, doc_type, content) in [
+            ("tokio", "api", "tokio::spawn 在运行时上启动异步任务"),
+            ("tokio", "example", "使用 #[tokio::main] 编写异步 main 函数的示例"),
+            ("serde-json", "qa", "serde_json 反序列化失败时如何定位出错字段"),
+        ] {
+            vector_tool.execute(json!({
+                "action": "store", "content": content, "doc_type": doc_type,
+                "language": "rust", "package_name": package_name, "version": "1.0.0"
+            })).await.unwrap();
+        }
+
+        let result = tool.execute(json!({})).await.unwrap();
+        assert_eq!(result["total_dependencies"], 3);
+        assert_eq!(result["summary"], json!({ "none": 1, "metadata_only": 1, "examples": 1 }));
+        let levels: HashMap<String, String> = result["dependencies"].as_array().unwrap().iter()
+            .map(|d| (d["package_name"].as_str().unwrap().to_string(), d["level"].as_str().unwrap().to_string()))
+            .collect();
+        assert_eq!(levels["tokio"], "examples");
+        assert_eq!(levels["serde-json"], "metadata_only");
+        assert_eq!(levels["reqwest"], "none");
+
+        let missing = tool.execute(json!({ "level": "none" })).await.unwrap();
+        assert_eq!(missing["dependencies"][0]["package_name"], "reqwest");
+        assert_eq!(missing["dependencies"][0]["missing"], json!(["api_docs", "examples"]));
+        assert_eq!(tool.execute(json!({ "language": "python" })).await.unwrap()["total_dependencies"], 0);
+        assert!(tool.execute(json!({ "level": "partial" })).await.is_err());
+        assert_eq!(package_coverage(&vector_tool, "cargo", "Tokio").unwrap(), CoverageLevel::Examples);
+    }
+}
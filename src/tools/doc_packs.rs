@@ -0,0 +1,337 @@
+use anyhow::{anyhow, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::tools::crypto::{from_hex, sha256_hex, to_hex};
+use crate::tools::vector_docs_tool::DocumentRecord;
+
+/// 文档包格式版本（2 起签名改为 Ed25519）
+pub const DOC_PACK_FORMAT_VERSION: u32 = 2;
+
+/// 文档包清单
+///
+/// 文档包是预先抓取并完成向量化的某个包版本的文档集合，
+/// 向量与生成它的嵌入模型绑定，只能导入到使用相同模型的向量库中。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocPackManifest {
+    pub format_version: u32,
+    pub language: String,
+    pub package_name: String,
+    pub version: String,
+    /// 生成向量所用的嵌入模型
+    pub embedding_model: String,
+    /// 向量维度
+    pub embedding_dimension: usize,
+    pub document_count: usize,
+    /// 文档负载的SHA-256（十六进制）
+    pub payload_sha256: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// 文档包文件（JSON信封）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocPackEnvelope {
+    pub manifest: DocPackManifest,
+    /// 序列化后的文档列表（JSON字符串），保持原始字节以便校验
+    pub payload: String,
+    /// 对清单的 Ed25519 签名（十六进制）
+    pub signature: Option<String>,
+    /// 签名者公钥（十六进制），必须在受信任公钥列表中
+    #[serde(default)]
+    pub signer: Option<String>,
+}
+
+/// 文档包的信任配置
+///
+/// 默认只接受由受信任公钥签名的文档包；未签名的文档包需要显式开启 `allow_unsigned`。
+#[derive(Debug, Clone, Default)]
+pub struct DocPackTrust {
+    pub trusted_keys: Vec<VerifyingKey>,
+    pub allow_unsigned: bool,
+}
+
+impl DocPackTrust {
+    /// 从环境变量读取：`DOC_PACK_TRUSTED_KEYS`（逗号分隔的十六进制公钥）和 `DOC_PACK_ALLOW_UNSIGNED`
+    pub fn from_env() -> Result<Self> {
+        let trusted_keys = std::env::var("DOC_PACK_TRUSTED_KEYS").unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(parse_verifying_key)
+            .collect::<Result<Vec<_>>>()?;
+        let allow_unsigned = std::env::var("DOC_PACK_ALLOW_UNSIGNED")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        Ok(Self { trusted_keys, allow_unsigned })
+    }
+
+    /// 只信任给定公钥
+    pub fn trusting(key: VerifyingKey) -> Self {
+        Self { trusted_keys: vec![key], allow_unsigned: false }
+    }
+}
+
+/// 校验通过的文档包
+#[derive(Debug, Clone)]
+pub struct DocPack {
+    pub manifest: DocPackManifest,
+    pub documents: Vec<DocumentRecord>,
+    /// 是否经过签名校验
+    pub signature_verified: bool,
+}
+
+/// 文档包导入报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocPackImportReport {
+    pub package: String,
+    pub embedding_model: String,
+    pub documents_in_pack: usize,
+    pub documents_imported: usize,
+    pub signature_verified: bool,
+}
+
+impl DocPack {
+    /// 从本地路径或 http(s) URL 加载并校验文档包
+    ///
+    /// 信任配置见 [`DocPackTrust::from_env`]。
+    pub async fn load(source: &str) -> Result<Self> {
+        let raw = if source.starts_with("http://") || source.starts_with("https://") {
+            let response = reqwest::get(source).await?;
+            if !response.status().is_success() {
+                return Err(anyhow!("下载文档包失败: {} ({})", source, response.status()));
+            }
+            response.text().await?
+        } else {
+            tokio::fs::read_to_string(Path::new(source)).await
+                .map_err(|e| anyhow!("读取文档包失败: {} - {}", source, e))?
+        };
+
+        Self::from_json(&raw, &DocPackTrust::from_env()?)
+    }
+
+    /// 解析并校验文档包JSON
+    pub fn from_json(raw: &str, trust: &DocPackTrust) -> Result<Self> {
+        let envelope: DocPackEnvelope = serde_json::from_str(raw)
+            .map_err(|e| anyhow!("文档包格式无效: {}", e))?;
+        let manifest = envelope.manifest;
+
+        if manifest.format_version > DOC_PACK_FORMAT_VERSION {
+            return Err(anyhow!(
+                "不支持的文档包格式版本: {} (当前支持 {})",
+                manifest.format_version, DOC_PACK_FORMAT_VERSION
+            ));
+        }
+
+        let payload_digest = sha256_hex(envelope.payload.as_bytes());
+        if payload_digest != manifest.payload_sha256 {
+            return Err(anyhow!("文档包内容校验失败: 摘要不匹配"));
+        }
+
+        let signature_verified = match envelope.signature.as_deref() {
+            Some(signature) => {
+                if manifest.format_version < 2 {
+                    return Err(anyhow!("文档包使用已废弃的 HMAC 签名，请用 Ed25519 密钥重新导出"));
+                }
+                verify_manifest(&manifest, signature, envelope.signer.as_deref(), &trust.trusted_keys)?;
+                true
+            }
+            None if trust.allow_unsigned => false,
+            None => return Err(anyhow!("文档包未签名，拒绝导入（设置 DOC_PACK_ALLOW_UNSIGNED=1 可显式允许）")),
+        };
+
+        let documents: Vec<DocumentRecord> = serde_json::from_str(&envelope.payload)
+            .map_err(|e| anyhow!("文档包负载解析失败: {}", e))?;
+        if documents.len() != manifest.document_count {
+            return Err(anyhow!(
+                "文档包文档数量不一致: 清单 {}，实际 {}",
+                manifest.document_count, documents.len()
+            ));
+        }
+        if let Some(doc) = documents.iter().find(|d| d.embedding.len() != manifest.embedding_dimension) {
+            return Err(anyhow!(
+                "文档 {} 向量维度 {} 与清单声明的 {} 不一致",
+                doc.id, doc.embedding.len(), manifest.embedding_dimension
+            ));
+        }
+        // 签名只覆盖清单，文档归属必须与清单一致，防止借合法清单写入其他包的文档
+        if let Some(doc) = documents.iter().find(|d| {
+            (&d.language, &d.package_name, &d.version) != (&manifest.language, &manifest.package_name, &manifest.version)
+        }) {
+            return Err(anyhow!(
+                "文档 {} 属于 {}/{}@{}，与清单声明的 {}/{}@{} 不一致",
+                doc.id, doc.language, doc.package_name, doc.version,
+                manifest.language, manifest.package_name, manifest.version
+            ));
+        }
+
+        Ok(Self { manifest, documents, signature_verified })
+    }
+
+    /// 构建文档包JSON（用于生成和分发预置文档包）
+    pub fn build_json(
+        language: &str,
+        package_name: &str,
+        version: &str,
+        embedding_model: &str,
+        documents: &[DocumentRecord],
+        signing_key: Option<&SigningKey>,
+    ) -> Result<String> {
+        let payload = serde_json::to_string(documents)?;
+        let manifest = DocPackManifest {
+            format_version: DOC_PACK_FORMAT_VERSION,
+            language: language.to_string(),
+            package_name: package_name.to_string(),
+            version: version.to_string(),
+            embedding_model: embedding_model.to_string(),
+            embedding_dimension: documents.first().map(|d| d.embedding.len()).unwrap_or(0),
+            document_count: documents.len(),
+            payload_sha256: sha256_hex(payload.as_bytes()),
+            created_at: chrono::Utc::now(),
+        };
+        let signature = signing_key.map(|key| sign_manifest(&manifest, key)).transpose()?;
+        let signer = signing_key.map(|key| to_hex(key.verifying_key().as_bytes()));
+        Ok(serde_json::to_string(&DocPackEnvelope { manifest, payload, signature, signer })?)
+    }
+
+    /// 检查文档包是否与当前嵌入模型和向量库维度兼容
+    ///
+    /// `store_dimension` 为目标向量库已有向量的维度，空库时传 `None`。
+    pub fn ensure_compatible(&self, embedding_model: &str, store_dimension: Option<usize>) -> Result<()> {
+        if self.manifest.embedding_model != embedding_model {
+            return Err(anyhow!(
+                "文档包使用的嵌入模型 {} 与当前模型 {} 不一致，无法导入",
+                self.manifest.embedding_model, embedding_model
+            ));
+        }
+        if let Some(dimension) = store_dimension.filter(|d| *d != self.manifest.embedding_dimension) {
+            return Err(anyhow!(
+                "文档包向量维度 {} 与向量库维度 {} 不一致，无法导入",
+                self.manifest.embedding_dimension, dimension
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 环境变量 `DOC_PACK_SIGNING_KEY` 配置的 Ed25519 签名私钥（十六进制 32 字节种子）
+pub fn signing_key_from_env() -> Result<Option<SigningKey>> {
+    match std::env::var("DOC_PACK_SIGNING_KEY").ok().filter(|k| !k.is_empty()) {
+        Some(hex) => {
+            let seed: [u8; 32] = from_hex(hex.trim())
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| anyhow!("DOC_PACK_SIGNING_KEY 必须是 64 位十六进制的 Ed25519 私钥种子"))?;
+            Ok(Some(SigningKey::from_bytes(&seed)))
+        }
+        None => Ok(None),
+    }
+}
+
+fn parse_verifying_key(hex: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = from_hex(hex)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("无效的 Ed25519 公钥: {}", hex))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| anyhow!("无效的 Ed25519 公钥 {}: {}", hex, e))
+}
+
+/// 使用 Ed25519 对清单签名
+fn sign_manifest(manifest: &DocPackManifest, key: &SigningKey) -> Result<String> {
+    let message = serde_json::to_vec(manifest)?;
+    Ok(to_hex(&key.sign(&message).to_bytes()))
+}
+
+/// 校验清单签名，签名者必须是受信任公钥之一
+fn verify_manifest(manifest: &DocPackManifest, signature: &str, signer: Option<&str>, trusted_keys: &[VerifyingKey]) -> Result<()> {
+    if trusted_keys.is_empty() {
+        return Err(anyhow!("未配置 DOC_PACK_TRUSTED_KEYS，无法校验文档包签名"));
+    }
+    let signature: [u8; 64] = from_hex(signature)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("文档包签名格式无效"))?;
+    let signature = Signature::from_bytes(&signature);
+    let message = serde_json::to_vec(manifest)?;
+
+    let candidates: Vec<&VerifyingKey> = match signer {
+        Some(signer) => {
+            let signer = parse_verifying_key(signer)?;
+            trusted_keys.iter().filter(|key| **key == signer).collect()
+        }
+        None => trusted_keys.iter().collect(),
+    };
+    if candidates.is_empty() {
+        return Err(anyhow!("文档包签名者不在受信任公钥列表中"));
+    }
+    if candidates.iter().any(|key| key.verify_strict(&message, &signature).is_ok()) {
+        Ok(())
+    } else {
+        Err(anyhow!("文档包签名校验失败"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn sample_document() -> DocumentRecord {
+        DocumentRecord {
+            id: "rust/serde/1.0.0/lib.rs".to_string(),
+            content: "Serde is a framework for serializing and deserializing Rust data structures.".to_string(),
+            title: "lib".to_string(),
+            language: "rust".to_string(),
+            package_name: "serde".to_string(),
+            version: "1.0.0".to_string(),
+            doc_type: "documentation".to_string(),
+            metadata: HashMap::new(),
+            embedding: vec![0.1, 0.2, 0.3],
+        }
+    }
+
+    #[test]
+    fn test_build_and_verify_signed_pack() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let json = DocPack::build_json("rust", "serde", "1.0.0", "model-a", &[sample_document()], Some(&key)).unwrap();
+
+        let pack = DocPack::from_json(&json, &DocPackTrust::trusting(key.verifying_key())).unwrap();
+        assert!(pack.signature_verified);
+        assert_eq!(pack.documents.len(), 1);
+        assert!(pack.ensure_compatible("model-a", None).is_ok());
+        assert!(pack.ensure_compatible("model-a", Some(3)).is_ok());
+        assert!(pack.ensure_compatible("model-a", Some(768)).is_err());
+        assert!(pack.ensure_compatible("model-b", None).is_err());
+
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        assert!(DocPack::from_json(&json, &DocPackTrust::trusting(other.verifying_key())).is_err());
+        assert!(DocPack::from_json(&json, &DocPackTrust::default()).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_pack_requires_opt_in() {
+        let json = DocPack::build_json("rust", "serde", "1.0.0", "model-a", &[sample_document()], None).unwrap();
+
+        assert!(DocPack::from_json(&json, &DocPackTrust::default()).is_err(), "未签名的文档包默认应被拒绝");
+        let allow = DocPackTrust { allow_unsigned: true, ..DocPackTrust::default() };
+        assert!(!DocPack::from_json(&json, &allow).unwrap().signature_verified);
+    }
+
+    #[test]
+    fn test_tampered_payload_rejected() {
+        let allow = DocPackTrust { allow_unsigned: true, ..DocPackTrust::default() };
+        let json = DocPack::build_json("rust", "serde", "1.0.0", "model-a", &[sample_document()], None).unwrap();
+        let mut envelope: DocPackEnvelope = serde_json::from_str(&json).unwrap();
+        envelope.payload = envelope.payload.replace("Serde", "Evil");
+        let tampered = serde_json::to_string(&envelope).unwrap();
+
+        assert!(DocPack::from_json(&tampered, &allow).is_err());
+    }
+
+    #[test]
+    fn test_documents_must_match_manifest_package() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let foreign = DocumentRecord { package_name: "tokio".to_string(), ..sample_document() };
+        let json = DocPack::build_json("rust", "serde", "1.0.0", "model-a", &[sample_document(), foreign], Some(&key)).unwrap();
+
+        let err = DocPack::from_json(&json, &DocPackTrust::trusting(key.verifying_key())).unwrap_err();
+        assert!(err.to_string().contains("tokio"));
+    }
+}
@@ -2,6 +2,9 @@ use anyhow::{anyhow, Result};
 use tracing::{info, warn, debug, error};
 
 use crate::tools::base::{FileDocumentFragment, MCPTool};
+use crate::tools::platform;
+use crate::tools::site_auth::SiteAuthenticated;
+use crate::tools::provenance;
 use crate::tools::vector_docs_tool::VectorDocsTool;
 
 /// 内容提取配置
@@ -13,6 +16,8 @@ pub struct ExtractionConfig {
     pub quality_threshold: f32,
     pub preserve_code_blocks: bool,
     pub extract_links: bool,
+    /// 按行列保留表格（参数表等），并从正文中剔除表格文本
+    pub extract_tables: bool,
 }
 
 /// 增强内容提取器 - 简化但功能完整的实现
@@ -29,6 +34,7 @@ pub struct ExtractedContent {
     pub code_blocks: Vec<CodeBlock>,
     pub api_docs: Vec<ApiDoc>,
     pub links: Vec<Link>,
+    pub tables: Vec<ExtractedTable>,
 }
 
 /// 代码块
@@ -52,11 +58,65 @@ pub struct Link {
     pub url: String,
 }
 
+/// 表格（保留行列结构）
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExtractedTable {
+    pub caption: Option<String>,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ExtractedTable {
+    /// 渲染为 Markdown 表格；没有表头时以首行作为表头
+    pub fn to_markdown(&self) -> String {
+        let (headers, rows) = match (self.headers.is_empty(), self.rows.split_first()) {
+            (true, Some((first, rest))) => (first.clone(), rest),
+            _ => (self.headers.clone(), self.rows.as_slice()),
+        };
+        let columns = headers.len().max(rows.iter().map(Vec::len).max().unwrap_or(0));
+        let render_row = |cells: &[String]| {
+            let cells: Vec<String> = (0..columns)
+                .map(|i| cells.get(i).map(|c| c.replace('|', "\\|")).unwrap_or_default())
+                .collect();
+            format!("| {} |\n", cells.join(" | "))
+        };
+
+        let mut markdown = String::new();
+        if let Some(caption) = &self.caption {
+            markdown.push_str(&format!("**{}**\n\n", caption));
+        }
+        markdown.push_str(&render_row(&headers));
+        markdown.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+        for row in rows {
+            markdown.push_str(&render_row(row));
+        }
+        markdown
+    }
+
+    /// 以表头为键转换为 JSON 对象数组
+    pub fn to_json_rows(&self) -> Vec<serde_json::Value> {
+        self.rows.iter()
+            .map(|row| {
+                let object: serde_json::Map<String, serde_json::Value> = row.iter()
+                    .enumerate()
+                    .map(|(i, cell)| {
+                        let key = self.headers.get(i).cloned().unwrap_or_else(|| format!("column_{}", i + 1));
+                        (key, serde_json::Value::String(cell.clone()))
+                    })
+                    .collect();
+                serde_json::Value::Object(object)
+            })
+            .collect()
+    }
+}
+
 impl EnhancedContentExtractor {
     pub async fn new(config: ExtractionConfig) -> Result<Self> {
+        // 保留站点登录后下发的会话 Cookie
         let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(30))
             .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .cookie_store(true)
             .build()?;
         
         Ok(Self {
@@ -69,7 +129,7 @@ impl EnhancedContentExtractor {
         info!("🔍 使用增强提取器处理URL: {}", url);
         
         // 获取网页内容
-        let response = self.client.get(url).send().await?;
+        let response = self.client.get(url).site_auth(url).send().await?;
         let html = response.text().await?;
         
         // 解析HTML
@@ -89,6 +149,13 @@ impl EnhancedContentExtractor {
         
         // 提取链接
         let links = self.extract_links(&document, url);
+
+        // 提取表格
+        let tables = if self.config.extract_tables {
+            self.extract_tables(&document)
+        } else {
+            Vec::new()
+        };
         
         Ok(ExtractedContent {
             title,
@@ -96,6 +163,7 @@ impl EnhancedContentExtractor {
             code_blocks,
             api_docs,
             links,
+            tables,
         })
     }
     
@@ -118,7 +186,7 @@ impl EnhancedContentExtractor {
         for selector_str in &content_selectors {
             if let Ok(selector) = scraper::Selector::parse(selector_str) {
                 if let Some(element) = document.select(&selector).next() {
-                    let text = element.text().collect::<Vec<_>>().join(" ");
+                    let text = self.element_text(element);
                     if text.len() >= self.config.min_content_length {
                         return self.clean_text(&text);
                     }
@@ -129,12 +197,83 @@ impl EnhancedContentExtractor {
         // 如果没有找到特定内容区域，提取body内容
         let body_selector = scraper::Selector::parse("body").unwrap();
         if let Some(body) = document.select(&body_selector).next() {
-            let text = body.text().collect::<Vec<_>>().join(" ");
+            let text = self.element_text(body);
             return self.clean_text(&text);
         }
         
         "No content found".to_string()
     }
+
+    /// 元素文本；启用表格提取时跳过表格内的文本，避免行列被压平成一串单词
+    fn element_text(&self, element: scraper::ElementRef) -> String {
+        if !self.config.extract_tables {
+            return element.text().collect::<Vec<_>>().join(" ");
+        }
+        element.descendants()
+            .filter_map(|node| node.value().as_text().map(|text| (node, text)))
+            .filter(|(node, _)| {
+                !node.ancestors().any(|a| a.value().as_element().map_or(false, |e| e.name() == "table"))
+            })
+            .map(|(_, text)| &**text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn extract_tables(&self, document: &scraper::Html) -> Vec<ExtractedTable> {
+        let table_selector = scraper::Selector::parse("table").unwrap();
+        let caption_selector = scraper::Selector::parse("caption").unwrap();
+        let row_selector = scraper::Selector::parse("tr").unwrap();
+        let cell_selector = scraper::Selector::parse("th, td").unwrap();
+
+        let mut tables = Vec::new();
+        for table in document.select(&table_selector) {
+            // 嵌套表格由外层表格的单元格文本覆盖，单独处理时跳过
+            let nested = table.ancestors().any(|a| a.value().as_element().map_or(false, |e| e.name() == "table"));
+            if nested {
+                continue;
+            }
+
+            let mut headers = Vec::new();
+            let mut rows = Vec::new();
+            for row in table.select(&row_selector) {
+                let in_nested = row.ancestors()
+                    .filter(|a| a.value().as_element().map_or(false, |e| e.name() == "table"))
+                    .count() > 1;
+                if in_nested {
+                    continue;
+                }
+                let mut cells = Vec::new();
+                let mut all_header = true;
+                for cell in row.select(&cell_selector) {
+                    all_header &= cell.value().name() == "th";
+                    let text = self.clean_text(&cell.text().collect::<Vec<_>>().join(" "));
+                    // colspan 展开为重复单元格，保持列对齐
+                    let span = cell.value().attr("colspan").and_then(|c| c.parse::<usize>().ok()).unwrap_or(1).clamp(1, 20);
+                    cells.extend(std::iter::repeat(text).take(span));
+                }
+                if cells.is_empty() {
+                    continue;
+                }
+                if all_header && headers.is_empty() && rows.is_empty() {
+                    headers = cells;
+                } else {
+                    rows.push(cells);
+                }
+            }
+
+            // 单列或没有数据行的表格多为排版用途，不作为数据表
+            let columns = headers.len().max(rows.iter().map(Vec::len).max().unwrap_or(0));
+            if rows.is_empty() || columns < 2 {
+                continue;
+            }
+            let caption = table.select(&caption_selector)
+                .next()
+                .map(|c| self.clean_text(&c.text().collect::<String>()))
+                .filter(|c| !c.is_empty());
+            tables.push(ExtractedTable { caption, headers, rows });
+        }
+        tables
+    }
     
     fn extract_code_blocks(&self, document: &scraper::Html) -> Vec<CodeBlock> {
         let mut code_blocks = Vec::new();
@@ -263,8 +402,11 @@ pub struct DocumentProcessor {
 impl DocumentProcessor {
     /// 创建新的文档处理器
     pub async fn new() -> Result<Self> {
-        let vector_tool = VectorDocsTool::new()?;
-        
+        Self::with_vector_tool(VectorDocsTool::new()?)
+    }
+
+    /// 使用指定的向量工具创建文档处理器（测试中使用临时目录下的存储）
+    pub fn with_vector_tool(vector_tool: VectorDocsTool) -> Result<Self> {
         // 创建工作目录
         let work_dir = std::env::temp_dir().join("grape-mcp-docs");
         std::fs::create_dir_all(&work_dir)?;
@@ -288,6 +430,7 @@ impl DocumentProcessor {
             quality_threshold: 0.7,
             preserve_code_blocks: true,
             extract_links: true,
+            extract_tables: true,
         };
 
         let extractor = EnhancedContentExtractor::new(config).await?;
@@ -308,6 +451,15 @@ impl DocumentProcessor {
             }
         }
 
+        // 添加表格（保留行列结构）
+        if !result.tables.is_empty() {
+            content.push_str("\n\n## 表格\n\n");
+            for table in &result.tables {
+                content.push_str(&table.to_markdown());
+                content.push('\n');
+            }
+        }
+
         // 添加API文档
         if !result.api_docs.is_empty() {
             content.push_str("\n\n## API文档\n\n");
@@ -427,13 +579,17 @@ impl DocumentProcessor {
                     result["language"].as_str()
                 ) {
                     if lang == language {
-                        let fragment = FileDocumentFragment::new(
+                        let mut fragment = FileDocumentFragment::new(
                             language.to_string(),
                             package_name.to_string(),
                             version.to_string(),
                             format!("{}.md", title.replace(" ", "_")),
                             content.to_string(),
                         );
+                        // 保留入库时记录的来源，便于调用方注明出处
+                        let metadata = &result["metadata"];
+                        fragment.source_url = metadata[provenance::SOURCE_URL_METADATA_KEY].as_str().map(str::to_string);
+                        fragment.license = metadata[provenance::LICENSE_METADATA_KEY].as_str().map(str::to_string);
                         fragments.push(fragment);
                     }
                 }
@@ -538,7 +694,7 @@ impl DocumentProcessor {
         info!("使用go CLI生成文档: {} {}", package_name, version);
         
         // 检查go是否可用
-        let go_check = tokio::process::Command::new("go")
+        let go_check = platform::command("go")
             .args(&["version"])
             .output()
             .await;
@@ -548,16 +704,16 @@ impl DocumentProcessor {
         }
         
         // 使用go doc命令
-        let doc_output = tokio::process::Command::new("go")
+        let doc_output = platform::command("go")
             .args(&["doc", package_name])
             .output()
             .await?;
             
         if !doc_output.status.success() {
-            return Err(anyhow!("go doc失败: {}", String::from_utf8_lossy(&doc_output.stderr)));
+            return Err(anyhow!("go doc失败: {}", platform::decode_output(&doc_output.stderr)));
         }
         
-        let doc_content = String::from_utf8_lossy(&doc_output.stdout);
+        let doc_content = platform::decode_output(&doc_output.stdout);
         
         let fragment = FileDocumentFragment::new(
             "go".to_string(),
@@ -575,7 +731,7 @@ impl DocumentProcessor {
         info!("使用pkg.go.dev API生成文档: {} {}", package_name, version);
         
         let url = format!("https://pkg.go.dev/{}", package_name);
-        let response = self.client.get(&url).send().await?;
+        let response = self.client.get(&url).site_auth(&url).send().await?;
         
         if !response.status().is_success() {
             return Err(anyhow!("Go包不存在: {}", package_name));
@@ -590,7 +746,7 @@ impl DocumentProcessor {
             version.to_string(),
             "pkg_go_dev.md".to_string(),
             format!("# Go Package {}\n\nVersion: {}\n\n{}\n\nSource: pkg.go.dev", package_name, version, cleaned_content),
-        );
+        ).with_source_url(url);
         
         Ok(vec![fragment])
     }
@@ -613,7 +769,7 @@ impl DocumentProcessor {
         info!("使用cargo CLI生成文档: {} {}", package_name, version);
         
         // 检查cargo是否可用
-        let cargo_check = tokio::process::Command::new("cargo")
+        let cargo_check = platform::command("cargo")
             .args(&["--version"])
             .output()
             .await;
@@ -623,8 +779,9 @@ impl DocumentProcessor {
         }
         
         // 创建临时目录
-        let temp_dir = std::env::temp_dir().join(format!("rust_docs_{}", uuid::Uuid::new_v4()));
-        tokio::fs::create_dir_all(&temp_dir).await?;
+        // 临时工程目录在函数返回（包括出错）时删除
+        let temp_project = platform::TempProject::create("rust-docs")?;
+        let temp_dir = temp_project.path();
         
         // 创建简单的Cargo.toml
         let cargo_content = format!(
@@ -644,14 +801,14 @@ edition = "2021"
         tokio::fs::write(temp_dir.join("src").join("main.rs"), "fn main() {}").await?;
         
         // 生成文档
-        let doc_output = tokio::process::Command::new("cargo")
+        let doc_output = platform::command("cargo")
             .args(&["doc", "--no-deps"])
-            .current_dir(&temp_dir)
+            .current_dir(temp_dir)
             .output()
             .await?;
             
         if !doc_output.status.success() {
-            return Err(anyhow!("cargo doc失败: {}", String::from_utf8_lossy(&doc_output.stderr)));
+            return Err(anyhow!("cargo doc失败: {}", platform::decode_output(&doc_output.stderr)));
         }
         
         let fragment = FileDocumentFragment::new(
@@ -662,9 +819,6 @@ edition = "2021"
             format!("# Rust Crate {}\n\nVersion: {}\n\nDocumentation generated with cargo doc.\n\nSource: cargo CLI", package_name, version),
         );
         
-        // 清理临时目录
-        let _ = tokio::fs::remove_dir_all(&temp_dir).await;
-        
         Ok(vec![fragment])
     }
     
@@ -678,7 +832,7 @@ edition = "2021"
             format!("https://docs.rs/{}/{}", package_name, version)
         };
         
-        let response = self.client.get(&url).send().await?;
+        let response = self.client.get(&url).site_auth(&url).send().await?;
         
         if !response.status().is_success() {
             return Err(anyhow!("Rust crate不存在: {}", package_name));
@@ -693,7 +847,7 @@ edition = "2021"
             version.to_string(),
             "docs_rs.md".to_string(),
             format!("# Rust Crate {}\n\nVersion: {}\n\n{}\n\nSource: docs.rs", package_name, version, cleaned_content),
-        );
+        ).with_source_url(url);
         
         Ok(vec![fragment])
     }
@@ -741,7 +895,7 @@ edition = "2021"
     /// 尝试使用pip CLI
     async fn try_pip_cli(&self, package_name: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查pip是否可用
-        let pip_check = tokio::process::Command::new("pip")
+        let pip_check = platform::command("pip")
             .args(&["--version"])
             .output()
             .await;
@@ -751,26 +905,26 @@ edition = "2021"
         }
         
         // 使用pip show命令获取包信息
-        let show_output = tokio::process::Command::new("pip")
+        let show_output = platform::command("pip")
             .args(&["show", package_name])
             .output()
             .await?;
             
         if !show_output.status.success() {
-            return Err(anyhow!("pip show失败: {}", String::from_utf8_lossy(&show_output.stderr)));
+            return Err(anyhow!("pip show失败: {}", platform::decode_output(&show_output.stderr)));
         }
         
-        let show_content = String::from_utf8_lossy(&show_output.stdout);
+        let show_content = platform::decode_output(&show_output.stdout);
         
         // 尝试获取包的依赖信息
-        let deps_output = tokio::process::Command::new("pip")
+        let deps_output = platform::command("pip")
             .args(&["show", package_name, "--verbose"])
             .output()
             .await;
             
         let deps_info = if let Ok(output) = deps_output {
             if output.status.success() {
-                String::from_utf8_lossy(&output.stdout).to_string()
+                platform::decode_output(&output.stdout)
             } else {
                 show_content.to_string()
             }
@@ -795,7 +949,7 @@ edition = "2021"
     /// 尝试使用poetry CLI
     async fn try_poetry_cli(&self, package_name: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查poetry是否可用
-        let poetry_check = tokio::process::Command::new("poetry")
+        let poetry_check = platform::command("poetry")
             .args(&["--version"])
             .output()
             .await;
@@ -805,16 +959,16 @@ edition = "2021"
         }
         
         // 使用poetry show命令获取包信息
-        let show_output = tokio::process::Command::new("poetry")
+        let show_output = platform::command("poetry")
             .args(&["show", package_name])
             .output()
             .await?;
             
         if !show_output.status.success() {
-            return Err(anyhow!("poetry show失败: {}", String::from_utf8_lossy(&show_output.stderr)));
+            return Err(anyhow!("poetry show失败: {}", platform::decode_output(&show_output.stderr)));
         }
         
-        let show_content = String::from_utf8_lossy(&show_output.stdout);
+        let show_content = platform::decode_output(&show_output.stdout);
         
         let content = format!(
             "# Python Package {}\n\nVersion: {}\n\n## Poetry Information\n\n```\n{}\n```\n\n## Installation\n\n### Poetry\n```bash\npoetry add {}=={}\n```\n\n### pip\n```bash\npip install {}=={}\n```\n\nSource: Poetry CLI",
@@ -833,7 +987,7 @@ edition = "2021"
     /// 尝试使用conda CLI
     async fn try_conda_cli(&self, package_name: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查conda是否可用
-        let conda_check = tokio::process::Command::new("conda")
+        let conda_check = platform::command("conda")
             .args(&["--version"])
             .output()
             .await;
@@ -843,13 +997,13 @@ edition = "2021"
         }
         
         // 使用conda search命令查找包
-        let search_output = tokio::process::Command::new("conda")
+        let search_output = platform::command("conda")
             .args(&["search", package_name])
             .output()
             .await?;
             
         let search_content = if search_output.status.success() {
-            String::from_utf8_lossy(&search_output.stdout).to_string()
+            platform::decode_output(&search_output.stdout)
         } else {
             "Package not found in conda repositories".to_string()
         };
@@ -871,7 +1025,7 @@ edition = "2021"
     /// 尝试使用pydoc CLI
     async fn try_pydoc_cli(&self, package_name: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查python是否可用
-        let python_check = tokio::process::Command::new("python")
+        let python_check = platform::command("python")
             .args(&["--version"])
             .output()
             .await;
@@ -881,23 +1035,23 @@ edition = "2021"
         }
         
         // 尝试使用pydoc获取模块文档
-        let pydoc_output = tokio::process::Command::new("python")
+        let pydoc_output = platform::command("python")
             .args(&["-m", "pydoc", package_name])
             .output()
             .await?;
             
         let pydoc_content = if pydoc_output.status.success() {
-            String::from_utf8_lossy(&pydoc_output.stdout).to_string()
+            platform::decode_output(&pydoc_output.stdout)
         } else {
             // 如果pydoc失败，尝试导入模块获取基本信息
-            let import_output = tokio::process::Command::new("python")
+            let import_output = platform::command("python")
                 .args(&["-c", &format!("import {}; print({}.__doc__ or 'No documentation available')", package_name, package_name)])
                 .output()
                 .await;
                 
             if let Ok(output) = import_output {
                 if output.status.success() {
-                    String::from_utf8_lossy(&output.stdout).to_string()
+                    platform::decode_output(&output.stdout)
                 } else {
                     format!("Module {} documentation not available", package_name)
                 }
@@ -925,7 +1079,7 @@ edition = "2021"
         info!("使用PyPI API生成文档: {} {}", package_name, version);
         
         let url = format!("https://pypi.org/pypi/{}/json", package_name);
-        let response = self.client.get(&url).send().await?;
+        let response = self.client.get(&url).site_auth(&url).send().await?;
         
         if !response.status().is_success() {
             return Err(anyhow!("Python包不存在: {}", package_name));
@@ -934,13 +1088,16 @@ edition = "2021"
         let json_content: serde_json::Value = response.json().await?;
         let description = json_content["info"]["description"].as_str().unwrap_or("No description available");
         
-        let fragment = FileDocumentFragment::new(
+        let mut fragment = FileDocumentFragment::new(
             "python".to_string(),
             package_name.to_string(),
             version.to_string(),
             "pypi_docs.md".to_string(),
             format!("# Python Package {}\n\nVersion: {}\n\n{}\n\nSource: PyPI API", package_name, version, description),
-        );
+        ).with_source_url(format!("https://pypi.org/project/{}/", package_name));
+        fragment.license = json_content["info"]["license"].as_str()
+            .filter(|license| !license.is_empty() && license.len() <= 64)
+            .map(str::to_string);
         
         Ok(vec![fragment])
     }
@@ -1016,7 +1173,7 @@ edition = "2021"
     /// 尝试使用npm CLI
     async fn try_npm_cli(&self, package_name: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查npm是否可用
-        let npm_check = tokio::process::Command::new("npm")
+        let npm_check = platform::command("npm")
             .args(&["--version"])
             .output()
             .await;
@@ -1026,26 +1183,26 @@ edition = "2021"
         }
         
         // 使用npm view命令获取包信息
-        let view_output = tokio::process::Command::new("npm")
+        let view_output = platform::command("npm")
             .args(&["view", package_name, "--json"])
             .output()
             .await?;
             
         if !view_output.status.success() {
-            return Err(anyhow!("npm view失败: {}", String::from_utf8_lossy(&view_output.stderr)));
+            return Err(anyhow!("npm view失败: {}", platform::decode_output(&view_output.stderr)));
         }
         
-        let view_content = String::from_utf8_lossy(&view_output.stdout);
+        let view_content = platform::decode_output(&view_output.stdout);
         
         // 尝试获取包的依赖信息
-        let deps_output = tokio::process::Command::new("npm")
+        let deps_output = platform::command("npm")
             .args(&["view", package_name, "dependencies", "--json"])
             .output()
             .await;
             
         let deps_info = if let Ok(output) = deps_output {
             if output.status.success() {
-                format!("\n\n## Dependencies\n\n```json\n{}\n```", String::from_utf8_lossy(&output.stdout))
+                format!("\n\n## Dependencies\n\n```json\n{}\n```", platform::decode_output(&output.stdout))
             } else {
                 String::new()
             }
@@ -1070,7 +1227,7 @@ edition = "2021"
     /// 尝试使用yarn CLI
     async fn try_yarn_cli(&self, package_name: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查yarn是否可用
-        let yarn_check = tokio::process::Command::new("yarn")
+        let yarn_check = platform::command("yarn")
             .args(&["--version"])
             .output()
             .await;
@@ -1080,16 +1237,16 @@ edition = "2021"
         }
         
         // 使用yarn info命令获取包信息
-        let info_output = tokio::process::Command::new("yarn")
+        let info_output = platform::command("yarn")
             .args(&["info", package_name, "--json"])
             .output()
             .await?;
             
         if !info_output.status.success() {
-            return Err(anyhow!("yarn info失败: {}", String::from_utf8_lossy(&info_output.stderr)));
+            return Err(anyhow!("yarn info失败: {}", platform::decode_output(&info_output.stderr)));
         }
         
-        let info_content = String::from_utf8_lossy(&info_output.stdout);
+        let info_content = platform::decode_output(&info_output.stdout);
         
         let content = format!(
             "# NPM Package {}\n\nVersion: {}\n\n## Yarn Information\n\n```json\n{}\n```\n\n## Installation\n\n### Yarn\n```bash\nyarn add {}@{}\n```\n\n### npm\n```bash\nnpm install {}@{}\n```\n\nSource: Yarn CLI",
@@ -1108,7 +1265,7 @@ edition = "2021"
     /// 尝试使用pnpm CLI
     async fn try_pnpm_cli(&self, package_name: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查pnpm是否可用
-        let pnpm_check = tokio::process::Command::new("pnpm")
+        let pnpm_check = platform::command("pnpm")
             .args(&["--version"])
             .output()
             .await;
@@ -1118,16 +1275,16 @@ edition = "2021"
         }
         
         // 使用pnpm view命令获取包信息
-        let view_output = tokio::process::Command::new("pnpm")
+        let view_output = platform::command("pnpm")
             .args(&["view", package_name, "--json"])
             .output()
             .await?;
             
         if !view_output.status.success() {
-            return Err(anyhow!("pnpm view失败: {}", String::from_utf8_lossy(&view_output.stderr)));
+            return Err(anyhow!("pnpm view失败: {}", platform::decode_output(&view_output.stderr)));
         }
         
-        let view_content = String::from_utf8_lossy(&view_output.stdout);
+        let view_content = platform::decode_output(&view_output.stdout);
         
         let content = format!(
             "# NPM Package {}\n\nVersion: {}\n\n## pnpm Information\n\n```json\n{}\n```\n\n## Installation\n\n### pnpm\n```bash\npnpm add {}@{}\n```\n\n### npm\n```bash\nnpm install {}@{}\n```\n\n### Yarn\n```bash\nyarn add {}@{}\n```\n\nSource: pnpm CLI",
@@ -1146,7 +1303,7 @@ edition = "2021"
     /// 尝试使用node CLI
     async fn try_node_cli(&self, package_name: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查node是否可用
-        let node_check = tokio::process::Command::new("node")
+        let node_check = platform::command("node")
             .args(&["--version"])
             .output()
             .await;
@@ -1161,13 +1318,13 @@ edition = "2021"
             package_name, package_name
         );
         
-        let node_output = tokio::process::Command::new("node")
+        let node_output = platform::command("node")
             .args(&["-e", &module_script])
             .output()
             .await?;
             
         let node_content = if node_output.status.success() {
-            String::from_utf8_lossy(&node_output.stdout).to_string()
+            platform::decode_output(&node_output.stdout)
         } else {
             format!("{{ \"error\": \"Module {} not found or not installed\" }}", package_name)
         };
@@ -1191,7 +1348,7 @@ edition = "2021"
         info!("使用NPM API生成文档: {} {}", package_name, version);
         
         let url = format!("https://registry.npmjs.org/{}", package_name);
-        let response = self.client.get(&url).send().await?;
+        let response = self.client.get(&url).site_auth(&url).send().await?;
         
         if !response.status().is_success() {
             return Err(anyhow!("NPM包不存在: {}", package_name));
@@ -1201,13 +1358,14 @@ edition = "2021"
         let description = json_content["description"].as_str().unwrap_or("No description available");
         let readme = json_content["readme"].as_str().unwrap_or("No README available");
         
-        let fragment = FileDocumentFragment::new(
+        let mut fragment = FileDocumentFragment::new(
             "javascript".to_string(),
             package_name.to_string(),
             version.to_string(),
             "npm_api_docs.md".to_string(),
             format!("# NPM Package {}\n\nVersion: {}\n\n## Description\n{}\n\n## README\n{}\n\nSource: NPM API", package_name, version, description, readme),
-        );
+        ).with_source_url(format!("https://www.npmjs.com/package/{}", package_name));
+        fragment.license = json_content["license"].as_str().map(str::to_string);
         
         Ok(vec![fragment])
     }
@@ -1259,7 +1417,7 @@ edition = "2021"
     /// 尝试使用mvn CLI
     async fn try_mvn_cli(&self, group_id: &str, artifact_id: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查mvn是否可用
-        let mvn_check = tokio::process::Command::new("mvn")
+        let mvn_check = platform::command("mvn")
             .args(&["--version"])
             .output()
             .await;
@@ -1269,8 +1427,9 @@ edition = "2021"
         }
         
         // 创建临时目录
-        let temp_dir = std::env::temp_dir().join(format!("java_docs_{}", uuid::Uuid::new_v4()));
-        tokio::fs::create_dir_all(&temp_dir).await?;
+        // 临时工程目录在函数返回（包括出错）时删除
+        let temp_project = platform::TempProject::create("java-docs")?;
+        let temp_dir = temp_project.path();
         
         // 创建简单的pom.xml
         let pom_content = format!(
@@ -1301,32 +1460,29 @@ edition = "2021"
         tokio::fs::write(temp_dir.join("pom.xml"), pom_content).await?;
         
         // 使用mvn dependency:resolve命令解析依赖
-        let resolve_output = tokio::process::Command::new("mvn")
+        let resolve_output = platform::command("mvn")
             .args(&["dependency:resolve", "-q"])
-            .current_dir(&temp_dir)
+            .current_dir(temp_dir)
             .output()
             .await?;
             
         if !resolve_output.status.success() {
-            return Err(anyhow!("Maven依赖解析失败: {}", String::from_utf8_lossy(&resolve_output.stderr)));
+            return Err(anyhow!("Maven依赖解析失败: {}", platform::decode_output(&resolve_output.stderr)));
         }
         
         // 使用mvn dependency:tree获取依赖树
-        let tree_output = tokio::process::Command::new("mvn")
+        let tree_output = platform::command("mvn")
             .args(&["dependency:tree", "-q"])
-            .current_dir(&temp_dir)
+            .current_dir(temp_dir)
             .output()
             .await?;
             
         let dependency_tree = if tree_output.status.success() {
-            String::from_utf8_lossy(&tree_output.stdout).to_string()
+            platform::decode_output(&tree_output.stdout)
         } else {
             "Dependency tree not available".to_string()
         };
         
-        // 清理临时目录
-        let _ = tokio::fs::remove_dir_all(&temp_dir).await;
-        
         let content = format!(
             "# Java Library {}:{}\n\nVersion: {}\n\n## Maven Information\n\nGroup ID: {}\nArtifact ID: {}\n\n## Dependency Tree\n\n```\n{}\n```\n\n## Installation\n\n### Maven\n```xml\n<dependency>\n    <groupId>{}</groupId>\n    <artifactId>{}</artifactId>\n    <version>{}</version>\n</dependency>\n```\n\n### Gradle\n```gradle\nimplementation '{}:{}:{}'\n```\n\nSource: Maven CLI",
             group_id, artifact_id, version, group_id, artifact_id, dependency_tree, group_id, artifact_id, version, group_id, artifact_id, version
@@ -1344,7 +1500,7 @@ edition = "2021"
     /// 尝试使用gradle CLI
     async fn try_gradle_cli(&self, group_id: &str, artifact_id: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查gradle是否可用
-        let gradle_check = tokio::process::Command::new("gradle")
+        let gradle_check = platform::command("gradle")
             .args(&["--version"])
             .output()
             .await;
@@ -1354,8 +1510,9 @@ edition = "2021"
         }
         
         // 创建临时目录
-        let temp_dir = std::env::temp_dir().join(format!("gradle_docs_{}", uuid::Uuid::new_v4()));
-        tokio::fs::create_dir_all(&temp_dir).await?;
+        // 临时工程目录在函数返回（包括出错）时删除
+        let temp_project = platform::TempProject::create("gradle-docs")?;
+        let temp_dir = temp_project.path();
         
         // 创建简单的build.gradle
         let build_gradle_content = format!(
@@ -1377,21 +1534,18 @@ dependencies {{
         tokio::fs::write(temp_dir.join("build.gradle"), build_gradle_content).await?;
         
         // 使用gradle dependencies命令获取依赖信息
-        let deps_output = tokio::process::Command::new("gradle")
+        let deps_output = platform::command("gradle")
             .args(&["dependencies", "--configuration", "compileClasspath", "-q"])
-            .current_dir(&temp_dir)
+            .current_dir(temp_dir)
             .output()
             .await?;
             
         let dependencies_info = if deps_output.status.success() {
-            String::from_utf8_lossy(&deps_output.stdout).to_string()
+            platform::decode_output(&deps_output.stdout)
         } else {
             "Dependencies information not available".to_string()
         };
         
-        // 清理临时目录
-        let _ = tokio::fs::remove_dir_all(&temp_dir).await;
-        
         let content = format!(
             "# Java Library {}:{}\n\nVersion: {}\n\n## Gradle Information\n\nGroup ID: {}\nArtifact ID: {}\n\n## Dependencies\n\n```\n{}\n```\n\n## Installation\n\n### Gradle\n```gradle\nimplementation '{}:{}:{}'\n```\n\n### Maven\n```xml\n<dependency>\n    <groupId>{}</groupId>\n    <artifactId>{}</artifactId>\n    <version>{}</version>\n</dependency>\n```\n\nSource: Gradle CLI",
             group_id, artifact_id, version, group_id, artifact_id, dependencies_info, group_id, artifact_id, version, group_id, artifact_id, version
@@ -1409,7 +1563,7 @@ dependencies {{
     /// 尝试使用javadoc CLI
     async fn try_javadoc_cli(&self, group_id: &str, artifact_id: &str, version: &str) -> Result<FileDocumentFragment> {
         // 检查javadoc是否可用
-        let javadoc_check = tokio::process::Command::new("javadoc")
+        let javadoc_check = platform::command("javadoc")
             .args(&["-version"])
             .output()
             .await;
@@ -1419,7 +1573,7 @@ dependencies {{
         }
         
         let javadoc_output = javadoc_check.unwrap();
-        let javadoc_version = String::from_utf8_lossy(&javadoc_output.stdout);
+        let javadoc_version = platform::decode_output(&javadoc_output.stdout);
         
         let content = format!(
             "# Java Library {}:{}\n\nVersion: {}\n\n## Javadoc Information\n\nGroup ID: {}\nArtifact ID: {}\nJavadoc Version: {}\n\n## Documentation Links\n\n- [Javadoc.io](https://javadoc.io/doc/{}/{})\n- [Maven Central](https://search.maven.org/artifact/{}/{})\n\n## Installation\n\n### Maven\n```xml\n<dependency>\n    <groupId>{}</groupId>\n    <artifactId>{}</artifactId>\n    <version>{}</version>\n</dependency>\n```\n\n### Gradle\n```gradle\nimplementation '{}:{}:{}'\n```\n\nSource: Javadoc CLI",
@@ -1449,7 +1603,7 @@ dependencies {{
         let artifact_id = parts[1];
         
         let url = format!("https://search.maven.org/solrsearch/select?q=g:{}+AND+a:{}&rows=1&wt=json", group_id, artifact_id);
-        let response = self.client.get(&url).send().await?;
+        let response = self.client.get(&url).site_auth(&url).send().await?;
         
         if !response.status().is_success() {
             return Err(anyhow!("Maven Central API请求失败"));
@@ -1472,7 +1626,7 @@ dependencies {{
             version.to_string(),
             "maven_central_docs.md".to_string(),
             format!("# Java Library {}\n\nVersion: {}\nLatest Version: {}\n\nGroup ID: {}\nArtifact ID: {}\n\nSource: Maven Central API", package_name, version, latest_version, group_id, artifact_id),
-        );
+        ).with_source_url(format!("https://central.sonatype.com/artifact/{}/{}", group_id, artifact_id));
         
         Ok(vec![fragment])
     }
@@ -1513,4 +1667,62 @@ dependencies {{
             result
         }
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extractor() -> EnhancedContentExtractor {
+        EnhancedContentExtractor {
+            client: reqwest::Client::new(),
+            config: ExtractionConfig {
+                min_content_length: 10,
+                max_content_length: 10000,
+                enable_js_rendering: false,
+                quality_threshold: 0.7,
+                preserve_code_blocks: true,
+                extract_links: false,
+                extract_tables: true,
+            },
+        }
+    }
+
+    #[test]
+    fn test_extract_parameter_table() {
+        let html = r#"<html><body><main>
+            <p>Creates a new client.</p>
+            <table>
+                <caption>Parameters</caption>
+                <thead><tr><th>Name</th><th>Type</th><th>Description</th></tr></thead>
+                <tbody>
+                    <tr><td>timeout</td><td>Duration</td><td>Request timeout | default 30s</td></tr>
+                    <tr><td>retries</td><td>u32</td><td>Retry count</td></tr>
+                    <tr><td colspan="3">Deprecated options omitted</td></tr>
+                </tbody>
+            </table>
+            <table><tr><td>layout only</td></tr></table>
+        </main></body></html>"#;
+        let document = scraper::Html::parse_document(html);
+        let extractor = extractor();
+
+        let tables = extractor.extract_tables(&document);
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!(table.caption.as_deref(), Some("Parameters"));
+        assert_eq!(table.headers, vec!["Name", "Type", "Description"]);
+        assert_eq!(table.rows[0], vec!["timeout", "Duration", "Request timeout | default 30s"]);
+        assert_eq!(table.rows[2].len(), 3);
+
+        let markdown = table.to_markdown();
+        assert!(markdown.contains("| Name | Type | Description |"));
+        assert!(markdown.contains("| --- | --- | --- |"));
+        assert!(markdown.contains("Request timeout \\| default 30s"));
+        assert_eq!(table.to_json_rows()[1]["Type"], "u32");
+
+        // 正文不再包含被压平的表格文本
+        let content = extractor.extract_main_content(&document);
+        assert!(content.contains("Creates a new client."));
+        assert!(!content.contains("Duration"));
+    }
+}
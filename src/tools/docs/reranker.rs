@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use anyhow::Result;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use reqwest::Client;
 use tracing::{debug, info, warn, error};
@@ -22,16 +23,31 @@ pub struct RerankPassage {
     pub text: String,
 }
 
+/// OpenAI 兼容重排接口（Cohere/Jina/vLLM 等 `/rerank`）的请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompatibleRerankRequest {
+    pub model: String,
+    pub query: String,
+    pub documents: Vec<String>,
+    pub top_n: usize,
+    pub return_documents: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RerankResponse {
+    /// NVIDIA NIM 返回 `rankings`，OpenAI 兼容接口返回 `results`
+    #[serde(alias = "rankings")]
     pub results: Vec<RerankResult>,
+    #[serde(default)]
     pub meta: Option<RerankMeta>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RerankResult {
     pub index: usize,
+    #[serde(alias = "logit", alias = "score")]
     pub relevance_score: f64,
+    #[serde(default)]
     pub document: Option<RerankDocument>,
 }
 
@@ -46,11 +62,31 @@ pub struct RerankMeta {
     pub billed_units: Option<HashMap<String, i32>>,
 }
 
+/// 重排接口格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RerankApi {
+    /// NVIDIA NIM：`{base}/{model}/reranking`，`query.text` + `passages`
+    Nvidia,
+    /// OpenAI 兼容：`{base}/rerank`，`query` + `documents` + `top_n`
+    OpenAiCompatible,
+}
+
+impl RerankApi {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "nvidia" | "nim" => Some(RerankApi::Nvidia),
+            "openai" | "compatible" | "cohere" | "jina" => Some(RerankApi::OpenAiCompatible),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RerankerConfig {
     pub api_key: String,
     pub api_base_url: String,
     pub model_name: String,
+    pub api: RerankApi,
     pub max_passages: usize,
     pub score_threshold: f64,
     pub timeout_seconds: u64,
@@ -62,7 +98,12 @@ impl Default for RerankerConfig {
             api_key: std::env::var("RERANK_API_KEY").unwrap_or_default(),
             api_base_url: std::env::var("RERANK_API_BASE_URL")
                 .unwrap_or_else(|_| "https://ai.api.nvidia.com/v1/retrieval".to_string()),
-            model_name: "nvidia/nv-rerankqa-mistral-4b-v3".to_string(),
+            model_name: std::env::var("RERANK_MODEL")
+                .unwrap_or_else(|_| "nvidia/nv-rerankqa-mistral-4b-v3".to_string()),
+            api: std::env::var("RERANK_API_STYLE")
+                .ok()
+                .and_then(|style| RerankApi::parse(&style))
+                .unwrap_or(RerankApi::Nvidia),
             max_passages: 10,
             score_threshold: 0.5,
             timeout_seconds: 30,
@@ -70,6 +111,16 @@ impl Default for RerankerConfig {
     }
 }
 
+/// 重排模型：按与查询的相关性为一组文本打分
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// 重排模型名称
+    fn model_name(&self) -> &str;
+
+    /// 为每个文本打分，返回按相关性降序的结果（`index` 为文本在输入中的下标），不按阈值过滤
+    async fn score(&self, query: &str, documents: Vec<String>) -> Result<Vec<RerankResult>>;
+}
+
 pub struct DocumentReranker {
     client: Client,
     config: RerankerConfig,
@@ -108,15 +159,8 @@ impl DocumentReranker {
         info!("🔍 开始重排 {} 个文档，查询: {}", documents.len(), query);
         debug!("重排参数: top_k={}, score_threshold={}", effective_top_k, self.config.score_threshold);
 
-        // 准备请求
-        let rerank_request = RerankRequest {
-            query: RerankQuery { text: query.to_string() },
-            passages: documents.into_iter().map(|text| RerankPassage { text }).collect(),
-            model: self.config.model_name.clone(),
-        };
-
-        // 发送重排请求
-        let response = self.send_rerank_request(&rerank_request).await?;
+        let document_count = documents.len();
+        let response = self.request_scores(query, documents).await?;
         
         // 过滤低分结果
         let filtered_results: Vec<RerankResult> = response.results
@@ -124,7 +168,7 @@ impl DocumentReranker {
             .filter(|result| result.relevance_score >= self.config.score_threshold)
             .collect();
 
-        info!("✅ 重排完成: {} -> {} 个高质量结果", documents.len(), filtered_results.len());
+        info!("✅ 重排完成: {} -> {} 个高质量结果", document_count, filtered_results.len());
         
         // 打印重排结果概览
         for (i, result) in filtered_results.iter().enumerate().take(3) {
@@ -134,13 +178,42 @@ impl DocumentReranker {
         Ok(filtered_results)
     }
 
+    /// 按配置的接口格式发送重排请求
+    async fn request_scores(&self, query: &str, documents: Vec<String>) -> Result<RerankResponse> {
+        match self.config.api {
+            RerankApi::Nvidia => {
+                let request = RerankRequest {
+                    query: RerankQuery { text: query.to_string() },
+                    passages: documents.into_iter().map(|text| RerankPassage { text }).collect(),
+                    model: self.config.model_name.clone(),
+                };
+                self.send_rerank_request(&request).await
+            }
+            RerankApi::OpenAiCompatible => {
+                let request = CompatibleRerankRequest {
+                    model: self.config.model_name.clone(),
+                    query: query.to_string(),
+                    top_n: documents.len(),
+                    documents,
+                    return_documents: false,
+                };
+                let url = format!("{}/rerank", self.config.api_base_url.trim_end_matches('/'));
+                self.post(&url, &request).await
+            }
+        }
+    }
+
     async fn send_rerank_request(&self, request: &RerankRequest) -> Result<RerankResponse> {
         let url = format!("{}/{}/reranking", self.config.api_base_url, self.config.model_name);
+        self.post(&url, request).await
+    }
+
+    async fn post<T: Serialize>(&self, url: &str, request: &T) -> Result<RerankResponse> {
 
         debug!("发送重排请求到: {}", url);
         
         let request_future = self.client
-            .post(&url)
+            .post(url)
             .header("Authorization", format!("Bearer {}", self.config.api_key))
             .header("Accept", "application/json")
             .header("Content-Type", "application/json")
@@ -217,6 +290,22 @@ impl DocumentReranker {
     }
 }
 
+#[async_trait]
+impl Reranker for DocumentReranker {
+    fn model_name(&self) -> &str {
+        &self.config.model_name
+    }
+
+    async fn score(&self, query: &str, documents: Vec<String>) -> Result<Vec<RerankResult>> {
+        if documents.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut results = self.request_scores(query, documents).await?.results;
+        results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(results)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,6 +316,7 @@ mod tests {
             api_key: "test_key".to_string(),
             api_base_url: "https://test.api.com".to_string(),
             model_name: "test-model".to_string(),
+            api: RerankApi::Nvidia,
             max_passages: 5,
             score_threshold: 0.7,
             timeout_seconds: 10,
@@ -237,6 +327,20 @@ mod tests {
         assert_eq!(reranker.config.max_passages, 5);
     }
     
+    #[test]
+    fn test_compatible_rerank_response_parsing() {
+        // Cohere/Jina 风格的响应没有 document 字段；NVIDIA 返回 logit
+        let compatible: RerankResponse = serde_json::from_str(
+            r#"{"id":"r1","results":[{"index":1,"relevance_score":0.9},{"index":0,"relevance_score":0.1}]}"#,
+        ).unwrap();
+        assert_eq!(compatible.results[0].index, 1);
+        assert!(compatible.results[0].document.is_none());
+
+        let nvidia: RerankResponse = serde_json::from_str(r#"{"rankings":[{"index":0,"logit":3.5}]}"#).unwrap();
+        assert_eq!(nvidia.results[0].relevance_score, 3.5);
+        assert_eq!(RerankApi::parse("OpenAI"), Some(RerankApi::OpenAiCompatible));
+    }
+
     #[test]
     fn test_rerank_request_serialization() {
         let request = RerankRequest {
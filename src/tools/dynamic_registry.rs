@@ -14,6 +14,8 @@ use super::vector_docs_tool::VectorDocsTool;
 use crate::cli::tool_installer::{ToolInstaller, ToolInstallConfig};
 use super::flutter_docs_tool::FlutterDocsTool;
 use super::enhanced_doc_processor::EnhancedDocumentProcessor;
+use super::project_context::ProjectProfile;
+use crate::config::{DependencyCleanupConfig, DependencyCleanupMode};
 
 // 新增：缓存配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -170,6 +172,10 @@ pub struct DynamicToolRegistry {
     // 新增：配置管理
     config_path: Option<std::path::PathBuf>,
     shared_doc_processor: Option<Arc<EnhancedDocumentProcessor>>,
+
+    // 已移除依赖的缓存清理：上一次扫描到的清单依赖
+    dependency_cleanup: DependencyCleanupConfig,
+    dependency_profile: Option<ProjectProfile>,
 }
 
 impl DynamicToolRegistry {
@@ -198,6 +204,9 @@ impl DynamicToolRegistry {
             // 配置管理
             config_path: None,
             shared_doc_processor: None,
+
+            dependency_cleanup: DependencyCleanupConfig::load(),
+            dependency_profile: None,
         }
     }
 
@@ -254,6 +263,10 @@ impl DynamicToolRegistry {
                 report
             }
         };
+        let profile = ProjectProfile::from_report(&detection_report);
+        if profile.has_dependencies() {
+            self.dependency_profile = Some(profile);
+        }
 
         let mut registered_tools_names = Vec::new();
         let mut failed_registrations = Vec::new();
@@ -838,6 +851,7 @@ impl DynamicToolRegistry {
         
         let new_report = self.detector.scan_environment().await?;
         self.cache_detection_report(new_report.clone()).await;
+        self.cleanup_removed_dependencies(&new_report);
         
         let new_plan = self.create_registration_plan(&new_report)?;
         
@@ -875,6 +889,54 @@ impl DynamicToolRegistry {
         Ok(changes_made)
     }
 
+    /// 与上一次扫描的清单依赖比较，按配置报告或清除项目已移除依赖的缓存文档
+    fn cleanup_removed_dependencies(&mut self, report: &DetectionReport) {
+        let current = ProjectProfile::from_report(report);
+        if !current.has_dependencies() {
+            // 没有读到任何依赖时视为扫描失败，保留上一次的结果
+            return;
+        }
+        let previous = self.dependency_profile.replace(current.clone());
+        if self.dependency_cleanup.mode == DependencyCleanupMode::Off {
+            return;
+        }
+        let (Some(previous), Some(processor)) = (previous, &self.shared_doc_processor) else {
+            return;
+        };
+
+        let removed = previous.removed_dependencies(&current);
+        let vector_tool = processor.vector_tool();
+        let pending = match vector_tool.record_removed_dependencies(&removed, &current) {
+            Ok(pending) => pending,
+            Err(e) => {
+                warn!("⚠️ 记录已移除依赖失败: {}", e);
+                return;
+            }
+        };
+        if !removed.is_empty() {
+            info!("📦 项目已移除依赖: {:?}", removed);
+        }
+        if pending == 0 {
+            return;
+        }
+
+        match self.dependency_cleanup.mode {
+            DependencyCleanupMode::Purge => match vector_tool.purge_removed_dependencies() {
+                Ok((package_versions, documents, bytes)) => {
+                    info!("🧹 已清除已移除依赖的 {} 个包版本，{} 个文档，释放 {} 字节", package_versions, documents, bytes);
+                }
+                Err(e) => warn!("⚠️ 清除已移除依赖的缓存失败: {}", e),
+            },
+            _ => match vector_tool.removed_dependency_status() {
+                Ok(status) => {
+                    let reclaimable = status["reclaimable_bytes"].as_u64().unwrap_or(0);
+                    info!("📦 {} 个已移除依赖的缓存可回收 {} 字节（vector_docs 的 purge_removed_dependencies 操作可清除）", pending, reclaimable);
+                }
+                Err(e) => warn!("⚠️ 统计已移除依赖的缓存占用失败: {}", e),
+            },
+        }
+    }
+
     // 新增：清理过期缓存
     async fn cleanup_expired_cache(&self) {
         let mut detection_cache = self.detection_cache.write().await;
@@ -1226,6 +1288,8 @@ impl DynamicRegistryBuilder {
             retry_delay_ms: self.retry_config.1,
             config_path: self.config_path,
             shared_doc_processor: self.shared_doc_processor,
+            dependency_cleanup: DependencyCleanupConfig::load(),
+            dependency_profile: None,
         }
     }
 }
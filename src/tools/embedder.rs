@@ -0,0 +1,616 @@
+//! 嵌入服务边界
+//!
+//! 向量工具只通过 [`TextEmbedder`] 生成嵌入向量，默认实现 [`NvidiaEmbedder`] 调用
+//! OpenAI 兼容的 `/embeddings` 接口（`EMBEDDING_API_BASE_URL`，默认 NVIDIA）。
+//! 设置 `EMBEDDING_PROVIDER=hash` 时改用 [`HashEmbeddingProvider`]：按文本确定性地生成向量，
+//! 不访问网络，用于离线环境和可复现的检索、去重、索引测试。
+//! 设置 `EMBEDDING_PROVIDER=local`（需要 `local-embeddings` 特性）时用本地 sentence-transformer 模型
+//! （[`crate::embeddings::LocalProvider`]）生成语义向量，不需要 API 密钥。
+//! 设置 `EMBEDDING_PROVIDER=cohere`、`jina` 或 `voyage` 时调用对应的托管服务（[`crate::embeddings::HostedProvider`]）。
+//! 设置 `EMBEDDING_TRUNCATE_DIMENSION` 时用 [`TruncatedEmbedder`] 把向量截断到前若干维并重新归一化，
+//! 适用于按 Matryoshka 表示学习训练的模型（如 text-embedding-3、nomic-embed、jina-embeddings-v3）。
+//! 单元测试中用 [`testing::MockEmbedder`] 替换，不需要 API 密钥和网络。
+//!
+//! 服务器启动时和运行期间定期调用 [`TextEmbedder::health_check`]，用一条探测文本验证 API 密钥、
+//! 模型名称和返回维度，结果显示在系统状态中，而不是等到第一次用户查询时才失败。
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+use crate::errors::MCPError;
+use crate::mcp::correlation::Correlated;
+
+/// 默认的嵌入接口地址
+pub const DEFAULT_EMBEDDING_API_BASE: &str = "https://integrate.api.nvidia.com/v1";
+
+/// 默认的嵌入模型
+pub const DEFAULT_EMBEDDING_MODEL: &str = "nvidia/nv-embedqa-mistral-7b-v2";
+
+/// 哈希嵌入的默认维度
+pub const DEFAULT_HASH_DIMENSION: usize = 384;
+
+/// 运行期间健康检查的默认间隔
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// 健康检查的探测文本
+const HEALTH_CHECK_PROBE: &str = "health check";
+
+/// 嵌入文本的用途（非对称检索模型对文档和查询使用不同编码）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputType {
+    Passage,
+    Query,
+}
+
+impl InputType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InputType::Passage => "passage",
+            InputType::Query => "query",
+        }
+    }
+}
+
+/// 嵌入服务的健康状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingHealthStatus {
+    Healthy,
+    /// 暂时不可用（限流），稍后可能恢复
+    Degraded,
+    /// 密钥、模型或维度有误，或服务不可达
+    Unhealthy,
+}
+
+/// 一次健康检查的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingHealth {
+    pub status: EmbeddingHealthStatus,
+    pub provider: String,
+    pub model: String,
+    /// 探测请求返回的向量维度
+    pub dimension: Option<usize>,
+    pub latency_ms: u64,
+    /// 降级或不健康的原因
+    pub reason: Option<String>,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl EmbeddingHealth {
+    /// 根据探测请求的结果判断健康状态
+    pub fn from_probe(provider: &str, model: &str, result: Result<Vec<Vec<f32>>>, latency: Duration) -> Self {
+        let (status, dimension, reason) = match result {
+            Ok(embeddings) => match embeddings.first() {
+                Some(embedding) if embeddings.len() == 1 && !embedding.is_empty() && embedding.iter().all(|x| x.is_finite()) => {
+                    (EmbeddingHealthStatus::Healthy, Some(embedding.len()), None)
+                }
+                _ => (EmbeddingHealthStatus::Unhealthy, None, Some("探测请求返回了空向量或无效数值".to_string())),
+            },
+            Err(e) => {
+                let (status, reason) = match e.downcast_ref::<MCPError>() {
+                    Some(MCPError::ProviderError { status: Some(401 | 403), .. }) => {
+                        (EmbeddingHealthStatus::Unhealthy, format!("API 密钥无效或无权使用模型 {}: {}", model, e))
+                    }
+                    Some(MCPError::ProviderError { status: Some(400 | 404 | 422), .. }) => {
+                        (EmbeddingHealthStatus::Unhealthy, format!("模型 {} 不存在或请求被拒绝: {}", model, e))
+                    }
+                    Some(MCPError::ProviderError { status: Some(429), .. }) => {
+                        (EmbeddingHealthStatus::Degraded, format!("嵌入服务限流: {}", e))
+                    }
+                    _ => (EmbeddingHealthStatus::Unhealthy, format!("嵌入服务不可用: {}", e)),
+                };
+                (status, None, Some(reason))
+            }
+        };
+        Self {
+            status,
+            provider: provider.to_string(),
+            model: model.to_string(),
+            dimension,
+            latency_ms: latency.as_millis() as u64,
+            reason,
+            checked_at: chrono::Utc::now(),
+        }
+    }
+
+    /// 返回维度与已存储向量的维度不一致时标记为不健康
+    pub fn with_expected_dimension(mut self, expected: Option<usize>) -> Self {
+        if let (Some(dimension), Some(expected)) = (self.dimension, expected) {
+            if dimension != expected {
+                self.status = EmbeddingHealthStatus::Unhealthy;
+                self.reason = Some(format!(
+                    "模型 {} 返回 {} 维向量，已存储的向量为 {} 维，需要重新嵌入或换回原模型",
+                    self.model, dimension, expected
+                ));
+            }
+        }
+        self
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.status == EmbeddingHealthStatus::Healthy
+    }
+}
+
+/// 文本嵌入服务
+#[async_trait]
+pub trait TextEmbedder: Send + Sync {
+    /// 提供商名称（统计信息展示用）
+    fn provider(&self) -> &str;
+
+    /// 嵌入模型名称（文档包按模型校验兼容性）
+    fn model_name(&self) -> &str;
+
+    /// 为一批文本生成嵌入向量，返回顺序与输入一致
+    async fn embed(&self, texts: &[String], input_type: InputType) -> Result<Vec<Vec<f32>>>;
+
+    /// 用一条探测文本检查 API 密钥、模型名称和返回维度；失败原因记录在结果中而不返回错误
+    async fn health_check(&self) -> EmbeddingHealth {
+        let started = Instant::now();
+        let result = self.embed(&[HEALTH_CHECK_PROBE.to_string()], InputType::Query).await;
+        EmbeddingHealth::from_probe(self.provider(), self.model_name(), result, started.elapsed())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    input: &'a [String],
+    model: &'a str,
+    input_type: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+}
+
+/// OpenAI 兼容接口的嵌入服务（默认指向 NVIDIA）
+pub struct NvidiaEmbedder {
+    client: Client,
+    api_base: String,
+    api_key: String,
+    model_name: String,
+}
+
+impl NvidiaEmbedder {
+    pub fn new(client: Client, api_base: impl Into<String>, api_key: impl Into<String>, model_name: impl Into<String>) -> Self {
+        Self {
+            client,
+            api_base: api_base.into().trim_end_matches('/').to_string(),
+            api_key: api_key.into(),
+            model_name: model_name.into(),
+        }
+    }
+
+    /// 按 `EMBEDDING_API_KEY`、`EMBEDDING_API_BASE_URL`、`EMBEDDING_MODEL_NAME` 创建，缺少密钥时报错
+    pub fn from_env(client: Client) -> Result<Self> {
+        let api_key = std::env::var("EMBEDDING_API_KEY").map_err(|_| {
+            anyhow!(
+                "❌ 必须设置 EMBEDDING_API_KEY 环境变量才能使用向量化功能。\n\
+                 请在 .env 文件中配置：\n\
+                 EMBEDDING_API_KEY=your-actual-api-key\n\
+                 EMBEDDING_API_BASE_URL={}\n\
+                 EMBEDDING_MODEL_NAME={}",
+                DEFAULT_EMBEDDING_API_BASE,
+                DEFAULT_EMBEDDING_MODEL
+            )
+        })?;
+        let api_base = std::env::var("EMBEDDING_API_BASE_URL").unwrap_or_else(|_| DEFAULT_EMBEDDING_API_BASE.to_string());
+        let model_name = std::env::var("EMBEDDING_MODEL_NAME").unwrap_or_else(|_| DEFAULT_EMBEDDING_MODEL.to_string());
+        Ok(Self::new(client, api_base, api_key, model_name))
+    }
+}
+
+#[async_trait]
+impl TextEmbedder for NvidiaEmbedder {
+    fn provider(&self) -> &str {
+        "NVIDIA"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    async fn embed(&self, texts: &[String], input_type: InputType) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = EmbeddingRequest {
+            input: texts,
+            model: &self.model_name,
+            input_type: input_type.as_str(),
+        };
+        let response = self
+            .client
+            .post(format!("{}/embeddings", self.api_base))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .correlated()
+            .send()
+            .instrument(tracing::debug_span!("embedding_request", model = %self.model_name, inputs = texts.len()))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(MCPError::from_provider_response("embedding", response).await.into());
+        }
+
+        let embedding_response: EmbeddingResponse = response.json().await?;
+        if embedding_response.data.len() != texts.len() {
+            return Err(anyhow!("返回的嵌入数量与请求文本数量不匹配"));
+        }
+        Ok(embedding_response.data.into_iter().map(|data| data.embedding).collect())
+    }
+}
+
+/// 按 `EMBEDDING_PROVIDER` 选择嵌入服务：`hash` 使用 [`HashEmbeddingProvider`]（维度取
+/// `EMBEDDING_DIMENSION`，默认 384），`local` 使用本地模型，`cohere`、`jina`、`voyage` 使用对应的托管服务，
+/// 未设置或其他值使用 [`NvidiaEmbedder::from_env`]
+///
+/// 设置 `EMBEDDING_TRUNCATE_DIMENSION` 时再用 [`TruncatedEmbedder`] 截断到该维度
+pub fn embedder_from_env(client: Client) -> Result<Arc<dyn TextEmbedder>> {
+    let embedder = provider_from_env(client)?;
+    let truncate_dimension = std::env::var("EMBEDDING_TRUNCATE_DIMENSION")
+        .ok()
+        .and_then(|d| d.trim().parse::<usize>().ok())
+        .filter(|d| *d > 0);
+    match truncate_dimension {
+        Some(dimension) => {
+            tracing::info!("嵌入向量截断到前 {} 维（Matryoshka）", dimension);
+            Ok(Arc::new(TruncatedEmbedder::new(embedder, dimension)))
+        }
+        None => Ok(embedder),
+    }
+}
+
+fn provider_from_env(client: Client) -> Result<Arc<dyn TextEmbedder>> {
+    match std::env::var("EMBEDDING_PROVIDER").map(|p| p.trim().to_lowercase()).as_deref() {
+        Ok("hash") => {
+            let dimension = std::env::var("EMBEDDING_DIMENSION")
+                .ok()
+                .and_then(|d| d.parse().ok())
+                .unwrap_or(DEFAULT_HASH_DIMENSION);
+            tracing::info!("使用确定性哈希嵌入（维度 {}），不调用外部嵌入服务", dimension);
+            Ok(Arc::new(HashEmbeddingProvider::new(dimension)))
+        }
+        Ok("local") => local_embedder_from_env(),
+        Ok(provider @ ("cohere" | "jina" | "voyage")) => hosted_embedder_from_env(provider),
+        _ => Ok(Arc::new(NvidiaEmbedder::from_env(client)?)),
+    }
+}
+
+/// 截断到前 `dimension` 维（至少 1 维）并重新归一化为单位向量
+///
+/// Matryoshka 表示学习训练的模型把主要信息集中在前面的维度，截断后的向量仍可直接比较，
+/// 内存占用和搜索耗时随维度成比例下降，召回率略有损失（见 `benches/matryoshka.rs`）。
+pub fn truncate_embedding(mut embedding: Vec<f32>, dimension: usize) -> Vec<f32> {
+    embedding.truncate(dimension.max(1));
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        embedding.iter_mut().for_each(|x| *x /= norm);
+    }
+    embedding
+}
+
+/// 把嵌入向量截断到较小维度的包装
+pub struct TruncatedEmbedder {
+    inner: Arc<dyn TextEmbedder>,
+    dimension: usize,
+    /// 原模型名加截断维度：嵌入缓存和文档包按模型名区分，截断前后的向量不能混用
+    model_name: String,
+}
+
+impl TruncatedEmbedder {
+    pub fn new(inner: Arc<dyn TextEmbedder>, dimension: usize) -> Self {
+        let dimension = dimension.max(1);
+        let model_name = format!("{}@{}", inner.model_name(), dimension);
+        Self { inner, dimension, model_name }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+#[async_trait]
+impl TextEmbedder for TruncatedEmbedder {
+    fn provider(&self) -> &str {
+        self.inner.provider()
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    async fn embed(&self, texts: &[String], input_type: InputType) -> Result<Vec<Vec<f32>>> {
+        let embeddings = self.inner.embed(texts, input_type).await?;
+        if let Some(embedding) = embeddings.iter().find(|embedding| embedding.len() < self.dimension) {
+            return Err(anyhow!(
+                "模型 {} 返回 {} 维向量，小于截断维度 {}",
+                self.inner.model_name(),
+                embedding.len(),
+                self.dimension
+            ));
+        }
+        Ok(embeddings.into_iter().map(|embedding| truncate_embedding(embedding, self.dimension)).collect())
+    }
+}
+
+/// 托管服务：`EMBEDDING_API_KEY` 必填，`EMBEDDING_MODEL_NAME` 默认为各服务的通用检索模型，
+/// `EMBEDDING_DIMENSION` 默认 1024
+fn hosted_embedder_from_env(provider: &str) -> Result<Arc<dyn TextEmbedder>> {
+    use crate::embeddings::{HostedApi, HostedProvider};
+
+    let api = HostedApi::from_provider(provider).ok_or_else(|| anyhow!("不支持的嵌入服务: {}", provider))?;
+    let api_key = std::env::var("EMBEDDING_API_KEY")
+        .map_err(|_| anyhow!("EMBEDDING_PROVIDER={} 需要设置 EMBEDDING_API_KEY", provider))?;
+    let config = crate::config::EmbeddingConfig {
+        provider: provider.to_string(),
+        api_key: Some(api_key),
+        model: std::env::var("EMBEDDING_MODEL_NAME").unwrap_or_else(|_| api.default_model().to_string()),
+        dimension: std::env::var("EMBEDDING_DIMENSION").ok().and_then(|d| d.parse().ok()),
+        ..Default::default()
+    };
+    let embedder = HostedProvider::new(api, &config)?;
+    tracing::info!("使用 {} 嵌入服务，模型 {}", provider, embedder.model_name());
+    Ok(Arc::new(embedder))
+}
+
+/// 本地模型：`EMBEDDING_MODEL_NAME` 为模型ID或目录（默认 bge-small），`EMBEDDING_DIMENSION` 默认 384，
+/// `EMBEDDING_MODEL_DIR` 为模型缓存目录，`EMBEDDING_OFFLINE=1` 时只使用已缓存的模型
+#[cfg(feature = "local-embeddings")]
+fn local_embedder_from_env() -> Result<Arc<dyn TextEmbedder>> {
+    use crate::embeddings::{LocalProvider, DEFAULT_LOCAL_DIMENSION, DEFAULT_LOCAL_MODEL};
+
+    let config = crate::config::EmbeddingConfig {
+        provider: "local".to_string(),
+        model: std::env::var("EMBEDDING_MODEL_NAME").unwrap_or_else(|_| DEFAULT_LOCAL_MODEL.to_string()),
+        dimension: Some(
+            std::env::var("EMBEDDING_DIMENSION")
+                .ok()
+                .and_then(|d| d.parse().ok())
+                .unwrap_or(DEFAULT_LOCAL_DIMENSION),
+        ),
+        model_cache_dir: std::env::var("EMBEDDING_MODEL_DIR").ok().map(std::path::PathBuf::from),
+        offline: matches!(std::env::var("EMBEDDING_OFFLINE").as_deref(), Ok("1") | Ok("true")),
+        ..Default::default()
+    };
+    tracing::info!("使用本地嵌入模型 {}（首次使用时加载）", config.model);
+    Ok(Arc::new(LocalProvider::new(&config)))
+}
+
+#[cfg(not(feature = "local-embeddings"))]
+fn local_embedder_from_env() -> Result<Arc<dyn TextEmbedder>> {
+    Err(anyhow!("EMBEDDING_PROVIDER=local 需要启用 local-embeddings 特性编译：cargo build --features local-embeddings"))
+}
+
+/// 确定性哈希嵌入：不访问网络，相同文本在任何平台上得到相同向量
+///
+/// 文本按非字母数字字符切词并转小写，每个词及其字符三元组用 FNV-1a 哈希到固定维度
+/// （哈希的一位决定符号，减少碰撞带来的偏差），累加后做 L2 归一化。共享词越多的文本
+/// 余弦相似度越高，拼写相近的词也会部分重合，但不具备真正的语义理解。
+#[derive(Debug, Clone)]
+pub struct HashEmbeddingProvider {
+    dimension: usize,
+    model_name: String,
+}
+
+impl HashEmbeddingProvider {
+    pub fn new(dimension: usize) -> Self {
+        let dimension = dimension.max(1);
+        Self { dimension, model_name: format!("hash-embedding-{}", dimension) }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// 计算单个文本的向量
+    pub fn vector(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimension];
+        for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+            let word = word.to_lowercase();
+            self.accumulate(&mut vector, word.as_bytes(), 1.0);
+            let chars: Vec<char> = word.chars().collect();
+            if chars.len() > 3 {
+                for trigram in chars.windows(3) {
+                    self.accumulate(&mut vector, trigram.iter().collect::<String>().as_bytes(), 0.25);
+                }
+            }
+        }
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+
+    fn accumulate(&self, vector: &mut [f32], feature: &[u8], weight: f32) {
+        let hash = fnv1a(feature);
+        let bucket = (hash % self.dimension as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[bucket] += sign * weight;
+    }
+}
+
+/// 64 位 FNV-1a（不依赖标准库哈希的随机种子，跨进程和平台稳定）
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[async_trait]
+impl TextEmbedder for HashEmbeddingProvider {
+    fn provider(&self) -> &str {
+        "hash"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    async fn embed(&self, texts: &[String], _input_type: InputType) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.vector(text)).collect())
+    }
+}
+
+/// 测试替身
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// 不访问网络的嵌入服务：用 [`HashEmbeddingProvider`] 生成向量，并记录调用
+    ///
+    /// 可以用 [`MockEmbedder::failing`] 模拟提供商故障。
+    pub struct MockEmbedder {
+        hash: HashEmbeddingProvider,
+        fail_with: Option<String>,
+        calls: AtomicUsize,
+        inputs: Mutex<Vec<(String, InputType)>>,
+    }
+
+    impl MockEmbedder {
+        pub fn new(dimension: usize) -> Self {
+            Self {
+                hash: HashEmbeddingProvider::new(dimension),
+                fail_with: None,
+                calls: AtomicUsize::new(0),
+                inputs: Mutex::new(Vec::new()),
+            }
+        }
+
+        /// 每次调用都返回指定错误
+        pub fn failing(message: &str) -> Self {
+            Self { fail_with: Some(message.to_string()), ..Self::new(8) }
+        }
+
+        /// 调用次数（一批文本算一次）
+        pub fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+
+        /// 收到的全部文本及用途
+        pub fn inputs(&self) -> Vec<(String, InputType)> {
+            self.inputs.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl TextEmbedder for MockEmbedder {
+        fn provider(&self) -> &str {
+            "mock"
+        }
+
+        fn model_name(&self) -> &str {
+            "mock-embedding"
+        }
+
+        async fn embed(&self, texts: &[String], input_type: InputType) -> Result<Vec<Vec<f32>>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.inputs.lock().unwrap().extend(texts.iter().map(|t| (t.clone(), input_type)));
+            if let Some(message) = &self.fail_with {
+                return Err(anyhow!(message.clone()));
+            }
+            Ok(texts.iter().map(|text| self.hash.vector(text)).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::MockEmbedder;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_embedder_is_deterministic_and_records_calls() {
+        let embedder = MockEmbedder::new(32);
+        let texts = vec!["tokio spawn task".to_string(), "serde serialize".to_string()];
+        let first = embedder.embed(&texts, InputType::Passage).await.unwrap();
+        let second = embedder.embed(&texts[..1], InputType::Query).await.unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0], second[0]);
+        assert_eq!(embedder.calls(), 2);
+        assert_eq!(embedder.inputs()[2], ("tokio spawn task".to_string(), InputType::Query));
+
+        let failing = MockEmbedder::failing("429 Too Many Requests");
+        assert!(failing.embed(&texts, InputType::Query).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_health_check_validates_key_and_dimension() {
+        let health = MockEmbedder::new(16).health_check().await;
+        assert!(health.is_healthy());
+        assert_eq!(health.dimension, Some(16));
+        assert!(health.clone().with_expected_dimension(Some(16)).is_healthy());
+        let mismatch = health.with_expected_dimension(Some(32));
+        assert_eq!(mismatch.status, EmbeddingHealthStatus::Unhealthy);
+        assert!(mismatch.reason.unwrap().contains("32"));
+
+        let unauthorized: Result<Vec<Vec<f32>>> = Err(MCPError::ProviderError {
+            provider: "embedding".to_string(),
+            status: Some(401),
+            retry_after_secs: None,
+            message: "401 Unauthorized".to_string(),
+        }
+        .into());
+        let health = EmbeddingHealth::from_probe("NVIDIA", "nv-embed", unauthorized, Duration::ZERO);
+        assert_eq!(health.status, EmbeddingHealthStatus::Unhealthy);
+        assert!(health.reason.unwrap().contains("API 密钥"));
+
+        let health = MockEmbedder::failing("connection refused").health_check().await;
+        assert_eq!(health.status, EmbeddingHealthStatus::Unhealthy);
+        assert_eq!(health.dimension, None);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_embedder_renormalizes_prefix() {
+        assert_eq!(truncate_embedding(vec![3.0, 4.0, 12.0], 2), vec![0.6, 0.8]);
+        assert_eq!(truncate_embedding(vec![0.0, 0.0, 1.0], 2), vec![0.0, 0.0]);
+
+        let inner = Arc::new(MockEmbedder::new(64));
+        let embedder = TruncatedEmbedder::new(inner.clone(), 16);
+        assert_eq!(embedder.model_name(), "mock-embedding@16");
+        let texts = vec!["tokio spawn task".to_string()];
+        let full = inner.embed(&texts, InputType::Passage).await.unwrap();
+        let truncated = embedder.embed(&texts, InputType::Passage).await.unwrap();
+        assert_eq!(truncated[0].len(), 16);
+        assert!((cosine(&truncated[0], &truncated[0]) - 1.0).abs() < 1e-5);
+        assert_eq!(truncated[0], truncate_embedding(full[0].clone(), 16));
+
+        // 模型维度小于截断维度时报错
+        assert!(TruncatedEmbedder::new(inner, 128).embed(&texts, InputType::Passage).await.is_err());
+    }
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn test_hash_embedding_is_stable_and_ranks_shared_words() {
+        let provider = HashEmbeddingProvider::new(128);
+        let query = provider.vector("tokio spawn async task");
+        assert_eq!(query.len(), 128);
+        assert!((cosine(&query, &query) - 1.0).abs() < 1e-5);
+        assert_eq!(query, HashEmbeddingProvider::new(128).vector("Tokio SPAWN async task"));
+
+        let related = provider.vector("spawning an async task with tokio");
+        let unrelated = provider.vector("serde derive serialize struct");
+        assert!(cosine(&query, &related) > cosine(&query, &unrelated));
+
+        // 空文本得到零向量，维度至少为 1
+        assert!(provider.vector("").iter().all(|x| *x == 0.0));
+        assert_eq!(HashEmbeddingProvider::new(0).dimension(), 1);
+        assert_eq!(provider.model_name(), "hash-embedding-128");
+    }
+}
@@ -0,0 +1,306 @@
+//! 按限流自适应的嵌入请求批处理
+//!
+//! 把所有未缓存文本放进一次请求，遇到 429 时整批失败，大批量导入几乎必然触发限流。
+//! [`AdaptiveBatcher`] 把文本切成若干批，同时最多发出 `max_concurrency` 个请求；
+//! 被限流时批大小减半，按 `Retry-After`（没有时按带抖动的指数退避）暂停所有请求后重试，
+//! 连续成功后批大小逐步回升。5xx 和网络错误同样退避重试，其他错误直接返回。
+//! 请求数、嵌入文本数、限流和重试次数、吞吐量记录在 [`MetricsCollector`] 中。
+
+use anyhow::Result;
+use futures::future::try_join_all;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::errors::MCPError;
+use crate::metrics::MetricsCollector;
+use crate::tools::embedder::{InputType, TextEmbedder};
+
+/// 批处理配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchingConfig {
+    /// 初始批大小
+    pub initial_batch_size: usize,
+    /// 限流时批大小的下限
+    pub min_batch_size: usize,
+    /// 批大小的上限
+    pub max_batch_size: usize,
+    /// 同时进行的请求数
+    pub max_concurrency: usize,
+    /// 单批的最大重试次数
+    pub max_retries: u32,
+    /// 首次退避时间，之后每次翻倍
+    pub base_backoff: Duration,
+    /// 退避时间上限（`Retry-After` 也不超过该值）
+    pub max_backoff: Duration,
+}
+
+impl Default for BatchingConfig {
+    fn default() -> Self {
+        Self {
+            initial_batch_size: 64,
+            min_batch_size: 1,
+            max_batch_size: 256,
+            max_concurrency: 4,
+            max_retries: 5,
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl BatchingConfig {
+    /// 从环境变量读取配置
+    ///
+    /// - `EMBEDDING_BATCH_SIZE`: 初始批大小
+    /// - `EMBEDDING_MAX_BATCH_SIZE`: 批大小上限
+    /// - `EMBEDDING_CONCURRENCY`: 同时进行的请求数
+    /// - `EMBEDDING_MAX_RETRIES`: 单批的最大重试次数
+    pub fn from_env() -> Self {
+        let env = |name: &str| std::env::var(name).ok().and_then(|v| v.trim().parse::<usize>().ok());
+        let defaults = Self::default();
+        let max_batch_size = env("EMBEDDING_MAX_BATCH_SIZE").filter(|n| *n > 0).unwrap_or(defaults.max_batch_size);
+        let initial_batch_size = env("EMBEDDING_BATCH_SIZE")
+            .filter(|n| *n > 0)
+            .unwrap_or(defaults.initial_batch_size)
+            .min(max_batch_size);
+        Self {
+            initial_batch_size,
+            max_batch_size,
+            max_concurrency: env("EMBEDDING_CONCURRENCY").filter(|n| *n > 0).unwrap_or(defaults.max_concurrency),
+            max_retries: env("EMBEDDING_MAX_RETRIES").map_or(defaults.max_retries, |n| n as u32),
+            ..defaults
+        }
+    }
+}
+
+/// 错误的重试方式
+enum Retry {
+    /// 被限流，附带服务端要求的等待时间
+    RateLimited(Option<Duration>),
+    /// 服务端或网络的临时故障
+    Transient,
+    /// 不可重试
+    Fatal,
+}
+
+fn classify(error: &anyhow::Error) -> Retry {
+    if let Some(MCPError::ProviderError { status, retry_after_secs, .. }) = error.downcast_ref::<MCPError>() {
+        return match status {
+            Some(429) => Retry::RateLimited(retry_after_secs.map(Duration::from_secs)),
+            Some(status) if *status >= 500 => Retry::Transient,
+            _ => Retry::Fatal,
+        };
+    }
+    if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+        if e.is_timeout() || e.is_connect() || e.status().is_some_and(|s| s.is_server_error()) {
+            return Retry::Transient;
+        }
+    }
+    Retry::Fatal
+}
+
+/// 0.5-1.0 之间的抖动系数，避免并发请求同时重试
+fn jitter() -> f64 {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.subsec_nanos());
+    // 打散低位规律后取 [0, 1)
+    let mixed = (nanos as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 11;
+    0.5 + 0.5 * (mixed as f64 / (1u64 << 53) as f64)
+}
+
+/// 自适应批处理器，同一嵌入服务的所有请求共用（共享批大小和限流暂停）
+pub struct AdaptiveBatcher {
+    config: BatchingConfig,
+    batch_size: AtomicUsize,
+    /// 被限流后暂停到该时间
+    cooldown_until: Mutex<Option<Instant>>,
+    metrics: Arc<MetricsCollector>,
+}
+
+impl AdaptiveBatcher {
+    pub fn new(config: BatchingConfig, metrics: Arc<MetricsCollector>) -> Self {
+        let initial = config.initial_batch_size.clamp(config.min_batch_size.max(1), config.max_batch_size.max(1));
+        metrics.update_embedding_batch_size(initial as u64);
+        Self { batch_size: AtomicUsize::new(initial), cooldown_until: Mutex::new(None), config, metrics }
+    }
+
+    /// 当前批大小
+    pub fn batch_size(&self) -> usize {
+        self.batch_size.load(Ordering::Relaxed)
+    }
+
+    pub fn metrics(&self) -> &Arc<MetricsCollector> {
+        &self.metrics
+    }
+
+    /// 为全部文本生成嵌入，返回顺序与输入一致
+    pub async fn embed_all(&self, embedder: &dyn TextEmbedder, texts: &[String], input_type: InputType) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        let mut offset = 0;
+        while offset < texts.len() {
+            // 每一轮按当前批大小切出最多 max_concurrency 批，同时发出
+            let batch_size = self.batch_size();
+            let window_end = (offset + batch_size * self.config.max_concurrency.max(1)).min(texts.len());
+            let batches = texts[offset..window_end]
+                .chunks(batch_size)
+                .map(|batch| self.embed_batch(embedder, batch, input_type));
+            for batch in try_join_all(batches).await? {
+                embeddings.extend(batch);
+            }
+            offset = window_end;
+        }
+        Ok(embeddings)
+    }
+
+    /// 嵌入一批文本：限流时等待后按缩小后的批大小拆分重试
+    async fn embed_batch(&self, embedder: &dyn TextEmbedder, texts: &[String], input_type: InputType) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        let mut offset = 0;
+        let mut attempt = 0u32;
+        while offset < texts.len() {
+            self.wait_for_cooldown().await;
+            let end = (offset + self.batch_size()).min(texts.len());
+            let batch = &texts[offset..end];
+            let started = Instant::now();
+            match embedder.embed(batch, input_type).await {
+                Ok(batch_embeddings) => {
+                    if batch_embeddings.len() != batch.len() {
+                        return Err(anyhow::anyhow!("返回的嵌入数量与请求文本数量不匹配"));
+                    }
+                    self.metrics.record_embedding_batch(batch.len() as u64, started.elapsed().as_secs_f64() * 1000.0);
+                    self.grow();
+                    embeddings.extend(batch_embeddings);
+                    offset = end;
+                    attempt = 0;
+                }
+                Err(e) => {
+                    let retry = classify(&e);
+                    if matches!(retry, Retry::Fatal) || attempt >= self.config.max_retries {
+                        return Err(e);
+                    }
+                    let backoff = self.backoff(attempt);
+                    attempt += 1;
+                    self.metrics.record_embedding_retry();
+                    match retry {
+                        Retry::RateLimited(retry_after) => {
+                            self.metrics.record_embedding_rate_limited();
+                            self.shrink();
+                            let wait = retry_after.map_or(backoff, |d| d.min(self.config.max_backoff));
+                            tracing::warn!("嵌入请求被限流，批大小降为 {}，{:?} 后重试", self.batch_size(), wait);
+                            self.pause_for(wait);
+                        }
+                        _ => {
+                            tracing::warn!("嵌入请求失败，{:?} 后重试（第 {} 次）: {}", backoff, attempt, e);
+                            tokio::time::sleep(backoff).await;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(embeddings)
+    }
+
+    /// 第 `attempt` 次重试的退避时间（指数增长并带抖动）
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self.config.base_backoff.saturating_mul(1u32 << attempt.min(16));
+        exponential.min(self.config.max_backoff).mul_f64(jitter())
+    }
+
+    /// 所有请求暂停到 `wait` 之后（已有更晚的暂停时保持不变）
+    fn pause_for(&self, wait: Duration) {
+        let until = Instant::now() + wait;
+        let mut cooldown = self.cooldown_until.lock().unwrap();
+        if !matches!(*cooldown, Some(current) if current >= until) {
+            *cooldown = Some(until);
+        }
+    }
+
+    async fn wait_for_cooldown(&self) {
+        let until = *self.cooldown_until.lock().unwrap();
+        if let Some(until) = until {
+            tokio::time::sleep_until(tokio::time::Instant::from_std(until)).await;
+        }
+    }
+
+    fn shrink(&self) {
+        let min = self.config.min_batch_size.max(1);
+        let previous = self
+            .batch_size
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |size| Some((size / 2).max(min)))
+            .unwrap_or_else(|size| size);
+        self.metrics.update_embedding_batch_size((previous / 2).max(min) as u64);
+    }
+
+    /// 成功后批大小增加四分之一（至少加一），不超过上限
+    fn grow(&self) {
+        let max = self.config.max_batch_size.max(1);
+        let previous = self.batch_size.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |size| {
+            (size < max).then(|| (size + (size / 4).max(1)).min(max))
+        });
+        if let Ok(previous) = previous {
+            self.metrics.update_embedding_batch_size((previous + (previous / 4).max(1)).min(max) as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::atomic::AtomicU32;
+
+    /// 单批超过 `limit` 条时返回 429 的嵌入服务
+    struct RateLimitedEmbedder {
+        limit: usize,
+        rejected: AtomicU32,
+    }
+
+    #[async_trait]
+    impl TextEmbedder for RateLimitedEmbedder {
+        fn provider(&self) -> &str {
+            "test"
+        }
+
+        fn model_name(&self) -> &str {
+            "test-model"
+        }
+
+        async fn embed(&self, texts: &[String], _input_type: InputType) -> Result<Vec<Vec<f32>>> {
+            if texts.len() > self.limit {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                return Err(MCPError::ProviderError {
+                    provider: "embedding".to_string(),
+                    status: Some(429),
+                    retry_after_secs: Some(0),
+                    message: "too many tokens".to_string(),
+                }
+                .into());
+            }
+            Ok(texts.iter().map(|text| vec![text.parse::<f32>().unwrap()]).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_shrinks_batches_and_keeps_order() {
+        let metrics = Arc::new(MetricsCollector::new());
+        let config = BatchingConfig { initial_batch_size: 32, max_concurrency: 2, base_backoff: Duration::from_millis(1), ..Default::default() };
+        let batcher = AdaptiveBatcher::new(config, metrics.clone());
+        let embedder = RateLimitedEmbedder { limit: 8, rejected: AtomicU32::new(0) };
+        let texts: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+
+        let embeddings = batcher.embed_all(&embedder, &texts, InputType::Passage).await.unwrap();
+        assert_eq!(embeddings.len(), 100);
+        assert!(embeddings.iter().enumerate().all(|(i, embedding)| embedding[0] == i as f32));
+
+        let snapshot = metrics.get_metrics();
+        assert!(snapshot.embedding_rate_limited > 0);
+        assert_eq!(snapshot.embedding_rate_limited, embedder.rejected.load(Ordering::Relaxed) as u64);
+        assert_eq!(snapshot.embedded_texts, 100);
+        assert!(snapshot.embedding_requests >= 10);
+
+        // 超过重试次数后返回错误
+        let failing = RateLimitedEmbedder { limit: 0, rejected: AtomicU32::new(0) };
+        let batcher = AdaptiveBatcher::new(BatchingConfig { max_retries: 0, ..Default::default() }, metrics);
+        assert!(batcher.embed_all(&failing, &texts[..1], InputType::Passage).await.is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! 嵌入向量的持久缓存
+//!
+//! 嵌入服务按调用计费，重启后重新嵌入同样的文本就是重复计费。缓存以（模型、文本用途、内容 MD5）为键，
+//! 保存在全局缓存目录的 `embedding_cache.bin` 中：单条和批量嵌入共用同一份缓存，换模型自然不命中。
+//! 条目超过有效期视为过期；超过容量上限时淘汰最久未使用的条目。新条目先在内存累积，
+//! 攒够一批、距上次落盘超过一定时间、休眠或退出时写盘（先写临时文件再替换）。
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config::VectorSearchConfig;
+use crate::tools::embedder::InputType;
+
+/// 缓存文件名（位于全局缓存目录下）
+pub const EMBEDDING_CACHE_FILE: &str = "embedding_cache.bin";
+
+/// 默认的最大条目数
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// 默认的有效期（小时）
+pub const DEFAULT_TTL_HOURS: u64 = 24;
+
+/// 累积多少条新条目后落盘
+const FLUSH_EVERY: usize = 64;
+
+/// 距上次落盘超过该时间后，下一次写入时落盘
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 嵌入缓存配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingCacheConfig {
+    /// 最大条目数，0 表示不缓存
+    pub max_entries: usize,
+    /// 条目有效期
+    pub ttl: Duration,
+}
+
+impl Default for EmbeddingCacheConfig {
+    fn default() -> Self {
+        Self { max_entries: DEFAULT_MAX_ENTRIES, ttl: Duration::from_secs(DEFAULT_TTL_HOURS * 3600) }
+    }
+}
+
+impl EmbeddingCacheConfig {
+    /// 从系统配置（`embedding_cache_max_entries`、`embedding_cache_ttl_hours`）读取，环境变量可覆盖
+    ///
+    /// - `EMBEDDING_CACHE_MAX_ENTRIES`: 最大条目数，0 表示关闭缓存
+    /// - `EMBEDDING_CACHE_TTL_HOURS`: 条目有效期（小时）
+    pub fn from_config(config: &VectorSearchConfig) -> Self {
+        let defaults = Self {
+            max_entries: config.embedding_cache_max_entries,
+            ttl: Duration::from_secs(config.embedding_cache_ttl_hours.max(1) * 3600),
+        };
+        let max_entries = std::env::var("EMBEDDING_CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .unwrap_or(defaults.max_entries);
+        let ttl = std::env::var("EMBEDDING_CACHE_TTL_HOURS")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|hours| *hours > 0)
+            .map_or(defaults.ttl, |hours| Duration::from_secs(hours * 3600));
+        Self { max_entries, ttl }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    embedding: Vec<f32>,
+    /// 写入时间（Unix 秒）
+    created_at: u64,
+    /// 最近命中时间（Unix 毫秒），用于淘汰
+    last_used: u64,
+}
+
+/// 缓存统计
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingCacheStats {
+    pub cached_embeddings: usize,
+    pub cache_limit: usize,
+    pub ttl_hours: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// 缓存文件，仅内存缓存时为 None
+    pub cache_file: Option<PathBuf>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn unix_now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// 按（模型、用途、内容哈希）缓存的嵌入向量
+#[derive(Debug)]
+pub struct EmbeddingCache {
+    /// 缓存文件，None 表示只在内存中缓存
+    path: Option<PathBuf>,
+    config: EmbeddingCacheConfig,
+    entries: HashMap<String, CacheEntry>,
+    /// 缓存文件是否已读入内存（休眠时卸载，下次访问时重新读取）
+    loaded: bool,
+    /// 上次落盘后新增的条目数
+    pending: usize,
+    last_flush: Instant,
+    hits: u64,
+    misses: u64,
+}
+
+impl EmbeddingCache {
+    /// 只在内存中缓存（进程退出即丢失）
+    pub fn in_memory(config: EmbeddingCacheConfig) -> Self {
+        Self {
+            path: None,
+            config,
+            entries: HashMap::new(),
+            loaded: true,
+            pending: 0,
+            last_flush: Instant::now(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// 使用目录下的缓存文件，首次访问时读取
+    pub fn open(dir: &Path, config: EmbeddingCacheConfig) -> Self {
+        Self { path: Some(dir.join(EMBEDDING_CACHE_FILE)), loaded: false, ..Self::in_memory(config) }
+    }
+
+    /// 缓存键：换模型或换用途（非对称模型的查询和文档编码不同）都不会命中
+    pub fn key(model: &str, input_type: InputType, text: &str) -> String {
+        format!("{}\n{}\n{:x}", model, input_type.as_str(), md5::compute(text.as_bytes()))
+    }
+
+    fn ensure_loaded(&mut self) {
+        if self.loaded {
+            return;
+        }
+        self.loaded = true;
+        let Some(path) = &self.path else { return };
+        match fs::read(path) {
+            Ok(data) => match bincode::deserialize::<HashMap<String, CacheEntry>>(&data) {
+                Ok(entries) => {
+                    let expired_before = unix_now().saturating_sub(self.config.ttl.as_secs());
+                    self.entries = entries.into_iter().filter(|(_, entry)| entry.created_at >= expired_before).collect();
+                    tracing::debug!("加载嵌入缓存 {} 条: {:?}", self.entries.len(), path);
+                }
+                Err(e) => tracing::warn!("嵌入缓存文件损坏，重新开始缓存: {:?}: {}", path, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("读取嵌入缓存失败: {:?}: {}", path, e),
+        }
+    }
+
+    /// 取得未过期的缓存向量
+    pub fn get(&mut self, key: &str) -> Option<Vec<f32>> {
+        self.ensure_loaded();
+        let now = unix_now();
+        let ttl = self.config.ttl.as_secs();
+        match self.entries.get_mut(key) {
+            Some(entry) if now.saturating_sub(entry.created_at) < ttl => {
+                entry.last_used = unix_now_millis();
+                self.hits += 1;
+                Some(entry.embedding.clone())
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// 写入缓存，超过容量时淘汰最久未使用的条目；累积够一批或距上次落盘较久时写盘
+    pub fn insert(&mut self, key: String, embedding: Vec<f32>) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+        self.ensure_loaded();
+        let now = unix_now();
+        self.entries.insert(key, CacheEntry { embedding, created_at: now, last_used: unix_now_millis() });
+        self.pending += 1;
+
+        if self.entries.len() > self.config.max_entries {
+            let expired_before = now.saturating_sub(self.config.ttl.as_secs());
+            self.entries.retain(|_, entry| entry.created_at >= expired_before);
+            let excess = self.entries.len().saturating_sub(self.config.max_entries);
+            if excess > 0 {
+                let mut by_use: Vec<(u64, String)> =
+                    self.entries.iter().map(|(key, entry)| (entry.last_used, key.clone())).collect();
+                by_use.sort_unstable();
+                for (_, key) in by_use.into_iter().take(excess) {
+                    self.entries.remove(&key);
+                }
+            }
+        }
+
+        if self.pending >= FLUSH_EVERY || self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            if let Err(e) = self.flush() {
+                tracing::warn!("保存嵌入缓存失败: {}", e);
+            }
+        }
+    }
+
+    /// 把新条目写盘
+    pub fn flush(&mut self) -> Result<()> {
+        self.last_flush = Instant::now();
+        let Some(path) = &self.path else { return Ok(()) };
+        if self.pending == 0 || !self.loaded {
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("bin.tmp");
+        fs::write(&tmp, bincode::serialize(&self.entries)?)?;
+        fs::rename(&tmp, path)?;
+        self.pending = 0;
+        Ok(())
+    }
+
+    /// 落盘后释放内存（空闲休眠时使用），下次访问时重新读取；仅内存缓存时直接清空
+    pub fn unload(&mut self) -> Result<()> {
+        self.flush()?;
+        if self.path.is_some() {
+            self.loaded = false;
+        }
+        self.entries = HashMap::new();
+        Ok(())
+    }
+
+    pub fn stats(&self) -> EmbeddingCacheStats {
+        EmbeddingCacheStats {
+            cached_embeddings: self.entries.len(),
+            cache_limit: self.config.max_entries,
+            ttl_hours: self.config.ttl.as_secs() / 3600,
+            hits: self.hits,
+            misses: self.misses,
+            cache_file: self.path.clone(),
+        }
+    }
+}
+
+impl Drop for EmbeddingCache {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!("退出时保存嵌入缓存失败: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_persists_and_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = EmbeddingCacheConfig { max_entries: 2, ttl: Duration::from_secs(3600) };
+        let key = |text: &str| EmbeddingCache::key("model-a", InputType::Passage, text);
+
+        {
+            let mut cache = EmbeddingCache::open(dir.path(), config.clone());
+            cache.insert(key("a"), vec![1.0]);
+            cache.insert(key("b"), vec![2.0]);
+            assert_eq!(cache.get(&key("a")), Some(vec![1.0]));
+        }
+
+        let mut cache = EmbeddingCache::open(dir.path(), config);
+        assert_eq!(cache.get(&key("b")), Some(vec![2.0]));
+        assert_eq!(cache.get(&EmbeddingCache::key("model-b", InputType::Passage, "b")), None);
+        assert_eq!(cache.get(&EmbeddingCache::key("model-a", InputType::Query, "b")), None);
+
+        // b 刚被命中，a 最久未使用
+        cache.get(&key("a"));
+        std::thread::sleep(Duration::from_millis(5));
+        cache.get(&key("b"));
+        cache.insert(key("c"), vec![3.0]);
+        assert_eq!(cache.get(&key("a")), None);
+        assert_eq!(cache.stats().cached_embeddings, 2);
+
+        cache.unload().unwrap();
+        assert_eq!(cache.get(&key("c")), Some(vec![3.0]));
+    }
+}
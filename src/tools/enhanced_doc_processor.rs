@@ -95,6 +95,11 @@ impl EnhancedDocumentProcessor {
         })
     }
     
+    /// 处理器使用的向量工具
+    pub fn vector_tool(&self) -> Arc<VectorDocsTool> {
+        Arc::clone(&self.vector_tool)
+    }
+
     /// 使用自定义配置创建处理器
     pub async fn with_config(config: ProcessorConfig, vector_tool: Arc<VectorDocsTool>) -> Result<Self> {
         let mut processor = Self::new(vector_tool).await?;
@@ -14,6 +14,8 @@ use crate::tools::base::{
 use crate::tools::docs::openai_vectorizer::OpenAIVectorizer;
 use super::enhanced_doc_processor::{EnhancedDocumentProcessor, ProcessorConfig, EnhancedSearchResult};
 use super::vector_docs_tool::{VectorDocsTool, SearchResult};
+use super::search_mode::{self, DeepCrawlOutcome, SearchMode, QUICK_TIMEOUT};
+use crate::mcp::streaming::PartialResultSink;
 // use crate::tools::docs::{DocumentReranker, RerankerConfig, RerankResult};
 
 /// CLI优先、HTTP后备的语言工具策略
@@ -33,6 +35,8 @@ pub struct EnhancedLanguageTool {
     pub strategy: DocumentStrategy,
     pub http_client: Client,
     pub vector_tool: Option<Arc<VectorDocsTool>>,
+    /// 共享的文档处理器，quick 模式查缓存、deep 模式抓取时使用
+    pub processor: Arc<EnhancedDocumentProcessor>,
 }
 
 impl EnhancedLanguageTool {
@@ -75,9 +79,120 @@ impl EnhancedLanguageTool {
             strategy: DocumentStrategy::CLIPrimary,
             http_client: Client::new(),
             vector_tool,
+            processor,
         })
     }
 
+    /// quick 模式：只在共享向量缓存中查找该包的文档，超过时限返回空结果
+    ///
+    /// 返回命中结果和是否在时限内完成。
+    async fn quick_search(&self, package_name: &str, query: &str) -> (Vec<Value>, bool) {
+        let vector_tool = self.processor.vector_tool();
+        let search = vector_tool.execute(json!({
+            "action": "search",
+            "query": format!("{} {}", package_name, query).trim(),
+            "limit": "10",
+        }));
+        match tokio::time::timeout(QUICK_TIMEOUT, search).await {
+            Ok(Ok(result)) => {
+                let hits = result["results"]
+                    .as_array()
+                    .map(|hits| {
+                        hits.iter()
+                            .filter(|hit| hit["package_name"].as_str().map_or(false, |p| p.eq_ignore_ascii_case(package_name)))
+                            .cloned()
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (hits, true)
+            }
+            Ok(Err(e)) => {
+                debug!("缓存搜索失败: {}", e);
+                (Vec::new(), true)
+            }
+            Err(_) => {
+                warn!("⚠️ {} 的缓存搜索超过 {:?}", package_name, QUICK_TIMEOUT);
+                (Vec::new(), false)
+            }
+        }
+    }
+
+    /// standard 模式：缓存 + CLI/注册表 API
+    async fn standard_search(&self, package_name: &str, version: Option<&str>, query: &str) -> Result<Value> {
+        // 使用完整的增强搜索功能，支持向量搜索和语义分析
+        info!("🔍 开始增强文档搜索: 语言={}, 包={}, 版本={}, 查询={}", 
+              self.language, package_name, version.unwrap_or("latest"), query);
+              
+        match self.enhanced_search(package_name, query, version).await {
+            Ok(result) => {
+                // 添加执行元数据
+                let mut enhanced_result = result;
+                enhanced_result["execution_metadata"] = json!({
+                    "tool_name": format!("enhanced_{}_docs", self.language),
+                    "language": self.language,
+                    "package_name": package_name,
+                    "version": version.unwrap_or("latest"),
+                    "query": query,
+                    "strategy_used": format!("{:?}", self.strategy),
+                    "execution_time": chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string()
+                });
+                
+                Ok(json!({
+                    "status": "success",
+                    "package_name": package_name,
+                    "version": version.unwrap_or("latest"),
+                    "query": query,
+                    "results": enhanced_result
+                }))
+            }
+            Err(e) => {
+                error!("❌ 增强文档搜索失败: 语言={}, 包={}, 错误={}", self.language, package_name, e);
+                Err(anyhow!("处理 {} 文档请求失败 for {}:{} - {}", self.language, package_name, version.unwrap_or("latest"), e))
+            }
+        }
+    }
+
+    /// 按搜索模式执行，`sink` 用于 deep 模式等待期间推送进度
+    async fn execute_with_mode(&self, params: Value, sink: Option<&PartialResultSink>) -> Result<Value> {
+        let package_name = params.get("package_name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("缺少 package_name 参数"))?;
+        let version = params.get("version").and_then(|v| v.as_str());
+        let query = params.get("query").and_then(|v| v.as_str()).unwrap_or("");
+        let mode = SearchMode::from_params(&params)?;
+        let started = std::time::Instant::now();
+
+        let (mut result, complete) = match mode {
+            SearchMode::Quick => {
+                let (hits, complete) = self.quick_search(package_name, query).await;
+                (json!({ "results": hits, "cache_hit": !hits.is_empty() }), complete)
+            }
+            SearchMode::Standard => (self.standard_search(package_name, version, query).await?, true),
+            SearchMode::Deep => {
+                let budget = search_mode::deep_budget(&params);
+                let outcome = search_mode::run_deep_crawl(
+                    Arc::clone(&self.processor), &self.language, package_name, version, query, budget, sink,
+                ).await?;
+                match outcome {
+                    DeepCrawlOutcome::Completed(results) => (json!({ "results": results, "crawl_status": "completed" }), true),
+                    DeepCrawlOutcome::InProgress => {
+                        let (hits, _) = self.quick_search(package_name, query).await;
+                        (json!({ "results": hits, "crawl_status": "in_progress" }), false)
+                    }
+                }
+            }
+        };
+
+        if mode != SearchMode::Standard {
+            result["status"] = json!("success");
+            result["package_name"] = json!(package_name);
+            result["version"] = json!(version.unwrap_or("latest"));
+            result["query"] = json!(query);
+        }
+        search_mode::annotate(&mut result, mode, started, complete);
+        Ok(result)
+    }
+
     /// 创建Schema的静态方法
     fn create_schema() -> Schema {
         let mut properties = HashMap::new();
@@ -803,6 +918,7 @@ impl MCPTool for EnhancedLanguageTool {
                 description: Some("搜索查询或问题 (可选)".to_string()),
                 enum_values: None,
             }));
+            search_mode::add_mode_properties(&mut properties);
             Schema::Object(SchemaObject {
                 required: vec!["package_name".to_string()],
                 properties,
@@ -812,42 +928,11 @@ impl MCPTool for EnhancedLanguageTool {
     }
 
     async fn execute(&self, params: Value) -> Result<Value> {
-        let package_name = params.get("package_name")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("缺少 package_name 参数"))?;
-        let version = params.get("version").and_then(|v| v.as_str());
-        let query = params.get("query").and_then(|v| v.as_str()).unwrap_or("");
+        self.execute_with_mode(params, None).await
+    }
 
-        // 使用完整的增强搜索功能，支持向量搜索和语义分析
-        info!("🔍 开始增强文档搜索: 语言={}, 包={}, 版本={}, 查询={}", 
-              self.language, package_name, version.unwrap_or("latest"), query);
-              
-        match self.enhanced_search(package_name, query, version).await {
-            Ok(result) => {
-                // 添加执行元数据
-                let mut enhanced_result = result;
-                enhanced_result["execution_metadata"] = json!({
-                    "tool_name": format!("enhanced_{}_docs", self.language),
-                    "language": self.language,
-                    "package_name": package_name,
-                    "version": version.unwrap_or("latest"),
-                    "query": query,
-                    "strategy_used": format!("{:?}", self.strategy),
-                    "execution_time": chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string()
-                });
-                
-                Ok(json!({
-                    "status": "success",
-                    "package_name": package_name,
-                    "version": version.unwrap_or("latest"),
-                    "query": query,
-                    "results": enhanced_result
-                }))
-            }
-            Err(e) => {
-                error!("❌ 增强文档搜索失败: 语言={}, 包={}, 错误={}", self.language, package_name, e);
-                Err(anyhow!("处理 {} 文档请求失败 for {}:{} - {}", self.language, package_name, version.unwrap_or("latest"), e))
-            }
-        }
+    async fn execute_streaming(&self, params: Value, sink: &PartialResultSink) -> Result<Value> {
+        let result = self.execute_with_mode(params, Some(sink)).await?;
+        Ok(sink.stream_results(result))
     }
-} 
\ No newline at end of file
+}
@@ -28,6 +28,12 @@ pub struct DetectionReport {
     pub scan_duration_ms: u64,
     pub scan_paths: Vec<PathBuf>,
     pub total_files_scanned: usize,
+    /// 语言 -> 项目清单中声明的依赖包名
+    #[serde(default)]
+    pub dependencies: HashMap<String, Vec<String>>,
+    /// 语言 -> 锁文件中锁定的包版本（包名 -> 版本），同一个包锁定了多个版本时不记录
+    #[serde(default)]
+    pub locked_versions: HashMap<String, HashMap<String, String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -158,6 +164,8 @@ impl EnvironmentDetector {
     pub async fn scan_environment(&mut self) -> Result<DetectionReport> {
         let start_time = std::time::Instant::now();
         let mut detected_languages = HashMap::new();
+        let mut dependencies: HashMap<String, Vec<String>> = HashMap::new();
+        let mut locked_versions: HashMap<String, HashMap<String, String>> = HashMap::new();
         let mut total_files_scanned = 0;
 
         info!("🔍 开始环境检测...");
@@ -170,6 +178,17 @@ impl EnvironmentDetector {
                 let cli_tools = self.check_cli_tools(&language).await;
                 let detected_features = self.detect_language_features(&language, &files, scan_path).await;
                 let score = self.calculate_language_score(&language, &files, &cli_tools);
+                let declared = collect_manifest_dependencies(&language, scan_path).await;
+                if !declared.is_empty() {
+                    let entry = dependencies.entry(language.clone()).or_default();
+                    entry.extend(declared);
+                    entry.sort();
+                    entry.dedup();
+                }
+                let locked = collect_locked_versions(&language, scan_path).await;
+                if !locked.is_empty() {
+                    locked_versions.entry(language.clone()).or_default().extend(locked);
+                }
                 
                 let lang_info = LanguageInfo {
                     name: language.clone(),
@@ -192,6 +211,8 @@ impl EnvironmentDetector {
             scan_duration_ms: duration.as_millis() as u64,
             scan_paths: self.scan_paths.clone(),
             total_files_scanned,
+            dependencies,
+            locked_versions,
         })
     }
 
@@ -527,8 +548,266 @@ impl EnvironmentDetector {
     }
 }
 
+/// 读取项目清单中声明的依赖包名（不含版本），清单不存在或无法解析时返回空列表
+async fn collect_manifest_dependencies(language: &str, scan_path: &Path) -> Vec<String> {
+    let manifests: &[&str] = match language {
+        "rust" => &["Cargo.toml"],
+        "python" => &["requirements.txt", "pyproject.toml"],
+        "javascript" => &["package.json"],
+        "go" => &["go.mod"],
+        "php" => &["composer.json"],
+        "dart" => &["pubspec.yaml"],
+        _ => return Vec::new(),
+    };
+    let mut names = Vec::new();
+    for manifest in manifests {
+        if let Ok(content) = tokio::fs::read_to_string(scan_path.join(manifest)).await {
+            names.extend(parse_manifest_dependencies(manifest, &content));
+        }
+    }
+    names
+}
+
+/// 从清单内容中提取依赖包名
+pub fn parse_manifest_dependencies(manifest: &str, content: &str) -> Vec<String> {
+    const TOML_SECTIONS: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+    const JSON_SECTIONS: &[&str] = &["dependencies", "devDependencies", "peerDependencies", "require", "require-dev"];
+
+    let mut names = Vec::new();
+    match manifest {
+        "Cargo.toml" => {
+            if let Ok(value) = content.parse::<toml::Value>() {
+                let tables = std::iter::once(&value).chain(value.get("workspace"));
+                for table in tables {
+                    for section in TOML_SECTIONS {
+                        if let Some(deps) = table.get(section).and_then(|d| d.as_table()) {
+                            names.extend(deps.keys().cloned());
+                        }
+                    }
+                }
+            }
+        }
+        "pyproject.toml" => {
+            if let Ok(value) = content.parse::<toml::Value>() {
+                let pep621 = value.get("project").and_then(|p| p.get("dependencies")).and_then(|d| d.as_array());
+                names.extend(pep621.into_iter().flatten().filter_map(|d| d.as_str()).filter_map(requirement_name));
+                let poetry = value.get("tool").and_then(|t| t.get("poetry")).and_then(|p| p.get("dependencies")).and_then(|d| d.as_table());
+                names.extend(poetry.into_iter().flat_map(|d| d.keys()).filter(|k| k.as_str() != "python").cloned());
+            }
+        }
+        "requirements.txt" => {
+            names.extend(content.lines().map(str::trim).filter(|l| !l.starts_with('-')).filter_map(requirement_name));
+        }
+        "package.json" | "composer.json" => {
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(content) {
+                for section in JSON_SECTIONS {
+                    if let Some(deps) = value.get(section).and_then(|d| d.as_object()) {
+                        names.extend(deps.keys().filter(|k| !k.starts_with("ext-") && k.as_str() != "php").cloned());
+                    }
+                }
+            }
+        }
+        "go.mod" => {
+            let mut in_block = false;
+            for line in content.lines().map(str::trim) {
+                let spec = if in_block {
+                    if line.starts_with(')') {
+                        in_block = false;
+                        continue;
+                    }
+                    line
+                } else if line == "require (" {
+                    in_block = true;
+                    continue;
+                } else if let Some(spec) = line.strip_prefix("require ") {
+                    spec
+                } else {
+                    continue;
+                };
+                if let Some(module) = spec.split_whitespace().next().filter(|m| !m.starts_with("//")) {
+                    names.push(module.to_string());
+                }
+            }
+        }
+        "pubspec.yaml" => {
+            let mut in_deps = false;
+            for line in content.lines() {
+                if !line.starts_with(' ') && !line.trim().is_empty() {
+                    in_deps = matches!(line.trim_end(), "dependencies:" | "dev_dependencies:");
+                    continue;
+                }
+                // 只取依赖段下第一层缩进的键
+                let key = line.strip_prefix("  ").filter(|l| !l.starts_with(' ')).and_then(|l| l.split(':').next());
+                if let Some(key) = key.filter(|k| in_deps && !k.is_empty() && *k != "flutter") {
+                    names.push(key.trim().to_string());
+                }
+            }
+        }
+        _ => {}
+    }
+    names
+}
+
+/// 读取项目锁文件中锁定的包版本，同一个包出现多个版本时无法确定项目用的是哪个，不记录
+async fn collect_locked_versions(language: &str, scan_path: &Path) -> HashMap<String, String> {
+    let lockfiles: &[&str] = match language {
+        "rust" => &["Cargo.lock"],
+        "python" => &["poetry.lock"],
+        "javascript" => &["package-lock.json"],
+        "php" => &["composer.lock"],
+        "dart" => &["pubspec.lock"],
+        _ => return HashMap::new(),
+    };
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+    for lockfile in lockfiles {
+        if let Ok(content) = tokio::fs::read_to_string(scan_path.join(lockfile)).await {
+            for (name, version) in parse_lockfile_versions(lockfile, &content) {
+                let entry = versions.entry(name).or_default();
+                if !entry.contains(&version) {
+                    entry.push(version);
+                }
+            }
+        }
+    }
+    versions
+        .into_iter()
+        .filter(|(_, versions)| versions.len() == 1)
+        .map(|(name, mut versions)| (name, versions.remove(0)))
+        .collect()
+}
+
+/// 从锁文件内容中提取 (包名, 锁定版本)
+pub fn parse_lockfile_versions(lockfile: &str, content: &str) -> Vec<(String, String)> {
+    let mut versions = Vec::new();
+    match lockfile {
+        "Cargo.lock" | "poetry.lock" => {
+            if let Ok(value) = content.parse::<toml::Value>() {
+                for package in value.get("package").and_then(|p| p.as_array()).into_iter().flatten() {
+                    let name = package.get("name").and_then(|n| n.as_str());
+                    let version = package.get("version").and_then(|v| v.as_str());
+                    if let (Some(name), Some(version)) = (name, version) {
+                        versions.push((name.to_string(), version.to_string()));
+                    }
+                }
+            }
+        }
+        "package-lock.json" => {
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(content) {
+                // lockfileVersion 2/3 的 packages 以安装路径为键，只取顶层 node_modules 下的包
+                if let Some(packages) = value.get("packages").and_then(|p| p.as_object()) {
+                    for (path, package) in packages {
+                        let Some(name) = path.strip_prefix("node_modules/").filter(|n| !n.contains("/node_modules/")) else {
+                            continue;
+                        };
+                        if let Some(version) = package.get("version").and_then(|v| v.as_str()) {
+                            versions.push((name.to_string(), version.to_string()));
+                        }
+                    }
+                } else if let Some(dependencies) = value.get("dependencies").and_then(|d| d.as_object()) {
+                    for (name, package) in dependencies {
+                        if let Some(version) = package.get("version").and_then(|v| v.as_str()) {
+                            versions.push((name.clone(), version.to_string()));
+                        }
+                    }
+                }
+            }
+        }
+        "composer.lock" => {
+            if let Ok(value) = serde_json::from_str::<serde_json::Value>(content) {
+                for section in ["packages", "packages-dev"] {
+                    for package in value.get(section).and_then(|p| p.as_array()).into_iter().flatten() {
+                        let name = package.get("name").and_then(|n| n.as_str());
+                        let version = package.get("version").and_then(|v| v.as_str());
+                        if let (Some(name), Some(version)) = (name, version) {
+                            versions.push((name.to_string(), version.trim_start_matches('v').to_string()));
+                        }
+                    }
+                }
+            }
+        }
+        "pubspec.lock" => {
+            let mut in_packages = false;
+            let mut current: Option<String> = None;
+            for line in content.lines() {
+                if !line.starts_with(' ') && !line.trim().is_empty() {
+                    in_packages = line.trim_end() == "packages:";
+                    current = None;
+                    continue;
+                }
+                if !in_packages {
+                    continue;
+                }
+                if let Some(key) = line.strip_prefix("  ").filter(|l| !l.starts_with(' ')).and_then(|l| l.strip_suffix(':')) {
+                    current = Some(key.trim().to_string());
+                } else if let (Some(name), Some(version)) = (&current, line.trim().strip_prefix("version:")) {
+                    versions.push((name.clone(), version.trim().trim_matches('"').to_string()));
+                }
+            }
+        }
+        _ => {}
+    }
+    versions
+}
+
+/// 从 PEP 508 依赖声明中取出包名，例如 `requests[socks]>=2.0` -> `requests`
+fn requirement_name(spec: &str) -> Option<String> {
+    let name: String = spec
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    (!name.is_empty() && !spec.trim_start().starts_with('#')).then_some(name)
+}
+
 impl Default for EnvironmentDetector {
     fn default() -> Self {
         Self::new()
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest_dependencies() {
+        let cargo = "[package]\nname = \"demo\"\n\n[dependencies]\nserde = \"1\"\ntokio = { version = \"1\" }\n\n[dev-dependencies]\ntempfile = \"3\"\n";
+        assert_eq!(parse_manifest_dependencies("Cargo.toml", cargo), vec!["serde", "tokio", "tempfile"]);
+
+        let requirements = "# web\nrequests[socks]>=2.0\n-r base.txt\nDjango==4.2\n";
+        assert_eq!(parse_manifest_dependencies("requirements.txt", requirements), vec!["requests", "Django"]);
+
+        let package_json = r#"{"dependencies": {"react": "^18"}, "devDependencies": {"vite": "^5"}}"#;
+        assert_eq!(parse_manifest_dependencies("package.json", package_json), vec!["react", "vite"]);
+
+        let go_mod = "module example.com/app\n\nrequire (\n\tgithub.com/gin-gonic/gin v1.9.1\n)\nrequire golang.org/x/sync v0.5.0\n";
+        assert_eq!(
+            parse_manifest_dependencies("go.mod", go_mod),
+            vec!["github.com/gin-gonic/gin", "golang.org/x/sync"]
+        );
+    }
+
+    #[test]
+    fn test_parse_lockfile_versions() {
+        let cargo_lock = "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.203\"\n\n[[package]]\nname = \"tokio\"\nversion = \"1.38.0\"\n";
+        assert_eq!(
+            parse_lockfile_versions("Cargo.lock", cargo_lock),
+            vec![("serde".to_string(), "1.0.203".to_string()), ("tokio".to_string(), "1.38.0".to_string())]
+        );
+
+        let package_lock = r#"{"lockfileVersion": 3, "packages": {
+            "": {"name": "app"},
+            "node_modules/react": {"version": "18.3.1"},
+            "node_modules/@types/node": {"version": "20.14.2"},
+            "node_modules/a/node_modules/react": {"version": "17.0.2"}
+        }}"#;
+        let mut npm = parse_lockfile_versions("package-lock.json", package_lock);
+        npm.sort();
+        assert_eq!(
+            npm,
+            vec![("@types/node".to_string(), "20.14.2".to_string()), ("react".to_string(), "18.3.1".to_string())]
+        );
+
+        let pubspec_lock = "packages:\n  http:\n    dependency: \"direct main\"\n    version: \"1.2.1\"\nsdks:\n  dart: \">=3.0.0 <4.0.0\"\n";
+        assert_eq!(parse_lockfile_versions("pubspec.lock", pubspec_lock), vec![("http".to_string(), "1.2.1".to_string())]);
+    }
+}
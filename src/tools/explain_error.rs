@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use anyhow::Result;
+
+use crate::errors::{server_error, MCPError};
+use crate::tools::base::{MCPTool, Schema, SchemaObject, SchemaString};
+use crate::tools::qa_enrichment::QA_DOC_TYPE;
+use crate::tools::vector_docs_tool::{SearchResult, VectorDocsTool};
+
+/// 归一化后的错误信息
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NormalizedError {
+    /// 推断或指定的语言
+    pub language: Option<String>,
+    /// 错误码，如 `E0382`、`TS2322`
+    pub code: Option<String>,
+    /// 错误类别，如 `TypeError`、`ModuleNotFoundError`
+    pub kind: Option<String>,
+    /// 去除路径、行号、地址后的错误消息
+    pub message: String,
+}
+
+impl NormalizedError {
+    /// 用于检索的查询文本，消息中未出现的错误码/类别补在前面
+    pub fn search_query(&self) -> String {
+        let mut parts: Vec<&str> = [self.code.as_deref(), self.kind.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|part| !self.message.contains(part))
+            .collect();
+        parts.push(&self.message);
+        parts.join(" ")
+    }
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("内置正则无效"))
+}
+
+/// 归一化错误字符串：挑出最有信息量的一行，去掉路径、行列号、内存地址等噪音
+pub fn normalize_error(raw: &str, language_hint: Option<&str>) -> NormalizedError {
+    static PATH: OnceLock<Regex> = OnceLock::new();
+    static LINE_COL: OnceLock<Regex> = OnceLock::new();
+    static ADDRESS: OnceLock<Regex> = OnceLock::new();
+    static CODE: OnceLock<Regex> = OnceLock::new();
+    static KIND: OnceLock<Regex> = OnceLock::new();
+
+    let code = regex(&CODE, r"\b(E\d{4}|TS\d{4,5}|CS\d{4})\b")
+        .captures(raw)
+        .map(|c| c[1].to_string());
+    let kind = regex(&KIND, r"\b([A-Z][A-Za-z]*(?:Error|Exception))\b")
+        .captures(raw)
+        .map(|c| c[1].to_string());
+
+    // 优先取带 error/exception/panic 字样的行，否则取第一行非空内容
+    let line = raw
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .find(|l| {
+            let lower = l.to_lowercase();
+            lower.contains("error") || lower.contains("exception") || lower.contains("panicked")
+        })
+        .or_else(|| raw.lines().map(str::trim).find(|l| !l.is_empty()))
+        .unwrap_or("");
+
+    let mut message = regex(&PATH, r#"(?:[A-Za-z]:)?(?:[\w.~-]*[/\\])+[\w.-]+\.(?:rs|py|js|mjs|ts|tsx|jsx|go|java|kt|dart|cs)\b"#)
+        .replace_all(line, "")
+        .to_string();
+    message = regex(&LINE_COL, r"(?:(?:,\s*)?\bline \d+|:\d+(?::\d+)?)")
+        .replace_all(&message, "")
+        .to_string();
+    message = regex(&ADDRESS, r"\b0x[0-9a-fA-F]+\b")
+        .replace_all(&message, "")
+        .to_string();
+    let message = message
+        .trim_start_matches(|c: char| c == '-' || c == '>' || c.is_whitespace())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let language = language_hint
+        .map(|l| l.to_lowercase())
+        .or_else(|| infer_language(raw, code.as_deref()).map(String::from));
+
+    NormalizedError { language, code, kind, message }
+}
+
+/// 根据错误格式推断语言
+fn infer_language(raw: &str, code: Option<&str>) -> Option<&'static str> {
+    if let Some(code) = code {
+        if code.starts_with('E') {
+            return Some("rust");
+        }
+        if code.starts_with("TS") {
+            return Some("typescript");
+        }
+    }
+    if raw.contains("panicked at") || raw.contains("error[") || raw.contains(".rs:") {
+        Some("rust")
+    } else if raw.contains("Traceback (most recent call last)") || raw.contains(".py\"") || raw.contains(".py:") {
+        Some("python")
+    } else if raw.contains("java.lang.") || raw.contains("Exception in thread") {
+        Some("java")
+    } else if raw.contains("goroutine ") || raw.contains(".go:") {
+        Some("go")
+    } else if raw.contains("at Object.<anonymous>") || raw.contains("node_modules") || raw.contains(".js:") {
+        Some("javascript")
+    } else {
+        None
+    }
+}
+
+/// 语言层面的常见错误说明（与具体包无关）
+fn language_hints(error: &NormalizedError) -> Vec<Value> {
+    const HINTS: &[(&str, &str, &str)] = &[
+        ("rust", "E0382", "使用了已被移动（move）的值。考虑借用（&value）、克隆（.clone()），或让类型实现 Copy。"),
+        ("rust", "E0499", "同一时间存在多个可变借用。缩小借用作用域，或拆分数据结构后分别借用。"),
+        ("rust", "E0502", "可变借用与不可变借用冲突。先结束不可变借用（例如先把需要的值复制出来）再进行修改。"),
+        ("rust", "E0106", "缺少生命周期标注。为引用参数/返回值添加生命周期参数，或返回拥有所有权的类型。"),
+        ("rust", "E0277", "类型没有实现所需的 trait。检查泛型约束，或为类型实现/派生该 trait。"),
+        ("rust", "E0308", "类型不匹配。检查返回值、分支表达式和函数参数的类型是否一致。"),
+        ("typescript", "TS2322", "赋值类型不兼容。检查目标类型声明，必要时收窄联合类型。"),
+        ("typescript", "TS2345", "实参类型与形参不兼容。检查调用处传入的值类型。"),
+        ("typescript", "TS2339", "类型上不存在该属性。检查拼写、类型声明，或先做类型收窄。"),
+        ("python", "ModuleNotFoundError", "模块未安装或不在当前解释器环境中。确认虚拟环境并用 pip 安装对应包。"),
+        ("python", "IndentationError", "缩进不一致。统一使用 4 个空格，不要混用 Tab。"),
+        ("python", "KeyError", "字典中不存在该键。使用 dict.get() 或先检查键是否存在。"),
+        ("python", "AttributeError", "对象没有该属性，常见原因是变量为 None 或类型与预期不同。"),
+        ("javascript", "TypeError", "值的类型与操作不符，常见于对 undefined/null 访问属性或调用非函数。"),
+        ("javascript", "ReferenceError", "使用了未声明的变量，检查拼写和作用域。"),
+        ("java", "NullPointerException", "对 null 引用调用了方法或访问字段。检查初始化，或使用 Optional。"),
+        ("go", "declared and not used", "Go 不允许未使用的局部变量。删除变量或用 _ 接收。"),
+    ];
+
+    let Some(language) = error.language.as_deref() else {
+        return Vec::new();
+    };
+    HINTS
+        .iter()
+        .filter(|(lang, key, _)| {
+            *lang == language
+                && (error.code.as_deref() == Some(*key)
+                    || error.kind.as_deref() == Some(*key)
+                    || error.message.contains(key))
+        })
+        .map(|(lang, key, hint)| json!({ "language": lang, "key": key, "explanation": hint }))
+        .collect()
+}
+
+/// 错误信息解释工具
+///
+/// 归一化编译器/运行时错误后，在已缓存的文档和问答片段中查找解释与修复方法，
+/// 并附上语言层面的常见错误说明。
+pub struct ExplainErrorTool {
+    vector_tool: Arc<VectorDocsTool>,
+    schema: Schema,
+}
+
+impl ExplainErrorTool {
+    pub fn new(vector_tool: Arc<VectorDocsTool>) -> Self {
+        Self {
+            vector_tool,
+            schema: Self::create_schema(),
+        }
+    }
+
+    fn create_schema() -> Schema {
+        let mut props = HashMap::new();
+        props.insert("error".to_string(), Schema::String(SchemaString {
+            description: Some("编译器或运行时的错误输出（可包含堆栈）".to_string()),
+            enum_values: None,
+        }));
+        props.insert("language".to_string(), Schema::String(SchemaString {
+            description: Some("编程语言（可选，默认根据错误格式推断）".to_string()),
+            enum_values: None,
+        }));
+        props.insert("package".to_string(), Schema::String(SchemaString {
+            description: Some("相关的包名（可选，用于优先返回该包的文档）".to_string()),
+            enum_values: None,
+        }));
+        props.insert("limit".to_string(), Schema::String(SchemaString {
+            description: Some("每类结果的数量，默认5".to_string()),
+            enum_values: None,
+        }));
+        Schema::Object(SchemaObject {
+            required: vec!["error".to_string()],
+            properties: props,
+            description: Some("解释错误信息".to_string()),
+        })
+    }
+
+    fn render_result(result: &SearchResult) -> Value {
+        json!({
+            "title": result.title,
+            "package": format!("{}/{}@{}", result.language, result.package_name, result.version),
+            "doc_type": result.doc_type,
+            "score": result.score,
+            "content": result.content,
+            "source": result.metadata.get("file_path").cloned().unwrap_or_else(|| result.id.clone()),
+        })
+    }
+}
+
+#[async_trait]
+impl MCPTool for ExplainErrorTool {
+    fn name(&self) -> &str {
+        "explain_error"
+    }
+
+    fn description(&self) -> &str {
+        "在遇到编译器或运行时错误时，归一化错误信息（去除路径和行号）并从已缓存的文档、社区问答和语言常见错误说明中查找解释与修复方法。"
+    }
+
+    fn parameters_schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let raw = params["error"]
+            .as_str()
+            .filter(|s| !s.trim().is_empty())
+            .ok_or_else(|| MCPError::InvalidParameter("缺少error参数".to_string()))?;
+        let package = params["package"].as_str();
+        let limit = params["limit"].as_u64()
+            .or_else(|| params["limit"].as_str().and_then(|s| s.parse().ok()))
+            .unwrap_or(5) as usize;
+
+        let normalized = normalize_error(raw, params["language"].as_str());
+        let query = match package {
+            Some(package) => format!("{} {}", package, normalized.search_query()),
+            None => normalized.search_query(),
+        };
+
+        let query_embedding = self.vector_tool.generate_embedding(&query).await
+            .map_err(|e| server_error("生成查询嵌入向量失败", e))?;
+        let mut results = self.vector_tool.hybrid_search(&query_embedding, &query, limit * 4)?;
+        if let Some(language) = normalized.language.as_deref() {
+            results.retain(|r| r.language == language || r.language == "unknown");
+        }
+        // 指定包时该包的片段排在前面，其余按得分
+        if let Some(package) = package {
+            results.sort_by_key(|r| r.package_name != package);
+        }
+
+        let (qa, docs): (Vec<_>, Vec<_>) = results.iter().partition(|r| r.doc_type == QA_DOC_TYPE);
+        let documentation: Vec<Value> = docs.into_iter().take(limit).map(Self::render_result).collect();
+        let answers: Vec<Value> = qa.into_iter().take(limit).map(Self::render_result).collect();
+        let hints = language_hints(&normalized);
+
+        Ok(json!({
+            "status": if documentation.is_empty() && answers.is_empty() && hints.is_empty() { "no_match" } else { "success" },
+            "normalized": normalized,
+            "query": query,
+            "language_hints": hints,
+            "documentation": documentation,
+            "qa": answers,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_rust_error() {
+        let raw = "error[E0382]: borrow of moved value: `config`\n  --> src/main.rs:42:15\n   |";
+        let normalized = normalize_error(raw, None);
+        assert_eq!(normalized.language.as_deref(), Some("rust"));
+        assert_eq!(normalized.code.as_deref(), Some("E0382"));
+        assert_eq!(normalized.message, "error[E0382]: borrow of moved value: `config`");
+        assert_eq!(language_hints(&normalized).len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_python_traceback() {
+        let raw = "Traceback (most recent call last):\n  File \"/home/dev/app/main.py\", line 12, in <module>\n    import requests\nModuleNotFoundError: No module named 'requests'";
+        let normalized = normalize_error(raw, None);
+        assert_eq!(normalized.language.as_deref(), Some("python"));
+        assert_eq!(normalized.kind.as_deref(), Some("ModuleNotFoundError"));
+        assert_eq!(normalized.message, "ModuleNotFoundError: No module named 'requests'");
+        assert_eq!(normalized.search_query(), "ModuleNotFoundError: No module named 'requests'");
+    }
+
+    #[test]
+    fn test_normalize_strips_paths_and_addresses() {
+        let raw = "TypeError: Cannot read properties of undefined (reading 'map') at /srv/app/src/list.js:17:9 (0x7ffd5e8c)";
+        let normalized = normalize_error(raw, Some("JavaScript"));
+        assert_eq!(normalized.language.as_deref(), Some("javascript"));
+        assert_eq!(normalized.message, "TypeError: Cannot read properties of undefined (reading 'map') at ()");
+        assert_eq!(normalize_error("error: E0308 mismatched types", None).search_query(), "error: E0308 mismatched types");
+    }
+}
@@ -0,0 +1,203 @@
+//! 混合搜索的候选打分
+//!
+//! 向量检索返回的候选按关键词匹配、语言/包名上下文和文档类型重新打分后排序。
+//! 设置了全文索引的 BM25 分数时，关键词分数取归一化后的 BM25 分数，不再逐个子串匹配。
+//! 候选数达到 [`PARALLEL_THRESHOLD`] 时用 rayon 并行打分和排序，少量候选仍串行处理，
+//! 避免线程调度开销超过打分本身。性能对比见 `cargo bench --bench hybrid_scoring`。
+
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use super::vector_docs_tool::SearchResult;
+
+/// 启用并行打分的最少候选数
+pub const PARALLEL_THRESHOLD: usize = 512;
+
+/// 一次查询的打分器（查询文本和关键词只预处理一次）
+#[derive(Debug, Clone)]
+pub struct HybridScorer {
+    query_lower: String,
+    /// (关键词, 前后带空格的精确匹配形式)
+    keywords: Vec<(String, String)>,
+    /// 文档ID -> 归一化到 0-1 的 BM25 分数
+    text_scores: Option<HashMap<String, f32>>,
+}
+
+fn by_score_desc(a: &SearchResult, b: &SearchResult) -> Ordering {
+    b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal)
+}
+
+impl HybridScorer {
+    /// `keywords` 为查询分词后的关键词（调用方负责过滤短词和去重）
+    pub fn new(query_text: &str, keywords: impl IntoIterator<Item = String>) -> Self {
+        let keywords = keywords
+            .into_iter()
+            .map(|keyword| {
+                let padded = format!(" {} ", keyword);
+                (keyword, padded)
+            })
+            .collect();
+        Self { query_lower: query_text.to_lowercase(), keywords, text_scores: None }
+    }
+
+    /// 使用全文索引的 BM25 分数作为关键词分数（按最高分归一化），不在其中的文档关键词分数为 0
+    pub fn with_text_scores(mut self, scores: &[(String, f32)]) -> Self {
+        let max = scores.iter().map(|(_, score)| *score).fold(0.0f32, f32::max);
+        self.text_scores = Some(
+            scores
+                .iter()
+                .map(|(id, score)| (id.clone(), if max > 0.0 { score / max } else { 0.0 }))
+                .collect(),
+        );
+        self
+    }
+
+    /// 关键词匹配分数（0-1），标题匹配权重高于内容匹配
+    fn keyword_score(&self, title_lower: &str, content_lower: &str) -> f32 {
+        if self.keywords.is_empty() {
+            return 0.0;
+        }
+        let total: f32 = self
+            .keywords
+            .iter()
+            .map(|(keyword, padded)| {
+                let mut word_score: f32 = 0.0;
+                if title_lower.contains(keyword.as_str()) {
+                    word_score += 0.6;
+                }
+                if content_lower.contains(keyword.as_str()) {
+                    word_score += 0.4;
+                }
+                // 精确匹配加分
+                if content_lower.contains(padded.as_str()) || title_lower.contains(padded.as_str()) {
+                    word_score += 0.2;
+                }
+                word_score.min(1.0)
+            })
+            .sum();
+        total / self.keywords.len() as f32
+    }
+
+    /// 重新计算单个候选的分数：向量相似度60% + 关键词匹配30% + 上下文加分 + 文档类型调整
+    pub fn score(&self, result: &mut SearchResult) {
+        let keyword_score = match &self.text_scores {
+            Some(scores) => scores.get(&result.id).copied().unwrap_or(0.0),
+            None => self.keyword_score(&result.title.to_lowercase(), &result.content.to_lowercase()),
+        };
+
+        // 语言和包名匹配加分
+        let mut context_bonus = 0.0;
+        if self.query_lower.contains(&result.language.to_lowercase()) {
+            context_bonus += 0.1;
+        }
+        if self.query_lower.contains(&result.package_name.to_lowercase()) {
+            context_bonus += 0.1;
+        }
+
+        result.score = result.score * 0.6 + keyword_score * 0.3 + context_bonus;
+
+        // 文档类型相关性调整
+        if self.query_lower.contains("api") && result.doc_type.contains("api") {
+            result.score += 0.05;
+        }
+        if self.query_lower.contains("tutorial") && result.doc_type.contains("tutorial") {
+            result.score += 0.05;
+        }
+    }
+
+    /// 打分并按分数降序排列，跳过 `exclude` 中的ID；按候选数自动选择串行或并行
+    pub fn rank(&self, candidates: Vec<SearchResult>, exclude: &HashSet<String>) -> Vec<SearchResult> {
+        if candidates.len() >= PARALLEL_THRESHOLD {
+            self.rank_parallel(candidates, exclude)
+        } else {
+            self.rank_serial(candidates, exclude)
+        }
+    }
+
+    pub fn rank_serial(&self, candidates: Vec<SearchResult>, exclude: &HashSet<String>) -> Vec<SearchResult> {
+        let mut ranked: Vec<SearchResult> = candidates
+            .into_iter()
+            .filter(|result| !exclude.contains(&result.id))
+            .map(|mut result| {
+                self.score(&mut result);
+                result
+            })
+            .collect();
+        ranked.sort_by(by_score_desc);
+        ranked
+    }
+
+    pub fn rank_parallel(&self, candidates: Vec<SearchResult>, exclude: &HashSet<String>) -> Vec<SearchResult> {
+        let mut ranked: Vec<SearchResult> = candidates
+            .into_par_iter()
+            .filter(|result| !exclude.contains(&result.id))
+            .map(|mut result| {
+                self.score(&mut result);
+                result
+            })
+            .collect();
+        // 稳定排序，同分时保持候选原有顺序，与串行结果一致
+        ranked.par_sort_by(by_score_desc);
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn candidate(i: usize) -> SearchResult {
+        SearchResult {
+            id: format!("doc-{}", i),
+            content: if i % 3 == 0 { "spawn a tokio task".to_string() } else { "serialize with serde".to_string() },
+            title: format!("page {}", i),
+            language: "rust".to_string(),
+            package_name: if i % 2 == 0 { "tokio".to_string() } else { "serde".to_string() },
+            version: "1.0".to_string(),
+            doc_type: if i % 5 == 0 { "api_reference".to_string() } else { "documentation".to_string() },
+            metadata: HashMap::new(),
+            score: 1.0 / (1.0 + i as f32 / 100.0),
+        }
+    }
+
+    #[test]
+    fn test_keyword_and_context_scoring() {
+        let scorer = HybridScorer::new("tokio spawn api", vec!["tokio".to_string(), "spawn".to_string()]);
+        let mut result = candidate(0);
+        result.score = 1.0;
+        scorer.score(&mut result);
+        // 向量 1.0*0.6 + 关键词 ((0.4+0.2) 与 0.4 的平均) * 0.3 + 包名 0.1 + api 0.05
+        assert!((result.score - (0.6 + 0.5 * 0.3 + 0.1 + 0.05)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_text_scores_replace_substring_matching() {
+        let scorer = HybridScorer::new("spawn", vec!["spawn".to_string()])
+            .with_text_scores(&[("doc-0".to_string(), 4.0), ("doc-1".to_string(), 2.0)]);
+        let (mut first, mut second, mut third) = (candidate(0), candidate(1), candidate(3));
+        for result in [&mut first, &mut second, &mut third] {
+            result.score = 0.0;
+            scorer.score(result);
+        }
+        assert!((first.score - 0.3).abs() < 1e-5);
+        assert!((second.score - 0.15).abs() < 1e-5);
+        // doc-3 内容含 spawn，但不在 BM25 结果中
+        assert_eq!(third.score, 0.0);
+    }
+
+    #[test]
+    fn test_parallel_ranking_matches_serial() {
+        let candidates: Vec<SearchResult> = (0..PARALLEL_THRESHOLD * 2).map(candidate).collect();
+        let exclude: HashSet<String> = ["doc-0".to_string(), "doc-7".to_string()].into_iter().collect();
+        let scorer = HybridScorer::new("how to spawn a tokio task", vec!["spawn".to_string(), "tokio".to_string(), "task".to_string()]);
+
+        let serial = scorer.rank_serial(candidates.clone(), &exclude);
+        let parallel = scorer.rank_parallel(candidates, &exclude);
+        assert_eq!(serial.len(), PARALLEL_THRESHOLD * 2 - 2);
+        assert!(serial.iter().all(|r| !exclude.contains(&r.id)));
+        let ids = |results: &[SearchResult]| results.iter().map(|r| r.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&serial), ids(&parallel));
+    }
+}
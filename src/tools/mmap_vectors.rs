@@ -0,0 +1,352 @@
+//! 可内存映射的向量文件与 HNSW 索引持久化
+//!
+//! 原来的格式（`vector_data.bin`）把全部向量反序列化进内存，启动时还要重新建 HNSW 索引，
+//! 几十万个向量时启动需要数十秒。启用 `mmap_index`（默认开启）后，向量单独写入按行连续存放的
+//! `vectors-<代>.f32`，HNSW 图写入 `hnsw-<代>.bin`（图中的点只保存行号）：
+//! - 启动时映射向量文件，只反序列化图结构，不重建索引，向量在搜索访问时由操作系统按页读入；
+//! - 每次保存写入新一代文件再切换 `vector_data.bin` 中的引用，从不原地修改已映射的文件，
+//!   并保留上一代文件，只读跟随实例在切换期间仍能打开它。
+
+use anyhow::{anyhow, Result};
+use instant_distance::HnswMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::fs;
+use std::io::{BufReader, BufWriter, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::DistanceMetric;
+
+const MAGIC: &[u8; 8] = b"GRVECMM1";
+
+/// 文件头长度：魔数、维度（u32）、保留（u32）、行数（u64）、保留（u64）；保持 f32 对齐
+const HEADER_LEN: usize = 32;
+
+/// 保留的文件代数（当前一代和上一代）
+const KEEP_GENERATIONS: usize = 2;
+
+/// 向量文件和索引文件的引用，记录在 `vector_data.bin` 中
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MappedLayout {
+    pub generation: u64,
+    pub dim: usize,
+    pub rows: usize,
+    /// 索引建图时使用的度量，与当前配置不同时需要重建索引
+    pub metric: DistanceMetric,
+    /// 是否保存了 HNSW 图（量化索引不保存，启动时重建）
+    pub has_index: bool,
+}
+
+impl MappedLayout {
+    pub fn vectors_file(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("vectors-{:016x}.f32", self.generation))
+    }
+
+    pub fn index_file(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("hnsw-{:016x}.bin", self.generation))
+    }
+}
+
+/// 新的文件代号（单调递增的时间戳）
+pub fn new_generation() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// 删除较旧的向量和索引文件，只保留最新的两代；正在被映射的文件（Windows）删除失败时留到下次
+pub fn remove_stale_generations(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(u64, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let hex = name.strip_prefix("vectors-").and_then(|rest| rest.strip_suffix(".f32"))
+                .or_else(|| name.strip_prefix("hnsw-").and_then(|rest| rest.strip_suffix(".bin")))?;
+            Some((u64::from_str_radix(hex, 16).ok()?, entry.path()))
+        })
+        .collect();
+    let mut generations: Vec<u64> = files.iter().map(|(generation, _)| *generation).collect();
+    generations.sort_unstable_by(|a, b| b.cmp(a));
+    generations.dedup();
+    let Some(&oldest_kept) = generations.get(KEEP_GENERATIONS - 1) else {
+        return;
+    };
+    files.retain(|(generation, _)| *generation < oldest_kept);
+    for (_, path) in files {
+        if let Err(e) = fs::remove_file(&path) {
+            tracing::debug!("删除旧向量文件 {:?} 失败: {}", path, e);
+        }
+    }
+}
+
+/// 内存映射的向量文件
+pub struct MappedVectors {
+    mmap: memmap2::Mmap,
+    dim: usize,
+    rows: usize,
+}
+
+impl std::fmt::Debug for MappedVectors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedVectors").field("dim", &self.dim).field("rows", &self.rows).finish()
+    }
+}
+
+impl MappedVectors {
+    /// 写入向量文件（小端 f32，按行连续存放），先写临时文件再改名
+    pub fn write<'a>(path: &Path, dim: usize, rows: impl ExactSizeIterator<Item = &'a [f32]>) -> Result<()> {
+        let tmp_path = path.with_extension("f32.tmp");
+        let mut writer = BufWriter::new(fs::File::create(&tmp_path)?);
+        let mut header = [0u8; HEADER_LEN];
+        header[..8].copy_from_slice(MAGIC);
+        header[8..12].copy_from_slice(&(dim as u32).to_le_bytes());
+        header[16..24].copy_from_slice(&(rows.len() as u64).to_le_bytes());
+        writer.write_all(&header)?;
+        for row in rows {
+            if row.len() != dim {
+                return Err(anyhow!("向量维度不一致: {} != {}", row.len(), dim));
+            }
+            for x in row {
+                writer.write_all(&x.to_le_bytes())?;
+            }
+        }
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        if cfg!(target_endian = "big") {
+            return Err(anyhow!("大端平台不支持内存映射向量文件"));
+        }
+        let file = fs::File::open(path)?;
+        // SAFETY: 向量文件按代写入后不再修改（保存时总是写新一代文件），映射期间内容不会变化
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        if mmap.len() < HEADER_LEN || &mmap[..8] != MAGIC {
+            return Err(anyhow!("不是有效的向量文件: {:?}", path));
+        }
+        let dim = u32::from_le_bytes(mmap[8..12].try_into().unwrap()) as usize;
+        let rows = u64::from_le_bytes(mmap[16..24].try_into().unwrap());
+        // 文件头来自磁盘，损坏时行数和维度可能大到乘法溢出
+        let expected_len = usize::try_from(rows)
+            .ok()
+            .and_then(|rows| rows.checked_mul(dim))
+            .and_then(|n| n.checked_mul(std::mem::size_of::<f32>()))
+            .and_then(|n| n.checked_add(HEADER_LEN));
+        if expected_len != Some(mmap.len()) {
+            return Err(anyhow!("向量文件长度与文件头不符（可能已损坏）: {:?}", path));
+        }
+        let rows = rows as usize;
+        Ok(Self { mmap, dim, rows })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn row(&self, index: usize) -> &[f32] {
+        assert!(index < self.rows, "向量行号越界: {} >= {}", index, self.rows);
+        let start = HEADER_LEN + index * self.dim * std::mem::size_of::<f32>();
+        let bytes = &self.mmap[start..start + self.dim * std::mem::size_of::<f32>()];
+        // SAFETY: 映射起始地址按页对齐，文件头长度是 4 的倍数，因此每行都按 f32 对齐；
+        // 长度已在打开时校验，且只在小端平台上映射（文件内容为小端 f32）
+        unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const f32, self.dim) }
+    }
+}
+
+/// 一行向量：新写入的保存在内存中，从向量文件加载的指向映射区域
+#[derive(Debug, Clone)]
+pub enum VectorRow {
+    Owned(Arc<[f32]>),
+    Mapped(Arc<MappedVectors>, usize),
+}
+
+impl Deref for VectorRow {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        match self {
+            VectorRow::Owned(vector) => vector,
+            VectorRow::Mapped(vectors, index) => vectors.row(*index),
+        }
+    }
+}
+
+impl AsRef<[f32]> for VectorRow {
+    fn as_ref(&self) -> &[f32] {
+        self
+    }
+}
+
+impl From<Vec<f32>> for VectorRow {
+    fn from(vector: Vec<f32>) -> Self {
+        VectorRow::Owned(vector.into())
+    }
+}
+
+/// 映射向量文件中的全部行
+pub fn mapped_rows(vectors: &Arc<MappedVectors>) -> Vec<VectorRow> {
+    (0..vectors.len()).map(|index| VectorRow::Mapped(vectors.clone(), index)).collect()
+}
+
+thread_local! {
+    /// 反序列化索引时点所引用的向量文件和度量
+    static LOADING: RefCell<Option<(Arc<MappedVectors>, DistanceMetric)>> = const { RefCell::new(None) };
+}
+
+/// HNSW 索引中的点：向量所在的行号和向量本身（共享存储，不复制）
+///
+/// 持久化时只保存行号，加载时指向映射的向量文件。
+#[derive(Debug, Clone)]
+pub struct VectorPoint {
+    row: u32,
+    vector: VectorRow,
+    metric: DistanceMetric,
+}
+
+impl VectorPoint {
+    pub fn new(row: usize, vector: VectorRow, metric: DistanceMetric) -> Self {
+        Self { row: row as u32, vector, metric }
+    }
+
+    /// 查询点（不属于任何行）
+    pub fn query(vector: &[f32], metric: DistanceMetric) -> Self {
+        Self { row: u32::MAX, vector: VectorRow::from(vector.to_vec()), metric }
+    }
+}
+
+impl instant_distance::Point for VectorPoint {
+    fn distance(&self, other: &Self) -> f32 {
+        self.metric.distance(&self.vector, &other.vector)
+    }
+}
+
+impl Serialize for VectorPoint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.row)
+    }
+}
+
+impl<'de> Deserialize<'de> for VectorPoint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let row = u32::deserialize(deserializer)?;
+        LOADING.with(|loading| {
+            let loading = loading.borrow();
+            let (vectors, metric) = loading.as_ref()
+                .ok_or_else(|| serde::de::Error::custom("只能通过 load_index 加载索引"))?;
+            if row as usize >= vectors.len() {
+                return Err(serde::de::Error::custom(format!("索引中的行号 {} 超出向量文件范围", row)));
+            }
+            Ok(VectorPoint::new(row as usize, VectorRow::Mapped(vectors.clone(), row as usize), *metric))
+        })
+    }
+}
+
+/// 保存 HNSW 图，先写临时文件再改名
+pub fn save_index(path: &Path, map: &HnswMap<VectorPoint, String>) -> Result<()> {
+    let tmp_path = path.with_extension("bin.tmp");
+    let mut writer = BufWriter::new(fs::File::create(&tmp_path)?);
+    bincode::serialize_into(&mut writer, map)?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// 加载 HNSW 图，图中的点指向已映射的向量文件
+pub fn load_index(path: &Path, vectors: Arc<MappedVectors>, metric: DistanceMetric) -> Result<HnswMap<VectorPoint, String>> {
+    struct Reset;
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            LOADING.with(|loading| loading.borrow_mut().take());
+        }
+    }
+
+    let reader = BufReader::new(fs::File::open(path)?);
+    LOADING.with(|loading| *loading.borrow_mut() = Some((vectors, metric)));
+    let _reset = Reset;
+    Ok(bincode::deserialize_from(reader)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use instant_distance::{Builder, Search};
+
+    #[test]
+    fn test_mapped_index_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let rows: Vec<VectorRow> = (0..50)
+            .map(|i| VectorRow::from(vec![i as f32, (i * i) as f32 * 0.01, 1.0]))
+            .collect();
+        let layout = MappedLayout { generation: new_generation(), dim: 3, rows: rows.len(), metric: DistanceMetric::L2, has_index: true };
+        MappedVectors::write(&layout.vectors_file(dir.path()), 3, rows.iter().map(|row| &row[..])).unwrap();
+
+        let points: Vec<VectorPoint> = rows.iter().enumerate()
+            .map(|(i, row)| VectorPoint::new(i, row.clone(), DistanceMetric::L2))
+            .collect();
+        let values: Vec<String> = (0..rows.len()).map(|i| format!("doc-{}", i)).collect();
+        let map = Builder::default().build(points, values);
+        save_index(&layout.index_file(dir.path()), &map).unwrap();
+
+        let vectors = Arc::new(MappedVectors::open(&layout.vectors_file(dir.path())).unwrap());
+        assert_eq!((vectors.len(), vectors.dim()), (50, 3));
+        assert_eq!(vectors.row(7), &rows[7][..]);
+        let loaded = load_index(&layout.index_file(dir.path()), vectors, DistanceMetric::L2).unwrap();
+
+        let query = VectorPoint::query(&[20.2, 4.0, 1.0], DistanceMetric::L2);
+        let mut search = Search::default();
+        let nearest = loaded.search(&query, &mut search).next().unwrap();
+        assert_eq!(nearest.value, "doc-20");
+
+        // 索引只能在提供向量文件时反序列化
+        let bytes = bincode::serialize(&map).unwrap();
+        assert!(bincode::deserialize::<HnswMap<VectorPoint, String>>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_stale_generations_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        for generation in 1..=3u64 {
+            let layout = MappedLayout { generation, dim: 1, rows: 1, metric: DistanceMetric::L2, has_index: false };
+            MappedVectors::write(&layout.vectors_file(dir.path()), 1, [&[1.0f32][..]].into_iter()).unwrap();
+            fs::write(layout.index_file(dir.path()), b"").unwrap();
+        }
+        remove_stale_generations(dir.path());
+        let mut names: Vec<String> = fs::read_dir(dir.path()).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![
+            "hnsw-0000000000000002.bin", "hnsw-0000000000000003.bin",
+            "vectors-0000000000000002.f32", "vectors-0000000000000003.f32",
+        ]);
+        assert!(MappedVectors::open(&dir.path().join("hnsw-0000000000000003.bin")).is_err());
+    }
+
+    #[test]
+    fn test_overflowing_header_is_reported_as_corrupt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vectors.f32");
+        MappedVectors::write(&path, 2, [&[1.0f32, 2.0][..]].into_iter()).unwrap();
+        let mut data = fs::read(&path).unwrap();
+        data[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        data[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(&path, data).unwrap();
+
+        let err = MappedVectors::open(&path).unwrap_err();
+        assert!(err.to_string().contains("可能已损坏"));
+    }
+}
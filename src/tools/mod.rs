@@ -14,18 +14,65 @@ pub mod security;
 pub mod versioning;
 pub mod vector_docs_tool;
 pub mod doc_processor;
+pub mod content_language;
+pub mod quality_gate;
+pub mod symbol_index;
+pub mod project_context;
+pub mod search_mode;
+pub mod text_analyzer;
+pub mod pagination;
+pub mod content_store;
+pub mod hybrid_scoring;
+pub mod bm25_index;
+pub mod dedup;
+pub mod score_normalization;
+pub mod embedding_cache;
+pub mod embedding_batcher;
+pub mod search_filter;
+pub mod embedder;
 pub mod enhanced_language_tool;
 pub mod environment_detector;
 pub mod dynamic_registry;
 pub mod enhanced_doc_processor;
 pub mod environment;
 pub mod background_cacher;
+pub mod cache_tiers;
+pub mod cache_eviction;
+pub mod crypto;
+pub mod doc_packs;
+pub mod devdocs;
+pub mod qa_enrichment;
+pub mod context_export;
+pub mod explain_error;
+pub mod data_lock;
+pub mod data_format;
+pub mod package_progress;
+pub mod workspace_versions;
+pub mod audit_tool;
+pub mod crawl_report;
+pub mod cache_webhook;
+pub mod platform;
+pub mod site_auth;
+pub mod quantization;
+pub mod provenance;
+pub mod mmap_vectors;
+pub mod cache_sync;
+pub mod snapshot;
+pub mod snapshot_tool;
+pub mod dependency_cleanup;
+pub mod doc_coverage;
+pub mod answer_validation;
+pub mod write_ahead_log;
+pub mod corpus_io;
+#[cfg(feature = "database")]
+pub mod sqlite_metadata;
 // pub mod unified_vector_store; // 禁用：Tantivy兼容性问题
 
 /// 文档处理模块 - 提供多语言文档解析和处理功能
 pub mod docs {
     pub mod doc_traits;
     pub mod openai_vectorizer;
+    pub mod reranker;
 }
 
 #[cfg(test)]
@@ -56,4 +103,11 @@ pub use dynamic_registry::DynamicToolRegistry;
 pub use doc_processor::DocumentProcessor;
 pub use enhanced_doc_processor::{EnhancedDocumentProcessor, ProcessorConfig, EnhancedSearchResult};
 pub use vector_docs_tool::VectorDocsTool;
+pub use context_export::ExportContextBundleTool;
+pub use explain_error::ExplainErrorTool;
+pub use audit_tool::AuditLogTool;
+pub use snapshot_tool::VectorSnapshotTool;
+pub use doc_coverage::DocCoverageTool;
+pub use answer_validation::AnswerValidationTool;
+pub use crawl_report::GetCrawlReportTool;
 pub use search::SearchDocsTools;
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+use crate::tools::quality_gate::RejectionCounts;
+
+/// 进行中的抓取超过该时间没有进展时视为中断，可被重新认领
+pub const STALE_PROGRESS_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// 包版本文档处理状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressStatus {
+    /// 正在抓取/处理
+    InProgress,
+    /// 流水线完整结束
+    Complete,
+    /// 处理失败，可重试
+    Failed,
+}
+
+/// 单个包版本的文档处理进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageProgress {
+    pub status: ProgressStatus,
+    /// 已抓取的页面/文档数
+    pub pages_fetched: usize,
+    /// 预计总页面数（未知时为None）
+    pub pages_total: Option<usize>,
+    /// 已写入向量库的片段数
+    pub fragments_stored: usize,
+    /// 被质量闸门拒绝的片段数（按原因）
+    #[serde(default)]
+    pub fragments_rejected: RejectionCounts,
+    /// 已尝试的次数（包括中断后恢复）
+    pub attempts: u32,
+    pub started_at: SystemTime,
+    pub updated_at: SystemTime,
+    pub last_error: Option<String>,
+}
+
+impl PackageProgress {
+    pub fn started() -> Self {
+        let now = SystemTime::now();
+        Self {
+            status: ProgressStatus::InProgress,
+            pages_fetched: 0,
+            pages_total: None,
+            fragments_stored: 0,
+            fragments_rejected: RejectionCounts::default(),
+            attempts: 1,
+            started_at: now,
+            updated_at: now,
+            last_error: None,
+        }
+    }
+
+    /// 完成百分比（总数未知时只有完成状态为100%）
+    pub fn completion_percent(&self) -> f32 {
+        if self.status == ProgressStatus::Complete {
+            return 100.0;
+        }
+        match self.pages_total {
+            Some(total) if total > 0 => (self.pages_fetched.min(total) as f32 / total as f32 * 100.0).min(99.0),
+            _ => 0.0,
+        }
+    }
+
+    /// 进行中但长时间没有更新（例如进程在抓取中途退出）
+    pub fn is_stale(&self, now: SystemTime) -> bool {
+        self.status == ProgressStatus::InProgress
+            && now.duration_since(self.updated_at).unwrap_or_default() >= STALE_PROGRESS_TIMEOUT
+    }
+
+    /// 是否可以由当前调用方认领处理
+    pub fn is_claimable(&self, now: SystemTime) -> bool {
+        match self.status {
+            ProgressStatus::Complete => false,
+            ProgressStatus::Failed => true,
+            ProgressStatus::InProgress => self.is_stale(now),
+        }
+    }
+
+    /// 重新认领：保留已完成的计数，便于从中断处恢复
+    pub fn resume(&mut self) {
+        self.status = ProgressStatus::InProgress;
+        self.attempts += 1;
+        self.updated_at = SystemTime::now();
+        self.last_error = None;
+    }
+
+    pub fn record(&mut self, pages_fetched: usize, fragments_stored: usize, pages_total: Option<usize>) {
+        self.pages_fetched += pages_fetched;
+        self.fragments_stored += fragments_stored;
+        if pages_total.is_some() {
+            self.pages_total = pages_total;
+        }
+        self.updated_at = SystemTime::now();
+    }
+
+    pub fn record_rejected(&mut self, rejected: &RejectionCounts) {
+        for (reason, count) in &rejected.by_reason {
+            *self.fragments_rejected.by_reason.entry(*reason).or_insert(0) += count;
+        }
+        self.updated_at = SystemTime::now();
+    }
+
+    pub fn complete(&mut self) {
+        self.status = ProgressStatus::Complete;
+        self.updated_at = SystemTime::now();
+        self.last_error = None;
+    }
+
+    pub fn fail(&mut self, error: String) {
+        self.status = ProgressStatus::Failed;
+        self.updated_at = SystemTime::now();
+        self.last_error = Some(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::quality_gate::RejectReason;
+
+    #[test]
+    fn test_progress_lifecycle() {
+        let mut progress = PackageProgress::started();
+        assert!(!progress.is_claimable(SystemTime::now()));
+
+        progress.record(3, 10, Some(6));
+        assert_eq!(progress.completion_percent(), 50.0);
+
+        progress.fail("网络错误".to_string());
+        assert!(progress.is_claimable(SystemTime::now()));
+
+        progress.resume();
+        assert_eq!(progress.attempts, 2);
+        assert_eq!(progress.pages_fetched, 3);
+
+        progress.record(3, 8, None);
+        assert_eq!(progress.completion_percent(), 99.0);
+
+        let mut rejected = RejectionCounts::default();
+        rejected.record(RejectReason::CookieBanner);
+        progress.record_rejected(&rejected);
+        progress.record_rejected(&rejected);
+        assert_eq!(progress.fragments_rejected.total(), 2);
+        progress.complete();
+        assert_eq!(progress.completion_percent(), 100.0);
+        assert!(!progress.is_claimable(SystemTime::now()));
+    }
+
+    #[test]
+    fn test_stale_in_progress_is_claimable() {
+        let progress = PackageProgress::started();
+        let later = SystemTime::now() + STALE_PROGRESS_TIMEOUT + Duration::from_secs(1);
+        assert!(progress.is_stale(later));
+        assert!(progress.is_claimable(later));
+    }
+}
@@ -0,0 +1,162 @@
+//! 列表类工具结果的游标分页
+//!
+//! 游标是不透明的 URL 安全字符串，记录上一页的结束位置和最后一项的键。续页时按键定位，
+//! 翻页期间有插入或删除也不会重复或遗漏；只支持按位置读取的存储（如 `VectorDatabase`）
+//! 使用其中的位置。
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::errors::MCPError;
+use super::base::{Schema, SchemaInteger, SchemaString};
+
+/// 默认每页条数
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+/// 每页条数上限
+pub const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorState {
+    /// 下一页的起始位置
+    o: usize,
+    /// 上一页最后一项的键
+    k: String,
+}
+
+/// 一页结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// 还有下一页时的游标
+    pub next_cursor: Option<String>,
+    /// 分页前的总条数
+    pub total: usize,
+}
+
+pub fn encode_cursor(offset: usize, last_key: &str) -> String {
+    let state = CursorState { o: offset, k: last_key.to_string() };
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&state).unwrap_or_default())
+}
+
+/// 解析游标，返回（起始位置，上一页最后一项的键）
+pub fn decode_cursor(cursor: &str) -> std::result::Result<(usize, String), MCPError> {
+    let invalid = || MCPError::InvalidParameter(format!("无效的分页游标: {}", cursor));
+    let bytes = URL_SAFE_NO_PAD.decode(cursor.trim()).map_err(|_| invalid())?;
+    let state: CursorState = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    Ok((state.o, state.k))
+}
+
+/// 从工具参数中读取 `cursor`（空字符串视为第一页）
+pub fn cursor_param(params: &Value) -> Option<&str> {
+    params.get("cursor").and_then(|v| v.as_str()).filter(|c| !c.trim().is_empty())
+}
+
+/// 从工具参数中读取 `page_size`，限制在 1..=MAX_PAGE_SIZE
+pub fn page_size_param(params: &Value) -> usize {
+    params
+        .get("page_size")
+        .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        .map_or(DEFAULT_PAGE_SIZE, |n| n as usize)
+        .clamp(1, MAX_PAGE_SIZE)
+}
+
+/// 参数中是否带有分页参数
+pub fn is_paginated(params: &Value) -> bool {
+    params.get("cursor").is_some() || params.get("page_size").is_some()
+}
+
+/// 对已按稳定顺序排列的列表分页，`key` 返回每项的唯一键
+///
+/// 续页时从上一页最后一项之后开始；该项已被删除时取第一个键大于它的项，
+/// 因此列表按键升序排列时删除也不影响续页。
+pub fn paginate<T, F>(items: Vec<T>, key: F, cursor: Option<&str>, page_size: usize) -> std::result::Result<Page<T>, MCPError>
+where
+    F: Fn(&T) -> &str,
+{
+    let total = items.len();
+    let start = match cursor {
+        None => 0,
+        Some(cursor) => {
+            let (_, last_key) = decode_cursor(cursor)?;
+            match items.iter().position(|item| key(item) == last_key) {
+                Some(pos) => pos + 1,
+                None => items.partition_point(|item| key(item) <= last_key.as_str()),
+            }
+        }
+    };
+    let end = (start + page_size.max(1)).min(total);
+    let next_cursor = (end < total).then(|| encode_cursor(end, key(&items[end - 1])));
+    let items = items.into_iter().skip(start).take(end - start).collect();
+    Ok(Page { items, next_cursor, total })
+}
+
+/// 向工具参数Schema中加入 `cursor` 和 `page_size`
+pub fn add_pagination_properties(properties: &mut HashMap<String, Schema>) {
+    properties.insert("cursor".to_string(), Schema::String(SchemaString {
+        description: Some("分页游标：上一页结果中的 next_cursor，省略时从第一页开始".to_string()),
+        enum_values: None,
+    }));
+    properties.insert("page_size".to_string(), Schema::Integer(SchemaInteger {
+        description: Some(format!("每页条数，默认 {}，最多 {}", DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE)),
+        minimum: Some(1),
+        maximum: Some(MAX_PAGE_SIZE as i64),
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ids(range: std::ops::Range<usize>) -> Vec<String> {
+        range.map(|i| format!("doc-{:03}", i)).collect()
+    }
+
+    #[test]
+    fn test_pages_through_all_items() {
+        let items = ids(0..7);
+        let mut cursor = None;
+        let mut seen = Vec::new();
+        loop {
+            let page = paginate(items.clone(), |s| s.as_str(), cursor.as_deref(), 3).unwrap();
+            assert_eq!(page.total, 7);
+            seen.extend(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(seen, items);
+    }
+
+    #[test]
+    fn test_cursor_survives_inserts_and_deletes() {
+        let first = paginate(ids(0..6), |s| s.as_str(), None, 2).unwrap();
+        let cursor = first.next_cursor.unwrap();
+
+        // 第一页之前插入一项：按键重新定位
+        let mut inserted = ids(0..6);
+        inserted.insert(0, "doc-000a".to_string());
+        inserted.sort();
+        let page = paginate(inserted, |s| s.as_str(), Some(&cursor), 2).unwrap();
+        assert_eq!(page.items, vec!["doc-002", "doc-003"]);
+
+        // 上一页最后一项被删除：从下一个键继续
+        let mut deleted = ids(0..6);
+        deleted.remove(1);
+        let page = paginate(deleted, |s| s.as_str(), Some(&cursor), 2).unwrap();
+        assert_eq!(page.items, vec!["doc-002", "doc-003"]);
+
+        assert!(paginate(ids(0..3), |s| s.as_str(), Some("not-a-cursor"), 2).is_err());
+    }
+
+    #[test]
+    fn test_page_size_param_is_clamped() {
+        assert_eq!(page_size_param(&json!({})), DEFAULT_PAGE_SIZE);
+        assert_eq!(page_size_param(&json!({"page_size": "20"})), 20);
+        assert_eq!(page_size_param(&json!({"page_size": 0})), 1);
+        assert_eq!(page_size_param(&json!({"page_size": 100_000})), MAX_PAGE_SIZE);
+    }
+}
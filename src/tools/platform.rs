@@ -0,0 +1,285 @@
+//! 外部命令和临时目录的平台差异处理
+//!
+//! 文档生成（`doc_processor`）和工具安装（`tool_installer`）都要调用外部 CLI，
+//! Windows 上有几处与 Unix 不同，统一在这里处理：
+//! - npm、yarn、pnpm、mvn、gradle、conda、scoop 等是 `.cmd`/`.bat` 包装脚本，
+//!   `Command::new("npm")` 只会查找 `npm.exe`，需要先按 `PATH` 和 `PATHEXT` 解析出完整路径；
+//! - 控制台程序按系统代码页输出（简体中文系统通常是 GBK），直接按 UTF-8 解码会乱码；
+//! - 临时目录可能很长或含空格，cargo/maven 在其中生成的深层目录容易超出 `MAX_PATH`，
+//!   临时工程目录名需要尽量短，并且出错时也要清理。
+
+use anyhow::Result;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// 未设置 `PATHEXT` 时 Windows 的默认可执行扩展名
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// 命令解析规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    Unix,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Unix
+        }
+    }
+
+    fn path_separator(&self) -> char {
+        match self {
+            Platform::Windows => ';',
+            Platform::Unix => ':',
+        }
+    }
+}
+
+/// 在给定的 `PATH` 中查找程序
+///
+/// Windows 下程序名没有扩展名时依次尝试 `PATHEXT` 中的扩展名（`npm` -> `npm.cmd`），
+/// Unix 下只查找同名文件。程序名带路径时只检查该路径本身。
+pub fn resolve_program_in(program: &str, path_var: &OsStr, pathext: Option<&str>, platform: Platform) -> Option<PathBuf> {
+    let candidates = |base: PathBuf| -> Vec<PathBuf> {
+        match platform {
+            Platform::Windows if base.extension().is_none() => {
+                pathext.unwrap_or(DEFAULT_PATHEXT)
+                    .split(';')
+                    .filter(|ext| !ext.is_empty())
+                    .map(|ext| {
+                        let mut name = base.clone().into_os_string();
+                        name.push(ext.to_ascii_lowercase());
+                        PathBuf::from(name)
+                    })
+                    .collect()
+            }
+            _ => vec![base],
+        }
+    };
+
+    let has_dir = program.contains('/') || (platform == Platform::Windows && program.contains('\\'));
+    if has_dir {
+        return candidates(PathBuf::from(program)).into_iter().find(|path| path.is_file());
+    }
+    path_var.to_string_lossy()
+        .split(platform.path_separator())
+        .filter(|dir| !dir.is_empty())
+        // Windows 的 PATH 条目可能带引号
+        .map(|dir| PathBuf::from(dir.trim_matches('"')))
+        .flat_map(|dir| candidates(dir.join(program)))
+        .find(|path| path.is_file())
+}
+
+/// 在当前环境的 `PATH` 中查找程序
+pub fn resolve_program(program: &str) -> Option<PathBuf> {
+    let path_var = std::env::var_os("PATH").unwrap_or_default();
+    let pathext = std::env::var("PATHEXT").ok();
+    resolve_program_in(program, &path_var, pathext.as_deref(), Platform::current())
+}
+
+/// 是否为需要经 `cmd.exe` 执行的批处理脚本
+pub fn is_batch_script(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("cmd") || ext.eq_ignore_ascii_case("bat"))
+}
+
+/// 实际要启动的程序：Windows 下解析为完整路径（标准库会经 `cmd.exe` 执行批处理脚本并正确转义参数），
+/// 其他平台和找不到时原样返回
+fn program_path(program: &str) -> OsString {
+    if Platform::current() == Platform::Windows {
+        if let Some(path) = resolve_program(program) {
+            return path.into_os_string();
+        }
+    }
+    OsString::from(program)
+}
+
+/// 创建异步外部命令，替代 `tokio::process::Command::new`
+pub fn command(program: &str) -> tokio::process::Command {
+    tokio::process::Command::new(program_path(program))
+}
+
+/// 创建同步外部命令，替代 `std::process::Command::new`
+pub fn std_command(program: &str) -> std::process::Command {
+    std::process::Command::new(program_path(program))
+}
+
+/// 拆分命令行，支持双引号包裹含空格的参数（如 `"C:\Program Files\nodejs\npm.cmd" install`）
+///
+/// 反斜杠不作转义，以免破坏 Windows 路径。
+pub fn split_command_line(command_line: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_part = false;
+    for c in command_line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_part = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_part {
+                    parts.push(std::mem::take(&mut current));
+                    has_part = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_part = true;
+            }
+        }
+    }
+    if has_part {
+        parts.push(current);
+    }
+    parts
+}
+
+/// 按 Windows 代码页选择编码，未知代码页返回 None
+#[cfg_attr(not(windows), allow(dead_code))]
+fn encoding_for_code_page(code_page: u32) -> Option<&'static encoding_rs::Encoding> {
+    match code_page {
+        65001 => Some(encoding_rs::UTF_8),
+        936 => Some(encoding_rs::GBK),
+        54936 => Some(encoding_rs::GB18030),
+        950 => Some(encoding_rs::BIG5),
+        932 => Some(encoding_rs::SHIFT_JIS),
+        949 => Some(encoding_rs::EUC_KR),
+        866 => Some(encoding_rs::IBM866),
+        1250..=1258 => encoding_rs::Encoding::for_label(format!("windows-{}", code_page).as_bytes()),
+        _ => None,
+    }
+}
+
+/// 控制台输出的回退编码：Windows 为系统 ANSI 代码页，其他平台为 UTF-8
+fn console_encoding() -> &'static encoding_rs::Encoding {
+    #[cfg(windows)]
+    {
+        // SAFETY: GetACP 没有参数也不会失败
+        let code_page = unsafe { winapi::um::winnls::GetACP() };
+        if let Some(encoding) = encoding_for_code_page(code_page) {
+            return encoding;
+        }
+    }
+    encoding_rs::UTF_8
+}
+
+/// 按指定的回退编码解码：合法的 UTF-8（可带 BOM）直接使用，否则按回退编码解码
+pub fn decode_output_with(bytes: &[u8], fallback: &'static encoding_rs::Encoding) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => fallback.decode_without_bom_handling(bytes).0.into_owned(),
+    }
+}
+
+/// 解码外部命令的输出，替代 `String::from_utf8_lossy`
+pub fn decode_output(bytes: &[u8]) -> String {
+    decode_output_with(bytes, console_encoding())
+}
+
+/// 临时工程目录，离开作用域时删除（包括出错提前返回的情况）
+#[derive(Debug)]
+pub struct TempProject {
+    path: PathBuf,
+}
+
+impl TempProject {
+    /// 在系统临时目录下创建 `<前缀>-<8位随机串>` 目录
+    ///
+    /// 目录名保持简短，避免 cargo/maven 在其中生成的深层路径在 Windows 上超出 `MAX_PATH`。
+    pub fn create(prefix: &str) -> Result<Self> {
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let path = std::env::temp_dir().join(format!("{}-{}", prefix, &suffix[..8]));
+        std::fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.path) {
+            tracing::debug!("清理临时目录 {:?} 失败: {}", self.path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, "").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_resolve_windows_shims() {
+        let dir = tempfile::tempdir().unwrap();
+        let npm = touch(dir.path(), "npm.cmd");
+        let go = touch(dir.path(), "go.exe");
+        // 路径条目带引号且与其他条目以分号分隔
+        let path_var = OsString::from(format!("C:\\missing;\"{}\"", dir.path().display()));
+
+        assert_eq!(resolve_program_in("npm", &path_var, None, Platform::Windows), Some(npm.clone()));
+        assert_eq!(resolve_program_in("go", &path_var, Some(".EXE;.CMD"), Platform::Windows), Some(go));
+        assert_eq!(resolve_program_in("npm", &path_var, Some(".EXE"), Platform::Windows), None);
+        assert_eq!(resolve_program_in("npm.cmd", &path_var, None, Platform::Windows), Some(npm.clone()));
+        assert!(is_batch_script(&npm));
+        assert!(!is_batch_script(Path::new("go.exe")));
+
+        // Unix 不补扩展名
+        let unix_path = OsString::from(dir.path().as_os_str());
+        assert_eq!(resolve_program_in("npm", &unix_path, None, Platform::Unix), None);
+    }
+
+    #[test]
+    fn test_decode_console_output() {
+        // "依赖" 的 GBK 编码
+        let gbk = [0xD2, 0xC0, 0xC0, 0xB5];
+        assert_eq!(decode_output_with(&gbk, encoding_rs::GBK), "依赖");
+        assert_eq!(decode_output_with("依赖".as_bytes(), encoding_rs::GBK), "依赖");
+        assert_eq!(decode_output_with(b"\xEF\xBB\xBFok", encoding_rs::GBK), "ok");
+        assert_eq!(encoding_for_code_page(936), Some(encoding_rs::GBK));
+        assert_eq!(encoding_for_code_page(1252), Some(encoding_rs::WINDOWS_1252));
+    }
+
+    #[test]
+    fn test_split_command_line_keeps_windows_paths() {
+        assert_eq!(
+            split_command_line(r#""C:\Program Files\nodejs\npm.cmd" install -g  "" typescript"#),
+            vec![r"C:\Program Files\nodejs\npm.cmd", "install", "-g", "", "typescript"]
+        );
+        assert!(split_command_line("   ").is_empty());
+    }
+
+    #[test]
+    fn test_temp_project_is_removed_on_drop() {
+        let project = TempProject::create("grape-test").unwrap();
+        let path = project.path().to_path_buf();
+        std::fs::write(path.join("Cargo.toml"), "[package]").unwrap();
+        drop(project);
+        assert!(!path.exists());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_resolves_cmd_from_path() {
+        // cmd.exe 总在 PATH 中，且需要补 .exe 才能找到
+        let cmd = resolve_program("cmd").expect("PATH 中应能找到 cmd.exe");
+        assert!(cmd.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("exe")));
+        let output = std_command("cmd").args(["/C", "echo", "ok"]).output().unwrap();
+        assert_eq!(decode_output(&output.stdout).trim(), "ok");
+    }
+}
@@ -0,0 +1,308 @@
+//! 按项目上下文调整搜索结果排序
+//!
+//! 根据环境检测报告（语言、框架特征、清单中的依赖）对搜索结果加权：项目实际依赖的包
+//! 排在前面，与项目依赖同名但属于其他生态的包降权。例如 Rust 项目搜索 `tokio` 时，
+//! crates.io 的 tokio 文档优先，同名的 npm 包靠后。
+//!
+//! 查询提到锁文件中的包时，搜索结果还会限定在锁定版本（[`ProjectProfile::pin_locked_versions`]），
+//! 避免按比项目实际使用更新的 API 回答。
+
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::environment_detector::DetectionReport;
+use super::hybrid_scoring::PARALLEL_THRESHOLD;
+use super::search_filter::SearchFilter;
+use super::vector_docs_tool::SearchResult;
+
+/// 项目依赖的包（同生态）
+const DEPENDENCY_BOOST: f32 = 1.5;
+/// 项目使用的语言或框架
+const LANGUAGE_BOOST: f32 = 1.15;
+/// 与项目依赖同名但属于其他生态
+const FOREIGN_NAMESAKE_PENALTY: f32 = 0.6;
+
+/// 从检测报告中提取的项目画像
+#[derive(Debug, Clone, Default)]
+pub struct ProjectProfile {
+    languages: HashSet<String>,
+    frameworks: HashSet<String>,
+    /// 语言 -> 归一化的依赖包名
+    dependencies: HashMap<String, HashSet<String>>,
+    /// 语言 -> 归一化包名 -> 锁文件中的版本
+    locked_versions: HashMap<String, HashMap<String, String>>,
+}
+
+/// 把文档和检测报告中的语言名统一为检测器使用的名称
+pub fn canonical_language(language: &str) -> String {
+    let language = language.trim().to_lowercase();
+    match language.as_str() {
+        "js" | "typescript" | "ts" | "node" | "nodejs" | "npm" => "javascript".to_string(),
+        "py" | "pip" | "pypi" => "python".to_string(),
+        "rs" | "cargo" | "crates" => "rust".to_string(),
+        "golang" => "go".to_string(),
+        "flutter" | "pub" => "dart".to_string(),
+        "c#" | "cs" | "dotnet" => "csharp".to_string(),
+        "c++" => "cpp".to_string(),
+        _ => language,
+    }
+}
+
+/// 归一化包名：小写，`_` 与 `-` 视为相同（PyPI、crates.io 的名称规则）
+pub(crate) fn normalize_package(name: &str) -> String {
+    name.trim().to_lowercase().replace('_', "-")
+}
+
+impl ProjectProfile {
+    pub fn from_report(report: &DetectionReport) -> Self {
+        let mut profile = Self::default();
+        for (language, info) in &report.detected_languages {
+            let language = canonical_language(language);
+            profile.frameworks.extend(info.detected_features.iter().map(|f| normalize_package(f)));
+            profile.languages.insert(language);
+        }
+        for (language, names) in &report.dependencies {
+            profile
+                .dependencies
+                .entry(canonical_language(language))
+                .or_default()
+                .extend(names.iter().map(|n| normalize_package(n)));
+        }
+        for (language, versions) in &report.locked_versions {
+            profile
+                .locked_versions
+                .entry(canonical_language(language))
+                .or_default()
+                .extend(versions.iter().map(|(name, version)| (normalize_package(name), version.clone())));
+        }
+        profile
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty() && self.dependencies.is_empty()
+    }
+
+    /// 项目依赖 (语言, 归一化包名)，按语言和包名排序
+    pub fn dependency_list(&self) -> Vec<(String, String)> {
+        let list: BTreeSet<(String, String)> = self.dependencies
+            .iter()
+            .flat_map(|(language, deps)| deps.iter().map(move |dep| (language.clone(), dep.clone())))
+            .collect();
+        list.into_iter().collect()
+    }
+
+    /// 清单中是否读到了任何依赖
+    pub fn has_dependencies(&self) -> bool {
+        self.dependencies.values().any(|deps| !deps.is_empty())
+    }
+
+    /// 是否为项目依赖的包（同生态）
+    pub fn has_dependency(&self, language: &str, package_name: &str) -> bool {
+        self.dependencies
+            .get(&canonical_language(language))
+            .map_or(false, |deps| deps.contains(&normalize_package(package_name)))
+    }
+
+    /// 本画像中有、`current` 的所有清单中都已没有的依赖，返回 (语言, 归一化包名)
+    ///
+    /// `current` 没有读到任何依赖时视为扫描失败（例如清单正在改写），不报告任何移除。
+    pub fn removed_dependencies(&self, current: &ProjectProfile) -> BTreeSet<(String, String)> {
+        if !current.has_dependencies() {
+            return BTreeSet::new();
+        }
+        self.dependencies
+            .iter()
+            .flat_map(|(language, deps)| deps.iter().map(move |dep| (language, dep)))
+            .filter(|(language, dep)| !current.has_dependency(language, dep))
+            .map(|(language, dep)| (language.clone(), dep.clone()))
+            .collect()
+    }
+
+    /// 锁文件中该包的版本
+    pub fn locked_version(&self, language: &str, package_name: &str) -> Option<&str> {
+        self.locked_versions
+            .get(&canonical_language(language))
+            .and_then(|versions| versions.get(&normalize_package(package_name)))
+            .map(String::as_str)
+    }
+
+    /// 查询文本或过滤条件的包名提到锁文件中的包时，把这些包锁定到锁文件中的版本，返回新增的锁定数
+    ///
+    /// 过滤条件已指定版本时以调用方为准不再锁定；指定了语言时只锁定该语言的包。
+    pub fn pin_locked_versions(&self, query: &str, filter: &mut SearchFilter) -> usize {
+        if filter.version.is_some() || self.locked_versions.is_empty() {
+            return 0;
+        }
+        let language = filter.language.as_deref().map(canonical_language);
+        let mentioned: HashSet<String> = query
+            .split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | '/')))
+            .map(|token| token.trim_matches(|c| c == '.' || c == '/'))
+            .chain(filter.package_name.as_deref())
+            .filter(|token| !token.is_empty())
+            .map(normalize_package)
+            .collect();
+
+        let mut pinned = 0;
+        for (lang, versions) in &self.locked_versions {
+            if language.as_ref().map_or(false, |language| language != lang) {
+                continue;
+            }
+            for package in &mentioned {
+                if let Some(version) = versions.get(package) {
+                    let key = super::dependency_cleanup::dependency_key(lang, package);
+                    if filter.pinned_versions.insert(key, version.clone()).is_none() {
+                        pinned += 1;
+                    }
+                }
+            }
+        }
+        pinned
+    }
+
+    /// 单个结果的权重系数，1.0 表示不调整
+    pub fn boost_factor(&self, language: &str, package_name: &str) -> f32 {
+        let language = canonical_language(language);
+        let package = normalize_package(package_name);
+        let is_dependency = |lang: &str| self.dependencies.get(lang).map_or(false, |deps| deps.contains(&package));
+
+        if is_dependency(&language) {
+            return DEPENDENCY_BOOST;
+        }
+        if self.dependencies.keys().any(|lang| *lang != language && is_dependency(lang)) {
+            return FOREIGN_NAMESAKE_PENALTY;
+        }
+        if self.languages.contains(&language) || self.frameworks.contains(&package) {
+            return LANGUAGE_BOOST;
+        }
+        1.0
+    }
+
+    /// 按项目上下文调整分数并重新排序，调整过的结果在 metadata 中记录 `project_boost`
+    pub fn apply(&self, results: &mut [SearchResult]) {
+        if self.is_empty() {
+            return;
+        }
+        let boost = |result: &mut SearchResult| {
+            let factor = self.boost_factor(&result.language, &result.package_name);
+            if (factor - 1.0).abs() > f32::EPSILON {
+                // 保持 0-1 的相关度刻度
+                result.score = (result.score * factor).min(1.0);
+                result.metadata.insert("project_boost".to_string(), format!("{:.2}", factor));
+            }
+        };
+        if results.len() >= PARALLEL_THRESHOLD {
+            results.par_iter_mut().for_each(boost);
+        } else {
+            results.iter_mut().for_each(boost);
+        }
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::environment_detector::LanguageInfo;
+
+    fn rust_project() -> ProjectProfile {
+        let mut report = DetectionReport {
+            detected_languages: HashMap::new(),
+            scan_duration_ms: 0,
+            scan_paths: Vec::new(),
+            total_files_scanned: 0,
+            dependencies: HashMap::new(),
+            locked_versions: HashMap::new(),
+        };
+        report.detected_languages.insert(
+            "rust".to_string(),
+            LanguageInfo {
+                name: "rust".to_string(),
+                score: 1.0,
+                project_files: vec!["Cargo.toml".to_string()],
+                cli_tools: Vec::new(),
+                detected_features: vec!["async".to_string()],
+            },
+        );
+        report.dependencies.insert("rust".to_string(), vec!["tokio".to_string(), "serde_json".to_string()]);
+        report.locked_versions.insert(
+            "rust".to_string(),
+            HashMap::from([
+                ("tokio".to_string(), "1.38.0".to_string()),
+                ("serde_json".to_string(), "1.0.117".to_string()),
+            ]),
+        );
+        ProjectProfile::from_report(&report)
+    }
+
+    fn result(id: &str, language: &str, package_name: &str, score: f32) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            content: String::new(),
+            title: id.to_string(),
+            language: language.to_string(),
+            package_name: package_name.to_string(),
+            version: "1.0.0".to_string(),
+            doc_type: "api".to_string(),
+            metadata: HashMap::new(),
+            score,
+        }
+    }
+
+    #[test]
+    fn test_boost_factor_by_ecosystem() {
+        let profile = rust_project();
+        assert_eq!(profile.boost_factor("rust", "tokio"), DEPENDENCY_BOOST);
+        assert_eq!(profile.boost_factor("rust", "serde-json"), DEPENDENCY_BOOST);
+        assert_eq!(profile.boost_factor("npm", "tokio"), FOREIGN_NAMESAKE_PENALTY);
+        assert_eq!(profile.boost_factor("rust", "reqwest"), LANGUAGE_BOOST);
+        assert_eq!(profile.boost_factor("python", "requests"), 1.0);
+    }
+
+    #[test]
+    fn test_removed_dependencies() {
+        let previous = rust_project();
+        let mut current = ProjectProfile::default();
+        current.dependencies.entry("rust".to_string()).or_default().insert("serde-json".to_string());
+        let removed: Vec<(String, String)> = previous.removed_dependencies(&current).into_iter().collect();
+        assert_eq!(removed, vec![("rust".to_string(), "tokio".to_string())]);
+        assert!(current.has_dependency("cargo", "serde_json"));
+
+        // 没有读到任何依赖时不认为依赖被移除
+        assert!(previous.removed_dependencies(&ProjectProfile::default()).is_empty());
+    }
+
+    #[test]
+    fn test_pin_locked_versions() {
+        let profile = rust_project();
+        assert_eq!(profile.locked_version("cargo", "serde-json"), Some("1.0.117"));
+
+        let mut filter = SearchFilter::new();
+        assert_eq!(profile.pin_locked_versions("how does tokio::spawn work with serde_json::Value?", &mut filter), 2);
+        assert_eq!(filter.pinned_versions.get("rust/tokio").map(String::as_str), Some("1.38.0"));
+        assert_eq!(filter.pinned_versions.get("rust/serde-json").map(String::as_str), Some("1.0.117"));
+
+        let mut filter = SearchFilter::new().package_name("tokio");
+        assert_eq!(profile.pin_locked_versions("spawn a task", &mut filter), 1);
+
+        // 调用方指定的版本或其他语言不锁定
+        let mut filter = SearchFilter::new().package_name("tokio").version("0.2.x");
+        assert_eq!(profile.pin_locked_versions("tokio", &mut filter), 0);
+        let mut filter = SearchFilter::new().language("javascript");
+        assert_eq!(profile.pin_locked_versions("tokio", &mut filter), 0);
+    }
+
+    #[test]
+    fn test_apply_reorders_results() {
+        let profile = rust_project();
+        let mut results = vec![
+            result("npm-tokio", "javascript", "tokio", 0.9),
+            result("crate-tokio", "rust", "tokio", 0.7),
+            result("pypi-requests", "python", "requests", 0.8),
+        ];
+        profile.apply(&mut results);
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["crate-tokio", "pypi-requests", "npm-tokio"]);
+        assert_eq!(results[0].metadata.get("project_boost").map(String::as_str), Some("1.50"));
+        assert!(!results[1].metadata.contains_key("project_boost"));
+    }
+}
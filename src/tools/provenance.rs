@@ -0,0 +1,183 @@
+//! 文档片段的来源与许可
+//!
+//! 入库的每个片段都在元数据中记录来源地址、抓取时间以及检测到的许可证和署名，搜索结果原样带出，
+//! 引用时可以注明出处。收到内容删除请求时，可以按来源地址整体清除文档：使用 `vector_docs` 的
+//! `purge_source` 操作，或执行 `cache purge-source` 命令。
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::tools::base::FileDocumentFragment;
+
+/// 文档元数据中记录来源地址的键
+pub const SOURCE_URL_METADATA_KEY: &str = "source_url";
+/// 文档元数据中记录抓取时间（RFC 3339）的键
+pub const FETCHED_AT_METADATA_KEY: &str = "fetched_at";
+/// 文档元数据中记录许可证（尽量使用 SPDX 标识符）的键
+pub const LICENSE_METADATA_KEY: &str = "license";
+/// 文档元数据中记录署名（版权声明行）的键
+pub const ATTRIBUTION_METADATA_KEY: &str = "attribution";
+
+/// 只在内容开头和结尾各这么多字符内查找声明：许可证通常写在页眉或页脚，
+/// 正文中提到的许可证不代表本页的许可
+const SCAN_CHARS: usize = 4000;
+
+const MAX_ATTRIBUTION_CHARS: usize = 200;
+
+/// 常见许可证的写法及对应的 SPDX 标识符，更具体的写法在前（如 LGPL 在 GPL 之前）
+fn license_patterns() -> &'static [(Regex, &'static str)] {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (r"(?i)\bCC[- ]BY[- ]SA[- ]?4\.0\b|creative commons attribution[- ]sharealike 4\.0", "CC-BY-SA-4.0"),
+            (r"(?i)\bCC[- ]BY[- ]SA[- ]?3\.0\b|creative commons attribution[- ]sharealike 3\.0", "CC-BY-SA-3.0"),
+            (r"(?i)\bCC[- ]BY[- ]?4\.0\b|creative commons attribution 4\.0", "CC-BY-4.0"),
+            (r"(?i)\bCC0\b|public domain dedication", "CC0-1.0"),
+            (r"(?i)\bapache[- ]2\.0\b|apache license,? version 2\.0", "Apache-2.0"),
+            (r"(?i)\bMPL[- ]2\.0\b|mozilla public license,? (?:version |v)?2\.0", "MPL-2.0"),
+            (r"(?i)\bLGPL\b|gnu lesser general public license", "LGPL"),
+            (r"(?i)\bAGPL\b|gnu affero general public license", "AGPL"),
+            (r"(?i)\bGPL\b|gnu general public license", "GPL"),
+            (r"(?i)\bBSD[- ]3[- ]clause\b|3-clause bsd", "BSD-3-Clause"),
+            (r"(?i)\bBSD[- ]2[- ]clause\b|2-clause bsd", "BSD-2-Clause"),
+            (r"(?i)\bMIT license\b|licensed under the MIT\b|\bMIT-licensed\b", "MIT"),
+            (r"(?i)\bthe unlicense\b", "Unlicense"),
+        ]
+        .into_iter()
+        .map(|(pattern, spdx)| (Regex::new(pattern).expect("许可证正则有效"), spdx))
+        .collect()
+    })
+}
+
+/// 内容开头和结尾用于查找声明的部分（内容较短时为全文）
+fn scanned_parts(content: &str) -> Vec<&str> {
+    let total = content.chars().count();
+    if total <= SCAN_CHARS * 2 {
+        return vec![content];
+    }
+    let head_end = content.char_indices().nth(SCAN_CHARS).map_or(content.len(), |(i, _)| i);
+    let tail_start = content.char_indices().nth(total - SCAN_CHARS).map_or(content.len(), |(i, _)| i);
+    vec![&content[..head_end], &content[tail_start..]]
+}
+
+/// 检测内容声明的许可证
+///
+/// `SPDX-License-Identifier:` 优先，其次是最先出现的常见许可证写法；都没有时返回 None。
+pub fn detect_license(content: &str) -> Option<String> {
+    static SPDX: OnceLock<Regex> = OnceLock::new();
+    let spdx = SPDX.get_or_init(|| Regex::new(r"SPDX-License-Identifier:\s*([A-Za-z0-9.+\-]+(?:\s+(?:OR|AND|WITH)\s+[A-Za-z0-9.+\-]+)*)").unwrap());
+
+    let parts = scanned_parts(content);
+    if let Some(captures) = parts.iter().find_map(|part| spdx.captures(part)) {
+        return Some(captures[1].to_string());
+    }
+    parts.iter()
+        .find_map(|part| {
+            license_patterns().iter()
+                .filter_map(|(pattern, spdx)| pattern.find(part).map(|m| (m.start(), *spdx)))
+                .min_by_key(|(start, _)| *start)
+        })
+        .map(|(_, spdx)| spdx.to_string())
+}
+
+/// 检测署名：第一条带年份的版权声明行
+pub fn detect_attribution(content: &str) -> Option<String> {
+    static COPYRIGHT: OnceLock<Regex> = OnceLock::new();
+    let copyright = COPYRIGHT.get_or_init(|| {
+        Regex::new(r"(?i)(?:copyright\s*(?:\(c\)|©)?|©)\s*(?:\d{4}\s*[-–,]\s*)*\d{4}\b.*").unwrap()
+    });
+    scanned_parts(content).iter()
+        .find_map(|part| part.lines().find_map(|line| copyright.find(line)))
+        .map(|m| m.as_str().trim().chars().take(MAX_ATTRIBUTION_CHARS).collect())
+}
+
+/// 补充检测到的许可证和署名，已有的值（调用方指定或来自包注册表）保留
+pub fn detect_into(metadata: &mut HashMap<String, String>, content: &str) {
+    if !metadata.contains_key(LICENSE_METADATA_KEY) {
+        if let Some(license) = detect_license(content) {
+            metadata.insert(LICENSE_METADATA_KEY.to_string(), license);
+        }
+    }
+    if !metadata.contains_key(ATTRIBUTION_METADATA_KEY) {
+        if let Some(attribution) = detect_attribution(content) {
+            metadata.insert(ATTRIBUTION_METADATA_KEY.to_string(), attribution);
+        }
+    }
+}
+
+/// 片段入库时记录的来源元数据
+pub fn fragment_metadata(fragment: &FileDocumentFragment) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    if let Some(source_url) = &fragment.source_url {
+        metadata.insert(SOURCE_URL_METADATA_KEY.to_string(), source_url.clone());
+    }
+    let fetched_at: chrono::DateTime<chrono::Utc> = fragment.created_at.into();
+    metadata.insert(FETCHED_AT_METADATA_KEY.to_string(), fetched_at.to_rfc3339());
+    if let Some(license) = fragment.license.as_ref().filter(|l| !l.trim().is_empty()) {
+        metadata.insert(LICENSE_METADATA_KEY.to_string(), license.trim().to_string());
+    }
+    detect_into(&mut metadata, &fragment.content);
+    metadata
+}
+
+/// 来源地址是否匹配清除条件：以 `*` 结尾时按前缀匹配，否则精确匹配（忽略末尾的 `/`）
+pub fn source_matches(pattern: &str, source_url: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => source_url.starts_with(prefix),
+        None => pattern.trim_end_matches('/') == source_url.trim_end_matches('/'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_license_and_attribution() {
+        assert_eq!(detect_license("// SPDX-License-Identifier: MIT OR Apache-2.0\nfn main() {}").as_deref(), Some("MIT OR Apache-2.0"));
+        assert_eq!(
+            detect_license("Content is available under CC BY-SA 4.0 unless otherwise noted.").as_deref(),
+            Some("CC-BY-SA-4.0")
+        );
+        assert_eq!(detect_license("Licensed under the Apache License, Version 2.0 or the MIT license").as_deref(), Some("Apache-2.0"));
+        assert_eq!(detect_license("GNU Lesser General Public License v2.1").as_deref(), Some("LGPL"));
+        assert_eq!(detect_license("# Guide\n\nNo notice here."), None);
+
+        // 只看开头和结尾，正文中间提到的许可证不算
+        let long = format!("# Guide\n{}\nCompare MIT license and GPL.\n{}", "a".repeat(SCAN_CHARS), "b".repeat(SCAN_CHARS));
+        assert_eq!(detect_license(&long), None);
+
+        let page = "# Tokio\n\nText\n\n© 2016-2024 Tokio Contributors. All rights reserved.";
+        assert_eq!(detect_attribution(page).as_deref(), Some("© 2016-2024 Tokio Contributors. All rights reserved."));
+        assert_eq!(detect_attribution("Copyright (c) 2023 The Rust Project Developers").as_deref(), Some("Copyright (c) 2023 The Rust Project Developers"));
+        assert_eq!(detect_attribution("copyright law applies"), None);
+    }
+
+    #[test]
+    fn test_fragment_metadata_prefers_registry_license() {
+        let mut fragment = FileDocumentFragment::new(
+            "python".to_string(),
+            "requests".to_string(),
+            "2.31.0".to_string(),
+            "pypi_docs.md".to_string(),
+            "Released under the MIT license.\nCopyright 2019 Kenneth Reitz".to_string(),
+        )
+        .with_source_url("https://pypi.org/pypi/requests/json");
+        fragment.license = Some("Apache-2.0".to_string());
+
+        let metadata = fragment_metadata(&fragment);
+        assert_eq!(metadata[SOURCE_URL_METADATA_KEY], "https://pypi.org/pypi/requests/json");
+        assert_eq!(metadata[LICENSE_METADATA_KEY], "Apache-2.0");
+        assert_eq!(metadata[ATTRIBUTION_METADATA_KEY], "Copyright 2019 Kenneth Reitz");
+        assert!(chrono::DateTime::parse_from_rfc3339(&metadata[FETCHED_AT_METADATA_KEY]).is_ok());
+    }
+
+    #[test]
+    fn test_source_matches() {
+        assert!(source_matches("https://docs.example.com/guide/", "https://docs.example.com/guide"));
+        assert!(!source_matches("https://docs.example.com/guide", "https://docs.example.com/guide/intro"));
+        assert!(source_matches("https://docs.example.com/*", "https://docs.example.com/guide/intro"));
+        assert!(!source_matches("https://docs.example.com/*", "https://other.example.com/"));
+    }
+}
@@ -0,0 +1,304 @@
+//! Stack Overflow 问答补充来源（需显式开启）
+//!
+//! 通过 StackExchange API 拉取某个包常见错误的高票问题及其被采纳的答案，
+//! 以 `doc_type: "qa"` 的片段入库。内容开头带有来源标注，检索时可按 `doc_type` 过滤。
+//!
+//! 配置来自环境变量：
+//! - `GRAPE_QA_ENRICHMENT`：设为 `on`/`true`/`1` 时开启（默认关闭）
+//! - `STACKEXCHANGE_KEY`：可选的 API key，提高请求配额
+//! - `GRAPE_QA_MAX_QUESTIONS`：每个查询最多收录的问题数（默认 5）
+
+use anyhow::Result;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::errors::MCPError;
+use crate::tools::base::{FileDocumentFragment, FileType};
+use crate::tools::devdocs::html_to_text;
+
+/// StackExchange API 地址
+pub const STACKEXCHANGE_API: &str = "https://api.stackexchange.com/2.3";
+
+/// 问答片段的文档类型
+pub const QA_DOC_TYPE: &str = "qa";
+
+/// 问答补充配置
+#[derive(Debug, Clone)]
+pub struct QaEnrichmentConfig {
+    pub enabled: bool,
+    pub api_key: Option<String>,
+    pub site: String,
+    pub max_questions: usize,
+    /// 问题的最低得分
+    pub min_score: i64,
+}
+
+impl Default for QaEnrichmentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_key: None,
+            site: "stackoverflow".to_string(),
+            max_questions: 5,
+            min_score: 1,
+        }
+    }
+}
+
+impl QaEnrichmentConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: std::env::var("GRAPE_QA_ENRICHMENT")
+                .map(|v| matches!(v.to_lowercase().as_str(), "on" | "true" | "1"))
+                .unwrap_or(defaults.enabled),
+            api_key: std::env::var("STACKEXCHANGE_KEY").ok().filter(|k| !k.is_empty()),
+            max_questions: std::env::var("GRAPE_QA_MAX_QUESTIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_questions),
+            ..defaults
+        }
+    }
+}
+
+/// StackExchange 分页响应
+#[derive(Debug, Deserialize)]
+struct ApiPage<T> {
+    #[serde(default = "Vec::new")]
+    items: Vec<T>,
+    #[serde(default)]
+    quota_remaining: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Question {
+    pub question_id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    pub score: i64,
+    pub link: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub accepted_answer_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Answer {
+    pub answer_id: u64,
+    pub question_id: u64,
+    #[serde(default)]
+    pub body: String,
+    pub score: i64,
+}
+
+/// 问答补充结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QaEnrichmentReport {
+    pub package: String,
+    pub queries: Vec<String>,
+    pub questions_found: usize,
+    pub fragments_stored: usize,
+    pub fragments_rejected: usize,
+}
+
+/// StackExchange 问答抓取
+pub struct QaEnricher {
+    client: Client,
+    config: QaEnrichmentConfig,
+}
+
+impl QaEnricher {
+    pub fn new(client: Client, config: QaEnrichmentConfig) -> Self {
+        Self { client, config }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 抓取包常见错误（以及调用方给出的错误信息）对应的已采纳答案，转换为问答片段
+    pub async fn fetch_fragments(
+        &self,
+        language: &str,
+        package_name: &str,
+        version: &str,
+        extra_queries: &[String],
+    ) -> Result<(Vec<String>, Vec<FileDocumentFragment>)> {
+        let mut queries = common_error_queries(language, package_name);
+        queries.extend(extra_queries.iter().cloned());
+        queries.dedup();
+
+        let mut questions: Vec<Question> = Vec::new();
+        for query in &queries {
+            match self.search_questions(language, query).await {
+                Ok(found) => {
+                    for question in found {
+                        if !questions.iter().any(|q| q.question_id == question.question_id) {
+                            questions.push(question);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("StackExchange 搜索失败 ({}): {}", query, e),
+            }
+        }
+
+        let answer_ids: Vec<u64> = questions.iter().filter_map(|q| q.accepted_answer_id).collect();
+        let answers = self.fetch_answers(&answer_ids).await?;
+        let fragments = questions
+            .iter()
+            .filter_map(|q| {
+                let answer = answers.get(&q.accepted_answer_id?)?;
+                Some(qa_fragment(language, package_name, version, q, answer))
+            })
+            .collect();
+        Ok((queries, fragments))
+    }
+
+    async fn search_questions(&self, language: &str, query: &str) -> Result<Vec<Question>> {
+        let pagesize = self.config.max_questions.to_string();
+        let mut params = vec![
+            ("order", "desc"),
+            ("sort", "votes"),
+            ("q", query),
+            ("accepted", "True"),
+            ("site", self.config.site.as_str()),
+            ("filter", "withbody"),
+            ("pagesize", pagesize.as_str()),
+        ];
+        let tag = language_tag(language);
+        if let Some(tag) = tag {
+            params.push(("tagged", tag));
+        }
+        let page: ApiPage<Question> = self.get("search/advanced", &params).await?;
+        Ok(page.items.into_iter().filter(|q| q.score >= self.config.min_score).collect())
+    }
+
+    async fn fetch_answers(&self, answer_ids: &[u64]) -> Result<HashMap<u64, Answer>> {
+        let mut answers = HashMap::new();
+        // 一次最多查询 100 个 ID
+        for chunk in answer_ids.chunks(100) {
+            let ids = chunk.iter().map(u64::to_string).collect::<Vec<_>>().join(";");
+            let params = [("site", self.config.site.as_str()), ("filter", "withbody")];
+            let page: ApiPage<Answer> = self.get(&format!("answers/{}", ids), &params).await?;
+            answers.extend(page.items.into_iter().map(|a| (a.answer_id, a)));
+        }
+        Ok(answers)
+    }
+
+    async fn get<T: for<'de> Deserialize<'de>>(&self, path: &str, params: &[(&str, &str)]) -> Result<ApiPage<T>> {
+        let mut request = self.client.get(format!("{}/{}", STACKEXCHANGE_API, path)).query(params);
+        if let Some(key) = &self.config.api_key {
+            request = request.query(&[("key", key.as_str())]);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(MCPError::from_provider_response("stackexchange", response).await.into());
+        }
+        let page: ApiPage<T> = response.json().await?;
+        if page.quota_remaining.map_or(false, |q| q < 10) {
+            tracing::warn!("StackExchange API 配额即将用尽: 剩余 {:?}", page.quota_remaining);
+        }
+        Ok(page)
+    }
+}
+
+/// 包的常见错误查询
+pub fn common_error_queries(language: &str, package_name: &str) -> Vec<String> {
+    let failure = match language {
+        "rust" => "panic",
+        "python" | "java" | "dart" => "exception",
+        _ => "failed",
+    };
+    vec![format!("{} error", package_name), format!("{} {}", package_name, failure)]
+}
+
+fn language_tag(language: &str) -> Option<&'static str> {
+    match language {
+        "rust" => Some("rust"),
+        "python" => Some("python"),
+        "javascript" => Some("javascript"),
+        "typescript" => Some("typescript"),
+        "go" => Some("go"),
+        "java" => Some("java"),
+        "dart" | "flutter" => Some("dart"),
+        _ => None,
+    }
+}
+
+/// 问答片段：开头标注来源和许可，便于与官方文档区分
+pub fn qa_fragment(language: &str, package_name: &str, version: &str, question: &Question, answer: &Answer) -> FileDocumentFragment {
+    let content = format!(
+        "> 来源: Stack Overflow 社区问答（非官方文档，CC BY-SA 4.0）\n> 问题: {}\n> 问题得分 {}，采纳答案得分 {}\n\n# {}\n\n## 问题\n\n{}\n\n## 采纳的答案\n\n{}\n",
+        question.link,
+        question.score,
+        answer.score,
+        html_escape_title(&question.title),
+        html_to_text(&question.body),
+        html_to_text(&answer.body),
+    );
+    let mut fragment = FileDocumentFragment::new(
+        language.to_string(),
+        package_name.to_string(),
+        version.to_string(),
+        format!("stackoverflow/{}.md", question.question_id),
+        content,
+    );
+    fragment.file_type = FileType::Other(QA_DOC_TYPE.to_string());
+    fragment.hierarchy_path.push("stackoverflow".to_string());
+    fragment
+}
+
+/// 标题中的 HTML 实体（API 返回的标题是转义过的）
+fn html_escape_title(title: &str) -> String {
+    title
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_api_page_and_build_fragment() {
+        let raw = r#"{
+            "items": [{
+                "question_id": 123,
+                "title": "serde: &quot;missing field&quot; when deserializing",
+                "body": "<p>I get <code>missing field `id`</code>.</p>",
+                "score": 42,
+                "link": "https://stackoverflow.com/q/123",
+                "tags": ["rust", "serde"],
+                "accepted_answer_id": 456
+            }],
+            "quota_remaining": 290
+        }"#;
+        let page: ApiPage<Question> = serde_json::from_str(raw).unwrap();
+        let question = &page.items[0];
+        let answer = Answer {
+            answer_id: 456,
+            question_id: 123,
+            body: "<p>Add <code>#[serde(default)]</code> to the field.</p>".to_string(),
+            score: 80,
+        };
+
+        let fragment = qa_fragment("rust", "serde", "1.0.190", question, &answer);
+        assert_eq!(fragment.file_type.doc_type(), QA_DOC_TYPE);
+        assert_eq!(fragment.file_path, "stackoverflow/123.md");
+        assert!(fragment.content.starts_with("> 来源: Stack Overflow 社区问答"));
+        assert!(fragment.content.contains("# serde: \"missing field\" when deserializing"));
+        assert!(fragment.content.contains("Add `#[serde(default)]` to the field."));
+    }
+
+    #[test]
+    fn test_common_error_queries_and_opt_in_default() {
+        assert_eq!(common_error_queries("rust", "tokio"), vec!["tokio error", "tokio panic"]);
+        assert!(!QaEnrichmentConfig::default().enabled);
+    }
+}
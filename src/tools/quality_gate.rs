@@ -0,0 +1,288 @@
+//! 入库前的文档质量闸门
+//!
+//! 在生成嵌入向量之前评估片段质量，拒绝样板内容（导航、页脚）、
+//! Cookie 提示、404 页面和过短的片段，避免浪费嵌入额度并污染检索结果。
+//!
+//! 配置来自环境变量：
+//! - `GRAPE_QUALITY_GATE`：设为 `off`/`false`/`0` 时关闭
+//! - `GRAPE_QUALITY_MIN_SCORE`：最低质量分（0.0-1.0，默认 0.35）
+//! - `GRAPE_QUALITY_MIN_CHARS`：最少有效字符数（默认 40）
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const DEFAULT_MIN_SCORE: f32 = 0.35;
+const DEFAULT_MIN_CHARS: usize = 40;
+
+/// 404/错误页的典型文本
+const NOT_FOUND_MARKERS: &[&str] = &[
+    "404 not found",
+    "page not found",
+    "this page could not be found",
+    "the page you requested",
+    "页面不存在",
+    "找不到页面",
+    "页面未找到",
+];
+
+/// Cookie/隐私提示的典型文本
+const COOKIE_MARKERS: &[&str] = &[
+    "we use cookies",
+    "this website uses cookies",
+    "accept all cookies",
+    "cookie policy",
+    "cookie settings",
+    "manage cookies",
+    "使用cookie",
+    "使用 cookie",
+];
+
+/// 导航/页脚等样板内容的典型文本
+const BOILERPLATE_MARKERS: &[&str] = &[
+    "all rights reserved",
+    "privacy policy",
+    "terms of service",
+    "terms of use",
+    "skip to content",
+    "skip to main content",
+    "sign in",
+    "log in",
+    "subscribe to our newsletter",
+    "版权所有",
+    "隐私政策",
+];
+
+/// 闸门配置
+#[derive(Debug, Clone)]
+pub struct QualityGateConfig {
+    pub enabled: bool,
+    /// 低于该分数的片段被拒绝
+    pub min_score: f32,
+    /// 去除空白后的最少字符数
+    pub min_chars: usize,
+}
+
+impl Default for QualityGateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_score: DEFAULT_MIN_SCORE,
+            min_chars: DEFAULT_MIN_CHARS,
+        }
+    }
+}
+
+impl QualityGateConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let enabled = std::env::var("GRAPE_QUALITY_GATE")
+            .map(|v| !matches!(v.to_lowercase().as_str(), "off" | "false" | "0"))
+            .unwrap_or(defaults.enabled);
+        let min_score = std::env::var("GRAPE_QUALITY_MIN_SCORE")
+            .ok()
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|v| v.clamp(0.0, 1.0))
+            .unwrap_or(defaults.min_score);
+        let min_chars = std::env::var("GRAPE_QUALITY_MIN_CHARS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.min_chars);
+        Self { enabled, min_score, min_chars }
+    }
+}
+
+/// 拒绝原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectReason {
+    TooShort,
+    NotFoundPage,
+    CookieBanner,
+    Boilerplate,
+    LowQuality,
+}
+
+impl RejectReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectReason::TooShort => "too_short",
+            RejectReason::NotFoundPage => "not_found_page",
+            RejectReason::CookieBanner => "cookie_banner",
+            RejectReason::Boilerplate => "boilerplate",
+            RejectReason::LowQuality => "low_quality",
+        }
+    }
+}
+
+/// 单个片段的评估结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityVerdict {
+    /// 质量分（0.0-1.0）
+    pub score: f32,
+    pub rejected: Option<RejectReason>,
+}
+
+impl QualityVerdict {
+    pub fn is_accepted(&self) -> bool {
+        self.rejected.is_none()
+    }
+}
+
+/// 按原因统计的拒绝数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RejectionCounts {
+    pub by_reason: BTreeMap<RejectReason, usize>,
+}
+
+impl RejectionCounts {
+    pub fn record(&mut self, reason: RejectReason) {
+        *self.by_reason.entry(reason).or_insert(0) += 1;
+    }
+
+    pub fn total(&self) -> usize {
+        self.by_reason.values().sum()
+    }
+}
+
+/// 文档质量闸门
+#[derive(Debug, Clone)]
+pub struct QualityGate {
+    config: QualityGateConfig,
+}
+
+impl QualityGate {
+    pub fn new(config: QualityGateConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(QualityGateConfig::from_env())
+    }
+
+    /// 评估片段质量；`package_name` 用于相关性加分
+    pub fn evaluate(&self, content: &str, package_name: &str) -> QualityVerdict {
+        let score = quality_score(content, package_name);
+        if !self.config.enabled {
+            return QualityVerdict { score, rejected: None };
+        }
+
+        let visible_chars = content.chars().filter(|c| !c.is_whitespace()).count();
+        let lower = content.to_lowercase();
+        let short_page = visible_chars < self.config.min_chars * 10;
+        let rejected = if visible_chars < self.config.min_chars {
+            Some(RejectReason::TooShort)
+        } else if short_page && NOT_FOUND_MARKERS.iter().any(|m| lower.contains(m)) {
+            // 只在短页面上判定，避免误伤讲解 HTTP 404 的正文
+            Some(RejectReason::NotFoundPage)
+        } else if short_page && COOKIE_MARKERS.iter().any(|m| lower.contains(m)) {
+            Some(RejectReason::CookieBanner)
+        } else if boilerplate_ratio(content) > 0.5 {
+            Some(RejectReason::Boilerplate)
+        } else if score < self.config.min_score {
+            Some(RejectReason::LowQuality)
+        } else {
+            None
+        };
+        QualityVerdict { score, rejected }
+    }
+}
+
+impl Default for QualityGate {
+    fn default() -> Self {
+        Self::new(QualityGateConfig::default())
+    }
+}
+
+/// 质量分：文本密度、句子结构、代码示例和与包的相关性
+fn quality_score(content: &str, package_name: &str) -> f32 {
+    let lines: Vec<&str> = content.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    if lines.is_empty() {
+        return 0.0;
+    }
+
+    // 成句的行（较长且包含多个词）占比，导航菜单和链接列表通常都是短行
+    let prose_lines = lines
+        .iter()
+        .filter(|l| l.chars().count() >= 40 || (l.split_whitespace().count() >= 6))
+        .count();
+    let prose_ratio = prose_lines as f32 / lines.len() as f32;
+
+    // 字母/汉字在非空白字符中的比例
+    let visible: Vec<char> = content.chars().filter(|c| !c.is_whitespace()).collect();
+    let letters = visible.iter().filter(|c| c.is_alphabetic()).count();
+    let letter_ratio = if visible.is_empty() { 0.0 } else { letters as f32 / visible.len() as f32 };
+
+    let has_code = content.contains("```") || lines.iter().any(|l| l.ends_with(';') || l.ends_with('{'));
+    let relevant = !package_name.is_empty() && content.to_lowercase().contains(&package_name.to_lowercase());
+
+    let mut score = prose_ratio * 0.5 + letter_ratio * 0.3;
+    if has_code {
+        score += 0.15;
+    }
+    if relevant {
+        score += 0.15;
+    }
+    score.min(1.0)
+}
+
+/// 命中样板标记的行占比
+fn boilerplate_ratio(content: &str) -> f32 {
+    let lines: Vec<String> = content
+        .lines()
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return 0.0;
+    }
+    let boilerplate = lines
+        .iter()
+        .filter(|l| l.chars().count() < 80 && BOILERPLATE_MARKERS.iter().any(|m| l.contains(m)))
+        .count();
+    boilerplate as f32 / lines.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOOD_DOC: &str = "# serde::Deserialize\n\nA data structure that can be deserialized from any data format supported by Serde.\n\nSerde provides Deserialize implementations for many Rust primitive and standard library types.\n\n```rust\nlet value: Config = serde_json::from_str(input)?;\n```\n";
+
+    #[test]
+    fn test_accepts_real_documentation() {
+        let verdict = QualityGate::default().evaluate(GOOD_DOC, "serde");
+        assert!(verdict.is_accepted(), "{:?}", verdict);
+        assert!(verdict.score > 0.5);
+    }
+
+    #[test]
+    fn test_rejects_junk_pages() {
+        let gate = QualityGate::default();
+        assert_eq!(gate.evaluate("Home", "serde").rejected, Some(RejectReason::TooShort));
+        assert_eq!(
+            gate.evaluate("404 Not Found\nThe page you requested does not exist on this server.", "serde").rejected,
+            Some(RejectReason::NotFoundPage)
+        );
+        assert_eq!(
+            gate.evaluate("We use cookies to improve your experience on our site.\nAccept all cookies", "serde").rejected,
+            Some(RejectReason::CookieBanner)
+        );
+        assert_eq!(
+            gate.evaluate("Skip to content\nSign in\nPrivacy Policy\nTerms of Service\n© 2024 Example. All rights reserved.", "serde").rejected,
+            Some(RejectReason::Boilerplate)
+        );
+    }
+
+    #[test]
+    fn test_disabled_gate_accepts_everything() {
+        let gate = QualityGate::new(QualityGateConfig { enabled: false, ..QualityGateConfig::default() });
+        assert!(gate.evaluate("Home", "serde").is_accepted());
+
+        let mut counts = RejectionCounts::default();
+        counts.record(RejectReason::TooShort);
+        counts.record(RejectReason::TooShort);
+        counts.record(RejectReason::CookieBanner);
+        assert_eq!(counts.total(), 3);
+        assert_eq!(counts.by_reason[&RejectReason::TooShort], 2);
+    }
+}
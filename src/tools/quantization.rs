@@ -0,0 +1,304 @@
+//! 嵌入向量量化
+//!
+//! 几十万个 1024 维 f32 向量仅 HNSW 索引里的副本就要占用数 GB 内存。启用量化后，索引改为保存
+//! 压缩后的向量（见配置 `[vector_search.quantization]`）：
+//! - `int8`：每维按训练集的取值范围线性映射到 0..=255，索引内存约为原来的 1/4；
+//! - `pq`：乘积量化，向量切成 `pq_subvectors` 个子空间，每个子空间用 k-means 训练的
+//!   256 个中心点编码为 1 字节。
+//!
+//! 量化索引只负责取候选，最终排序和分数仍按原始向量计算（见 `VectorStore::search_similar`）。
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::config::{DistanceMetric, QuantizationConfig, QuantizationMode};
+
+/// 每个子空间的码本大小（编码为 1 字节）
+const PQ_CENTROIDS: usize = 256;
+
+/// 训练码本最多使用的向量数
+const TRAINING_SAMPLE: usize = 8192;
+
+const KMEANS_ITERATIONS: usize = 10;
+
+/// int8 标量量化
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalarQuantizer {
+    min: Vec<f32>,
+    /// 每个编码单位对应的取值，该维取值都相同时为 0
+    scale: Vec<f32>,
+}
+
+impl ScalarQuantizer {
+    pub fn train<V: AsRef<[f32]>>(vectors: &[V]) -> Self {
+        let dim = vectors.first().map_or(0, |v| v.as_ref().len());
+        let mut min = vec![f32::INFINITY; dim];
+        let mut max = vec![f32::NEG_INFINITY; dim];
+        for vector in vectors {
+            for (i, x) in vector.as_ref().iter().take(dim).enumerate() {
+                min[i] = min[i].min(*x);
+                max[i] = max[i].max(*x);
+            }
+        }
+        let scale = min.iter().zip(&max)
+            .map(|(lo, hi)| if hi > lo { (hi - lo) / 255.0 } else { 0.0 })
+            .collect();
+        Self { min, scale }
+    }
+
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        self.min.iter().zip(&self.scale).enumerate()
+            .map(|(i, (lo, scale))| {
+                let x = vector.get(i).copied().unwrap_or(*lo);
+                if *scale == 0.0 { 0 } else { ((x - lo) / scale).round().clamp(0.0, 255.0) as u8 }
+            })
+            .collect()
+    }
+
+    fn value(&self, codes: &[u8], i: usize) -> f32 {
+        self.min[i] + self.scale[i] * codes[i] as f32
+    }
+}
+
+/// 乘积量化
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductQuantizer {
+    ranges: Vec<Range<usize>>,
+    /// 每一维所属的子空间
+    subspace_of: Vec<usize>,
+    /// 每个子空间的中心点
+    codebooks: Vec<Vec<Vec<f32>>>,
+}
+
+impl ProductQuantizer {
+    /// 训练码本；子空间数超过维度时按维度截断
+    pub fn train<V: AsRef<[f32]>>(vectors: &[V], subvectors: usize) -> Self {
+        let dim = vectors.first().map_or(0, |v| v.as_ref().len());
+        let m = subvectors.clamp(1, dim.max(1));
+        let ranges: Vec<Range<usize>> = (0..m).map(|j| j * dim / m..(j + 1) * dim / m).collect();
+        let subspace_of = ranges.iter().enumerate().flat_map(|(j, range)| range.clone().map(move |_| j)).collect();
+
+        let step = (vectors.len() / TRAINING_SAMPLE).max(1);
+        let sample: Vec<&[f32]> = vectors.iter().step_by(step).map(|v| v.as_ref()).filter(|v| v.len() == dim).collect();
+        let codebooks = ranges.iter()
+            .map(|range| {
+                let points: Vec<&[f32]> = sample.iter().map(|v| &v[range.clone()]).collect();
+                kmeans(&points, PQ_CENTROIDS, KMEANS_ITERATIONS)
+            })
+            .collect();
+        Self { ranges, subspace_of, codebooks }
+    }
+
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        self.ranges.iter().zip(&self.codebooks)
+            .map(|(range, codebook)| match vector.get(range.clone()) {
+                Some(sub) => nearest_centroid(codebook, sub) as u8,
+                None => 0,
+            })
+            .collect()
+    }
+
+    fn value(&self, codes: &[u8], i: usize) -> f32 {
+        let j = self.subspace_of[i];
+        self.codebooks[j].get(codes[j] as usize).map_or(0.0, |centroid| centroid[i - self.ranges[j].start])
+    }
+}
+
+fn squared_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+fn nearest_centroid(centroids: &[Vec<f32>], point: &[f32]) -> usize {
+    centroids.iter()
+        .enumerate()
+        .map(|(i, centroid)| (i, squared_distance(centroid, point)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map_or(0, |(i, _)| i)
+}
+
+/// k-means，初始中心点按等间隔选取（结果可复现）；点数不足 k 时每个点一个中心
+fn kmeans(points: &[&[f32]], k: usize, iterations: usize) -> Vec<Vec<f32>> {
+    let k = k.min(points.len());
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut centroids: Vec<Vec<f32>> = (0..k).map(|i| points[i * points.len() / k].to_vec()).collect();
+    let sub_dim = centroids[0].len();
+    for _ in 0..iterations {
+        let mut sums = vec![vec![0.0f32; sub_dim]; k];
+        let mut counts = vec![0usize; k];
+        for point in points {
+            let c = nearest_centroid(&centroids, point);
+            counts[c] += 1;
+            for (sum, x) in sums[c].iter_mut().zip(point.iter()) {
+                *sum += x;
+            }
+        }
+        let mut moved = false;
+        for ((centroid, sum), count) in centroids.iter_mut().zip(sums).zip(counts) {
+            // 空簇保留原中心点
+            if count == 0 {
+                continue;
+            }
+            let updated: Vec<f32> = sum.into_iter().map(|s| s / count as f32).collect();
+            moved |= updated != *centroid;
+            *centroid = updated;
+        }
+        if !moved {
+            break;
+        }
+    }
+    centroids
+}
+
+/// 训练好的量化器
+#[derive(Debug, Clone, PartialEq)]
+pub enum Quantizer {
+    Scalar(ScalarQuantizer),
+    Product(ProductQuantizer),
+}
+
+impl Quantizer {
+    /// 按配置训练；不量化、没有向量或向量维度不一致时返回 None
+    pub fn train<V: AsRef<[f32]>>(config: &QuantizationConfig, vectors: &[V]) -> Option<Self> {
+        let dim = vectors.first().map_or(0, |v| v.as_ref().len());
+        if dim == 0 || vectors.iter().any(|v| v.as_ref().len() != dim) {
+            return None;
+        }
+        match config.mode {
+            QuantizationMode::None => None,
+            QuantizationMode::Int8 => Some(Quantizer::Scalar(ScalarQuantizer::train(vectors))),
+            QuantizationMode::Pq => Some(Quantizer::Product(ProductQuantizer::train(vectors, config.pq_subvectors))),
+        }
+    }
+
+    pub fn mode(&self) -> QuantizationMode {
+        match self {
+            Quantizer::Scalar(_) => QuantizationMode::Int8,
+            Quantizer::Product(_) => QuantizationMode::Pq,
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        match self {
+            Quantizer::Scalar(q) => q.min.len(),
+            Quantizer::Product(q) => q.subspace_of.len(),
+        }
+    }
+
+    /// 每个向量编码后的字节数
+    pub fn bytes_per_vector(&self) -> usize {
+        match self {
+            Quantizer::Scalar(q) => q.min.len(),
+            Quantizer::Product(q) => q.ranges.len(),
+        }
+    }
+
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        match self {
+            Quantizer::Scalar(q) => q.encode(vector),
+            Quantizer::Product(q) => q.encode(vector),
+        }
+    }
+
+    fn value(&self, codes: &[u8], i: usize) -> f32 {
+        match self {
+            Quantizer::Scalar(q) => q.value(codes, i),
+            Quantizer::Product(q) => q.value(codes, i),
+        }
+    }
+
+    /// 还原为近似的 f32 向量
+    pub fn decode(&self, codes: &[u8]) -> Vec<f32> {
+        (0..self.dim()).map(|i| self.value(codes, i)).collect()
+    }
+
+    /// 两个编码之间的近似距离，逐维还原而不分配新向量
+    pub fn distance(&self, metric: DistanceMetric, a: &[u8], b: &[u8]) -> f32 {
+        metric.distance_pairs((0..self.dim()).map(|i| (self.value(a, i), self.value(b, i))))
+    }
+}
+
+/// 量化后的向量点，用于在 HNSW 中按近似距离建图
+#[derive(Debug, Clone)]
+pub struct QuantizedPoint {
+    codes: Vec<u8>,
+    quantizer: Arc<Quantizer>,
+    metric: DistanceMetric,
+}
+
+impl QuantizedPoint {
+    pub fn new(quantizer: Arc<Quantizer>, metric: DistanceMetric, vector: &[f32]) -> Self {
+        Self { codes: quantizer.encode(vector), quantizer, metric }
+    }
+}
+
+impl instant_distance::Point for QuantizedPoint {
+    fn distance(&self, other: &Self) -> f32 {
+        self.quantizer.distance(self.metric, &self.codes, &other.codes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 可复现的伪随机向量
+    fn vectors(n: usize, dim: usize) -> Vec<Vec<f32>> {
+        let mut state = 42u64;
+        (0..n)
+            .map(|_| {
+                (0..dim)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        ((state >> 33) as f32 / (1u64 << 31) as f32) * 2.0 - 1.0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn config(mode: QuantizationMode) -> QuantizationConfig {
+        QuantizationConfig { mode, pq_subvectors: 8, ..QuantizationConfig::default() }
+    }
+
+    #[test]
+    fn test_int8_round_trip() {
+        let data = vectors(200, 16);
+        let quantizer = Quantizer::train(&config(QuantizationMode::Int8), &data).unwrap();
+        assert_eq!(quantizer.bytes_per_vector(), 16);
+        for vector in &data {
+            let decoded = quantizer.decode(&quantizer.encode(vector));
+            // 误差不超过半个编码单位（取值范围 2 / 255 / 2）
+            assert!(vector.iter().zip(&decoded).all(|(x, y)| (x - y).abs() <= 0.005), "{:?} {:?}", vector, decoded);
+        }
+        assert!(Quantizer::train(&config(QuantizationMode::None), &data).is_none());
+        assert!(Quantizer::train(&config(QuantizationMode::Int8), &[vec![1.0, 2.0], vec![1.0]]).is_none());
+    }
+
+    #[test]
+    fn test_pq_preserves_nearest_neighbours() {
+        let data = vectors(600, 32);
+        let quantizer = Quantizer::train(&config(QuantizationMode::Pq), &data).unwrap();
+        assert_eq!(quantizer.bytes_per_vector(), 8);
+
+        let metric = DistanceMetric::L2;
+        let codes: Vec<Vec<u8>> = data.iter().map(|v| quantizer.encode(v)).collect();
+        // 用近似距离取前 20 个候选，真实最近邻应在其中（即重排能找回）
+        let mut hits = 0;
+        for (q, query) in data.iter().enumerate().take(20) {
+            let exact = (0..data.len())
+                .filter(|i| *i != q)
+                .min_by(|a, b| metric.distance(query, &data[*a]).partial_cmp(&metric.distance(query, &data[*b])).unwrap())
+                .unwrap();
+            let mut approx: Vec<usize> = (0..data.len()).filter(|i| *i != q).collect();
+            approx.sort_by(|a, b| {
+                quantizer.distance(metric, &codes[q], &codes[*a])
+                    .partial_cmp(&quantizer.distance(metric, &codes[q], &codes[*b]))
+                    .unwrap()
+            });
+            hits += approx[..20].contains(&exact) as usize;
+        }
+        assert!(hits >= 16, "召回过低: {}/20", hits);
+    }
+}
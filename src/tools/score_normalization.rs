@@ -0,0 +1,121 @@
+//! 搜索分数归一化
+//!
+//! 各搜索模式的原始分数量纲不同：向量相似度由距离换算（`1 / (1 + d)` 或 `(1 + cos) / 2`），BM25 分数没有上限，
+//! 混合分数是加权和再加上下文加分，符号匹配固定在 1.6-2.0，重排模型可能返回 logit。客户端按固定阈值过滤时，
+//! 同一个阈值在不同模式下含义完全不同。这里把各模式的原始分数映射为 0-1 的相关度：
+//!
+//! - 向量：相似度本身已在 0-1 内，只做截断
+//! - 全文：`s / (s + BM25_HALF_SCORE)`，与查询无关，同一阈值在不同查询间可比（按最高分归一化则做不到）
+//! - 混合：按理论最大值缩放到 0-[`HYBRID_CEILING`]，低于任何符号精确匹配
+//! - 符号：映射到 [`HYBRID_CEILING`]-1
+//! - 重排：整批都在 0-1 内时保留，否则视为 logit 取 sigmoid
+//!
+//! 原始分数和模式记录在结果元数据的 `raw_score` 和 `score_kind` 中，便于调试排序。
+
+use super::vector_docs_tool::SearchResult;
+
+/// 结果元数据中的原始分数
+pub const RAW_SCORE_METADATA_KEY: &str = "raw_score";
+
+/// 结果元数据中的分数来源（vector、text、hybrid、symbol、rerank）
+pub const SCORE_KIND_METADATA_KEY: &str = "score_kind";
+
+/// BM25 分数为该值时归一化分数为 0.5
+const BM25_HALF_SCORE: f32 = 4.0;
+
+/// 混合打分的理论最大值：向量 0.6 + 关键词 0.3 + 语言和包名 0.2 + 文档类型 0.1
+const HYBRID_MAX_RAW: f32 = 1.2;
+
+/// 混合结果归一化后的上限，也是符号匹配结果的下限
+pub const HYBRID_CEILING: f32 = 0.95;
+
+/// 符号匹配原始分数的范围
+const SYMBOL_RAW_RANGE: (f32, f32) = (1.6, 2.0);
+
+/// 分数来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreKind {
+    Vector,
+    Text,
+    Hybrid,
+    Symbol,
+    Rerank,
+}
+
+impl ScoreKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScoreKind::Vector => "vector",
+            ScoreKind::Text => "text",
+            ScoreKind::Hybrid => "hybrid",
+            ScoreKind::Symbol => "symbol",
+            ScoreKind::Rerank => "rerank",
+        }
+    }
+
+    /// 把原始分数映射为 0-1 的相关度；重排分数需要整批判断，见 [`normalize_rerank_scores`]
+    pub fn normalize(&self, raw: f32) -> f32 {
+        let score = match self {
+            ScoreKind::Vector | ScoreKind::Rerank => raw,
+            ScoreKind::Text => {
+                let raw = raw.max(0.0);
+                raw / (raw + BM25_HALF_SCORE)
+            }
+            ScoreKind::Hybrid => raw / HYBRID_MAX_RAW * HYBRID_CEILING,
+            ScoreKind::Symbol => {
+                let (low, high) = SYMBOL_RAW_RANGE;
+                HYBRID_CEILING + (raw - low) / (high - low) * (1.0 - HYBRID_CEILING)
+            }
+        };
+        if score.is_nan() {
+            return 0.0;
+        }
+        match self {
+            ScoreKind::Hybrid => score.clamp(0.0, HYBRID_CEILING),
+            ScoreKind::Symbol => score.clamp(HYBRID_CEILING, 1.0),
+            _ => score.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// 归一化结果分数，并在元数据中记录原始分数和来源
+pub fn normalize_result(kind: ScoreKind, result: &mut SearchResult) {
+    result.metadata.insert(RAW_SCORE_METADATA_KEY.to_string(), result.score.to_string());
+    result.metadata.insert(SCORE_KIND_METADATA_KEY.to_string(), kind.as_str().to_string());
+    result.score = kind.normalize(result.score);
+}
+
+/// 重排模型的分数：全部在 0-1 内时原样使用，否则（如 NVIDIA 返回的 logit）整批取 sigmoid，保持相对顺序
+pub fn normalize_rerank_scores(raw: &[f64]) -> Vec<f32> {
+    let calibrated = raw.iter().all(|score| (0.0..=1.0).contains(score));
+    raw.iter()
+        .map(|&score| if calibrated { score as f32 } else { (1.0 / (1.0 + (-score).exp())) as f32 })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_map_to_unit_range_and_keep_order() {
+        for kind in [ScoreKind::Vector, ScoreKind::Text, ScoreKind::Hybrid, ScoreKind::Symbol] {
+            for raw in [-1.0, 0.0, 0.3, 1.0, 1.2, 2.0, 25.0, f32::NAN] {
+                let score = kind.normalize(raw);
+                assert!((0.0..=1.0).contains(&score), "{:?} {} -> {}", kind, raw, score);
+            }
+        }
+
+        // BM25 单调且与查询无关
+        assert!(ScoreKind::Text.normalize(2.0) < ScoreKind::Text.normalize(8.0));
+        assert_eq!(ScoreKind::Text.normalize(BM25_HALF_SCORE), 0.5);
+
+        // 混合结果不超过符号精确匹配
+        assert!(ScoreKind::Hybrid.normalize(HYBRID_MAX_RAW) <= ScoreKind::Symbol.normalize(1.6));
+        assert_eq!(ScoreKind::Symbol.normalize(2.0), 1.0);
+
+        assert_eq!(normalize_rerank_scores(&[0.9, 0.1]), vec![0.9, 0.1]);
+        let logits = normalize_rerank_scores(&[3.5, -2.0]);
+        assert!(logits[0] > logits[1] && logits[0] < 1.0 && logits[1] > 0.0);
+    }
+}
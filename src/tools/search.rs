@@ -7,12 +7,18 @@ use serde_json::{json, Value};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use crate::errors::MCPError;
-use super::base::{MCPTool, ToolAnnotations, Schema, SchemaObject, SchemaString, SchemaNumber};
+use crate::mcp::session::{self, SessionPreferences};
+use crate::mcp::streaming::PartialResultSink;
+use super::base::{MCPTool, ToolAnnotations, Schema, SchemaObject, SchemaString, SchemaNumber, SchemaArray, SchemaInteger};
+use super::enhanced_doc_processor::EnhancedDocumentProcessor;
+use super::search_mode::{self, DeepCrawlOutcome, SearchMode};
 
 pub struct SearchDocsTools {
     _annotations: ToolAnnotations,
     cache: Arc<RwLock<HashMap<String, (Value, DateTime<Utc>)>>>,
     client: reqwest::Client,
+    /// deep 模式抓取文档使用的处理器（未设置时 deep 模式等同 standard）
+    doc_processor: Option<Arc<EnhancedDocumentProcessor>>,
 }
 
 impl SearchDocsTools {
@@ -25,8 +31,15 @@ impl SearchDocsTools {
             },
             cache: Arc::new(RwLock::new(HashMap::new())),
             client: reqwest::Client::new(),
+            doc_processor: None,
         }
     }
+
+    /// 设置文档处理器，启用 deep 模式的针对性抓取
+    pub fn with_doc_processor(mut self, doc_processor: Arc<EnhancedDocumentProcessor>) -> Self {
+        self.doc_processor = Some(doc_processor);
+        self
+    }
     
     fn validate_params(&self, params: &Value) -> Result<()> {
         if params["query"].as_str().is_none() {
@@ -34,35 +47,118 @@ impl SearchDocsTools {
         }
         
         if params["language"].as_str().is_none() {
-            return Err(MCPError::InvalidParameter("缺少language参数".to_string()).into());
+            return Err(MCPError::InvalidParameter("缺少language参数（可通过 set_session_preferences 设置会话默认语言）".to_string()).into());
         }
         
         Ok(())
     }
     
-    async fn search_or_get_cached(&self, query: &str, language: &str) -> Result<Value> {
+    /// 只查缓存，未命中或已过期时返回 None
+    async fn get_cached(&self, query: &str, language: &str) -> Option<Value> {
         let cache_key = format!("{}:{}", language, query);
         let cache_ttl = chrono::Duration::hours(1);
-        
-        {
-            let cache = self.cache.read().await;
-            if let Some((cached_result, timestamp)) = cache.get(&cache_key) {
-                if Utc::now() - *timestamp < cache_ttl {
-                    tracing::debug!("从缓存返回搜索结果: {}", cache_key);
-                    return Ok(cached_result.clone());
-                }
-            }
+        let cache = self.cache.read().await;
+        let (cached_result, timestamp) = cache.get(&cache_key)?;
+        if Utc::now() - *timestamp < cache_ttl {
+            tracing::debug!("从缓存返回搜索结果: {}", cache_key);
+            return Some(cached_result.clone());
+        }
+        None
+    }
+
+    async fn search_or_get_cached(&self, query: &str, language: &str) -> Result<Value> {
+        if let Some(cached) = self.get_cached(query, language).await {
+            return Ok(cached);
         }
         
         let results = self.perform_search(query, language).await?;
         
         {
             let mut cache = self.cache.write().await;
-            cache.insert(cache_key, (results.clone(), Utc::now()));
+            cache.insert(format!("{}:{}", language, query), (results.clone(), Utc::now()));
         }
         
         Ok(results)
     }
+
+    async fn execute_search(&self, params: Value, sink: Option<&PartialResultSink>) -> Result<Value> {
+        self.validate_params(&params)?;
+        
+        let query = params["query"]
+            .as_str()
+            .ok_or_else(|| MCPError::InvalidParameter("query 参数无效".into()))?;
+            
+        let language = params["language"]
+            .as_str()
+            .ok_or_else(|| MCPError::InvalidParameter("language 参数无效".into()))?;
+            
+        let max_results = params["max_results"]
+            .as_u64()
+            .unwrap_or(10) as usize;
+            
+        let mut results = self.search_with_mode(&params, query, language, sink).await?;
+        
+        if let Some(results_array) = results["results"].as_array_mut() {
+            if results_array.len() > max_results {
+                *results_array = results_array[0..max_results].to_vec();
+                results["total_hits"] = json!(max_results);
+            }
+        }
+        
+        Ok(results)
+    }
+
+    /// 按搜索模式执行，`sink` 用于 deep 模式等待期间推送进度
+    async fn search_with_mode(&self, params: &Value, query: &str, language: &str, sink: Option<&PartialResultSink>) -> Result<Value> {
+        let mode = SearchMode::from_params(params)?;
+        let started = std::time::Instant::now();
+        let (mut results, complete) = match mode {
+            SearchMode::Quick => match self.get_cached(query, language).await {
+                Some(cached) => (cached, true),
+                None => (json!({
+                    "results": [],
+                    "total_hits": 0,
+                    "language": language,
+                    "summary": format!("缓存中没有 '{}' 的搜索结果，可使用 standard 或 deep 模式", query),
+                }), true),
+            },
+            SearchMode::Standard => (self.search_or_get_cached(query, language).await?, true),
+            SearchMode::Deep => {
+                let mut results = self.search_or_get_cached(query, language).await?;
+                let package = params["package"].as_str();
+                let mut complete = true;
+                if let (Some(processor), Some(package)) = (&self.doc_processor, package) {
+                    let budget = search_mode::deep_budget(params);
+                    let outcome = search_mode::run_deep_crawl(
+                        Arc::clone(processor), language, package, params["version"].as_str(), query, budget, sink,
+                    ).await?;
+                    match outcome {
+                        DeepCrawlOutcome::Completed(crawled) => {
+                            let hits: Vec<Value> = crawled.iter().map(|hit| json!({
+                                "title": hit.fragment.file_path,
+                                "content": hit.content_preview,
+                                "relevance": hit.score,
+                                "source": format!("{}_docs_cache", language),
+                                "url": hit.fragment.file_path,
+                            })).collect();
+                            if let Some(list) = results["results"].as_array_mut() {
+                                list.splice(0..0, hits);
+                                results["total_hits"] = json!(list.len());
+                            }
+                            results["crawl_status"] = json!("completed");
+                        }
+                        DeepCrawlOutcome::InProgress => {
+                            results["crawl_status"] = json!("in_progress");
+                            complete = false;
+                        }
+                    }
+                }
+                (results, complete)
+            }
+        };
+        search_mode::annotate(&mut results, mode, started, complete);
+        Ok(results)
+    }
     
     async fn perform_search(&self, query: &str, language: &str) -> Result<Value> {
         tracing::info!("执行文档搜索: {} (语言: {})", query, language);
@@ -409,14 +505,14 @@ impl MCPTool for SearchDocsTools {
         static SCHEMA: OnceLock<Schema> = OnceLock::new();
         
         SCHEMA.get_or_init(|| {            Schema::Object(SchemaObject {
-                required: vec!["query".to_string(), "language".to_string()],
+                required: vec!["query".to_string()],
                 properties: {
                     let mut map = HashMap::new();                    map.insert("query".to_string(), Schema::String(SchemaString {
                         description: Some("要搜索的功能或技术需求".to_string()),
                         enum_values: None,
                     }));
                     map.insert("language".to_string(), Schema::String(SchemaString {
-                        description: Some("目标编程语言".to_string()),
+                        description: Some("目标编程语言，省略时使用会话偏好语言".to_string()),
                         enum_values: None,
                     }));
                     map.insert("scope".to_string(), Schema::String(SchemaString {
@@ -424,10 +520,15 @@ impl MCPTool for SearchDocsTools {
                         enum_values: None,
                     }));
                     map.insert("max_results".to_string(), Schema::Number(SchemaNumber {
-                        description: Some("最大结果数".to_string()),
+                        description: Some("最大结果数，省略时使用会话偏好".to_string()),
                         minimum: Some(1.0),
                         maximum: Some(100.0),
                     }));
+                    map.insert("package".to_string(), Schema::String(SchemaString {
+                        description: Some("deep 模式下要抓取文档的包名（可选）".to_string()),
+                        enum_values: None,
+                    }));
+                    search_mode::add_mode_properties(&mut map);
                     map
                 },
                 ..Default::default()
@@ -435,30 +536,53 @@ impl MCPTool for SearchDocsTools {
         })
     }
     
-    async fn execute(&self, params: Value) -> Result<Value> {
-        self.validate_params(&params)?;
-        
-        let query = params["query"]
-            .as_str()
-            .ok_or_else(|| MCPError::InvalidParameter("query 参数无效".into()))?;
-            
-        let language = params["language"]
-            .as_str()
-            .ok_or_else(|| MCPError::InvalidParameter("language 参数无效".into()))?;
-            
-        let max_results = params["max_results"]
-            .as_u64()
-            .unwrap_or(10) as usize;
-            
-        let mut results = self.search_or_get_cached(query, language).await?;
-        
-        if let Some(results_array) = results["results"].as_array_mut() {
-            if results_array.len() > max_results {
-                *results_array = results_array[0..max_results].to_vec();
-                results["total_hits"] = json!(max_results);
-            }
+    fn apply_session_defaults(&self, params: &mut Value, session: &SessionPreferences) {
+        if let Some(language) = session.primary_language() {
+            session::fill_default(params, "language", json!(language));
         }
-        
-        Ok(results)
+        if let Some(max_results) = session.max_results {
+            session::fill_default(params, "max_results", json!(max_results));
+        }
+    }
+
+    fn output_schema(&self) -> Option<&Schema> {
+        static OUTPUT_SCHEMA: OnceLock<Schema> = OnceLock::new();
+
+        Some(OUTPUT_SCHEMA.get_or_init(|| {
+            let mut hit_properties = HashMap::new();
+            hit_properties.insert("title".to_string(), Schema::String(SchemaString::default()));
+            hit_properties.insert("content".to_string(), Schema::String(SchemaString::default()));
+            hit_properties.insert("relevance".to_string(), Schema::Number(SchemaNumber::default()));
+            hit_properties.insert("source".to_string(), Schema::String(SchemaString::default()));
+            hit_properties.insert("url".to_string(), Schema::String(SchemaString::default()));
+
+            let mut properties = HashMap::new();
+            properties.insert("results".to_string(), Schema::Array(SchemaArray {
+                description: Some("搜索命中列表".to_string()),
+                items: Box::new(Schema::Object(SchemaObject {
+                    required: vec!["title".to_string(), "content".to_string(), "url".to_string()],
+                    properties: hit_properties,
+                    description: Some("单条搜索命中".to_string()),
+                })),
+            }));
+            properties.insert("total_hits".to_string(), Schema::Integer(SchemaInteger::default()));
+            properties.insert("language".to_string(), Schema::String(SchemaString::default()));
+            properties.insert("summary".to_string(), Schema::String(SchemaString::default()));
+
+            Schema::Object(SchemaObject {
+                required: vec!["results".to_string(), "total_hits".to_string()],
+                properties,
+                description: Some("文档搜索结果".to_string()),
+            })
+        }))
+    }
+    
+    async fn execute(&self, params: Value) -> Result<Value> {
+        self.execute_search(params, None).await
+    }
+
+    async fn execute_streaming(&self, params: Value, sink: &PartialResultSink) -> Result<Value> {
+        let result = self.execute_search(params, Some(sink)).await?;
+        Ok(sink.stream_results(result))
     }
 }
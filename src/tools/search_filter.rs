@@ -0,0 +1,328 @@
+//! 搜索元数据过滤
+//!
+//! [`SearchFilter`] 按语言、包名（或包名通配）、版本、文档类型、入库时间以及文档自然语言/代码块语言
+//! 限制搜索结果，在向量存储内部检索候选时应用，因此过滤后仍能返回足够的结果，不需要调用方多取再过滤。
+//! 启用 SQLite 元数据后端时，能用 SQL 表达的条件会下推到数据库（见 `sqlite_metadata`）。
+//!
+//! 版本条件支持三种写法：
+//! - 通配前缀：`1.x`、`1.*`、`1`、`1.2.x`，按版本号分段前缀匹配
+//! - semver 约束：`^1.2`、`~0.3`、`>=1.0, <2`
+//! - 其他写法按字符串精确匹配（如 `latest`、`go1.21`）
+//!
+//! 另外可以按包锁定版本（[`SearchFilter::pinned_versions`]）：锁定的包只返回该版本的文档，其他包不受影响。
+//! 搜索时由项目画像根据锁文件填入，见 `ProjectProfile::pin_locked_versions`。
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::tools::content_language;
+use crate::tools::dependency_cleanup::dependency_key;
+
+/// 文档元数据中记录入库时间（RFC 3339）的键
+pub const CREATED_AT_METADATA_KEY: &str = "created_at";
+
+/// 搜索过滤条件，未设置的字段不过滤
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<String>,
+    /// 包名通配，`*` 匹配任意字符、`?` 匹配单个字符（如 `tokio-*`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_pattern: Option<String>,
+    /// 版本条件，写法见模块文档
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_type: Option<String>,
+    /// 文档自然语言（ISO 639-1，如 en、zh）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub natural_language: Option<String>,
+    /// 文档中代码块的编程语言
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_language: Option<String>,
+    /// 只保留该时间及之后入库的文档
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<DateTime<Utc>>,
+    /// 只保留该时间及之前入库的文档
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<DateTime<Utc>>,
+    /// 依赖键（`语言/包名`，见 `dependency_cleanup::dependency_key`）-> 锁定版本，这些包只保留锁定版本的文档
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pinned_versions: BTreeMap<String, String>,
+}
+
+impl SearchFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    pub fn package_name(mut self, package_name: impl Into<String>) -> Self {
+        self.package_name = Some(package_name.into());
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    pub fn doc_type(mut self, doc_type: impl Into<String>) -> Self {
+        self.doc_type = Some(doc_type.into());
+        self
+    }
+
+    pub fn package_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.package_pattern = Some(pattern.into());
+        self
+    }
+
+    /// 把一个包锁定到指定版本
+    pub fn pin_version(mut self, language: &str, package_name: &str, version: impl Into<String>) -> Self {
+        self.pinned_versions.insert(dependency_key(language, package_name), version.into());
+        self
+    }
+
+    pub fn created_between(mut self, after: Option<DateTime<Utc>>, before: Option<DateTime<Utc>>) -> Self {
+        self.created_after = after;
+        self.created_before = before;
+        self
+    }
+
+    /// 从工具参数中读取同名字段，空字符串和无法解析的时间视为未设置
+    pub fn from_params(params: &Value) -> Self {
+        let text = |key: &str| {
+            params.get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let time = |key: &str| text(key).and_then(|value| parse_timestamp(&value));
+        Self {
+            language: text("language"),
+            package_name: text("package_name"),
+            package_pattern: text("package_pattern"),
+            version: text("version"),
+            doc_type: text("doc_type"),
+            natural_language: text("natural_language"),
+            code_language: text("code_language"),
+            created_after: time("created_after"),
+            created_before: time("created_before"),
+            pinned_versions: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 判断一个文档的元数据是否满足条件
+    pub fn matches(
+        &self,
+        language: &str,
+        package_name: &str,
+        version: &str,
+        doc_type: &str,
+        metadata: &HashMap<String, String>,
+    ) -> bool {
+        self.language.as_deref().map_or(true, |l| l.eq_ignore_ascii_case(language))
+            && self.package_name.as_deref().map_or(true, |p| p == package_name)
+            && self.package_pattern.as_deref().map_or(true, |p| glob_matches(p, package_name))
+            && self.matches_created_at(metadata)
+            && self.version.as_deref().map_or(true, |v| version_matches(v, version))
+            && self.matches_pinned_version(language, package_name, version)
+            && self.doc_type.as_deref().map_or(true, |t| t == doc_type)
+            && content_language::matches_language_filter(
+                metadata,
+                self.natural_language.as_deref(),
+                self.code_language.as_deref(),
+            )
+    }
+
+    /// 没有锁定版本的包都满足
+    fn matches_pinned_version(&self, language: &str, package_name: &str, version: &str) -> bool {
+        if self.pinned_versions.is_empty() {
+            return true;
+        }
+        self.pinned_versions
+            .get(&dependency_key(language, package_name))
+            .map_or(true, |pinned| version.trim_start_matches('v') == pinned.trim_start_matches('v'))
+    }
+
+    /// 设置了入库时间范围时，没有（或无法解析）入库时间的文档不匹配
+    fn matches_created_at(&self, metadata: &HashMap<String, String>) -> bool {
+        if self.created_after.is_none() && self.created_before.is_none() {
+            return true;
+        }
+        let Some(created_at) = metadata.get(CREATED_AT_METADATA_KEY).and_then(|value| parse_timestamp(value)) else {
+            return false;
+        };
+        self.created_after.map_or(true, |after| created_at >= after)
+            && self.created_before.map_or(true, |before| created_at <= before)
+    }
+}
+
+/// 解析 RFC 3339 时间
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim()).ok().map(|time| time.with_timezone(&Utc))
+}
+
+/// 通配匹配：`*` 匹配任意个字符，`?` 匹配单个字符
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// 判断版本是否满足版本条件
+pub fn version_matches(pattern: &str, version: &str) -> bool {
+    let pattern = pattern.trim();
+    if pattern.is_empty() || pattern == "*" || pattern == version {
+        return true;
+    }
+    if let Some(prefix) = wildcard_prefix(pattern) {
+        let version = version.trim_start_matches('v');
+        let mut parts = version.split(|c| c == '.' || c == '-' || c == '+');
+        return prefix.iter().all(|want| parts.next() == Some(*want));
+    }
+    if pattern.starts_with(|c| matches!(c, '^' | '~' | '>' | '<' | '=')) {
+        if let (Ok(req), Some(version)) = (semver::VersionReq::parse(pattern), lenient_version(version)) {
+            return req.matches(&version);
+        }
+    }
+    false
+}
+
+/// `1.x`、`1.2.*`、`1` 形式的条件返回数字前缀分段，其他写法返回 None
+pub(crate) fn wildcard_prefix(pattern: &str) -> Option<Vec<&str>> {
+    let pattern = pattern.trim_start_matches('v');
+    let mut prefix = Vec::new();
+    for part in pattern.split('.') {
+        match part {
+            "x" | "X" | "*" => break,
+            _ if !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) => prefix.push(part),
+            _ => return None,
+        }
+    }
+    // 完整的三段版本号按精确匹配处理
+    (!prefix.is_empty() && (prefix.len() < 3 || pattern.split('.').count() > prefix.len())).then_some(prefix)
+}
+
+/// 解析版本号，缺少的次版本号和修订号补 0（`1.2` -> `1.2.0`）
+pub(crate) fn lenient_version(version: &str) -> Option<semver::Version> {
+    let version = version.trim().trim_start_matches('v');
+    semver::Version::parse(version).ok().or_else(|| {
+        let parts: Vec<&str> = version.split('.').collect();
+        if parts.is_empty() || parts.len() > 3 || !parts.iter().all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit())) {
+            return None;
+        }
+        let mut padded = parts.clone();
+        padded.resize(3, "0");
+        semver::Version::parse(&padded.join(".")).ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_version_patterns() {
+        assert!(version_matches("1.x", "1.38.0"));
+        assert!(version_matches("1.*", "1.0.0-beta.1"));
+        assert!(version_matches("1", "v1.2.3"));
+        assert!(!version_matches("1.x", "0.3.1"));
+        assert!(!version_matches("1.x", "10.0.0"));
+        assert!(version_matches("1.2.x", "1.2.9"));
+        assert!(!version_matches("1.2.x", "1.20.0"));
+
+        assert!(version_matches("^1.2", "1.9.0"));
+        assert!(!version_matches("^1.2", "2.0.0"));
+        assert!(version_matches(">=0.3, <0.5", "0.4"));
+
+        assert!(version_matches("1.2.3", "1.2.3"));
+        assert!(!version_matches("1.2.3", "1.2.30"));
+        assert!(version_matches("latest", "latest"));
+        assert!(!version_matches("latest", "1.0.0"));
+    }
+
+    #[test]
+    fn test_filter_from_params() {
+        let filter = SearchFilter::from_params(&json!({
+            "query": "spawn",
+            "language": "rust",
+            "package_name": "tokio",
+            "version": "1.x",
+            "doc_type": "",
+        }));
+        assert_eq!(filter, SearchFilter::new().language("rust").package_name("tokio").version("1.x"));
+
+        let metadata = HashMap::new();
+        assert!(filter.matches("Rust", "tokio", "1.38.0", "api", &metadata));
+        assert!(!filter.matches("rust", "tokio", "0.2.25", "api", &metadata));
+        assert!(!filter.matches("rust", "serde", "1.0.0", "api", &metadata));
+        assert!(SearchFilter::from_params(&json!({ "query": "spawn" })).is_empty());
+    }
+
+    #[test]
+    fn test_pinned_versions() {
+        let filter = SearchFilter::new().pin_version("rust", "serde_json", "1.0.117");
+        let metadata = HashMap::new();
+        assert!(filter.matches("rust", "serde-json", "1.0.117", "api", &metadata));
+        assert!(!filter.matches("cargo", "serde_json", "1.0.120", "api", &metadata));
+        // 其他包和其他生态的同名包不受影响
+        assert!(filter.matches("rust", "tokio", "1.38.0", "api", &metadata));
+        assert!(filter.matches("python", "serde-json", "0.1.0", "api", &metadata));
+    }
+
+    #[test]
+    fn test_package_pattern_and_created_window() {
+        assert!(glob_matches("tokio-*", "tokio-util"));
+        assert!(glob_matches("*-derive", "serde-derive"));
+        assert!(glob_matches("s?rde", "serde"));
+        assert!(!glob_matches("tokio-*", "tokio"));
+
+        let filter = SearchFilter::from_params(&json!({
+            "package_pattern": "tokio*",
+            "created_after": "2024-05-01T00:00:00Z",
+            "created_before": "not a time",
+        }));
+        assert!(filter.created_before.is_none());
+        let created = |at: &str| HashMap::from([(CREATED_AT_METADATA_KEY.to_string(), at.to_string())]);
+        assert!(filter.matches("rust", "tokio-util", "0.7.0", "api", &created("2024-06-01T08:00:00+08:00")));
+        assert!(!filter.matches("rust", "tokio-util", "0.7.0", "api", &created("2024-04-30T00:00:00Z")));
+        assert!(!filter.matches("rust", "tokio", "1.38.0", "api", &HashMap::new()));
+        assert!(!filter.matches("rust", "serde", "1.0.0", "api", &created("2024-06-01T00:00:00Z")));
+    }
+}
@@ -0,0 +1,184 @@
+//! 文档工具的搜索模式
+//!
+//! 调用方通过 `mode` 参数在延迟和完整度之间取舍：
+//! - `quick`：只查本地缓存，1 秒内返回，缓存未命中时返回空结果；
+//! - `standard`：缓存 + 包注册表 API（默认，与之前的行为一致）；
+//! - `deep`：在 `budget_secs` 预算内触发针对性抓取并入库。预算用完时先返回已有结果，
+//!   抓取在后台继续；客户端提供 progressToken 时等待期间推送进度通知。
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::errors::MCPError;
+use crate::mcp::streaming::PartialResultSink;
+use super::base::{Schema, SchemaInteger, SchemaString};
+use super::enhanced_doc_processor::{EnhancedDocumentProcessor, EnhancedSearchResult};
+
+/// quick 模式的时间上限
+pub const QUICK_TIMEOUT: Duration = Duration::from_secs(1);
+/// deep 模式默认的抓取预算（秒）
+pub const DEFAULT_DEEP_BUDGET_SECS: u64 = 60;
+/// deep 模式允许的最大预算（秒）
+pub const MAX_DEEP_BUDGET_SECS: u64 = 600;
+
+/// 搜索模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    Quick,
+    #[default]
+    Standard,
+    Deep,
+}
+
+impl SearchMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "quick" => Some(SearchMode::Quick),
+            "standard" => Some(SearchMode::Standard),
+            "deep" => Some(SearchMode::Deep),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchMode::Quick => "quick",
+            SearchMode::Standard => "standard",
+            SearchMode::Deep => "deep",
+        }
+    }
+
+    /// 从工具参数中读取 `mode`，未提供时为 standard
+    pub fn from_params(params: &Value) -> Result<Self> {
+        match params.get("mode").and_then(|v| v.as_str()) {
+            None => Ok(SearchMode::Standard),
+            Some(mode) => Self::parse(mode).ok_or_else(|| {
+                MCPError::InvalidParameter(format!("无效的 mode: {}，可选 quick|standard|deep", mode)).into()
+            }),
+        }
+    }
+}
+
+/// 从工具参数中读取 deep 模式的预算 `budget_secs`
+pub fn deep_budget(params: &Value) -> Duration {
+    let secs = params
+        .get("budget_secs")
+        .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        .unwrap_or(DEFAULT_DEEP_BUDGET_SECS)
+        .clamp(1, MAX_DEEP_BUDGET_SECS);
+    Duration::from_secs(secs)
+}
+
+/// 向工具参数Schema中加入 `mode` 和 `budget_secs`
+pub fn add_mode_properties(properties: &mut HashMap<String, Schema>) {
+    properties.insert("mode".to_string(), Schema::String(SchemaString {
+        description: Some("搜索模式: quick（只查缓存，1秒内）| standard（缓存+注册表API，默认）| deep（在预算内抓取文档）".to_string()),
+        enum_values: Some(vec!["quick".to_string(), "standard".to_string(), "deep".to_string()]),
+    }));
+    properties.insert("budget_secs".to_string(), Schema::Integer(SchemaInteger {
+        description: Some(format!("deep 模式的抓取预算（秒），默认 {}", DEFAULT_DEEP_BUDGET_SECS)),
+        minimum: Some(1),
+        maximum: Some(MAX_DEEP_BUDGET_SECS as i64),
+    }));
+}
+
+/// 在结果中记录实际使用的模式、耗时和是否完整
+pub fn annotate(result: &mut Value, mode: SearchMode, started: Instant, complete: bool) {
+    if result.is_object() {
+        result["search_mode"] = json!({
+            "mode": mode.as_str(),
+            "elapsed_ms": started.elapsed().as_millis() as u64,
+            "complete": complete,
+        });
+    }
+}
+
+/// deep 模式抓取的结果
+#[derive(Debug)]
+pub enum DeepCrawlOutcome {
+    /// 抓取在预算内完成
+    Completed(Vec<EnhancedSearchResult>),
+    /// 预算用完，抓取仍在后台进行
+    InProgress,
+}
+
+/// 在预算内等待针对性抓取；抓取在独立任务中运行，超出预算不会被取消
+pub async fn run_deep_crawl(
+    processor: Arc<EnhancedDocumentProcessor>,
+    language: &str,
+    package_name: &str,
+    version: Option<&str>,
+    query: &str,
+    budget: Duration,
+    sink: Option<&PartialResultSink>,
+) -> Result<DeepCrawlOutcome> {
+    let (language, package, version, query) = (
+        language.to_string(),
+        package_name.to_string(),
+        version.map(str::to_string),
+        query.to_string(),
+    );
+    let label = format!("{}/{}", language, package);
+    let mut crawl = tokio::spawn(async move {
+        processor
+            .process_documentation_request_enhanced(&language, &package, version.as_deref(), &query)
+            .await
+    });
+
+    let started = Instant::now();
+    let deadline = tokio::time::Instant::now() + budget;
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    ticker.tick().await;
+    loop {
+        tokio::select! {
+            joined = &mut crawl => {
+                return match joined {
+                    Ok(result) => result.map(DeepCrawlOutcome::Completed),
+                    Err(e) => Err(MCPError::ServerError(format!("抓取任务异常退出: {}", e)).into()),
+                };
+            }
+            _ = tokio::time::sleep_until(deadline) => {
+                tracing::info!("⏳ {} 的深度抓取超出 {:?} 预算，转入后台继续", label, budget);
+                return Ok(DeepCrawlOutcome::InProgress);
+            }
+            _ = ticker.tick() => {
+                if let Some(sink) = sink {
+                    let message = format!("正在抓取 {} 的文档", label);
+                    sink.send_progress(started.elapsed().as_secs_f64(), Some(budget.as_secs_f64()), &message);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_from_params() {
+        assert_eq!(SearchMode::from_params(&json!({})).unwrap(), SearchMode::Standard);
+        assert_eq!(SearchMode::from_params(&json!({"mode": "Quick"})).unwrap(), SearchMode::Quick);
+        assert_eq!(SearchMode::from_params(&json!({"mode": "deep"})).unwrap(), SearchMode::Deep);
+        assert!(SearchMode::from_params(&json!({"mode": "thorough"})).is_err());
+    }
+
+    #[test]
+    fn test_deep_budget_is_clamped() {
+        assert_eq!(deep_budget(&json!({})), Duration::from_secs(DEFAULT_DEEP_BUDGET_SECS));
+        assert_eq!(deep_budget(&json!({"budget_secs": "5"})), Duration::from_secs(5));
+        assert_eq!(deep_budget(&json!({"budget_secs": 0})), Duration::from_secs(1));
+        assert_eq!(deep_budget(&json!({"budget_secs": 100_000})), Duration::from_secs(MAX_DEEP_BUDGET_SECS));
+    }
+
+    #[test]
+    fn test_annotate_records_mode() {
+        let mut result = json!({"results": []});
+        annotate(&mut result, SearchMode::Quick, Instant::now(), false);
+        assert_eq!(result["search_mode"]["mode"], "quick");
+        assert_eq!(result["search_mode"]["complete"], false);
+    }
+}
@@ -0,0 +1,182 @@
+//! 需要登录的文档站点认证
+//!
+//! 内部文档门户通常要求登录。抓取网页时按请求地址的域名附加配置的 Cookie 和请求头
+//! （见 `system_config.toml` 的 `[site_auth]`），也可以用 [`register_hook`] 注册自定义的认证钩子
+//! （例如从单点登录服务换取短期令牌）。所有抓取文档页面的请求都应经过 [`SiteAuthenticated::site_auth`]。
+
+use reqwest::Url;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::config::{DomainAuthConfig, SiteAuthConfig, SystemConfig};
+
+/// 认证钩子：返回要附加到某个地址请求上的请求头
+pub trait SiteAuthHook: Send + Sync {
+    fn headers_for(&self, url: &Url) -> Vec<(String, String)>;
+}
+
+/// 按域名配置的 Cookie 和请求头
+#[derive(Debug, Clone, Default)]
+pub struct DomainAuthRules {
+    domains: Vec<DomainAuthConfig>,
+}
+
+impl DomainAuthRules {
+    pub fn new(config: &SiteAuthConfig) -> Self {
+        Self {
+            domains: config.domains.iter()
+                .filter(|domain| !domain.domain.trim().is_empty())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    fn rule_for(&self, url: &Url) -> Option<&DomainAuthConfig> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        // 多条规则匹配时取最具体（域名最长）的一条
+        self.domains.iter()
+            .filter(|rule| url.scheme() == "https" || (url.scheme() == "http" && rule.allow_insecure))
+            .filter(|rule| domain_matches(&rule.domain, &host))
+            .max_by_key(|rule| rule.domain.len())
+    }
+}
+
+impl SiteAuthHook for DomainAuthRules {
+    fn headers_for(&self, url: &Url) -> Vec<(String, String)> {
+        let Some(rule) = self.rule_for(url) else {
+            return Vec::new();
+        };
+        let mut headers: Vec<(String, String)> = rule.headers.iter()
+            .map(|(name, value)| (name.clone(), expand_env(value)))
+            .collect();
+        if !rule.cookies.is_empty() {
+            let cookie = rule.cookies.iter()
+                .map(|(name, value)| format!("{}={}", name, expand_env(value)))
+                .collect::<Vec<_>>()
+                .join("; ");
+            headers.push(("Cookie".to_string(), cookie));
+        }
+        headers
+    }
+}
+
+/// `host` 是否为 `domain` 或其子域名；`domain` 可写成 `*.example.com` 或 `.example.com`
+pub fn domain_matches(domain: &str, host: &str) -> bool {
+    let domain = domain.trim().trim_start_matches("*.").trim_start_matches('.').to_ascii_lowercase();
+    !domain.is_empty()
+        && (host == domain || host.strip_suffix(domain.as_str()).map_or(false, |prefix| prefix.ends_with('.')))
+}
+
+/// 把值中的 `${变量名}` 替换为环境变量，未设置的变量替换为空并记录警告
+fn expand_env(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let name = &rest[start + 2..start + 2 + len];
+        match std::env::var(name) {
+            Ok(var) => result.push_str(&var),
+            Err(_) => tracing::warn!("站点认证配置引用的环境变量 {} 未设置", name),
+        }
+        rest = &rest[start + 3 + len..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn hooks() -> &'static RwLock<Vec<Arc<dyn SiteAuthHook>>> {
+    static HOOKS: OnceLock<RwLock<Vec<Arc<dyn SiteAuthHook>>>> = OnceLock::new();
+    HOOKS.get_or_init(|| {
+        let rules = DomainAuthRules::new(&SystemConfig::load().site_auth);
+        let mut hooks: Vec<Arc<dyn SiteAuthHook>> = Vec::new();
+        if !rules.is_empty() {
+            tracing::info!("已为 {} 个文档站点配置认证信息", rules.domains.len());
+            hooks.push(Arc::new(rules));
+        }
+        RwLock::new(hooks)
+    })
+}
+
+/// 注册自定义认证钩子，在配置的域名规则之后应用（同名请求头以后注册的为准）
+pub fn register_hook(hook: Arc<dyn SiteAuthHook>) {
+    hooks().write().unwrap().push(hook);
+}
+
+/// 某个地址的请求需要附加的请求头，地址无法解析时为空
+pub fn headers_for(url: &str) -> Vec<(String, String)> {
+    let Ok(url) = Url::parse(url) else {
+        return Vec::new();
+    };
+    hooks().read().unwrap().iter().flat_map(|hook| hook.headers_for(&url)).collect()
+}
+
+/// 给抓取文档页面的请求附加站点认证信息
+pub trait SiteAuthenticated {
+    fn site_auth(self, url: &str) -> Self;
+}
+
+impl SiteAuthenticated for reqwest::RequestBuilder {
+    fn site_auth(self, url: &str) -> Self {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in headers_for(url) {
+            match (reqwest::header::HeaderName::from_bytes(name.as_bytes()), reqwest::header::HeaderValue::from_str(&value)) {
+                (Ok(name), Ok(mut value)) => {
+                    value.set_sensitive(true);
+                    headers.insert(name, value);
+                }
+                _ => tracing::warn!("忽略无效的站点认证请求头: {}", name),
+            }
+        }
+        if headers.is_empty() {
+            self
+        } else {
+            self.headers(headers)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_domain_rules() {
+        assert!(domain_matches("docs.example.com", "docs.example.com"));
+        assert!(domain_matches("*.example.com", "api.docs.example.com"));
+        assert!(!domain_matches("example.com", "badexample.com"));
+
+        std::env::set_var("GRAPE_TEST_DOCS_SESSION", "s3cret");
+        let rules = DomainAuthRules::new(&SiteAuthConfig {
+            domains: vec![
+                DomainAuthConfig {
+                    domain: "example.com".to_string(),
+                    headers: BTreeMap::from([("X-Team".to_string(), "docs".to_string())]),
+                    ..DomainAuthConfig::default()
+                },
+                DomainAuthConfig {
+                    domain: "docs.example.com".to_string(),
+                    cookies: BTreeMap::from([
+                        ("session".to_string(), "${GRAPE_TEST_DOCS_SESSION}".to_string()),
+                        ("theme".to_string(), "dark".to_string()),
+                    ]),
+                    ..DomainAuthConfig::default()
+                },
+            ],
+        });
+
+        let headers = rules.headers_for(&Url::parse("https://api.docs.example.com/guide").unwrap());
+        assert_eq!(headers, vec![("Cookie".to_string(), "session=s3cret; theme=dark".to_string())]);
+        let headers = rules.headers_for(&Url::parse("https://www.example.com/").unwrap());
+        assert_eq!(headers, vec![("X-Team".to_string(), "docs".to_string())]);
+        // 默认不通过明文 http 发送凭据
+        assert!(rules.headers_for(&Url::parse("http://docs.example.com/").unwrap()).is_empty());
+        assert!(rules.headers_for(&Url::parse("https://docs.rs/").unwrap()).is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! 向量库快照
+//!
+//! 快照是单个 gzip 压缩的 JSON 文件，包含每个层级和集合的文档（全文和嵌入向量）以及已处理包版本标记，
+//! 可以在一台机器上预先构建文档缓存，分发到 CI 机器后直接恢复。向量与生成它的嵌入模型绑定，
+//! 只能恢复到使用相同模型的向量库中。
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// 快照格式版本
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// 快照文件的扩展名
+pub const SNAPSHOT_EXTENSION: &str = "snapshot.gz";
+
+/// 数据目录下存放快照的子目录
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// 非默认集合在快照中的存储名前缀
+pub const COLLECTION_STORE_PREFIX: &str = "collections/";
+
+/// 快照清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub embedding_model: String,
+    pub document_count: usize,
+}
+
+/// 一个存储（层级或集合）的快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreSnapshot<D> {
+    /// 层级名（`global` / `workspace`）或 `collections/<集合名>`
+    pub name: String,
+    pub documents: Vec<D>,
+    #[serde(default)]
+    pub processed_package_versions: BTreeSet<String>,
+}
+
+/// 向量库快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot<D> {
+    pub manifest: SnapshotManifest,
+    pub stores: Vec<StoreSnapshot<D>>,
+}
+
+/// 快照创建或恢复结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotReport {
+    pub path: String,
+    pub embedding_model: String,
+    pub stores: Vec<String>,
+    pub documents: usize,
+    pub processed_package_versions: usize,
+    /// 快照文件大小
+    pub bytes: u64,
+}
+
+impl<D: Serialize + DeserializeOwned> Snapshot<D> {
+    pub fn new(embedding_model: &str, stores: Vec<StoreSnapshot<D>>) -> Self {
+        Self {
+            manifest: SnapshotManifest {
+                format_version: SNAPSHOT_FORMAT_VERSION,
+                created_at: Utc::now(),
+                embedding_model: embedding_model.to_string(),
+                document_count: stores.iter().map(|store| store.documents.len()).sum(),
+            },
+            stores,
+        }
+    }
+
+    /// 写入快照文件（先写临时文件再改名），返回文件大小
+    pub fn write(&self, path: &Path) -> Result<u64> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        let file = std::fs::File::create(&tmp).map_err(|e| anyhow!("创建快照文件 {} 失败: {}", tmp.display(), e))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.finish()?.flush()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(std::fs::metadata(path)?.len())
+    }
+
+    /// 读取并校验快照文件
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path).map_err(|e| anyhow!("读取快照 {} 失败: {}", path.display(), e))?;
+        let snapshot: Self = serde_json::from_reader(GzDecoder::new(BufReader::new(file)))
+            .map_err(|e| anyhow!("快照格式无效: {} - {}", path.display(), e))?;
+        if snapshot.manifest.format_version > SNAPSHOT_FORMAT_VERSION {
+            return Err(anyhow!(
+                "不支持的快照格式版本: {} (当前支持 {})",
+                snapshot.manifest.format_version, SNAPSHOT_FORMAT_VERSION
+            ));
+        }
+        let document_count: usize = snapshot.stores.iter().map(|store| store.documents.len()).sum();
+        if document_count != snapshot.manifest.document_count {
+            return Err(anyhow!(
+                "快照文档数量不一致: 清单 {}，实际 {}",
+                snapshot.manifest.document_count, document_count
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn ensure_compatible(&self, embedding_model: &str) -> Result<()> {
+        if self.manifest.embedding_model != embedding_model {
+            return Err(anyhow!(
+                "快照使用的嵌入模型 {} 与当前模型 {} 不一致，无法恢复",
+                self.manifest.embedding_model, embedding_model
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn report(&self, path: &Path, bytes: u64) -> SnapshotReport {
+        SnapshotReport {
+            path: path.display().to_string(),
+            embedding_model: self.manifest.embedding_model.clone(),
+            stores: self.stores.iter().map(|store| store.name.clone()).collect(),
+            documents: self.manifest.document_count,
+            processed_package_versions: self.stores.iter().map(|store| store.processed_package_versions.len()).sum(),
+            bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(format!("docs.{}", SNAPSHOT_EXTENSION));
+        let snapshot = Snapshot::new("mock-embedding", vec![StoreSnapshot {
+            name: "global".to_string(),
+            documents: vec![vec![0.5f32, 1.0], vec![0.25, 0.0]],
+            processed_package_versions: BTreeSet::from(["rust/serde/1.0.0".to_string()]),
+        }]);
+        let bytes = snapshot.write(&path).unwrap();
+        assert!(bytes > 0);
+
+        let loaded = Snapshot::<Vec<f32>>::read(&path).unwrap();
+        assert_eq!(loaded.stores[0].documents, vec![vec![0.5f32, 1.0], vec![0.25, 0.0]]);
+        let report = loaded.report(&path, bytes);
+        assert_eq!((report.documents, report.processed_package_versions), (2, 1));
+        assert!(loaded.ensure_compatible("mock-embedding").is_ok());
+        assert!(loaded.ensure_compatible("text-embedding-3-small").is_err());
+
+        std::fs::write(&path, b"not a snapshot").unwrap();
+        assert!(Snapshot::<Vec<f32>>::read(&path).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use anyhow::Result;
+
+use crate::errors::MCPError;
+use crate::mcp::auth::AuthScope;
+use crate::tools::base::{MCPTool, Schema, SchemaObject, SchemaString};
+use crate::tools::snapshot::SNAPSHOT_EXTENSION;
+use crate::tools::vector_docs_tool::{is_valid_collection_name, VectorDocsTool};
+
+/// 创建、恢复和列出向量库快照的管理工具
+///
+/// 快照只能读写数据目录下的 `snapshots` 目录，按名称引用；需要任意路径时使用 `snapshot` 命令行子命令。
+pub struct VectorSnapshotTool {
+    vector_tool: Arc<VectorDocsTool>,
+    schema: Schema,
+}
+
+impl VectorSnapshotTool {
+    pub fn new(vector_tool: Arc<VectorDocsTool>) -> Self {
+        Self {
+            vector_tool,
+            schema: Self::create_schema(),
+        }
+    }
+
+    fn create_schema() -> Schema {
+        let mut props = HashMap::new();
+        props.insert("action".to_string(), Schema::String(SchemaString {
+            description: Some("操作类型".to_string()),
+            enum_values: Some(vec!["create".to_string(), "restore".to_string(), "list".to_string()]),
+        }));
+        props.insert("name".to_string(), Schema::String(SchemaString {
+            description: Some("快照名称（字母、数字、- 和 _），create 时默认使用当前时间".to_string()),
+            enum_values: None,
+        }));
+        Schema::Object(SchemaObject {
+            required: vec!["action".to_string()],
+            properties: props,
+            description: Some("向量库快照的创建、恢复和列出".to_string()),
+        })
+    }
+
+    fn snapshot_path(&self, name: &str) -> Result<PathBuf> {
+        if !is_valid_collection_name(name) {
+            return Err(MCPError::InvalidParameter(format!("无效的快照名称: {:?}（只允许字母、数字、- 和 _，最长 64 个字符）", name)).into());
+        }
+        Ok(self.vector_tool.snapshots_dir().join(format!("{}.{}", name, SNAPSHOT_EXTENSION)))
+    }
+
+    fn list_snapshots(&self) -> Result<Vec<Value>> {
+        let dir = self.vector_tool.snapshots_dir();
+        let mut snapshots = Vec::new();
+        if dir.is_dir() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let Some(name) = file_name.strip_suffix(&format!(".{}", SNAPSHOT_EXTENSION)) else {
+                    continue;
+                };
+                let metadata = entry.metadata()?;
+                let modified: Option<chrono::DateTime<chrono::Utc>> = metadata.modified().ok().map(Into::into);
+                snapshots.push(json!({
+                    "name": name,
+                    "bytes": metadata.len(),
+                    "modified_at": modified.map(|m| m.to_rfc3339()),
+                }));
+            }
+        }
+        snapshots.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        Ok(snapshots)
+    }
+}
+
+#[async_trait]
+impl MCPTool for VectorSnapshotTool {
+    fn name(&self) -> &str {
+        "vector_snapshot"
+    }
+
+    fn description(&self) -> &str {
+        "管理工具：把所有层级和集合的文档、向量和已处理包版本标记保存为单个快照文件（create），从快照替换恢复（restore），或列出已有快照（list）。"
+    }
+
+    fn parameters_schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn mutating_actions(&self) -> &[&'static str] {
+        &["create", "restore"]
+    }
+
+    fn required_scope(&self, _params: &Value) -> AuthScope {
+        AuthScope::Admin
+    }
+
+    async fn execute(&self, params: Value) -> Result<Value> {
+        let action = params["action"].as_str()
+            .ok_or_else(|| MCPError::InvalidParameter("缺少action参数".to_string()))?;
+        let name = params["name"].as_str().map(str::trim).filter(|s| !s.is_empty());
+
+        match action {
+            "list" => {
+                let snapshots = self.list_snapshots()?;
+                Ok(json!({ "count": snapshots.len(), "snapshots": snapshots }))
+            }
+            "create" => {
+                let name = name.map(str::to_string)
+                    .unwrap_or_else(|| chrono::Utc::now().format("snapshot-%Y%m%d-%H%M%S").to_string());
+                let path = self.snapshot_path(&name)?;
+                let vector_tool = Arc::clone(&self.vector_tool);
+                let report = tokio::task::spawn_blocking(move || vector_tool.create_snapshot(&path)).await??;
+                Ok(json!({ "status": "success", "name": name, "snapshot": report }))
+            }
+            "restore" => {
+                let name = name.ok_or_else(|| MCPError::InvalidParameter("restore操作需要name参数".to_string()))?;
+                let path = self.snapshot_path(name)?;
+                if !path.is_file() {
+                    return Err(MCPError::NotFound(format!("快照 {} 不存在", name)).into());
+                }
+                let vector_tool = Arc::clone(&self.vector_tool);
+                let report = tokio::task::spawn_blocking(move || vector_tool.restore_snapshot(&path)).await??;
+                Ok(json!({ "status": "success", "name": name, "snapshot": report }))
+            }
+            other => Err(MCPError::InvalidParameter(format!("未知的操作: {}", other)).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::embedder::testing::MockEmbedder;
+
+    #[tokio::test]
+    async fn test_snapshot_restores_documents_collections_and_markers() {
+        let dir = tempfile::tempdir().unwrap();
+        let vector_tool = Arc::new(
+            VectorDocsTool::open_local(dir.path().join("data")).unwrap().with_embedder(Arc::new(MockEmbedder::new(32))),
+        );
+        let tool = VectorSnapshotTool::new(Arc::clone(&vector_tool));
+        assert_eq!(tool.required_scope(&Value::Null), AuthScope::Admin);
+
+        let stored = vector_tool.execute(json!({
+            "action": "store", "title": "serde", "content": "Serialize 把数据结构序列化。",
+            "language": "rust", "package_name": "serde", "version": "1.0.0"
+        })).await.unwrap();
+        vector_tool.mark_package_version_as_processed("rust", "serde", "1.0.0").unwrap();
+        vector_tool.execute(json!({ "action": "create_collection", "collection": "team" })).await.unwrap();
+        vector_tool.execute(json!({ "action": "store", "collection": "team", "id": "team-1", "content": "团队内部约定" })).await.unwrap();
+
+        let created = tool.execute(json!({ "action": "create", "name": "ci-cache" })).await.unwrap();
+        assert_eq!(created["snapshot"]["documents"], 2);
+        assert_eq!(tool.execute(json!({ "action": "list" })).await.unwrap()["snapshots"][0]["name"], "ci-cache");
+
+        // 快照之后的写入在恢复时被替换
+        vector_tool.execute(json!({ "action": "delete", "id": stored["document_id"] })).await.unwrap();
+        vector_tool.execute(json!({ "action": "store", "id": "later", "content": "快照之后写入的文档" })).await.unwrap();
+
+        let restored = tool.execute(json!({ "action": "restore", "name": "ci-cache" })).await.unwrap();
+        assert_eq!(restored["snapshot"]["processed_package_versions"], 1);
+        assert_eq!(vector_tool.execute(json!({ "action": "get", "id": stored["document_id"] })).await.unwrap()["status"], "success");
+        assert_eq!(vector_tool.execute(json!({ "action": "get", "id": "later" })).await.unwrap()["status"], "not_found");
+        assert_eq!(
+            vector_tool.execute(json!({ "action": "get", "collection": "team", "id": "team-1" })).await.unwrap()["status"],
+            "success"
+        );
+        assert!(vector_tool.has_processed_package_version("rust", "serde", "1.0.0").unwrap());
+
+        assert!(tool.execute(json!({ "action": "restore", "name": "../etc" })).await.is_err());
+        assert!(tool.execute(json!({ "action": "restore", "name": "missing" })).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_server_rejects_create_and_restore() {
+        use crate::config::ToolExecutionConfig;
+        use crate::mcp::server::MCPServer;
+
+        let dir = tempfile::tempdir().unwrap();
+        let vector_tool = Arc::new(
+            VectorDocsTool::open_local(dir.path().join("data")).unwrap().with_embedder(Arc::new(MockEmbedder::new(32))),
+        );
+        let server = MCPServer::with_limits(ToolExecutionConfig { read_only: true, ..ToolExecutionConfig::default() });
+        server.register_tool(Box::new(VectorSnapshotTool::new(vector_tool))).await.unwrap();
+
+        let info = server.get_tool_info("vector_snapshot").await.unwrap().unwrap();
+        let advertised = info.parameters.to_string();
+        assert!(advertised.contains("list"));
+        assert!(!advertised.contains("restore") && !advertised.contains("\"create\""));
+
+        assert!(server.execute_tool("vector_snapshot", json!({ "action": "list" })).await.is_ok());
+        for action in ["create", "restore"] {
+            let err = server.execute_tool("vector_snapshot", json!({ "action": action, "name": "ci-cache" })).await.unwrap_err();
+            assert!(matches!(crate::errors::find_mcp_error(&err), Some(MCPError::AuthorizationError(_))), "{}", action);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+//! SQLite 文档元数据索引（`database` feature）
+//!
+//! 向量仍保存在 HNSW 索引中，这里只保存每个文档的语言、包名、版本、文档类型和入库时间。
+//! 过滤搜索的候选不足、需要精确扫描时，先把过滤条件翻译成 SQL 取得候选文档ID，
+//! 只对候选计算距离，不再逐条匹配全部记录。
+//!
+//! SQL 只负责粗筛：版本条件按解析出的主/次/修订号比较，无法解析的版本一律保留；
+//! 自然语言、代码语言和命名空间不下推。候选最终仍由 [`SearchFilter::matches`] 精确判断。
+
+use anyhow::Result;
+use chrono::SecondsFormat;
+use rusqlite::{params, params_from_iter, types::Value as SqlValue, Connection};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::mcp::namespace::NAMESPACE_METADATA_KEY;
+use crate::tools::search_filter::{lenient_version, parse_timestamp, wildcard_prefix, SearchFilter, CREATED_AT_METADATA_KEY};
+use crate::tools::vector_docs_tool::DocumentRecord;
+
+/// 元数据数据库文件名（位于向量数据目录下）
+pub const METADATA_DB_FILE: &str = "metadata.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS documents (
+    id TEXT PRIMARY KEY,
+    language TEXT NOT NULL,
+    package_name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    version_major INTEGER,
+    version_minor INTEGER,
+    version_patch INTEGER,
+    doc_type TEXT NOT NULL,
+    namespace TEXT,
+    created_at TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_documents_package ON documents(language, package_name, version);
+CREATE INDEX IF NOT EXISTS idx_documents_semver ON documents(version_major, version_minor, version_patch);
+CREATE INDEX IF NOT EXISTS idx_documents_created_at ON documents(created_at);
+";
+
+/// 文档元数据的 SQLite 索引
+pub struct SqliteMetadataIndex {
+    conn: Connection,
+    /// 数据库文件，内存数据库为 None
+    path: Option<PathBuf>,
+    /// 已写入数据库的文档ID（文档只增删不修改，按ID同步即可）
+    synced: HashSet<String>,
+}
+
+impl std::fmt::Debug for SqliteMetadataIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteMetadataIndex")
+            .field("path", &self.path)
+            .field("documents", &self.synced.len())
+            .finish()
+    }
+}
+
+impl SqliteMetadataIndex {
+    /// 打开数据目录下的元数据数据库；只读实例不写共享目录，使用内存数据库
+    pub fn open(data_dir: &Path, read_only: bool) -> Result<Self> {
+        if read_only {
+            return Self::in_memory();
+        }
+        let path = data_dir.join(METADATA_DB_FILE);
+        let conn = Connection::open(&path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::init(conn, Some(path))
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?, None)
+    }
+
+    fn init(conn: Connection, path: Option<PathBuf>) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        let synced = {
+            let mut statement = conn.prepare("SELECT id FROM documents")?;
+            let ids = statement.query_map([], |row| row.get::<_, String>(0))?;
+            ids.collect::<rusqlite::Result<HashSet<String>>>()?
+        };
+        Ok(Self { conn, path, synced })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.synced.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.synced.is_empty()
+    }
+
+    /// 与内存中的文档同步：写入新文档，删除已不存在的文档
+    pub fn sync(&mut self, documents: &HashMap<String, DocumentRecord>) -> Result<()> {
+        let stale: Vec<String> = self.synced.iter().filter(|id| !documents.contains_key(*id)).cloned().collect();
+        let added: Vec<&DocumentRecord> = documents.values().filter(|doc| !self.synced.contains(&doc.id)).collect();
+        if stale.is_empty() && added.is_empty() {
+            return Ok(());
+        }
+
+        let tx = self.conn.transaction()?;
+        {
+            let mut delete = tx.prepare_cached("DELETE FROM documents WHERE id = ?1")?;
+            for id in &stale {
+                delete.execute(params![id])?;
+            }
+            let mut insert = tx.prepare_cached(
+                "INSERT OR REPLACE INTO documents
+                 (id, language, package_name, version, version_major, version_minor, version_patch, doc_type, namespace, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            for doc in &added {
+                let semver = lenient_version(&doc.version);
+                let created_at = doc
+                    .metadata
+                    .get(CREATED_AT_METADATA_KEY)
+                    .and_then(|value| parse_timestamp(value))
+                    .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true));
+                insert.execute(params![
+                    doc.id,
+                    doc.language,
+                    doc.package_name,
+                    doc.version,
+                    semver.as_ref().map(|v| v.major as i64),
+                    semver.as_ref().map(|v| v.minor as i64),
+                    semver.as_ref().map(|v| v.patch as i64),
+                    doc.doc_type,
+                    doc.metadata.get(NAMESPACE_METADATA_KEY),
+                    created_at,
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        for id in &stale {
+            self.synced.remove(id);
+        }
+        self.synced.extend(added.into_iter().map(|doc| doc.id.clone()));
+        Ok(())
+    }
+
+    /// 用 SQL 筛选可能满足条件的文档ID；过滤条件中没有可下推的部分时返回 None
+    pub fn candidate_ids(&self, filter: &SearchFilter) -> Result<Option<HashSet<String>>> {
+        let Some((clause, values)) = where_clause(filter) else {
+            return Ok(None);
+        };
+        let mut statement = self.conn.prepare(&format!("SELECT id FROM documents WHERE {}", clause))?;
+        let ids = statement.query_map(params_from_iter(values), |row| row.get::<_, String>(0))?;
+        Ok(Some(ids.collect::<rusqlite::Result<HashSet<String>>>()?))
+    }
+}
+
+/// 把过滤条件翻译为 WHERE 子句和参数
+fn where_clause(filter: &SearchFilter) -> Option<(String, Vec<SqlValue>)> {
+    let mut conditions: Vec<String> = Vec::new();
+    let mut values: Vec<SqlValue> = Vec::new();
+
+    if let Some(language) = &filter.language {
+        conditions.push("language = ? COLLATE NOCASE".to_string());
+        values.push(SqlValue::Text(language.clone()));
+    }
+    if let Some(package_name) = &filter.package_name {
+        conditions.push("package_name = ?".to_string());
+        values.push(SqlValue::Text(package_name.clone()));
+    }
+    if let Some(pattern) = &filter.package_pattern {
+        conditions.push("package_name LIKE ? ESCAPE '\\'".to_string());
+        values.push(SqlValue::Text(glob_to_like(pattern)));
+    }
+    if let Some(doc_type) = &filter.doc_type {
+        conditions.push("doc_type = ?".to_string());
+        values.push(SqlValue::Text(doc_type.clone()));
+    }
+    if let Some(after) = filter.created_after {
+        conditions.push("created_at >= ?".to_string());
+        values.push(SqlValue::Text(after.to_rfc3339_opts(SecondsFormat::Millis, true)));
+    }
+    if let Some(before) = filter.created_before {
+        conditions.push("created_at <= ?".to_string());
+        values.push(SqlValue::Text(before.to_rfc3339_opts(SecondsFormat::Millis, true)));
+    }
+    if let Some((condition, version_values)) = filter.version.as_deref().and_then(version_condition) {
+        conditions.push(condition);
+        values.extend(version_values);
+    }
+
+    (!conditions.is_empty()).then(|| (conditions.join(" AND "), values))
+}
+
+/// `*`/`?` 通配转为 LIKE 模式，转义 LIKE 自身的特殊字符
+fn glob_to_like(pattern: &str) -> String {
+    let mut like = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        match c {
+            '*' => like.push('%'),
+            '?' => like.push('_'),
+            '%' | '_' | '\\' => {
+                like.push('\\');
+                like.push(c);
+            }
+            _ => like.push(c),
+        }
+    }
+    like
+}
+
+type SemverTuple = (u64, u64, u64);
+
+/// 版本区间的一端
+enum Bound {
+    AtLeast(SemverTuple),
+    Above(SemverTuple),
+    Below(SemverTuple),
+    AtMost(SemverTuple),
+}
+
+impl Bound {
+    fn sql(&self) -> (&'static str, SemverTuple) {
+        match self {
+            Bound::AtLeast(v) => (">=", *v),
+            Bound::Above(v) => (">", *v),
+            Bound::Below(v) => ("<", *v),
+            Bound::AtMost(v) => ("<=", *v),
+        }
+    }
+}
+
+/// 版本条件的 SQL 粗筛；`*` 等不限制版本的写法返回 None
+fn version_condition(pattern: &str) -> Option<(String, Vec<SqlValue>)> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() || pattern == "*" {
+        return None;
+    }
+    let bounds = if let Some(prefix) = wildcard_prefix(pattern) {
+        let parts: Vec<u64> = prefix.iter().filter_map(|part| part.parse().ok()).collect();
+        match parts.as_slice() {
+            [major] => vec![Bound::AtLeast((*major, 0, 0)), Bound::Below((major + 1, 0, 0))],
+            [major, minor, ..] => vec![Bound::AtLeast((*major, *minor, 0)), Bound::Below((*major, minor + 1, 0))],
+            [] => return None,
+        }
+    } else if pattern.starts_with(|c| matches!(c, '^' | '~' | '>' | '<' | '=')) {
+        let req = semver::VersionReq::parse(pattern).ok()?;
+        req.comparators.iter().flat_map(comparator_bounds).collect()
+    } else {
+        return Some(("version = ?".to_string(), vec![SqlValue::Text(pattern.to_string())]));
+    };
+    if bounds.is_empty() {
+        return None;
+    }
+
+    let mut values = Vec::new();
+    let conditions: Vec<String> = bounds
+        .iter()
+        .map(|bound| {
+            let (op, (major, minor, patch)) = bound.sql();
+            values.extend([major, minor, patch].map(|n| SqlValue::Integer(n as i64)));
+            format!("(version_major, version_minor, version_patch) {} (?, ?, ?)", op)
+        })
+        .collect();
+    // 无法解析的版本号不在 SQL 中判断，交给精确匹配
+    Some((format!("(version_major IS NULL OR ({}))", conditions.join(" AND ")), values))
+}
+
+/// semver 比较符对应的版本区间（预发布版本按所属版本号处理，只会多选不会漏选）
+fn comparator_bounds(comparator: &semver::Comparator) -> Vec<Bound> {
+    use semver::Op;
+    let (major, minor, patch) = (comparator.major, comparator.minor, comparator.patch);
+    let lower = (major, minor.unwrap_or(0), patch.unwrap_or(0));
+    // 省略的最低位之后的下一个版本，如 `1` -> 2.0.0，`1.2` -> 1.3.0
+    let next = match (minor, patch) {
+        (None, _) => (major + 1, 0, 0),
+        (Some(minor), None) => (major, minor + 1, 0),
+        (Some(minor), Some(patch)) => (major, minor, patch + 1),
+    };
+    match comparator.op {
+        Op::Exact | Op::Wildcard => vec![Bound::AtLeast(lower), Bound::Below(next)],
+        Op::Greater if patch.is_some() => vec![Bound::Above(lower)],
+        Op::Greater => vec![Bound::AtLeast(next)],
+        Op::GreaterEq => vec![Bound::AtLeast(lower)],
+        Op::Less => vec![Bound::Below(lower)],
+        Op::LessEq if patch.is_some() => vec![Bound::AtMost(lower)],
+        Op::LessEq => vec![Bound::Below(next)],
+        Op::Tilde => match minor {
+            None => vec![Bound::AtLeast(lower), Bound::Below((major + 1, 0, 0))],
+            Some(minor) => vec![Bound::AtLeast(lower), Bound::Below((major, minor + 1, 0))],
+        },
+        Op::Caret => {
+            let upper = match (major, minor, patch) {
+                (0, Some(0), Some(patch)) => (0, 0, patch + 1),
+                (0, Some(minor), _) => (0, minor + 1, 0),
+                _ => (major + 1, 0, 0),
+            };
+            vec![Bound::AtLeast(lower), Bound::Below(upper)]
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(id: &str, package_name: &str, version: &str, created_at: &str) -> DocumentRecord {
+        DocumentRecord {
+            id: id.to_string(),
+            content: String::new(),
+            title: id.to_string(),
+            language: "rust".to_string(),
+            package_name: package_name.to_string(),
+            version: version.to_string(),
+            doc_type: "api".to_string(),
+            metadata: HashMap::from([(CREATED_AT_METADATA_KEY.to_string(), created_at.to_string())]),
+            embedding: Vec::new(),
+        }
+    }
+
+    fn ids(index: &SqliteMetadataIndex, filter: &SearchFilter) -> Vec<String> {
+        let mut ids: Vec<String> = index.candidate_ids(filter).unwrap().unwrap().into_iter().collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_filters_are_pushed_down_as_sql() {
+        let mut index = SqliteMetadataIndex::in_memory().unwrap();
+        let mut documents: HashMap<String, DocumentRecord> = [
+            doc("a", "tokio", "1.38.0", "2024-05-01T00:00:00Z"),
+            doc("b", "tokio", "0.2.25", "2023-01-01T00:00:00Z"),
+            doc("c", "tokio-util", "0.7.10", "2024-06-01T00:00:00Z"),
+            doc("d", "serde", "nightly-2024", "2024-06-01T00:00:00Z"),
+        ]
+        .into_iter()
+        .map(|doc| (doc.id.clone(), doc))
+        .collect();
+        index.sync(&documents).unwrap();
+        assert_eq!(index.len(), 4);
+
+        assert!(index.candidate_ids(&SearchFilter::new()).unwrap().is_none());
+        assert_eq!(ids(&index, &SearchFilter::new().package_pattern("tokio*")), vec!["a", "b", "c"]);
+        assert_eq!(ids(&index, &SearchFilter::new().language("Rust").version("^1.2")), vec!["a", "d"]);
+        assert_eq!(ids(&index, &SearchFilter::new().package_name("tokio").version("0.x")), vec!["b"]);
+        assert_eq!(ids(&index, &SearchFilter::new().version(">=0.3, <1")), vec!["c", "d"]);
+
+        let after = parse_timestamp("2024-05-15T00:00:00+08:00");
+        assert_eq!(ids(&index, &SearchFilter::new().created_between(after, None)), vec!["c", "d"]);
+
+        documents.remove("a");
+        index.sync(&documents).unwrap();
+        assert_eq!(ids(&index, &SearchFilter::new().package_name("tokio")), vec!["b"]);
+    }
+
+    #[test]
+    fn test_glob_to_like_escapes_wildcards() {
+        assert_eq!(glob_to_like("tokio-*"), "tokio-%");
+        assert_eq!(glob_to_like("my_pkg?"), "my\\_pkg_");
+    }
+}
@@ -0,0 +1,239 @@
+//! 符号精确匹配索引
+//!
+//! 从文档片段中提取符号（标题中的 API 名称、代码中声明的函数/类型/trait 等），
+//! 建立 符号 -> 片段 的索引。查询明显是标识符（如 `tokio::select!`、`DataFrame.merge`）时，
+//! 先按符号精确匹配返回定义页面，再用语义搜索补足。
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// 符号来源，值越大越可能是定义页面
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SymbolSource {
+    /// 代码中的声明（`fn`、`class`、`def` 等）
+    Declaration,
+    /// 文档标题或一级/二级标题中的 API 名称
+    Heading,
+}
+
+/// 一次符号命中
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolHit {
+    pub doc_id: String,
+    /// 查询与片段中的完整符号路径一致（而不只是后缀一致）
+    pub exact: bool,
+    pub source: SymbolSource,
+}
+
+#[derive(Debug, Clone)]
+struct SymbolEntry {
+    doc_id: String,
+    /// 归一化后的完整路径
+    path: String,
+    source: SymbolSource,
+}
+
+/// 符号 -> 片段 索引
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    /// 归一化的符号路径（含各级后缀） -> 条目
+    entries: HashMap<String, Vec<SymbolEntry>>,
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("内置正则无效"))
+}
+
+/// 把符号归一化为小写的路径段：去掉反引号、参数列表和宏的 `!`，`::`、`.`、`#` 都视为分隔符
+pub fn normalize_symbol(symbol: &str) -> Option<Vec<String>> {
+    static SEGMENT: OnceLock<Regex> = OnceLock::new();
+    let mut symbol = symbol.trim().trim_matches('`');
+    if let Some(paren) = symbol.find('(') {
+        symbol = &symbol[..paren];
+    }
+    let symbol = symbol.trim_end_matches('!');
+    if symbol.is_empty() {
+        return None;
+    }
+    let segments: Vec<String> = symbol
+        .split("::")
+        .flat_map(|part| part.split(['.', '#']))
+        .map(str::to_lowercase)
+        .collect();
+    let segment = regex(&SEGMENT, r"^[a-z_$][a-z0-9_$]*$");
+    segments.iter().all(|s| segment.is_match(s)).then_some(segments)
+}
+
+/// 查询是否明显是一个标识符，是则返回归一化的符号路径
+///
+/// 单个普通单词（如 `tokio`）不算标识符；需要带路径分隔符、`!`/`()`、下划线或驼峰写法。
+pub fn identifier_query(query: &str) -> Option<String> {
+    let query = query.trim().trim_matches('`');
+    if query.is_empty() || query.contains(char::is_whitespace) {
+        return None;
+    }
+    let segments = normalize_symbol(query)?;
+    let looks_like_identifier = segments.len() > 1
+        || query.ends_with('!')
+        || query.ends_with(')')
+        || query.contains('_')
+        || query.chars().skip(1).any(|c| c.is_uppercase());
+    looks_like_identifier.then(|| segments.join("::"))
+}
+
+/// 从片段标题和内容中提取符号
+pub fn extract_symbols(title: &str, content: &str) -> Vec<(String, SymbolSource)> {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    static HEADING_SYMBOL: OnceLock<Regex> = OnceLock::new();
+    static DECLARATION: OnceLock<Regex> = OnceLock::new();
+    static MACRO_RULES: OnceLock<Regex> = OnceLock::new();
+
+    let heading_symbol = regex(
+        &HEADING_SYMBOL,
+        r"(?i)^(?:(?:struct|enum|trait|fn|function|method|macro|module|mod|class|type|attribute|property|constant|interface)\s+)?`?([A-Za-z_$][\w$]*(?:(?:::|\.|#)[A-Za-z_$][\w$]*)*!?)`?(?:\(.*\))?$",
+    );
+    let mut symbols = Vec::new();
+    let headings = std::iter::once(title).chain(
+        regex(&HEADING, r"(?m)^#{1,3}\s+(.+?)\s*$")
+            .captures_iter(content)
+            .filter_map(|c| c.get(1).map(|m| m.as_str())),
+    );
+    for heading in headings {
+        if let Some(symbol) = heading_symbol.captures(heading.trim()).and_then(|c| c.get(1)) {
+            symbols.push((symbol.as_str().to_string(), SymbolSource::Heading));
+        }
+    }
+
+    let declaration = regex(
+        &DECLARATION,
+        r"(?m)^\s*(?:(?:pub(?:\([^)]*\))?|export|public|private|protected|static|abstract|final|async|unsafe|default|declare)\s+)*(?:fn|struct|enum|trait|type|mod|union|def|class|interface|function|func)\s+(?:\([^)]*\)\s*)?([A-Za-z_$][\w$]*)",
+    );
+    for capture in declaration.captures_iter(content) {
+        symbols.push((capture[1].to_string(), SymbolSource::Declaration));
+    }
+    for capture in regex(&MACRO_RULES, r"macro_rules!\s*([A-Za-z_]\w*)").captures_iter(content) {
+        symbols.push((format!("{}!", &capture[1]), SymbolSource::Declaration));
+    }
+    symbols
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// 不同符号键的数量
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 索引一个片段；单段符号同时以 `包名::符号` 登记，便于带包名的查询命中
+    pub fn insert_document(&mut self, doc_id: &str, package_name: &str, title: &str, content: &str) {
+        let package = normalize_symbol(package_name).filter(|p| p.len() == 1).map(|mut p| p.remove(0));
+        for (symbol, source) in extract_symbols(title, content) {
+            let Some(mut segments) = normalize_symbol(&symbol) else {
+                continue;
+            };
+            if let Some(package) = &package {
+                if segments.len() == 1 && &segments[0] != package {
+                    segments.insert(0, package.clone());
+                }
+            }
+            let path = segments.join("::");
+            for start in 0..segments.len() {
+                let key = segments[start..].join("::");
+                let entries = self.entries.entry(key).or_default();
+                match entries.iter_mut().find(|e| e.doc_id == doc_id && e.path == path) {
+                    Some(existing) => existing.source = existing.source.max(source),
+                    None => entries.push(SymbolEntry { doc_id: doc_id.to_string(), path: path.clone(), source }),
+                }
+            }
+        }
+    }
+
+    /// 移除一个片段登记的所有符号
+    pub fn remove_document(&mut self, doc_id: &str) {
+        self.entries.retain(|_, entries| {
+            entries.retain(|e| e.doc_id != doc_id);
+            !entries.is_empty()
+        });
+    }
+
+    /// 查找符号，完整路径一致的排在前面，其次是标题中的定义；每个片段只返回一次
+    pub fn lookup(&self, symbol: &str) -> Vec<SymbolHit> {
+        let Some(key) = normalize_symbol(symbol).map(|s| s.join("::")) else {
+            return Vec::new();
+        };
+        let Some(entries) = self.entries.get(&key) else {
+            return Vec::new();
+        };
+        let mut hits: Vec<SymbolHit> = entries
+            .iter()
+            .map(|e| SymbolHit { doc_id: e.doc_id.clone(), exact: e.path == key, source: e.source })
+            .collect();
+        hits.sort_by(|a, b| (b.exact, b.source).cmp(&(a.exact, a.source)));
+        let mut seen = std::collections::HashSet::new();
+        hits.retain(|hit| seen.insert(hit.doc_id.clone()));
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_query_detection() {
+        assert_eq!(identifier_query("tokio::select!").as_deref(), Some("tokio::select"));
+        assert_eq!(identifier_query("DataFrame.merge").as_deref(), Some("dataframe::merge"));
+        assert_eq!(identifier_query("`serde_json::from_str()`").as_deref(), Some("serde_json::from_str"));
+        assert_eq!(identifier_query("useEffect").as_deref(), Some("useeffect"));
+        assert!(identifier_query("tokio").is_none());
+        assert!(identifier_query("how to merge dataframes").is_none());
+    }
+
+    #[test]
+    fn test_extract_symbols_from_headings_and_code() {
+        let content = "# Macro tokio::select\n\nWaits on multiple branches.\n\n```rust\npub async fn run() {}\nmacro_rules! my_macro { () => {} }\n```\n\n## Examples\n";
+        let symbols = extract_symbols("select", content);
+        assert!(symbols.contains(&("tokio::select".to_string(), SymbolSource::Heading)));
+        assert!(symbols.contains(&("run".to_string(), SymbolSource::Declaration)));
+        assert!(symbols.contains(&("my_macro!".to_string(), SymbolSource::Declaration)));
+    }
+
+    #[test]
+    fn test_lookup_prefers_exact_definition() {
+        let mut index = SymbolIndex::new();
+        index.insert_document("merge-page", "pandas", "pandas.DataFrame.merge", "Merge DataFrame objects.");
+        index.insert_document("join-page", "pandas", "DataFrame.join", "```python\ndef merge(left, right):\n    pass\n```");
+        index.insert_document("select-page", "tokio", "select!", "Waits on multiple concurrent branches.");
+
+        let hits = index.lookup("DataFrame.merge");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].doc_id, "merge-page");
+
+        let hits = index.lookup("merge");
+        assert_eq!(hits[0].doc_id, "merge-page");
+        assert_eq!(hits[0].source, SymbolSource::Heading);
+        assert_eq!(hits[1].doc_id, "join-page");
+
+        let hits = index.lookup("tokio::select!");
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].exact);
+
+        index.remove_document("merge-page");
+        let hits = index.lookup("merge");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].doc_id, "join-page");
+        assert!(index.lookup("DataFrame.merge").is_empty());
+    }
+}
@@ -1,5 +1,8 @@
 use anyhow::Result;
 use crate::tools::doc_processor::DocumentProcessor;
+use crate::tools::embedder::testing::MockEmbedder;
+use crate::tools::vector_docs_tool::VectorDocsTool;
+use std::sync::Arc;
 use crate::ai::intelligent_web_analyzer::{CrawlTask, ContentType};
 use crate::ai::task_oriented_crawler::TaskOrientedCrawler;
 use crate::ai::ai_service::{AIService, AIServiceConfig};
@@ -7,14 +10,20 @@ use crate::ai::smart_url_crawler::CrawlerConfig;
 use crate::ai::advanced_intelligent_crawler::AdvancedIntelligentCrawler;
 use chrono::Utc;
 
+/// 在临时目录中打开使用模拟嵌入服务的文档处理器，不读写用户目录下的全局缓存
+fn hermetic_processor(dir: &tempfile::TempDir) -> Result<DocumentProcessor> {
+    let vector_tool = VectorDocsTool::open_local(dir.path().to_path_buf())?.with_embedder(Arc::new(MockEmbedder::new(64)));
+    DocumentProcessor::with_vector_tool(vector_tool)
+}
+
 /// AI爬虫备用策略测试套件
 /// 测试当CLI工具不可用时，AI爬虫系统是否能正确生成文档
-
 #[tokio::test]
 async fn test_rust_syntax_query_with_ai_fallback() -> Result<()> {
     println!("🦀 测试Rust语法查询 - AI爬虫备用策略");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试Rust语法相关的查询
     let result = processor.process_documentation_request(
@@ -71,7 +80,8 @@ async fn test_rust_syntax_query_with_ai_fallback() -> Result<()> {
 async fn test_python_library_introduction_with_ai() -> Result<()> {
     println!("🐍 测试Python库简介 - AI爬虫生成");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试Python流行库的简介
     let result = processor.process_documentation_request(
@@ -125,7 +135,8 @@ async fn test_python_library_introduction_with_ai() -> Result<()> {
 async fn test_javascript_advanced_features_with_ai() -> Result<()> {
     println!("🟨 测试JavaScript高级特性 - AI智能爬虫");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试JavaScript高级特性查询
     let result = processor.process_documentation_request(
@@ -167,7 +178,8 @@ async fn test_javascript_advanced_features_with_ai() -> Result<()> {
 async fn test_go_concurrency_patterns_with_ai() -> Result<()> {
     println!("🐹 测试Go并发模式 - AI爬虫文档生成");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试Go并发相关的查询
     let result = processor.process_documentation_request(
@@ -210,7 +222,8 @@ async fn test_go_concurrency_patterns_with_ai() -> Result<()> {
 async fn test_java_spring_framework_with_ai() -> Result<()> {
     println!("☕ 测试Java Spring框架 - AI智能文档爬取");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试Java Spring框架的查询
     let result = processor.process_documentation_request(
@@ -253,7 +266,8 @@ async fn test_java_spring_framework_with_ai() -> Result<()> {
 async fn test_typescript_type_system_with_ai() -> Result<()> {
     println!("🔷 测试TypeScript类型系统 - AI爬虫分析");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试TypeScript类型系统的查询
     let result = processor.process_documentation_request(
@@ -372,7 +386,8 @@ async fn test_ai_crawler_task_oriented_approach() -> Result<()> {
 async fn test_multilingual_documentation_generation() -> Result<()> {
     println!("🌍 测试多语言文档生成综合能力");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试多种语言的库文档生成
     let test_cases = vec![
@@ -431,7 +446,8 @@ async fn test_multilingual_documentation_generation() -> Result<()> {
 async fn test_complex_query_scenarios() -> Result<()> {
     println!("🔍 测试复杂查询场景");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试复杂的、具体的查询场景
     let complex_queries = vec![
@@ -511,7 +527,8 @@ async fn test_complex_query_scenarios() -> Result<()> {
 async fn test_emergency_fallback_scenarios() -> Result<()> {
     println!("🚨 测试紧急备用场景");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试当主要方法都失败时的备用策略
     let emergency_queries = vec![
@@ -1,11 +1,21 @@
 use anyhow::Result;
 use crate::tools::doc_processor::DocumentProcessor;
+use crate::tools::embedder::testing::MockEmbedder;
+use crate::tools::vector_docs_tool::VectorDocsTool;
+use std::sync::Arc;
+
+/// 在临时目录中打开使用模拟嵌入服务的文档处理器，不读写用户目录下的全局缓存
+fn hermetic_processor(dir: &tempfile::TempDir) -> Result<DocumentProcessor> {
+    let vector_tool = VectorDocsTool::open_local(dir.path().to_path_buf())?.with_embedder(Arc::new(MockEmbedder::new(64)));
+    DocumentProcessor::with_vector_tool(vector_tool)
+}
 
 #[tokio::test]
 async fn test_doc_processor_creation() -> Result<()> {
     println!("🔧 测试DocumentProcessor创建");
     
-    let _processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let _processor = hermetic_processor(&dir)?;
     println!("✅ DocumentProcessor创建成功");
     
     Ok(())
@@ -15,7 +25,8 @@ async fn test_doc_processor_creation() -> Result<()> {
 async fn test_go_docs_generation() -> Result<()> {
     println!("🐹 测试Go文档生成");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试一个简单的Go包
     let result = processor.process_documentation_request(
@@ -53,7 +64,8 @@ async fn test_go_docs_generation() -> Result<()> {
 async fn test_python_docs_generation() -> Result<()> {
     println!("🐍 测试Python文档生成");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试一个简单的Python包
     let result = processor.process_documentation_request(
@@ -87,7 +99,8 @@ async fn test_python_docs_generation() -> Result<()> {
 async fn test_npm_docs_generation() -> Result<()> {
     println!("📦 测试NPM文档生成");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试一个简单的NPM包
     let result = processor.process_documentation_request(
@@ -124,7 +137,8 @@ async fn test_npm_docs_generation() -> Result<()> {
 async fn test_java_docs_generation() -> Result<()> {
     println!("☕ 测试Java文档生成");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试一个简单的Java库（使用Maven坐标）
     let result = processor.process_documentation_request(
@@ -159,7 +173,8 @@ async fn test_java_docs_generation() -> Result<()> {
 async fn test_rust_docs_generation() -> Result<()> {
     println!("🦀 测试Rust文档生成");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 测试一个简单的Rust crate
     let result = processor.process_documentation_request(
@@ -194,7 +209,8 @@ async fn test_rust_docs_generation() -> Result<()> {
 async fn test_vector_storage_and_search() -> Result<()> {
     println!("🔍 测试向量存储和搜索");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     // 第一次请求：生成并存储文档
     let result1 = processor.process_documentation_request(
@@ -239,7 +255,8 @@ async fn test_vector_storage_and_search() -> Result<()> {
 async fn test_unsupported_language() -> Result<()> {
     println!("❌ 测试不支持的语言");
     
-    let processor = DocumentProcessor::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let processor = hermetic_processor(&dir)?;
     
     let result = processor.process_documentation_request(
         "unsupported_language",
@@ -1,24 +1,23 @@
 use anyhow::Result;
 use serde_json::json;
 
+use std::sync::Arc;
+
 use crate::tools::vector_docs_tool::VectorDocsTool;
 use crate::tools::base::MCPTool;
+use crate::tools::embedder::testing::MockEmbedder;
+
+/// 在临时目录中打开使用模拟嵌入服务的工具，不需要 API 密钥和网络
+fn hermetic_tool(dir: &tempfile::TempDir, embedder: Arc<MockEmbedder>) -> Result<VectorDocsTool> {
+    Ok(VectorDocsTool::open_local(dir.path().to_path_buf())?.with_embedder(embedder))
+}
 
 #[tokio::test]
 async fn test_vector_docs_store_and_search() -> Result<()> {
-    // 加载环境变量
-    dotenv::dotenv().ok();
-    
-    // 注意：这个测试使用嵌入式instant-distance，不需要外部服务器
-    // 如果没有配置NVIDIA_API_KEY环境变量，测试可能会失败
-    
-    let tool = match VectorDocsTool::new() {
-        Ok(tool) => tool,
-        Err(e) => {
-            println!("⚠️ 跳过向量化测试：API不可用 - {}", e);
-            return Ok(());
-        }
-    };
+    // 嵌入式instant-distance + 模拟嵌入服务，不需要外部服务器
+    let dir = tempfile::TempDir::new()?;
+    let embedder = Arc::new(MockEmbedder::new(64));
+    let tool = hermetic_tool(&dir, embedder.clone())?;
     
     // 测试存储文档
     let store_params = json!({
@@ -65,23 +64,18 @@ async fn test_vector_docs_store_and_search() -> Result<()> {
     
     let delete_result = tool.execute(delete_params).await?;
     assert_eq!(delete_result["status"], "success");
+
+    // 存储和搜索各生成一次嵌入
+    assert_eq!(embedder.calls(), 2);
     
     Ok(())
 }
 
 #[tokio::test]
 async fn test_vector_docs_persistence() -> Result<()> {
-    // 加载环境变量
-    dotenv::dotenv().ok();
-    
     // 测试数据持久化功能
-    let tool1 = match VectorDocsTool::new() {
-        Ok(tool) => tool,
-        Err(e) => {
-            println!("⚠️ 跳过持久化测试：API不可用 - {}", e);
-            return Ok(());
-        }
-    };
+    let dir = tempfile::TempDir::new()?;
+    let tool1 = hermetic_tool(&dir, Arc::new(MockEmbedder::new(64)))?;
     
     // 存储一个文档
     let store_params = json!({
@@ -96,14 +90,9 @@ async fn test_vector_docs_persistence() -> Result<()> {
     assert_eq!(store_result["status"], "success");
     let document_id = store_result["document_id"].as_str().unwrap().to_string();
     
-    // 创建新的工具实例（模拟程序重启）
-    let tool2 = match VectorDocsTool::new() {
-        Ok(tool) => tool,
-        Err(e) => {
-            println!("⚠️ 跳过持久化测试第二部分：API不可用 - {}", e);
-            return Ok(());
-        }
-    };
+    // 创建新的工具实例（模拟程序重启，先释放数据目录锁）
+    drop(tool1);
+    let tool2 = hermetic_tool(&dir, Arc::new(MockEmbedder::new(64)))?;
     
     // 尝试获取之前存储的文档
     let get_params = json!({
@@ -120,6 +109,87 @@ async fn test_vector_docs_persistence() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_vector_docs_collections_are_isolated() -> Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let tool = hermetic_tool(&dir, Arc::new(MockEmbedder::new(64)))?;
+
+    let created = tool.execute(json!({ "action": "create_collection", "collection": "project-a" })).await?;
+    assert_eq!(created["status"], "success");
+    assert_eq!(tool.execute(json!({ "action": "create_collection", "collection": "project-a" })).await?["status"], "exists");
+    assert!(tool.execute(json!({ "action": "create_collection", "collection": "../escape" })).await.is_err());
+
+    let stored = tool.execute(json!({
+        "action": "store",
+        "collection": "project-a",
+        "title": "内部 API",
+        "content": "project-a 的内部 API 文档",
+        "language": "rust"
+    })).await?;
+    let document_id = stored["document_id"].as_str().unwrap().to_string();
+
+    // 默认集合看不到其他集合的文档
+    let search = json!({ "action": "search", "query": "内部 API" });
+    assert_eq!(tool.execute(search.clone()).await?["results_count"], 0);
+    let mut in_collection = search.clone();
+    in_collection["collection"] = json!("project-a");
+    assert_eq!(tool.execute(in_collection).await?["results_count"], 1);
+    assert_eq!(tool.execute(json!({ "action": "get", "id": document_id })).await?["status"], "not_found");
+
+    let listed = tool.execute(json!({ "action": "list_collections" })).await?;
+    assert_eq!(listed["collections"][0]["name"], "default");
+    assert_eq!(listed["collections"][1]["name"], "project-a");
+    assert_eq!(listed["collections"][1]["documents"], 1);
+
+    assert_eq!(tool.execute(json!({ "action": "drop_collection", "collection": "project-a" })).await?["status"], "success");
+    assert!(tool.execute(json!({ "action": "list", "collection": "project-a" })).await.is_err());
+    assert!(tool.execute(json!({ "action": "drop_collection", "collection": "default" })).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_docs_records_provenance_and_purges_by_source() -> Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let tool = hermetic_tool(&dir, Arc::new(MockEmbedder::new(64)))?;
+    tool.execute(json!({ "action": "create_collection", "collection": "mirror" })).await?;
+
+    let licensed = json!({
+        "action": "store",
+        "title": "Example 指南",
+        "content": "Example 客户端的使用指南。\n\n© 2024 Example Corp. Content licensed under CC BY 4.0.",
+        "source_url": "https://docs.example.com/guide/intro"
+    });
+    let stored = tool.execute(licensed.clone()).await?;
+    let mut mirrored = licensed.clone();
+    mirrored["collection"] = json!("mirror");
+    tool.execute(mirrored).await?;
+    let other = tool.execute(json!({
+        "action": "store",
+        "title": "Other 指南",
+        "content": "Other 客户端的使用指南。",
+        "source_url": "https://other.example.com/guide"
+    })).await?;
+
+    // 搜索结果带出来源、许可证和署名
+    let results = tool.execute(json!({ "action": "search", "query": "Example 客户端的使用指南", "limit": "5" })).await?;
+    let hit = results["results"].as_array().unwrap().iter()
+        .find(|hit| hit["id"] == stored["document_id"])
+        .expect("应能搜到已存储的文档");
+    assert_eq!(hit["metadata"]["source_url"], "https://docs.example.com/guide/intro");
+    assert_eq!(hit["metadata"]["license"], "CC-BY-4.0");
+    assert_eq!(hit["metadata"]["attribution"], "© 2024 Example Corp. Content licensed under CC BY 4.0.");
+
+    // 按前缀清除，默认集合和其他集合中的副本都会删除
+    let purged = tool.execute(json!({ "action": "purge_source", "source_url": "https://docs.example.com/*" })).await?;
+    assert_eq!(purged["documents_removed"], 2);
+    assert_eq!(tool.execute(json!({ "action": "get", "id": stored["document_id"] })).await?["status"], "not_found");
+    assert_eq!(tool.execute(json!({ "action": "get", "id": other["document_id"] })).await?["status"], "success");
+    assert!(tool.execute(json!({ "action": "purge_source", "source_url": "*" })).await.is_err());
+
+    Ok(())
+}
+
 #[test]
 fn test_vector_docs_tool_schema() {
     // 测试工具的基本属性，不需要API密钥
@@ -138,7 +208,11 @@ fn test_vector_docs_tool_schema() {
 fn test_vector_docs_tool_creation_with_api_key() {
     // 只有在有API密钥时才测试工具创建
     if std::env::var("EMBEDDING_API_KEY").is_ok() {
-        let tool = VectorDocsTool::new().expect("无法创建嵌入式向量化文档工具");
+        let dir = tempfile::TempDir::new().unwrap();
+        let embedder = crate::tools::embedder::embedder_from_env(reqwest::Client::new()).expect("无法创建嵌入服务");
+        let tool = VectorDocsTool::open_local(dir.path().to_path_buf())
+            .expect("无法创建嵌入式向量化文档工具")
+            .with_embedder(embedder);
         assert_eq!(tool.name(), "vector_docs");
         println!("✅ 工具创建成功（有API密钥）");
     } else {
@@ -0,0 +1,347 @@
+//! 关键词打分用的分词器
+//!
+//! 相似度计算和混合搜索的关键词匹配原先只按空格切词、只过滤英文停用词，
+//! 中文/日文正文几乎切不出词，`snake_case`/`CamelCase` 标识符也无法与自然语言查询对上。
+//! 这里把分词做成可替换的 `TextAnalyzer`，按自然语言选择：
+//! - 拉丁字母、西里尔字母按单词切分，过滤该语言的停用词；
+//! - 中日韩文字按连续字符的二元组切分（单字片段保留单字）；
+//! - 标识符保留完整形式，同时拆出各个组成部分（`read_to_string` → `read`、`to`、`string`）。
+//!
+//! 停用词可通过配置文件的 `[text_analysis]` 段按语言追加。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::config::TextAnalysisConfig;
+use super::content_language;
+
+/// 分词器
+pub trait TextAnalyzer: Send + Sync + std::fmt::Debug {
+    /// 分词器对应的自然语言（ISO 639-1）
+    fn language(&self) -> &str;
+
+    /// 切分为小写词条，已去掉停用词
+    fn tokenize(&self, text: &str) -> Vec<String>;
+
+    /// 是否为停用词（参数应为小写）
+    fn is_stop_word(&self, token: &str) -> bool;
+}
+
+/// 内置停用词表
+const BUILTIN_STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &[
+        "the", "a", "an", "and", "or", "but", "in", "on", "at", "to", "for", "of", "with", "by",
+        "is", "are", "was", "were", "be", "been", "being", "have", "has", "had", "do", "does", "did",
+        "will", "would", "could", "should", "may", "might", "can", "this", "that", "these", "those",
+        "i", "you", "he", "she", "it", "we", "they", "me", "him", "her", "us", "them", "my", "your",
+        "his", "its", "our", "their", "from", "up", "about", "into", "through", "during",
+        "before", "after", "above", "below", "between", "among", "within", "without", "under", "over",
+    ]),
+    ("de", &[
+        "der", "die", "das", "den", "dem", "des", "und", "oder", "ist", "sind", "nicht", "mit", "ein",
+        "eine", "einen", "wird", "werden", "für", "sie", "es", "auf", "im", "zu", "von", "bei", "auch",
+    ]),
+    ("fr", &[
+        "le", "la", "les", "un", "une", "et", "ou", "est", "sont", "des", "du", "de", "pour", "dans",
+        "qui", "que", "pas", "vous", "nous", "il", "elle", "sur", "avec", "par", "ce", "cette", "au",
+    ]),
+    ("es", &[
+        "el", "la", "los", "las", "un", "una", "y", "o", "es", "son", "para", "que", "con", "por",
+        "del", "de", "se", "en", "al", "lo", "su", "como", "más", "este", "esta",
+    ]),
+    ("ru", &[
+        "и", "в", "во", "не", "что", "он", "на", "я", "с", "со", "как", "а", "то", "все", "она",
+        "так", "его", "но", "для", "это", "из", "по", "от", "при", "или", "если",
+    ]),
+    ("zh", &[
+        "的", "了", "是", "在", "和", "与", "或", "也", "都", "就", "而", "及", "等", "这", "那",
+        "个", "之", "其", "被", "把", "对", "为", "一个", "可以", "如果", "我们", "你", "我",
+    ]),
+    ("ja", &[
+        "の", "に", "は", "を", "た", "が", "で", "て", "と", "し", "れ", "さ", "ある", "いる", "も",
+        "な", "する", "から", "こと", "として", "です", "ます",
+    ]),
+    ("ko", &["이", "그", "저", "것", "수", "등", "및", "를", "을", "은", "는", "에", "의", "가"]),
+];
+
+/// 是否为按字符二元组切分的文字（汉字、假名、谚文）
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{1100}'..='\u{11FF}')
+}
+
+/// 把标识符拆成组成部分：`snake_case`、`kebab-case`、`CamelCase`、`HTTPServer` 都能拆开
+///
+/// 只有一个部分时返回空列表。
+pub fn split_identifier(identifier: &str) -> Vec<String> {
+    let chars: Vec<char> = identifier.chars().collect();
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            if !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map_or(false, |n| n.is_lowercase());
+            // aB 或 ABc（缩写后接单词）处断开
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower) {
+                parts.push(std::mem::take(&mut current));
+            }
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    if parts.len() > 1 {
+        parts
+    } else {
+        Vec::new()
+    }
+}
+
+/// 默认分词器：单词 + 中日韩二元组 + 标识符拆分，停用词按语言配置
+#[derive(Debug, Clone)]
+pub struct StandardAnalyzer {
+    language: String,
+    stopwords: HashSet<String>,
+    /// 非中日韩词条的最短字符数
+    min_token_length: usize,
+    split_identifiers: bool,
+}
+
+impl StandardAnalyzer {
+    /// 使用内置停用词表创建，未内置的语言没有停用词
+    pub fn new(language: &str) -> Self {
+        let stopwords = BUILTIN_STOPWORDS
+            .iter()
+            .find(|(lang, _)| *lang == language)
+            .map(|(_, words)| words.iter().map(|w| w.to_string()).collect())
+            .unwrap_or_default();
+        Self {
+            language: language.to_string(),
+            stopwords,
+            min_token_length: 2,
+            split_identifiers: true,
+        }
+    }
+
+    pub fn with_stopwords<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.stopwords.extend(words.into_iter().map(|w| w.as_ref().trim().to_lowercase()));
+        self
+    }
+
+    pub fn with_min_token_length(mut self, min_token_length: usize) -> Self {
+        self.min_token_length = min_token_length.max(1);
+        self
+    }
+
+    pub fn with_split_identifiers(mut self, split_identifiers: bool) -> Self {
+        self.split_identifiers = split_identifiers;
+        self
+    }
+
+    fn push_word(&self, word: &str, tokens: &mut Vec<String>) {
+        let lower = word.to_lowercase();
+        let keep = |token: &str| token.chars().count() >= self.min_token_length && !self.stopwords.contains(token);
+        let parts = if self.split_identifiers { split_identifier(word) } else { Vec::new() };
+        if keep(&lower) {
+            tokens.push(lower);
+        }
+        tokens.extend(parts.into_iter().filter(|part| keep(part)));
+    }
+
+    fn push_cjk_run(&self, run: &[char], tokens: &mut Vec<String>) {
+        // 先按单字停用词断开，再对每一段取二元组
+        for segment in run.split(|c| self.stopwords.contains(c.to_string().as_str())) {
+            match segment.len() {
+                0 => {}
+                1 => tokens.push(segment[0].to_string()),
+                _ => tokens.extend(
+                    segment
+                        .windows(2)
+                        .map(|pair| pair.iter().collect::<String>())
+                        .filter(|bigram| !self.stopwords.contains(bigram)),
+                ),
+            }
+        }
+    }
+}
+
+impl TextAnalyzer for StandardAnalyzer {
+    fn language(&self) -> &str {
+        &self.language
+    }
+
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut word = String::new();
+        let mut cjk_run: Vec<char> = Vec::new();
+        for c in text.chars() {
+            if is_cjk(c) {
+                if !word.is_empty() {
+                    self.push_word(&std::mem::take(&mut word), &mut tokens);
+                }
+                cjk_run.push(c);
+                continue;
+            }
+            if !cjk_run.is_empty() {
+                self.push_cjk_run(&std::mem::take(&mut cjk_run), &mut tokens);
+            }
+            if c.is_alphanumeric() || c == '_' {
+                word.push(c);
+            } else if !word.is_empty() {
+                self.push_word(&std::mem::take(&mut word), &mut tokens);
+            }
+        }
+        if !word.is_empty() {
+            self.push_word(&word, &mut tokens);
+        }
+        if !cjk_run.is_empty() {
+            self.push_cjk_run(&cjk_run, &mut tokens);
+        }
+        tokens
+    }
+
+    fn is_stop_word(&self, token: &str) -> bool {
+        self.stopwords.contains(token)
+    }
+}
+
+/// 按自然语言选择分词器
+#[derive(Debug, Clone)]
+pub struct AnalyzerRegistry {
+    analyzers: HashMap<String, Arc<dyn TextAnalyzer>>,
+    /// 无法判定语言时使用的分词器
+    fallback: Arc<dyn TextAnalyzer>,
+}
+
+impl Default for AnalyzerRegistry {
+    fn default() -> Self {
+        Self::from_config(&TextAnalysisConfig::default())
+    }
+}
+
+impl AnalyzerRegistry {
+    /// 为所有内置语言和配置中出现的语言创建 `StandardAnalyzer`
+    pub fn from_config(config: &TextAnalysisConfig) -> Self {
+        let mut languages: Vec<String> = BUILTIN_STOPWORDS.iter().map(|(lang, _)| lang.to_string()).collect();
+        languages.extend(config.extra_stopwords.keys().cloned());
+        languages.push(config.default_language.clone());
+
+        let mut analyzers: HashMap<String, Arc<dyn TextAnalyzer>> = HashMap::new();
+        for language in languages {
+            let code = content_language::normalize_natural_language(&language)
+                .map(str::to_string)
+                .unwrap_or_else(|| language.trim().to_lowercase());
+            if analyzers.contains_key(&code) {
+                continue;
+            }
+            let extra = config
+                .extra_stopwords
+                .iter()
+                .filter(|(lang, _)| content_language::normalize_natural_language(lang).unwrap_or(lang.as_str()) == code)
+                .flat_map(|(_, words)| words.iter());
+            let analyzer = StandardAnalyzer::new(&code)
+                .with_stopwords(extra)
+                .with_min_token_length(config.min_token_length)
+                .with_split_identifiers(config.split_identifiers);
+            analyzers.insert(code, Arc::new(analyzer));
+        }
+
+        let default_language = content_language::normalize_natural_language(&config.default_language)
+            .unwrap_or("en");
+        let fallback = analyzers
+            .get(default_language)
+            .cloned()
+            .unwrap_or_else(|| Arc::new(StandardAnalyzer::new("en")));
+        Self { analyzers, fallback }
+    }
+
+    /// 注册（或替换）某个语言的分词器
+    pub fn register(&mut self, analyzer: Arc<dyn TextAnalyzer>) {
+        self.analyzers.insert(analyzer.language().to_string(), analyzer);
+    }
+
+    /// 无法判定语言时使用的分词器
+    pub fn fallback(&self) -> &Arc<dyn TextAnalyzer> {
+        &self.fallback
+    }
+
+    /// 按语言名（`zh`、`中文`、`English` 等）取分词器，没有对应分词器时使用默认分词器
+    pub fn get(&self, language: &str) -> &Arc<dyn TextAnalyzer> {
+        let code = content_language::normalize_natural_language(language).unwrap_or(language);
+        self.analyzers.get(code).unwrap_or(&self.fallback)
+    }
+
+    /// 按检测到的自然语言选择分词器
+    pub fn for_text(&self, text: &str) -> &Arc<dyn TextAnalyzer> {
+        content_language::detect_natural_language(text).map_or(&self.fallback, |language| self.get(language))
+    }
+
+    /// 检测语言后分词
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        self.for_text(text).tokenize(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_identifier() {
+        assert_eq!(split_identifier("read_to_string"), vec!["read", "to", "string"]);
+        assert_eq!(split_identifier("HashMap"), vec!["hash", "map"]);
+        assert_eq!(split_identifier("HTTPServer"), vec!["http", "server"]);
+        assert_eq!(split_identifier("getUTF8Bytes"), vec!["get", "utf8", "bytes"]);
+        assert!(split_identifier("tokio").is_empty());
+    }
+
+    #[test]
+    fn test_english_tokens_keep_identifiers_and_parts() {
+        let analyzer = StandardAnalyzer::new("en");
+        let tokens = analyzer.tokenize("Use the HashMap with read_to_string");
+        assert_eq!(tokens, vec!["use", "hashmap", "hash", "map", "read_to_string", "read", "string"]);
+    }
+
+    #[test]
+    fn test_cjk_bigrams_skip_stopwords() {
+        let analyzer = StandardAnalyzer::new("zh");
+        let tokens = analyzer.tokenize("异步运行时的任务调度");
+        assert!(tokens.contains(&"异步".to_string()));
+        assert!(tokens.contains(&"任务".to_string()));
+        assert!(tokens.contains(&"调度".to_string()));
+        assert!(!tokens.iter().any(|t| t.contains('的')));
+    }
+
+    #[test]
+    fn test_registry_selects_by_language_and_config() {
+        let mut config = TextAnalysisConfig::default();
+        config.extra_stopwords.insert("de".to_string(), vec!["beispiel".to_string()]);
+        let registry = AnalyzerRegistry::from_config(&config);
+
+        let german = "Die Funktion gibt einen Wert zurück und ist nicht blockierend, zum Beispiel bei der Eingabe";
+        assert_eq!(registry.for_text(german).language(), "de");
+        let tokens = registry.tokenize(german);
+        assert!(tokens.contains(&"funktion".to_string()));
+        assert!(!tokens.contains(&"die".to_string()));
+        assert!(!tokens.contains(&"beispiel".to_string()));
+
+        assert_eq!(registry.get("中文").language(), "zh");
+        assert_eq!(registry.get("klingon").language(), "en");
+        assert!(registry.fallback().is_stop_word("the"));
+    }
+}
@@ -1,5 +1,5 @@
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::path::PathBuf;
 use std::fs;
 use async_trait::async_trait;
@@ -7,14 +7,59 @@ use serde_json::{json, Value};
 use anyhow::Result;
 use uuid::Uuid;
 use instant_distance::{Builder, HnswMap, Search};
+use rayon::prelude::*;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use dotenv;
+use ed25519_dalek::SigningKey;
 use regex;
-use md5;
 
-use crate::tools::base::{MCPTool, Schema, SchemaObject, SchemaString, FileDocumentFragment};
-use crate::errors::MCPError;
+use crate::tools::base::{MCPTool, Schema, SchemaBoolean, SchemaNumber, SchemaObject, SchemaString, FileDocumentFragment};
+use crate::tools::cache_tiers::{CacheTier, CacheTierPaths};
+use crate::tools::doc_packs::{DocPack, DocPackImportReport};
+use crate::tools::devdocs::{DevDocsImportReport, DevDocsSet};
+use crate::tools::qa_enrichment::{QaEnricher, QaEnrichmentConfig, QaEnrichmentReport};
+use crate::tools::data_lock::{write_atomic, AccessMode, DataDirLock, LockPolicy};
+use crate::tools::data_format::{self, MigrationRegistry};
+use crate::tools::content_language;
+use crate::tools::project_context::ProjectProfile;
+use crate::tools::dependency_cleanup::{self, RemovedDependency};
+use crate::tools::symbol_index::{self, SymbolIndex, SymbolSource};
+use crate::tools::text_analyzer::AnalyzerRegistry;
+use crate::tools::bm25_index::Bm25Index;
+use crate::tools::pagination::{self, Page};
+use crate::tools::hybrid_scoring::HybridScorer;
+use crate::tools::search_filter::{SearchFilter, CREATED_AT_METADATA_KEY};
+use crate::tools::quantization::{QuantizedPoint, Quantizer};
+use crate::tools::provenance;
+use crate::tools::mmap_vectors::{self, MappedLayout, MappedVectors, VectorPoint, VectorRow};
+use crate::tools::write_ahead_log::{self, WalOp, WriteAheadLog};
+use crate::tools::corpus_io::{self, CorpusFormat, CorpusReport, EXPORTS_DIR};
+use crate::tools::score_normalization::{self, ScoreKind};
+use crate::tools::dedup::{self, DedupItem, DedupReport, DuplicateCluster, ScopedCluster, DEFAULT_DEDUP_THRESHOLD};
+use crate::tools::snapshot::{Snapshot, SnapshotReport, StoreSnapshot, COLLECTION_STORE_PREFIX, SNAPSHOTS_DIR};
+#[cfg(feature = "database")]
+use crate::tools::sqlite_metadata::SqliteMetadataIndex;
+use crate::tools::crawl_report::estimate_tokens;
+use crate::tools::content_store::{self, ContentStore, ContentTierConfig};
+use crate::tools::embedder::{embedder_from_env, EmbeddingHealth, InputType, TextEmbedder, DEFAULT_EMBEDDING_MODEL};
+use crate::tools::embedding_cache::{EmbeddingCache, EmbeddingCacheConfig};
+use crate::tools::embedding_batcher::{AdaptiveBatcher, BatchingConfig};
+use crate::metrics::MetricsCollector;
+use crate::storage::embedding_meta::{EmbeddingMeta, EMBEDDING_META_FILE, UNKNOWN_EMBEDDING_MODEL};
+use crate::tools::docs::reranker::{DocumentReranker, Reranker};
+use crate::tools::quality_gate::{QualityGate, RejectionCounts};
+use crate::tools::package_progress::{PackageProgress, ProgressStatus};
+use crate::tools::cache_eviction::{
+    package_version_key, select_eviction_victims, split_package_version_key, CacheEvictionConfig, EvictionStats,
+    PackageUsage,
+};
+use crate::errors::{server_error, MCPError, VectorDbError};
+use crate::mcp::namespace;
+use crate::config::{
+    DistanceMetric, MetadataBackend, QuantizationConfig, QuantizationMode, ShardStrategy, ShardingConfig, SystemConfig,
+    VectorSearchConfig,
+};
 
 /// 文档结构特征
 #[derive(Debug, Clone)]
@@ -24,38 +69,84 @@ struct StructureFeatures {
     list_count: usize,
 }
 
-/// 向量点类型，实现 Point trait
-#[derive(Debug, Clone, PartialEq)]
-struct VectorPoint(Vec<f32>);
+/// 向量索引：保存完整向量，或保存量化向量（值为 `vectors` 中的下标，取候选后按原始向量重排）
+enum VectorIndex {
+    Exact(HnswMap<VectorPoint, String>),
+    Quantized {
+        map: HnswMap<QuantizedPoint, usize>,
+        quantizer: Arc<Quantizer>,
+    },
+}
+
+/// 构建向量索引的输入快照，构建过程不需要持有存储的锁
+struct IndexBuild {
+    vectors: Vec<VectorRow>,
+    /// 取快照时的向量ID到文档ID映射，换上索引前据此确认向量没有变化
+    doc_ids: Vec<String>,
+    metric: DistanceMetric,
+    quantizer: Option<Arc<Quantizer>>,
+}
 
-impl instant_distance::Point for VectorPoint {
-    fn distance(&self, other: &Self) -> f32 {
-        // 欧几里得距离
-        self.0.iter()
-            .zip(other.0.iter())
-            .map(|(a, b)| (a - b).powi(2))
-            .sum::<f32>()
-            .sqrt()
+impl IndexBuild {
+    fn build(&self) -> Option<VectorIndex> {
+        if self.vectors.is_empty() {
+            return None;
+        }
+        let builder = Builder::default();
+        Some(match &self.quantizer {
+            Some(quantizer) => {
+                let points: Vec<QuantizedPoint> = self.vectors.iter()
+                    .map(|v| QuantizedPoint::new(quantizer.clone(), self.metric, v))
+                    .collect();
+                let values: Vec<usize> = (0..points.len()).collect();
+                VectorIndex::Quantized { map: builder.build(points, values), quantizer: quantizer.clone() }
+            }
+            None => {
+                let points: Vec<VectorPoint> = self.vectors.iter()
+                    .enumerate()
+                    .map(|(row, v)| VectorPoint::new(row, v.clone(), self.metric))
+                    .collect();
+                VectorIndex::Exact(builder.build(points, self.doc_ids.clone()))
+            }
+        })
     }
 }
 
-/// NVIDIA API 嵌入响应
-#[derive(Debug, Deserialize)]
-struct EmbeddingResponse {
-    data: Vec<EmbeddingData>,
+/// 向量索引的一个分片：分片内的向量和在其上构建的索引（量化索引的值是分片内的下标）
+struct IndexShard {
+    doc_ids: Vec<String>,
+    vectors: Vec<VectorRow>,
+    quantizer: Option<Arc<Quantizer>>,
+    index: Option<VectorIndex>,
+}
+
+impl IndexShard {
+    /// 分片成员和量化器都没有变化，不需要重建
+    fn is_current(&self, build: &IndexBuild) -> bool {
+        let same_quantizer = match (&self.quantizer, &build.quantizer) {
+            (None, None) => true,
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+        same_quantizer && self.doc_ids == build.doc_ids
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct EmbeddingData {
-    embedding: Vec<f32>,
+/// 一次索引重建：未分片时是整个索引，分片时只包含成员有变化的分片
+struct IndexRebuild {
+    /// 取快照时的向量ID到文档ID映射，换上索引前据此确认向量没有变化
+    doc_ids: Vec<String>,
+    /// (分片下标, 构建输入)；未分片时只有下标为 0 的一项
+    builds: Vec<(usize, IndexBuild)>,
+    /// 分片数，未分片时为 None
+    shard_count: Option<usize>,
 }
 
-/// NVIDIA API 嵌入请求
-#[derive(Debug, Serialize)]
-struct EmbeddingRequest {
-    input: Vec<String>,
-    model: String,
-    input_type: String,
+impl IndexRebuild {
+    /// 并行构建各分片的索引，顺序与 `builds` 一致
+    fn build(&self) -> Vec<Option<VectorIndex>> {
+        self.builds.par_iter().map(|(_, build)| build.build()).collect()
+    }
 }
 
 /// 文档记录结构
@@ -73,6 +164,10 @@ pub struct DocumentRecord {
 }
 
 /// 搜索结果
+///
+/// `score` 是 0-1 的相关度，各搜索模式的原始分数经 [`score_normalization`] 映射到同一刻度，客户端可用同一个阈值
+/// 过滤：向量模式为相似度本身，全文模式为 `bm25 / (bm25 + 4)`，混合模式不超过 0.95，符号精确匹配在 0.95-1 之间，
+/// 重排后为重排模型的相关度。原始分数和来源见元数据的 `raw_score`、`score_kind`。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub id: String,
@@ -86,6 +181,54 @@ pub struct SearchResult {
     pub score: f32,
 }
 
+/// 已缓存的包版本概要
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedPackage {
+    pub language: String,
+    pub package_name: String,
+    pub version: String,
+    /// 文档类型 -> 文档数
+    pub doc_types: std::collections::BTreeMap<String, usize>,
+    pub document_count: usize,
+}
+
+/// 批量入库结果
+#[derive(Debug, Clone, Default)]
+pub struct FragmentIngestReport {
+    /// 已入库（或此前已存在）的片段ID
+    pub added_ids: Vec<String>,
+    /// 被质量闸门拒绝的片段数（按原因）
+    pub rejected: RejectionCounts,
+    /// 新写入向量库的片段数
+    pub stored: usize,
+    /// 内容为空或已在向量库中而跳过的片段数
+    pub skipped: usize,
+    /// 生成嵌入向量失败的片段数
+    pub failed: usize,
+    /// 嵌入请求的估算 token 数
+    pub embedding_tokens: usize,
+}
+
+/// 重新嵌入（见 [`VectorDocsTool::reembed_all`]）的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReembedReport {
+    /// 生成新向量的嵌入模型和维度
+    pub model: String,
+    pub dimension: Option<usize>,
+    /// 处理的层级和集合数
+    pub stores: usize,
+    /// 重新嵌入的文档数
+    pub documents: usize,
+}
+
+/// 缓存中新增文档的通知（按包版本）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedDocsUpdate {
+    pub language: String,
+    pub package_name: String,
+    pub version: String,
+}
+
 /// 持久化数据结构
 #[derive(Debug, Serialize, Deserialize)]
 struct PersistentData {
@@ -93,1214 +236,4009 @@ struct PersistentData {
     vectors: Vec<Vec<f32>>,
     vector_to_doc_id: Vec<String>,
     processed_package_versions: Option<std::collections::HashSet<String>>,
+    /// 各包版本的处理进度
+    package_progress: HashMap<String, PackageProgress>,
+    /// 全文已落盘的文档（文档ID -> 全文字节数），这些文档的 `content` 只是摘要
+    offloaded: HashMap<String, usize>,
+    /// 向量和索引以内存映射格式保存时的文件引用；此时 `vectors` 和文档的 `embedding` 为空
+    layout: Option<MappedLayout>,
+}
+
+/// 缓存容量统计的持久化数据（独立于向量数据文件保存）
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheAccountingData {
+    package_usage: HashMap<String, PackageUsage>,
+    eviction_stats: EvictionStats,
 }
 
 /// 嵌入式向量数据库存储
 struct VectorStore {
     /// 文档记录
     documents: HashMap<String, DocumentRecord>,
-    /// 向量索引
-    search_index: Option<HnswMap<VectorPoint, String>>,
-    /// 向量数据
-    vectors: Vec<Vec<f32>>,
+    /// 向量索引（未分片时）
+    search_index: Option<VectorIndex>,
+    /// 分片的向量索引（启用分片时），下标为分片号
+    shards: Vec<IndexShard>,
+    /// 索引分片配置
+    sharding: ShardingConfig,
+    /// 符号精确匹配索引（随向量索引一起重建）
+    symbol_index: SymbolIndex,
+    /// BM25 全文索引（随文档增删增量维护）
+    text_index: Bm25Index,
+    /// 全文索引和关键词打分用的分词器
+    analyzers: AnalyzerRegistry,
+    /// 向量数据（新写入的在内存中，从内存映射格式加载的指向向量文件）
+    vectors: Vec<VectorRow>,
     /// 向量ID到文档ID的映射
     vector_to_doc_id: Vec<String>,
     /// 数据存储路径
     data_dir: PathBuf,
     processed_package_versions: std::collections::HashSet<String>,
+    /// 各包版本的处理进度（支持中断后恢复）
+    package_progress: HashMap<String, PackageProgress>,
+    /// 缓存容量配置
+    eviction_config: CacheEvictionConfig,
+    /// 包版本的访问情况（用于淘汰决策）；搜索在读锁下也要记录命中，单独加锁
+    package_usage: Mutex<HashMap<String, PackageUsage>>,
+    /// 淘汰统计
+    eviction_stats: EvictionStats,
+    /// 是否处于休眠状态（文档、向量和索引已从内存释放）
+    hibernated: bool,
+    /// 数据目录锁（多实例共享同一目录时只有一个实例可写）
+    data_lock: Option<DataDirLock>,
+    /// 上次加载时数据文件的修改时间（只读跟随实例据此判断是否需要重新加载）
+    loaded_mtime: Option<std::time::SystemTime>,
+    /// 上次检查数据文件是否更新的时间（只读副本按刷新间隔检查）
+    refresh_checked_at: Option<std::time::Instant>,
+    /// 落盘的文档全文
+    content_store: ContentStore,
+    /// 全文分层配置
+    content_tier: ContentTierConfig,
+    /// 全文已落盘的文档（文档ID -> 全文字节数）
+    offloaded: HashMap<String, usize>,
+    /// 向量距离度量（只影响索引和分数，切换后重建索引即可，无需迁移数据）
+    distance_metric: DistanceMetric,
+    /// 索引向量量化配置
+    quantization: QuantizationConfig,
+    /// 已训练的量化器及训练时的向量数（向量数翻倍前复用，避免每次写入都重新训练）
+    quantizer: Option<(Arc<Quantizer>, usize)>,
+    /// 生成库中向量的嵌入模型和维度（数据目录下的 `embedding_meta.json`），不一致的写入被拒绝
+    embedding_meta: Option<EmbeddingMeta>,
+    /// 是否以内存映射格式保存向量和索引
+    mmap_index: bool,
+    /// 预写日志（重放日志期间暂时取出，避免重放的修改再次写入日志）
+    wal: Option<WriteAheadLog>,
+    /// SQLite 元数据索引（随向量索引一起同步），过滤扫描时用 SQL 筛选候选；
+    /// SQLite 连接不能跨线程共享，读锁下并发搜索时逐个使用
+    #[cfg(feature = "database")]
+    metadata_index: Option<Mutex<SqliteMetadataIndex>>,
 }
 
 impl VectorStore {
     fn new(data_dir: PathBuf) -> Self {
         Self {
+            content_store: ContentStore::new(&data_dir),
+            wal: Some(WriteAheadLog::new(&data_dir)),
             documents: HashMap::new(),
             search_index: None,
+            shards: Vec::new(),
+            sharding: VectorSearchConfig::sharding(),
+            symbol_index: SymbolIndex::new(),
+            text_index: Bm25Index::new(),
+            analyzers: AnalyzerRegistry::from_config(&SystemConfig::load().text_analysis),
             vectors: Vec::new(),
             vector_to_doc_id: Vec::new(),
             data_dir,
             processed_package_versions: std::collections::HashSet::new(),
+            package_progress: HashMap::new(),
+            eviction_config: CacheEvictionConfig::from_env(),
+            package_usage: Mutex::new(HashMap::new()),
+            eviction_stats: EvictionStats::default(),
+            hibernated: false,
+            data_lock: None,
+            loaded_mtime: None,
+            refresh_checked_at: None,
+            content_tier: ContentTierConfig::from_env(),
+            offloaded: HashMap::new(),
+            distance_metric: VectorSearchConfig::distance_metric(),
+            quantization: VectorSearchConfig::quantization(),
+            quantizer: None,
+            embedding_meta: None,
+            mmap_index: VectorSearchConfig::mmap_index(),
+            #[cfg(feature = "database")]
+            metadata_index: None,
         }
     }
 
-    /// 从磁盘加载数据
-    fn load(&mut self) -> Result<()> {
-        let data_file = self.data_dir.join("vector_data.bin");
-        
-        if !data_file.exists() {
-            // 首次运行，没有数据文件
+    /// 按配置启用元数据后端
+    fn open_metadata_backend(&mut self, backend: MetadataBackend) -> Result<()> {
+        if backend != MetadataBackend::Sqlite {
             return Ok(());
         }
+        #[cfg(feature = "database")]
+        {
+            self.metadata_index = Some(Mutex::new(SqliteMetadataIndex::open(&self.data_dir, self.access_mode().is_read_only())?));
+        }
+        #[cfg(not(feature = "database"))]
+        {
+            tracing::warn!("元数据后端 sqlite 需要启用 database feature，继续使用内存过滤");
+        }
+        Ok(())
+    }
 
-        let data = fs::read(&data_file)?;
-        match bincode::deserialize::<PersistentData>(&data) {
-            Ok(persistent_data) => {
-                self.documents = persistent_data.documents;
-                self.vectors = persistent_data.vectors;
-                self.vector_to_doc_id = persistent_data.vector_to_doc_id;
-                self.processed_package_versions = persistent_data.processed_package_versions.unwrap_or_else(|| std::collections::HashSet::new());
-                self.rebuild_index()?;
-                tracing::info!("从磁盘加载了 {} 个文档和 {} 个已处理包版本标记。", self.documents.len(), self.processed_package_versions.len());
-            }
+    /// SQLite 元数据索引筛出的候选文档ID；未启用或过滤条件无法下推时为 None
+    #[cfg(feature = "database")]
+    fn sql_candidates(&self, filter: &SearchFilter) -> Option<std::collections::HashSet<String>> {
+        let index = self.metadata_index.as_ref()?;
+        let candidates = index.lock().unwrap().candidate_ids(filter);
+        match candidates {
+            Ok(ids) => ids,
             Err(e) => {
-                // 尝试加载旧格式数据（不含 processed_package_versions）
-                tracing::warn!("尝试加载新格式数据失败: {}. 尝试加载旧格式...", e);
-                let old_persistent_data: Result<OldPersistentData, _> = bincode::deserialize(&data);
-                 match old_persistent_data {
-                    Ok(old_data) => {
-                        self.documents = old_data.documents;
-                        self.vectors = old_data.vectors;
-                        self.vector_to_doc_id = old_data.vector_to_doc_id;
-                        self.processed_package_versions = std::collections::HashSet::new();
-                        self.rebuild_index()?;
-                        tracing::info!("成功从旧格式磁盘数据加载了 {} 个文档。已处理包版本标记将重新建立。", self.documents.len());
-                    }
-                    Err(old_err) => {
-                        tracing::error!("加载旧格式数据也失败: {}. 将创建新的向量库。", old_err);
-                        // 如果都失败，则不改变当前状态（相当于新建）
-                    }
-                }
+                tracing::warn!("SQLite 元数据查询失败，改为内存过滤: {}", e);
+                None
             }
         }
-        Ok(())
     }
 
-    /// 保存数据到磁盘
-    fn save(&self) -> Result<()> {
-        // 确保数据目录存在
-        fs::create_dir_all(&self.data_dir)?;
-        
-        let persistent_data = PersistentData {
-            documents: self.documents.clone(),
-            vectors: self.vectors.clone(),
-            vector_to_doc_id: self.vector_to_doc_id.clone(),
-            processed_package_versions: Some(self.processed_package_versions.clone()),
-        };
-        
-        let data = bincode::serialize(&persistent_data)?;
-        let data_file = self.data_dir.join("vector_data.bin");
-        fs::write(&data_file, data)?;
-        
-        tracing::debug!("向量数据（包含已处理包版本标记）已保存到: {:?}", data_file);
-        Ok(())
+    #[cfg(not(feature = "database"))]
+    fn sql_candidates(&self, _filter: &SearchFilter) -> Option<std::collections::HashSet<String>> {
+        None
     }
 
-    fn add_document(&mut self, doc: DocumentRecord) -> Result<()> {
-        let doc_id = doc.id.clone();
-        // 检查文档是否已存在，如果存在则可以考虑更新或跳过
-        if self.documents.contains_key(&doc_id) {
-            // 简单的跳过逻辑，可以根据需求改为更新
-            tracing::debug!("文档 {} 已存在，跳过添加单个文档。", doc_id);
-            return Ok(()); 
+    /// 实际使用的元数据后端
+    fn metadata_backend(&self) -> MetadataBackend {
+        #[cfg(feature = "database")]
+        if self.metadata_index.is_some() {
+            return MetadataBackend::Sqlite;
         }
-        let embedding = doc.embedding.clone(); 
-        
-        self.documents.insert(doc_id.clone(), doc);
-        self.vectors.push(embedding);
-        self.vector_to_doc_id.push(doc_id.clone());
-        
-        self.rebuild_index()?;        
-        self.save() // 单个添加后保存
+        MetadataBackend::Memory
     }
 
-    /// 批量添加文档记录，并在完成后重建索引和保存
-    fn add_documents_batch(&mut self, docs: Vec<DocumentRecord>) -> Result<()> {
-        if docs.is_empty() {
-            return Ok(());
-        }
-        let mut new_docs_count = 0;
-        for doc in docs {
-            let doc_id = doc.id.clone();
-            // 检查文档是否已存在，如果存在则可以考虑更新或跳过
-            if self.documents.contains_key(&doc_id) {
-                tracing::debug!("文档 {} 已存在于批处理中，跳过添加。", doc_id);
-                continue; 
-            }
-            let embedding = doc.embedding.clone();
+    /// 当前实例对数据目录的访问模式
+    fn access_mode(&self) -> AccessMode {
+        self.data_lock.as_ref().map(|lock| lock.mode()).unwrap_or(AccessMode::Unlocked)
+    }
 
-            self.documents.insert(doc_id.clone(), doc);
-            self.vectors.push(embedding);
-            self.vector_to_doc_id.push(doc_id.clone());
-            new_docs_count += 1;
+    /// 只读跟随实例拒绝写操作
+    fn ensure_writable(&self) -> Result<()> {
+        if self.access_mode().is_read_only() {
+            return Err(MCPError::ServerError(format!(
+                "向量存储 {:?} 由其他实例持有写锁，当前实例为只读跟随模式", self.data_dir
+            )).into());
         }
+        Ok(())
+    }
 
-        if new_docs_count > 0 {
-            self.rebuild_index()?;
-            self.save()?; // 所有新文档添加完成后保存一次
-            tracing::info!("成功批量添加 {} 个新文档记录到向量库并已保存。", new_docs_count);
-        } else {
-            tracing::info!("批量添加操作中没有新的文档被添加。");
+    fn data_file_mtime(&self) -> Option<std::time::SystemTime> {
+        fs::metadata(self.data_dir.join("vector_data.bin")).and_then(|m| m.modified()).ok()
+    }
+
+    /// 是否需要先取写锁从休眠中恢复或检查只读副本的数据文件
+    fn needs_refresh(&self) -> bool {
+        let mode = self.access_mode();
+        self.hibernated
+            || (mode.is_read_only()
+                && self.refresh_checked_at.map_or(true, |checked| checked.elapsed() >= mode.refresh_interval()))
+    }
+
+    /// 只读实例在写入实例更新数据文件后重新加载
+    fn refresh_if_stale(&mut self) -> Result<()> {
+        let mode = self.access_mode();
+        if !mode.is_read_only() || self.hibernated {
+            return Ok(());
+        }
+        let now = std::time::Instant::now();
+        if self.refresh_checked_at.map_or(false, |checked| now.duration_since(checked) < mode.refresh_interval()) {
+            return Ok(());
+        }
+        self.refresh_checked_at = Some(now);
+        let current = self.data_file_mtime();
+        if current.is_some() && current != self.loaded_mtime {
+            tracing::debug!("检测到数据文件已被持锁实例更新，重新加载: {:?}", self.data_dir);
+            self.load()?;
         }
         Ok(())
     }
 
-    fn rebuild_index(&mut self) -> Result<()> {
-        if self.vectors.is_empty() {
-            self.search_index = None;
+    /// 将数据落盘后释放文档、向量和HNSW索引占用的内存
+    fn hibernate(&mut self) -> Result<()> {
+        if self.hibernated {
             return Ok(());
         }
-
-        let builder = Builder::default();
-        let points: Vec<VectorPoint> = self.vectors.iter()
-            .map(|v| VectorPoint(v.clone()))
-            .collect();
-        let values: Vec<String> = self.vector_to_doc_id.clone();
-        
-        let search_map = builder.build(points, values);
-        self.search_index = Some(search_map);
-        
+        self.save()?;
+        self.documents = HashMap::new();
+        self.offloaded = HashMap::new();
+        self.vectors = Vec::new();
+        self.vector_to_doc_id = Vec::new();
+        self.search_index = None;
+        self.shards = Vec::new();
+        self.symbol_index.clear();
+        self.text_index.clear();
+        self.hibernated = true;
+        tracing::info!("向量存储已休眠: {:?}", self.data_dir);
         Ok(())
     }
 
-    fn search_similar(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
-        let search_index = match &self.search_index {
-            Some(index) => index,
-            None => return Ok(Vec::new()),
-        };
+    /// 从休眠中恢复：重新从磁盘加载数据并重建索引
+    fn wake(&mut self) -> Result<()> {
+        if !self.hibernated {
+            return Ok(());
+        }
+        self.load()?;
+        self.hibernated = false;
+        tracing::info!("向量存储已从休眠中恢复: {:?} ({} 个文档)", self.data_dir, self.documents.len());
+        Ok(())
+    }
 
-        let query_point = VectorPoint(query_embedding.to_vec());
-        let mut search = Search::default();
-        
-        let mut results = Vec::new();
-        for item in search_index.search(&query_point, &mut search).take(limit) {
-            if let Some(doc) = self.documents.get(item.value.as_str()) {
-                let distance = item.distance;
-                results.push(SearchResult {
-                    id: doc.id.clone(),
-                    content: doc.content.clone(),
-                    title: doc.title.clone(),
-                    language: doc.language.clone(),
-                    package_name: doc.package_name.clone(),
-                    version: doc.version.clone(),
-                    doc_type: doc.doc_type.clone(),
-                    metadata: doc.metadata.clone(),
-                    score: 1.0 / (1.0 + distance), // 转换距离为相似度分数
-                });
+    /// 加载缓存容量统计
+    fn load_accounting(&mut self) {
+        let accounting_file = self.data_dir.join("cache_accounting.json");
+        if !accounting_file.exists() {
+            return;
+        }
+        match fs::read_to_string(&accounting_file).map(|text| serde_json::from_str::<CacheAccountingData>(&text)) {
+            Ok(Ok(accounting)) => {
+                *self.package_usage.get_mut().unwrap() = accounting.package_usage;
+                self.eviction_stats = accounting.eviction_stats;
             }
+            Ok(Err(e)) => tracing::warn!("解析缓存容量统计失败，将重新统计: {}", e),
+            Err(e) => tracing::warn!("读取缓存容量统计失败，将重新统计: {}", e),
         }
-        
-        Ok(results)
     }
 
-    fn get_document(&self, doc_id: &str) -> Option<&DocumentRecord> {
-        self.documents.get(doc_id)
+    /// 加载包处理进度；进度文件比数据文件更新得频繁，以进度文件为准
+    fn load_progress(&mut self) {
+        let progress_file = self.data_dir.join("package_progress.json");
+        if !progress_file.exists() {
+            return;
+        }
+        match fs::read_to_string(&progress_file).map(|text| serde_json::from_str::<HashMap<String, PackageProgress>>(&text)) {
+            Ok(Ok(progress)) => self.package_progress.extend(progress),
+            Ok(Err(e)) => tracing::warn!("解析包处理进度失败，使用数据文件中的进度: {}", e),
+            Err(e) => tracing::warn!("读取包处理进度失败，使用数据文件中的进度: {}", e),
+        }
     }
 
-    fn delete_document(&mut self, doc_id: &str) -> Result<bool> {
-        if let Some(_) = self.documents.remove(doc_id) {
-            // 找到并移除对应的向量
-            if let Some(pos) = self.vector_to_doc_id.iter().position(|id| id == doc_id) {
-                self.vectors.remove(pos);
-                self.vector_to_doc_id.remove(pos);
-                
-                // 重建索引
-                self.rebuild_index()?;
-                
-                // 自动保存
-                self.save()?;
-                
-                Ok(true)
-            } else {
-                Ok(false)
-            }
-        } else {
-            Ok(false)
+    /// 单独保存包处理进度，进度更新时不必重写整个数据文件
+    fn save_progress(&self) -> Result<()> {
+        if self.access_mode().is_read_only() {
+            return Ok(());
         }
+        fs::create_dir_all(&self.data_dir)?;
+        write_atomic(&self.data_dir.join("package_progress.json"), serde_json::to_string(&self.package_progress)?.as_bytes())
     }
 
-    /// 获取统计信息
-    fn get_stats(&self) -> (usize, usize) {
-        (self.documents.len(), self.vectors.len())
+    /// 保存缓存容量统计
+    fn save_accounting(&self) -> Result<()> {
+        let accounting = CacheAccountingData {
+            package_usage: self.package_usage.lock().unwrap().clone(),
+            eviction_stats: self.eviction_stats.clone(),
+        };
+        write_atomic(&self.data_dir.join("cache_accounting.json"), serde_json::to_string(&accounting)?.as_bytes())?;
+        Ok(())
     }
 
-    /// 检查某个包的特定版本是否已被标记为完整处理
-    pub fn has_processed_package_version(&self, language: &str, package_name: &str, version: &str) -> bool {
-        let key = format!("{}/{}/{}", language, package_name, version);
-        self.processed_package_versions.contains(&key)
+    /// 估算单个文档占用的字节数（内容、标题、元数据和向量），全文已落盘的按全文计
+    fn document_bytes(&self, doc: &DocumentRecord) -> u64 {
+        let metadata_bytes: usize = doc.metadata.iter().map(|(k, v)| k.len() + v.len()).sum();
+        let content_bytes = self.offloaded.get(&doc.id).copied().unwrap_or(doc.content.len());
+        (content_bytes
+            + doc.title.len()
+            + doc.id.len()
+            + metadata_bytes
+            + self.vectors.first().map_or(doc.embedding.len(), |row| row.len()) * std::mem::size_of::<f32>()) as u64
     }
 
-    /// 标记某个包的特定版本为已完整处理
-    pub fn mark_package_version_as_processed(&mut self, language: &str, package_name: &str, version: &str) -> Result<()> {
-        let key = format!("{}/{}/{}", language, package_name, version);
-        if self.processed_package_versions.insert(key.clone()) {
-            tracing::info!("已标记包版本 {} 为已处理。", key);
-            self.save() // 保存更改
-        } else {
-            tracing::debug!("包版本 {} 已被标记为已处理，无需重复标记。", key);
-            Ok(())
+    /// 按包版本统计占用字节数
+    fn package_bytes(&self) -> HashMap<String, u64> {
+        let mut bytes_by_package = HashMap::new();
+        for doc in self.documents.values() {
+            let key = package_version_key(&doc.language, &doc.package_name, &doc.version);
+            *bytes_by_package.entry(key).or_insert(0) += self.document_bytes(doc);
         }
+        bytes_by_package
     }
 
-    /// 混合搜索：向量相似度 + 关键词匹配
-    fn hybrid_search(&self, query_embedding: &[f32], query_text: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        // 1. 向量相似度搜索
-        let vector_results = self.search_similar(query_embedding, limit * 2)?; // 获取更多候选
-        
-        // 2. 关键词匹配增强
-        let query_lower = query_text.to_lowercase();
-        let query_keywords: std::collections::HashSet<String> = query_lower
-            .split_whitespace()
-            .filter(|word| word.len() > 2) // 过滤短词
-            .map(|word| word.to_string())
+    /// 更新包版本的访问时间，`search_hit` 为true时累加搜索命中次数
+    fn touch_package_version(&self, key: &str, search_hit: bool) {
+        let mut package_usage = self.package_usage.lock().unwrap();
+        let usage = package_usage.entry(key.to_string()).or_default();
+        usage.last_access = std::time::SystemTime::now();
+        if search_hit {
+            usage.search_hits += 1;
+        }
+    }
+
+    /// 记录搜索结果涉及的包版本（仅更新内存中的统计，随下次保存一起持久化）
+    fn record_search_hits(&self, results: &[SearchResult]) {
+        let keys: std::collections::HashSet<String> = results.iter()
+            .map(|r| package_version_key(&r.language, &r.package_name, &r.version))
             .collect();
-        
-        // 3. 重新计算混合分数
-        let mut enhanced_results: Vec<SearchResult> = vector_results
+        for key in keys {
+            self.touch_package_version(&key, true);
+        }
+    }
+
+    /// 所有包版本键，包括只有已处理标记或抓取进度、没有文档的版本
+    fn package_version_keys(&self) -> BTreeSet<String> {
+        self.documents.values()
+            .map(|doc| package_version_key(&doc.language, &doc.package_name, &doc.version))
+            .chain(self.processed_package_versions.iter().cloned())
+            .chain(self.package_progress.keys().cloned())
+            .collect()
+    }
+
+    /// 依赖键满足条件的包版本键
+    fn package_versions_matching(&self, matches: impl Fn(&str) -> bool) -> Vec<String> {
+        self.package_version_keys()
             .into_iter()
-            .map(|mut result| {
-                // 计算关键词匹配分数
-                let doc_content_lower = result.content.to_lowercase();
-                let doc_title_lower = result.title.to_lowercase();
-                
-                let mut keyword_score = 0.0;
-                let total_keywords = query_keywords.len() as f32;
-                
-                if total_keywords > 0.0 {
-                    for keyword in &query_keywords {
-                        let mut word_score: f32 = 0.0;
-                        
-                        // 标题匹配权重更高
-                        if doc_title_lower.contains(keyword) {
-                            word_score += 0.6;
-                        }
-                        
-                        // 内容匹配
-                        if doc_content_lower.contains(keyword) {
-                            word_score += 0.4;
-                        }
-                        
-                        // 精确匹配加分
-                        if doc_content_lower.contains(&format!(" {} ", keyword)) || 
-                           doc_title_lower.contains(&format!(" {} ", keyword)) {
-                            word_score += 0.2;
-                        }
-                        
-                        keyword_score += word_score.min(1.0);
-                    }
-                    keyword_score /= total_keywords;
-                }
-                
-                // 4. 语言和包名匹配加分
-                let mut context_bonus = 0.0;
-                if query_lower.contains(&result.language.to_lowercase()) {
-                    context_bonus += 0.1;
-                }
-                if query_lower.contains(&result.package_name.to_lowercase()) {
-                    context_bonus += 0.1;
-                }
-                
-                // 5. 混合分数计算：向量相似度60% + 关键词匹配30% + 上下文10%
-                result.score = result.score * 0.6 + keyword_score * 0.3 + context_bonus;
-                
-                // 6. 文档类型相关性调整
-                if query_lower.contains("api") && result.doc_type.contains("api") {
-                    result.score += 0.05;
-                }
-                if query_lower.contains("tutorial") && result.doc_type.contains("tutorial") {
-                    result.score += 0.05;
-                }
-                
-                result
+            .filter(|key| dependency_cleanup::dependency_key_of_package_version(key).map_or(false, |dep| matches(&dep)))
+            .collect()
+    }
+
+    /// 删除语言、包名和版本都匹配（None 表示不限）的包版本：全部文档、索引条目、已处理标记和抓取进度，
+    /// 返回 (删除的包版本键, 删除文档数, 释放字节数)
+    fn delete_by_filter(
+        &mut self,
+        language: Option<&str>,
+        package_name: Option<&str>,
+        version: Option<&str>,
+    ) -> Result<(Vec<String>, usize, u64)> {
+        let keys: Vec<String> = self.package_version_keys()
+            .into_iter()
+            .filter(|key| {
+                split_package_version_key(key).map_or(false, |(l, p, v)| {
+                    language.map_or(true, |language| language == l)
+                        && package_name.map_or(true, |package_name| package_name == p)
+                        && version.map_or(true, |version| version == v)
+                })
             })
             .collect();
-        
-        // 按新分数排序并返回指定数量的结果
-        enhanced_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-        enhanced_results.truncate(limit);
-        
-        Ok(enhanced_results)
+        if keys.is_empty() {
+            return Ok((keys, 0, 0));
+        }
+        let (removed_docs, removed_bytes) = self.remove_package_versions(&keys)?;
+        self.save()?;
+        Ok((keys, removed_docs, removed_bytes))
     }
-}
 
-/// 为了兼容旧的 PersistentData 格式，定义一个不包含 processed_package_versions 的结构
-#[derive(Debug, Serialize, Deserialize)]
-struct OldPersistentData {
-    documents: HashMap<String, DocumentRecord>,
-    vectors: Vec<Vec<f32>>,
-    vector_to_doc_id: Vec<String>,
-}
+    /// 整体删除若干包版本的所有文档，返回 (删除文档数, 释放字节数)
+    fn remove_package_versions(&mut self, keys: &[String]) -> Result<(usize, u64)> {
+        self.ensure_writable()?;
+        self.journal(&WalOp::RemovePackageVersions(keys.to_vec()))?;
+        let key_set: std::collections::HashSet<&String> = keys.iter().collect();
+        let removed_ids: std::collections::HashSet<String> = self.documents.values()
+            .filter(|doc| key_set.contains(&package_version_key(&doc.language, &doc.package_name, &doc.version)))
+            .map(|doc| doc.id.clone())
+            .collect();
+        let removed_bytes = self.remove_documents(&removed_ids)?;
 
-/// 嵌入式向量化文档工具
-pub struct VectorDocsTool {
-    /// 向量存储
-    store: Arc<Mutex<VectorStore>>,
-    /// HTTP客户端
-    client: Client,
-    /// NVIDIA API密钥
-    api_key: String,
-    /// 嵌入模型名称
-    model_name: String,
-    /// 参数schema
-    schema: Schema,
-    /// 语义嵌入缓存（文本内容 -> 嵌入向量）
-    embedding_cache: Arc<Mutex<HashMap<String, (Vec<f32>, std::time::SystemTime)>>>,
-}
+        for key in keys {
+            self.processed_package_versions.remove(key);
+            self.package_progress.remove(key);
+            self.package_usage.get_mut().unwrap().remove(key);
+        }
 
-impl Default for VectorDocsTool {
-    fn default() -> Self {
-        // 创建一个默认的占位工具，用于错误情况
-        let data_dir = std::env::current_dir()
-            .unwrap_or_else(|_| PathBuf::from("."))
-            .join(".vector_db");
-        
-        Self {
-            store: Arc::new(Mutex::new(VectorStore::new(data_dir))),
-            client: Client::new(),
-            api_key: String::new(),
-            model_name: "nvidia/nv-embedqa-e5-v5".to_string(),
-            schema: Self::create_schema(),
-            embedding_cache: Arc::new(Mutex::new(HashMap::new())),
+        if !removed_ids.is_empty() {
+            self.rebuild_index()?;
         }
+        Ok((removed_ids.len(), removed_bytes))
     }
-}
 
-impl VectorDocsTool {
-    /// 创建新的嵌入式向量化文档工具
-    pub fn new() -> Result<Self> {
-        // 加载环境变量
-        dotenv::dotenv().ok();
-        
-        // 必须有API密钥，不允许简化模式
-        let api_key = std::env::var("EMBEDDING_API_KEY")
-            .map_err(|_| anyhow::anyhow!(
-                "❌ 必须设置 EMBEDDING_API_KEY 环境变量才能使用向量化功能。\n\
-                 请在 .env 文件中配置：\n\
-                 EMBEDDING_API_KEY=your-actual-api-key\n\
-                 EMBEDDING_API_BASE_URL=https://integrate.api.nvidia.com/v1\n\
-                 EMBEDDING_MODEL_NAME=nvidia/nv-embedqa-mistral-7b-v2"
-            ))?;
-            
-        let model_name = std::env::var("EMBEDDING_MODEL_NAME")
-            .unwrap_or_else(|_| "nvidia/nv-embedqa-mistral-7b-v2".to_string());
+    /// 删除一批文档及其向量，返回释放的字节数；不重建索引
+    fn remove_documents(&mut self, removed_ids: &std::collections::HashSet<String>) -> Result<u64> {
+        if !removed_ids.is_empty() {
+            self.journal(&WalOp::Delete(removed_ids.iter().cloned().collect()))?;
+        }
+        let mut removed_bytes = 0;
+        for id in removed_ids {
+            if let Some(doc) = self.documents.get(id) {
+                removed_bytes += self.document_bytes(doc);
+            }
+            self.documents.remove(id);
+            self.symbol_index.remove_document(id);
+            self.text_index.remove(id);
+            self.discard_content(id);
+        }
 
-        // 创建数据目录
-        let data_dir = std::env::var("VECTOR_STORAGE_PATH")
-            .unwrap_or_else(|_| ".mcp_vector_data".to_string());
-        let data_path = PathBuf::from(data_dir);
-        
-        if !data_path.exists() {
-            fs::create_dir_all(&data_path)?;
+        let mut kept_vectors = Vec::with_capacity(self.vectors.len());
+        let mut kept_ids = Vec::with_capacity(self.vector_to_doc_id.len());
+        for (vector, doc_id) in self.vectors.drain(..).zip(self.vector_to_doc_id.drain(..)) {
+            if !removed_ids.contains(&doc_id) {
+                kept_vectors.push(vector);
+                kept_ids.push(doc_id);
+            }
         }
+        self.vectors = kept_vectors;
+        self.vector_to_doc_id = kept_ids;
+        Ok(removed_bytes)
+    }
 
-        let mut store = VectorStore::new(data_path);
-        
-        // 尝试加载现有数据
-        store.load()?;
+    /// 删除来源地址匹配 `pattern` 的文档并落盘，返回 (删除文档数, 释放字节数)
+    ///
+    /// `visible_only` 为 true 时跳过当前调用方看不到的（其他命名空间的）文档。
+    fn remove_by_source(&mut self, pattern: &str, visible_only: bool) -> Result<(usize, u64)> {
+        let removed_ids: std::collections::HashSet<String> = self.documents.values()
+            .filter(|doc| !visible_only || namespace::is_visible_metadata(&doc.metadata))
+            .filter(|doc| {
+                doc.metadata.get(provenance::SOURCE_URL_METADATA_KEY)
+                    .map_or(false, |source_url| provenance::source_matches(pattern, source_url))
+            })
+            .map(|doc| doc.id.clone())
+            .collect();
+        if removed_ids.is_empty() {
+            return Ok((0, 0));
+        }
+        self.ensure_writable()?;
+        let removed_bytes = self.remove_documents(&removed_ids)?;
+        self.rebuild_index()?;
+        self.save()?;
+        Ok((removed_ids.len(), removed_bytes))
+    }
 
-        Ok(Self {
-            store: Arc::new(Mutex::new(store)),
-            client: Client::new(),
-            api_key,
-            model_name,
-            schema: Self::create_schema(),
-            embedding_cache: Arc::new(Mutex::new(HashMap::new())),
-        })
+    /// 当前调用方可见文档中的近似重复簇
+    fn duplicate_clusters(&self, threshold: f32) -> Vec<DuplicateCluster> {
+        let rows = self.vector_rows();
+        let items: Vec<DedupItem> = self.documents.values()
+            .filter(|doc| namespace::is_visible_metadata(&doc.metadata))
+            .filter_map(|doc| {
+                let &row = rows.get(doc.id.as_str())?;
+                Some(DedupItem {
+                    id: &doc.id,
+                    vector: &self.vectors[row],
+                    content_len: self.offloaded.get(&doc.id).copied().unwrap_or(doc.content.len()),
+                })
+            })
+            .collect();
+        dedup::find_clusters(&items, threshold)
     }
 
-    /// 创建参数schema
-    fn create_schema() -> Schema {
-        Schema::Object(SchemaObject {
-            properties: {
-                let mut props = HashMap::new();
-                props.insert("action".to_string(), Schema::String(SchemaString {
-                    description: Some("操作类型: store(存储), search(搜索), get(获取), delete(删除)".to_string()),
-                    enum_values: Some(vec!["store".to_string(), "search".to_string(), "get".to_string(), "delete".to_string()]),
-                }));
-                props.insert("content".to_string(), Schema::String(SchemaString {
-                    description: Some("文档内容 (store操作必需)".to_string()),
-                    enum_values: None,
-                }));
-                props.insert("title".to_string(), Schema::String(SchemaString {
-                    description: Some("文档标题 (store操作可选)".to_string()),
-                    enum_values: None,
-                }));
-                props.insert("language".to_string(), Schema::String(SchemaString {
-                    description: Some("编程语言或文档语言 (store操作可选)".to_string()),
-                    enum_values: None,
-                }));
-                props.insert("doc_type".to_string(), Schema::String(SchemaString {
-                    description: Some("文档类型 (store操作可选)".to_string()),
-                    enum_values: None,
-                }));
-                props.insert("query".to_string(), Schema::String(SchemaString {
-                    description: Some("搜索查询 (search操作必需)".to_string()),
-                    enum_values: None,
-                }));
-                props.insert("id".to_string(), Schema::String(SchemaString {
-                    description: Some("文档ID (get/delete操作必需)".to_string()),
-                    enum_values: None,
-                }));
-                props.insert("limit".to_string(), Schema::String(SchemaString {
-                    description: Some("搜索结果限制 (search操作可选，默认5)".to_string()),
-                    enum_values: None,
-                }));
-                props
-            },
-            required: vec!["action".to_string()],
-            description: Some("嵌入式向量化文档管理工具参数".to_string()),
-        })
+    /// 删除各簇中保留文档以外的文档并落盘，返回 (删除文档数, 释放字节数)
+    fn merge_duplicates(&mut self, clusters: &[DuplicateCluster]) -> Result<(usize, u64)> {
+        let removed_ids: std::collections::HashSet<String> = clusters.iter()
+            .flat_map(|cluster| cluster.duplicates.iter().cloned())
+            .collect();
+        if removed_ids.is_empty() {
+            return Ok((0, 0));
+        }
+        self.ensure_writable()?;
+        let removed_bytes = self.remove_documents(&removed_ids)?;
+        self.rebuild_index()?;
+        self.save()?;
+        Ok((removed_ids.len(), removed_bytes))
     }
 
-    /// 生成文本的嵌入向量
-    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        // 生成内容哈希作为缓存键
-        let content_hash = format!("{:x}", md5::compute(text.as_bytes()));
-        
-        // 检查缓存
-        {
-            let cache = self.embedding_cache.lock().unwrap();
-            if let Some((embedding, timestamp)) = cache.get(&content_hash) {
-                // 检查缓存是否过期（24小时）
-                if timestamp.elapsed().unwrap_or(std::time::Duration::MAX) < std::time::Duration::from_secs(86400) {
-                    tracing::debug!("命中嵌入向量缓存，内容哈希: {}", &content_hash[..8]);
-                    return Ok(embedding.clone());
-                }
-            }
+    /// 按文档ID查找向量行
+    fn vector_rows(&self) -> HashMap<&str, usize> {
+        self.vector_to_doc_id.iter().enumerate().map(|(row, id)| (id.as_str(), row)).collect()
+    }
+
+    /// 带全文和嵌入向量的文档副本；从内存映射格式加载的文档不带向量副本，从向量行读取
+    fn exported_document(&self, doc: &DocumentRecord, rows: &HashMap<&str, usize>) -> DocumentRecord {
+        let embedding = match rows.get(doc.id.as_str()) {
+            Some(&row) if doc.embedding.is_empty() => self.vectors[row].to_vec(),
+            _ => doc.embedding.clone(),
+        };
+        DocumentRecord { content: self.full_content(doc), embedding, ..doc.clone() }
+    }
+
+    /// 存储的快照：全部文档（不区分命名空间）和已处理包版本标记
+    fn snapshot(&self, name: &str) -> StoreSnapshot<DocumentRecord> {
+        let rows = self.vector_rows();
+        let mut documents: Vec<DocumentRecord> = self.documents.values().map(|doc| self.exported_document(doc, &rows)).collect();
+        documents.sort_by(|a, b| a.id.cmp(&b.id));
+        StoreSnapshot {
+            name: name.to_string(),
+            documents,
+            processed_package_versions: self.processed_package_versions.iter().cloned().collect(),
         }
-        
-        // 缓存未命中，调用API
-        tracing::debug!("调用NVIDIA API生成嵌入向量，内容长度: {} 字符", text.len());
-        
-        let request = EmbeddingRequest {
-            input: vec![text.to_string()],
-            model: self.model_name.clone(),
-            input_type: "passage".to_string(),
+    }
+
+    /// 用快照替换存储内容：清除现有文档和包版本记录后写入快照中的文档
+    fn restore_from(&mut self, snapshots: Vec<StoreSnapshot<DocumentRecord>>) -> Result<()> {
+        self.ensure_writable()?;
+        let existing_ids: std::collections::HashSet<String> = self.documents.keys().cloned().collect();
+        self.remove_documents(&existing_ids)?;
+        // 快照的模型已在恢复前核对过，清除旧记录，下一次写入时按当前模型重新记录
+        self.forget_embedding_meta()?;
+        self.processed_package_versions.clear();
+        self.package_progress.clear();
+        self.package_usage.get_mut().unwrap().clear();
+
+        let mut documents = Vec::new();
+        for snapshot in snapshots {
+            self.processed_package_versions.extend(snapshot.processed_package_versions);
+            documents.extend(snapshot.documents);
+        }
+        if documents.is_empty() {
+            self.rebuild_index()?;
+            return self.save();
+        }
+        self.add_documents_batch(documents)
+    }
+
+    /// 超出容量上限时按策略淘汰整个包版本，返回被淘汰的包版本
+    fn enforce_size_limit(&mut self, protected: &[String]) -> Result<Vec<String>> {
+        let max_total_bytes = match self.eviction_config.max_total_bytes {
+            Some(limit) => limit,
+            None => return Ok(Vec::new()),
         };
 
-        let response = self.client
-            .post("https://ai.api.nvidia.com/v1/retrieval/nvidia/nv-embedqa-e5-v5/embeddings")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+        let victims = select_eviction_victims(
+            &self.package_bytes(),
+            &self.package_usage.lock().unwrap(),
+            max_total_bytes,
+            self.eviction_config.policy,
+            protected,
+        );
+        if victims.is_empty() {
+            return Ok(victims);
+        }
+
+        let (removed_docs, removed_bytes) = self.remove_package_versions(&victims)?;
+        self.eviction_stats.evicted_package_versions += victims.len() as u64;
+        self.eviction_stats.evicted_documents += removed_docs as u64;
+        self.eviction_stats.evicted_bytes += removed_bytes;
+        self.eviction_stats.last_evicted = victims.clone();
+        self.eviction_stats.last_eviction_at = Some(std::time::SystemTime::now());
 
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(anyhow::anyhow!("NVIDIA API请求失败: {}", error_text));
+        tracing::info!(
+            "缓存超出上限 {} 字节，按 {:?} 策略淘汰 {} 个包版本（{} 个文档，{} 字节）: {:?}",
+            max_total_bytes, self.eviction_config.policy, victims.len(), removed_docs, removed_bytes, victims
+        );
+        Ok(victims)
+    }
+
+    /// 缓存容量统计信息
+    fn cache_accounting_status(&self) -> Value {
+        let package_bytes = self.package_bytes();
+        let total_bytes: u64 = package_bytes.values().sum();
+        json!({
+            "total_bytes": total_bytes,
+            "max_total_bytes": self.eviction_config.max_total_bytes,
+            "eviction_policy": self.eviction_config.policy,
+            "package_bytes": package_bytes,
+            "evictions": self.eviction_stats,
+            "content_tier": {
+                "offloaded_documents": self.offloaded.len(),
+                "offload_min_bytes": self.content_tier.offload_min_bytes,
+                "summary_chars": self.content_tier.summary_chars,
+            },
+        })
+    }
+
+    /// 从磁盘加载数据
+    fn load(&mut self) -> Result<()> {
+        self.embedding_meta = EmbeddingMeta::load(&self.data_dir)?;
+        // 数据文件校验失败时从上一份一致的数据文件加载，并多重放一段日志
+        let Some((data_file, from_previous)) = write_ahead_log::consistent_data_file(&self.data_dir.join("vector_data.bin")) else {
+            // 首次运行，没有数据文件；也可能是第一次保存之前进程就退出了
+            self.load_progress();
+            return self.replay_wal(false);
+        };
+
+        // 只有内容无法解码的文件按损坏处理；版本不匹配的数据是完好的，I/O、内存映射和日志重放
+        // 失败也不能说明文件损坏，都交给调用方处理
+        match self.load_data_file(&data_file, from_previous) {
+            Err(e) if data_format::is_corrupt_data(&e) => {
+                self.discard_unreadable_data(&data_file, e)?;
+                // 在空存储上重放保留的全部日志，找回损坏文件之外还能恢复的修改
+                self.replay_wal(true)
+            }
+            result => result,
         }
+    }
 
-        let embedding_response: EmbeddingResponse = response.json().await?;
-        
-        if let Some(embedding_data) = embedding_response.data.first() {
-            let embedding = embedding_data.embedding.clone();
-            
-            // 更新缓存
-            {
-                let mut cache = self.embedding_cache.lock().unwrap();
-                
-                // 如果缓存太大，清理旧条目
-                if cache.len() > 1000 {
-                    let cutoff_time = std::time::SystemTime::now() - std::time::Duration::from_secs(43200); // 12小时
-                    cache.retain(|_, (_, timestamp)| *timestamp > cutoff_time);
+    fn load_data_file(&mut self, data_file: &std::path::Path, from_previous: bool) -> Result<()> {
+        // 只读跟随实例只在内存中迁移，不改写持锁实例的数据文件
+        let loaded = if self.access_mode().is_read_only() {
+            vector_data_format().decode(&fs::read(data_file)?)?
+        } else {
+            vector_data_format().load_file(data_file)?
+        };
+        self.loaded_mtime = self.data_file_mtime();
+        let persistent_data: PersistentData = bincode::deserialize(&loaded.payload)
+            .map_err(|e| data_format::CorruptData(format!("解析向量数据失败 (格式 v{}): {}", loaded.source_version, e)))?;
+
+        self.documents = persistent_data.documents;
+        self.vector_to_doc_id = persistent_data.vector_to_doc_id;
+        self.processed_package_versions = persistent_data.processed_package_versions.unwrap_or_default();
+        self.package_progress = persistent_data.package_progress;
+        self.load_progress();
+        self.offloaded = persistent_data.offloaded;
+        // 重新加载的数据按全部分片重建
+        self.shards.clear();
+        let index_loaded = match &persistent_data.layout {
+            Some(layout) => {
+                let vectors = Arc::new(MappedVectors::open(&layout.vectors_file(&self.data_dir))?);
+                if vectors.len() != self.vector_to_doc_id.len() {
+                    return Err(anyhow::anyhow!("向量文件行数 {} 与文档映射 {} 不一致", vectors.len(), self.vector_to_doc_id.len()));
                 }
-                
-                cache.insert(content_hash.clone(), (embedding.clone(), std::time::SystemTime::now()));
-                tracing::debug!("缓存嵌入向量，内容哈希: {}，当前缓存大小: {}", &content_hash[..8], cache.len());
+                self.vectors = mmap_vectors::mapped_rows(&vectors);
+                self.load_mapped_index(layout, vectors)
             }
-            
-            Ok(embedding)
+            None => {
+                self.vectors = persistent_data.vectors.into_iter().map(VectorRow::from).collect();
+                false
+            }
+        };
+        self.load_accounting();
+        self.rebuild_text_indexes();
+        if index_loaded {
+            self.sync_metadata_index();
         } else {
-            Err(anyhow::anyhow!("NVIDIA API返回空的嵌入向量"))
+            self.rebuild_index()?;
+        }
+        self.replay_wal(from_previous)?;
+        // 旧格式的数据在第一次加载时转换为内存映射格式
+        let needs_conversion = self.mmap_index && persistent_data.layout.is_none() && !self.vectors.is_empty();
+        if !self.access_mode().is_read_only() && (self.offload_hot_documents()? > 0 || needs_conversion) {
+            self.save()?;
         }
+        tracing::info!(
+            "从磁盘加载了 {} 个文档和 {} 个已处理包版本标记（数据格式 v{}）。",
+            self.documents.len(), self.processed_package_versions.len(), loaded.source_version
+        );
+        Ok(())
     }
 
-    /// 智能文档相似度检测（替代简单哈希比较）
-    /// 基于语义相似度和内容特征的综合评估
-    async fn calculate_document_similarity(&self, existing_content: &str, new_content: &str) -> Result<f32> {
-        // 1. 基础文本相似度检测
-        let text_similarity = self.calculate_text_similarity(existing_content, new_content);
-        
-        // 2. 结构化内容相似度
-        let structure_similarity = self.calculate_structure_similarity(existing_content, new_content);
-        
-        // 3. 语义关键词相似度
-        let keyword_similarity = self.calculate_keyword_similarity(existing_content, new_content);
-        
-        // 4. 长度和复杂度相似度
-        let complexity_similarity = self.calculate_complexity_similarity(existing_content, new_content);
-        
-        // 加权综合相似度分数
-        let weighted_similarity = 
-            text_similarity * 0.4 +           // 文本相似度权重40%
-            structure_similarity * 0.25 +     // 结构相似度权重25%
-            keyword_similarity * 0.25 +       // 关键词相似度权重25%
-            complexity_similarity * 0.1;      // 复杂度相似度权重10%
-        
-        Ok(weighted_similarity)
+    /// 数据文件内容损坏时备份原文件并重置为空存储，避免一个坏文件让服务无法启动
+    fn discard_unreadable_data(&mut self, data_file: &std::path::Path, error: anyhow::Error) -> Result<()> {
+        if self.access_mode().is_read_only() {
+            tracing::error!("无法读取向量数据 {:?}: {}，只读实例以空存储运行", data_file, error);
+        } else {
+            let mut backup = data_file.as_os_str().to_owned();
+            backup.push(format!(".corrupt-{}", chrono::Utc::now().format("%Y%m%d%H%M%S")));
+            let backup = PathBuf::from(backup);
+            fs::rename(data_file, &backup)?;
+            tracing::error!("无法读取向量数据 {:?}: {}，已备份为 {:?} 并创建新的向量库", data_file, error, backup);
+        }
+        self.documents = HashMap::new();
+        self.offloaded = HashMap::new();
+        self.vectors = Vec::new();
+        self.vector_to_doc_id = Vec::new();
+        self.processed_package_versions = Default::default();
+        self.package_progress = Default::default();
+        self.shards = Vec::new();
+        self.rebuild_text_indexes();
+        self.sync_metadata_index();
+        self.rebuild_index()
     }
-    
-    /// 计算文本相似度（混合模式：词频向量 + 语义嵌入）
-    fn calculate_text_similarity(&self, text1: &str, text2: &str) -> f32 {
-        if text1.is_empty() && text2.is_empty() {
-            return 1.0;
+
+    /// 修改内存之前写入预写日志（只读实例和重放日志时不写）
+    fn journal(&self, op: &WalOp<&DocumentRecord>) -> Result<()> {
+        match &self.wal {
+            Some(wal) if !self.access_mode().is_read_only() => wal.append(op),
+            _ => Ok(()),
         }
-        if text1.is_empty() || text2.is_empty() {
-            return 0.0;
+    }
+
+    /// 重放预写日志中的修改（只读实例不重放，由持锁实例负责恢复），有修改时重建索引并保存
+    fn replay_wal(&mut self, include_previous: bool) -> Result<()> {
+        if self.access_mode().is_read_only() {
+            return Ok(());
         }
-        
-        // 标准化文本
-        let normalized1 = self.normalize_text(text1);
-        let normalized2 = self.normalize_text(text2);
-        
-        // 1. 基于词频向量的余弦相似度（快速，离线）
-        let vector1 = self.build_word_frequency_vector(&normalized1);
-        let vector2 = self.build_word_frequency_vector(&normalized2);
-        let lexical_similarity = self.calculate_cosine_similarity(&vector1, &vector2);
-        
-        // 2. 尝试使用语义嵌入相似度（如果文本足够长且重要）
-        if text1.len() > 100 && text2.len() > 100 && !self.api_key.is_empty() {
-            // 异步调用嵌入API会比较复杂，这里使用同步的备用方案
-            // 实际应用中可以考虑缓存常用文本的嵌入向量
-            
-            // 现在先使用增强的词频分析
-            let enhanced_similarity = self.calculate_enhanced_lexical_similarity(&normalized1, &normalized2);
-            
-            // 混合权重：70%词频 + 30%增强分析
-            lexical_similarity * 0.7 + enhanced_similarity * 0.3
-        } else {
-            lexical_similarity
+        let Some(wal) = self.wal.take() else {
+            return Ok(());
+        };
+        let entries: Vec<WalOp<DocumentRecord>> = wal.entries(include_previous);
+        let replayed = entries.len();
+        let applied = self.apply_wal_entries(entries);
+        self.wal = Some(wal);
+        applied?;
+        if replayed > 0 {
+            tracing::info!("从预写日志重放了 {} 条修改: {:?}", replayed, self.data_dir);
+            self.rebuild_index()?;
+            self.save()?;
         }
+        Ok(())
     }
-    
-    /// 增强的词汇相似度分析（基于语义场和上下文）
-    fn calculate_enhanced_lexical_similarity(&self, text1: &str, text2: &str) -> f32 {
-        // 1. N-gram相似度分析
-        let bigrams1 = self.extract_ngrams(text1, 2);
-        let bigrams2 = self.extract_ngrams(text2, 2);
-        let bigram_similarity = self.calculate_set_similarity(&bigrams1, &bigrams2);
-        
-        // 2. 技术术语权重提升
-        let tech_terms1 = self.extract_technical_terms(text1);
-        let tech_terms2 = self.extract_technical_terms(text2);
-        let tech_similarity = self.calculate_set_similarity(&tech_terms1, &tech_terms2);
-        
-        // 3. 语义场相似度（同义词和相关概念）
-        let semantic_similarity = self.calculate_semantic_field_similarity(text1, text2);
-        
-        // 加权组合
-        bigram_similarity * 0.4 + tech_similarity * 0.4 + semantic_similarity * 0.2
-    }
-    
-    /// 提取N-gram特征
-    fn extract_ngrams(&self, text: &str, n: usize) -> std::collections::HashSet<String> {
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let mut ngrams = std::collections::HashSet::new();
-        
-        for i in 0..=words.len().saturating_sub(n) {
-            let ngram = words[i..i + n].join(" ");
-            if ngram.len() >= 4 { // 过滤太短的ngram
-                ngrams.insert(ngram);
+
+    fn apply_wal_entries(&mut self, entries: Vec<WalOp<DocumentRecord>>) -> Result<()> {
+        for op in entries {
+            match op {
+                WalOp::Insert(docs) => {
+                    self.insert_documents(docs, None)?;
+                }
+                WalOp::Delete(ids) => {
+                    self.remove_documents(&ids.into_iter().collect())?;
+                }
+                WalOp::MarkProcessed(key) => {
+                    self.processed_package_versions.insert(key);
+                }
+                WalOp::RemovePackageVersions(keys) => {
+                    for key in keys {
+                        self.processed_package_versions.remove(&key);
+                        self.package_progress.remove(&key);
+                        self.package_usage.get_mut().unwrap().remove(&key);
+                    }
+                }
             }
         }
-        
-        ngrams
+        Ok(())
     }
-    
-    /// 提取技术术语
-    fn extract_technical_terms(&self, text: &str) -> std::collections::HashSet<String> {
-        let mut tech_terms = std::collections::HashSet::new();
-        let text_lower = text.to_lowercase();
-        
-        // 编程相关术语词典
-        let programming_terms = [
-            "api", "sdk", "framework", "library", "function", "method", "class", "interface",
-            "async", "await", "promise", "callback", "closure", "lambda", "generic", "template",
-            "memory", "thread", "process", "concurrency", "parallelism", "synchronization",
-            "mutex", "lock", "atomic", "volatile", "pointer", "reference", "ownership",
-            "compilation", "runtime", "virtual", "machine", "garbage", "collection",
-            "serialization", "deserialization", "encoding", "decoding", "protocol",
-            "http", "https", "tcp", "udp", "websocket", "database", "sql", "nosql"
-        ];
-        
-        for term in programming_terms {
-            if text_lower.contains(term) {
-                tech_terms.insert(term.to_string());
+
+    /// 保存数据到磁盘
+    fn save(&self) -> Result<()> {
+        // 只读跟随实例不写磁盘
+        if self.access_mode().is_read_only() {
+            return Ok(());
+        }
+        // 休眠状态下内存中没有文档，磁盘上的数据才是最新的
+        if self.hibernated {
+            if !self.documents.is_empty() || !self.vectors.is_empty() {
+                return Err(anyhow::anyhow!("向量存储处于休眠状态却有未落盘的修改，拒绝保存以免覆盖磁盘数据: {:?}", self.data_dir));
             }
+            self.save_progress()?;
+            return self.save_accounting();
         }
+
+        // 确保数据目录存在
+        fs::create_dir_all(&self.data_dir)?;
         
-        // 提取大写的标识符（通常是类名、常量等）
-        for word in text.split_whitespace() {
-            if word.len() > 2 && word.chars().next().unwrap().is_uppercase() {
-                tech_terms.insert(word.to_lowercase());
-            }
+        let layout = self.write_mapped_files()?;
+        let persistent_data = PersistentData {
+            // 内存映射格式下向量只保存在向量文件中
+            documents: match layout {
+                Some(_) => self.documents.iter()
+                    .map(|(id, doc)| (id.clone(), DocumentRecord { embedding: Vec::new(), ..doc.clone() }))
+                    .collect(),
+                None => self.documents.clone(),
+            },
+            vectors: match layout {
+                Some(_) => Vec::new(),
+                None => self.vectors.iter().map(|row| row.to_vec()).collect(),
+            },
+            vector_to_doc_id: self.vector_to_doc_id.clone(),
+            processed_package_versions: Some(self.processed_package_versions.clone()),
+            package_progress: self.package_progress.clone(),
+            offloaded: self.offloaded.clone(),
+            layout,
+        };
+        
+        let data = vector_data_format().encode(&bincode::serialize(&persistent_data)?);
+        let data_file = self.data_dir.join("vector_data.bin");
+        // 先写临时文件再替换，只读实例不会读到写了一半的数据；上一份数据文件保留用于崩溃恢复
+        let tmp_file = data_file.with_extension("bin.tmp");
+        fs::write(&tmp_file, &data)?;
+        write_ahead_log::commit_data_file(&data_file, &tmp_file, &data)?;
+        // 日志中的修改都已落盘
+        if let Some(wal) = &self.wal {
+            wal.rotate()?;
         }
+        if layout.is_some() {
+            mmap_vectors::remove_stale_generations(&self.data_dir);
+        }
+        self.save_progress()?;
+        self.save_accounting()?;
         
-        tech_terms
+        tracing::debug!("向量数据（包含已处理包版本标记）已保存到: {:?}", data_file);
+        Ok(())
     }
-    
-    /// 计算集合相似度（Jaccard Index）
-    fn calculate_set_similarity(&self, set1: &std::collections::HashSet<String>, set2: &std::collections::HashSet<String>) -> f32 {
-        if set1.is_empty() && set2.is_empty() {
-            return 1.0;
+
+    /// 以内存映射格式写入新一代向量文件和 HNSW 图；未启用或向量维度不一致时返回 None
+    fn write_mapped_files(&self) -> Result<Option<MappedLayout>> {
+        if !self.mmap_index {
+            return Ok(None);
         }
-        if set1.is_empty() || set2.is_empty() {
-            return 0.0;
+        let dim = self.vectors.first().map_or(0, |row| row.len());
+        if self.vectors.iter().any(|row| row.len() != dim) {
+            tracing::warn!("向量维度不一致，改用默认格式保存: {:?}", self.data_dir);
+            return Ok(None);
         }
-        
-        let intersection = set1.intersection(set2).count() as f32;
-        let union = set1.union(set2).count() as f32;
-        
-        intersection / union
+        let mut layout = MappedLayout {
+            generation: mmap_vectors::new_generation(),
+            dim,
+            rows: self.vectors.len(),
+            metric: self.distance_metric,
+            has_index: false,
+        };
+        MappedVectors::write(&layout.vectors_file(&self.data_dir), dim, self.vectors.iter().map(|row| &row[..]))?;
+        // 量化索引依赖训练结果，启动时重建
+        if let Some(VectorIndex::Exact(map)) = &self.search_index {
+            mmap_vectors::save_index(&layout.index_file(&self.data_dir), map)?;
+            layout.has_index = true;
+        }
+        Ok(Some(layout))
     }
-    
-    /// 计算语义场相似度（基于预定义的概念关系）
-    fn calculate_semantic_field_similarity(&self, text1: &str, text2: &str) -> f32 {
-        // 定义语义场映射
-        let semantic_fields = [
-            (vec!["rust", "cargo", "crate", "rustc"], "rust_ecosystem"),
-            (vec!["python", "pip", "conda", "pypi"], "python_ecosystem"),
+
+    /// 加载持久化的 HNSW 图；度量或量化配置已变化、文件缺失或损坏时返回 false（改为重建索引）
+    fn load_mapped_index(&mut self, layout: &MappedLayout, vectors: Arc<MappedVectors>) -> bool {
+        let exact = self.quantization.mode == QuantizationMode::None || self.vectors.len() < self.quantization.min_vectors.max(1);
+        // 持久化的是单个索引，启用分片时按分片重建
+        if !layout.has_index || layout.metric != self.distance_metric || !exact || self.sharding.shard_count().is_some() {
+            return false;
+        }
+        match mmap_vectors::load_index(&layout.index_file(&self.data_dir), vectors, self.distance_metric) {
+            Ok(map) => {
+                self.search_index = Some(VectorIndex::Exact(map));
+                self.quantizer = None;
+                true
+            }
+            Err(e) => {
+                tracing::warn!("加载 HNSW 索引失败，改为重建: {}", e);
+                false
+            }
+        }
+    }
+
+    /// 全文超过阈值时写入磁盘，内存中只保留摘要
+    fn offload_content(&mut self, doc: &mut DocumentRecord) -> Result<()> {
+        if self.offloaded.contains_key(&doc.id) || !self.content_tier.should_offload(&doc.content) {
+            return Ok(());
+        }
+        self.content_store.write(&doc.id, &doc.content)?;
+        self.offloaded.insert(doc.id.clone(), doc.content.len());
+        doc.content = content_store::summarize(&doc.content, self.content_tier.summary_chars);
+        Ok(())
+    }
+
+    /// 将内存中超过阈值的全文落盘（例如升级前入库的文档），返回落盘的文档数
+    fn offload_hot_documents(&mut self) -> Result<usize> {
+        let mut documents = std::mem::take(&mut self.documents);
+        let before = self.offloaded.len();
+        let result = documents.values_mut().try_for_each(|doc| self.offload_content(doc));
+        self.documents = documents;
+        result?;
+        let offloaded = self.offloaded.len() - before;
+        if offloaded > 0 {
+            tracing::info!("已将 {} 个文档的全文移至磁盘: {:?}", offloaded, self.data_dir);
+        }
+        Ok(offloaded)
+    }
+
+    /// 删除文档的落盘全文
+    fn discard_content(&mut self, doc_id: &str) {
+        if self.offloaded.remove(doc_id).is_some() {
+            if let Err(e) = self.content_store.remove(doc_id) {
+                tracing::warn!("删除文档 {} 的落盘全文失败: {}", doc_id, e);
+            }
+        }
+    }
+
+    /// 文档的完整内容：全文已落盘时从磁盘读取，读取失败时退回摘要
+    fn full_content(&self, doc: &DocumentRecord) -> String {
+        if !self.offloaded.contains_key(&doc.id) {
+            return doc.content.clone();
+        }
+        self.content_store.read(&doc.id).unwrap_or_else(|e| {
+            tracing::warn!("{}，返回摘要", e);
+            doc.content.clone()
+        })
+    }
+
+    /// 为最终结果加载全文（候选打分只用内存中的摘要）
+    fn hydrate(&self, results: &mut [SearchResult]) {
+        for result in results.iter_mut().filter(|r| self.offloaded.contains_key(&r.id)) {
+            if let Some(doc) = self.documents.get(&result.id) {
+                result.content = self.full_content(doc);
+            }
+        }
+    }
+
+    /// 添加单个文档记录（已存在的ID跳过），重建索引后保存
+    #[cfg(test)]
+    fn add_document(&mut self, doc: DocumentRecord) -> Result<()> {
+        self.add_documents_batch(vec![doc])
+    }
+
+    /// 批量添加文档记录，并在完成后重建索引和保存
+    fn add_documents_batch(&mut self, docs: Vec<DocumentRecord>) -> Result<()> {
+        let (new_docs_count, touched_packages) = self.insert_documents(docs, None)?;
+        if new_docs_count > 0 {
+            self.rebuild_index()?;
+            self.finish_insert(&touched_packages)?;
+            tracing::info!("成功批量添加 {} 个新文档记录到向量库并已保存。", new_docs_count);
+        } else {
+            tracing::info!("批量添加操作中没有新的文档被添加。");
+        }
+        Ok(())
+    }
+
+    /// 写入文档记录和向量（已存在的ID跳过），不重建向量索引；返回新增文档数和涉及的包版本
+    ///
+    /// `model` 为生成这些向量的嵌入模型，与库中记录的模型或维度不一致时拒绝写入；重放日志和恢复快照时
+    /// 为 None，不再核对（日志中的写入在记录前已核对过，快照在恢复前已核对模型）。
+    fn insert_documents(&mut self, docs: Vec<DocumentRecord>, model: Option<&str>) -> Result<(usize, Vec<String>)> {
+        self.ensure_writable()?;
+        let mut new_docs_count = 0;
+        let mut touched_packages = Vec::new();
+        let pending: Vec<&DocumentRecord> = docs.iter().filter(|doc| !self.documents.contains_key(&doc.id)).collect();
+        if let Some(model) = model {
+            self.check_embeddings(&pending, model)?;
+        }
+        if !pending.is_empty() {
+            self.journal(&WalOp::Insert(pending))?;
+        }
+        for mut doc in docs {
+            let doc_id = doc.id.clone();
+            // 检查文档是否已存在，如果存在则可以考虑更新或跳过
+            if self.documents.contains_key(&doc_id) {
+                tracing::debug!("文档 {} 已存在于批处理中，跳过添加。", doc_id);
+                continue; 
+            }
+            let embedding = doc.embedding.clone();
+            let package_key = package_version_key(&doc.language, &doc.package_name, &doc.version);
+            if !touched_packages.contains(&package_key) {
+                touched_packages.push(package_key);
+            }
+            stamp_created_at(&mut doc);
+            self.symbol_index.insert_document(&doc.id, &doc.package_name, &doc.title, &doc.content);
+            self.text_index.insert(&doc.id, &self.analyzers.tokenize(&doc.title), &self.analyzers.tokenize(&doc.content));
+            self.offload_content(&mut doc)?;
+
+            self.documents.insert(doc_id.clone(), doc);
+            self.vectors.push(VectorRow::from(embedding));
+            self.vector_to_doc_id.push(doc_id.clone());
+            new_docs_count += 1;
+        }
+        Ok((new_docs_count, touched_packages))
+    }
+
+    /// 核对待写入向量与库中记录的嵌入模型和维度；没有记录时以已有向量的维度为准，一致时补上记录
+    fn check_embeddings(&mut self, pending: &[&DocumentRecord], model: &str) -> Result<()> {
+        let Some(dimension) = pending.first().map(|doc| doc.embedding.len()) else {
+            return Ok(());
+        };
+        if let Some(doc) = pending.iter().find(|doc| doc.embedding.len() != dimension) {
+            return Err(VectorDbError::InvalidVectorDimension { expected: dimension, actual: doc.embedding.len() }.into());
+        }
+        let (stored_model, stored_dimension) = match (&self.embedding_meta, self.vector_dimension()) {
+            (Some(meta), _) => (meta.model.clone(), meta.dimension),
+            (None, Some(existing)) if existing != dimension => (UNKNOWN_EMBEDDING_MODEL.to_string(), existing),
+            (None, _) => return self.record_embedding_meta(EmbeddingMeta::new(model, dimension)),
+        };
+        if stored_dimension != dimension || stored_model != model {
+            return Err(VectorDbError::EmbeddingModelMismatch {
+                stored_model,
+                stored_dimension,
+                model: model.to_string(),
+                dimension,
+            }.into());
+        }
+        Ok(())
+    }
+
+    /// 记录生成库中向量的嵌入模型并落盘
+    fn record_embedding_meta(&mut self, meta: EmbeddingMeta) -> Result<()> {
+        meta.save(&self.data_dir)?;
+        self.embedding_meta = Some(meta);
+        Ok(())
+    }
+
+    /// 清除嵌入模型记录（库中向量被整体替换前调用），下一次写入时重新记录
+    fn forget_embedding_meta(&mut self) -> Result<()> {
+        self.embedding_meta = None;
+        match fs::remove_file(self.data_dir.join(EMBEDDING_META_FILE)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// 用新模型生成的向量替换全部文档（文档内容不变），重建索引后落盘并记录新模型
+    fn replace_embeddings(&mut self, documents: Vec<DocumentRecord>, model: &str) -> Result<usize> {
+        self.ensure_writable()?;
+        let ids: std::collections::HashSet<String> = documents.iter().map(|doc| doc.id.clone()).collect();
+        self.remove_documents(&ids)?;
+        self.forget_embedding_meta()?;
+        // 旧量化器和分片按旧向量训练、划分，全部丢弃后重新构建
+        self.quantizer = None;
+        self.shards.clear();
+        let (replaced, touched_packages) = self.insert_documents(documents, Some(model))?;
+        self.rebuild_index()?;
+        self.finish_insert(&touched_packages)?;
+        Ok(replaced)
+    }
+
+    /// 新文档写入并建好索引后更新包版本访问时间、按容量上限淘汰并落盘
+    fn finish_insert(&mut self, touched_packages: &[String]) -> Result<()> {
+        for package_key in touched_packages {
+            self.touch_package_version(package_key, false);
+        }
+        self.enforce_size_limit(touched_packages)?;
+        self.save() // 所有新文档添加完成后保存一次
+    }
+
+    /// 重建符号索引和全文索引（加载时调用，之后随文档增删增量维护）；全文已落盘的文档从磁盘读取全文
+    fn rebuild_text_indexes(&mut self) {
+        let mut symbol_index = SymbolIndex::new();
+        let mut text_index = Bm25Index::new();
+        for doc in self.documents.values() {
+            let content = self.full_content(doc);
+            symbol_index.insert_document(&doc.id, &doc.package_name, &doc.title, &content);
+            text_index.insert(&doc.id, &self.analyzers.tokenize(&doc.title), &self.analyzers.tokenize(&content));
+        }
+        self.symbol_index = symbol_index;
+        self.text_index = text_index;
+    }
+
+    /// 重建向量索引；启用分片时只重建成员有变化的分片
+    fn rebuild_index(&mut self) -> Result<()> {
+        if self.vectors.is_empty() {
+            self.search_index = None;
+            self.shards.clear();
+            return Ok(());
+        }
+        let rebuild = self.index_build();
+        let indexes = rebuild.build();
+        self.install_index(&rebuild, indexes);
+        Ok(())
+    }
+
+    /// 取出构建向量索引所需的数据（向量按引用计数共享，复制代价很小），可以在锁外构建
+    fn index_build(&mut self) -> IndexRebuild {
+        let quantizer = self.prepare_quantizer();
+        let metric = self.distance_metric;
+        let doc_ids = self.vector_to_doc_id.clone();
+        let Some(shard_count) = self.sharding.shard_count() else {
+            let build = IndexBuild { vectors: self.vectors.clone(), doc_ids: doc_ids.clone(), metric, quantizer };
+            return IndexRebuild { doc_ids, builds: vec![(0, build)], shard_count: None };
+        };
+
+        let mut shards: Vec<IndexBuild> = (0..shard_count)
+            .map(|_| IndexBuild { vectors: Vec::new(), doc_ids: Vec::new(), metric, quantizer: quantizer.clone() })
+            .collect();
+        for (doc_id, vector) in self.vector_to_doc_id.iter().zip(&self.vectors) {
+            let language = self.documents.get(doc_id).map_or("", |doc| doc.language.as_str());
+            let shard = &mut shards[self.sharding.shard_of(language, doc_id)];
+            shard.vectors.push(vector.clone());
+            shard.doc_ids.push(doc_id.clone());
+        }
+        let builds = shards.into_iter()
+            .enumerate()
+            .filter(|(i, build)| self.shards.get(*i).map_or(true, |shard| !shard.is_current(build)))
+            .collect();
+        IndexRebuild { doc_ids, builds, shard_count: Some(shard_count) }
+    }
+
+    /// 换上在锁外构建好的索引；构建期间向量已被其他写入改变时放弃并返回 false
+    fn install_index(&mut self, rebuild: &IndexRebuild, indexes: Vec<Option<VectorIndex>>) -> bool {
+        if self.vector_to_doc_id != rebuild.doc_ids {
+            return false;
+        }
+        match rebuild.shard_count {
+            None => {
+                self.search_index = indexes.into_iter().next().flatten();
+                self.shards.clear();
+            }
+            Some(shard_count) => {
+                self.search_index = None;
+                self.shards.truncate(shard_count);
+                while self.shards.len() < shard_count {
+                    self.shards.push(IndexShard { doc_ids: Vec::new(), vectors: Vec::new(), quantizer: None, index: None });
+                }
+                for ((shard, build), index) in rebuild.builds.iter().zip(indexes) {
+                    self.shards[*shard] = IndexShard {
+                        doc_ids: build.doc_ids.clone(),
+                        vectors: build.vectors.clone(),
+                        quantizer: build.quantizer.clone(),
+                        index,
+                    };
+                }
+            }
+        }
+        self.sync_metadata_index();
+        true
+    }
+
+    /// 是否已有可用的向量索引（未分片的索引或任一分片的索引）
+    fn has_index(&self) -> bool {
+        self.search_index.is_some() || self.shards.iter().any(|shard| shard.index.is_some())
+    }
+
+    /// 同步 SQLite 元数据索引（随向量索引一起更新）
+    fn sync_metadata_index(&mut self) {
+        #[cfg(feature = "database")]
+        {
+            let synced = self.metadata_index.as_mut().map(|index| index.get_mut().unwrap().sync(&self.documents));
+            if let Some(Err(e)) = synced {
+                tracing::warn!("同步 SQLite 元数据索引失败，改为内存过滤: {}", e);
+                self.metadata_index = None;
+            }
+        }
+    }
+
+    /// 按配置准备索引使用的量化器，向量数不足 `min_vectors` 时不量化
+    fn prepare_quantizer(&mut self) -> Option<Arc<Quantizer>> {
+        let config = self.quantization;
+        if config.mode == QuantizationMode::None || self.vectors.len() < config.min_vectors.max(1) {
+            self.quantizer = None;
+            return None;
+        }
+        let dim = self.vectors[0].len();
+        if let Some((quantizer, trained_on)) = &self.quantizer {
+            if quantizer.mode() == config.mode && quantizer.dim() == dim && self.vectors.len() < trained_on.saturating_mul(2) {
+                return Some(quantizer.clone());
+            }
+        }
+        let quantizer = Quantizer::train(&config, &self.vectors).map(Arc::new);
+        if quantizer.is_none() {
+            tracing::warn!("向量维度不一致，索引不做量化: {:?}", self.data_dir);
+        }
+        self.quantizer = quantizer.clone().map(|q| (q, self.vectors.len()));
+        quantizer
+    }
+
+    fn quantization_status(&self) -> Value {
+        let active = matches!(self.search_index, Some(VectorIndex::Quantized { .. }))
+            || self.shards.iter().any(|shard| matches!(shard.index, Some(VectorIndex::Quantized { .. })));
+        json!({
+            "mode": self.quantization.mode.as_str(),
+            "active": active,
+            "bytes_per_vector": self.quantizer.as_ref()
+                .filter(|_| active)
+                .map(|(quantizer, _)| quantizer.bytes_per_vector()),
+            "rerank_factor": self.quantization.rerank_factor,
+        })
+    }
+
+    fn sharding_status(&self) -> Value {
+        json!({
+            "strategy": self.sharding.strategy.as_str(),
+            "shards": self.sharding.shard_count().unwrap_or(1),
+            "vectors_per_shard": self.shards.iter().map(|shard| shard.doc_ids.len()).collect::<Vec<_>>(),
+        })
+    }
+
+    /// 满足过滤条件且当前调用方的命名空间可见
+    fn matches_filter(doc: &DocumentRecord, filter: &SearchFilter) -> bool {
+        Self::matches_filter_for(doc, filter, namespace::current().as_deref())
+    }
+
+    /// 满足过滤条件且 `caller` 命名空间可见，用于不在调用方任务内执行的并行查询
+    fn matches_filter_for(doc: &DocumentRecord, filter: &SearchFilter, caller: Option<&str>) -> bool {
+        namespace::is_metadata_visible_to(&doc.metadata, caller)
+            && filter.matches(&doc.language, &doc.package_name, &doc.version, &doc.doc_type, &doc.metadata)
+    }
+
+    fn similarity_result(&self, doc: &DocumentRecord, distance: f32) -> SearchResult {
+        Self::scored_result(doc, self.distance_metric.similarity(distance))
+    }
+
+    fn scored_result(doc: &DocumentRecord, score: f32) -> SearchResult {
+        SearchResult {
+            id: doc.id.clone(),
+            content: doc.content.clone(),
+            title: doc.title.clone(),
+            language: doc.language.clone(),
+            package_name: doc.package_name.clone(),
+            version: doc.version.clone(),
+            doc_type: doc.doc_type.clone(),
+            metadata: doc.metadata.clone(),
+            score,
+        }
+    }
+
+    /// BM25 全文检索，返回 (文档ID, BM25 分数)，只包含满足过滤条件的文档
+    fn text_hits(&self, query_text: &str, limit: usize, filter: &SearchFilter) -> Vec<(String, f32)> {
+        let tokens = self.analyzers.tokenize(query_text);
+        self.text_index.search(&tokens, limit, |id| {
+            self.documents.get(id).map_or(false, |doc| Self::matches_filter(doc, filter))
+        })
+    }
+
+    /// 全文搜索：按 BM25 分数排序，不需要查询向量
+    fn text_search(&self, query_text: &str, limit: usize, filter: &SearchFilter) -> Vec<SearchResult> {
+        let mut results: Vec<SearchResult> = self.text_hits(query_text, limit, filter)
+            .into_iter()
+            .filter_map(|(id, score)| Some(Self::scored_result(self.documents.get(&id)?, score)))
+            .collect();
+        for result in &mut results {
+            score_normalization::normalize_result(ScoreKind::Text, result);
+        }
+        self.hydrate(&mut results);
+        results
+    }
+
+    /// 向量相似度搜索，只返回满足过滤条件的文档
+    ///
+    /// HNSW 只返回有限的近邻候选，过滤条件（或其他命名空间的文档）排除了部分候选而剩余不足
+    /// `limit` 个时，改为对满足条件的向量做精确扫描。量化索引取 `limit * rerank_factor` 个候选，
+    /// 按原始向量重新计算距离后排序。启用分片时并行查询各分片后按分数合并。
+    fn search_similar(&self, query_embedding: &[f32], limit: usize, filter: &SearchFilter) -> Result<Vec<SearchResult>> {
+        let (results, excluded) = if self.shards.is_empty() {
+            match &self.search_index {
+                Some(index) => {
+                    let caller = namespace::current();
+                    self.search_index_with(index, &self.vector_to_doc_id, &self.vectors, query_embedding, limit, filter, caller.as_deref())
+                }
+                None => return Ok(Vec::new()),
+            }
+        } else {
+            self.search_shards(query_embedding, limit, filter)
+        };
+        if results.len() >= limit || !excluded {
+            return Ok(results);
+        }
+
+        // 启用 SQLite 元数据索引时只扫描 SQL 筛出的候选
+        let candidates = self.sql_candidates(filter);
+        let mut scanned: Vec<(f32, &DocumentRecord)> = self.vector_to_doc_id.iter()
+            .zip(&self.vectors)
+            .filter(|(doc_id, _)| candidates.as_ref().map_or(true, |ids| ids.contains(*doc_id)))
+            .filter_map(|(doc_id, vector)| {
+                let doc = self.documents.get(doc_id).filter(|doc| Self::matches_filter(doc, filter))?;
+                Some((self.distance_metric.distance(query_embedding, vector), doc))
+            })
+            .collect();
+        scanned.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(scanned.into_iter()
+            .take(limit)
+            .map(|(distance, doc)| self.similarity_result(doc, distance))
+            .collect())
+    }
+
+    /// 并行查询各分片并按分数合并；按语言分片且过滤条件指定了语言时只查询该语言所在的分片
+    ///
+    /// rayon 线程上读不到调用方任务的命名空间，先取出再传给各分片的可见性判断。
+    fn search_shards(&self, query_embedding: &[f32], limit: usize, filter: &SearchFilter) -> (Vec<SearchResult>, bool) {
+        let caller = namespace::current();
+        let only_shard = match (self.sharding.strategy, &filter.language) {
+            (ShardStrategy::Language, Some(language)) => Some(self.sharding.shard_of(language, "")),
+            _ => None,
+        };
+        let per_shard: Vec<(Vec<SearchResult>, bool)> = self.shards.par_iter()
+            .enumerate()
+            .filter(|(i, _)| only_shard.map_or(true, |only| only == *i))
+            .filter_map(|(_, shard)| {
+                let index = shard.index.as_ref()?;
+                Some(self.search_index_with(index, &shard.doc_ids, &shard.vectors, query_embedding, limit, filter, caller.as_deref()))
+            })
+            .collect();
+        let excluded = per_shard.iter().any(|(_, excluded)| *excluded);
+        let mut results: Vec<SearchResult> = per_shard.into_iter().flat_map(|(results, _)| results).collect();
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results.truncate(limit);
+        (results, excluded)
+    }
+
+    /// 在一个索引中取满足过滤条件且 `caller` 命名空间可见的近邻，`doc_ids`/`vectors` 是构建该索引时的向量；
+    /// 返回结果和是否有候选被过滤条件排除
+    #[allow(clippy::too_many_arguments)]
+    fn search_index_with(
+        &self,
+        search_index: &VectorIndex,
+        doc_ids: &[String],
+        vectors: &[VectorRow],
+        query_embedding: &[f32],
+        limit: usize,
+        filter: &SearchFilter,
+        caller: Option<&str>,
+    ) -> (Vec<SearchResult>, bool) {
+        let mut search = Search::default();
+        let mut excluded = false;
+        let results: Vec<SearchResult> = match search_index {
+            VectorIndex::Exact(map) => {
+                let query_point = VectorPoint::query(query_embedding, self.distance_metric);
+                map.search(&query_point, &mut search)
+                    .filter_map(|item| {
+                        let doc = self.documents.get(item.value.as_str())?;
+                        let matched = Self::matches_filter_for(doc, filter, caller);
+                        excluded |= !matched;
+                        matched.then(|| self.similarity_result(doc, item.distance))
+                    })
+                    .take(limit)
+                    .collect()
+            }
+            VectorIndex::Quantized { map, quantizer } => {
+                let query_point = QuantizedPoint::new(quantizer.clone(), self.distance_metric, query_embedding);
+                let mut candidates: Vec<(f32, &DocumentRecord)> = map.search(&query_point, &mut search)
+                    .filter_map(|item| {
+                        let index = *item.value;
+                        let doc = self.documents.get(doc_ids.get(index)?)?;
+                        let matched = Self::matches_filter_for(doc, filter, caller);
+                        excluded |= !matched;
+                        matched.then(|| (self.distance_metric.distance(query_embedding, &vectors[index]), doc))
+                    })
+                    .take(limit.saturating_mul(self.quantization.rerank_factor.max(1)))
+                    .collect();
+                candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+                candidates.into_iter()
+                    .take(limit)
+                    .map(|(distance, doc)| self.similarity_result(doc, distance))
+                    .collect()
+            }
+        };
+        (results, excluded)
+    }
+
+    /// 获取文档（含全文）
+    fn get_document(&self, doc_id: &str) -> Option<DocumentRecord> {
+        self.documents.get(doc_id).map(|doc| DocumentRecord { content: self.full_content(doc), ..doc.clone() })
+    }
+
+    fn contains_document(&self, doc_id: &str) -> bool {
+        self.documents.contains_key(doc_id)
+    }
+
+    fn delete_document(&mut self, doc_id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        if self.documents.contains_key(doc_id) {
+            self.journal(&WalOp::Delete(vec![doc_id.to_string()]))?;
+        }
+        if let Some(_) = self.documents.remove(doc_id) {
+            self.symbol_index.remove_document(doc_id);
+            self.text_index.remove(doc_id);
+            self.discard_content(doc_id);
+            // 找到并移除对应的向量
+            if let Some(pos) = self.vector_to_doc_id.iter().position(|id| id == doc_id) {
+                self.vectors.remove(pos);
+                self.vector_to_doc_id.remove(pos);
+                
+                // 重建索引
+                self.rebuild_index()?;
+                
+                // 自动保存
+                self.save()?;
+                
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// 获取统计信息
+    fn get_stats(&self) -> (usize, usize) {
+        (self.documents.len(), self.vectors.len())
+    }
+
+    /// 已存储向量的维度（没有向量或已休眠时为 None）
+    fn vector_dimension(&self) -> Option<usize> {
+        self.vectors.first().map(|vector| vector.len())
+    }
+
+    /// 检查某个包的特定版本是否已被标记为完整处理
+    pub fn has_processed_package_version(&self, language: &str, package_name: &str, version: &str) -> bool {
+        let key = format!("{}/{}/{}", language, package_name, version);
+        self.processed_package_versions.contains(&key)
+    }
+
+    /// 标记某个包的特定版本为已完整处理
+    pub fn mark_package_version_as_processed(&mut self, language: &str, package_name: &str, version: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let key = format!("{}/{}/{}", language, package_name, version);
+        self.package_progress.entry(key.clone())
+            .or_insert_with(PackageProgress::started)
+            .complete();
+        if !self.processed_package_versions.contains(&key) {
+            self.journal(&WalOp::MarkProcessed(key.clone()))?;
+        }
+        if self.processed_package_versions.insert(key.clone()) {
+            tracing::info!("已标记包版本 {} 为已处理。", key);
+            self.save() // 保存更改
+        } else {
+            tracing::debug!("包版本 {} 已被标记为已处理，无需重复标记。", key);
+            Ok(())
+        }
+    }
+
+    /// 尝试认领包版本的处理权：已完成或他人正在处理时返回 false
+    fn try_claim_package_version(&mut self, key: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        if self.processed_package_versions.contains(key) {
+            return Ok(false);
+        }
+        let now = std::time::SystemTime::now();
+        match self.package_progress.get_mut(key) {
+            Some(progress) if !progress.is_claimable(now) => return Ok(false),
+            Some(progress) => {
+                tracing::info!(
+                    "恢复包版本 {} 的处理（第 {} 次尝试，已抓取 {} 页，已存储 {} 个片段）",
+                    key, progress.attempts + 1, progress.pages_fetched, progress.fragments_stored
+                );
+                progress.resume();
+            }
+            None => {
+                self.package_progress.insert(key.to_string(), PackageProgress::started());
+            }
+        }
+        self.save_progress()?;
+        Ok(true)
+    }
+
+    fn update_package_progress(&mut self, key: &str, update: impl FnOnce(&mut PackageProgress)) -> Result<()> {
+        self.ensure_writable()?;
+        update(self.package_progress.entry(key.to_string()).or_insert_with(PackageProgress::started));
+        self.save_progress()
+    }
+
+    /// 符号精确匹配：查询是标识符时返回定义该符号的片段，分数高于所有语义结果
+    fn symbol_search(&self, query_text: &str, limit: usize, filter: &SearchFilter) -> Vec<SearchResult> {
+        let Some(symbol) = symbol_index::identifier_query(query_text) else {
+            return Vec::new();
+        };
+        self.symbol_index.lookup(&symbol)
+            .into_iter()
+            .filter_map(|hit| {
+                let doc = self.documents.get(&hit.doc_id).filter(|doc| Self::matches_filter(doc, filter))?;
+                let mut metadata = doc.metadata.clone();
+                metadata.insert("match_type".to_string(), "symbol".to_string());
+                let score = match (hit.exact, hit.source) {
+                    (true, SymbolSource::Heading) => 2.0,
+                    (true, SymbolSource::Declaration) | (false, SymbolSource::Heading) => 1.8,
+                    (false, SymbolSource::Declaration) => 1.6,
+                };
+                Some(SearchResult {
+                    id: doc.id.clone(),
+                    content: doc.content.clone(),
+                    title: doc.title.clone(),
+                    language: doc.language.clone(),
+                    package_name: doc.package_name.clone(),
+                    version: doc.version.clone(),
+                    doc_type: doc.doc_type.clone(),
+                    metadata,
+                    score,
+                })
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// 混合搜索：符号精确匹配优先，其余为向量相似度 + BM25 关键词分数，均只返回满足过滤条件的文档
+    fn hybrid_search(&self, query_embedding: &[f32], query_text: &str, limit: usize, filter: &SearchFilter) -> Result<Vec<SearchResult>> {
+        // 0. 标识符查询先走符号索引
+        let mut symbol_results = self.symbol_search(query_text, limit, filter);
+        if symbol_results.len() >= limit {
+            for result in &mut symbol_results {
+                score_normalization::normalize_result(ScoreKind::Symbol, result);
+            }
+            self.hydrate(&mut symbol_results);
+            return Ok(symbol_results);
+        }
+
+        // 1. 向量相似度搜索
+        let mut candidates = self.search_similar(query_embedding, limit * 2, filter)?; // 获取更多候选
+
+        // 2. 全文索引补充关键词命中而向量未召回的文档（这些文档的向量分数记为 0）
+        let text_hits = self.text_hits(query_text, limit * 2, filter);
+        let vector_ids: std::collections::HashSet<String> = candidates.iter().map(|r| r.id.clone()).collect();
+        candidates.extend(text_hits.iter()
+            .filter(|(id, _)| !vector_ids.contains(id))
+            .filter_map(|(id, _)| Some(Self::scored_result(self.documents.get(id)?, 0.0))));
+
+        // 3. 以归一化的 BM25 分数作为关键词分数重新计算混合分数（候选较多时并行）
+        let scorer = HybridScorer::new(query_text, Vec::new()).with_text_scores(&text_hits);
+        let symbol_ids: std::collections::HashSet<String> = symbol_results.iter().map(|r| r.id.clone()).collect();
+        let mut enhanced_results = scorer.rank(candidates, &symbol_ids);
+        for result in &mut symbol_results {
+            score_normalization::normalize_result(ScoreKind::Symbol, result);
+        }
+        for result in &mut enhanced_results {
+            score_normalization::normalize_result(ScoreKind::Hybrid, result);
+        }
+
+        let mut results = symbol_results;
+        results.extend(enhanced_results);
+        results.truncate(limit);
+        self.hydrate(&mut results);
+        
+        Ok(results)
+    }
+}
+
+/// 记录文档入库时间，已有（如从文档包导入）时保留
+fn stamp_created_at(doc: &mut DocumentRecord) {
+    doc.metadata
+        .entry(CREATED_AT_METADATA_KEY.to_string())
+        .or_insert_with(|| chrono::Utc::now().to_rfc3339());
+}
+
+/// 为了兼容旧的 PersistentData 格式，定义一个不包含 processed_package_versions 的结构
+#[derive(Debug, Serialize, Deserialize)]
+struct OldPersistentData {
+    documents: HashMap<String, DocumentRecord>,
+    vectors: Vec<Vec<f32>>,
+    vector_to_doc_id: Vec<String>,
+}
+
+/// v2 格式的持久化结构（尚无包处理进度）
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistentDataV2 {
+    documents: HashMap<String, DocumentRecord>,
+    vectors: Vec<Vec<f32>>,
+    vector_to_doc_id: Vec<String>,
+    processed_package_versions: Option<std::collections::HashSet<String>>,
+}
+
+/// v3 格式的持久化结构（全文均在内存中）
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistentDataV3 {
+    documents: HashMap<String, DocumentRecord>,
+    vectors: Vec<Vec<f32>>,
+    vector_to_doc_id: Vec<String>,
+    processed_package_versions: Option<std::collections::HashSet<String>>,
+    package_progress: HashMap<String, PackageProgress>,
+}
+
+/// v4 格式的持久化结构（向量保存在数据文件中）
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistentDataV4 {
+    documents: HashMap<String, DocumentRecord>,
+    vectors: Vec<Vec<f32>>,
+    vector_to_doc_id: Vec<String>,
+    processed_package_versions: Option<std::collections::HashSet<String>>,
+    package_progress: HashMap<String, PackageProgress>,
+    offloaded: HashMap<String, usize>,
+}
+
+/// 向量数据文件的当前格式版本
+///
+/// - v1: 无文件头的 `OldPersistentData`
+/// - v2: `PersistentDataV2`（增加已处理包版本标记），自 v2 起带版本文件头
+/// - v3: `PersistentDataV3`（增加包处理进度）
+/// - v4: `PersistentDataV4`（增加全文落盘的文档列表）
+/// - v5: `PersistentData`（增加内存映射向量文件的引用）
+///
+/// 修改持久化结构时递增版本号，并在 `vector_data_format` 中注册对应迁移。
+const VECTOR_DATA_FORMAT_VERSION: u32 = 5;
+
+fn vector_data_format() -> MigrationRegistry {
+    MigrationRegistry::new(VECTOR_DATA_FORMAT_VERSION)
+        .register(1, "增加已处理包版本标记", migrate_vector_data_v1_to_v2)
+        .register(2, "增加包处理进度", migrate_vector_data_v2_to_v3)
+        .register(3, "增加全文落盘的文档列表", migrate_vector_data_v3_to_v4)
+        .register(4, "增加内存映射向量文件的引用", migrate_vector_data_v4_to_v5)
+        .register_legacy(2, |data| bincode::deserialize::<PersistentDataV2>(data).is_ok())
+        .register_legacy(1, |data| bincode::deserialize::<OldPersistentData>(data).is_ok())
+}
+
+fn migrate_vector_data_v1_to_v2(payload: &[u8]) -> Result<Vec<u8>> {
+    let old: OldPersistentData = bincode::deserialize(payload)?;
+    Ok(bincode::serialize(&PersistentDataV2 {
+        documents: old.documents,
+        vectors: old.vectors,
+        vector_to_doc_id: old.vector_to_doc_id,
+        processed_package_versions: Some(std::collections::HashSet::new()),
+    })?)
+}
+
+fn migrate_vector_data_v2_to_v3(payload: &[u8]) -> Result<Vec<u8>> {
+    let old: PersistentDataV2 = bincode::deserialize(payload)?;
+    let processed = old.processed_package_versions.unwrap_or_default();
+    // 旧版本的已处理标记视为已完成
+    let package_progress = processed.iter()
+        .map(|key| {
+            let mut progress = PackageProgress::started();
+            progress.complete();
+            (key.clone(), progress)
+        })
+        .collect();
+    Ok(bincode::serialize(&PersistentDataV3 {
+        documents: old.documents,
+        vectors: old.vectors,
+        vector_to_doc_id: old.vector_to_doc_id,
+        processed_package_versions: Some(processed),
+        package_progress,
+    })?)
+}
+
+fn migrate_vector_data_v3_to_v4(payload: &[u8]) -> Result<Vec<u8>> {
+    let old: PersistentDataV3 = bincode::deserialize(payload)?;
+    // 旧数据的全文都在内存中，加载后按当前配置落盘
+    Ok(bincode::serialize(&PersistentDataV4 {
+        documents: old.documents,
+        vectors: old.vectors,
+        vector_to_doc_id: old.vector_to_doc_id,
+        processed_package_versions: old.processed_package_versions,
+        package_progress: old.package_progress,
+        offloaded: HashMap::new(),
+    })?)
+}
+
+fn migrate_vector_data_v4_to_v5(payload: &[u8]) -> Result<Vec<u8>> {
+    let old: PersistentDataV4 = bincode::deserialize(payload)?;
+    // 向量仍在数据文件中，加载后按配置转换为内存映射格式
+    Ok(bincode::serialize(&PersistentData {
+        documents: old.documents,
+        vectors: old.vectors,
+        vector_to_doc_id: old.vector_to_doc_id,
+        processed_package_versions: old.processed_package_versions,
+        package_progress: old.package_progress,
+        offloaded: old.offloaded,
+        layout: None,
+    })?)
+}
+
+/// 默认集合，不指定集合的操作都作用于全局层和工作区层
+pub const DEFAULT_COLLECTION: &str = "default";
+
+/// 其他集合的数据目录：`<全局层目录>/collections/<集合名>`
+pub const COLLECTIONS_DIR: &str = "collections";
+
+/// 集合名只允许字母、数字、`-` 和 `_`，最长 64 个字符
+pub fn is_valid_collection_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// 共享的向量存储：搜索等只读操作持读锁并发执行，写入持写锁
+type SharedStore = Arc<RwLock<VectorStore>>;
+
+/// 进程内已打开的数据目录
+///
+/// 数据目录锁按打开的锁文件持有，同一进程内再次加锁会失败并退化为只读跟随，
+/// 因此每个目录在进程内只打开一次，多个 `VectorDocsTool` 共享同一个存储实例。
+static OPEN_STORES: std::sync::OnceLock<Mutex<HashMap<PathBuf, std::sync::Weak<RwLock<VectorStore>>>>> = std::sync::OnceLock::new();
+
+/// 重排时取 `limit * RERANK_CANDIDATE_FACTOR` 个融合候选交给重排模型
+const RERANK_CANDIDATE_FACTOR: usize = 3;
+
+/// 交给重排模型的每个候选最多取这么多字符（标题 + 内容开头）
+const RERANK_PASSAGE_CHARS: usize = 2000;
+
+/// 嵌入式向量化文档工具
+pub struct VectorDocsTool {
+    /// 向量存储（全局层：按用户共享，按包名/版本区分）
+    store: SharedStore,
+    /// 工作区覆盖层（项目本地文档），与全局层目录相同时为None
+    workspace_store: Option<SharedStore>,
+    /// HTTP客户端
+    client: Client,
+    /// 嵌入服务（离线打开的存储没有）
+    embedder: Option<Arc<dyn TextEmbedder>>,
+    /// 重排模型（配置了 `RERANK_API_KEY` 时可用），search 操作传 `rerank: true` 时使用
+    reranker: Option<Arc<dyn Reranker>>,
+    /// 参数schema
+    schema: Schema,
+    /// 嵌入向量缓存（模型、用途、内容哈希 -> 嵌入向量），`new` 创建的工具持久化到全局缓存目录
+    embedding_cache: Arc<Mutex<EmbeddingCache>>,
+    /// 批量嵌入的分批、并发与限流退避，吞吐量指标在其中的 `MetricsCollector`
+    embedding_batcher: Arc<AdaptiveBatcher>,
+    /// 最近一次嵌入服务健康检查的结果（启动时和定期检查）
+    embedding_health: std::sync::RwLock<Option<EmbeddingHealth>>,
+    /// 最近一次访问存储的时间（用于空闲休眠）
+    last_activity: Arc<Mutex<std::time::Instant>>,
+    /// 新增文档通知（MCP 资源订阅）
+    updates: tokio::sync::broadcast::Sender<CachedDocsUpdate>,
+    /// 入库前的质量闸门
+    quality_gate: QualityGate,
+    /// 当前项目画像，用于按项目依赖调整搜索排序
+    project_profile: std::sync::RwLock<Option<ProjectProfile>>,
+    /// 关键词打分用的分词器（按文本语言选择）
+    analyzers: AnalyzerRegistry,
+    /// 已打开的集合（集合名 -> 存储），按需打开
+    collections: std::sync::RwLock<HashMap<String, SharedStore>>,
+}
+
+impl Default for VectorDocsTool {
+    fn default() -> Self {
+        // 创建一个默认的占位工具，用于错误情况
+        let data_dir = std::env::current_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join(".vector_db");
+        
+        Self {
+            store: Arc::new(RwLock::new(VectorStore::new(data_dir))),
+            workspace_store: None,
+            client: Client::new(),
+            embedder: None,
+            reranker: None,
+            schema: Self::create_schema(),
+            embedding_cache: Arc::new(Mutex::new(EmbeddingCache::in_memory(EmbeddingCacheConfig::default()))),
+            embedding_batcher: Arc::new(AdaptiveBatcher::new(BatchingConfig::default(), Arc::new(MetricsCollector::new()))),
+            embedding_health: std::sync::RwLock::new(None),
+            last_activity: Arc::new(Mutex::new(std::time::Instant::now())),
+            updates: tokio::sync::broadcast::channel(256).0,
+            quality_gate: QualityGate::default(),
+            project_profile: std::sync::RwLock::new(None),
+            analyzers: AnalyzerRegistry::default(),
+            collections: std::sync::RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl VectorDocsTool {
+    /// 创建新的嵌入式向量化文档工具
+    pub fn new() -> Result<Self> {
+        // 解析两级缓存目录：全局层 + 工作区覆盖层
+        Self::with_tier_paths(CacheTierPaths::from_env())
+    }
+
+    /// 在指定目录下创建单层向量存储，嵌入服务和其他配置与 `new` 相同
+    pub fn open_data_dir(data_dir: PathBuf) -> Result<Self> {
+        Self::with_tier_paths(CacheTierPaths::single(data_dir))
+    }
+
+    fn with_tier_paths(tier_paths: CacheTierPaths) -> Result<Self> {
+        // 加载环境变量
+        dotenv::dotenv().ok();
+        
+        // 默认必须有API密钥；EMBEDDING_PROVIDER=hash 时使用离线的确定性哈希嵌入
+        let client = Client::new();
+        let embedder = embedder_from_env(client.clone())?;
+
+        let global_store = Self::open_store(tier_paths.global_dir.clone())?;
+        let workspace_store = if tier_paths.is_single_tier() {
+            None
+        } else {
+            Some(Self::open_store(tier_paths.workspace_dir.clone())?)
+        };
+
+        tracing::info!(
+            "向量缓存层级: 全局={:?}, 工作区={:?}",
+            tier_paths.global_dir,
+            workspace_store.as_ref().map(|_| &tier_paths.workspace_dir)
+        );
+
+        Ok(Self {
+            store: global_store,
+            workspace_store,
+            client,
+            embedder: Some(embedder),
+            reranker: DocumentReranker::from_env().ok().map(|reranker| Arc::new(reranker) as Arc<dyn Reranker>),
+            schema: Self::create_schema(),
+            embedding_cache: Arc::new(Mutex::new(EmbeddingCache::open(&tier_paths.global_dir, EmbeddingCacheConfig::from_config(&SystemConfig::load().vector_search)))),
+            embedding_batcher: Arc::new(AdaptiveBatcher::new(BatchingConfig::from_env(), Arc::new(MetricsCollector::new()))),
+            embedding_health: std::sync::RwLock::new(None),
+            last_activity: Arc::new(Mutex::new(std::time::Instant::now())),
+            updates: tokio::sync::broadcast::channel(256).0,
+            quality_gate: QualityGate::from_env(),
+            project_profile: std::sync::RwLock::new(None),
+            analyzers: AnalyzerRegistry::from_config(&SystemConfig::load().text_analysis),
+            collections: std::sync::RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// 打开指定目录下的单层向量存储，不配置嵌入服务
+    ///
+    /// 只能写入已带嵌入向量的文档（见 `add_documents`），查询时需由调用方提供查询向量，
+    /// 用于离线工具和基准测试。
+    pub fn open_local(data_dir: PathBuf) -> Result<Self> {
+        Ok(Self {
+            store: Self::open_store(data_dir)?,
+            ..Self::default()
+        })
+    }
+
+    /// 替换嵌入服务（测试中使用不访问网络的实现）
+    pub fn with_embedder(mut self, embedder: Arc<dyn TextEmbedder>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
+    /// 替换重排模型（测试中使用不访问网络的实现）
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    fn embedder(&self) -> Result<&Arc<dyn TextEmbedder>> {
+        self.embedder.as_ref().ok_or_else(|| anyhow::anyhow!("未配置嵌入服务，无法生成嵌入向量"))
+    }
+
+    /// 嵌入模型名称
+    pub(crate) fn model_name(&self) -> &str {
+        self.embedder.as_ref().map_or(DEFAULT_EMBEDDING_MODEL, |embedder| embedder.model_name())
+    }
+
+    /// 打开（必要时创建）指定目录下的向量存储并加载已有数据
+    fn open_store(data_path: PathBuf) -> Result<SharedStore> {
+        let policy = LockPolicy::from_env();
+        if !data_path.exists() && policy != LockPolicy::Replica {
+            fs::create_dir_all(&data_path)?;
+        }
+        // 同一目录已在进程内打开时直接共享，打开过程持有注册表锁，避免并发打开两次
+        let key = data_path.canonicalize().unwrap_or_else(|_| data_path.clone());
+        let mut open_stores = OPEN_STORES.get_or_init(Default::default).lock().unwrap();
+        if let Some(store) = open_stores.get(&key).and_then(|store| store.upgrade()) {
+            return Ok(store);
+        }
+
+        let data_lock = DataDirLock::acquire(&data_path, policy)?;
+        let mut store = VectorStore::new(data_path);
+        store.data_lock = Some(data_lock);
+        store.open_metadata_backend(VectorSearchConfig::metadata_backend())?;
+        store.load()?;
+
+        let store = Arc::new(RwLock::new(store));
+        open_stores.retain(|_, store| store.strong_count() > 0);
+        open_stores.insert(key, Arc::downgrade(&store));
+        Ok(store)
+    }
+
+    /// 以写锁锁定存储并记录访问时间，存储处于休眠状态时先从磁盘恢复
+    fn acquire_store<'a>(&self, store: &'a SharedStore) -> Result<RwLockWriteGuard<'a, VectorStore>> {
+        *self.last_activity.lock().unwrap() = std::time::Instant::now();
+        let mut guard = store.write().unwrap();
+        // 恢复失败时存储仍是空的休眠状态，写入其中的数据无法落盘，必须把错误交给调用方
+        guard.wake().map_err(|e| MCPError::ServerError(format!("从休眠中恢复向量存储失败: {}", e)))?;
+        if let Err(e) = guard.refresh_if_stale() {
+            tracing::warn!("重新加载只读向量存储失败: {}", e);
+        }
+        Ok(guard)
+    }
+
+    /// 以读锁锁定存储（搜索、读取等只读操作），多个读取可以并发；
+    /// 存储需要从休眠中恢复或重新加载时先短暂取写锁完成，恢复失败时返回错误，
+    /// 不以空存储回答查询
+    fn read_store<'a>(&self, store: &'a SharedStore) -> Result<RwLockReadGuard<'a, VectorStore>> {
+        *self.last_activity.lock().unwrap() = std::time::Instant::now();
+        {
+            let guard = store.read().unwrap();
+            if !guard.needs_refresh() {
+                return Ok(guard);
+            }
+        }
+        drop(self.acquire_store(store)?);
+        Ok(store.read().unwrap())
+    }
+
+    /// 写入已带嵌入向量的文档，返回新增文档数
+    ///
+    /// 写锁内只插入文档，HNSW 索引在锁外按写时复制方式构建后整体换上，构建期间搜索继续使用
+    /// 旧索引，不必等待写入完成（新文档换上索引后才能被向量检索到）。
+    fn ingest_documents(&self, store: &SharedStore, docs: Vec<DocumentRecord>) -> Result<usize> {
+        let (added, touched_packages, mut build) = {
+            let mut guard = self.acquire_store(store)?;
+            let (added, touched_packages) = guard.insert_documents(docs, Some(self.model_name()))?;
+            if added == 0 {
+                return Ok(0);
+            }
+            (added, touched_packages, guard.index_build())
+        };
+        loop {
+            let index = build.build();
+            let mut guard = self.acquire_store(store)?;
+            if guard.install_index(&build, index) {
+                guard.finish_insert(&touched_packages)?;
+                return Ok(added);
+            }
+            // 构建期间有其他写入改变了向量，按最新数据重新构建
+            build = guard.index_build();
+        }
+    }
+
+    /// 获取指定层级对应的存储，未启用工作区层时统一落到全局层
+    fn store_for_tier(&self, tier: CacheTier) -> &SharedStore {
+        match (tier, &self.workspace_store) {
+            (CacheTier::Workspace, Some(workspace)) => workspace,
+            _ => &self.store,
+        }
+    }
+
+    /// 所有已启用的层级，工作区层优先
+    fn tier_stores(&self) -> Vec<(CacheTier, &SharedStore)> {
+        let mut stores = Vec::with_capacity(2);
+        if let Some(workspace) = &self.workspace_store {
+            stores.push((CacheTier::Workspace, workspace));
+        }
+        stores.push((CacheTier::Global, &self.store));
+        stores
+    }
+
+    fn collections_dir(&self) -> PathBuf {
+        self.store.read().unwrap().data_dir.join(COLLECTIONS_DIR)
+    }
+
+    /// 参数中的集合名，未指定或指定默认集合时返回 None
+    fn collection_param(args: &Value) -> Option<&str> {
+        args.get("collection")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != DEFAULT_COLLECTION)
+    }
+
+    fn validate_collection_name(name: &str) -> Result<()> {
+        if is_valid_collection_name(name) {
+            Ok(())
+        } else {
+            Err(MCPError::InvalidParameter(format!("无效的集合名: {:?}（只允许字母、数字、- 和 _，最长 64 个字符）", name)).into())
+        }
+    }
+
+    fn ensure_collections_writable(&self) -> Result<()> {
+        if self.store.read().unwrap().access_mode().is_read_only() {
+            return Err(MCPError::AuthorizationError("当前实例为只读模式，不能创建或删除集合".to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// 获取集合的存储，尚未打开的从磁盘打开；集合不存在时返回 NotFound
+    fn collection_store(&self, name: &str) -> Result<SharedStore> {
+        Self::validate_collection_name(name)?;
+        if let Some(store) = self.collections.read().unwrap().get(name) {
+            return Ok(store.clone());
+        }
+        let dir = self.collections_dir().join(name);
+        if !dir.is_dir() {
+            return Err(MCPError::NotFound(format!("集合 {} 不存在", name)).into());
+        }
+        let mut collections = self.collections.write().unwrap();
+        if let Some(store) = collections.get(name) {
+            return Ok(store.clone());
+        }
+        let store = Self::open_store(dir)?;
+        collections.insert(name.to_string(), store.clone());
+        Ok(store)
+    }
+
+    /// 创建集合，已存在时返回 false
+    pub fn create_collection(&self, name: &str) -> Result<bool> {
+        Self::validate_collection_name(name)?;
+        self.ensure_collections_writable()?;
+        let dir = self.collections_dir().join(name);
+        if name == DEFAULT_COLLECTION || dir.is_dir() {
+            return Ok(false);
+        }
+        fs::create_dir_all(&dir)?;
+        self.collection_store(name)?;
+        Ok(true)
+    }
+
+    /// 删除集合及其全部文档，默认集合不能删除；集合不存在时返回 false
+    pub fn drop_collection(&self, name: &str) -> Result<bool> {
+        Self::validate_collection_name(name)?;
+        if name == DEFAULT_COLLECTION {
+            return Err(MCPError::InvalidParameter("默认集合不能删除".to_string()).into());
+        }
+        self.ensure_collections_writable()?;
+        // 先释放存储（及其目录锁）再删除目录
+        self.collections.write().unwrap().remove(name);
+        let dir = self.collections_dir().join(name);
+        if !dir.is_dir() {
+            return Ok(false);
+        }
+        fs::remove_dir_all(&dir)?;
+        Ok(true)
+    }
+
+    /// 列出所有集合（默认集合在前）及各自的文档数、向量数和索引状态
+    pub fn list_collections(&self) -> Result<Vec<Value>> {
+        let stats = |name: &str, stores: &[&SharedStore]| {
+            let (mut documents, mut vectors, mut indexed) = (0, 0, true);
+            for store in stores {
+                let store = self.read_store(store)?;
+                let (store_docs, store_vectors) = store.get_stats();
+                documents += store_docs;
+                vectors += store_vectors;
+                indexed &= store_vectors == 0 || store.has_index();
+            }
+            Ok::<_, anyhow::Error>(json!({ "name": name, "documents": documents, "vectors": vectors, "indexed": indexed }))
+        };
+
+        let default_stores: Vec<&SharedStore> = self.tier_stores().into_iter().map(|(_, store)| store).collect();
+        let mut collections = vec![stats(DEFAULT_COLLECTION, &default_stores)?];
+        for name in self.collection_names()? {
+            let store = self.collection_store(&name)?;
+            collections.push(stats(&name, &[&store])?);
+        }
+        Ok(collections)
+    }
+
+    /// 磁盘上已有的非默认集合名（已排序）
+    fn collection_names(&self) -> Result<Vec<String>> {
+        let dir = self.collections_dir();
+        let mut names = Vec::new();
+        if dir.is_dir() {
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                if entry.file_type()?.is_dir() && is_valid_collection_name(&name) && name != DEFAULT_COLLECTION {
+                    names.push(name);
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// 合并多个层级的搜索结果：同ID文档以先出现的层级（工作区）为准，再按分数排序截断
+    fn merge_tier_results(tiered_results: Vec<(CacheTier, Vec<SearchResult>)>, limit: usize) -> Vec<SearchResult> {
+        let mut seen_ids = std::collections::HashSet::new();
+        let mut merged = Vec::new();
+        for (tier, results) in tiered_results {
+            for mut result in results {
+                if seen_ids.insert(result.id.clone()) {
+                    result.metadata.insert("cache_tier".to_string(), tier.as_str().to_string());
+                    merged.push(result);
+                }
+            }
+        }
+        merged.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        merged.truncate(limit);
+        merged
+    }
+
+    /// 创建参数schema
+    fn create_schema() -> Schema {
+        Schema::Object(SchemaObject {
+            properties: {
+                let mut props = HashMap::new();
+                props.insert("action".to_string(), Schema::String(SchemaString {
+                    description: Some("操作类型: store(存储), search(搜索), get(获取), delete(删除), list(分页列出已缓存文档), import_pack(导入预置文档包), import_devdocs(导入DevDocs文档集), enrich_qa(补充Stack Overflow问答，需开启GRAPE_QA_ENRICHMENT), create_collection/drop_collection/list_collections(管理集合), purge_source(按来源地址清除文档), purge_removed_dependencies(清除项目已移除依赖的缓存文档), delete_by_filter(按语言/包名/版本删除整个包版本), export/import(把文档和嵌入向量导出到数据目录 exports 下的 JSONL/Parquet 语料文件，或从中导入), dedup_report(列出语料中的近似重复文档簇，merge=true 时自动合并)".to_string()),
+                    enum_values: Some(vec!["store".to_string(), "search".to_string(), "get".to_string(), "delete".to_string(), "list".to_string(), "import_pack".to_string(), "import_devdocs".to_string(), "enrich_qa".to_string(), "create_collection".to_string(), "drop_collection".to_string(), "list_collections".to_string(), "purge_source".to_string(), "purge_removed_dependencies".to_string(), "delete_by_filter".to_string(), "export".to_string(), "import".to_string(), "dedup_report".to_string()]),
+                }));
+                props.insert("content".to_string(), Schema::String(SchemaString {
+                    description: Some("文档内容 (store操作必需)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("title".to_string(), Schema::String(SchemaString {
+                    description: Some("文档标题 (store操作可选)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("language".to_string(), Schema::String(SchemaString {
+                    description: Some("编程语言或文档语言 (store操作可选；search操作时按语言过滤；delete_by_filter操作的删除条件)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("doc_type".to_string(), Schema::String(SchemaString {
+                    description: Some("文档类型 (store操作可选；search操作时按类型过滤，如 qa 只返回社区问答)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("query".to_string(), Schema::String(SchemaString {
+                    description: Some("搜索查询 (search操作必需)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("mode".to_string(), Schema::String(SchemaString {
+                    description: Some("搜索模式 (search操作可选)：hybrid 为向量相似度加 BM25 关键词（默认），text 只做 BM25 全文检索、不生成查询向量".to_string()),
+                    enum_values: Some(vec!["hybrid".to_string(), "text".to_string()]),
+                }));
+                props.insert("id".to_string(), Schema::String(SchemaString {
+                    description: Some("文档ID (get/delete操作必需)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("limit".to_string(), Schema::String(SchemaString {
+                    description: Some("搜索结果限制 (search操作可选，默认5)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("natural_language".to_string(), Schema::String(SchemaString {
+                    description: Some("按文档自然语言过滤，如 en、zh、ja (search操作可选)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("code_language".to_string(), Schema::String(SchemaString {
+                    description: Some("按代码块编程语言过滤，如 rust、python (search操作可选)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("package_name".to_string(), Schema::String(SchemaString {
+                    description: Some("包名 (enrich_qa操作必需；list/search操作可选，按包名过滤；delete_by_filter操作的删除条件)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("version".to_string(), Schema::String(SchemaString {
+                    description: Some("包版本 (store操作可选；search操作时按版本过滤，支持 1.x、^1.2、>=1.0, <2 等写法)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("rerank".to_string(), Schema::Boolean(SchemaBoolean {
+                    description: Some("search操作时，用重排模型对融合后的候选重新排序（需配置 RERANK_API_KEY），默认false".to_string()),
+                }));
+                props.insert("pin_versions".to_string(), Schema::Boolean(SchemaBoolean {
+                    description: Some("search操作时，查询提到项目锁文件中的包时只返回锁定版本的文档，默认true；传false搜索所有版本".to_string()),
+                }));
+                props.insert("package_pattern".to_string(), Schema::String(SchemaString {
+                    description: Some("包名通配 (search操作可选，* 匹配任意字符，如 tokio-*)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("created_after".to_string(), Schema::String(SchemaString {
+                    description: Some("只搜索该时间之后入库的文档 (search操作可选，RFC 3339)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("created_before".to_string(), Schema::String(SchemaString {
+                    description: Some("只搜索该时间之前入库的文档 (search操作可选，RFC 3339)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("source".to_string(), Schema::String(SchemaString {
+                    description: Some("文档包的本地路径或URL (import_pack操作必需)；DevDocs文档集slug(如 python~3.12)或含index.json/db.json的本地目录 (import_devdocs操作必需)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("scope".to_string(), Schema::String(SchemaString {
+                    description: Some("缓存层级: global(全局共享), workspace(当前工作区)。store默认按是否指定包名推断，search/get/delete默认合并两层".to_string()),
+                    enum_values: Some(vec!["global".to_string(), "workspace".to_string()]),
+                }));
+                props.insert("collection".to_string(), Schema::String(SchemaString {
+                    description: Some("集合名 (create_collection/drop_collection操作必需；store/search/get/delete/list操作可选，指定后只在该集合中操作，默认集合为 default)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("source_url".to_string(), Schema::String(SchemaString {
+                    description: Some("文档来源地址 (store操作可选，记录出处；purge_source操作必需，以 * 结尾时按前缀匹配)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("name".to_string(), Schema::String(SchemaString {
+                    description: Some("语料文件名 (export/import操作必需，字母、数字、- 和 _，文件位于数据目录 exports 下)".to_string()),
+                    enum_values: None,
+                }));
+                props.insert("format".to_string(), Schema::String(SchemaString {
+                    description: Some("语料文件格式 (export/import操作可选，默认 jsonl；parquet 需要启用 parquet-export feature)".to_string()),
+                    enum_values: Some(vec!["jsonl".to_string(), "parquet".to_string()]),
+                }));
+                props.insert("threshold".to_string(), Schema::Number(SchemaNumber {
+                    description: Some("判定为近似重复的嵌入向量余弦相似度 (dedup_report操作可选，默认0.95)".to_string()),
+                    minimum: Some(0.0),
+                    maximum: Some(1.0),
+                }));
+                props.insert("merge".to_string(), Schema::Boolean(SchemaBoolean {
+                    description: Some("dedup_report操作时自动合并：每个簇只保留全文最长的文档，删除其余文档，默认false".to_string()),
+                }));
+                pagination::add_pagination_properties(&mut props);
+                props
+            },
+            required: vec!["action".to_string()],
+            description: Some("嵌入式向量化文档管理工具参数".to_string()),
+        })
+    }
+
+    /// 生成文本的嵌入向量
+    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        let cache_key = EmbeddingCache::key(self.model_name(), InputType::Passage, text);
+        if let Some(embedding) = self.embedding_cache.lock().unwrap().get(&cache_key) {
+            tracing::debug!("命中嵌入向量缓存，内容长度: {} 字符", text.len());
+            return Ok(embedding);
+        }
+        
+        // 缓存未命中，调用嵌入服务
+        let embedder = self.embedder()?;
+        tracing::debug!("调用{}嵌入服务生成嵌入向量，内容长度: {} 字符", embedder.provider(), text.len());
+
+        let embeddings = embedder.embed(&[text.to_string()], InputType::Passage).await?;
+
+        if let Some(embedding) = embeddings.into_iter().next() {
+            self.embedding_cache.lock().unwrap().insert(cache_key, embedding.clone());
+            Ok(embedding)
+        } else {
+            Err(anyhow::anyhow!("{} 嵌入服务返回空的嵌入向量", embedder.provider()))
+        }
+    }
+
+    /// 智能文档相似度检测（替代简单哈希比较）
+    /// 基于语义相似度和内容特征的综合评估
+    async fn calculate_document_similarity(&self, existing_content: &str, new_content: &str) -> Result<f32> {
+        // 1. 基础文本相似度检测
+        let text_similarity = self.calculate_text_similarity(existing_content, new_content);
+        
+        // 2. 结构化内容相似度
+        let structure_similarity = self.calculate_structure_similarity(existing_content, new_content);
+        
+        // 3. 语义关键词相似度
+        let keyword_similarity = self.calculate_keyword_similarity(existing_content, new_content);
+        
+        // 4. 长度和复杂度相似度
+        let complexity_similarity = self.calculate_complexity_similarity(existing_content, new_content);
+        
+        // 加权综合相似度分数
+        let weighted_similarity = 
+            text_similarity * 0.4 +           // 文本相似度权重40%
+            structure_similarity * 0.25 +     // 结构相似度权重25%
+            keyword_similarity * 0.25 +       // 关键词相似度权重25%
+            complexity_similarity * 0.1;      // 复杂度相似度权重10%
+        
+        Ok(weighted_similarity)
+    }
+    
+    /// 计算文本相似度（混合模式：词频向量 + 语义嵌入）
+    fn calculate_text_similarity(&self, text1: &str, text2: &str) -> f32 {
+        if text1.is_empty() && text2.is_empty() {
+            return 1.0;
+        }
+        if text1.is_empty() || text2.is_empty() {
+            return 0.0;
+        }
+        
+        // 标准化文本
+        let normalized1 = self.normalize_text(text1);
+        let normalized2 = self.normalize_text(text2);
+        
+        // 1. 基于词频向量的余弦相似度（快速，离线）
+        let vector1 = self.build_word_frequency_vector(&normalized1);
+        let vector2 = self.build_word_frequency_vector(&normalized2);
+        let lexical_similarity = self.calculate_cosine_similarity(&vector1, &vector2);
+        
+        // 2. 尝试使用语义嵌入相似度（如果文本足够长且重要）
+        if text1.len() > 100 && text2.len() > 100 && self.embedder.is_some() {
+            // 异步调用嵌入API会比较复杂，这里使用同步的备用方案
+            // 实际应用中可以考虑缓存常用文本的嵌入向量
+            
+            // 现在先使用增强的词频分析
+            let enhanced_similarity = self.calculate_enhanced_lexical_similarity(&normalized1, &normalized2);
+            
+            // 混合权重：70%词频 + 30%增强分析
+            lexical_similarity * 0.7 + enhanced_similarity * 0.3
+        } else {
+            lexical_similarity
+        }
+    }
+    
+    /// 增强的词汇相似度分析（基于语义场和上下文）
+    fn calculate_enhanced_lexical_similarity(&self, text1: &str, text2: &str) -> f32 {
+        // 1. N-gram相似度分析
+        let bigrams1 = self.extract_ngrams(text1, 2);
+        let bigrams2 = self.extract_ngrams(text2, 2);
+        let bigram_similarity = self.calculate_set_similarity(&bigrams1, &bigrams2);
+        
+        // 2. 技术术语权重提升
+        let tech_terms1 = self.extract_technical_terms(text1);
+        let tech_terms2 = self.extract_technical_terms(text2);
+        let tech_similarity = self.calculate_set_similarity(&tech_terms1, &tech_terms2);
+        
+        // 3. 语义场相似度（同义词和相关概念）
+        let semantic_similarity = self.calculate_semantic_field_similarity(text1, text2);
+        
+        // 加权组合
+        bigram_similarity * 0.4 + tech_similarity * 0.4 + semantic_similarity * 0.2
+    }
+    
+    /// 提取N-gram特征（基于分词结果）
+    fn extract_ngrams(&self, text: &str, n: usize) -> std::collections::HashSet<String> {
+        let words = self.analyzers.tokenize(text);
+        let mut ngrams = std::collections::HashSet::new();
+        if words.len() < n {
+            return ngrams;
+        }
+        
+        for i in 0..=words.len() - n {
+            let ngram = words[i..i + n].join(" ");
+            if ngram.len() >= 4 { // 过滤太短的ngram
+                ngrams.insert(ngram);
+            }
+        }
+        
+        ngrams
+    }
+    
+    /// 提取技术术语
+    fn extract_technical_terms(&self, text: &str) -> std::collections::HashSet<String> {
+        let mut tech_terms = std::collections::HashSet::new();
+        let text_lower = text.to_lowercase();
+        
+        // 编程相关术语词典
+        let programming_terms = [
+            "api", "sdk", "framework", "library", "function", "method", "class", "interface",
+            "async", "await", "promise", "callback", "closure", "lambda", "generic", "template",
+            "memory", "thread", "process", "concurrency", "parallelism", "synchronization",
+            "mutex", "lock", "atomic", "volatile", "pointer", "reference", "ownership",
+            "compilation", "runtime", "virtual", "machine", "garbage", "collection",
+            "serialization", "deserialization", "encoding", "decoding", "protocol",
+            "http", "https", "tcp", "udp", "websocket", "database", "sql", "nosql"
+        ];
+        
+        for term in programming_terms {
+            if text_lower.contains(term) {
+                tech_terms.insert(term.to_string());
+            }
+        }
+        
+        // 提取大写的标识符（通常是类名、常量等）
+        for word in text.split_whitespace() {
+            if word.len() > 2 && word.chars().next().unwrap().is_uppercase() {
+                tech_terms.insert(word.to_lowercase());
+            }
+        }
+        
+        tech_terms
+    }
+    
+    /// 计算集合相似度（Jaccard Index）
+    fn calculate_set_similarity(&self, set1: &std::collections::HashSet<String>, set2: &std::collections::HashSet<String>) -> f32 {
+        if set1.is_empty() && set2.is_empty() {
+            return 1.0;
+        }
+        if set1.is_empty() || set2.is_empty() {
+            return 0.0;
+        }
+        
+        let intersection = set1.intersection(set2).count() as f32;
+        let union = set1.union(set2).count() as f32;
+        
+        intersection / union
+    }
+    
+    /// 计算语义场相似度（基于预定义的概念关系）
+    fn calculate_semantic_field_similarity(&self, text1: &str, text2: &str) -> f32 {
+        // 定义语义场映射
+        let semantic_fields = [
+            (vec!["rust", "cargo", "crate", "rustc"], "rust_ecosystem"),
+            (vec!["python", "pip", "conda", "pypi"], "python_ecosystem"),
             (vec!["javascript", "npm", "node", "yarn"], "js_ecosystem"),
             (vec!["memory", "allocation", "heap", "stack"], "memory_management"),
             (vec!["async", "await", "concurrent", "parallel"], "concurrency"),
             (vec!["api", "endpoint", "request", "response"], "web_api"),
         ];
         
-        let mut field_matches = 0;
-        let mut total_fields = 0;
+        let mut field_matches = 0;
+        let mut total_fields = 0;
+        
+        for (terms, _field_name) in semantic_fields.iter() {
+            total_fields += 1;
+            
+            let text1_lower = text1.to_lowercase();
+            let text2_lower = text2.to_lowercase();
+            
+            let has_field1 = terms.iter().any(|term| text1_lower.contains(term));
+            let has_field2 = terms.iter().any(|term| text2_lower.contains(term));
+            
+            if has_field1 && has_field2 {
+                field_matches += 1;
+            }
+        }
+        
+        if total_fields > 0 {
+            field_matches as f32 / total_fields as f32
+        } else {
+            0.0
+        }
+    }
+    
+    /// 构建词频向量（按文本语言分词，已去掉停用词）
+    fn build_word_frequency_vector(&self, text: &str) -> std::collections::HashMap<String, f32> {
+        let mut word_freq = std::collections::HashMap::new();
+        let words = self.analyzers.tokenize(text);
+        let total_words = words.len() as f32;
+        
+        if total_words == 0.0 {
+            return word_freq;
+        }
+        
+        // 计算词频
+        for word in words {
+            *word_freq.entry(word).or_insert(0.0) += 1.0;
+        }
+        
+        // 标准化词频（可选：使用TF-IDF，这里使用简单的词频标准化）
+        for freq in word_freq.values_mut() {
+            *freq /= total_words;
+        }
+        
+        word_freq
+    }
+    
+    /// 计算两个词频向量的余弦相似度
+    fn calculate_cosine_similarity(&self, vector1: &std::collections::HashMap<String, f32>, vector2: &std::collections::HashMap<String, f32>) -> f32 {
+        if vector1.is_empty() && vector2.is_empty() {
+            return 1.0;
+        }
+        if vector1.is_empty() || vector2.is_empty() {
+            return 0.0;
+        }
+        
+        // 获取所有唯一词汇
+        let mut all_words: std::collections::HashSet<String> = std::collections::HashSet::new();
+        all_words.extend(vector1.keys().cloned());
+        all_words.extend(vector2.keys().cloned());
+        
+        // 计算点积和向量模长
+        let mut dot_product = 0.0;
+        let mut norm1 = 0.0;
+        let mut norm2 = 0.0;
+        
+        for word in all_words {
+            let freq1 = vector1.get(&word).unwrap_or(&0.0);
+            let freq2 = vector2.get(&word).unwrap_or(&0.0);
+            
+            dot_product += freq1 * freq2;
+            norm1 += freq1 * freq1;
+            norm2 += freq2 * freq2;
+        }
+        
+        // 计算余弦相似度
+        let norm_product = norm1.sqrt() * norm2.sqrt();
+        if norm_product == 0.0 {
+            return 0.0;
+        }
+        
+        (dot_product / norm_product).max(0.0).min(1.0)
+    }
+    
+    /// 判断是否为停用词（默认语言）
+    fn is_stop_word(&self, word: &str) -> bool {
+        self.analyzers.fallback().is_stop_word(word)
+    }
+    
+    /// 计算结构化内容相似度
+    fn calculate_structure_similarity(&self, text1: &str, text2: &str) -> f32 {
+        // 提取结构特征
+        let features1 = self.extract_structure_features(text1);
+        let features2 = self.extract_structure_features(text2);
+        
+        // 比较结构特征
+        let mut similarity_score = 0.0;
+        let mut total_features = 0;
+        
+        // 比较段落数量相似度
+        let para_diff = (features1.paragraph_count as f32 - features2.paragraph_count as f32).abs();
+        let para_max = features1.paragraph_count.max(features2.paragraph_count) as f32;
+        let para_similarity = if para_max > 0.0 { 1.0 - (para_diff / para_max) } else { 1.0 };
+        similarity_score += para_similarity;
+        total_features += 1;
+        
+        // 比较代码块数量相似度
+        let code_diff = (features1.code_block_count as f32 - features2.code_block_count as f32).abs();
+        let code_max = features1.code_block_count.max(features2.code_block_count) as f32;
+        let code_similarity = if code_max > 0.0 { 1.0 - (code_diff / code_max) } else { 1.0 };
+        similarity_score += code_similarity;
+        total_features += 1;
+        
+        // 比较列表数量相似度
+        let list_diff = (features1.list_count as f32 - features2.list_count as f32).abs();
+        let list_max = features1.list_count.max(features2.list_count) as f32;
+        let list_similarity = if list_max > 0.0 { 1.0 - (list_diff / list_max) } else { 1.0 };
+        similarity_score += list_similarity;
+        total_features += 1;
+        
+        if total_features > 0 {
+            similarity_score / total_features as f32
+        } else {
+            0.5 // 默认中等相似度
+        }
+    }
+    
+    /// 计算关键词相似度
+    fn calculate_keyword_similarity(&self, text1: &str, text2: &str) -> f32 {
+        let keywords1 = self.extract_technical_keywords(text1);
+        let keywords2 = self.extract_technical_keywords(text2);
+        
+        if keywords1.is_empty() && keywords2.is_empty() {
+            return 1.0;
+        }
+        if keywords1.is_empty() || keywords2.is_empty() {
+            return 0.0;
+        }
+        
+        let intersection = keywords1.intersection(&keywords2).count() as f32;
+        let union = keywords1.union(&keywords2).count() as f32;
+        
+        if union > 0.0 {
+            intersection / union
+        } else {
+            0.0
+        }
+    }
+    
+    /// 计算复杂度相似度
+    fn calculate_complexity_similarity(&self, text1: &str, text2: &str) -> f32 {
+        let len1 = text1.len() as f32;
+        let len2 = text2.len() as f32;
+        
+        if len1 == 0.0 && len2 == 0.0 {
+            return 1.0;
+        }
+        
+        let length_ratio = if len1 > len2 { len2 / len1 } else { len1 / len2 };
+        
+        // 计算词汇复杂度
+        let vocab1 = text1.split_whitespace().collect::<std::collections::HashSet<_>>().len() as f32;
+        let vocab2 = text2.split_whitespace().collect::<std::collections::HashSet<_>>().len() as f32;
+        let vocab_ratio = if vocab1 > vocab2 { vocab2 / vocab1 } else { vocab1 / vocab2 };
+        
+        // 综合复杂度相似度
+        (length_ratio * 0.6 + vocab_ratio * 0.4).min(1.0)
+    }
+    
+    /// 标准化文本
+    fn normalize_text(&self, text: &str) -> String {
+        text.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .filter(|c| c.is_alphanumeric() || c.is_whitespace() || "_.,!?;:()[]{}\"'".contains(*c))
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    
+    /// 提取结构特征
+    fn extract_structure_features(&self, text: &str) -> StructureFeatures {
+        let paragraph_count = text.split("\n\n").filter(|p| !p.trim().is_empty()).count();
+        let code_block_count = text.matches("```").count() / 2; // 代码块成对出现
+        let list_count = text.lines().filter(|line| {
+            let trimmed = line.trim();
+            trimmed.starts_with("- ") || trimmed.starts_with("* ") || 
+            trimmed.chars().next().map_or(false, |c| c.is_ascii_digit())
+        }).count();
+        
+        StructureFeatures {
+            paragraph_count,
+            code_block_count,
+            list_count,
+        }
+    }
+    
+    /// 提取技术关键词
+    fn extract_technical_keywords(&self, text: &str) -> std::collections::HashSet<String> {
+        let technical_patterns = [
+            // 编程概念
+            r"\b(function|method|class|struct|enum|trait|interface|module|package|library|framework)\b",
+            // 数据类型
+            r"\b(string|integer|boolean|array|vector|hashmap|option|result|future|stream)\b",
+            // 操作关键词
+            r"\b(async|await|impl|pub|use|mod|crate|extern|unsafe|mut|ref|move|clone)\b",
+            // API相关
+            r"\b(api|endpoint|request|response|http|json|xml|rest|graphql|websocket)\b",
+        ];
+        
+        let mut keywords = std::collections::HashSet::new();
+        let text_lower = text.to_lowercase();
+        
+        for pattern in &technical_patterns {
+            if let Ok(regex) = regex::Regex::new(pattern) {
+                for mat in regex.find_iter(&text_lower) {
+                    keywords.insert(mat.as_str().to_string());
+                }
+            }
+        }
+        
+        // 提取驼峰命名和下划线命名的标识符
+        if let Ok(identifier_regex) = regex::Regex::new(r"\b[a-zA-Z][a-zA-Z0-9_]*[a-zA-Z0-9]\b") {
+            for mat in identifier_regex.find_iter(&text) {
+                let identifier = mat.as_str();
+                if identifier.len() > 3 && (identifier.contains('_') || identifier.chars().any(|c| c.is_uppercase())) {
+                    keywords.insert(identifier.to_lowercase());
+                }
+            }
+        }
+        
+        keywords
+    }
+
+    /// 智能重复检查（替代原来的哈希比较）
+    async fn intelligent_duplicate_check(&self, fragment: &FileDocumentFragment) -> Result<bool> {
+        let store_guard = self.read_store(self.store_for_tier(CacheTier::infer(&fragment.package_name)))?;
+        if let Some(existing_doc) = store_guard.get_document(&fragment.id) {
+            // 版本检查
+            if existing_doc.version != fragment.version {
+                tracing::info!("文档 {} 版本不同 (现有: {}, 新: {})，需要更新", 
+                    fragment.id, existing_doc.version, fragment.version);
+                return Ok(false); // 版本不同，不是重复
+            }
+            
+            // 智能相似度检测
+            let similarity = self.calculate_document_similarity(&existing_doc.content, &fragment.content).await?;
+            
+            // 相似度阈值：85%以上认为是重复内容
+            const SIMILARITY_THRESHOLD: f32 = 0.85;
+            
+            if similarity >= SIMILARITY_THRESHOLD {
+                tracing::info!("文档 {} 内容相似度 {:.2}%，判定为重复内容", 
+                    fragment.id, similarity * 100.0);
+                return Ok(true); // 是重复内容
+            } else {
+                tracing::info!("文档 {} 内容相似度 {:.2}%，判定为不同内容，需要更新", 
+                    fragment.id, similarity * 100.0);
+                return Ok(false); // 内容有显著差异，需要更新
+            }
+        }
+        
+        Ok(false) // 文档不存在，不是重复
+    }
+
+    /// 公开方法，用于从 FileDocumentFragment 添加文档
+    /// 这个方法会被 BackgroundDocCacher 调用
+    pub async fn add_file_fragment(&self, fragment: &FileDocumentFragment) -> Result<String> {
+        if fragment.content.trim().is_empty() {
+            return Err(anyhow::anyhow!("文档内容为空，跳过嵌入和存储: {}", fragment.id));
+        }
+        let verdict = self.quality_gate.evaluate(&fragment.content, &fragment.package_name);
+        if let Some(reason) = verdict.rejected {
+            return Err(anyhow::anyhow!("文档质量未达标({}，得分 {:.2})，跳过嵌入和存储: {}", reason.as_str(), verdict.score, fragment.id));
+        }
+
+        // 智能重复检查
+        if let Ok(is_duplicate) = self.intelligent_duplicate_check(fragment).await {
+            if is_duplicate {
+                tracing::info!("文档 {} 已存在且内容相同，跳过添加。", fragment.id);
+                return Ok(fragment.id.clone());
+            } else {
+                tracing::info!("文档 {} 存在但内容已更新，将替换现有文档。", fragment.id);
+                // 继续执行以更新文档
+            }
+        }
+
+        let embedding = self.generate_embedding(&fragment.content).await
+            .map_err(|e| anyhow::anyhow!("为文档 {} 生成嵌入向量失败: {}", fragment.id, e))?;
+
+        let mut metadata = HashMap::new();
+        metadata.insert("file_path".to_string(), fragment.file_path.clone());
+        metadata.insert("hierarchy_path".to_string(), fragment.hierarchy_path.join("/"));
+        metadata.insert("similarity_check".to_string(), "intelligent".to_string());
+        metadata.extend(content_language::language_metadata(&fragment.content));
+        metadata.extend(provenance::fragment_metadata(fragment));
+
+        let doc_record = DocumentRecord {
+            id: fragment.id.clone(),
+            content: fragment.content.clone(),
+            title: fragment.get_filename_without_ext().unwrap_or_else(|| "Unknown Title".to_string()),
+            language: fragment.language.clone(),
+            package_name: fragment.package_name.clone(),
+            version: fragment.version.clone(),
+            doc_type: fragment.file_type.doc_type(), // e.g., "source", "documentation"
+            metadata,
+            embedding,
+        };
+
+        self.ingest_documents(self.store_for_tier(CacheTier::infer(&fragment.package_name)), vec![doc_record.clone()])?;
+        self.notify_update(&doc_record.language, &doc_record.package_name, &doc_record.version);
         
-        for (terms, _field_name) in semantic_fields.iter() {
-            total_fields += 1;
-            
-            let text1_lower = text1.to_lowercase();
-            let text2_lower = text2.to_lowercase();
-            
-            let has_field1 = terms.iter().any(|term| text1_lower.contains(term));
-            let has_field2 = terms.iter().any(|term| text2_lower.contains(term));
-            
-            if has_field1 && has_field2 {
-                field_matches += 1;
+        tracing::info!("文档 {} 已成功向量化并存储。", fragment.id);
+        Ok(fragment.id.clone())
+    }
+
+    /// 批量添加 FileDocumentFragment
+    pub async fn add_file_fragments_batch(&self, fragments: &[FileDocumentFragment]) -> Result<Vec<String>> {
+        Ok(self.ingest_fragments(fragments).await?.added_ids)
+    }
+
+    /// 批量入库并返回质量闸门的拒绝统计
+    pub async fn ingest_fragments(&self, fragments: &[FileDocumentFragment]) -> Result<FragmentIngestReport> {
+        let mut report = FragmentIngestReport::default();
+        if fragments.is_empty() {
+            return Ok(report);
+        }
+
+        let mut added_ids = Vec::new();
+        let mut records_to_add = Vec::new();
+
+        {
+            for fragment in fragments {
+                if fragment.content.trim().is_empty() {
+                    tracing::warn!("文档内容为空，跳过嵌入和存储: {}", fragment.id);
+                    report.skipped += 1;
+                    continue;
+                }
+                // 初步检查是否已存在 (更精细的检查在VectorStore的批量添加中进行)
+                let store_guard = self.read_store(self.store_for_tier(CacheTier::infer(&fragment.package_name)))?;
+                if store_guard.contains_document(&fragment.id) {
+                    tracing::info!("文档 {} 已存在于向量库 (初步检查)，跳过处理。", fragment.id);
+                    added_ids.push(fragment.id.clone()); // 认为已存在即为"已添加"
+                    report.skipped += 1;
+                    continue;
+                }
+                let verdict = self.quality_gate.evaluate(&fragment.content, &fragment.package_name);
+                if let Some(reason) = verdict.rejected {
+                    tracing::debug!("文档 {} 质量未达标({}，得分 {:.2})，拒绝入库", fragment.id, reason.as_str(), verdict.score);
+                    report.rejected.record(reason);
+                    continue;
+                }
+                records_to_add.push(fragment);
             }
         }
         
-        if total_fields > 0 {
-            field_matches as f32 / total_fields as f32
-        } else {
-            0.0
+        if report.rejected.total() > 0 {
+            tracing::info!("质量闸门拒绝了 {} 个片段: {:?}", report.rejected.total(), report.rejected.by_reason);
         }
-    }
-    
-    /// 构建词频向量
-    fn build_word_frequency_vector(&self, text: &str) -> std::collections::HashMap<String, f32> {
-        let mut word_freq = std::collections::HashMap::new();
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let total_words = words.len() as f32;
-        
-        if total_words == 0.0 {
-            return word_freq;
+        if records_to_add.is_empty() {
+            report.added_ids = added_ids;
+            return Ok(report);
         }
-        
-        // 计算词频
-        for word in words {
-            let word_lower = word.to_lowercase();
-            // 过滤掉过短的词和常见停用词
-            if word_lower.len() >= 2 && !self.is_stop_word(&word_lower) {
-                *word_freq.entry(word_lower).or_insert(0.0) += 1.0;
+
+        let mut document_records = Vec::with_capacity(records_to_add.len());
+        for fragment_ref in records_to_add {
+            // 这里直接使用 fragment_ref, 因为 records_to_add 中的生命周期足够
+            let fragment = fragment_ref; 
+            report.embedding_tokens += estimate_tokens(&fragment.content);
+            match self.generate_embedding(&fragment.content).await {
+                Ok(embedding) => {
+                    let mut metadata = HashMap::new();
+                    metadata.insert("file_path".to_string(), fragment.file_path.clone());
+                    metadata.insert("hierarchy_path".to_string(), fragment.hierarchy_path.join("/"));
+                    metadata.extend(content_language::language_metadata(&fragment.content));
+                    metadata.extend(provenance::fragment_metadata(fragment));
+
+                    document_records.push(DocumentRecord {
+                        id: fragment.id.clone(),
+                        content: fragment.content.clone(),
+                        title: fragment.get_filename_without_ext().unwrap_or_else(|| "Unknown Title".to_string()),
+                        language: fragment.language.clone(),
+                        package_name: fragment.package_name.clone(),
+                        version: fragment.version.clone(),
+                        doc_type: fragment.file_type.doc_type(),
+                        metadata,
+                        embedding,
+                    });
+                    added_ids.push(fragment.id.clone());
+                }
+                Err(e) => {
+                    tracing::error!("为文档 {} 生成嵌入向量失败: {}。将跳过此文档。", fragment.id, e);
+                    report.failed += 1;
+                }
             }
         }
         
-        // 标准化词频（可选：使用TF-IDF，这里使用简单的词频标准化）
-        for freq in word_freq.values_mut() {
-            *freq /= total_words;
+        if !document_records.is_empty() {
+            let (global_records, workspace_records): (Vec<_>, Vec<_>) = document_records
+                .into_iter()
+                .partition(|record| CacheTier::infer(&record.package_name) == CacheTier::Global);
+
+            for (tier, records) in [(CacheTier::Global, global_records), (CacheTier::Workspace, workspace_records)] {
+                if records.is_empty() {
+                    continue;
+                }
+                let record_count = records.len();
+                let mut packages: Vec<(String, String, String)> = records.iter()
+                    .map(|r| (r.language.clone(), r.package_name.clone(), r.version.clone()))
+                    .collect();
+                packages.sort();
+                packages.dedup();
+                match self.ingest_documents(self.store_for_tier(tier), records) {
+                    Ok(_) => {
+                        report.stored += record_count;
+                        tracing::info!("成功批量添加 {} 个新文档记录到{}层向量库。", record_count, tier.as_str());
+                        for (language, package_name, version) in &packages {
+                            self.notify_update(language, package_name, version);
+                        }
+                    }
+                    Err(e) => tracing::error!("批量添加文档到{}层向量库失败: {}", tier.as_str(), e),
+                }
+            }
         }
-        
-        word_freq
+
+        report.added_ids = added_ids;
+        Ok(report)
     }
-    
-    /// 计算两个词频向量的余弦相似度
-    fn calculate_cosine_similarity(&self, vector1: &std::collections::HashMap<String, f32>, vector2: &std::collections::HashMap<String, f32>) -> f32 {
-        if vector1.is_empty() && vector2.is_empty() {
-            return 1.0;
+
+    /// 检查某个包的特定版本是否已在任一层级被标记为完整处理
+    pub fn has_processed_package_version(&self, language: &str, package_name: &str, version: &str) -> Result<bool> {
+        for (_, store) in self.tier_stores() {
+            if self.read_store(store)?.has_processed_package_version(language, package_name, version) {
+                return Ok(true);
+            }
         }
-        if vector1.is_empty() || vector2.is_empty() {
-            return 0.0;
+        Ok(false)
+    }
+
+    /// 标记某个包的特定版本为已完整处理，与认领和进度记录写入同一层级
+    pub fn mark_package_version_as_processed(&self, language: &str, package_name: &str, version: &str) -> Result<()> {
+        let mut store_guard = self.acquire_store(self.store_for_tier(CacheTier::infer(package_name)))?;
+        store_guard.mark_package_version_as_processed(language, package_name, version)
+    }
+
+    /// 认领包版本的文档处理，返回 false 表示已完成或其他任务正在处理
+    pub fn try_claim_package_version(&self, language: &str, package_name: &str, version: &str) -> Result<bool> {
+        let key = package_version_key(language, package_name, version);
+        let mut store_guard = self.acquire_store(self.store_for_tier(CacheTier::infer(package_name)))?;
+        store_guard.try_claim_package_version(&key)
+    }
+
+    /// 记录包版本的处理进展
+    pub fn record_package_progress(
+        &self,
+        language: &str,
+        package_name: &str,
+        version: &str,
+        pages_fetched: usize,
+        fragments_stored: usize,
+        pages_total: Option<usize>,
+    ) -> Result<()> {
+        let key = package_version_key(language, package_name, version);
+        let mut store_guard = self.acquire_store(self.store_for_tier(CacheTier::infer(package_name)))?;
+        store_guard.update_package_progress(&key, |progress| progress.record(pages_fetched, fragments_stored, pages_total))
+    }
+
+    /// 记录被质量闸门拒绝的片段数
+    pub fn record_rejected_fragments(&self, language: &str, package_name: &str, version: &str, rejected: &RejectionCounts) -> Result<()> {
+        if rejected.total() == 0 {
+            return Ok(());
         }
-        
-        // 获取所有唯一词汇
-        let mut all_words: std::collections::HashSet<String> = std::collections::HashSet::new();
-        all_words.extend(vector1.keys().cloned());
-        all_words.extend(vector2.keys().cloned());
-        
-        // 计算点积和向量模长
-        let mut dot_product = 0.0;
-        let mut norm1 = 0.0;
-        let mut norm2 = 0.0;
-        
-        for word in all_words {
-            let freq1 = vector1.get(&word).unwrap_or(&0.0);
-            let freq2 = vector2.get(&word).unwrap_or(&0.0);
-            
-            dot_product += freq1 * freq2;
-            norm1 += freq1 * freq1;
-            norm2 += freq2 * freq2;
+        let key = package_version_key(language, package_name, version);
+        let mut store_guard = self.acquire_store(self.store_for_tier(CacheTier::infer(package_name)))?;
+        store_guard.update_package_progress(&key, |progress| progress.record_rejected(rejected))
+    }
+
+    /// 标记包版本处理失败，保留已完成的进度以便下次恢复
+    pub fn fail_package_progress(&self, language: &str, package_name: &str, version: &str, error: &str) -> Result<()> {
+        let key = package_version_key(language, package_name, version);
+        let mut store_guard = self.acquire_store(self.store_for_tier(CacheTier::infer(package_name)))?;
+        store_guard.update_package_progress(&key, |progress| progress.fail(error.to_string()))
+    }
+
+    /// 完整处理结束后标记完成
+    pub fn complete_package_progress(&self, language: &str, package_name: &str, version: &str) -> Result<()> {
+        let mut store_guard = self.acquire_store(self.store_for_tier(CacheTier::infer(package_name)))?;
+        store_guard.mark_package_version_as_processed(language, package_name, version)
+    }
+
+    /// 查询包版本的处理进度
+    pub fn package_progress(&self, language: &str, package_name: &str, version: &str) -> Result<Option<PackageProgress>> {
+        let key = package_version_key(language, package_name, version);
+        for (_, store) in self.tier_stores() {
+            if let Some(progress) = self.read_store(store)?.package_progress.get(&key) {
+                return Ok(Some(progress.clone()));
+            }
         }
-        
-        // 计算余弦相似度
-        let norm_product = norm1.sqrt() * norm2.sqrt();
-        if norm_product == 0.0 {
-            return 0.0;
+        Ok(None)
+    }
+
+    /// 获取系统状态和统计信息
+    pub fn get_system_status(&self) -> Value {
+        let mut doc_count = 0;
+        let mut vector_count = 0;
+        let mut tiers = serde_json::Map::new();
+        for (tier, store) in self.tier_stores() {
+            let store = store.read().unwrap();
+            let (tier_docs, tier_vectors) = store.get_stats();
+            doc_count += tier_docs;
+            vector_count += tier_vectors;
+            tiers.insert(tier.as_str().to_string(), json!({
+                "hibernated": store.hibernated,
+                "access_mode": store.access_mode().as_str(),
+                "snapshot_modified_at": store.loaded_mtime.map(chrono::DateTime::<chrono::Utc>::from),
+                "quantization": store.quantization_status(),
+                "sharding": store.sharding_status(),
+                "documents": tier_docs,
+                "vectors": tier_vectors,
+                "data_dir": store.data_dir.to_string_lossy(),
+                "cache_size": store.cache_accounting_status(),
+                "embedding": store.embedding_meta,
+                "incomplete_packages": store.package_progress.iter()
+                    .filter(|(_, progress)| progress.status != ProgressStatus::Complete)
+                    .map(|(key, progress)| (key.clone(), json!({
+                        "status": progress.status,
+                        "completion_percent": progress.completion_percent(),
+                        "pages_fetched": progress.pages_fetched,
+                        "fragments_stored": progress.fragments_stored,
+                        "attempts": progress.attempts,
+                        "last_error": progress.last_error,
+                    })))
+                    .collect::<serde_json::Map<String, Value>>(),
+            }));
         }
         
-        (dot_product / norm_product).max(0.0).min(1.0)
-    }
-    
-    /// 判断是否为停用词
-    fn is_stop_word(&self, word: &str) -> bool {
-        // 常见的英文停用词
-        const STOP_WORDS: &[&str] = &[
-            "the", "a", "an", "and", "or", "but", "in", "on", "at", "to", "for", "of", "with", "by",
-            "is", "are", "was", "were", "be", "been", "being", "have", "has", "had", "do", "does", "did",
-            "will", "would", "could", "should", "may", "might", "can", "this", "that", "these", "those",
-            "i", "you", "he", "she", "it", "we", "they", "me", "him", "her", "us", "them", "my", "your",
-            "his", "her", "its", "our", "their", "from", "up", "about", "into", "through", "during",
-            "before", "after", "above", "below", "between", "among", "within", "without", "under", "over"
-        ];
+        let removed_dependencies = self.removed_dependency_status()
+            .unwrap_or_else(|e| json!({ "error": e.to_string() }));
+
+        let cache_stats = {
+            let stats = self.embedding_cache.lock().unwrap().stats();
+            json!({
+                "cached_embeddings": stats.cached_embeddings,
+                "cache_limit": stats.cache_limit,
+                "cache_usage_percent": (stats.cached_embeddings as f32 / stats.cache_limit.max(1) as f32 * 100.0).round(),
+                "ttl_hours": stats.ttl_hours,
+                "hits": stats.hits,
+                "misses": stats.misses,
+                "cache_file": stats.cache_file
+            })
+        };
         
-        STOP_WORDS.contains(&word)
+        json!({
+            "status": "运行中",
+            "database": {
+                "total_documents": doc_count,
+                "total_vectors": vector_count,
+                "backend": "instant-distance (HNSW)",
+                "distance_metric": self.store.read().unwrap().distance_metric.as_str(),
+                "metadata_backend": self.store.read().unwrap().metadata_backend().as_str(),
+                "tiers": tiers
+            },
+            "cache": cache_stats,
+            "embedding_throughput": self.embedding_metrics(),
+            "removed_dependencies": removed_dependencies,
+            "api": {
+                "provider": self.embedder.as_ref().map_or("none", |embedder| embedder.provider()),
+                "model": self.model_name(),
+                "has_api_key": self.embedder.is_some(),
+                "health": self.embedding_health()
+            },
+            "performance": {
+                "search_algorithm": "混合搜索 (向量60% + 关键词30% + 上下文10%)",
+                "similarity_detection": "智能多维度相似度检测",
+                "caching": "按模型和内容哈希的持久嵌入缓存，LRU淘汰"
+            }
+        })
     }
-    
-    /// 计算结构化内容相似度
-    fn calculate_structure_similarity(&self, text1: &str, text2: &str) -> f32 {
-        // 提取结构特征
-        let features1 = self.extract_structure_features(text1);
-        let features2 = self.extract_structure_features(text2);
-        
-        // 比较结构特征
-        let mut similarity_score = 0.0;
-        let mut total_features = 0;
-        
-        // 比较段落数量相似度
-        let para_diff = (features1.paragraph_count as f32 - features2.paragraph_count as f32).abs();
-        let para_max = features1.paragraph_count.max(features2.paragraph_count) as f32;
-        let para_similarity = if para_max > 0.0 { 1.0 - (para_diff / para_max) } else { 1.0 };
-        similarity_score += para_similarity;
-        total_features += 1;
-        
-        // 比较代码块数量相似度
-        let code_diff = (features1.code_block_count as f32 - features2.code_block_count as f32).abs();
-        let code_max = features1.code_block_count.max(features2.code_block_count) as f32;
-        let code_similarity = if code_max > 0.0 { 1.0 - (code_diff / code_max) } else { 1.0 };
-        similarity_score += code_similarity;
-        total_features += 1;
-        
-        // 比较列表数量相似度
-        let list_diff = (features1.list_count as f32 - features2.list_count as f32).abs();
-        let list_max = features1.list_count.max(features2.list_count) as f32;
-        let list_similarity = if list_max > 0.0 { 1.0 - (list_diff / list_max) } else { 1.0 };
-        similarity_score += list_similarity;
-        total_features += 1;
-        
-        if total_features > 0 {
-            similarity_score / total_features as f32
-        } else {
-            0.5 // 默认中等相似度
+
+    /// 批量嵌入的请求数、限流与重试次数、吞吐量和当前批大小
+    pub fn embedding_metrics(&self) -> Value {
+        let metrics = self.embedding_batcher.metrics().get_metrics();
+        json!({
+            "requests": metrics.embedding_requests,
+            "embedded_texts": metrics.embedded_texts,
+            "rate_limited": metrics.embedding_rate_limited,
+            "retries": metrics.embedding_retries,
+            "texts_per_second": metrics.embedding_texts_per_second,
+            "batch_size": metrics.embedding_batch_size
+        })
+    }
+
+    /// 批量生成嵌入向量（按限流自适应分批，见 [`AdaptiveBatcher`]）
+    pub async fn generate_embeddings_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // 检查缓存
+        let mut cached_embeddings = Vec::new();
+        let mut uncached_texts = Vec::new();
+        let mut uncached_indices = Vec::new();
+
+        {
+            let mut cache = self.embedding_cache.lock().unwrap();
+            for (idx, text) in texts.iter().enumerate() {
+                if let Some(embedding) = cache.get(&EmbeddingCache::key(self.model_name(), InputType::Query, text)) {
+                    cached_embeddings.push((idx, embedding));
+                    continue;
+                }
+                uncached_texts.push(text.clone());
+                uncached_indices.push(idx);
+            }
+        }
+
+        // 为未缓存的文本生成嵌入
+        let mut new_embeddings = Vec::new();
+        if !uncached_texts.is_empty() {
+            let embeddings = self.embedding_batcher.embed_all(self.embedder()?.as_ref(), &uncached_texts, InputType::Query).await?;
+            new_embeddings.extend(uncached_indices.iter().copied().zip(embeddings));
+
+            // 缓存新的嵌入
+            {
+                let mut cache = self.embedding_cache.lock().unwrap();
+                for (text, (_, embedding)) in uncached_texts.iter().zip(&new_embeddings) {
+                    cache.insert(EmbeddingCache::key(self.model_name(), InputType::Query, text), embedding.clone());
+                }
+            }
+        }
+
+        // 合并缓存和新生成的嵌入
+        let mut final_embeddings = vec![Vec::new(); texts.len()];
+        for (idx, embedding) in cached_embeddings {
+            final_embeddings[idx] = embedding;
+        }
+        for (idx, embedding) in new_embeddings {
+            final_embeddings[idx] = embedding;
         }
+
+        Ok(final_embeddings)
     }
-    
-    /// 计算关键词相似度
-    fn calculate_keyword_similarity(&self, text1: &str, text2: &str) -> f32 {
-        let keywords1 = self.extract_technical_keywords(text1);
-        let keywords2 = self.extract_technical_keywords(text2);
-        
-        if keywords1.is_empty() && keywords2.is_empty() {
-            return 1.0;
+
+    /// 导入预置文档包（本地路径或URL）到全局缓存层
+    pub async fn import_doc_pack(&self, source: &str) -> Result<DocPackImportReport> {
+        let pack = DocPack::load(source).await?;
+        self.import_loaded_pack(pack, false)
+    }
+
+    /// 导入已校验的文档包；`replace` 为 true 时先清除全局层中该包版本的旧文档，使缓存内容与文档包一致
+    pub fn import_loaded_pack(&self, pack: DocPack, replace: bool) -> Result<DocPackImportReport> {
+        let store = self.store_for_tier(CacheTier::Global);
+        pack.ensure_compatible(self.model_name(), self.read_store(store)?.vector_dimension())?;
+
+        let manifest = pack.manifest.clone();
+        let documents_in_pack = pack.documents.len();
+        if replace {
+            self.purge_packages(Some(&manifest.language), Some(&manifest.package_name), Some(&manifest.version), Some(CacheTier::Global))?;
         }
-        if keywords1.is_empty() || keywords2.is_empty() {
-            return 0.0;
+        let documents_imported = self.ingest_documents(store, pack.documents)?;
+        self.acquire_store(store)?.mark_package_version_as_processed(&manifest.language, &manifest.package_name, &manifest.version)?;
+        self.notify_update(&manifest.language, &manifest.package_name, &manifest.version);
+
+        tracing::info!(
+            "已导入文档包 {}/{}/{}: {} 个文档（新增 {} 个），签名校验: {}",
+            manifest.language, manifest.package_name, manifest.version,
+            documents_in_pack, documents_imported, pack.signature_verified
+        );
+
+        Ok(DocPackImportReport {
+            package: format!("{}/{}/{}", manifest.language, manifest.package_name, manifest.version),
+            embedding_model: manifest.embedding_model,
+            documents_in_pack,
+            documents_imported,
+            signature_verified: pack.signature_verified,
+        })
+    }
+
+    /// 导入 DevDocs 文档集（slug 或本地目录），整套参考文档无需爬取即可入库
+    pub async fn import_devdocs(&self, source: &str) -> Result<DevDocsImportReport> {
+        let set = DevDocsSet::load(&self.client, source).await?;
+        let (language, package_name, version) = set.identity();
+        let fragments = set.to_fragments();
+        tracing::info!("开始导入 DevDocs 文档集 {}: {} 个页面", set.slug, fragments.len());
+
+        let report = self.ingest_fragments(&fragments).await?;
+        self.record_package_progress(&language, &package_name, &version, fragments.len(), report.added_ids.len(), Some(fragments.len()))?;
+        self.record_rejected_fragments(&language, &package_name, &version, &report.rejected)?;
+        self.complete_package_progress(&language, &package_name, &version)?;
+
+        Ok(DevDocsImportReport {
+            slug: set.slug.clone(),
+            package: format!("{}/{}/{}", language, package_name, version),
+            pages_in_set: set.pages.len(),
+            fragments_stored: report.added_ids.len(),
+            fragments_rejected: report.rejected.total(),
+        })
+    }
+
+    /// 从 Stack Overflow 补充包常见错误的问答片段（`doc_type: "qa"`）
+    ///
+    /// 需要通过 `GRAPE_QA_ENRICHMENT` 显式开启。
+    pub async fn enrich_with_qa(&self, language: &str, package_name: &str, version: &str, error_messages: &[String]) -> Result<QaEnrichmentReport> {
+        let enricher = QaEnricher::new(self.client.clone(), QaEnrichmentConfig::from_env());
+        if !enricher.is_enabled() {
+            return Err(anyhow::anyhow!("问答补充未开启，请设置 GRAPE_QA_ENRICHMENT=on"));
         }
-        
-        let intersection = keywords1.intersection(&keywords2).count() as f32;
-        let union = keywords1.union(&keywords2).count() as f32;
-        
-        if union > 0.0 {
-            intersection / union
-        } else {
-            0.0
+
+        let (queries, fragments) = enricher.fetch_fragments(language, package_name, version, error_messages).await?;
+        let report = self.ingest_fragments(&fragments).await?;
+        tracing::info!("为 {}/{} 补充了 {} 条问答", language, package_name, report.added_ids.len());
+
+        Ok(QaEnrichmentReport {
+            package: format!("{}/{}/{}", language, package_name, version),
+            queries,
+            questions_found: fragments.len(),
+            fragments_stored: report.added_ids.len(),
+            fragments_rejected: report.rejected.total(),
+        })
+    }
+
+    /// 按过滤条件整体清除包版本，返回 (清除的包版本数, 删除文档数, 释放字节数)
+    ///
+    /// 过滤条件均为 None 时清除整个层级（tier 为 None 时清除所有层级）。
+    pub fn purge_packages(
+        &self,
+        language: Option<&str>,
+        package_name: Option<&str>,
+        version: Option<&str>,
+        tier: Option<CacheTier>,
+    ) -> Result<(usize, usize, u64)> {
+        let mut purged = (0, 0, 0);
+        for (store_tier, store) in self.tier_stores() {
+            if tier.map_or(false, |t| t != store_tier) {
+                continue;
+            }
+            let (keys, removed_docs, removed_bytes) = self.acquire_store(store)?.delete_by_filter(language, package_name, version)?;
+            purged.0 += keys.len();
+            purged.1 += removed_docs;
+            purged.2 += removed_bytes;
         }
+        Ok(purged)
     }
-    
-    /// 计算复杂度相似度
-    fn calculate_complexity_similarity(&self, text1: &str, text2: &str) -> f32 {
-        let len1 = text1.len() as f32;
-        let len2 = text2.len() as f32;
-        
-        if len1 == 0.0 && len2 == 0.0 {
-            return 1.0;
+
+    /// 删除语言、包名和版本匹配的所有包版本（至少指定一个条件），包括索引条目和已处理标记，
+    /// 返回被删除的包版本键及 (删除文档数, 释放字节数)
+    pub fn delete_by_filter(
+        &self,
+        language: Option<&str>,
+        package_name: Option<&str>,
+        version: Option<&str>,
+        tier: Option<CacheTier>,
+    ) -> Result<(Vec<String>, usize, u64)> {
+        if language.is_none() && package_name.is_none() && version.is_none() {
+            return Err(MCPError::InvalidParameter("至少需要指定language、package_name或version之一".to_string()).into());
         }
-        
-        let length_ratio = if len1 > len2 { len2 / len1 } else { len1 / len2 };
-        
-        // 计算词汇复杂度
-        let vocab1 = text1.split_whitespace().collect::<std::collections::HashSet<_>>().len() as f32;
-        let vocab2 = text2.split_whitespace().collect::<std::collections::HashSet<_>>().len() as f32;
-        let vocab_ratio = if vocab1 > vocab2 { vocab2 / vocab1 } else { vocab1 / vocab2 };
-        
-        // 综合复杂度相似度
-        (length_ratio * 0.6 + vocab_ratio * 0.4).min(1.0)
+        let mut deleted = (BTreeSet::new(), 0, 0);
+        for (store_tier, store) in self.tier_stores() {
+            if tier.map_or(false, |t| t != store_tier) {
+                continue;
+            }
+            let (keys, removed_docs, removed_bytes) = self.acquire_store(store)?.delete_by_filter(language, package_name, version)?;
+            deleted.0.extend(keys);
+            deleted.1 += removed_docs;
+            deleted.2 += removed_bytes;
+        }
+        if !deleted.0.is_empty() {
+            tracing::info!("已按条件删除 {} 个包版本（{} 个文档）", deleted.0.len(), deleted.1);
+        }
+        Ok((deleted.0.into_iter().collect(), deleted.1, deleted.2))
     }
-    
-    /// 标准化文本
-    fn normalize_text(&self, text: &str) -> String {
-        text.lines()
-            .map(|line| line.trim())
-            .filter(|line| !line.is_empty())
-            .collect::<Vec<_>>()
-            .join(" ")
-            .chars()
-            .filter(|c| c.is_alphanumeric() || c.is_whitespace() || ".,!?;:()[]{}\"'".contains(*c))
-            .collect::<String>()
-            .split_whitespace()
-            .collect::<Vec<_>>()
-            .join(" ")
+
+    /// 语料级近似重复报告（嵌入向量余弦相似度不低于 `threshold`），按缓存层级分别检测；
+    /// `merge` 为 true 时每簇只保留全文最长的文档，其余删除
+    pub fn dedup_report(&self, tier: Option<CacheTier>, threshold: f32, merge: bool) -> Result<DedupReport> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(MCPError::InvalidParameter(format!("threshold 必须在 0 到 1 之间: {}", threshold)).into());
+        }
+        let mut report = DedupReport { threshold, ..Default::default() };
+        for (store_tier, store) in self.tier_stores() {
+            if tier.map_or(false, |t| t != store_tier) {
+                continue;
+            }
+            let clusters = if merge {
+                // 检测和删除在同一个写锁内完成，避免期间的写入让报告失效
+                let mut store = self.acquire_store(store)?;
+                let clusters = store.duplicate_clusters(threshold);
+                let (removed_docs, removed_bytes) = store.merge_duplicates(&clusters)?;
+                report.merged_documents += removed_docs;
+                report.bytes_freed += removed_bytes;
+                clusters
+            } else {
+                self.read_store(store)?.duplicate_clusters(threshold)
+            };
+            report.duplicate_documents += clusters.iter().map(|cluster| cluster.duplicates.len()).sum::<usize>();
+            report.clusters.extend(clusters.into_iter().map(|cluster| ScopedCluster { scope: store_tier.as_str().to_string(), cluster }));
+        }
+        if report.merged_documents > 0 {
+            tracing::info!("去重合并 {} 个簇，删除 {} 个近似重复文档", report.clusters.len(), report.merged_documents);
+        }
+        Ok(report)
     }
-    
-    /// 提取结构特征
-    fn extract_structure_features(&self, text: &str) -> StructureFeatures {
-        let paragraph_count = text.split("\n\n").filter(|p| !p.trim().is_empty()).count();
-        let code_block_count = text.matches("```").count() / 2; // 代码块成对出现
-        let list_count = text.lines().filter(|line| {
-            let trimmed = line.trim();
-            trimmed.starts_with("- ") || trimmed.starts_with("* ") || 
-            trimmed.chars().next().map_or(false, |c| c.is_ascii_digit())
-        }).count();
-        
-        StructureFeatures {
-            paragraph_count,
-            code_block_count,
-            list_count,
+
+    /// 已移除依赖记录所在目录（工作区层，未启用工作区层时为全局层）
+    fn removed_dependencies_dir(&self) -> PathBuf {
+        self.store_for_tier(CacheTier::Workspace).read().unwrap().data_dir.clone()
+    }
+
+    /// 记录项目清单中已移除的依赖，`current` 中重新出现的依赖不再视为已移除，返回待清理的依赖数
+    pub fn record_removed_dependencies(&self, removed: &BTreeSet<(String, String)>, current: &ProjectProfile) -> Result<usize> {
+        let dir = self.removed_dependencies_dir();
+        let mut pending = dependency_cleanup::load(&dir);
+        let before = pending.clone();
+        for (language, package_name) in removed {
+            pending.entry(dependency_cleanup::dependency_key(language, package_name))
+                .or_insert_with(|| RemovedDependency {
+                    language: language.clone(),
+                    package_name: package_name.clone(),
+                    detected_at: chrono::Utc::now(),
+                });
         }
+        pending.retain(|_, dep| !current.has_dependency(&dep.language, &dep.package_name));
+        if pending != before {
+            dependency_cleanup::save(&dir, &pending)?;
+        }
+        Ok(pending.len())
     }
-    
-    /// 提取技术关键词
-    fn extract_technical_keywords(&self, text: &str) -> std::collections::HashSet<String> {
-        let technical_patterns = [
-            // 编程概念
-            r"\b(function|method|class|struct|enum|trait|interface|module|package|library|framework)\b",
-            // 数据类型
-            r"\b(string|integer|boolean|array|vector|hashmap|option|result|future|stream)\b",
-            // 操作关键词
-            r"\b(async|await|impl|pub|use|mod|crate|extern|unsafe|mut|ref|move|clone)\b",
-            // API相关
-            r"\b(api|endpoint|request|response|http|json|xml|rest|graphql|websocket)\b",
-        ];
-        
-        let mut keywords = std::collections::HashSet::new();
-        let text_lower = text.to_lowercase();
-        
-        for pattern in &technical_patterns {
-            if let Ok(regex) = regex::Regex::new(pattern) {
-                for mat in regex.find_iter(&text_lower) {
-                    keywords.insert(mat.as_str().to_string());
+
+    /// 已移除依赖在各层级中的缓存占用和可回收的总字节数
+    pub fn removed_dependency_status(&self) -> Result<Value> {
+        let pending = dependency_cleanup::load(&self.removed_dependencies_dir());
+        let mut usage: BTreeMap<String, (Vec<String>, u64)> = BTreeMap::new();
+        if !pending.is_empty() {
+            for (_, store) in self.tier_stores() {
+                let store = self.read_store(store)?;
+                let bytes_by_package = store.package_bytes();
+                for key in store.package_versions_matching(|dep| pending.contains_key(dep)) {
+                    let Some(dep) = dependency_cleanup::dependency_key_of_package_version(&key) else {
+                        continue;
+                    };
+                    let entry = usage.entry(dep).or_default();
+                    entry.1 += bytes_by_package.get(&key).copied().unwrap_or(0);
+                    entry.0.push(key);
                 }
             }
         }
-        
-        // 提取驼峰命名和下划线命名的标识符
-        if let Ok(identifier_regex) = regex::Regex::new(r"\b[a-zA-Z][a-zA-Z0-9_]*[a-zA-Z0-9]\b") {
-            for mat in identifier_regex.find_iter(&text) {
-                let identifier = mat.as_str();
-                if identifier.len() > 3 && (identifier.contains('_') || identifier.chars().any(|c| c.is_uppercase())) {
-                    keywords.insert(identifier.to_lowercase());
+
+        let dependencies: Vec<Value> = pending.iter()
+            .map(|(dep, removed)| {
+                let (package_versions, bytes) = usage.get(dep).cloned().unwrap_or_default();
+                json!({
+                    "language": removed.language,
+                    "package_name": removed.package_name,
+                    "detected_at": removed.detected_at,
+                    "package_versions": package_versions,
+                    "bytes": bytes,
+                })
+            })
+            .collect();
+        Ok(json!({
+            "dependencies": dependencies,
+            "reclaimable_bytes": usage.values().map(|(_, bytes)| bytes).sum::<u64>(),
+        }))
+    }
+
+    /// 清除已移除依赖的所有包版本（文档、已处理标记和抓取进度）并清空记录，
+    /// 返回 (清除的包版本数, 删除文档数, 释放字节数)
+    ///
+    /// 全局层按用户共享，其他仍依赖这些包的项目会在需要时重新抓取。
+    pub fn purge_removed_dependencies(&self) -> Result<(usize, usize, u64)> {
+        let dir = self.removed_dependencies_dir();
+        let pending = dependency_cleanup::load(&dir);
+        if pending.is_empty() {
+            return Ok((0, 0, 0));
+        }
+
+        let mut purged = (0, 0, 0);
+        for (_, store) in self.tier_stores() {
+            let mut store = self.acquire_store(store)?;
+            let keys = store.package_versions_matching(|dep| pending.contains_key(dep));
+            if keys.is_empty() {
+                continue;
+            }
+            let (removed_docs, removed_bytes) = store.remove_package_versions(&keys)?;
+            store.save()?;
+            purged.0 += keys.len();
+            purged.1 += removed_docs;
+            purged.2 += removed_bytes;
+        }
+        dependency_cleanup::save(&dir, &BTreeMap::new())?;
+        tracing::info!(
+            "已清除 {} 个已移除依赖的 {} 个包版本（{} 个文档，{} 字节）",
+            pending.len(), purged.0, purged.1, purged.2
+        );
+        Ok(purged)
+    }
+
+    /// 清除来源地址匹配的文档（响应内容删除请求），返回 (删除文档数, 释放字节数)
+    ///
+    /// `pattern` 以 `*` 结尾时按前缀匹配，否则精确匹配。tier 为 None 时清除所有层级和所有集合，
+    /// 否则只清除该层级。`visible_only` 为 true 时只清除当前调用方可见的文档（MCP 调用），
+    /// 运维命令传 false 以清除所有命名空间中的副本。
+    pub fn purge_source(&self, pattern: &str, tier: Option<CacheTier>, visible_only: bool) -> Result<(usize, u64)> {
+        if pattern.trim_end_matches('*').trim().is_empty() {
+            return Err(MCPError::InvalidParameter("来源地址不能为空".to_string()).into());
+        }
+        let mut stores: Vec<SharedStore> = self.tier_stores().into_iter()
+            .filter(|(store_tier, _)| tier.map_or(true, |t| t == *store_tier))
+            .map(|(_, store)| store.clone())
+            .collect();
+        if tier.is_none() {
+            for name in self.collection_names()? {
+                stores.push(self.collection_store(&name)?);
+            }
+        }
+
+        let mut purged = (0, 0);
+        for store in &stores {
+            let (removed_docs, removed_bytes) = self.acquire_store(store)?.remove_by_source(pattern, visible_only)?;
+            purged.0 += removed_docs;
+            purged.1 += removed_bytes;
+        }
+        if purged.0 > 0 {
+            tracing::info!("已按来源 {} 清除 {} 个文档", pattern, purged.0);
+        }
+        Ok(purged)
+    }
+
+    /// 订阅新增文档通知
+    pub fn subscribe_updates(&self) -> tokio::sync::broadcast::Receiver<CachedDocsUpdate> {
+        self.updates.subscribe()
+    }
+
+    fn notify_update(&self, language: &str, package_name: &str, version: &str) {
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.updates.send(CachedDocsUpdate {
+            language: language.to_string(),
+            package_name: package_name.to_string(),
+            version: version.to_string(),
+        });
+    }
+
+    /// 列出所有层级中已缓存的包版本（同一包版本跨层级合并）
+    pub fn list_cached_packages(&self) -> Result<Vec<CachedPackage>> {
+        let mut packages: std::collections::BTreeMap<(String, String, String), CachedPackage> = std::collections::BTreeMap::new();
+        let mut seen_ids = std::collections::HashSet::new();
+        for (_, store) in self.tier_stores() {
+            let store = self.read_store(store)?;
+            for doc in store.documents.values() {
+                if !seen_ids.insert(doc.id.clone()) {
+                    continue;
                 }
+                let entry = packages
+                    .entry((doc.language.clone(), doc.package_name.clone(), doc.version.clone()))
+                    .or_insert_with(|| CachedPackage {
+                        language: doc.language.clone(),
+                        package_name: doc.package_name.clone(),
+                        version: doc.version.clone(),
+                        doc_types: std::collections::BTreeMap::new(),
+                        document_count: 0,
+                    });
+                *entry.doc_types.entry(doc.doc_type.clone()).or_insert(0) += 1;
+                entry.document_count += 1;
             }
         }
-        
-        keywords
+        Ok(packages.into_values().collect())
     }
 
-    /// 智能重复检查（替代原来的哈希比较）
-    async fn intelligent_duplicate_check(&self, fragment: &FileDocumentFragment) -> Result<bool> {
-        let store_guard = self.store.lock().unwrap();
-        if let Some(existing_doc) = store_guard.get_document(&fragment.id) {
-            // 版本检查
-            if existing_doc.version != fragment.version {
-                tracing::info!("文档 {} 版本不同 (现有: {}, 新: {})，需要更新", 
-                    fragment.id, existing_doc.version, fragment.version);
-                return Ok(false); // 版本不同，不是重复
+    /// 获取某个包版本的已缓存文档（不含嵌入向量），可按文档类型过滤，按ID排序
+    pub fn package_documents(&self, language: &str, package_name: &str, version: &str, doc_type: Option<&str>) -> Result<Vec<DocumentRecord>> {
+        let mut by_id: HashMap<String, DocumentRecord> = HashMap::new();
+        for (_, store) in self.tier_stores() {
+            let store = self.read_store(store)?;
+            for doc in store.documents.values()
+                .filter(|doc| doc.language == language && doc.package_name == package_name && doc.version == version)
+                .filter(|doc| namespace::is_visible_metadata(&doc.metadata))
+                .filter(|doc| doc_type.map_or(true, |t| doc.doc_type == t))
+            {
+                by_id.entry(doc.id.clone()).or_insert_with(|| DocumentRecord {
+                    content: store.full_content(doc),
+                    embedding: Vec::new(),
+                    ..doc.clone()
+                });
             }
-            
-            // 智能相似度检测
-            let similarity = self.calculate_document_similarity(&existing_doc.content, &fragment.content).await?;
-            
-            // 相似度阈值：85%以上认为是重复内容
-            const SIMILARITY_THRESHOLD: f32 = 0.85;
-            
-            if similarity >= SIMILARITY_THRESHOLD {
-                tracing::info!("文档 {} 内容相似度 {:.2}%，判定为重复内容", 
-                    fragment.id, similarity * 100.0);
-                return Ok(true); // 是重复内容
-            } else {
-                tracing::info!("文档 {} 内容相似度 {:.2}%，判定为不同内容，需要更新", 
-                    fragment.id, similarity * 100.0);
-                return Ok(false); // 内容有显著差异，需要更新
+        }
+        let mut documents: Vec<DocumentRecord> = by_id.into_values().collect();
+        documents.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(documents)
+    }
+
+    /// 分页列出已缓存文档（不含内容和嵌入向量），按ID排序，可按层级、语言、包名过滤
+    pub fn list_documents(
+        &self,
+        tier: Option<CacheTier>,
+        language: Option<&str>,
+        package_name: Option<&str>,
+        cursor: Option<&str>,
+        page_size: usize,
+    ) -> Result<Page<Value>> {
+        let mut by_id: std::collections::BTreeMap<String, Value> = std::collections::BTreeMap::new();
+        for (doc_tier, store) in self.tier_stores() {
+            if tier.map_or(false, |t| t != doc_tier) {
+                continue;
+            }
+            let store = self.read_store(store)?;
+            for doc in store.documents.values()
+                .filter(|doc| namespace::is_visible_metadata(&doc.metadata))
+                .filter(|doc| language.map_or(true, |l| doc.language == l))
+                .filter(|doc| package_name.map_or(true, |p| doc.package_name == p))
+            {
+                by_id.entry(doc.id.clone()).or_insert_with(|| json!({
+                    "id": doc.id,
+                    "title": doc.title,
+                    "language": doc.language,
+                    "package_name": doc.package_name,
+                    "version": doc.version,
+                    "doc_type": doc.doc_type,
+                    "scope": doc_tier.as_str(),
+                }));
             }
         }
-        
-        Ok(false) // 文档不存在，不是重复
+        let documents: Vec<(String, Value)> = by_id.into_iter().collect();
+        let page = pagination::paginate(documents, |(id, _)| id.as_str(), cursor, page_size)?;
+        Ok(Page {
+            items: page.items.into_iter().map(|(_, doc)| doc).collect(),
+            next_cursor: page.next_cursor,
+            total: page.total,
+        })
     }
 
-    /// 公开方法，用于从 FileDocumentFragment 添加文档
-    /// 这个方法会被 BackgroundDocCacher 调用
-    pub async fn add_file_fragment(&self, fragment: &FileDocumentFragment) -> Result<String> {
-        if fragment.content.trim().is_empty() {
-            return Err(anyhow::anyhow!("文档内容为空，跳过嵌入和存储: {}", fragment.id));
+    /// 将某个包版本的已缓存文档导出为文档包JSON
+    pub fn export_doc_pack(&self, language: &str, package_name: &str, version: &str, signing_key: Option<&SigningKey>) -> Result<String> {
+        let mut by_id: HashMap<String, DocumentRecord> = HashMap::new();
+        for (_, store) in self.tier_stores() {
+            let store = self.read_store(store)?;
+            let rows = store.vector_rows();
+            for doc in store.documents.values()
+                .filter(|doc| doc.language == language && doc.package_name == package_name && doc.version == version)
+                .filter(|doc| namespace::is_visible_metadata(&doc.metadata))
+            {
+                by_id.entry(doc.id.clone()).or_insert_with(|| store.exported_document(doc, &rows));
+            }
         }
+        if by_id.is_empty() {
+            return Err(MCPError::NotFound(format!("缓存中没有 {}/{}/{} 的文档", language, package_name, version)).into());
+        }
+        let mut documents: Vec<DocumentRecord> = by_id.into_values().collect();
+        documents.sort_by(|a, b| a.id.cmp(&b.id));
+        DocPack::build_json(language, package_name, version, self.model_name(), &documents, signing_key)
+    }
 
-        // 智能重复检查
-        if let Ok(is_duplicate) = self.intelligent_duplicate_check(fragment).await {
-            if is_duplicate {
-                tracing::info!("文档 {} 已存在且内容相同，跳过添加。", fragment.id);
-                return Ok(fragment.id.clone());
-            } else {
-                tracing::info!("文档 {} 存在但内容已更新，将替换现有文档。", fragment.id);
-                // 继续执行以更新文档
+    /// 把满足过滤条件的文档（含全文和嵌入向量）导出为 JSONL 或 Parquet 语料文件
+    ///
+    /// `tier` 为 None 时导出所有层级，同一ID以工作区层为准；只导出当前调用方可见的文档。
+    pub fn export_corpus(
+        &self,
+        path: &std::path::Path,
+        format: CorpusFormat,
+        language: Option<&str>,
+        package_name: Option<&str>,
+        version: Option<&str>,
+        tier: Option<CacheTier>,
+    ) -> Result<CorpusReport> {
+        let mut by_id: HashMap<String, DocumentRecord> = HashMap::new();
+        for (store_tier, store) in self.tier_stores() {
+            if tier.map_or(false, |t| t != store_tier) {
+                continue;
+            }
+            let store = self.read_store(store)?;
+            let rows = store.vector_rows();
+            for doc in store.documents.values()
+                .filter(|doc| namespace::is_visible_metadata(&doc.metadata))
+                .filter(|doc| language.map_or(true, |l| doc.language == l))
+                .filter(|doc| package_name.map_or(true, |p| doc.package_name == p))
+                .filter(|doc| version.map_or(true, |v| doc.version == v))
+            {
+                by_id.entry(doc.id.clone()).or_insert_with(|| store.exported_document(doc, &rows));
             }
         }
+        let mut documents: Vec<DocumentRecord> = by_id.into_values().collect();
+        documents.sort_by(|a, b| a.id.cmp(&b.id));
+        let bytes = corpus_io::write_corpus(path, format, self.model_name(), &documents)?;
+        tracing::info!("已导出 {} 个文档到语料文件 {}（{} 字节）", documents.len(), path.display(), bytes);
+        Ok(CorpusReport {
+            path: path.display().to_string(),
+            format,
+            embedding_model: self.model_name().to_string(),
+            documents: documents.len(),
+            imported: None,
+            bytes,
+        })
+    }
 
-        let embedding = self.generate_embedding(&fragment.content).await
-            .map_err(|e| anyhow::anyhow!("为文档 {} 生成嵌入向量失败: {}", fragment.id, e))?;
+    /// 导入 JSONL 或 Parquet 语料文件中的文档（已存在的ID跳过）
+    ///
+    /// 向量必须由当前嵌入模型生成；`tier` 为 None 时按包名推断每个文档写入的层级。
+    pub fn import_corpus(&self, path: &std::path::Path, format: CorpusFormat, tier: Option<CacheTier>) -> Result<CorpusReport> {
+        let (documents, model) = corpus_io::read_corpus(path, format)?;
+        let embedding_model = model.unwrap_or_else(|| self.model_name().to_string());
+        if embedding_model != self.model_name() {
+            return Err(MCPError::InvalidParameter(format!(
+                "语料文件的向量由 {} 生成，与当前嵌入模型 {} 不一致", embedding_model, self.model_name()
+            )).into());
+        }
+        let total = documents.len();
+        let mut by_tier: BTreeMap<&'static str, (CacheTier, Vec<DocumentRecord>)> = BTreeMap::new();
+        for doc in documents {
+            let doc_tier = tier.unwrap_or_else(|| CacheTier::infer(&doc.package_name));
+            by_tier.entry(doc_tier.as_str()).or_insert_with(|| (doc_tier, Vec::new())).1.push(doc);
+        }
+        let mut imported = 0;
+        for (_, (doc_tier, documents)) in by_tier {
+            let mut packages: Vec<(String, String, String)> = documents.iter()
+                .map(|doc| (doc.language.clone(), doc.package_name.clone(), doc.version.clone()))
+                .collect();
+            packages.sort();
+            packages.dedup();
+            imported += self.ingest_documents(self.store_for_tier(doc_tier), documents)?;
+            for (language, package_name, version) in &packages {
+                self.notify_update(language, package_name, version);
+            }
+        }
+        tracing::info!("已从语料文件 {} 导入 {} 个文档（共 {} 个）", path.display(), imported, total);
+        Ok(CorpusReport {
+            path: path.display().to_string(),
+            format,
+            embedding_model,
+            documents: total,
+            imported: Some(imported),
+            bytes: fs::metadata(path)?.len(),
+        })
+    }
 
-        let mut metadata = HashMap::new();
-        metadata.insert("file_path".to_string(), fragment.file_path.clone());
-        metadata.insert("hierarchy_path".to_string(), fragment.hierarchy_path.join("/"));
-        metadata.insert("similarity_check".to_string(), "intelligent".to_string());
+    /// MCP 导出、导入操作使用的语料文件：数据目录 `exports` 下的 `名称.格式`
+    fn corpus_path(&self, args: &Value) -> Result<(PathBuf, CorpusFormat)> {
+        let name = args.get("name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| MCPError::InvalidParameter("export/import操作需要name参数".to_string()))?;
+        if !is_valid_collection_name(name) {
+            return Err(MCPError::InvalidParameter(format!("无效的语料文件名: {:?}（只允许字母、数字、- 和 _，最长 64 个字符）", name)).into());
+        }
+        let format = match args.get("format").and_then(|v| v.as_str()) {
+            Some(format) => CorpusFormat::parse(format)
+                .ok_or_else(|| MCPError::InvalidParameter(format!("不支持的语料格式: {}（可选 jsonl/parquet）", format)))?,
+            None => CorpusFormat::Jsonl,
+        };
+        let dir = self.store.read().unwrap().data_dir.join(EXPORTS_DIR);
+        Ok((dir.join(format!("{}.{}", name, format.extension())), format))
+    }
+
+    /// 快照文件的默认存放目录（管理工具只读写该目录下的快照）
+    pub fn snapshots_dir(&self) -> PathBuf {
+        self.store.read().unwrap().data_dir.join(SNAPSHOTS_DIR)
+    }
+
+    /// 所有层级和集合的存储及其在快照中的名称
+    fn snapshot_stores(&self) -> Result<Vec<(String, SharedStore)>> {
+        let mut stores: Vec<(String, SharedStore)> = self.tier_stores().into_iter()
+            .map(|(tier, store)| (tier.as_str().to_string(), store.clone()))
+            .collect();
+        for name in self.collection_names()? {
+            stores.push((format!("{}{}", COLLECTION_STORE_PREFIX, name), self.collection_store(&name)?));
+        }
+        Ok(stores)
+    }
+
+    /// 把所有层级和集合的文档、向量和已处理包版本标记写入单个快照文件
+    pub fn create_snapshot(&self, path: &std::path::Path) -> Result<SnapshotReport> {
+        let stores = self.snapshot_stores()?.iter()
+            .map(|(name, store)| Ok(self.read_store(store)?.snapshot(name)))
+            .collect::<Result<Vec<_>>>()?;
+        let snapshot = Snapshot::new(self.model_name(), stores);
+        let bytes = snapshot.write(path)?;
+        tracing::info!("已创建快照 {}: {} 个文档，{} 字节", path.display(), snapshot.manifest.document_count, bytes);
+        Ok(snapshot.report(path, bytes))
+    }
+
+    /// 从快照恢复：快照中每个层级和集合的现有内容被替换，快照中没有的集合保持不变，缺少的集合会创建
+    pub fn restore_snapshot(&self, path: &std::path::Path) -> Result<SnapshotReport> {
+        let snapshot = Snapshot::<DocumentRecord>::read(path)?;
+        snapshot.ensure_compatible(self.model_name())?;
+        let report = snapshot.report(path, fs::metadata(path)?.len());
+
+        // 先解析全部目标再写入，快照中有未知存储时不改动任何数据；
+        // 指向同一存储的快照合并写入（未启用工作区层时 workspace 落到全局层）
+        let mut targets: Vec<(SharedStore, Vec<StoreSnapshot<DocumentRecord>>)> = Vec::new();
+        for store_snapshot in snapshot.stores {
+            let store = match store_snapshot.name.strip_prefix(COLLECTION_STORE_PREFIX) {
+                Some(collection) => {
+                    self.create_collection(collection)?;
+                    self.collection_store(collection)?
+                }
+                None => {
+                    let tier = CacheTier::parse(&store_snapshot.name)
+                        .ok_or_else(|| MCPError::InvalidParameter(format!("快照中有未知的存储: {}", store_snapshot.name)))?;
+                    self.store_for_tier(tier).clone()
+                }
+            };
+            match targets.iter_mut().find(|(target, _)| Arc::ptr_eq(target, &store)) {
+                Some((_, snapshots)) => snapshots.push(store_snapshot),
+                None => targets.push((store, vec![store_snapshot])),
+            }
+        }
+        for (store, snapshots) in targets {
+            self.acquire_store(&store)?.restore_from(snapshots)?;
+        }
+        tracing::info!("已从快照 {} 恢复 {} 个文档", path.display(), report.documents);
+        Ok(report)
+    }
+
+    /// 用当前配置的嵌入模型重新生成所有层级和集合的向量，并记录新模型（更换嵌入模型后运行）
+    ///
+    /// 嵌入期间只持有读锁，搜索照常使用旧向量；每个存储嵌入完成后在写锁内整体替换。
+    pub async fn reembed_all(&self) -> Result<ReembedReport> {
+        let embedder = self.embedder()?;
+        let mut report = ReembedReport { model: self.model_name().to_string(), ..Default::default() };
+        let mut done: Vec<SharedStore> = Vec::new();
+        for (name, store) in self.snapshot_stores()? {
+            // 未启用工作区层时多个层级共用同一存储，只处理一次
+            if done.iter().any(|seen| Arc::ptr_eq(seen, &store)) {
+                continue;
+            }
+            done.push(store.clone());
+            let mut documents: Vec<DocumentRecord> = {
+                let guard = self.read_store(&store)?;
+                guard.documents.values()
+                    .map(|doc| DocumentRecord { content: guard.full_content(doc), ..doc.clone() })
+                    .collect()
+            };
+            let texts: Vec<String> = documents.iter().map(|doc| doc.content.clone()).collect();
+            let embeddings = self.embedding_batcher.embed_all(embedder.as_ref(), &texts, InputType::Passage).await?;
+            if embeddings.len() != documents.len() {
+                return Err(anyhow::anyhow!("{} 嵌入服务返回的向量数与文档数不一致", embedder.provider()));
+            }
+            for (doc, embedding) in documents.iter_mut().zip(embeddings) {
+                doc.embedding = embedding;
+            }
+            report.dimension = report.dimension.or(documents.first().map(|doc| doc.embedding.len()));
+            let replaced = self.acquire_store(&store)?.replace_embeddings(documents, self.model_name())?;
+            tracing::info!("已用 {} 重新嵌入 {} 的 {} 个文档", report.model, name, replaced);
+            report.stores += 1;
+            report.documents += replaced;
+        }
+        Ok(report)
+    }
 
-        let doc_record = DocumentRecord {
-            id: fragment.id.clone(),
-            content: fragment.content.clone(),
-            title: fragment.get_filename_without_ext().unwrap_or_else(|| "Unknown Title".to_string()),
-            language: fragment.language.clone(),
-            package_name: fragment.package_name.clone(),
-            version: fragment.version.clone(),
-            doc_type: format!("{:?}", fragment.file_type).to_lowercase(), // e.g., "source", "documentation"
-            metadata,
-            embedding,
-        };
+    /// 批量写入已带嵌入向量的文档（已存在的ID跳过），写入后重建索引并落盘
+    pub fn add_documents(&self, tier: CacheTier, documents: Vec<DocumentRecord>) -> Result<()> {
+        self.ingest_documents(self.store_for_tier(tier), documents).map(|_| ())
+    }
 
-        let mut store_guard = self.store.lock().unwrap();
-        store_guard.add_document(doc_record.clone())?; // 假设 add_document 内部会调用 save
-        
-        tracing::info!("文档 {} 已成功向量化并存储。", fragment.id);
-        Ok(fragment.id.clone())
+    /// 重建所有层级的向量索引和符号索引
+    pub fn rebuild_indexes(&self) -> Result<()> {
+        for (_, store) in self.tier_stores() {
+            let mut store = self.acquire_store(store)?;
+            store.rebuild_text_indexes();
+            // 丢弃现有分片，所有分片都重新构建
+            store.shards.clear();
+            store.rebuild_index()?;
+        }
+        Ok(())
     }
 
-    /// 批量添加 FileDocumentFragment
-    pub async fn add_file_fragments_batch(&self, fragments: &[FileDocumentFragment]) -> Result<Vec<String>> {
-        if fragments.is_empty() {
-            return Ok(Vec::new());
+    /// 将所有层级的向量数据和容量统计落盘（服务器关闭时调用）
+    pub fn flush(&self) -> Result<()> {
+        for (_, store) in self.tier_stores() {
+            store.write().unwrap().save()?;
         }
+        Ok(())
+    }
 
-        let mut added_ids = Vec::new();
-        let mut records_to_add = Vec::new();
+    /// 空闲时间超过阈值时让所有层级进入休眠，并清空嵌入缓存，返回是否执行了休眠
+    pub fn hibernate_if_idle(&self, idle_timeout: std::time::Duration) -> Result<bool> {
+        if self.last_activity.lock().unwrap().elapsed() < idle_timeout {
+            return Ok(false);
+        }
 
-        {
-            let store_guard = self.store.lock().unwrap();
-            for fragment in fragments {
-                if fragment.content.trim().is_empty() {
-                    tracing::warn!("文档内容为空，跳过嵌入和存储: {}", fragment.id);
-                    continue;
-                }
-                // 初步检查是否已存在 (更精细的检查在VectorStore的批量添加中进行)
-                if store_guard.get_document(&fragment.id).is_some() {
-                    tracing::info!("文档 {} 已存在于向量库 (初步检查)，跳过处理。", fragment.id);
-                    added_ids.push(fragment.id.clone()); // 认为已存在即为"已添加"
-                    continue;
-                }
-                 records_to_add.push(fragment);
+        let mut hibernated_any = false;
+        for (_, store) in self.tier_stores() {
+            let mut store = store.write().unwrap();
+            if !store.hibernated {
+                store.hibernate()?;
+                hibernated_any = true;
             }
         }
-        
-        if records_to_add.is_empty() && !added_ids.is_empty(){
-             return Ok(added_ids);
-        } else if records_to_add.is_empty() {
-            return Ok(Vec::new());
+        if hibernated_any {
+            self.embedding_cache.lock().unwrap().unload()?;
         }
+        Ok(hibernated_any)
+    }
 
-        let mut document_records = Vec::with_capacity(records_to_add.len());
-        for fragment_ref in records_to_add {
-            // 这里直接使用 fragment_ref, 因为 records_to_add 中的生命周期足够
-            let fragment = fragment_ref; 
-            match self.generate_embedding(&fragment.content).await {
-                Ok(embedding) => {
-                    let mut metadata = HashMap::new();
-                    metadata.insert("file_path".to_string(), fragment.file_path.clone());
-                    metadata.insert("hierarchy_path".to_string(), fragment.hierarchy_path.join("/"));
-
-                    document_records.push(DocumentRecord {
-                        id: fragment.id.clone(),
-                        content: fragment.content.clone(),
-                        title: fragment.get_filename_without_ext().unwrap_or_else(|| "Unknown Title".to_string()),
-                        language: fragment.language.clone(),
-                        package_name: fragment.package_name.clone(),
-                        version: fragment.version.clone(),
-                        doc_type: format!("{:?}", fragment.file_type).to_lowercase(),
-                        metadata,
-                        embedding,
-                    });
-                    added_ids.push(fragment.id.clone());
-                }
-                Err(e) => {
-                    tracing::error!("为文档 {} 生成嵌入向量失败: {}。将跳过此文档。", fragment.id, e);
+    /// 启动空闲休眠后台任务（守护进程模式）
+    ///
+    /// 空闲超过 `idle_timeout` 后释放HNSW索引和大缓存，下次访问存储时自动重新加载。
+    pub fn spawn_idle_hibernation(self: &Arc<Self>, idle_timeout: std::time::Duration) -> tokio::task::JoinHandle<()> {
+        let tool = Arc::clone(self);
+        let check_interval = (idle_timeout / 4).clamp(std::time::Duration::from_secs(1), std::time::Duration::from_secs(30));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(check_interval);
+            loop {
+                ticker.tick().await;
+                match tool.hibernate_if_idle(idle_timeout) {
+                    Ok(true) => tracing::info!("空闲超过 {:?}，已释放向量索引和缓存", idle_timeout),
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!("空闲休眠失败: {}", e),
                 }
             }
+        })
+    }
+
+    /// 检查嵌入服务并记录结果：返回维度还要与已存储的向量一致；未配置嵌入服务时返回 None
+    pub async fn check_embedding_health(&self) -> Option<EmbeddingHealth> {
+        let embedder = self.embedder.as_ref()?;
+        // 直接读取而不经过 read_store：健康检查不算作活动，不应推迟空闲休眠
+        let stored_dimension = self.tier_stores().into_iter().find_map(|(_, store)| store.read().unwrap().vector_dimension());
+        let health = embedder.health_check().await.with_expected_dimension(stored_dimension);
+        match &health.reason {
+            None => tracing::info!("嵌入服务正常: {} {}（{} 维，{} ms）", health.provider, health.model, health.dimension.unwrap_or(0), health.latency_ms),
+            Some(reason) => tracing::warn!("嵌入服务检查未通过（{:?}）: {}", health.status, reason),
+        }
+        *self.embedding_health.write().unwrap() = Some(health.clone());
+        Some(health)
+    }
+
+    /// 最近一次嵌入服务健康检查的结果
+    pub fn embedding_health(&self) -> Option<EmbeddingHealth> {
+        self.embedding_health.read().unwrap().clone()
+    }
+
+    /// 启动定期的嵌入服务健康检查（第一次在 `interval` 之后）
+    pub fn spawn_embedding_health_checks(self: &Arc<Self>, interval: std::time::Duration) -> tokio::task::JoinHandle<()> {
+        let tool = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            loop {
+                ticker.tick().await;
+                tool.check_embedding_health().await;
+            }
+        })
+    }
+
+    /// 设置项目画像，之后的混合搜索优先返回项目依赖的包
+    pub fn set_project_profile(&self, profile: Option<ProjectProfile>) {
+        *self.project_profile.write().unwrap() = profile;
+    }
+
+    /// 当前项目画像
+    pub fn project_profile(&self) -> Option<ProjectProfile> {
+        self.project_profile.read().unwrap().clone()
+    }
+
+    /// 符号索引中定义或提到该符号的文档（全文），合并所有层级；完整路径一致的结果在 metadata 中记录
+    /// `symbol_exact`
+    pub fn lookup_symbol(&self, symbol: &str) -> Result<Vec<SearchResult>> {
+        let mut results = Vec::new();
+        for (_, store) in self.tier_stores() {
+            let store = self.read_store(store)?;
+            for hit in store.symbol_index.lookup(symbol) {
+                let Some(doc) = store.documents.get(&hit.doc_id).filter(|doc| namespace::is_visible_metadata(&doc.metadata)) else {
+                    continue;
+                };
+                let mut metadata = doc.metadata.clone();
+                metadata.insert("symbol_exact".to_string(), hit.exact.to_string());
+                results.push(SearchResult {
+                    id: doc.id.clone(),
+                    content: store.full_content(doc),
+                    title: doc.title.clone(),
+                    language: doc.language.clone(),
+                    package_name: doc.package_name.clone(),
+                    version: doc.version.clone(),
+                    doc_type: doc.doc_type.clone(),
+                    metadata,
+                    score: if hit.exact { 1.0 } else { 0.5 },
+                });
+            }
         }
-        
-        if !document_records.is_empty() {
-            let mut store_guard = self.store.lock().unwrap();
-            // 假设 VectorStore 有一个批量添加方法
-            match store_guard.add_documents_batch(document_records) { 
-                Ok(_) => tracing::info!("成功批量添加 {} 个新文档记录到向量库。", added_ids.len()),
-                Err(e) => tracing::error!("批量添加文档到向量库失败: {}", e),
+        Ok(results)
+    }
+
+    /// search 操作的过滤条件：查询提到锁文件中的包时锁定到项目使用的版本，`pin_versions` 为 false 时不锁定
+    fn search_filter(&self, query: &str, args: &Value) -> SearchFilter {
+        let mut filter = SearchFilter::from_params(args);
+        if args.get("pin_versions").and_then(|v| v.as_bool()) != Some(false) {
+            if let Some(profile) = self.project_profile.read().unwrap().as_ref() {
+                profile.pin_locked_versions(query, &mut filter);
             }
         }
+        filter
+    }
 
-        Ok(added_ids)
+    /// 公开的混合搜索方法
+    pub fn hybrid_search(&self, query_embedding: &[f32], query_text: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        self.hybrid_search_in_tiers(query_embedding, query_text, limit, None, &SearchFilter::default())
     }
 
-    /// 检查某个包的特定版本是否已被标记为完整处理
-    pub fn has_processed_package_version(&self, language: &str, package_name: &str, version: &str) -> bool {
-        let store_guard = self.store.lock().unwrap();
-        store_guard.has_processed_package_version(language, package_name, version)
+    /// 在指定层级（None表示合并所有层级）中执行混合搜索，只返回满足过滤条件的文档
+    pub fn hybrid_search_in_tiers(
+        &self,
+        query_embedding: &[f32],
+        query_text: &str,
+        limit: usize,
+        tier: Option<CacheTier>,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchResult>> {
+        let mut tiered_results = Vec::new();
+        for (store_tier, store) in self.tier_stores() {
+            if tier.map_or(false, |t| t != store_tier) {
+                continue;
+            }
+            let store = self.read_store(store)?;
+            let mut results = store.hybrid_search(query_embedding, query_text, limit, filter)?;
+            store.record_search_hits(&results);
+            if let Some(profile) = self.project_profile.read().unwrap().as_ref() {
+                profile.apply(&mut results);
+            }
+            tiered_results.push((store_tier, results));
+        }
+        Ok(Self::merge_tier_results(tiered_results, limit))
     }
 
-    /// 标记某个包的特定版本为已完整处理
-    pub fn mark_package_version_as_processed(&self, language: &str, package_name: &str, version: &str) -> Result<()> {
-        let mut store_guard = self.store.lock().unwrap();
-        store_guard.mark_package_version_as_processed(language, package_name, version)
+    /// 在指定层级（None表示合并所有层级）中执行 BM25 全文搜索，不需要生成查询向量
+    pub fn text_search_in_tiers(&self, query_text: &str, limit: usize, tier: Option<CacheTier>, filter: &SearchFilter) -> Result<Vec<SearchResult>> {
+        let mut tiered_results = Vec::new();
+        for (store_tier, store) in self.tier_stores() {
+            if tier.map_or(false, |t| t != store_tier) {
+                continue;
+            }
+            let store = self.read_store(store)?;
+            let mut results = store.text_search(query_text, limit, filter);
+            store.record_search_hits(&results);
+            if let Some(profile) = self.project_profile.read().unwrap().as_ref() {
+                profile.apply(&mut results);
+            }
+            tiered_results.push((store_tier, results));
+        }
+        Ok(Self::merge_tier_results(tiered_results, limit))
     }
 
-    /// 获取系统状态和统计信息
-    pub fn get_system_status(&self) -> Value {
-        let store = self.store.lock().unwrap();
-        let (doc_count, vector_count) = store.get_stats();
-        
-        let cache_stats = {
-            let cache = self.embedding_cache.lock().unwrap();
-            json!({
-                "cached_embeddings": cache.len(),
-                "cache_limit": 1000,
-                "cache_usage_percent": (cache.len() as f32 / 1000.0 * 100.0).round()
-            })
+    /// 用重排模型对融合后的候选重新打分排序，返回前 `limit` 个和是否完成了重排
+    ///
+    /// 未配置重排模型或重排请求失败时按原有顺序截断，不让搜索失败。重排后的分数为模型给出的相关性，
+    /// 原来的融合分数记在元数据 `fused_score` 中。
+    async fn rerank_results(&self, query: &str, mut candidates: Vec<SearchResult>, limit: usize) -> (Vec<SearchResult>, bool) {
+        let Some(reranker) = &self.reranker else {
+            tracing::warn!("请求了重排但未配置重排模型（RERANK_API_KEY），返回融合排序结果");
+            candidates.truncate(limit);
+            return (candidates, false);
         };
-        
-        json!({
-            "status": "运行中",
-            "database": {
-                "total_documents": doc_count,
-                "total_vectors": vector_count,
-                "backend": "instant-distance (HNSW)"
-            },
-            "cache": cache_stats,
-            "api": {
-                "provider": "NVIDIA",
-                "model": self.model_name,
-                "has_api_key": !self.api_key.is_empty()
-            },
-            "performance": {
-                "search_algorithm": "混合搜索 (向量60% + 关键词30% + 上下文10%)",
-                "similarity_detection": "智能多维度相似度检测",
-                "caching": "MD5哈希缓存，24小时有效期"
+        let passages = candidates.iter()
+            .map(|r| format!("{}\n{}", r.title, r.content).chars().take(RERANK_PASSAGE_CHARS).collect())
+            .collect();
+        let scores = match reranker.score(query, passages).await {
+            Ok(scores) => scores,
+            Err(e) => {
+                tracing::warn!("重排失败，返回融合排序结果: {}", e);
+                candidates.truncate(limit);
+                return (candidates, false);
             }
-        })
+        };
+
+        let mut slots: Vec<Option<SearchResult>> = candidates.into_iter().map(Some).collect();
+        let relevance = score_normalization::normalize_rerank_scores(&scores.iter().map(|s| s.relevance_score).collect::<Vec<_>>());
+        let mut reranked: Vec<SearchResult> = scores.iter()
+            .zip(relevance)
+            .filter_map(|(scored, relevance)| {
+                let mut result = slots.get_mut(scored.index)?.take()?;
+                result.metadata.insert("fused_score".to_string(), result.score.to_string());
+                result.metadata.insert(score_normalization::RAW_SCORE_METADATA_KEY.to_string(), scored.relevance_score.to_string());
+                result.metadata.insert(score_normalization::SCORE_KIND_METADATA_KEY.to_string(), ScoreKind::Rerank.as_str().to_string());
+                result.score = relevance;
+                Some(result)
+            })
+            .collect();
+        reranked.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        reranked.truncate(limit);
+        (reranked, true)
     }
 
-    /// 批量生成嵌入向量
-    pub async fn generate_embeddings_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-        if texts.is_empty() {
-            return Ok(Vec::new());
+    /// search 操作是否只做全文检索（`mode` 为 `text`）
+    fn is_text_mode(args: &Value) -> Result<bool> {
+        match args.get("mode").and_then(|v| v.as_str()) {
+            None | Some("hybrid") => Ok(false),
+            Some("text") => Ok(true),
+            Some(other) => Err(MCPError::InvalidParameter(format!("不支持的搜索模式: {}（可选 hybrid、text）", other)).into()),
         }
+    }
 
-        // 检查缓存
-        let mut cached_embeddings = Vec::new();
-        let mut uncached_texts = Vec::new();
-        let mut uncached_indices = Vec::new();
+    /// 文档属于其他命名空间（对当前调用方不可见）
+    fn owned_by_other_namespace(store: &VectorStore, id: &str) -> bool {
+        store.documents.get(id).map_or(false, |doc| !namespace::is_visible_metadata(&doc.metadata))
+    }
 
-        {
-            let cache = self.embedding_cache.lock().unwrap();
-            for (idx, text) in texts.iter().enumerate() {
-                let hash = format!("{:x}", md5::compute(text));
-                if let Some((embedding, timestamp)) = cache.get(&hash) {
-                    // 检查是否过期（24小时）
-                    if timestamp.elapsed().unwrap_or(std::time::Duration::from_secs(86401)) < std::time::Duration::from_secs(86400) {
-                        cached_embeddings.push((idx, embedding.clone()));
-                        continue;
-                    }
+    /// 按 store 操作的参数构造文档并生成嵌入向量
+    async fn document_from_args(&self, args: &Value) -> Result<DocumentRecord> {
+        let text = |key: &str, default: &'static str| args.get(key).and_then(|v| v.as_str()).unwrap_or(default).to_string();
+        let content = args.get("content")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| MCPError::InvalidParameter("store操作需要content参数".to_string()))?;
+
+        let embedding = self.generate_embedding(content).await
+            .map_err(|e| server_error("生成嵌入向量失败", e))?;
+
+        let mut metadata_map = HashMap::new();
+        if let Some(meta_obj) = args.get("metadata").and_then(|v| v.as_object()) {
+            for (k, v) in meta_obj {
+                if let Some(val_str) = v.as_str() {
+                    metadata_map.insert(k.clone(), val_str.to_string());
                 }
-                uncached_texts.push(text.clone());
-                uncached_indices.push(idx);
             }
         }
+        if let Some(source_url) = args.get("source_url").and_then(|v| v.as_str()) {
+            metadata_map.insert(provenance::SOURCE_URL_METADATA_KEY.to_string(), source_url.to_string());
+        }
+        // 调用方未指定时补充检测到的语言、许可证和署名
+        for (key, value) in content_language::language_metadata(content) {
+            metadata_map.entry(key).or_insert(value);
+        }
+        provenance::detect_into(&mut metadata_map, content);
+        namespace::tag_metadata(&mut metadata_map);
 
-        // 为未缓存的文本生成嵌入
-        let mut new_embeddings = Vec::new();
-        if !uncached_texts.is_empty() {
-            let request = EmbeddingRequest {
-                input: uncached_texts.clone(),
-                model: self.model_name.clone(),
-                input_type: "query".to_string(),
-            };
+        Ok(DocumentRecord {
+            id: args.get("id").and_then(|v| v.as_str()).map_or_else(|| Uuid::new_v4().to_string(), |s| s.to_string()),
+            content: content.to_string(),
+            title: text("title", "未命名文档"),
+            language: text("language", "unknown"),
+            package_name: text("package_name", "unknown"),
+            version: text("version", "unknown"),
+            doc_type: text("doc_type", "text"),
+            metadata: metadata_map,
+            embedding,
+        })
+    }
 
-            let response = self.client
-                .post("https://integrate.api.nvidia.com/v1/embeddings")
-                .header("Authorization", format!("Bearer {}", self.api_key))
-                .header("Content-Type", "application/json")
-                .json(&request)
-                .send()
-                .await?;
+    /// 在指定集合中执行 store/search/get/delete/list，集合只有一层存储
+    async fn execute_in_collection(&self, collection: &str, action: &str, args: &Value) -> Result<Value> {
+        let store = self.collection_store(collection)?;
+        match action {
+            "store" => {
+                if let Some(id) = args.get("id").and_then(|v| v.as_str()) {
+                    if Self::owned_by_other_namespace(&self.read_store(&store)?, id) {
+                        return Err(MCPError::AuthorizationError(format!("文档 {} 属于其他命名空间", id)).into());
+                    }
+                }
+                let doc = self.document_from_args(args).await?;
+                let document_id = doc.id.clone();
+                self.ingest_documents(&store, vec![doc])
+                    .map_err(|e| MCPError::ServerError(format!("存储文档失败: {}", e)))?;
 
-            if !response.status().is_success() {
-                return Err(anyhow::anyhow!("NVIDIA API请求失败: {}", response.status()));
+                Ok(json!({
+                    "status": "success",
+                    "document_id": document_id,
+                    "collection": collection
+                }))
             }
 
-            let embedding_response: EmbeddingResponse = response.json().await?;
-            
-            if embedding_response.data.len() != uncached_texts.len() {
-                return Err(anyhow::anyhow!("返回的嵌入数量与请求文本数量不匹配"));
+            "search" => {
+                let query = args.get("query")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("search操作需要query参数".to_string()))?;
+                let limit = args.get("limit")
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| s.parse::<usize>().ok())
+                    .unwrap_or(5);
+                let rerank = args.get("rerank").and_then(|v| v.as_bool()).unwrap_or(false);
+                let fetch = if rerank { limit * RERANK_CANDIDATE_FACTOR } else { limit };
+                let text_mode = Self::is_text_mode(args)?;
+                let query_embedding = if text_mode {
+                    Vec::new()
+                } else {
+                    self.generate_embedding(query).await
+                        .map_err(|e| server_error("生成查询嵌入向量失败", e))?
+                };
+
+                let filter = self.search_filter(query, args);
+                let mut results = {
+                    let store = self.read_store(&store)?;
+                    let results = if text_mode {
+                        store.text_search(query, fetch, &filter)
+                    } else {
+                        store.hybrid_search(&query_embedding, query, fetch, &filter)
+                            .map_err(|e| MCPError::ServerError(format!("搜索失败: {}", e)))?
+                    };
+                    store.record_search_hits(&results);
+                    results
+                };
+                if let Some(profile) = self.project_profile.read().unwrap().as_ref() {
+                    profile.apply(&mut results);
+                }
+                let (results, reranked) = if rerank {
+                    self.rerank_results(query, results, limit).await
+                } else {
+                    (results, false)
+                };
+
+                Ok(json!({
+                    "status": "success",
+                    "query": query,
+                    "filter": filter,
+                    "collection": collection,
+                    "results": results,
+                    "results_count": results.len(),
+                    "reranked": reranked
+                }))
             }
 
-            for (i, embedding_data) in embedding_response.data.into_iter().enumerate() {
-                new_embeddings.push((uncached_indices[i], embedding_data.embedding));
+            "get" => {
+                let id = args.get("id")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("get操作需要id参数".to_string()))?;
+                let doc = self.read_store(&store)?
+                    .get_document(id)
+                    .filter(|doc| namespace::is_visible_metadata(&doc.metadata));
+
+                Ok(match doc {
+                    Some(doc) => json!({
+                        "status": "success",
+                        "document": {
+                            "id": doc.id,
+                            "title": doc.title,
+                            "content": doc.content,
+                            "language": doc.language,
+                            "doc_type": doc.doc_type,
+                            "metadata": doc.metadata
+                        },
+                        "collection": collection
+                    }),
+                    None => json!({
+                        "status": "not_found",
+                        "message": "文档未找到",
+                        "document_id": id,
+                        "collection": collection
+                    }),
+                })
             }
 
-            // 缓存新的嵌入
-            {
-                let mut cache = self.embedding_cache.lock().unwrap();
-                for (i, text) in uncached_texts.iter().enumerate() {
-                    let hash = format!("{:x}", md5::compute(text));
-                    if let Some((_, embedding)) = new_embeddings.get(i) {
-                        cache.insert(hash, (embedding.clone(), std::time::SystemTime::now()));
-                    }
-                }
+            "delete" => {
+                let id = args.get("id")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("delete操作需要id参数".to_string()))?;
+                let mut store = self.acquire_store(&store)?;
+                let deleted = !Self::owned_by_other_namespace(&store, id)
+                    && store.delete_document(id).map_err(|e| MCPError::ServerError(format!("删除文档失败: {}", e)))?;
 
-                // 清理缓存（如果超过1000个条目）
-                if cache.len() > 1000 {
-                    let cutoff_time = std::time::SystemTime::now() - std::time::Duration::from_secs(43200); // 12小时前
-                    cache.retain(|_, (_, timestamp)| *timestamp > cutoff_time);
-                }
+                Ok(json!({
+                    "status": if deleted { "success" } else { "not_found" },
+                    "document_id": id,
+                    "collection": collection
+                }))
             }
-        }
 
-        // 合并缓存和新生成的嵌入
-        let mut final_embeddings = vec![Vec::new(); texts.len()];
-        for (idx, embedding) in cached_embeddings {
-            final_embeddings[idx] = embedding;
-        }
-        for (idx, embedding) in new_embeddings {
-            final_embeddings[idx] = embedding;
-        }
+            "list" => {
+                let language = args.get("language").and_then(|v| v.as_str());
+                let package_name = args.get("package_name").and_then(|v| v.as_str());
+                let store = self.read_store(&store)?;
+                let mut documents: Vec<&DocumentRecord> = store.documents.values()
+                    .filter(|doc| namespace::is_visible_metadata(&doc.metadata))
+                    .filter(|doc| language.map_or(true, |l| doc.language == l))
+                    .filter(|doc| package_name.map_or(true, |p| doc.package_name == p))
+                    .collect();
+                documents.sort_by(|a, b| a.id.cmp(&b.id));
+                let page = pagination::paginate(
+                    documents,
+                    |doc| doc.id.as_str(),
+                    pagination::cursor_param(args),
+                    pagination::page_size_param(args),
+                )?;
 
-        Ok(final_embeddings)
-    }
+                Ok(json!({
+                    "status": "success",
+                    "documents": page.items.iter().map(|doc| json!({
+                        "id": doc.id,
+                        "title": doc.title,
+                        "language": doc.language,
+                        "package_name": doc.package_name,
+                        "version": doc.version,
+                        "doc_type": doc.doc_type,
+                    })).collect::<Vec<_>>(),
+                    "total": page.total,
+                    "next_cursor": page.next_cursor,
+                    "collection": collection
+                }))
+            }
 
-    /// 公开的混合搜索方法
-    pub fn hybrid_search(&self, query_embedding: &[f32], query_text: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        let store = self.store.lock().unwrap();
-        store.hybrid_search(query_embedding, query_text, limit)
+            _ => Err(MCPError::InvalidParameter(format!("集合不支持操作: {}", action)).into()),
+        }
     }
 
     /// 公开的向量相似度搜索方法
     pub fn search_similar(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
-        let store = self.store.lock().unwrap();
-        store.search_similar(query_embedding, limit)
+        let mut tiered_results = Vec::new();
+        for (tier, store) in self.tier_stores() {
+            let store = self.read_store(store)?;
+            let mut results = store.search_similar(query_embedding, limit, &SearchFilter::default())?;
+            for result in &mut results {
+                score_normalization::normalize_result(ScoreKind::Vector, result);
+            }
+            store.hydrate(&mut results);
+            tiered_results.push((tier, results));
+        }
+        Ok(Self::merge_tier_results(tiered_results, limit))
     }
 }
 
@@ -1318,58 +4256,46 @@ impl MCPTool for VectorDocsTool {
         &self.schema
     }
 
+    fn mutating_actions(&self) -> &[&'static str] {
+        &["store", "delete", "import_pack", "import_devdocs", "enrich_qa", "create_collection", "drop_collection", "purge_source", "purge_removed_dependencies", "delete_by_filter", "export", "import", "dedup_report"]
+    }
+
     async fn execute(&self, args: Value) -> Result<Value> {
         let action = args.get("action")
             .and_then(|v| v.as_str())
             .ok_or_else(|| MCPError::InvalidParameter("缺少action参数".to_string()))?;
 
+        if let Some(collection) = Self::collection_param(&args) {
+            if matches!(action, "store" | "search" | "get" | "delete" | "list") {
+                return self.execute_in_collection(collection, action, &args).await;
+            }
+        }
+
         match action {
             "store" => {
-                let content = args.get("content")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| MCPError::InvalidParameter("store操作需要content参数".to_string()))?;
-                let title = args.get("title").and_then(|v| v.as_str()).unwrap_or("未命名文档");
-                let language = args.get("language").and_then(|v| v.as_str()).unwrap_or("unknown");
                 let package_name = args.get("package_name").and_then(|v| v.as_str()).unwrap_or("unknown");
-                let version = args.get("version").and_then(|v| v.as_str()).unwrap_or("unknown");
-                let doc_type = args.get("doc_type").and_then(|v| v.as_str()).unwrap_or("text");
-                let id_param = args.get("id").and_then(|v| v.as_str());
-
-                let embedding = self.generate_embedding(content).await
-                    .map_err(|e| MCPError::ServerError(format!("生成嵌入向量失败: {}", e)))?;
+                let tier = args.get("scope")
+                    .and_then(|v| v.as_str())
+                    .and_then(CacheTier::parse)
+                    .unwrap_or_else(|| CacheTier::infer(package_name));
 
-                let doc_id = id_param.map_or_else(|| Uuid::new_v4().to_string(), |s| s.to_string());
-                
-                let mut metadata_map = HashMap::new();
-                if let Some(meta_val) = args.get("metadata") {
-                    if let Some(meta_obj) = meta_val.as_object() {
-                        for (k,v) in meta_obj {
-                            if let Some(val_str) = v.as_str() {
-                                metadata_map.insert(k.clone(), val_str.to_string());
-                            }
+                // 不能覆盖其他命名空间的同ID文档
+                if let Some(id) = args.get("id").and_then(|v| v.as_str()) {
+                    for (_, store) in self.tier_stores() {
+                        if Self::owned_by_other_namespace(&self.read_store(store)?, id) {
+                            return Err(MCPError::AuthorizationError(format!("文档 {} 属于其他命名空间", id)).into());
                         }
                     }
                 }
 
-                let doc = DocumentRecord {
-                    id: doc_id,
-                    content: content.to_string(),
-                    title: title.to_string(),
-                    language: language.to_string(),
-                    package_name: package_name.to_string(),
-                    version: version.to_string(),
-                    doc_type: doc_type.to_string(),
-                    metadata: metadata_map,
-                    embedding,
-                };
-
-                let mut store = self.store.lock().unwrap();
-                store.add_document(doc.clone())
+                let doc = self.document_from_args(&args).await?;
+                self.ingest_documents(self.store_for_tier(tier), vec![doc.clone()])
                     .map_err(|e| MCPError::ServerError(format!("存储文档失败: {}", e)))?;
 
                 Ok(json!({
                     "status": "success",
-                    "document_id": doc.id
+                    "document_id": doc.id,
+                    "scope": tier.as_str()
                 }))
             }
 
@@ -1383,19 +4309,35 @@ impl MCPTool for VectorDocsTool {
                     .and_then(|s| s.parse::<usize>().ok())
                     .unwrap_or(5);
 
-                // 生成查询嵌入向量
-                let query_embedding = self.generate_embedding(query).await
-                    .map_err(|e| MCPError::ServerError(format!("生成查询嵌入向量失败: {}", e)))?;
+                // 重排时多取候选，交给重排模型后再截断
+                let rerank = args.get("rerank").and_then(|v| v.as_bool()).unwrap_or(false);
+                let fetch = if rerank { limit * RERANK_CANDIDATE_FACTOR } else { limit };
 
-                let store = self.store.lock().unwrap();
-                let results = store.hybrid_search(&query_embedding, query, limit)
-                    .map_err(|e| MCPError::ServerError(format!("搜索失败: {}", e)))?;
+                let tier = args.get("scope").and_then(|v| v.as_str()).and_then(CacheTier::parse);
+                let filter = self.search_filter(query, &args);
+                let results = if Self::is_text_mode(&args)? {
+                    self.text_search_in_tiers(query, fetch, tier, &filter)
+                        .map_err(|e| MCPError::ServerError(format!("搜索失败: {}", e)))?
+                } else {
+                    // 生成查询嵌入向量
+                    let query_embedding = self.generate_embedding(query).await
+                        .map_err(|e| server_error("生成查询嵌入向量失败", e))?;
+                    self.hybrid_search_in_tiers(&query_embedding, query, fetch, tier, &filter)
+                        .map_err(|e| MCPError::ServerError(format!("搜索失败: {}", e)))?
+                };
+                let (results, reranked) = if rerank {
+                    self.rerank_results(query, results, limit).await
+                } else {
+                    (results, false)
+                };
 
                 Ok(json!({
                     "status": "success",
                     "query": query,
+                    "filter": filter,
                     "results": results,
                     "results_count": results.len(),
+                    "reranked": reranked,
                     "database": "instant-distance (嵌入式)"
                 }))
             }
@@ -1405,8 +4347,17 @@ impl MCPTool for VectorDocsTool {
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| MCPError::InvalidParameter("get操作需要id参数".to_string()))?;
 
-                let store = self.store.lock().unwrap();
-                if let Some(doc) = store.get_document(id) {
+                let requested_tier = args.get("scope").and_then(|v| v.as_str()).and_then(CacheTier::parse);
+                let found = self.tier_stores().into_iter()
+                    .filter(|(tier, _)| requested_tier.map_or(true, |t| t == *tier))
+                    .find_map(|(tier, store)| {
+                        let store = self.read_store(store)?;
+                        store.get_document(id)
+                            .filter(|doc| namespace::is_visible_metadata(&doc.metadata))
+                            .map(|doc| (tier, doc))
+                    });
+
+                if let Some((tier, doc)) = found {
                     Ok(json!({
                         "status": "success",
                         "document": {
@@ -1417,6 +4368,7 @@ impl MCPTool for VectorDocsTool {
                             "doc_type": doc.doc_type,
                             "metadata": doc.metadata
                         },
+                        "scope": tier.as_str(),
                         "database": "instant-distance (嵌入式)"
                     }))
                 } else {
@@ -1434,9 +4386,19 @@ impl MCPTool for VectorDocsTool {
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| MCPError::InvalidParameter("delete操作需要id参数".to_string()))?;
 
-                let mut store = self.store.lock().unwrap();
-                let deleted = store.delete_document(id)
-                    .map_err(|e| MCPError::ServerError(format!("删除文档失败: {}", e)))?;
+                let requested_tier = args.get("scope").and_then(|v| v.as_str()).and_then(CacheTier::parse);
+                let mut deleted = false;
+                for (tier, store) in self.tier_stores() {
+                    if requested_tier.map_or(false, |t| t != tier) {
+                        continue;
+                    }
+                    let mut store = self.acquire_store(store)?;
+                    if store.documents.get(id).map_or(false, |doc| !namespace::is_visible_metadata(&doc.metadata)) {
+                        continue;
+                    }
+                    deleted |= store.delete_document(id)
+                        .map_err(|e| MCPError::ServerError(format!("删除文档失败: {}", e)))?;
+                }
 
                 if deleted {
                     Ok(json!({
@@ -1455,6 +4417,177 @@ impl MCPTool for VectorDocsTool {
                 }
             }
 
+            "list" => {
+                let tier = args.get("scope").and_then(|v| v.as_str()).and_then(CacheTier::parse);
+                let page = self.list_documents(
+                    tier,
+                    args.get("language").and_then(|v| v.as_str()),
+                    args.get("package_name").and_then(|v| v.as_str()),
+                    pagination::cursor_param(&args),
+                    pagination::page_size_param(&args),
+                )?;
+
+                Ok(json!({
+                    "status": "success",
+                    "documents": page.items,
+                    "total": page.total,
+                    "next_cursor": page.next_cursor,
+                    "database": "instant-distance (嵌入式)"
+                }))
+            }
+
+            "import_pack" => {
+                let source = args.get("source")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("import_pack操作需要source参数".to_string()))?;
+
+                let report = self.import_doc_pack(source).await
+                    .map_err(|e| MCPError::ServerError(format!("导入文档包失败: {}", e)))?;
+
+                Ok(json!({
+                    "status": "success",
+                    "report": report,
+                    "database": "instant-distance (嵌入式)"
+                }))
+            }
+
+            "enrich_qa" => {
+                let package_name = args.get("package_name")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("enrich_qa操作需要package_name参数".to_string()))?;
+                let language = args.get("language")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("enrich_qa操作需要language参数".to_string()))?;
+                let version = args.get("version").and_then(|v| v.as_str()).unwrap_or("latest");
+                let error_messages: Vec<String> = args.get("query")
+                    .and_then(|v| v.as_str())
+                    .map(|q| vec![q.to_string()])
+                    .unwrap_or_default();
+
+                let report = self.enrich_with_qa(language, package_name, version, &error_messages).await
+                    .map_err(|e| MCPError::ServerError(format!("问答补充失败: {}", e)))?;
+
+                Ok(json!({
+                    "status": "success",
+                    "report": report,
+                    "database": "instant-distance (嵌入式)"
+                }))
+            }
+
+            "create_collection" => {
+                let name = args.get("collection")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("create_collection操作需要collection参数".to_string()))?;
+                let created = self.create_collection(name)?;
+
+                Ok(json!({
+                    "status": if created { "success" } else { "exists" },
+                    "collection": name
+                }))
+            }
+
+            "drop_collection" => {
+                let name = args.get("collection")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("drop_collection操作需要collection参数".to_string()))?;
+                let dropped = self.drop_collection(name)?;
+
+                Ok(json!({
+                    "status": if dropped { "success" } else { "not_found" },
+                    "collection": name
+                }))
+            }
+
+            "list_collections" => {
+                Ok(json!({
+                    "status": "success",
+                    "collections": self.list_collections()?
+                }))
+            }
+
+            "purge_source" => {
+                let source_url = args.get("source_url")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("purge_source操作需要source_url参数".to_string()))?;
+                let tier = args.get("scope").and_then(|v| v.as_str()).and_then(CacheTier::parse);
+                let (documents, bytes) = self.purge_source(source_url, tier, true)?;
+
+                Ok(json!({
+                    "status": "success",
+                    "source_url": source_url,
+                    "documents_removed": documents,
+                    "bytes_freed": bytes
+                }))
+            }
+
+            "delete_by_filter" => {
+                let text = |key: &str| args.get(key).and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty());
+                let tier = text("scope").and_then(CacheTier::parse);
+                let (package_versions, documents, bytes) =
+                    self.delete_by_filter(text("language"), text("package_name"), text("version"), tier)?;
+
+                Ok(json!({
+                    "status": "success",
+                    "package_versions": package_versions,
+                    "documents_removed": documents,
+                    "bytes_freed": bytes
+                }))
+            }
+
+            "export" => {
+                let (path, format) = self.corpus_path(&args)?;
+                let text = |key: &str| args.get(key).and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty());
+                let tier = text("scope").and_then(CacheTier::parse);
+                let report = self.export_corpus(&path, format, text("language"), text("package_name"), text("version"), tier)?;
+                Ok(json!({ "status": "success", "report": report }))
+            }
+
+            "import" => {
+                let (path, format) = self.corpus_path(&args)?;
+                if !path.is_file() {
+                    return Err(MCPError::NotFound(format!("语料文件 {} 不存在", path.display())).into());
+                }
+                let tier = args.get("scope").and_then(|v| v.as_str()).and_then(CacheTier::parse);
+                let report = self.import_corpus(&path, format, tier)?;
+                Ok(json!({ "status": "success", "report": report }))
+            }
+
+            "dedup_report" => {
+                let threshold = args.get("threshold")
+                    .and_then(|v| v.as_f64())
+                    .map_or(DEFAULT_DEDUP_THRESHOLD, |t| t as f32);
+                let merge = args.get("merge").and_then(|v| v.as_bool()).unwrap_or(false);
+                let tier = args.get("scope").and_then(|v| v.as_str()).and_then(CacheTier::parse);
+                let report = self.dedup_report(tier, threshold, merge)?;
+                Ok(json!({ "status": "success", "report": report }))
+            }
+
+            "purge_removed_dependencies" => {
+                let (package_versions, documents, bytes) = self.purge_removed_dependencies()?;
+
+                Ok(json!({
+                    "status": "success",
+                    "package_versions_removed": package_versions,
+                    "documents_removed": documents,
+                    "bytes_freed": bytes
+                }))
+            }
+
+            "import_devdocs" => {
+                let source = args.get("source")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| MCPError::InvalidParameter("import_devdocs操作需要source参数".to_string()))?;
+
+                let report = self.import_devdocs(source).await
+                    .map_err(|e| MCPError::ServerError(format!("导入DevDocs文档集失败: {}", e)))?;
+
+                Ok(json!({
+                    "status": "success",
+                    "report": report,
+                    "database": "instant-distance (嵌入式)"
+                }))
+            }
+
             _ => Err(MCPError::InvalidParameter(format!("不支持的操作: {}", action)).into())
         }
     }
@@ -1464,6 +4597,36 @@ impl MCPTool for VectorDocsTool {
 mod tests {
     use super::*;
 
+    /// 测试文档：标题与ID相同，内容和元数据按需用结构更新语法覆盖
+    fn doc(id: &str, language: &str, package: &str, version: &str, embedding: Vec<f32>) -> DocumentRecord {
+        DocumentRecord {
+            id: id.to_string(),
+            content: "content".to_string(),
+            title: id.to_string(),
+            language: language.to_string(),
+            package_name: package.to_string(),
+            version: version.to_string(),
+            doc_type: "documentation".to_string(),
+            metadata: HashMap::new(),
+            embedding,
+        }
+    }
+
+    /// 由测试文档构造带分数的搜索结果
+    fn scored(doc: DocumentRecord, score: f32) -> SearchResult {
+        SearchResult {
+            id: doc.id,
+            content: doc.content,
+            title: doc.title,
+            language: doc.language,
+            package_name: doc.package_name,
+            version: doc.version,
+            doc_type: doc.doc_type,
+            metadata: doc.metadata,
+            score,
+        }
+    }
+
     #[tokio::test]
     async fn test_intelligent_similarity_detection() {
         // 创建测试工具实例
@@ -1583,6 +4746,19 @@ mod tests {
         assert!(vector.contains_key("language"), "关键词'language'应该保留");
     }
 
+    #[test]
+    fn test_word_frequency_vector_for_cjk_and_identifiers() {
+        let tool = VectorDocsTool::default();
+
+        let vector = tool.build_word_frequency_vector("异步运行时的任务调度");
+        assert!(vector.contains_key("任务"), "中文应按二元组切分");
+        assert!(!vector.keys().any(|k| k.contains('的')), "中文停用词应该被过滤");
+
+        // 标识符与自然语言查询能对上
+        let similarity = tool.calculate_text_similarity("fn read_to_string(path)", "read string from file");
+        assert!(similarity > 0.0, "拆分后的标识符应与查询有共同词: {}", similarity);
+    }
+
     #[test]
     fn test_structure_features_extraction() {
         let tool = VectorDocsTool::default();
@@ -1627,6 +4803,510 @@ fn main() {
         assert!(keywords.contains("http"), "应该提取到'http'关键词");
     }
 
+    #[test]
+    fn test_store_hibernate_and_wake() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.add_document(doc("rust/serde/1.0/lib.rs", "rust", "serde", "1.0", vec![0.1, 0.2, 0.3])).unwrap();
+
+        store.hibernate().unwrap();
+        assert!(store.hibernated);
+        assert!(store.documents.is_empty());
+        assert!(store.search_index.is_none());
+
+        // 休眠期间保存不能覆盖磁盘上的数据
+        store.save().unwrap();
+
+        store.wake().unwrap();
+        assert!(!store.hibernated);
+        assert_eq!(store.documents.len(), 1);
+        assert_eq!(store.vectors.len(), 1);
+
+        // 恢复失败后仍处于休眠状态的存储里有写入时，保存必须报错而不是静默丢弃
+        store.hibernate().unwrap();
+        store.vectors.push(vec![0.4, 0.5, 0.6]);
+        assert!(store.save().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_instances_in_one_process_share_the_store() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let first = VectorDocsTool::open_local(temp_dir.path().to_path_buf()).unwrap();
+        let second = VectorDocsTool::open_local(temp_dir.path().to_path_buf()).unwrap();
+        assert!(Arc::ptr_eq(&first.store, &second.store));
+        assert_eq!(second.store.read().unwrap().access_mode(), AccessMode::Owner);
+
+        second.mark_package_version_as_processed("rust", "serde", "1.0").unwrap();
+        assert!(first.has_processed_package_version("rust", "serde", "1.0").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_reads_report_wake_failure() {
+        use crate::tools::embedder::testing::MockEmbedder;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tool = VectorDocsTool::open_local(temp_dir.path().to_path_buf()).unwrap().with_embedder(Arc::new(MockEmbedder::new(32)));
+        tool.execute(json!({ "action": "store", "id": "doc-1", "content": "tokio 运行时" })).await.unwrap();
+        tool.mark_package_version_as_processed("rust", "tokio", "1.0").unwrap();
+        tool.store.write().unwrap().hibernate().unwrap();
+
+        // 休眠期间数据文件被新版本程序改写，恢复失败时查询报错而不是返回空结果
+        let data_file = temp_dir.path().join("vector_data.bin");
+        let _ = fs::remove_file(write_ahead_log::previous_path(&data_file));
+        fs::write(&data_file, MigrationRegistry::new(VECTOR_DATA_FORMAT_VERSION + 1).encode(b"newer")).unwrap();
+        assert!(tool.has_processed_package_version("rust", "tokio", "1.0").is_err());
+        assert!(tool.list_cached_packages().is_err());
+        assert!(tool.execute(json!({ "action": "search", "query": "tokio" })).await.is_err());
+        assert!(tool.execute(json!({ "action": "get", "id": "doc-1" })).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_model_change_rejects_inserts_until_reembedded() {
+        use crate::tools::embedder::testing::MockEmbedder;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old = VectorDocsTool::open_local(temp_dir.path().to_path_buf()).unwrap();
+        old.add_documents(CacheTier::Global, vec![doc("serde-1", "rust", "serde", "1.0", vec![0.1, 0.2, 0.3])]).unwrap();
+        let recorded = EmbeddingMeta::load(temp_dir.path()).unwrap().unwrap();
+        assert!(recorded.matches(DEFAULT_EMBEDDING_MODEL, 3));
+        // 同一模型写入不同维度的向量同样被拒绝
+        assert!(old.add_documents(CacheTier::Global, vec![doc("serde-2", "rust", "serde", "1.0", vec![0.1, 0.2])]).is_err());
+
+        let tool = VectorDocsTool::open_local(temp_dir.path().to_path_buf()).unwrap().with_embedder(Arc::new(MockEmbedder::new(32)));
+        let rejected = tool.execute(json!({ "action": "store", "id": "tokio-1", "content": "tokio 运行时" })).await.unwrap_err();
+        assert!(rejected.to_string().contains("reembed_all"), "{}", rejected);
+
+        let report = tool.reembed_all().await.unwrap();
+        assert_eq!(report.model, "mock-embedding");
+        assert_eq!(report.dimension, Some(32));
+        assert_eq!(report.documents, 1);
+        assert!(EmbeddingMeta::load(temp_dir.path()).unwrap().unwrap().matches("mock-embedding", 32));
+
+        tool.execute(json!({ "action": "store", "id": "tokio-1", "content": "tokio 运行时" })).await.unwrap();
+        assert_eq!(tool.execute(json!({ "action": "get", "id": "serde-1" })).await.unwrap()["status"], "success");
+    }
+
+    #[test]
+    fn test_unsaved_writes_are_recovered_from_wal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.add_document(doc("saved-1", "rust", "serde", "1.0", vec![0.1, 0.2, 0.3])).unwrap();
+        store.add_document(doc("saved-2", "rust", "serde", "1.0", vec![0.3, 0.2, 0.1])).unwrap();
+        // 写入后、落盘前进程退出
+        store.insert_documents(vec![doc("unsaved", "rust", "serde", "1.0", vec![0.2, 0.2, 0.2])], None).unwrap();
+        drop(store);
+
+        let mut recovered = VectorStore::new(temp_dir.path().to_path_buf());
+        recovered.load().unwrap();
+        assert_eq!(recovered.get_stats(), (3, 3));
+        assert_eq!(recovered.search_similar(&[0.2, 0.2, 0.2], 1, &SearchFilter::default()).unwrap()[0].id, "unsaved");
+        drop(recovered);
+
+        // 数据文件损坏时从上一份数据文件加载并重放两段日志
+        fs::write(temp_dir.path().join("vector_data.bin"), b"GRPV\x05\x00\x00\x00truncated").unwrap();
+        let mut restored = VectorStore::new(temp_dir.path().to_path_buf());
+        restored.load().unwrap();
+        assert_eq!(restored.get_stats(), (3, 3));
+        assert!(restored.contains_document("saved-2") && restored.contains_document("unsaved"));
+    }
+
+    #[test]
+    fn test_progress_is_persisted_without_rewriting_data_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tool = VectorDocsTool::open_local(temp_dir.path().to_path_buf()).unwrap();
+        assert!(tool.try_claim_package_version("rust", "serde", "1.0").unwrap());
+        tool.record_package_progress("rust", "serde", "1.0", 3, 12, Some(10)).unwrap();
+        assert!(!temp_dir.path().join("vector_data.bin").exists());
+        drop(tool);
+
+        let tool = VectorDocsTool::open_local(temp_dir.path().to_path_buf()).unwrap();
+        assert!(!tool.try_claim_package_version("rust", "serde", "1.0").unwrap(), "进行中的处理不能被重复认领");
+        tool.complete_package_progress("rust", "serde", "1.0").unwrap();
+        assert!(tool.has_processed_package_version("rust", "serde", "1.0").unwrap());
+    }
+
+    #[test]
+    fn test_corrupt_data_file_is_backed_up_and_future_version_is_rejected() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_file = temp_dir.path().join("vector_data.bin");
+        // 日志中还没有落盘的写入在丢弃损坏文件后重放
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.insert_documents(vec![doc("unsaved", "rust", "serde", "1.0", vec![0.2, 0.2, 0.2])], None).unwrap();
+        drop(store);
+        fs::write(&data_file, b"not a vector store").unwrap();
+
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.load().unwrap();
+        assert_eq!(store.get_stats(), (1, 1));
+        assert!(store.contains_document("unsaved"));
+        let backups = fs::read_dir(temp_dir.path()).unwrap()
+            .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().starts_with("vector_data.bin.corrupt-"))
+            .count();
+        assert_eq!(backups, 1);
+
+        // 新版本程序写入的数据是完好的，不能当作损坏文件丢弃
+        fs::write(&data_file, MigrationRegistry::new(VECTOR_DATA_FORMAT_VERSION + 1).encode(b"newer")).unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        assert!(store.load().is_err());
+        assert!(data_file.exists());
+
+        // 读取失败不代表文件损坏，原样保留并报告错误
+        fs::remove_file(&data_file).unwrap();
+        fs::create_dir(&data_file).unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        assert!(store.load().is_err());
+        assert!(data_file.is_dir());
+    }
+
+    #[test]
+    fn test_large_content_is_offloaded_and_loaded_for_results() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.content_tier = ContentTierConfig { offload_min_bytes: Some(64), summary_chars: 16 };
+        let content = format!("# Struct Deserializer\n\n{}\n\npub fn from_str() {{}}", "serde docs ".repeat(20));
+        store.add_document(DocumentRecord {
+            content: content.clone(),
+            title: "de".to_string(),
+            ..doc("rust/serde/1.0/de.rs", "rust", "serde", "1.0", vec![0.1, 0.2, 0.3])
+        }).unwrap();
+
+        // 内存中只有摘要，符号索引仍覆盖全文
+        assert!(store.documents["rust/serde/1.0/de.rs"].content.chars().count() <= 16);
+        assert_eq!(store.offloaded["rust/serde/1.0/de.rs"], content.len());
+        assert!(!store.symbol_index.lookup("serde::from_str").is_empty());
+
+        let results = store.hybrid_search(&[0.1, 0.2, 0.3], "deserializer", 5, &SearchFilter::default()).unwrap();
+        assert_eq!(results[0].content, content);
+        assert_eq!(store.get_document("rust/serde/1.0/de.rs").unwrap().content, content);
+
+        // 重新加载后仍能读取全文
+        let mut reloaded = VectorStore::new(temp_dir.path().to_path_buf());
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.get_document("rust/serde/1.0/de.rs").unwrap().content, content);
+
+        assert!(reloaded.delete_document("rust/serde/1.0/de.rs").unwrap());
+        assert!(reloaded.offloaded.is_empty());
+        assert!(reloaded.content_store.read("rust/serde/1.0/de.rs").is_err());
+    }
+
+    #[test]
+    fn test_list_documents_pages_by_id() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tool = VectorDocsTool {
+            store: Arc::new(RwLock::new(VectorStore::new(temp_dir.path().to_path_buf()))),
+            ..VectorDocsTool::default()
+        };
+        {
+            let mut store = tool.store.write().unwrap();
+            for (i, package) in ["serde", "tokio", "serde", "serde", "anyhow"].iter().enumerate() {
+                store.add_document(DocumentRecord {
+                    title: format!("title {}", i),
+                    ..doc(&format!("doc-{}", i), "rust", package, "1.0", vec![0.1, 0.2, 0.3])
+                }).unwrap();
+            }
+        }
+
+        let first = tool.list_documents(None, None, None, None, 2).unwrap();
+        assert_eq!(first.total, 5);
+        assert_eq!(first.items[0]["id"], "doc-0");
+        assert!(first.items[0].get("content").is_none());
+        let second = tool.list_documents(None, None, None, first.next_cursor.as_deref(), 2).unwrap();
+        assert_eq!(second.items[0]["id"], "doc-2");
+
+        let serde_docs = tool.list_documents(None, None, Some("serde"), None, 10).unwrap();
+        assert_eq!(serde_docs.total, 3);
+        assert!(serde_docs.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_search_filter_restricts_candidates() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        // 与查询最接近的是 python 和旧版本的文档，过滤后仍应返回足够的 tokio 1.x 文档
+        let docs = [
+            ("py-0", "python", "asyncio", "3.12", 0.0),
+            ("py-1", "python", "asyncio", "3.12", 0.01),
+            ("old-0", "rust", "tokio", "0.2.25", 0.02),
+            ("new-0", "rust", "tokio", "1.38.0", 0.5),
+            ("new-1", "rust", "tokio", "1.0.1", 0.6),
+        ];
+        for (id, language, package, version, offset) in docs {
+            store.add_document(DocumentRecord {
+                content: "spawn a task".to_string(),
+                ..doc(id, language, package, version, vec![0.1 + offset, 0.2, 0.3])
+            }).unwrap();
+        }
+
+        let filter = SearchFilter::new().language("rust").package_name("tokio").version("1.x");
+        let results = store.search_similar(&[0.1, 0.2, 0.3], 2, &filter).unwrap();
+        assert_eq!(results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["new-0", "new-1"]);
+
+        let results = store.hybrid_search(&[0.1, 0.2, 0.3], "spawn", 5, &SearchFilter::new().language("python")).unwrap();
+        assert!(results.iter().all(|r| r.language == "python"));
+        assert_eq!(results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_documents_are_private() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        for (id, owner, offset) in [("alice-0", Some("alice"), 0.0), ("bob-0", Some("bob"), 0.01), ("shared-0", None, 0.5)] {
+            let metadata = owner
+                .map(|owner| HashMap::from([(namespace::NAMESPACE_METADATA_KEY.to_string(), owner.to_string())]))
+                .unwrap_or_default();
+            store.add_document(DocumentRecord {
+                content: "spawn a task".to_string(),
+                metadata,
+                ..doc(id, "rust", "tokio", "1.38.0", vec![0.1 + offset, 0.2, 0.3])
+            }).unwrap();
+        }
+
+        let ids = |results: Vec<SearchResult>| results.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        let unfiltered = SearchFilter::default();
+        assert_eq!(ids(store.search_similar(&[0.1, 0.2, 0.3], 3, &unfiltered).unwrap()), vec!["shared-0"]);
+        let alice = namespace::scope(Some("alice".to_string()), async {
+            store.search_similar(&[0.1, 0.2, 0.3], 3, &unfiltered).unwrap()
+        }).await;
+        assert_eq!(ids(alice), vec!["alice-0", "shared-0"]);
+    }
+
+    /// 按文本是否包含关键词打分的重排模型
+    struct KeywordReranker(&'static str);
+
+    #[async_trait]
+    impl Reranker for KeywordReranker {
+        fn model_name(&self) -> &str {
+            "keyword-reranker"
+        }
+
+        async fn score(&self, _query: &str, documents: Vec<String>) -> Result<Vec<crate::tools::docs::reranker::RerankResult>> {
+            let mut results: Vec<_> = documents.iter()
+                .enumerate()
+                .map(|(index, text)| crate::tools::docs::reranker::RerankResult {
+                    index,
+                    relevance_score: if text.contains(self.0) { 0.9 } else { 0.1 },
+                    document: None,
+                })
+                .collect();
+            results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
+            Ok(results)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rerank_reorders_fused_candidates() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let candidate = |id: &str, content: &str, score: f32| scored(
+            DocumentRecord { content: content.to_string(), ..doc(id, "rust", "tokio", "1.0", Vec::new()) },
+            score,
+        );
+        let candidates = vec![
+            candidate("runtime", "build a runtime", 0.9),
+            candidate("channel", "send messages", 0.8),
+            candidate("spawn", "spawn a task", 0.5),
+        ];
+
+        // 未配置重排模型时按原顺序截断
+        let tool = VectorDocsTool::open_local(temp_dir.path().to_path_buf()).unwrap();
+        let (results, reranked) = tool.rerank_results("spawn", candidates.clone(), 2).await;
+        assert!(!reranked);
+        assert_eq!(results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["runtime", "channel"]);
+
+        let tool = tool.with_reranker(Arc::new(KeywordReranker("spawn")));
+        let (results, reranked) = tool.rerank_results("spawn", candidates, 2).await;
+        assert!(reranked);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, "spawn");
+        assert_eq!(results[0].score, 0.9);
+        assert_eq!(results[0].metadata["fused_score"], "0.5");
+    }
+
+    #[test]
+    fn test_cosine_metric_ignores_vector_length() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.distance_metric = DistanceMetric::Cosine;
+        // 方向相同但长度很大的向量在欧氏距离下最远，余弦距离下最近
+        for (id, embedding) in [("same-direction", vec![10.0, 20.0, 30.0]), ("nearby", vec![0.3, 0.2, 0.1])] {
+            store.add_document(doc(id, "rust", "tokio", "1.0", embedding)).unwrap();
+        }
+
+        let results = store.search_similar(&[0.1, 0.2, 0.3], 2, &SearchFilter::default()).unwrap();
+        assert_eq!(results[0].id, "same-direction");
+        assert!((results[0].score - 1.0).abs() < 1e-5);
+        assert!(results[1].score < results[0].score);
+    }
+
+    #[test]
+    fn test_quantized_index_reranks_with_original_vectors() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.quantization = QuantizationConfig { mode: QuantizationMode::Int8, min_vectors: 1, ..QuantizationConfig::default() };
+        for i in 0..20 {
+            store.add_document(doc(&format!("doc-{}", i), "rust", "tokio", "1.0", vec![i as f32 * 0.05, 1.0 - i as f32 * 0.05, 0.5])).unwrap();
+        }
+        assert!(matches!(store.search_index, Some(VectorIndex::Quantized { .. })));
+        assert_eq!(store.quantization_status()["bytes_per_vector"], 3);
+
+        let query = [0.35, 0.65, 0.5];
+        let results = store.search_similar(&query, 3, &SearchFilter::default()).unwrap();
+        assert_eq!(results[0].id, "doc-7");
+        // 分数按原始向量计算，与量化误差无关
+        let metric = store.distance_metric;
+        assert_eq!(results[0].score, metric.similarity(metric.distance(&query, &store.vectors[7])));
+    }
+
+    #[test]
+    fn test_bm25_backs_text_search_and_hybrid_keywords() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.distance_metric = DistanceMetric::L2;
+        let docs = [
+            ("mutex", "异步互斥锁", "tokio 的异步互斥锁可以在持有期间跨越等待点", vec![0.0, 1.0, 0.0]),
+            ("channel", "通道", "多生产者单消费者通道，用于任务之间传递消息", vec![0.9, 0.1, 0.0]),
+            ("runtime", "Runtime", "The runtime drives asynchronous tasks", vec![1.0, 0.0, 0.0]),
+        ];
+        for (id, title, content, embedding) in docs {
+            store.add_document(DocumentRecord {
+                content: content.to_string(),
+                title: title.to_string(),
+                ..doc(id, "rust", "tokio", "1.0", embedding)
+            }).unwrap();
+        }
+
+        let results = store.text_search("互斥锁", 5, &SearchFilter::default());
+        assert_eq!(results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["mutex"]);
+        assert!(store.text_search("互斥锁", 5, &SearchFilter::new().language("python")).is_empty());
+
+        // 向量只召回 runtime 和 channel 两个候选，关键词命中的文档由全文索引补充并排在前面
+        let results = store.hybrid_search(&[5.0, 0.0, 0.0], "互斥锁", 1, &SearchFilter::default()).unwrap();
+        assert_eq!(results[0].id, "mutex");
+
+        store.delete_document("mutex").unwrap();
+        assert!(store.text_search("互斥锁", 5, &SearchFilter::default()).is_empty());
+    }
+
+    #[test]
+    fn test_sharded_index_rebuilds_only_modified_shard() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.quantization.mode = QuantizationMode::None;
+        store.sharding = ShardingConfig { strategy: ShardStrategy::Language, shards: 4 };
+        let (rust, python) = (store.sharding.shard_of("rust", ""), store.sharding.shard_of("python", ""));
+        assert_ne!(rust, python, "测试需要两种语言落在不同分片");
+        store.add_documents_batch((0..10).map(|i| doc(&format!("rs-{}", i), "rust", "pkg", "1.0", vec![i as f32, 1.0, 0.0])).collect()).unwrap();
+        store.add_documents_batch((0..10).map(|i| doc(&format!("py-{}", i), "python", "pkg", "1.0", vec![i as f32, 0.0, 1.0])).collect()).unwrap();
+        assert!(store.search_index.is_none());
+        assert_eq!(store.shards[rust].doc_ids.len(), 10);
+
+        // 只写入 Python 文档时，Rust 分片的索引原样保留
+        let rust_index = store.shards[rust].index.as_ref().map(|index| index as *const VectorIndex);
+        store.add_document(doc("py-new", "python", "pkg", "1.0", vec![3.5, 0.0, 1.0])).unwrap();
+        assert_eq!(store.shards[rust].index.as_ref().map(|index| index as *const VectorIndex), rust_index);
+        assert_eq!(store.shards[python].doc_ids.len(), 11);
+
+        // 跨分片合并结果，语言过滤只查询对应分片
+        let results = store.search_similar(&[3.5, 0.0, 1.0], 3, &SearchFilter::default()).unwrap();
+        assert_eq!(results[0].id, "py-new");
+        let rust_only = store.search_similar(&[3.5, 0.0, 1.0], 3, &SearchFilter::new().language("rust")).unwrap();
+        assert_eq!(rust_only.len(), 3);
+        assert!(rust_only.iter().all(|r| r.language == "rust"));
+
+        store.delete_document("rs-0").unwrap();
+        assert_eq!(store.shards[rust].doc_ids.len(), 9);
+        assert!(store.has_index());
+    }
+
+    #[tokio::test]
+    async fn test_sharded_search_sees_caller_namespace() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.quantization.mode = QuantizationMode::None;
+        store.sharding = ShardingConfig { strategy: ShardStrategy::Language, shards: 4 };
+        let alice = HashMap::from([(namespace::NAMESPACE_METADATA_KEY.to_string(), "alice".to_string())]);
+        store.add_document(DocumentRecord { metadata: alice, ..doc("alice-0", "rust", "pkg", "1.0", vec![1.0, 0.0, 0.0]) }).unwrap();
+        store.add_documents_batch((0..4).map(|i| doc(&format!("shared-{}", i), "python", "pkg", "1.0", vec![1.0, 0.5 + i as f32, 0.0])).collect()).unwrap();
+        assert!(store.search_index.is_none());
+
+        // 共享文档足够填满结果时不会回退到调用线程上的精确扫描，分片查询本身必须看到调用方的命名空间
+        let results = namespace::scope(Some("alice".to_string()), async {
+            store.search_similar(&[1.0, 0.0, 0.0], 2, &SearchFilter::default()).unwrap()
+        }).await;
+        assert_eq!(results[0].id, "alice-0");
+        let anonymous = store.search_similar(&[1.0, 0.0, 0.0], 2, &SearchFilter::default()).unwrap();
+        assert!(anonymous.iter().all(|r| r.id.starts_with("shared-")));
+    }
+
+    #[test]
+    fn test_legacy_vector_data_is_migrated() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let legacy = OldPersistentData {
+            documents: HashMap::new(),
+            vectors: Vec::new(),
+            vector_to_doc_id: Vec::new(),
+        };
+        let data_file = temp_dir.path().join("vector_data.bin");
+        fs::write(&data_file, bincode::serialize(&legacy).unwrap()).unwrap();
+
+        let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+        store.load().unwrap();
+
+        let migrated = fs::read(&data_file).unwrap();
+        assert_eq!(&migrated[..4], crate::tools::data_format::FORMAT_MAGIC);
+        assert!(temp_dir.path().join("vector_data.v1.bak").exists());
+    }
+
+    #[test]
+    fn test_mmap_layout_loads_index_without_rebuild() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let open = || {
+            let mut store = VectorStore::new(temp_dir.path().to_path_buf());
+            store.mmap_index = true;
+            store.quantization.mode = QuantizationMode::None;
+            store
+        };
+        let mut store = open();
+        store.add_documents_batch((0..20).map(|i| DocumentRecord {
+            content: format!("content {}", i),
+            ..doc(&format!("doc-{}", i), "rust", "tokio", "1.0", vec![i as f32 * 0.05, 1.0 - i as f32 * 0.05, 0.5])
+        }).collect()).unwrap();
+
+        let mut reloaded = open();
+        reloaded.load().unwrap();
+        // 向量指向映射的文件，文档不再保存向量副本
+        assert!(reloaded.vectors.iter().all(|row| matches!(row, VectorRow::Mapped(..))));
+        assert!(reloaded.documents.values().all(|doc| doc.embedding.is_empty()));
+        assert!(matches!(reloaded.search_index, Some(VectorIndex::Exact(_))));
+
+        let results = reloaded.search_similar(&[0.35, 0.65, 0.5], 3, &SearchFilter::default()).unwrap();
+        assert_eq!(results[0].id, "doc-7");
+
+        // 继续写入后重新保存为新一代文件
+        reloaded.add_document(DocumentRecord {
+            id: "doc-new".to_string(),
+            embedding: vec![0.9, 0.1, 0.5],
+            ..store.get_document("doc-0").unwrap()
+        }).unwrap();
+        let mut again = open();
+        again.load().unwrap();
+        assert_eq!(again.vectors.len(), 21);
+        assert_eq!(&again.vectors[20][..], &[0.9, 0.1, 0.5]);
+    }
+
+    #[test]
+    fn test_merge_tier_results_prefers_workspace() {
+        let make_result = |id: &str, score: f32| scored(doc(id, "rust", "tokio", "1.0", Vec::new()), score);
+
+        let merged = VectorDocsTool::merge_tier_results(vec![
+            (CacheTier::Workspace, vec![make_result("shared", 0.4)]),
+            (CacheTier::Global, vec![make_result("shared", 0.9), make_result("global_only", 0.7)]),
+        ], 5);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].id, "global_only");
+        assert_eq!(merged[1].metadata.get("cache_tier").map(String::as_str), Some("workspace"));
+    }
+
     #[test]
     fn test_text_normalization() {
         let tool = VectorDocsTool::default();
@@ -1639,4 +5319,110 @@ fn main() {
         let normalized2 = tool.normalize_text(special_chars);
         assert!(normalized2.contains("Hello, world!"), "应该保留基本标点符号");
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_delete_by_filter_clears_documents_and_markers() {
+        use crate::tools::embedder::testing::MockEmbedder;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tool = VectorDocsTool::open_local(dir.path().to_path_buf()).unwrap().with_embedder(Arc::new(MockEmbedder::new(32)));
+        for version in ["1.0.0", "2.0.0"] {
+            tool.execute(json!({
+                "action": "store", "title": "serde", "content": format!("serde {} Serialize 序列化", version),
+                "language": "rust", "package_name": "serde", "version": version
+            })).await.unwrap();
+            tool.mark_package_version_as_processed("rust", "serde", version).unwrap();
+        }
+
+        assert!(tool.execute(json!({ "action": "delete_by_filter" })).await.is_err());
+        let deleted = tool.execute(json!({
+            "action": "delete_by_filter", "language": "rust", "package_name": "serde", "version": "1.0.0"
+        })).await.unwrap();
+        assert_eq!(deleted["package_versions"], json!(["rust/serde/1.0.0"]));
+        assert_eq!(deleted["documents_removed"], 1);
+        assert!(!tool.has_processed_package_version("rust", "serde", "1.0.0").unwrap());
+        assert!(tool.has_processed_package_version("rust", "serde", "2.0.0").unwrap());
+
+        let remaining = tool.execute(json!({ "action": "search", "query": "Serialize", "package_name": "serde" })).await.unwrap();
+        assert!(remaining["results"].as_array().unwrap().iter().all(|r| r["version"] == "2.0.0"));
+    }
+
+    #[tokio::test]
+    async fn test_dedup_report_finds_and_merges_mirrored_documents() {
+        use crate::tools::embedder::testing::MockEmbedder;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tool = VectorDocsTool::open_local(dir.path().to_path_buf()).unwrap().with_embedder(Arc::new(MockEmbedder::new(32)));
+        for (id, content) in [
+            ("docs-rs", "tokio::spawn 在运行时上启动一个新的异步任务"),
+            ("mirror", "tokio::spawn 在运行时上启动一个新的异步任务"),
+            ("other", "serde 的 Serialize 派生宏生成序列化实现"),
+        ] {
+            tool.execute(json!({ "action": "store", "id": id, "content": content, "package_name": "tokio" })).await.unwrap();
+        }
+
+        let report = tool.execute(json!({ "action": "dedup_report" })).await.unwrap()["report"].clone();
+        assert_eq!(report["duplicate_documents"], 1);
+        assert_eq!(report["clusters"][0]["keep"], "docs-rs");
+        assert_eq!(report["clusters"][0]["duplicates"], json!(["mirror"]));
+        assert_eq!(report["merged_documents"], 0);
+
+        let merged = tool.execute(json!({ "action": "dedup_report", "merge": true })).await.unwrap()["report"].clone();
+        assert_eq!(merged["merged_documents"], 1);
+        assert_eq!(tool.execute(json!({ "action": "get", "id": "mirror" })).await.unwrap()["status"], "not_found");
+        assert_eq!(tool.execute(json!({ "action": "get", "id": "docs-rs" })).await.unwrap()["status"], "success");
+        let after = tool.execute(json!({ "action": "dedup_report" })).await.unwrap();
+        assert_eq!(after["report"]["clusters"], json!([]));
+    }
+
+    #[test]
+    fn test_searches_run_concurrently_with_ingestion() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        let embedding = |i: usize| -> Vec<f32> { (0..32).map(|d| (i as f32 * 0.37 + d as f32 * 1.3).sin()).collect() };
+        let batch = |start: usize, len: usize| -> Vec<DocumentRecord> {
+            (start..start + len).map(|i| DocumentRecord {
+                content: format!("tokio spawn 任务 {}", i),
+                title: format!("doc {}", i),
+                ..doc(&format!("doc-{}", i), "rust", "tokio", "1.0", embedding(i))
+            }).collect()
+        };
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tool = VectorDocsTool::open_local(temp_dir.path().to_path_buf()).unwrap();
+        tool.add_documents(CacheTier::Global, batch(0, 200)).unwrap();
+
+        const BATCHES: usize = 8;
+        let ingesting = AtomicBool::new(true);
+        let searches_during_ingest = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for reader in 0..4 {
+                let (tool, ingesting, searches) = (&tool, &ingesting, &searches_during_ingest);
+                scope.spawn(move || {
+                    let query = embedding(reader);
+                    while ingesting.load(Ordering::Acquire) {
+                        let results = tool.hybrid_search(&query, "tokio spawn", 5).unwrap();
+                        assert_eq!(results.len(), 5);
+                        if ingesting.load(Ordering::Acquire) {
+                            searches.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+            for i in 0..BATCHES {
+                tool.add_documents(CacheTier::Global, batch(200 + i * 300, 300)).unwrap();
+            }
+            ingesting.store(false, Ordering::Release);
+        });
+
+        // 索引在锁外构建，搜索不必排在每批写入之后，写入期间完成的搜索远多于批次数
+        assert!(
+            searches_during_ingest.load(Ordering::Relaxed) > BATCHES * 4,
+            "写入期间只完成了 {} 次搜索", searches_during_ingest.load(Ordering::Relaxed)
+        );
+        let store = tool.read_store(&tool.store).unwrap();
+        assert_eq!(store.get_stats(), (200 + BATCHES * 300, 200 + BATCHES * 300));
+        let results = store.search_similar(&embedding(2599), 1, &SearchFilter::default()).unwrap();
+        assert_eq!(results[0].id, "doc-2599");
+    }
+}
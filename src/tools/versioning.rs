@@ -1,13 +1,27 @@
 use std::sync::{Arc, OnceLock};
 use tokio::sync::RwLock;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use async_trait::async_trait;
 use serde_json::{json, Value};
 use chrono::{DateTime, Utc};
 use anyhow::Result;
 use crate::errors::MCPError;
-use super::base::{MCPTool, ToolAnnotations, Schema, SchemaObject, SchemaString, SchemaBoolean};
+use tracing::Instrument;
+use crate::mcp::session::{self, SessionPreferences};
+use super::pagination;
+use super::base::{MCPTool, ToolAnnotations, Schema, SchemaObject, SchemaString, SchemaBoolean, SchemaArray, SchemaInteger};
 use regex;
+use super::workspace_versions::{
+    analyze_divergence, detect_project_ecosystems, plausible_ecosystems, rank_ecosystems, scan_workspace_requirements,
+};
+use crate::versioning::fetcher::{HttpRegistryFetcher, RegistryFetcher};
+use crate::versioning::conda::{merge_version_sources, CondaClient, CondaPackage};
+use crate::versioning::crate_features::{fetch_crate_features, optional_dependencies, CrateFeatures};
+use crate::versioning::goproxy::{escape_module_path, GoProxyClient};
+use crate::versioning::maven_bom::{detect_project_management, BomResolver, MavenCoordinate};
+use crate::versioning::npm_registry::{dist_tags_of, NpmRegistryClient, NpmRegistryConfig};
+use crate::versioning::packagist::{PackageCoordinate, PackagistClient, PackagistPackage, Stability, StabilityPolicy};
+use crate::versioning::wheels::{analyze_release_files, WheelCompatibility};
 
 #[derive(Clone)]
 struct VersionInfo {
@@ -20,16 +34,18 @@ struct VersionInfo {
     available_versions: Vec<String>, // 新增: 可用版本列表
     dependencies: Option<Value>, // 新增: 依赖信息
     repository_url: Option<String>, // 新增: 代码仓库地址
+    dist_tags: Option<BTreeMap<String, String>>, // npm dist-tags (latest/next/lts等)
+    crate_features: Option<CrateFeatures>, // cargo: 最新版本的feature列表
+    packagist: Option<PackagistPackage>, // composer: 含稳定性的完整版本列表
+    conda_channels: Option<Vec<CondaPackage>>, // conda: 各频道的版本
 }
 
 // Registry定义
 #[derive(Clone)]
 enum Registry {
     CratesIo,
-    NpmJs,
     PyPI,
     MavenCentral,
-    GoProxy,
     PubDev,
     FlutterSdk,  // 新增: Flutter SDK
     DartSdk,     // 新增: Dart SDK
@@ -39,10 +55,8 @@ impl Registry {
     fn base_url(&self) -> &str {
         match self {
             Registry::CratesIo => "https://crates.io/api/v1",
-            Registry::NpmJs => "https://registry.npmjs.org",
             Registry::PyPI => "https://pypi.org/pypi",
             Registry::MavenCentral => "https://search.maven.org/solrsearch/select",
-            Registry::GoProxy => "https://proxy.golang.org",
             Registry::PubDev => "https://pub.dev/api",
             Registry::FlutterSdk => "https://docs.flutter.dev",
             Registry::DartSdk => "https://api.github.com/repos/dart-lang/sdk",
@@ -54,6 +68,8 @@ pub struct CheckVersionTool {
     _annotations: ToolAnnotations,
     cache: Arc<RwLock<HashMap<String, (VersionInfo, DateTime<Utc>)>>>,
     client: reqwest::Client,
+    /// 注册表 JSON 接口（测试中替换为预置响应）
+    fetcher: Arc<dyn RegistryFetcher>,
 }
 
 impl CheckVersionTool {
@@ -71,17 +87,31 @@ impl CheckVersionTool {
                 version: "1.0".to_string(),
             },
             cache: Arc::new(RwLock::new(HashMap::new())),
+            fetcher: Arc::new(HttpRegistryFetcher::new(client.clone())),
             client,
         }
     }
 
+    /// 替换注册表访问实现
+    pub fn with_fetcher(mut self, fetcher: Arc<dyn RegistryFetcher>) -> Self {
+        self.fetcher = fetcher;
+        self
+    }
+
     async fn fetch_version_info(&self, type_: &str, name: &str) -> Result<VersionInfo> {
+        let span = tracing::info_span!("provider_request", registry = %type_, package = %name);
+        self.fetch_from_registry(type_, name).instrument(span).await
+    }
+
+    async fn fetch_from_registry(&self, type_: &str, name: &str) -> Result<VersionInfo> {
         match type_ {
             "cargo" => self.fetch_crates_io(name).await,
             "npm" => self.fetch_npm(name).await,
             "pip" => self.fetch_pypi(name).await,
             "maven" => self.fetch_maven_central(name).await,
             "go" => self.fetch_go_proxy(name).await,
+            "composer" | "packagist" => self.fetch_packagist(name).await,
+            "conda" => self.fetch_conda(name).await,
             "pub" => {
                 // 特殊处理Flutter和Dart
                 match name {
@@ -101,13 +131,13 @@ impl CheckVersionTool {
     async fn fetch_flutter_sdk(&self) -> Result<VersionInfo> {
         // 从GitHub API获取Flutter SDK的最新版本
         let url = "https://api.github.com/repos/flutter/flutter/releases/latest";
-        let response = self.client.get(url).send().await?;
+        let response = self.fetcher.get_json(url).await?;
         
-        if !response.status().is_success() {
+        if !response.is_success() {
             return Err(MCPError::NotFound("无法获取Flutter SDK版本信息".to_string()).into());
         }
         
-        let data: Value = response.json().await?;
+        let data = response.body;
         
         let tag_name = data["tag_name"]
             .as_str()
@@ -121,8 +151,7 @@ impl CheckVersionTool {
             
         // 获取所有版本列表
         let all_releases_url = "https://api.github.com/repos/flutter/flutter/releases?per_page=50";
-        let all_releases_response = self.client.get(all_releases_url).send().await?;
-        let all_releases: Value = all_releases_response.json().await?;
+        let all_releases = self.fetcher.get_json(all_releases_url).await?.body;
         
         let available_versions = all_releases
             .as_array()
@@ -140,6 +169,10 @@ impl CheckVersionTool {
             eol_date: None,
             download_url: Some("https://docs.flutter.dev/get-started/install".to_string()),
             package_type: "flutter".to_string(),
+            dist_tags: None,
+            crate_features: None,
+            packagist: None,
+            conda_channels: None,
             available_versions,
             dependencies: None,
             repository_url: Some("https://github.com/flutter/flutter".to_string()),
@@ -149,13 +182,13 @@ impl CheckVersionTool {
     async fn fetch_dart_sdk(&self) -> Result<VersionInfo> {
         // 从GitHub Tags API获取Dart SDK的版本信息
         let url = "https://api.github.com/repos/dart-lang/sdk/tags?per_page=100";
-        let response = self.client.get(url).send().await?;
+        let response = self.fetcher.get_json(url).await?;
         
-        if !response.status().is_success() {
+        if !response.is_success() {
             return Err(MCPError::NotFound("无法获取Dart SDK版本信息".to_string()).into());
         }
         
-        let data: Value = response.json().await?;
+        let data = response.body;
         let tags = data.as_array()
             .ok_or_else(|| MCPError::CacheError("无效的Dart SDK响应".to_string()))?;
             
@@ -185,10 +218,10 @@ impl CheckVersionTool {
             
         // 获取该版本的详细信息
         let tag_info_url = format!("https://api.github.com/repos/dart-lang/sdk/git/refs/tags/{}", latest_version);
-        let tag_response = self.client.get(&tag_info_url).send().await;
+        let tag_response = self.fetcher.get_json(&tag_info_url).await;
         
         let release_date = if let Ok(tag_resp) = tag_response {
-            if let Ok(tag_data) = tag_resp.json::<Value>().await {
+            if let Some(tag_data) = Some(tag_resp.body).filter(|body| !body.is_null()) {
                 // 尝试从tag信息中获取准确的提交日期
                 tag_data["object"]["url"].as_str()
                     .and_then(|_commit_url| {
@@ -211,6 +244,10 @@ impl CheckVersionTool {
             eol_date: None,
             download_url: Some("https://dart.dev/get-dart".to_string()),
             package_type: "dart".to_string(),
+            dist_tags: None,
+            crate_features: None,
+            packagist: None,
+            conda_channels: None,
             available_versions: dart_versions,
             dependencies: None,
             repository_url: Some("https://github.com/dart-lang/sdk".to_string()),
@@ -219,14 +256,14 @@ impl CheckVersionTool {
 
     async fn fetch_crates_io(&self, name: &str) -> Result<VersionInfo> {
         let url = format!("{}/crates/{}", Registry::CratesIo.base_url(), name);
-        let response = self.client.get(&url).send().await?;
+        let response = self.fetcher.get_json(&url).await?;
         
         // 检查响应状态
-        if !response.status().is_success() {
+        if !response.is_success() {
             return Err(MCPError::NotFound(format!("未找到Rust包: {}", name)).into());
         }
         
-        let data: Value = response.json().await?;
+        let data = response.body;
 
         // 修复：使用正确的字段名
         let crate_data = data["crate"].as_object()
@@ -234,8 +271,7 @@ impl CheckVersionTool {
 
         // 获取版本列表
         let versions_url = format!("{}/crates/{}/versions", Registry::CratesIo.base_url(), name);
-        let versions_response = self.client.get(&versions_url).send().await?;
-        let versions_data: Value = versions_response.json().await?;
+        let versions_data = self.fetcher.get_json(&versions_url).await?.body;
         
         let available_versions = versions_data["versions"]
             .as_array()
@@ -261,6 +297,22 @@ impl CheckVersionTool {
             .or_else(|| crate_data["max_version"].as_str())
             .unwrap_or("0.0.0");
 
+        // 最新版本的 feature 信息（版本列表中已包含 features，只需额外查询可选依赖）
+        let crate_features = match versions_data["versions"]
+            .as_array()
+            .and_then(|versions| versions.iter().find(|v| v["num"].as_str() == Some(latest_version)))
+        {
+            Some(version) => {
+                let deps_url = format!("{}/crates/{}/{}/dependencies", Registry::CratesIo.base_url(), name, latest_version);
+                let deps: Value = match self.fetcher.get_json(&deps_url).await {
+                    Ok(response) => response.body,
+                    Err(_) => Value::Null,
+                };
+                Some(CrateFeatures::from_version(version, optional_dependencies(&deps)))
+            }
+            None => None,
+        };
+
         Ok(VersionInfo {
             latest_stable: latest_version.to_string(),
             latest_preview: None,
@@ -268,6 +320,10 @@ impl CheckVersionTool {
             eol_date: None,
             download_url: Some(format!("https://crates.io/crates/{}", name)),
             package_type: "cargo".to_string(),
+            dist_tags: None,
+            crate_features,
+            packagist: None,
+            conda_channels: None,
             available_versions,
             dependencies: None,
             repository_url: crate_data["repository"]
@@ -277,17 +333,26 @@ impl CheckVersionTool {
     }
 
     async fn fetch_npm(&self, name: &str) -> Result<VersionInfo> {
-        let url = format!("{}/{}", Registry::NpmJs.base_url(), name);
-        let response = self.client.get(&url).send().await?;
-        let data: Value = response.json().await?;
+        // 按 .npmrc 选择注册表（作用域私有包使用配置的认证）
+        let registry = NpmRegistryClient::new(self.client.clone(), NpmRegistryConfig::load(None));
+        let data = registry.fetch_packument(name).await
+            .map_err(|e| MCPError::NotFound(format!("未找到npm包: {} ({})", name, e)))?;
 
         let latest_version = data["dist-tags"]["latest"]
             .as_str()
             .ok_or_else(|| MCPError::CacheError("无效的npm响应".to_string()))?;
+        let dist_tags = dist_tags_of(&data);
+        // next 标签高于 latest 时作为预览版本
+        let latest_preview = dist_tags.get("next")
+            .filter(|next| match (semver::Version::parse(next), semver::Version::parse(latest_version)) {
+                (Ok(next), Ok(latest)) => next > latest,
+                _ => false,
+            })
+            .cloned();
 
         Ok(VersionInfo {
             latest_stable: latest_version.to_string(),
-            latest_preview: None,
+            latest_preview,
             release_date: data["time"][latest_version]
                 .as_str()
                 .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
@@ -296,6 +361,10 @@ impl CheckVersionTool {
             eol_date: None,
             download_url: Some(format!("https://www.npmjs.com/package/{}", name)),
             package_type: "npm".to_string(),
+            dist_tags: Some(dist_tags),
+            crate_features: None,
+            packagist: None,
+            conda_channels: None,
             available_versions: data["versions"]
                 .as_object()
                 .map(|versions| versions.keys().cloned().collect())
@@ -311,8 +380,7 @@ impl CheckVersionTool {
 
     async fn fetch_pypi(&self, name: &str) -> Result<VersionInfo> {
         let url = format!("{}/{}/json", Registry::PyPI.base_url(), name);
-        let response = self.client.get(&url).send().await?;
-        let data: Value = response.json().await?;
+        let data = self.fetcher.get_json(&url).await?.body;
 
         let info = data["info"].as_object()
             .ok_or_else(|| MCPError::CacheError("无效的PyPI响应".to_string()))?;
@@ -332,6 +400,10 @@ impl CheckVersionTool {
             eol_date: None,
             download_url: Some(format!("https://pypi.org/project/{}", name)),
             package_type: "pip".to_string(),
+            dist_tags: None,
+            crate_features: None,
+            packagist: None,
+            conda_channels: None,
             available_versions: data["releases"]
                 .as_object()
                 .map(|releases| releases.keys().cloned().collect())
@@ -343,6 +415,16 @@ impl CheckVersionTool {
         })
     }
 
+    /// 获取PyPI某版本的wheel兼容性报告
+    async fn fetch_pypi_wheels(&self, name: &str, version: &str) -> Result<WheelCompatibility> {
+        let url = format!("{}/{}/{}/json", Registry::PyPI.base_url(), name, version);
+        let data = self.fetcher.get_json(&url).await?.body;
+        let files = data["urls"]
+            .as_array()
+            .ok_or_else(|| MCPError::CacheError("无效的PyPI响应".to_string()))?;
+        Ok(analyze_release_files(version, files.iter().filter_map(|f| f["filename"].as_str())))
+    }
+
     async fn fetch_maven_central(&self, name: &str) -> Result<VersionInfo> {
         // Maven Central使用Solr查询API
         // 对于 "org.springframework:spring-core" 格式，需要分离groupId和artifactId
@@ -372,14 +454,14 @@ impl CheckVersionTool {
             )
         };
         
-        let response = self.client.get(&url).send().await?;
+        let response = self.fetcher.get_json(&url).await?;
         
         // 检查响应状态
-        if !response.status().is_success() {
+        if !response.is_success() {
             return Err(MCPError::NotFound(format!("未找到Maven包: {}", name)).into());
         }
         
-        let data: Value = response.json().await?;
+        let data = response.body;
         
         let docs = data["response"]["docs"].as_array()
             .ok_or_else(|| MCPError::CacheError("无效的Maven Central响应".to_string()))?;
@@ -410,6 +492,10 @@ impl CheckVersionTool {
                 artifact_id
             )),
             package_type: "maven".to_string(),
+            dist_tags: None,
+            crate_features: None,
+            packagist: None,
+            conda_channels: None,
             available_versions: docs.iter()
                 .filter_map(|doc| doc["v"].as_str().map(String::from))
                 .collect(),
@@ -419,59 +505,93 @@ impl CheckVersionTool {
     }
 
     async fn fetch_go_proxy(&self, name: &str) -> Result<VersionInfo> {
-        // Go Proxy API
-        let url = format!("{}/{}/@v/list", Registry::GoProxy.base_url(), name);
-        let response = self.client.get(&url).send().await?;
-        let versions: Vec<String> = response
-            .text()
-            .await?
-            .lines()
-            .map(String::from)
-            .collect();
-            
-        if versions.is_empty() {
-            return Err(MCPError::NotFound(format!("未找到Go包: {}", name)).into());
-        }
-        
-        // 获取最新版本的详细信息
-        let latest = versions.last()
+        // 按 GOPROXY 配置依次查询模块代理（默认 proxy.golang.org）
+        let module = GoProxyClient::new(self.client.clone())
+            .fetch_module(name)
+            .await
+            .map_err(|e| MCPError::NotFound(format!("未找到Go包: {} ({})", name, e)))?;
+
+        let latest = module.latest()
             .ok_or_else(|| MCPError::CacheError("无法获取最新版本".to_string()))?;
-            
-        let info_url = format!(
-            "{}/{}/@v/{}.info",
-            Registry::GoProxy.base_url(),
-            name,
-            latest
-        );
-        
-        let info: Value = self.client.get(&info_url)
-            .send()
-            .await?
-            .json()
-            .await?;
-            
+
         Ok(VersionInfo {
-            latest_stable: latest.clone(),
-            latest_preview: None,
-            release_date: info["Time"]
-                .as_str()
-                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(Utc::now),
+            latest_stable: latest.raw.clone(),
+            latest_preview: module.resolved.latest_preview.as_ref().map(|v| v.raw.clone()),
+            release_date: module.release_date.unwrap_or_else(Utc::now),
             eol_date: None,
-            download_url: Some(format!("https://pkg.go.dev/{}", name)),
+            download_url: Some(format!("{}/{}/@v/{}.zip", module.proxy, escape_module_path(name), latest.raw)),
             package_type: "go".to_string(),
-            available_versions: versions,
+            dist_tags: None,
+            crate_features: None,
+            packagist: None,
+            conda_channels: None,
+            available_versions: module.resolved.versions.iter().map(|v| v.raw.clone()).collect(),
             dependencies: None,
             repository_url: Some(format!("https://pkg.go.dev/{}", name)),
         })
     }
 
+    async fn fetch_packagist(&self, name: &str) -> Result<VersionInfo> {
+        let coordinate = PackageCoordinate::parse(name)
+            .map_err(|e| MCPError::InvalidParameter(e.to_string()))?;
+        // 同时取开发分支，按项目的 minimum-stability 解析时可能用到
+        let package = PackagistClient::new(self.fetcher.clone())
+            .fetch_package(&coordinate, true)
+            .await
+            .map_err(|e| MCPError::NotFound(format!("未找到Composer包: {} ({})", name, e)))?;
+
+        // 没有稳定版时退回最新的预发布版本
+        let latest = package.latest_stable()
+            .or_else(|| package.latest(Stability::Alpha))
+            .ok_or_else(|| MCPError::NotFound(format!("{} 只有开发分支，没有可用版本", name)))?;
+
+        Ok(VersionInfo {
+            latest_stable: latest.version.clone(),
+            latest_preview: package.latest_preview().map(|v| v.version.clone()),
+            release_date: latest.released.unwrap_or_else(Utc::now),
+            eol_date: None,
+            download_url: Some(format!("https://packagist.org/packages/{}", coordinate.name())),
+            package_type: "composer".to_string(),
+            dist_tags: None,
+            crate_features: None,
+            available_versions: package.versions.iter().map(|v| v.version.clone()).collect(),
+            dependencies: latest.require.clone(),
+            repository_url: package.repository_url.clone(),
+            conda_channels: None,
+            packagist: Some(package),
+        })
+    }
+
+    async fn fetch_conda(&self, name: &str) -> Result<VersionInfo> {
+        let channels = CondaClient::from_env(self.fetcher.clone()).fetch_all(name).await;
+        // 频道按优先级排列，conda 从第一个包含该包的频道安装
+        let Some(primary) = channels.first() else {
+            return Err(MCPError::NotFound(format!("在conda频道中未找到包: {}", name)).into());
+        };
+        let latest = primary.latest()
+            .ok_or_else(|| MCPError::CacheError("无法获取最新版本".to_string()))?;
+
+        Ok(VersionInfo {
+            latest_stable: latest.version.clone(),
+            latest_preview: None,
+            release_date: latest.uploaded.unwrap_or_else(Utc::now),
+            eol_date: None,
+            download_url: Some(format!("https://anaconda.org/{}/{}", primary.channel, primary.name)),
+            package_type: "conda".to_string(),
+            dist_tags: None,
+            crate_features: None,
+            packagist: None,
+            available_versions: merge_version_sources(&[], &channels).into_iter().map(|v| v.version).collect(),
+            dependencies: None,
+            repository_url: primary.dev_url.clone().or_else(|| primary.home.clone()),
+            conda_channels: Some(channels),
+        })
+    }
+
     async fn fetch_pub_dev(&self, name: &str) -> Result<VersionInfo> {
         // pub.dev API
         let url = format!("{}/packages/{}", Registry::PubDev.base_url(), name);
-        let response = self.client.get(&url).send().await?;
-        let data: Value = response.json().await?;
+        let data = self.fetcher.get_json(&url).await?.body;
         
         let latest = data["latest"]
             .as_object()
@@ -492,6 +612,10 @@ impl CheckVersionTool {
             eol_date: None,
             download_url: Some(format!("https://pub.dev/packages/{}", name)),
             package_type: "pub".to_string(),
+            dist_tags: None,
+            crate_features: None,
+            packagist: None,
+            conda_channels: None,
             available_versions: data["versions"]
                 .as_array()
                 .map(|versions| {
@@ -509,6 +633,18 @@ impl CheckVersionTool {
         })
     }
     
+    /// 未指定包管理器时并发查询所有可能的注册表，超过截止时间的查询视为失败
+    async fn fan_out(&self, name: &str, ecosystems: &[&'static str], deadline: std::time::Duration) -> Vec<(&'static str, Result<VersionInfo>)> {
+        let queries = ecosystems.iter().map(|ecosystem| async move {
+            let result = match tokio::time::timeout(deadline, self.get_version_info(ecosystem, name)).await {
+                Ok(result) => result,
+                Err(_) => Err(MCPError::ServerError(format!("查询超时({}ms)", deadline.as_millis())).into()),
+            };
+            (*ecosystem, result)
+        });
+        futures::future::join_all(queries).await
+    }
+
     async fn get_version_info(&self, type_: &str, name: &str) -> Result<VersionInfo> {
         let cache_key = format!("{}:{}", type_, name);
         let cache_ttl = chrono::Duration::hours(1);
@@ -550,16 +686,24 @@ impl MCPTool for CheckVersionTool {
         static SCHEMA: OnceLock<Schema> = OnceLock::new();
         SCHEMA.get_or_init(|| {
             Schema::Object(SchemaObject {
-                required: vec!["type".to_string(), "name".to_string()],
+                required: vec!["name".to_string()],
                 properties: {
                     let mut map = HashMap::new();
                     map.insert(
                         "type".to_string(),
                         Schema::String(SchemaString {
-                            description: Some("包所属的包管理器类型(cargo/npm/pip/maven/go/pub/flutter/dart)，其中flutter和dart为SDK版本检查".to_string()),
+                            description: Some("包所属的包管理器类型(cargo/npm/pip/conda/maven/go/composer/pub/flutter/dart)，其中flutter和dart为SDK版本检查，composer包名为vendor/package。省略时使用会话偏好的默认注册表；未设置时并发查询cargo/npm/pip/go中可能的注册表，按项目语言排序返回所有匹配".to_string()),
                             ..Default::default()
                         }),
                     );
+                    map.insert(
+                        "timeout_ms".to_string(),
+                        Schema::Integer(SchemaInteger {
+                            description: Some("省略type时每个注册表查询的截止时间（毫秒），默认5000".to_string()),
+                            minimum: Some(100),
+                            maximum: Some(30000),
+                        }),
+                    );
                     map.insert(
                         "name".to_string(),
                         Schema::String(SchemaString {
@@ -573,6 +717,51 @@ impl MCPTool for CheckVersionTool {
                             description: Some("是否包含预览版本".to_string()),
                         }),
                     );
+                    map.insert(
+                        "workspace_path".to_string(),
+                        Schema::String(SchemaString {
+                            description: Some("工作区根目录（可选）。提供时扫描所有成员清单，报告同一依赖的版本分歧并给出统一建议".to_string()),
+                            ..Default::default()
+                        }),
+                    );
+                    map.insert(
+                        "version".to_string(),
+                        Schema::String(SchemaString {
+                            description: Some("cargo类型可选：查询该版本的feature信息，默认最新稳定版".to_string()),
+                            ..Default::default()
+                        }),
+                    );
+                    map.insert(
+                        "feature_query".to_string(),
+                        Schema::String(SchemaString {
+                            description: Some("cargo类型可选：按关键字查找启用相关功能的feature，例如 tls、json".to_string()),
+                            ..Default::default()
+                        }),
+                    );
+                    map.insert(
+                        "boms".to_string(),
+                        Schema::Array(SchemaArray {
+                            description: Some("maven类型可选：项目导入的BOM坐标(groupId:artifactId:version)，按声明顺序。与workspace_path中pom.xml/build.gradle检测到的BOM合并，用于计算项目实际得到的生效版本".to_string()),
+                            items: Box::new(Schema::String(SchemaString::default())),
+                        }),
+                    );
+                    map.insert(
+                        "conda_channels".to_string(),
+                        Schema::Array(SchemaArray {
+                            description: Some("pip/conda类型可选：按优先级排列的conda频道，默认取CONDA_CHANNELS环境变量，未设置时为conda-forge。pip类型传空数组时不查询conda".to_string()),
+                            items: Box::new(Schema::String(SchemaString::default())),
+                        }),
+                    );
+                    map.insert(
+                        "minimum_stability".to_string(),
+                        Schema::String(SchemaString {
+                            description: Some("composer类型可选：允许的最低稳定性(dev/alpha/beta/RC/stable)。省略时读取workspace_path（或当前目录）composer.json的minimum-stability、prefer-stable和该包约束上的@flag，默认stable".to_string()),
+                            enum_values: Some(vec!["dev".to_string(), "alpha".to_string(), "beta".to_string(), "RC".to_string(), "stable".to_string()]),
+                            ..Default::default()
+                        }),
+                    );
+                    // 对 available_versions 分页
+                    pagination::add_pagination_properties(&mut map);
                     map
                 },
                 ..Default::default()
@@ -580,14 +769,99 @@ impl MCPTool for CheckVersionTool {
         })
     }
 
+    fn apply_session_defaults(&self, params: &mut Value, session: &SessionPreferences) {
+        if let Some(registry) = &session.default_registry {
+            session::fill_default(params, "type", json!(registry));
+        }
+    }
+
+    fn output_schema(&self) -> Option<&Schema> {
+        static OUTPUT_SCHEMA: OnceLock<Schema> = OnceLock::new();
+        Some(OUTPUT_SCHEMA.get_or_init(|| {
+            let mut map = HashMap::new();
+            map.insert("latest_stable".to_string(), Schema::String(SchemaString {
+                description: Some("最新稳定版本".to_string()),
+                ..Default::default()
+            }));
+            map.insert("latest_preview".to_string(), Schema::String(SchemaString {
+                description: Some("最新预览版本（可能为null）".to_string()),
+                ..Default::default()
+            }));
+            map.insert("release_date".to_string(), Schema::String(SchemaString {
+                description: Some("最新版本发布时间(RFC3339)".to_string()),
+                ..Default::default()
+            }));
+            map.insert("package_type".to_string(), Schema::String(SchemaString {
+                description: Some("包管理器类型".to_string()),
+                ..Default::default()
+            }));
+            map.insert("available_versions".to_string(), Schema::Array(SchemaArray {
+                description: Some("可用版本列表（传入cursor或page_size时为当前页）".to_string()),
+                items: Box::new(Schema::String(SchemaString::default())),
+            }));
+            map.insert("next_cursor".to_string(), Schema::String(SchemaString {
+                description: Some("分页时下一页的游标，没有更多版本时省略".to_string()),
+                ..Default::default()
+            }));
+            map.insert("download_url".to_string(), Schema::String(SchemaString::default()));
+            map.insert("repository_url".to_string(), Schema::String(SchemaString::default()));
+            map.insert("features".to_string(), Schema::Object(SchemaObject {
+                description: Some("cargo类型：指定版本的feature列表(features)、默认feature(default_features)和可选依赖".to_string()),
+                ..Default::default()
+            }));
+            map.insert("dist_tags".to_string(), Schema::Object(SchemaObject {
+                description: Some("npm类型：dist-tags（latest、next、lts等）到版本的映射".to_string()),
+                ..Default::default()
+            }));
+            map.insert("effective_version".to_string(), Schema::String(SchemaString {
+                description: Some("maven类型：受BOM/dependencyManagement管理时项目实际得到的版本".to_string()),
+                ..Default::default()
+            }));
+            map.insert("wheel_compatibility".to_string(), Schema::Object(SchemaObject {
+                description: Some("pip类型：最新版本各平台/Python版本可用的wheel，source_only_platforms列出只能源码构建的平台".to_string()),
+                ..Default::default()
+            }));
+            map.insert("resolved_version".to_string(), Schema::String(SchemaString {
+                description: Some("composer类型：按稳定性策略解析出的最新版本，stability_policy为使用的策略".to_string()),
+                ..Default::default()
+            }));
+            map.insert("version_sources".to_string(), Schema::Array(SchemaArray {
+                description: Some("pip/conda类型：合并PyPI和各conda频道后的版本（从新到旧），sources列出提供该版本的来源(pypi或频道名)".to_string()),
+                items: Box::new(Schema::Object(SchemaObject::default())),
+            }));
+            map.insert("conda_channels".to_string(), Schema::Array(SchemaArray {
+                description: Some("pip/conda类型：包含该包的conda频道及其最新版本、提供构建的平台子目录".to_string()),
+                items: Box::new(Schema::Object(SchemaObject::default())),
+            }));
+            map.insert("matches".to_string(), Schema::Array(SchemaArray {
+                description: Some("省略type时：各注册表的匹配结果，按项目语言排序，第一个即顶层返回的结果".to_string()),
+                items: Box::new(Schema::Object(SchemaObject::default())),
+            }));
+            map.insert("managed_by".to_string(), Schema::String(SchemaString {
+                description: Some("定义生效版本的BOM坐标，本地dependencyManagement声明时为project".to_string()),
+                ..Default::default()
+            }));
+            Schema::Object(SchemaObject {
+                required: vec![
+                    "latest_stable".to_string(),
+                    "release_date".to_string(),
+                    "package_type".to_string(),
+                    "available_versions".to_string(),
+                ],
+                properties: map,
+                description: Some("包版本信息".to_string()),
+            })
+        }))
+    }
+
     async fn execute(&self, parameters: Value) -> Result<Value> {
-        let type_ = parameters["type"]
-            .as_str()
-            .ok_or_else(|| MCPError::InvalidParameter("缺少type参数".to_string()))?;
-            
         let name = parameters["name"]
             .as_str()
             .ok_or_else(|| MCPError::InvalidParameter("缺少name参数".to_string()))?;
+
+        let Some(type_) = parameters["type"].as_str() else {
+            return self.execute_ambiguous(name, &parameters).await;
+        };
             
         let _include_preview = parameters["include_preview"]
             .as_bool()
@@ -595,16 +869,230 @@ impl MCPTool for CheckVersionTool {
 
         let info = self.get_version_info(type_, name).await?;
         
-        Ok(json!({
-            "latest_stable": info.latest_stable,
-            "latest_preview": info.latest_preview,
-            "release_date": info.release_date,
-            "eol_date": info.eol_date,
-            "download_url": info.download_url,
-            "package_type": info.package_type,
-            "available_versions": info.available_versions,
-            "dependencies": info.dependencies,
-            "repository_url": info.repository_url,
-        }))
+        let mut result = summarize(&info);
+        if pagination::is_paginated(&parameters) {
+            let page = pagination::paginate(
+                info.available_versions.clone(),
+                |version| version.as_str(),
+                pagination::cursor_param(&parameters),
+                pagination::page_size_param(&parameters),
+            )?;
+            result["available_versions"] = json!(page.items);
+            result["versions_total"] = json!(page.total);
+            if let Some(next_cursor) = page.next_cursor {
+                result["next_cursor"] = json!(next_cursor);
+            }
+        }
+
+        if type_ == "cargo" {
+            let requested = parameters["version"].as_str().filter(|v| *v != info.latest_stable);
+            let features = match requested {
+                Some(version) => Some(fetch_crate_features(&self.client, name, version).await
+                    .map_err(|e| MCPError::NotFound(format!("获取 {} {} 的feature失败: {}", name, version, e)))?),
+                None => info.crate_features.clone(),
+            };
+            if let Some(features) = features {
+                if let Some(keyword) = parameters["feature_query"].as_str() {
+                    result["features_matching"] = json!(features.features_matching(keyword));
+                }
+                result["features"] = serde_json::to_value(&features)?;
+                result["default_enabled"] = json!(features.enabled_by_default());
+            }
+        }
+
+        let workspace_root = match parameters["workspace_path"].as_str() {
+            Some(workspace_path) => {
+                let root = std::path::Path::new(workspace_path);
+                if !root.is_dir() {
+                    return Err(MCPError::InvalidParameter(format!("工作区目录不存在: {}", workspace_path)).into());
+                }
+                Some(root)
+            }
+            None => None,
+        };
+
+        if type_ == "pip" {
+            match self.fetch_pypi_wheels(name, &info.latest_stable).await {
+                Ok(wheels) => result["wheel_compatibility"] = serde_json::to_value(wheels)?,
+                Err(e) => tracing::debug!("获取 {} 的wheel信息失败: {}", name, e),
+            }
+        }
+
+        if type_ == "pip" || type_ == "conda" {
+            let channels = match parameters["conda_channels"].as_array() {
+                Some(channels) => {
+                    let channels: Vec<String> = channels.iter().filter_map(|c| c.as_str()).map(String::from).collect();
+                    if channels.is_empty() {
+                        Vec::new()
+                    } else {
+                        CondaClient::new(self.fetcher.clone()).with_channels(channels).fetch_all(name).await
+                    }
+                }
+                None => match &info.conda_channels {
+                    Some(channels) => channels.clone(),
+                    None => CondaClient::from_env(self.fetcher.clone()).fetch_all(name).await,
+                },
+            };
+            if !channels.is_empty() {
+                let pypi_versions: &[String] = if type_ == "pip" { &info.available_versions } else { &[] };
+                result["version_sources"] = json!(merge_version_sources(pypi_versions, &channels));
+                result["conda_channels"] = json!(channels.iter().map(|package| json!({
+                    "channel": package.channel,
+                    "latest_version": package.latest_version,
+                    "subdirs": package.latest().map(|v| &v.subdirs),
+                    "url": format!("https://anaconda.org/{}/{}", package.channel, package.name),
+                })).collect::<Vec<_>>());
+            }
+        }
+
+        if let Some(package) = &info.packagist {
+            let policy = match parameters["minimum_stability"].as_str() {
+                Some(flag) => StabilityPolicy {
+                    minimum: Stability::parse(flag)
+                        .ok_or_else(|| MCPError::InvalidParameter(format!("无效的minimum_stability: {}", flag)))?,
+                    prefer_stable: false,
+                },
+                None => {
+                    let project_root = match workspace_root {
+                        Some(root) => root.to_path_buf(),
+                        None => std::env::current_dir()?,
+                    };
+                    std::fs::read_to_string(project_root.join("composer.json"))
+                        .ok()
+                        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+                        .map(|manifest| StabilityPolicy::from_manifest(&manifest, name))
+                        .unwrap_or_default()
+                }
+            };
+            let resolved = policy.resolve(package);
+            result["resolved_version"] = json!(resolved.map(|v| &v.version));
+            result["resolved_stability"] = json!(resolved.map(|v| v.stability));
+            result["stability_policy"] = serde_json::to_value(&policy)?;
+        }
+
+        if type_ == "maven" {
+            let mut management = workspace_root.map(detect_project_management).unwrap_or_default();
+            if let Some(boms) = parameters["boms"].as_array() {
+                // 显式传入的 BOM 优先于项目文件中检测到的
+                let explicit = boms.iter()
+                    .filter_map(|b| b.as_str())
+                    .map(|bom| MavenCoordinate::parse(bom)
+                        .ok_or_else(|| MCPError::InvalidParameter(format!("无效的BOM坐标: {}", bom))))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                management.boms.splice(0..0, explicit);
+            }
+            if !management.boms.is_empty() || !management.local.is_empty() {
+                let managed = BomResolver::new(self.client.clone())
+                    .effective_version_for_project(&management, name)
+                    .await?;
+                result["effective_version"] = json!(managed.as_ref().map(|m| &m.version));
+                result["managed_by"] = json!(managed.as_ref().map(|m| &m.managed_by));
+            }
+        }
+
+        if let Some(root) = workspace_root {
+            let requirements = scan_workspace_requirements(root, type_, name);
+            let divergence = analyze_divergence(name, requirements, Some(info.latest_stable.as_str()));
+            result["workspace_divergence"] = serde_json::to_value(divergence)?;
+        }
+
+        Ok(result)
+    }
+}
+
+impl CheckVersionTool {
+    /// 未指定 type：并发查询可能的注册表，返回全部匹配，项目使用的生态排在前面
+    async fn execute_ambiguous(&self, name: &str, parameters: &Value) -> Result<Value> {
+        let deadline = std::time::Duration::from_millis(parameters["timeout_ms"].as_u64().unwrap_or(5000).clamp(100, 30000));
+        let project_root = match parameters["workspace_path"].as_str() {
+            Some(path) => std::path::PathBuf::from(path),
+            None => std::env::current_dir()?,
+        };
+        let project_ecosystems = detect_project_ecosystems(&project_root);
+        let candidates = rank_ecosystems(&plausible_ecosystems(name), &project_ecosystems);
+        if candidates.is_empty() {
+            return Err(MCPError::InvalidParameter(format!("无法根据包名 {} 推断包管理器，请指定type参数", name)).into());
+        }
+
+        let mut matches = Vec::new();
+        let mut failures = serde_json::Map::new();
+        // join_all 保持输入顺序，结果天然按排名排列
+        for (ecosystem, outcome) in self.fan_out(name, &candidates, deadline).await {
+            match outcome {
+                Ok(info) => matches.push(summarize(&info)),
+                Err(e) => {
+                    failures.insert(ecosystem.to_string(), json!(e.to_string()));
+                }
+            }
+        }
+        if matches.is_empty() {
+            return Err(MCPError::NotFound(format!(
+                "在 {} 中均未找到包 {}", candidates.join("/"), name
+            )).into());
+        }
+
+        let mut result = matches[0].clone();
+        result["ecosystem_inferred"] = json!(true);
+        result["project_ecosystems"] = json!(project_ecosystems);
+        result["matches"] = json!(matches);
+        result["failed_registries"] = Value::Object(failures);
+        Ok(result)
+    }
+}
+
+/// 版本信息的基本输出字段
+fn summarize(info: &VersionInfo) -> Value {
+    let mut result = json!({
+        "latest_stable": info.latest_stable,
+        "latest_preview": info.latest_preview,
+        "release_date": info.release_date,
+        "eol_date": info.eol_date,
+        "download_url": info.download_url,
+        "package_type": info.package_type,
+        "available_versions": info.available_versions,
+        "dependencies": info.dependencies,
+        "repository_url": info.repository_url,
+    });
+    if let Some(dist_tags) = &info.dist_tags {
+        result["dist_tags"] = json!(dist_tags);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::fetcher::testing::MockRegistry;
+
+    #[tokio::test]
+    async fn test_crates_io_parsing_against_mock_registry() {
+        let base = Registry::CratesIo.base_url();
+        let registry = Arc::new(
+            MockRegistry::new()
+                .with_json(&format!("{}/crates/serde", base), json!({ "crate": { "newest_version": "1.0.200" } }))
+                .with_json(
+                    &format!("{}/crates/serde/versions", base),
+                    json!({ "versions": [
+                        { "num": "1.0.200", "created_at": "2024-05-01T00:00:00Z", "features": { "derive": ["serde_derive"] } },
+                        { "num": "1.0.199", "created_at": "2024-04-20T00:00:00Z", "features": {} },
+                    ] }),
+                )
+                .with_json(
+                    &format!("{}/crates/serde/1.0.200/dependencies", base),
+                    json!({ "dependencies": [{ "crate_id": "serde_derive", "optional": true, "kind": "normal" }] }),
+                ),
+        );
+        let tool = CheckVersionTool::new().with_fetcher(registry.clone());
+
+        let info = tool.fetch_crates_io("serde").await.unwrap();
+        assert_eq!(info.latest_stable, "1.0.200");
+        assert_eq!(info.available_versions, vec!["1.0.200", "1.0.199"]);
+        assert_eq!(info.release_date.to_rfc3339(), "2024-05-01T00:00:00+00:00");
+        assert!(info.crate_features.is_some());
+        assert_eq!(registry.requests().len(), 3);
+
+        // 未预置的包按 404 处理
+        let missing = tool.fetch_crates_io("no-such-crate").await.unwrap_err();
+        assert!(missing.to_string().contains("no-such-crate"));
     }
 }
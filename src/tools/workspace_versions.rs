@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::versioning::npm_registry::find_manifest_requirement;
+
+/// 扫描时跳过的目录
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules", ".git", "build", "dist", ".dart_tool", "vendor", "__pycache__", ".venv"];
+
+/// 工作区成员清单中对某个依赖的版本要求
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManifestRequirement {
+    /// 清单文件路径（相对工作区根目录）
+    pub manifest: String,
+    /// 原始版本要求，例如 `^1.2`、`==2.0.1`
+    pub requirement: String,
+    /// 归一化后的版本（去掉比较运算符并补齐为 x.y.z）
+    pub normalized: Option<String>,
+}
+
+/// 工作区内同一依赖的版本分歧
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionDivergence {
+    pub package: String,
+    pub requirements: Vec<ManifestRequirement>,
+    /// 不同版本要求的数量
+    pub distinct_versions: usize,
+    pub diverged: bool,
+    /// 建议统一使用的版本
+    pub recommended_version: Option<String>,
+    pub reason: String,
+}
+
+/// 根据项目根目录的清单文件检测项目使用的包管理器，按检测顺序返回
+pub fn detect_project_ecosystems(root: &Path) -> Vec<&'static str> {
+    const MARKERS: &[(&str, &[&str])] = &[
+        ("cargo", &["Cargo.toml"]),
+        ("npm", &["package.json"]),
+        ("pip", &["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"]),
+        ("go", &["go.mod"]),
+        ("maven", &["pom.xml"]),
+        ("pub", &["pubspec.yaml"]),
+        ("composer", &["composer.json"]),
+    ];
+    MARKERS.iter()
+        .filter(|(_, files)| files.iter().any(|file| root.join(file).is_file()))
+        .map(|(ecosystem, _)| *ecosystem)
+        .collect()
+}
+
+/// 未指定包管理器时可能包含该包名的注册表
+///
+/// Go 只在名称像模块路径（首段含域名）时查询；npm 作用域包只查 npm；`vendor/package` 只查 Packagist。
+pub fn plausible_ecosystems(name: &str) -> Vec<&'static str> {
+    if name.starts_with('@') {
+        return vec!["npm"];
+    }
+    let is_module_path = name.split('/').next().map_or(false, |host| host.contains('.')) && name.contains('/');
+    if is_module_path {
+        return vec!["go"];
+    }
+    if crate::versioning::packagist::PackageCoordinate::parse(name).is_ok() {
+        return vec!["composer"];
+    }
+    let simple = |extra: &[char]| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || extra.contains(&c));
+    let mut ecosystems = Vec::new();
+    if simple(&['-', '_']) {
+        ecosystems.push("cargo");
+    }
+    if simple(&['-', '_', '.']) && name.chars().all(|c| !c.is_ascii_uppercase()) {
+        ecosystems.push("npm");
+    }
+    if simple(&['-', '_', '.']) {
+        ecosystems.push("pip");
+    }
+    ecosystems
+}
+
+/// 按项目语言排序候选包管理器：项目使用的排在前面，其余保持原顺序
+pub fn rank_ecosystems(candidates: &[&'static str], project: &[&str]) -> Vec<&'static str> {
+    let mut ranked = candidates.to_vec();
+    ranked.sort_by_key(|ecosystem| project.iter().position(|p| p == ecosystem).unwrap_or(usize::MAX));
+    ranked
+}
+
+/// 在工作区内扫描指定包管理器的清单，收集对某个依赖的版本要求
+pub fn scan_workspace_requirements(root: &Path, package_type: &str, name: &str) -> Vec<ManifestRequirement> {
+    let manifest_names: &[&str] = match package_type {
+        "cargo" => &["Cargo.toml"],
+        "npm" => &["package.json"],
+        "pip" => &["requirements.txt", "requirements-dev.txt", "pyproject.toml"],
+        "go" => &["go.mod"],
+        "pub" | "flutter" | "dart" => &["pubspec.yaml"],
+        "composer" | "packagist" => &["composer.json"],
+        _ => return Vec::new(),
+    };
+
+    let mut requirements = Vec::new();
+    let walker = WalkDir::new(root)
+        .max_depth(6)
+        .into_iter()
+        .filter_entry(|entry| {
+            !(entry.file_type().is_dir()
+                && entry.depth() > 0
+                && SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+        });
+
+    for entry in walker.filter_map(|e| e.ok()) {
+        let file_name = entry.file_name().to_string_lossy();
+        if !entry.file_type().is_file() || !manifest_names.contains(&file_name.as_ref()) {
+            continue;
+        }
+        let content = match std::fs::read_to_string(entry.path()) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let found = match file_name.as_ref() {
+            "Cargo.toml" => cargo_requirement(&content, name),
+            "package.json" => npm_requirement(&content, name),
+            "pyproject.toml" => pyproject_requirement(&content, name),
+            "go.mod" => go_requirement(&content, name),
+            "pubspec.yaml" => pubspec_requirement(&content, name),
+            "composer.json" => composer_requirement(&content, name),
+            _ => requirements_txt_requirement(&content, name),
+        };
+        if let Some(requirement) = found {
+            let manifest = relative_path(root, entry.path());
+            requirements.push(ManifestRequirement {
+                manifest,
+                normalized: normalize_version(&requirement),
+                requirement,
+            });
+        }
+    }
+    requirements.sort_by(|a, b| a.manifest.cmp(&b.manifest));
+    requirements
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn cargo_requirement(content: &str, name: &str) -> Option<String> {
+    let manifest: toml::Value = toml::from_str(content).ok()?;
+    let tables = [
+        manifest.get("dependencies"),
+        manifest.get("dev-dependencies"),
+        manifest.get("build-dependencies"),
+        manifest.get("workspace").and_then(|w| w.get("dependencies")),
+    ];
+    tables.iter().flatten().find_map(|table| match table.get(name)? {
+        toml::Value::String(version) => Some(version.clone()),
+        // `workspace = true` 继承工作区版本，不单独计入
+        toml::Value::Table(spec) => spec.get("version").and_then(|v| v.as_str()).map(String::from),
+        _ => None,
+    })
+}
+
+/// 包括 `npm:` 别名引用；`workspace:` 协议指向本地包，不计入
+fn npm_requirement(content: &str, name: &str) -> Option<String> {
+    let manifest: serde_json::Value = serde_json::from_str(content).ok()?;
+    find_manifest_requirement(&manifest, name)
+}
+
+/// composer.json 的 `require`/`require-dev`（包名不区分大小写）
+fn composer_requirement(content: &str, name: &str) -> Option<String> {
+    let manifest: serde_json::Value = serde_json::from_str(content).ok()?;
+    ["require", "require-dev"]
+        .iter()
+        .filter_map(|section| manifest[section].as_object())
+        .flat_map(|deps| deps.iter())
+        .find(|(dependency, _)| dependency.eq_ignore_ascii_case(name))
+        .and_then(|(_, constraint)| constraint.as_str().map(String::from))
+}
+
+fn requirements_txt_requirement(content: &str, name: &str) -> Option<String> {
+    content.lines().find_map(|line| pep508_requirement(line.split('#').next()?.trim(), name))
+}
+
+fn pyproject_requirement(content: &str, name: &str) -> Option<String> {
+    let manifest: toml::Value = toml::from_str(content).ok()?;
+    if let Some(deps) = manifest.get("project").and_then(|p| p.get("dependencies")).and_then(|d| d.as_array()) {
+        if let Some(found) = deps.iter().filter_map(|d| d.as_str()).find_map(|d| pep508_requirement(d, name)) {
+            return Some(found);
+        }
+    }
+    // Poetry
+    manifest.get("tool")
+        .and_then(|t| t.get("poetry"))
+        .and_then(|p| p.get("dependencies"))
+        .and_then(|d| d.get(name))
+        .and_then(|v| v.as_str().map(String::from).or_else(|| v.get("version").and_then(|x| x.as_str()).map(String::from)))
+}
+
+/// 解析 `requests>=2.28,<3` 形式的依赖声明
+fn pep508_requirement(spec: &str, name: &str) -> Option<String> {
+    let spec = spec.split(';').next()?.trim();
+    let split_at = spec.find(|c: char| "=<>!~ [".contains(c)).unwrap_or(spec.len());
+    let (package, rest) = spec.split_at(split_at);
+    let normalize = |s: &str| s.to_lowercase().replace('_', "-");
+    if normalize(package.trim()) != normalize(name) {
+        return None;
+    }
+    let rest = match rest.find(']') {
+        Some(end) if rest.trim_start().starts_with('[') => &rest[end + 1..],
+        _ => rest,
+    };
+    let rest = rest.trim();
+    (!rest.is_empty()).then(|| rest.to_string())
+}
+
+fn go_requirement(content: &str, name: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let line = line.trim().trim_start_matches("require").trim();
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some(module), Some(version)) if module == name => Some(version.to_string()),
+            _ => None,
+        }
+    })
+}
+
+fn pubspec_requirement(content: &str, name: &str) -> Option<String> {
+    let manifest: serde_yaml::Value = serde_yaml::from_str(content).ok()?;
+    ["dependencies", "dev_dependencies"].iter().find_map(|section| {
+        match manifest.get(*section)?.get(name)? {
+            serde_yaml::Value::String(version) => Some(version.clone()),
+            serde_yaml::Value::Mapping(spec) => spec.get("version").and_then(|v| v.as_str()).map(String::from),
+            _ => None,
+        }
+    })
+}
+
+/// 把版本要求归一化为 x.y.z，无法识别时返回 None
+pub fn normalize_version(requirement: &str) -> Option<String> {
+    let first = requirement.split(',').next()?.trim();
+    let version = first.trim_start_matches(|c: char| "^~=<>!v ".contains(c));
+    let core: String = version.chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    let mut parts: Vec<&str> = core.split('.').filter(|p| !p.is_empty()).collect();
+    if parts.is_empty() {
+        return None;
+    }
+    parts.truncate(3);
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+    Some(parts.join("."))
+}
+
+/// 分析版本分歧并给出统一建议
+///
+/// 建议版本取各成员要求中的最高版本；若最新稳定版与之同一主版本，则建议直接统一到最新稳定版。
+pub fn analyze_divergence(package: &str, requirements: Vec<ManifestRequirement>, latest_stable: Option<&str>) -> VersionDivergence {
+    let mut distinct: Vec<semver::Version> = requirements.iter()
+        .filter_map(|r| r.normalized.as_deref())
+        .filter_map(|v| semver::Version::parse(v).ok())
+        .collect();
+    distinct.sort();
+    distinct.dedup();
+
+    let highest = distinct.last().cloned();
+    let latest = latest_stable
+        .and_then(normalize_version)
+        .and_then(|v| semver::Version::parse(&v).ok());
+
+    let (recommended_version, reason) = match (&highest, &latest) {
+        (Some(highest), Some(latest)) if latest.major == highest.major && latest >= highest => (
+            Some(latest.to_string()),
+            format!("最新稳定版 {} 与工作区最高要求 {} 属于同一主版本，可直接统一升级", latest, highest),
+        ),
+        (Some(highest), _) => (
+            Some(highest.to_string()),
+            format!("统一到工作区成员要求的最高版本 {}，避免同一依赖被解析为多个版本", highest),
+        ),
+        (None, _) => (None, "未在工作区清单中找到可比较的版本要求".to_string()),
+    };
+
+    VersionDivergence {
+        package: package.to_string(),
+        distinct_versions: distinct.len(),
+        diverged: distinct.len() > 1,
+        requirements,
+        recommended_version,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plausible_and_ranked_ecosystems() {
+        assert_eq!(plausible_ecosystems("serde"), vec!["cargo", "npm", "pip"]);
+        assert_eq!(plausible_ecosystems("@types/node"), vec!["npm"]);
+        assert_eq!(plausible_ecosystems("github.com/gin-gonic/gin"), vec!["go"]);
+        assert_eq!(plausible_ecosystems("zope.interface"), vec!["npm", "pip"]);
+        assert_eq!(plausible_ecosystems("Django"), vec!["cargo", "pip"]);
+        assert_eq!(plausible_ecosystems("symfony/console"), vec!["composer"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("pyproject.toml"), "[project]\nname = \"demo\"\n").unwrap();
+        let project = detect_project_ecosystems(dir.path());
+        assert_eq!(project, vec!["pip"]);
+        assert_eq!(rank_ecosystems(&["cargo", "npm", "pip"], &project), vec!["pip", "cargo", "npm"]);
+    }
+
+    #[test]
+    fn test_normalize_and_divergence() {
+        assert_eq!(normalize_version("^1.2").as_deref(), Some("1.2.0"));
+        assert_eq!(normalize_version(">=2.28,<3").as_deref(), Some("2.28.0"));
+        assert_eq!(normalize_version("v0.9.1").as_deref(), Some("0.9.1"));
+        assert_eq!(normalize_version("workspace:*"), None);
+
+        let requirements = vec![
+            ManifestRequirement { manifest: "a/Cargo.toml".into(), requirement: "1.28".into(), normalized: normalize_version("1.28") },
+            ManifestRequirement { manifest: "b/Cargo.toml".into(), requirement: "^1.35".into(), normalized: normalize_version("^1.35") },
+        ];
+        let divergence = analyze_divergence("tokio", requirements.clone(), Some("1.38.0"));
+        assert!(divergence.diverged);
+        assert_eq!(divergence.recommended_version.as_deref(), Some("1.38.0"));
+
+        let divergence = analyze_divergence("tokio", requirements, Some("2.0.0"));
+        assert_eq!(divergence.recommended_version.as_deref(), Some("1.35.0"));
+    }
+
+    #[test]
+    fn test_scan_cargo_workspace() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("crates/a")).unwrap();
+        std::fs::create_dir_all(root.join("crates/b")).unwrap();
+        std::fs::write(root.join("crates/a/Cargo.toml"), "[dependencies]\nserde = \"1.0.100\"\n").unwrap();
+        std::fs::write(root.join("crates/b/Cargo.toml"), "[dependencies]\nserde = { version = \"1.0.190\", features = [\"derive\"] }\n").unwrap();
+
+        let requirements = scan_workspace_requirements(root, "cargo", "serde");
+        assert_eq!(requirements.len(), 2);
+        assert_eq!(requirements[0].manifest, "crates/a/Cargo.toml");
+        assert_eq!(requirements[1].requirement, "1.0.190");
+    }
+}
@@ -0,0 +1,251 @@
+//! 向量存储的预写日志（WAL）和崩溃恢复
+//!
+//! 写入文档、删除文档和包版本标记在修改内存之前先追加到数据目录的 `vector_data.wal`，每条记录带
+//! 长度和 SHA-256 校验；进程在修改和落盘之间退出时，下次启动重放日志恢复这些修改，写了一半的末尾
+//! 记录按校验失败丢弃。每次保存数据文件时同时写入它的校验和，上一份数据文件保留为 `.prev`，
+//! 当前日志轮转为 `vector_data.wal.prev`；数据文件校验失败时改从上一份一致的数据文件加载，
+//! 再依次重放两段日志。重放的操作都是幂等的，日志中已经落盘的修改重放一次结果不变。
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::crypto::to_hex;
+
+/// 预写日志文件名
+pub const WAL_FILE: &str = "vector_data.wal";
+
+/// 上次保存前的日志（从上一份数据文件恢复时需要一并重放）
+pub const PREVIOUS_WAL_FILE: &str = "vector_data.wal.prev";
+
+/// 数据文件校验和的扩展名（`vector_data.bin.sha256`）
+const CHECKSUM_EXTENSION: &str = "sha256";
+
+/// 上一份数据文件的扩展名（`vector_data.bin.prev`）
+const PREVIOUS_EXTENSION: &str = "prev";
+
+/// 记录头长度：负载长度(u32 LE) + SHA-256 摘要
+const RECORD_HEADER_LEN: usize = 4 + 32;
+
+/// 日志中的一次修改，`D` 为文档记录类型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WalOp<D> {
+    /// 写入文档（已存在的ID跳过）
+    Insert(Vec<D>),
+    /// 删除文档
+    Delete(Vec<String>),
+    /// 标记包版本已处理
+    MarkProcessed(String),
+    /// 清除包版本的已处理标记和进度（文档删除另有 `Delete` 记录）
+    RemovePackageVersions(Vec<String>),
+}
+
+/// 数据目录下的预写日志
+#[derive(Debug, Clone)]
+pub struct WriteAheadLog {
+    dir: PathBuf,
+}
+
+impl WriteAheadLog {
+    pub fn new(dir: &Path) -> Self {
+        Self { dir: dir.to_path_buf() }
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join(WAL_FILE)
+    }
+
+    /// 追加一条记录并刷到磁盘
+    pub fn append<D: Serialize>(&self, op: &WalOp<D>) -> Result<()> {
+        let payload = bincode::serialize(op)?;
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        record.extend_from_slice(&Sha256::digest(&payload));
+        record.extend_from_slice(&payload);
+
+        fs::create_dir_all(&self.dir)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(self.path())?;
+        file.write_all(&record)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// 数据文件保存完成后轮转日志：当前日志成为上一段日志，之后的修改写入新日志
+    pub fn rotate(&self) -> Result<()> {
+        let path = self.path();
+        if path.exists() {
+            fs::rename(&path, self.dir.join(PREVIOUS_WAL_FILE))?;
+        }
+        Ok(())
+    }
+
+    /// 读取需要重放的记录，`include_previous` 为 true 时先读上一段日志
+    pub fn entries<D: DeserializeOwned>(&self, include_previous: bool) -> Vec<WalOp<D>> {
+        let mut entries = Vec::new();
+        if include_previous {
+            entries.extend(read_entries(&self.dir.join(PREVIOUS_WAL_FILE)));
+        }
+        entries.extend(read_entries(&self.path()));
+        entries
+    }
+}
+
+/// 读取日志文件中完整且校验通过的记录，遇到写了一半或损坏的记录时停止
+fn read_entries<D: DeserializeOwned>(path: &Path) -> Vec<WalOp<D>> {
+    let Ok(data) = fs::read(path) else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let Some(header) = data.get(offset..offset + RECORD_HEADER_LEN) else {
+            tracing::warn!("预写日志 {:?} 末尾有不完整的记录，已忽略", path);
+            break;
+        };
+        let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let start = offset + RECORD_HEADER_LEN;
+        let Some(payload) = data.get(start..start + len) else {
+            tracing::warn!("预写日志 {:?} 末尾有不完整的记录，已忽略", path);
+            break;
+        };
+        if Sha256::digest(payload).as_slice() != &header[4..] {
+            tracing::warn!("预写日志 {:?} 在偏移 {} 处校验失败，忽略之后的记录", path, offset);
+            break;
+        }
+        match bincode::deserialize(payload) {
+            Ok(op) => entries.push(op),
+            Err(e) => {
+                tracing::warn!("解析预写日志 {:?} 的记录失败，忽略之后的记录: {}", path, e);
+                break;
+            }
+        }
+        offset = start + len;
+    }
+    entries
+}
+
+fn checksum_path(data_file: &Path) -> PathBuf {
+    let mut name = data_file.as_os_str().to_os_string();
+    name.push(".");
+    name.push(CHECKSUM_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// 上一份数据文件的路径
+pub fn previous_path(data_file: &Path) -> PathBuf {
+    let mut name = data_file.as_os_str().to_os_string();
+    name.push(".");
+    name.push(PREVIOUS_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// 校验数据文件；没有校验和文件（旧版本写入）时返回 None
+pub fn verify_checksum(data_file: &Path) -> Option<bool> {
+    let expected = fs::read_to_string(checksum_path(data_file)).ok()?;
+    let Ok(data) = fs::read(data_file) else {
+        return Some(false);
+    };
+    Some(to_hex(&Sha256::digest(&data)) == expected.trim())
+}
+
+/// 用临时文件 `tmp_file` 中已写好的数据替换数据文件
+///
+/// 先把当前数据文件及其校验和保留为上一份，再写入新校验和并替换数据文件；任何一步中断时，
+/// 数据文件要么校验通过，要么校验失败而上一份可用。
+pub fn commit_data_file(data_file: &Path, tmp_file: &Path, data: &[u8]) -> Result<()> {
+    let previous = previous_path(data_file);
+    if data_file.exists() {
+        // 硬链接不复制数据，文件系统不支持时退回复制
+        let _ = fs::remove_file(&previous);
+        if fs::hard_link(data_file, &previous).is_err() {
+            fs::copy(data_file, &previous)?;
+        }
+        let checksum = checksum_path(data_file);
+        if checksum.exists() {
+            fs::rename(&checksum, checksum_path(&previous))?;
+        } else {
+            let _ = fs::remove_file(checksum_path(&previous));
+        }
+    }
+    let checksum = checksum_path(data_file);
+    let checksum_tmp = checksum.with_extension("sha256.tmp");
+    fs::write(&checksum_tmp, to_hex(&Sha256::digest(data)))?;
+    fs::rename(&checksum_tmp, &checksum)?;
+    fs::rename(tmp_file, data_file)?;
+    Ok(())
+}
+
+/// 选择要加载的数据文件，返回 (路径, 是否为上一份)；都不存在时返回 None
+///
+/// 数据文件校验通过时使用它；校验失败或校验和缺失而上一份可用时改用上一份；
+/// 没有校验和的旧数据且没有上一份时照常使用。
+pub fn consistent_data_file(data_file: &Path) -> Option<(PathBuf, bool)> {
+    let previous = previous_path(data_file);
+    let current_status = verify_checksum(data_file);
+    if data_file.exists() && (current_status == Some(true) || (current_status.is_none() && !previous.exists())) {
+        return Some((data_file.to_path_buf(), false));
+    }
+    if previous.exists() && verify_checksum(&previous) != Some(false) {
+        if data_file.exists() {
+            tracing::warn!("数据文件 {:?} 校验失败，从上一份一致的数据文件恢复", data_file);
+        }
+        return Some((previous, true));
+    }
+    if data_file.exists() {
+        tracing::error!("数据文件 {:?} 校验失败且没有可用的上一份数据，仍尝试加载", data_file);
+        return Some((data_file.to_path_buf(), false));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_torn_tail_is_ignored_and_rotation_keeps_previous_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let wal = WriteAheadLog::new(dir.path());
+        wal.append(&WalOp::Insert(vec!["a".to_string()])).unwrap();
+        wal.append::<String>(&WalOp::MarkProcessed("rust/serde/1.0.0".to_string())).unwrap();
+        // 模拟写到一半时进程退出
+        let mut file = OpenOptions::new().append(true).open(dir.path().join(WAL_FILE)).unwrap();
+        file.write_all(&[9, 0, 0, 0, 1, 2]).unwrap();
+
+        let entries: Vec<WalOp<String>> = wal.entries(false);
+        assert_eq!(entries, vec![WalOp::Insert(vec!["a".to_string()]), WalOp::MarkProcessed("rust/serde/1.0.0".to_string())]);
+
+        wal.rotate().unwrap();
+        wal.append::<String>(&WalOp::Delete(vec!["a".to_string()])).unwrap();
+        assert_eq!(wal.entries::<String>(false), vec![WalOp::Delete(vec!["a".to_string()])]);
+        assert_eq!(wal.entries::<String>(true).len(), 3);
+    }
+
+    #[test]
+    fn test_corrupted_data_file_falls_back_to_previous() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_file = dir.path().join("vector_data.bin");
+        let tmp_file = dir.path().join("vector_data.bin.tmp");
+        assert_eq!(consistent_data_file(&data_file), None);
+
+        // 没有校验和的旧数据照常使用
+        fs::write(&data_file, b"legacy").unwrap();
+        assert_eq!(consistent_data_file(&data_file), Some((data_file.clone(), false)));
+
+        for data in [&b"first"[..], &b"second"[..]] {
+            fs::write(&tmp_file, data).unwrap();
+            commit_data_file(&data_file, &tmp_file, data).unwrap();
+        }
+        assert_eq!(verify_checksum(&data_file), Some(true));
+        assert_eq!(consistent_data_file(&data_file), Some((data_file.clone(), false)));
+
+        fs::write(&data_file, b"sec").unwrap();
+        let previous = previous_path(&data_file);
+        assert_eq!(consistent_data_file(&data_file), Some((previous.clone(), true)));
+        assert_eq!(fs::read(&previous).unwrap(), b"first");
+    }
+}
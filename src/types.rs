@@ -30,6 +30,14 @@ impl Default for Document {
     }
 }
 
+/// 文档列表的一页
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentPage {
+    pub documents: Vec<Document>,
+    /// 还有下一页时的游标
+    pub next_cursor: Option<String>,
+}
+
 /// 文档记录（包含嵌入向量）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentRecord {
@@ -0,0 +1,122 @@
+//! 按 token 数切分长文本
+//!
+//! 嵌入接口对输入长度有上限，整篇长文档提交时会被拒绝或被静默截断，后半部分无法检索。
+//! 这里不依赖具体模型的分词器，用近似规则估算 token：ASCII 单词每 4 个字符计 1 个，
+//! 中日韩等非 ASCII 文字和标点每个字符计 1 个，空白不计。按该估算切成不超过 `max_tokens`
+//! 的块，相邻块重叠 `overlap_tokens`；块的后半段有换行时在换行处断开，尽量不切断段落。
+
+/// ASCII 单词每个 token 的字符数
+const CHARS_PER_TOKEN: usize = 4;
+
+/// 切分出的文本块
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChunk {
+    /// 块序号，从 0 开始
+    pub index: usize,
+    pub text: String,
+    /// 在原文中的字节范围
+    pub start: usize,
+    pub end: usize,
+    /// 估算的 token 数
+    pub tokens: usize,
+}
+
+/// 近似 token 的字节范围
+pub fn token_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    // 当前 ASCII 单词片段：(起始字节, 已含字符数)
+    let mut word: Option<(usize, usize)> = None;
+    for (i, c) in text.char_indices() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            if let Some((_, chars)) = word.as_mut().filter(|(_, chars)| *chars < CHARS_PER_TOKEN) {
+                *chars += 1;
+            } else if let Some((start, _)) = word.replace((i, 1)) {
+                spans.push((start, i));
+            }
+            continue;
+        }
+        if let Some((start, _)) = word.take() {
+            spans.push((start, i));
+        }
+        if !c.is_whitespace() {
+            spans.push((i, i + c.len_utf8()));
+        }
+    }
+    if let Some((start, _)) = word {
+        spans.push((start, text.len()));
+    }
+    spans
+}
+
+/// 估算文本的 token 数
+pub fn count_tokens(text: &str) -> usize {
+    token_spans(text).len()
+}
+
+/// 把文本切成不超过 `max_tokens` 的块；不超过上限的文本返回一个块，空白文本不返回块
+pub fn chunk_by_tokens(text: &str, max_tokens: usize, overlap_tokens: usize) -> Vec<TextChunk> {
+    let spans = token_spans(text);
+    if spans.is_empty() {
+        return Vec::new();
+    }
+    let max_tokens = max_tokens.max(1);
+    if spans.len() <= max_tokens {
+        return vec![TextChunk { index: 0, text: text.to_string(), start: 0, end: text.len(), tokens: spans.len() }];
+    }
+    // 重叠不超过块长的一半，保证每次至少前进半块
+    let overlap_tokens = overlap_tokens.min(max_tokens / 2);
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        let mut end = (start + max_tokens).min(spans.len());
+        if end < spans.len() {
+            // 块的后半段有换行时在最后一个换行处断开
+            let paragraph_break = (start + max_tokens / 2 + 1..end)
+                .rev()
+                .find(|&i| text[spans[i - 1].1..spans[i].0].contains('\n'));
+            if let Some(i) = paragraph_break {
+                end = i;
+            }
+        }
+        let (byte_start, byte_end) = (spans[start].0, spans[end - 1].1);
+        chunks.push(TextChunk {
+            index: chunks.len(),
+            text: text[byte_start..byte_end].to_string(),
+            start: byte_start,
+            end: byte_end,
+            tokens: end - start,
+        });
+        if end == spans.len() {
+            return chunks;
+        }
+        start = end.saturating_sub(overlap_tokens).max(start + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_respect_token_limit_and_overlap() {
+        // "toki" "o" ":" ":" "spaw" "n" 以及 4 个汉字
+        assert_eq!(count_tokens("tokio::spawn 异步任务"), 10);
+        assert!(chunk_by_tokens("  \n ", 8, 2).is_empty());
+        assert_eq!(chunk_by_tokens("short text", 8, 2).len(), 1);
+
+        let paragraph = "word ".repeat(30);
+        let text = format!("{}\n\n{}\n\n{}", paragraph, paragraph, paragraph);
+        let chunks = chunk_by_tokens(&text, 40, 5);
+        assert!(chunks.len() > 2);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.index, i);
+            assert!(chunk.tokens <= 40 && count_tokens(&chunk.text) == chunk.tokens);
+            assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+        }
+        // 在段落边界断开，下一块与上一块重叠
+        assert_eq!(chunks[0].tokens, 30);
+        assert!(chunks[1].start < chunks[0].end);
+        assert_eq!(chunks.last().unwrap().end, text.len());
+    }
+}
@@ -0,0 +1,4 @@
+//! 向量化前的文本预处理与稀疏嵌入
+
+pub mod file_chunker;
+pub mod sparse;
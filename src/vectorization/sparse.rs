@@ -0,0 +1,240 @@
+//! 稀疏嵌入与混合检索的分数融合
+//!
+//! 稠密向量擅长语义相近的查询，对 `tokio::select!`、`HashMap::entry` 这类精确标识符却经常排不到前面。
+//! 稀疏向量按词项加权（SPLADE/BM42 的思路）：[`LexicalSparseEncoder`] 不依赖模型，把标识符整体及其各段
+//! 作为词项，词频做饱和处理，完整标识符加权；词项哈希为 32 位ID。[`SparseIndex`] 是倒排索引，
+//! 查询时乘以 IDF。两路结果用 [`fuse`] 按 RRF 或加权方式合并。需要真正的 SPLADE 模型时实现
+//! [`SparseEncoder`] 替换即可。
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{SparseConfig, SparseFusion};
+
+/// 词频饱和参数（同 BM25 的 k1）
+const TF_SATURATION: f32 = 1.2;
+
+/// 完整标识符（含 `::`、`.`、`_`、`!` 的词项）相对各段的权重
+const COMPOUND_BOOST: f32 = 2.0;
+
+/// 稀疏向量，`indices` 升序且不重复
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SparseVector {
+    pub indices: Vec<u32>,
+    pub values: Vec<f32>,
+}
+
+impl SparseVector {
+    fn from_weights(weights: BTreeMap<u32, f32>) -> Self {
+        let (indices, values) = weights.into_iter().unzip();
+        Self { indices, values }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, f32)> + '_ {
+        self.indices.iter().copied().zip(self.values.iter().copied())
+    }
+}
+
+/// 稀疏编码器
+pub trait SparseEncoder: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// 编码文档
+    fn encode_document(&self, text: &str) -> SparseVector;
+
+    /// 编码查询（默认与文档相同）
+    fn encode_query(&self, text: &str) -> SparseVector {
+        self.encode_document(text)
+    }
+}
+
+/// 按词项加权的词法稀疏编码器
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexicalSparseEncoder;
+
+impl LexicalSparseEncoder {
+    /// 文本中的词项（小写）：完整标识符如 `tokio::select!` 及其各段 `tokio`、`select`，非 ASCII 文字逐字
+    pub fn terms(text: &str) -> Vec<(String, bool)> {
+        let mut terms = Vec::new();
+        let mut current = String::new();
+        let flush = |current: &mut String, terms: &mut Vec<(String, bool)>| {
+            let word = current.trim_matches(|c| c == ':' || c == '.').to_lowercase();
+            current.clear();
+            if word.is_empty() {
+                return;
+            }
+            let parts: Vec<&str> = word.split(|c: char| !c.is_ascii_alphanumeric()).filter(|p| !p.is_empty()).collect();
+            if parts.len() > 1 || word.ends_with('!') {
+                terms.push((word.clone(), true));
+            }
+            terms.extend(parts.into_iter().map(|part| (part.to_string(), false)));
+        };
+        for c in text.chars() {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '.' | '!') {
+                current.push(c);
+            } else {
+                flush(&mut current, &mut terms);
+                if !c.is_ascii() && c.is_alphanumeric() {
+                    terms.push((c.to_string(), false));
+                }
+            }
+        }
+        flush(&mut current, &mut terms);
+        terms
+    }
+
+    fn weights(text: &str, saturate: bool) -> SparseVector {
+        let mut counts: HashMap<u32, (f32, bool)> = HashMap::new();
+        for (term, compound) in Self::terms(text) {
+            let entry = counts.entry(term_id(&term)).or_insert((0.0, compound));
+            entry.0 += 1.0;
+        }
+        let weights = counts.into_iter()
+            .map(|(id, (tf, compound))| {
+                let weight = if saturate { tf * (TF_SATURATION + 1.0) / (tf + TF_SATURATION) } else { 1.0 };
+                (id, if compound { weight * COMPOUND_BOOST } else { weight })
+            })
+            .collect();
+        SparseVector::from_weights(weights)
+    }
+}
+
+impl SparseEncoder for LexicalSparseEncoder {
+    fn name(&self) -> &str {
+        "lexical"
+    }
+
+    fn encode_document(&self, text: &str) -> SparseVector {
+        Self::weights(text, true)
+    }
+
+    /// 查询中重复的词不额外加权
+    fn encode_query(&self, text: &str) -> SparseVector {
+        Self::weights(text, false)
+    }
+}
+
+/// 词项的 32 位ID（FNV-1a），跨平台稳定
+fn term_id(term: &str) -> u32 {
+    term.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193))
+}
+
+/// 稀疏向量的倒排索引
+#[derive(Debug, Default)]
+pub struct SparseIndex {
+    doc_ids: Vec<String>,
+    postings: HashMap<u32, Vec<(usize, f32)>>,
+}
+
+impl SparseIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.doc_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.doc_ids.is_empty()
+    }
+
+    pub fn insert(&mut self, doc_id: String, vector: &SparseVector) {
+        let slot = self.doc_ids.len();
+        self.doc_ids.push(doc_id);
+        for (term, weight) in vector.iter() {
+            self.postings.entry(term).or_default().push((slot, weight));
+        }
+    }
+
+    /// 按 `Σ 查询权重 × IDF × 文档权重` 打分，返回分数最高的 `limit` 个文档
+    pub fn search(&self, query: &SparseVector, limit: usize) -> Vec<(String, f32)> {
+        let n = self.doc_ids.len() as f32;
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        for (term, query_weight) in query.iter() {
+            let Some(postings) = self.postings.get(&term) else { continue };
+            let df = postings.len() as f32;
+            let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+            for &(slot, weight) in postings {
+                *scores.entry(slot).or_insert(0.0) += query_weight * idf * weight;
+            }
+        }
+        let mut ranked: Vec<(usize, f32)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+        ranked.into_iter().take(limit).map(|(slot, score)| (self.doc_ids[slot].clone(), score)).collect()
+    }
+}
+
+/// 融合稠密和稀疏两路结果（均按分数降序），返回 0-1 的融合分数，降序排列
+///
+/// RRF 的分数除以两路都排第一时的最大值；加权融合时稀疏分数按本次最高分归一化，稠密分数应已在 0-1 之间。
+pub fn fuse(dense: &[(String, f32)], sparse: &[(String, f32)], config: &SparseConfig) -> Vec<(String, f32)> {
+    let dense_weight = config.dense_weight.clamp(0.0, 1.0);
+    let sparse_weight = 1.0 - dense_weight;
+    let mut fused: HashMap<&str, f32> = HashMap::new();
+    match config.fusion {
+        SparseFusion::Rrf => {
+            let k = config.rrf_k.max(0.0);
+            for (results, weight) in [(dense, dense_weight), (sparse, sparse_weight)] {
+                for (rank, (id, _)) in results.iter().enumerate() {
+                    *fused.entry(id.as_str()).or_insert(0.0) += weight * (k + 1.0) / (k + rank as f32 + 1.0);
+                }
+            }
+        }
+        SparseFusion::Weighted => {
+            let max_sparse = sparse.iter().map(|(_, score)| *score).fold(0.0f32, f32::max);
+            for (id, score) in dense {
+                *fused.entry(id.as_str()).or_insert(0.0) += dense_weight * score.clamp(0.0, 1.0);
+            }
+            if max_sparse > 0.0 {
+                for (id, score) in sparse {
+                    *fused.entry(id.as_str()).or_insert(0.0) += sparse_weight * score / max_sparse;
+                }
+            }
+        }
+    }
+    let mut fused: Vec<(String, f32)> = fused.into_iter().map(|(id, score)| (id.to_string(), score)).collect();
+    fused.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_terms_and_sparse_ranking() {
+        let terms: Vec<String> = LexicalSparseEncoder::terms("use tokio::select! 宏").into_iter().map(|(term, _)| term).collect();
+        assert_eq!(terms, vec!["use", "tokio::select!", "tokio", "select", "宏"]);
+
+        let encoder = LexicalSparseEncoder;
+        let mut index = SparseIndex::new();
+        index.insert("select".to_string(), &encoder.encode_document("The tokio::select! macro waits on multiple futures"));
+        index.insert("spawn".to_string(), &encoder.encode_document("tokio::spawn runs a future on the tokio runtime"));
+        index.insert("std".to_string(), &encoder.encode_document("std::thread::spawn starts an OS thread"));
+        let hits = index.search(&encoder.encode_query("tokio::select!"), 3);
+        assert_eq!(hits[0].0, "select");
+        assert!(hits.iter().all(|(id, _)| id != "std"));
+    }
+
+    #[test]
+    fn test_fusion_promotes_documents_found_by_both() {
+        let dense = vec![("a".to_string(), 0.9), ("b".to_string(), 0.8), ("c".to_string(), 0.7)];
+        let sparse = vec![("c".to_string(), 12.0), ("d".to_string(), 3.0)];
+
+        let rrf = fuse(&dense, &sparse, &SparseConfig::default());
+        assert_eq!(rrf[0].0, "c");
+        assert!(rrf.iter().all(|(_, score)| *score > 0.0 && *score <= 1.0));
+
+        let config = SparseConfig { fusion: SparseFusion::Weighted, dense_weight: 0.5, ..Default::default() };
+        let weighted = fuse(&dense, &sparse, &config);
+        assert_eq!(weighted[0].0, "c");
+        assert!((weighted[0].1 - 0.85).abs() < 1e-6);
+        assert_eq!(weighted.len(), 4);
+    }
+}
@@ -11,6 +11,13 @@ pub trait VersionChecker: Send + Sync {
     /// 获取包的版本信息
     async fn check_version(&self, package: &Package) -> Result<VersionInfo>;
     
+    /// 获取校验过的规范化版本信息
+    async fn normalized_version(&self, package: &Package) -> Result<VersionInfo> {
+        let info = self.check_version(package).await?;
+        info.validate()?;
+        Ok(info)
+    }
+    
     /// 获取包的所有可用版本
     async fn list_versions(&self, package: &Package) -> Result<Vec<String>>;
     
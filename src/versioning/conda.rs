@@ -0,0 +1,305 @@
+//! Conda 频道（anaconda.org）支持
+//!
+//! 数据科学项目常用 conda 而不是 pip 安装依赖，同一个包在 conda-forge 上的版本可能落后于 PyPI，
+//! 也可能只在某个频道发布。这里按频道列表查询 anaconda.org 的包接口 `/package/{channel}/{name}`，
+//! 并把各频道的版本与 PyPI 的版本合并，标出每个版本来自哪些来源。
+//!
+//! 频道列表默认只有 conda-forge，可用 `CONDA_CHANNELS`（逗号分隔，按优先级排列）覆盖；
+//! `defaults` 对应 anaconda.org 上的 `anaconda` 频道。
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use crate::versioning::fetcher::RegistryFetcher;
+
+/// anaconda.org API 地址
+pub const ANACONDA_API_URL: &str = "https://api.anaconda.org";
+
+/// 未配置 `CONDA_CHANNELS` 时查询的频道
+pub const DEFAULT_CONDA_CHANNELS: &[&str] = &["conda-forge"];
+
+/// 合并结果中 PyPI 来源的名称
+pub const PYPI_SOURCE: &str = "pypi";
+
+/// 频道中的一个版本
+#[derive(Debug, Clone, Serialize)]
+pub struct CondaVersion {
+    pub version: String,
+    /// 该版本最早上传的文件时间
+    pub uploaded: Option<DateTime<Utc>>,
+    /// 提供构建的平台子目录（`noarch`、`linux-64`、`osx-arm64` 等）
+    pub subdirs: BTreeSet<String>,
+}
+
+/// 一个频道中的包
+#[derive(Debug, Clone, Serialize)]
+pub struct CondaPackage {
+    pub channel: String,
+    pub name: String,
+    /// 从新到旧
+    pub versions: Vec<CondaVersion>,
+    pub latest_version: Option<String>,
+    pub home: Option<String>,
+    pub dev_url: Option<String>,
+}
+
+impl CondaPackage {
+    fn from_response(channel: &str, name: &str, body: &Value) -> Self {
+        let mut versions: BTreeMap<String, CondaVersion> = body["versions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .map(|v| (v.to_string(), CondaVersion { version: v.to_string(), uploaded: None, subdirs: BTreeSet::new() }))
+            .collect();
+        for file in body["files"].as_array().into_iter().flatten() {
+            let Some(version) = file["version"].as_str() else { continue };
+            let entry = versions.entry(version.to_string()).or_insert_with(|| CondaVersion {
+                version: version.to_string(),
+                uploaded: None,
+                subdirs: BTreeSet::new(),
+            });
+            if let Some(subdir) = file["attrs"]["subdir"].as_str() {
+                entry.subdirs.insert(subdir.to_string());
+            }
+            if let Some(uploaded) = file["upload_time"].as_str().and_then(parse_upload_time) {
+                entry.uploaded = Some(entry.uploaded.map_or(uploaded, |current| current.min(uploaded)));
+            }
+        }
+        let mut versions: Vec<CondaVersion> = versions.into_values().collect();
+        versions.sort_by(|a, b| compare_versions(&b.version, &a.version));
+
+        let text = |key: &str| body[key].as_str().filter(|s| !s.is_empty()).map(String::from);
+        Self {
+            channel: channel.to_string(),
+            name: body["name"].as_str().unwrap_or(name).to_string(),
+            latest_version: text("latest_version").or_else(|| versions.first().map(|v| v.version.clone())),
+            home: text("home"),
+            dev_url: text("dev_url"),
+            versions,
+        }
+    }
+
+    pub fn latest(&self) -> Option<&CondaVersion> {
+        match &self.latest_version {
+            Some(latest) => self.versions.iter().find(|v| &v.version == latest),
+            None => self.versions.first(),
+        }
+    }
+}
+
+/// 合并后的一个版本及其来源（`pypi` 或频道名，频道按配置顺序排列）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VersionSource {
+    pub version: String,
+    pub sources: Vec<String>,
+}
+
+/// anaconda.org 的上传时间形如 `2024-03-01 12:00:00.123000+00:00`
+fn parse_upload_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%:z")
+        .or_else(|_| DateTime::parse_from_rfc3339(value))
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").ok().map(|dt| dt.and_utc()))
+}
+
+/// PyPI 包名对应的 conda 包名：conda 包名全部小写，下划线写作连字符（`PyYAML` -> `pyyaml`）
+pub fn conda_package_name(pypi_name: &str) -> String {
+    pypi_name.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+/// 频道名在 anaconda.org 上的所有者名
+fn channel_owner(channel: &str) -> &str {
+    match channel {
+        "defaults" | "main" => "anaconda",
+        other => other,
+    }
+}
+
+/// 比较 conda/PEP 440 风格的版本号：按 `.`、`-`、`_` 分段，每段先比开头的数字再比后缀，
+/// 没有后缀的段大于有后缀的段（`1.0` > `1.0rc1`），后缀 `post` 除外
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn segment(part: &str) -> (Option<u64>, &str) {
+        let end = part.find(|c: char| !c.is_ascii_digit()).unwrap_or(part.len());
+        (part[..end].parse().ok(), &part[end..])
+    }
+    fn suffix_rank(suffix: &str) -> u8 {
+        match suffix {
+            "" => 1,
+            s if s.starts_with("post") => 2,
+            _ => 0,
+        }
+    }
+    let split = |v: &str| v.trim_start_matches('v').split(['.', '-', '_']).map(String::from).collect::<Vec<_>>();
+    let (a, b) = (split(a), split(b));
+    for i in 0..a.len().max(b.len()) {
+        let (a_number, a_suffix) = a.get(i).map_or((Some(0), ""), |p| segment(p));
+        let (b_number, b_suffix) = b.get(i).map_or((Some(0), ""), |p| segment(p));
+        let ordering = a_number
+            .cmp(&b_number)
+            .then(suffix_rank(a_suffix).cmp(&suffix_rank(b_suffix)))
+            .then_with(|| a_suffix.cmp(b_suffix));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// 合并 PyPI 与各频道的版本，从新到旧排列
+pub fn merge_version_sources(pypi_versions: &[String], channels: &[CondaPackage]) -> Vec<VersionSource> {
+    let mut merged: Vec<VersionSource> = Vec::new();
+    let sources = pypi_versions
+        .iter()
+        .map(|version| (version.as_str(), PYPI_SOURCE))
+        .chain(channels.iter().flat_map(|package| {
+            package.versions.iter().map(move |v| (v.version.as_str(), package.channel.as_str()))
+        }));
+    for (version, source) in sources {
+        // 同一版本在不同来源中的写法可能不同（`1.0` 与 `1.0.0`），按比较结果归并
+        match merged.iter_mut().find(|m| compare_versions(&m.version, version) == Ordering::Equal) {
+            Some(existing) => {
+                if !existing.sources.iter().any(|s| s == source) {
+                    existing.sources.push(source.to_string());
+                }
+            }
+            None => merged.push(VersionSource { version: version.to_string(), sources: vec![source.to_string()] }),
+        }
+    }
+    merged.sort_by(|a, b| compare_versions(&b.version, &a.version));
+    merged
+}
+
+/// anaconda.org 频道客户端
+pub struct CondaClient {
+    fetcher: Arc<dyn RegistryFetcher>,
+    base_url: String,
+    channels: Vec<String>,
+}
+
+impl CondaClient {
+    pub fn new(fetcher: Arc<dyn RegistryFetcher>) -> Self {
+        Self {
+            fetcher,
+            base_url: ANACONDA_API_URL.to_string(),
+            channels: DEFAULT_CONDA_CHANNELS.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// 频道列表取自 `CONDA_CHANNELS`，未设置时为 conda-forge
+    pub fn from_env(fetcher: Arc<dyn RegistryFetcher>) -> Self {
+        let client = Self::new(fetcher);
+        match std::env::var("CONDA_CHANNELS") {
+            Ok(value) if !value.trim().is_empty() => {
+                client.with_channels(value.split(',').map(|c| c.trim().to_string()).collect())
+            }
+            _ => client,
+        }
+    }
+
+    /// 按优先级排列的频道列表
+    pub fn with_channels(mut self, channels: Vec<String>) -> Self {
+        self.channels = channels.into_iter().filter(|c| !c.is_empty()).collect();
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    /// 查询单个频道中的包
+    pub async fn fetch_channel(&self, channel: &str, name: &str) -> Result<CondaPackage> {
+        let name = conda_package_name(name);
+        let url = format!("{}/package/{}/{}", self.base_url, channel_owner(channel), name);
+        let response = self.fetcher.get_json(&url).await?;
+        if !response.is_success() {
+            return Err(anyhow!("anaconda.org 返回 {}: {}/{}", response.status, channel, name));
+        }
+        Ok(CondaPackage::from_response(channel, &name, &response.body))
+    }
+
+    /// 并发查询所有频道，按频道顺序返回包含该包的频道；单个频道失败时跳过
+    pub async fn fetch_all(&self, name: &str) -> Vec<CondaPackage> {
+        let queries = self.channels.iter().map(|channel| async move { (channel, self.fetch_channel(channel, name).await) });
+        futures::future::join_all(queries)
+            .await
+            .into_iter()
+            .filter_map(|(channel, outcome)| match outcome {
+                Ok(package) if !package.versions.is_empty() => Some(package),
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::debug!("查询conda频道 {} 失败: {}", channel, e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::fetcher::testing::MockRegistry;
+    use serde_json::json;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0", "2.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0.0", "2.0.0rc1"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0.0.post1", "2.0.0"), Ordering::Greater);
+        assert_eq!(conda_package_name("PyYAML"), "pyyaml");
+        assert_eq!(conda_package_name("typing_extensions"), "typing-extensions");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_channels_and_merge_with_pypi() {
+        let registry = Arc::new(
+            MockRegistry::new()
+                .with_json(
+                    "https://api.anaconda.org/package/conda-forge/numpy",
+                    json!({
+                        "name": "numpy",
+                        "latest_version": "1.26.4",
+                        "versions": ["1.26.3", "1.26.4"],
+                        "home": "https://numpy.org",
+                        "files": [
+                            { "version": "1.26.4", "upload_time": "2024-02-06 01:00:00.000000+00:00", "attrs": { "subdir": "linux-64" } },
+                            { "version": "1.26.4", "upload_time": "2024-02-05 23:00:00.000000+00:00", "attrs": { "subdir": "osx-arm64" } },
+                        ],
+                    }),
+                )
+                .with_json(
+                    "https://api.anaconda.org/package/anaconda/numpy",
+                    json!({ "name": "numpy", "latest_version": "1.26.3", "versions": ["1.26.3"] }),
+                ),
+        );
+        let client = CondaClient::new(registry.clone())
+            .with_channels(vec!["conda-forge".into(), "defaults".into(), "bioconda".into()]);
+        let channels = client.fetch_all("NumPy").await;
+
+        // bioconda 没有该包（404），被跳过
+        assert_eq!(channels.iter().map(|c| c.channel.as_str()).collect::<Vec<_>>(), vec!["conda-forge", "defaults"]);
+        let latest = channels[0].latest().unwrap();
+        assert_eq!(latest.subdirs.iter().map(String::as_str).collect::<Vec<_>>(), vec!["linux-64", "osx-arm64"]);
+        assert_eq!(latest.uploaded.unwrap().to_rfc3339(), "2024-02-05T23:00:00+00:00");
+
+        let pypi = vec!["2.0.0".to_string(), "1.26.4".to_string(), "1.26.3".to_string()];
+        let merged = merge_version_sources(&pypi, &channels);
+        assert_eq!(merged[0], VersionSource { version: "2.0.0".into(), sources: vec!["pypi".into()] });
+        assert_eq!(merged[1].sources, vec!["pypi", "conda-forge"]);
+        assert_eq!(merged[2].sources, vec!["pypi", "conda-forge", "defaults"]);
+        assert_eq!(registry.requests().len(), 3);
+    }
+}
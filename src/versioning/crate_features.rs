@@ -0,0 +1,177 @@
+//! crates.io feature 元数据
+//!
+//! 从 crates.io 版本信息中提取 feature 列表和默认 feature，并把可选依赖形成的
+//! 隐式 feature 补全，便于回答“reqwest 0.12 的哪些 feature 会启用 TLS”这类问题。
+
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use crate::mcp::correlation::Correlated;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+const CRATES_IO_API: &str = "https://crates.io/api/v1";
+
+/// 某个 crate 版本的 feature 信息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CrateFeatures {
+    pub version: String,
+    /// `default` feature 直接启用的项
+    pub default_features: Vec<String>,
+    /// feature 名 -> 启用的 feature/依赖（`dep:xxx`、`xxx/feature`、`xxx?/feature`）
+    pub features: BTreeMap<String, Vec<String>>,
+    /// 可选依赖（未通过 `dep:` 引用的会形成同名隐式 feature）
+    pub optional_dependencies: Vec<String>,
+}
+
+impl CrateFeatures {
+    /// 由 crates.io 的版本对象和可选依赖列表构建
+    pub fn from_version(version: &Value, optional_dependencies: Vec<String>) -> Self {
+        let mut features: BTreeMap<String, Vec<String>> = version["features"]
+            .as_object()
+            .map(|map| {
+                map.iter()
+                    .map(|(name, items)| {
+                        let items = items.as_array()
+                            .map(|items| items.iter().filter_map(|i| i.as_str().map(String::from)).collect())
+                            .unwrap_or_default();
+                        (name.clone(), items)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // 没有被 `dep:` 显式引用的可选依赖会生成同名隐式 feature
+        let explicit_deps: BTreeSet<&str> = features.values()
+            .flatten()
+            .filter_map(|item| item.strip_prefix("dep:"))
+            .collect();
+        let implicit: Vec<String> = optional_dependencies.iter()
+            .filter(|dep| !explicit_deps.contains(dep.as_str()) && !features.contains_key(dep.as_str()))
+            .cloned()
+            .collect();
+        for dep in implicit {
+            features.insert(dep.clone(), vec![format!("dep:{}", dep)]);
+        }
+
+        Self {
+            version: version["num"].as_str().unwrap_or_default().to_string(),
+            default_features: features.get("default").cloned().unwrap_or_default(),
+            features,
+            optional_dependencies,
+        }
+    }
+
+    /// 递归展开某个 feature 启用的所有 feature 和依赖
+    pub fn expand(&self, feature: &str) -> BTreeSet<String> {
+        let mut enabled = BTreeSet::new();
+        let mut stack = vec![feature.to_string()];
+        while let Some(current) = stack.pop() {
+            if !enabled.insert(current.clone()) {
+                continue;
+            }
+            for item in self.features.get(&current).into_iter().flatten() {
+                if self.features.contains_key(item) {
+                    stack.push(item.clone());
+                } else {
+                    enabled.insert(item.clone());
+                }
+            }
+        }
+        enabled.remove(feature);
+        enabled
+    }
+
+    /// 默认启用的全部 feature（展开后）
+    pub fn enabled_by_default(&self) -> BTreeSet<String> {
+        if self.features.contains_key("default") {
+            self.expand("default")
+        } else {
+            BTreeSet::new()
+        }
+    }
+
+    /// 查找名称或（展开后）启用项包含关键字的 feature，例如 `tls`
+    pub fn features_matching(&self, keyword: &str) -> Vec<String> {
+        let keyword = keyword.to_lowercase();
+        self.features.keys()
+            .filter(|name| {
+                name.to_lowercase().contains(&keyword)
+                    || self.expand(name).iter().any(|item| item.to_lowercase().contains(&keyword))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// 从 crates.io 获取指定版本的 feature 信息
+pub async fn fetch_crate_features(client: &Client, name: &str, version: &str) -> Result<CrateFeatures> {
+    let version_url = format!("{}/crates/{}/{}", CRATES_IO_API, name, version);
+    let response = client.get(&version_url).correlated().send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("未找到 {} {}: HTTP {}", name, version, response.status()));
+    }
+    let data: Value = response.json().await?;
+
+    let deps_url = format!("{}/crates/{}/{}/dependencies", CRATES_IO_API, name, version);
+    let deps: Value = client.get(&deps_url).correlated().send().await?.json().await.unwrap_or(Value::Null);
+    Ok(CrateFeatures::from_version(&data["version"], optional_dependencies(&deps)))
+}
+
+/// 提取 `/dependencies` 响应中的可选依赖名
+pub fn optional_dependencies(dependencies: &Value) -> Vec<String> {
+    dependencies["dependencies"]
+        .as_array()
+        .map(|deps| {
+            deps.iter()
+                .filter(|d| d["optional"].as_bool().unwrap_or(false) && d["kind"].as_str() != Some("dev"))
+                .filter_map(|d| d["crate_id"].as_str().map(String::from))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn reqwest_like() -> CrateFeatures {
+        let version = json!({
+            "num": "0.12.4",
+            "features": {
+                "default": ["default-tls", "charset"],
+                "default-tls": ["dep:hyper-tls", "dep:native-tls-crate", "__tls"],
+                "rustls-tls": ["rustls-tls-webpki-roots"],
+                "rustls-tls-webpki-roots": ["dep:webpki-roots", "__rustls"],
+                "__rustls": ["dep:rustls", "__tls"],
+                "__tls": [],
+                "charset": ["dep:encoding_rs"],
+            }
+        });
+        CrateFeatures::from_version(&version, vec!["cookie_store".to_string(), "rustls".to_string(), "webpki-roots".to_string()])
+    }
+
+    #[test]
+    fn test_default_features_and_implicit_optional_deps() {
+        let features = reqwest_like();
+        assert_eq!(features.version, "0.12.4");
+        assert_eq!(features.default_features, vec!["default-tls", "charset"]);
+        // cookie_store 未被 dep: 引用，形成隐式 feature
+        assert_eq!(features.features.get("cookie_store"), Some(&vec!["dep:cookie_store".to_string()]));
+        assert!(!features.features.contains_key("rustls"));
+        assert!(features.enabled_by_default().contains("dep:hyper-tls"));
+    }
+
+    #[test]
+    fn test_features_matching_tls() {
+        let features = reqwest_like();
+        let tls = features.features_matching("tls");
+        assert!(tls.contains(&"rustls-tls".to_string()));
+        assert!(tls.contains(&"default".to_string()));
+        assert!(!tls.contains(&"charset".to_string()));
+        assert!(features.expand("rustls-tls").contains("dep:rustls"));
+    }
+}
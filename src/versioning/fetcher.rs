@@ -0,0 +1,97 @@
+//! 注册表 HTTP 访问边界
+//!
+//! 版本检查工具通过 [`RegistryFetcher`] 读取注册表的 JSON 接口，默认实现
+//! [`HttpRegistryFetcher`] 使用 reqwest；测试中用 [`testing::MockRegistry`] 按 URL
+//! 返回预置响应，不访问网络。
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::mcp::correlation::Correlated;
+
+/// 注册表响应：状态码和 JSON 响应体（响应体不是 JSON 时为 `Null`）
+#[derive(Debug, Clone)]
+pub struct JsonResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl JsonResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// 读取注册表 JSON 接口
+#[async_trait]
+pub trait RegistryFetcher: Send + Sync {
+    /// GET 指定地址；网络错误返回 Err，HTTP 错误状态通过 [`JsonResponse::status`] 返回
+    async fn get_json(&self, url: &str) -> Result<JsonResponse>;
+}
+
+/// 基于 reqwest 的实现
+pub struct HttpRegistryFetcher {
+    client: Client,
+}
+
+impl HttpRegistryFetcher {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl RegistryFetcher for HttpRegistryFetcher {
+    async fn get_json(&self, url: &str) -> Result<JsonResponse> {
+        let response = self.client.get(url).correlated().send().await?;
+        let status = response.status().as_u16();
+        let body = response.json::<Value>().await.unwrap_or(Value::Null);
+        Ok(JsonResponse { status, body })
+    }
+}
+
+/// 测试替身
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// 按 URL 返回预置响应的注册表，未预置的地址返回 404
+    #[derive(Default)]
+    pub struct MockRegistry {
+        routes: HashMap<String, JsonResponse>,
+        requests: Mutex<Vec<String>>,
+    }
+
+    impl MockRegistry {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// 预置 200 响应
+        pub fn with_json(self, url: &str, body: Value) -> Self {
+            self.with_status(url, 200, body)
+        }
+
+        pub fn with_status(mut self, url: &str, status: u16, body: Value) -> Self {
+            self.routes.insert(url.to_string(), JsonResponse { status, body });
+            self
+        }
+
+        /// 按顺序记录的请求地址
+        pub fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl RegistryFetcher for MockRegistry {
+        async fn get_json(&self, url: &str) -> Result<JsonResponse> {
+            self.requests.lock().unwrap().push(url.to_string());
+            Ok(self.routes.get(url).cloned().unwrap_or(JsonResponse { status: 404, body: Value::Null }))
+        }
+    }
+}
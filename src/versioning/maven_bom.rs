@@ -0,0 +1,490 @@
+//! Maven BOM / dependencyManagement 解析
+//!
+//! 项目通过 `<dependencyManagement>` 导入 BOM（如 Spring Boot BOM）或继承父 POM 时，
+//! 依赖的实际版本由 BOM 决定，而不是仓库中的最新版本。这里解析 BOM 链，
+//! 计算项目实际会得到的“生效版本”。
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use reqwest::Client;
+use crate::mcp::correlation::Correlated;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const DEFAULT_MAVEN_REPOSITORY: &str = "https://repo1.maven.org/maven2";
+
+/// BOM 导入与父 POM 的最大嵌套深度
+const MAX_BOM_DEPTH: usize = 8;
+
+/// Maven 坐标 `groupId:artifactId:version`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MavenCoordinate {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: String,
+}
+
+impl MavenCoordinate {
+    pub fn parse(coordinate: &str) -> Option<Self> {
+        let mut parts = coordinate.trim().split(':');
+        let (group_id, artifact_id, version) = (parts.next()?, parts.next()?, parts.next()?);
+        if group_id.is_empty() || artifact_id.is_empty() || version.is_empty() || parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            group_id: group_id.to_string(),
+            artifact_id: artifact_id.to_string(),
+            version: version.to_string(),
+        })
+    }
+
+    /// 不含版本的 `groupId:artifactId`
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.group_id, self.artifact_id)
+    }
+
+    fn pom_path(&self) -> String {
+        format!(
+            "{}/{}/{}/{}-{}.pom",
+            self.group_id.replace('.', "/"),
+            self.artifact_id,
+            self.version,
+            self.artifact_id,
+            self.version
+        )
+    }
+}
+
+impl std::fmt::Display for MavenCoordinate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.group_id, self.artifact_id, self.version)
+    }
+}
+
+/// `<dependencyManagement>` 中的一项
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagedEntry {
+    pub group_id: String,
+    pub artifact_id: String,
+    /// 未插值的原始版本，例如 `${jackson.version}`
+    pub version: String,
+    /// `<type>pom</type><scope>import</scope>` 表示导入BOM
+    pub is_import: bool,
+}
+
+/// 解析后的 POM 模型（只保留版本管理相关部分）
+#[derive(Debug, Clone, Default)]
+pub struct PomModel {
+    pub group_id: Option<String>,
+    pub artifact_id: Option<String>,
+    pub version: Option<String>,
+    pub parent: Option<MavenCoordinate>,
+    pub properties: HashMap<String, String>,
+    pub managed: Vec<ManagedEntry>,
+}
+
+/// 某个依赖被 BOM 管理的版本
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagedVersion {
+    pub version: String,
+    /// 定义该版本的 BOM（本地 pom.xml 中直接声明时为 `project`）
+    pub managed_by: String,
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.tag_name().name() == name)
+        .and_then(|c| c.text())
+        .map(str::trim)
+}
+
+/// 解析 POM 文本
+pub fn parse_pom(content: &str) -> Result<PomModel> {
+    let doc = roxmltree::Document::parse(content)?;
+    let project = doc.root_element();
+    let mut model = PomModel {
+        group_id: child_text(project, "groupId").map(String::from),
+        artifact_id: child_text(project, "artifactId").map(String::from),
+        version: child_text(project, "version").map(String::from),
+        ..Default::default()
+    };
+
+    for child in project.children().filter(|c| c.is_element()) {
+        match child.tag_name().name() {
+            "parent" => {
+                model.parent = match (child_text(child, "groupId"), child_text(child, "artifactId"), child_text(child, "version")) {
+                    (Some(g), Some(a), Some(v)) => Some(MavenCoordinate {
+                        group_id: g.to_string(),
+                        artifact_id: a.to_string(),
+                        version: v.to_string(),
+                    }),
+                    _ => None,
+                };
+            }
+            "properties" => {
+                for property in child.children().filter(|c| c.is_element()) {
+                    model.properties.insert(
+                        property.tag_name().name().to_string(),
+                        property.text().unwrap_or("").trim().to_string(),
+                    );
+                }
+            }
+            "dependencyManagement" => {
+                let dependencies = child.children().find(|c| c.tag_name().name() == "dependencies");
+                for dependency in dependencies.into_iter().flat_map(|d| d.children()).filter(|c| c.tag_name().name() == "dependency") {
+                    let (Some(group_id), Some(artifact_id)) = (child_text(dependency, "groupId"), child_text(dependency, "artifactId")) else {
+                        continue;
+                    };
+                    model.managed.push(ManagedEntry {
+                        group_id: group_id.to_string(),
+                        artifact_id: artifact_id.to_string(),
+                        version: child_text(dependency, "version").unwrap_or("").to_string(),
+                        is_import: child_text(dependency, "type") == Some("pom") && child_text(dependency, "scope") == Some("import"),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 子模块可省略 groupId/version，从父 POM 继承
+    if let Some(parent) = &model.parent {
+        model.group_id.get_or_insert_with(|| parent.group_id.clone());
+        model.version.get_or_insert_with(|| parent.version.clone());
+    }
+    Ok(model)
+}
+
+/// 替换 `${property}` 占位符，未知属性保留原样
+pub fn interpolate(value: &str, properties: &HashMap<String, String>) -> String {
+    let mut result = value.to_string();
+    // 属性可能引用其他属性，多轮替换
+    for _ in 0..5 {
+        let mut replaced = String::with_capacity(result.len());
+        let mut rest = result.as_str();
+        let mut changed = false;
+        while let Some(start) = rest.find("${") {
+            let Some(end) = rest[start..].find('}') else { break };
+            let name = &rest[start + 2..start + end];
+            replaced.push_str(&rest[..start]);
+            match properties.get(name) {
+                Some(v) => {
+                    replaced.push_str(v);
+                    changed = true;
+                }
+                None => replaced.push_str(&rest[start..start + end + 1]),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        replaced.push_str(rest);
+        result = replaced;
+        if !changed {
+            break;
+        }
+    }
+    result
+}
+
+/// 项目中声明的版本管理来源
+#[derive(Debug, Clone, Default)]
+pub struct ProjectManagement {
+    /// 导入的 BOM 与父 POM，按声明顺序（先声明者优先）
+    pub boms: Vec<MavenCoordinate>,
+    /// 项目自身 `<dependencyManagement>` 中直接声明的版本
+    pub local: HashMap<String, String>,
+}
+
+/// 从项目根目录的 pom.xml / build.gradle(.kts) 检测 BOM
+pub fn detect_project_management(root: &Path) -> ProjectManagement {
+    let mut management = ProjectManagement::default();
+
+    if let Some(model) = std::fs::read_to_string(root.join("pom.xml")).ok().and_then(|c| parse_pom(&c).ok()) {
+        let properties = project_properties(&model);
+        for entry in &model.managed {
+            let coordinate = MavenCoordinate {
+                group_id: entry.group_id.clone(),
+                artifact_id: entry.artifact_id.clone(),
+                version: interpolate(&entry.version, &properties),
+            };
+            if entry.is_import {
+                management.boms.push(coordinate);
+            } else if !coordinate.version.is_empty() {
+                management.local.insert(coordinate.key(), coordinate.version);
+            }
+        }
+        // 父 POM（如 spring-boot-starter-parent）的版本管理优先级低于本项目的导入
+        if let Some(parent) = model.parent {
+            management.boms.push(parent);
+        }
+    }
+
+    for file in ["build.gradle", "build.gradle.kts"] {
+        if let Ok(content) = std::fs::read_to_string(root.join(file)) {
+            management.boms.extend(gradle_boms(&content));
+        }
+    }
+    management
+}
+
+fn project_properties(model: &PomModel) -> HashMap<String, String> {
+    let mut properties = model.properties.clone();
+    if let Some(version) = &model.version {
+        properties.insert("project.version".to_string(), version.clone());
+    }
+    if let Some(group_id) = &model.group_id {
+        properties.insert("project.groupId".to_string(), group_id.clone());
+    }
+    if let Some(parent) = &model.parent {
+        properties.insert("project.parent.version".to_string(), parent.version.clone());
+    }
+    properties
+}
+
+/// 识别 Gradle 中的 `platform(...)`、`enforcedPlatform(...)`、`mavenBom` 和 Spring Boot 插件
+pub fn gradle_boms(content: &str) -> Vec<MavenCoordinate> {
+    let patterns = [
+        r#"(?:enforcedPlatform|platform)\s*\(\s*["']([^"']+)["']"#,
+        r#"mavenBom\s*\(?\s*["']([^"']+)["']"#,
+    ];
+    let mut boms: Vec<MavenCoordinate> = patterns
+        .iter()
+        .filter_map(|p| Regex::new(p).ok())
+        .flat_map(|re| {
+            re.captures_iter(content)
+                .filter_map(|c| MavenCoordinate::parse(&c[1]))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // Spring Boot 插件会隐式导入 spring-boot-dependencies
+    let boot_plugin = Regex::new(r#"id\s*\(?\s*["']org\.springframework\.boot["']\s*\)?\s*version\s*\(?\s*["']([^"']+)["']"#)
+        .ok()
+        .and_then(|re| re.captures(content).map(|c| c[1].to_string()));
+    if let Some(version) = boot_plugin {
+        boms.push(MavenCoordinate {
+            group_id: "org.springframework.boot".to_string(),
+            artifact_id: "spring-boot-dependencies".to_string(),
+            version,
+        });
+    }
+    boms
+}
+
+/// BOM 解析器
+pub struct BomResolver {
+    client: Client,
+    repository_url: String,
+    poms: HashMap<MavenCoordinate, PomModel>,
+}
+
+impl BomResolver {
+    /// 仓库地址可通过 `MAVEN_REPOSITORY_URL` 指向私有仓库
+    pub fn new(client: Client) -> Self {
+        let repository_url = std::env::var("MAVEN_REPOSITORY_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_MAVEN_REPOSITORY.to_string());
+        Self {
+            client,
+            repository_url: repository_url.trim_end_matches('/').to_string(),
+            poms: HashMap::new(),
+        }
+    }
+
+    async fn fetch_pom(&mut self, coordinate: &MavenCoordinate) -> Result<PomModel> {
+        if let Some(model) = self.poms.get(coordinate) {
+            return Ok(model.clone());
+        }
+        let url = format!("{}/{}", self.repository_url, coordinate.pom_path());
+        let response = self.client.get(&url).correlated().send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("获取BOM {} 失败: HTTP {}", coordinate, response.status()));
+        }
+        let model = parse_pom(&response.text().await?)?;
+        self.poms.insert(coordinate.clone(), model.clone());
+        Ok(model)
+    }
+
+    /// 解析一个 BOM（含其父 POM 和嵌套导入）管理的全部依赖版本
+    pub async fn managed_versions(&mut self, bom: &MavenCoordinate) -> Result<HashMap<String, ManagedVersion>> {
+        let mut visited = HashSet::new();
+        let (managed, _) = self.resolve_bom(bom, &mut visited, 0).await?;
+        Ok(managed)
+    }
+
+    /// 返回 (管理的版本, 生效属性)
+    fn resolve_bom<'a>(
+        &'a mut self,
+        bom: &'a MavenCoordinate,
+        visited: &'a mut HashSet<MavenCoordinate>,
+        depth: usize,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(HashMap<String, ManagedVersion>, HashMap<String, String>)>> + Send + 'a>> {
+        Box::pin(async move {
+            if depth > MAX_BOM_DEPTH || !visited.insert(bom.clone()) {
+                return Ok((HashMap::new(), HashMap::new()));
+            }
+            let model = self.fetch_pom(bom).await?;
+
+            // 父 POM 的属性和版本管理先载入，再被子 POM 覆盖
+            let (mut managed, mut properties) = match &model.parent {
+                Some(parent) => self.resolve_bom(parent, visited, depth + 1).await.unwrap_or_default(),
+                None => (HashMap::new(), HashMap::new()),
+            };
+            properties.extend(project_properties(&model));
+
+            let mut own = HashMap::new();
+            let mut imports = Vec::new();
+            for entry in &model.managed {
+                let coordinate = MavenCoordinate {
+                    group_id: interpolate(&entry.group_id, &properties),
+                    artifact_id: interpolate(&entry.artifact_id, &properties),
+                    version: interpolate(&entry.version, &properties),
+                };
+                if entry.is_import {
+                    imports.push(coordinate);
+                } else if !coordinate.version.is_empty() && !coordinate.version.contains("${") {
+                    own.entry(coordinate.key()).or_insert(ManagedVersion {
+                        version: coordinate.version,
+                        managed_by: bom.to_string(),
+                    });
+                }
+            }
+
+            // 导入的 BOM 优先级低于本 POM 直接声明的版本，先声明的导入优先
+            let mut imported = HashMap::new();
+            for import in imports {
+                match self.resolve_bom(&import, visited, depth + 1).await {
+                    Ok((versions, _)) => {
+                        for (key, version) in versions {
+                            imported.entry(key).or_insert(version);
+                        }
+                    }
+                    Err(e) => tracing::warn!("解析导入的BOM {} 失败: {}", import, e),
+                }
+            }
+            managed.extend(imported);
+            managed.extend(own);
+            Ok((managed, properties))
+        })
+    }
+
+    /// 计算依赖在给定 BOM 列表下的生效版本（先声明的 BOM 优先）
+    pub async fn effective_version(&mut self, boms: &[MavenCoordinate], key: &str) -> Result<Option<ManagedVersion>> {
+        for bom in boms {
+            match self.managed_versions(bom).await {
+                Ok(managed) => {
+                    if let Some(version) = managed.get(key) {
+                        return Ok(Some(version.clone()));
+                    }
+                }
+                Err(e) => tracing::warn!("解析BOM {} 失败: {}", bom, e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// 结合项目本地声明与 BOM 计算生效版本
+    pub async fn effective_version_for_project(&mut self, management: &ProjectManagement, key: &str) -> Result<Option<ManagedVersion>> {
+        if let Some(version) = management.local.get(key) {
+            return Ok(Some(ManagedVersion {
+                version: version.clone(),
+                managed_by: "project".to_string(),
+            }));
+        }
+        self.effective_version(&management.boms, key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <groupId>org.example</groupId>
+  <artifactId>example-bom</artifactId>
+  <version>2.1.0</version>
+  <properties>
+    <jackson.version>2.15.3</jackson.version>
+    <jackson-bom.version>${jackson.version}</jackson-bom.version>
+  </properties>
+  <dependencyManagement>
+    <dependencies>
+      <dependency>
+        <groupId>com.fasterxml.jackson</groupId>
+        <artifactId>jackson-bom</artifactId>
+        <version>${jackson-bom.version}</version>
+        <type>pom</type>
+        <scope>import</scope>
+      </dependency>
+      <dependency>
+        <groupId>org.example</groupId>
+        <artifactId>example-core</artifactId>
+        <version>${project.version}</version>
+      </dependency>
+    </dependencies>
+  </dependencyManagement>
+</project>"#;
+
+    #[test]
+    fn test_parse_pom_and_interpolate() {
+        let model = parse_pom(BOM).unwrap();
+        assert_eq!(model.managed.len(), 2);
+        assert!(model.managed[0].is_import);
+        assert!(!model.managed[1].is_import);
+
+        let properties = project_properties(&model);
+        assert_eq!(interpolate(&model.managed[0].version, &properties), "2.15.3");
+        assert_eq!(interpolate(&model.managed[1].version, &properties), "2.1.0");
+        assert_eq!(interpolate("${unknown}", &properties), "${unknown}");
+    }
+
+    #[test]
+    fn test_gradle_boms() {
+        let build = r#"
+plugins {
+    id("org.springframework.boot") version "3.2.1"
+}
+dependencies {
+    implementation(platform("software.amazon.awssdk:bom:2.21.0"))
+}
+dependencyManagement {
+    imports { mavenBom "com.google.cloud:libraries-bom:26.29.0" }
+}
+"#;
+        let boms: Vec<String> = gradle_boms(build).iter().map(|b| b.to_string()).collect();
+        assert_eq!(boms, vec![
+            "software.amazon.awssdk:bom:2.21.0",
+            "com.google.cloud:libraries-bom:26.29.0",
+            "org.springframework.boot:spring-boot-dependencies:3.2.1",
+        ]);
+    }
+
+    #[test]
+    fn test_detect_project_management() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("pom.xml"), r#"<project>
+  <parent>
+    <groupId>org.springframework.boot</groupId>
+    <artifactId>spring-boot-starter-parent</artifactId>
+    <version>3.2.1</version>
+  </parent>
+  <artifactId>demo</artifactId>
+  <properties><guava.version>33.0.0-jre</guava.version></properties>
+  <dependencyManagement>
+    <dependencies>
+      <dependency>
+        <groupId>com.google.guava</groupId>
+        <artifactId>guava</artifactId>
+        <version>${guava.version}</version>
+      </dependency>
+    </dependencies>
+  </dependencyManagement>
+</project>"#).unwrap();
+
+        let management = detect_project_management(temp_dir.path());
+        assert_eq!(management.local.get("com.google.guava:guava").map(String::as_str), Some("33.0.0-jre"));
+        assert_eq!(management.boms.len(), 1);
+        assert_eq!(management.boms[0].artifact_id, "spring-boot-starter-parent");
+    }
+}
@@ -1,7 +1,15 @@
 // 版本检查模块
 pub mod base;
+pub mod conda;
+pub mod crate_features;
+pub mod fetcher;
+pub mod goproxy;
+pub mod maven_bom;
+pub mod npm_registry;
+pub mod packagist;
 pub mod models;
 pub mod providers;
 pub mod traits;
+pub mod wheels;
 
  
\ No newline at end of file
@@ -3,5 +3,5 @@ pub mod version;
 pub mod registry;
 
 pub use package::Package;
-pub use version::VersionInfo;
+pub use version::{Provenance, VersionInfo, VersionLinks, NORMALIZED_FIELDS};
 pub use registry::Registry;
@@ -1,20 +1,48 @@
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use super::package::Package;
+use super::registry::Registry;
+
+/// 规范化版本信息序列化后的顶层字段，所有注册表一致
+pub const NORMALIZED_FIELDS: &[&str] = &[
+    "package",
+    "version",
+    "latest_preview",
+    "published_at",
+    "eol_date",
+    "yanked",
+    "license",
+    "links",
+    "provenance",
+    "available_versions",
+    "dependencies",
+    "downloads",
+];
 
 /// 版本信息
+///
+/// 各注册表提供者返回统一的结构，下游工具无需区分生态。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VersionInfo {
     /// 包信息
     pub package: Package,
     /// 最新稳定版本
-    pub latest_stable: String,
+    pub version: String,
     /// 最新预览版本(如有)
     pub latest_preview: Option<String>,
-    /// 发布日期
-    pub release_date: DateTime<Utc>,
+    /// 发布时间
+    pub published_at: DateTime<Utc>,
     /// 生命周期结束日期(如有)
     pub eol_date: Option<DateTime<Utc>>,
+    /// 该版本是否已被撤回(yanked/retracted)
+    pub yanked: bool,
+    /// 许可证(SPDX表达式或注册表提供的原文)
+    pub license: Option<String>,
+    /// 相关链接
+    pub links: VersionLinks,
+    /// 数据来源
+    pub provenance: Provenance,
     /// 可用版本列表
     pub available_versions: Vec<String>,
     /// 依赖信息
@@ -23,6 +51,80 @@ pub struct VersionInfo {
     pub downloads: Option<u64>,
 }
 
+/// 版本相关链接
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VersionLinks {
+    /// 注册表中的包页面
+    pub registry: Option<String>,
+    pub homepage: Option<String>,
+    pub repository: Option<String>,
+    pub documentation: Option<String>,
+    /// 该版本的下载地址
+    pub download: Option<String>,
+}
+
+/// 版本信息的来源
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// 来源注册表
+    pub registry: Registry,
+    /// 实际请求的API地址
+    pub source_url: String,
+    /// 获取时间
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl VersionInfo {
+    /// 由包信息构建规范化版本信息
+    pub fn from_package(package: Package, registry: Registry, source_url: impl Into<String>) -> Self {
+        let links = VersionLinks {
+            registry: Some(registry.package_url(&package.name)),
+            homepage: package.homepage.clone(),
+            repository: package.repository.clone(),
+            documentation: None,
+            download: None,
+        };
+        Self {
+            version: package.version.clone(),
+            latest_preview: None,
+            published_at: package.release_date,
+            eol_date: None,
+            yanked: false,
+            license: Some(package.license.clone()).filter(|l| !l.is_empty()),
+            links,
+            provenance: Provenance {
+                registry,
+                source_url: source_url.into(),
+                fetched_at: Utc::now(),
+            },
+            available_versions: package.available_versions.clone(),
+            dependencies: None,
+            downloads: package.download_count,
+            package,
+        }
+    }
+
+    /// 校验必填字段，提供者返回前调用
+    pub fn validate(&self) -> Result<()> {
+        if self.version.is_empty() || self.version == "unknown" {
+            return Err(anyhow!("{} 的版本信息缺少有效版本号", self.package.name));
+        }
+        if self.provenance.source_url.is_empty() {
+            return Err(anyhow!("{} 的版本信息缺少来源地址", self.package.name));
+        }
+        Ok(())
+    }
+}
+
+/// 测试辅助：断言序列化结果符合规范化字段集合
+#[cfg(test)]
+pub(crate) fn assert_normalized_shape(info: &VersionInfo) {
+    let value = serde_json::to_value(info).unwrap();
+    let keys: std::collections::BTreeSet<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+    assert_eq!(keys, NORMALIZED_FIELDS.iter().copied().collect());
+    assert!(info.validate().is_ok());
+}
+
 /// 版本比较结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VersionDiff {
@@ -50,3 +152,33 @@ pub enum VersionDiffType {
     /// 无变化
     None,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_package_and_validate() {
+        let package = Package {
+            name: "serde".to_string(),
+            version: "1.0.190".to_string(),
+            description: String::new(),
+            license: "MIT OR Apache-2.0".to_string(),
+            homepage: Some("https://serde.rs".to_string()),
+            repository: None,
+            author: None,
+            release_date: Utc::now(),
+            download_count: Some(10),
+            available_versions: vec!["1.0.190".to_string()],
+        };
+        let info = VersionInfo::from_package(package, Registry::Cargo, "https://crates.io/api/v1/crates/serde");
+        assert_eq!(info.links.registry.as_deref(), Some("https://crates.io/crates/serde"));
+
+        assert_normalized_shape(&info);
+        assert_eq!(serde_json::to_value(&info).unwrap()["provenance"]["registry"], "cargo");
+
+        let mut unknown = info.clone();
+        unknown.version = "unknown".to_string();
+        assert!(unknown.validate().is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! npm 注册表访问：dist-tags、清单中的 `workspace:`/`npm:` 协议、作用域私有包认证
+//!
+//! 注册表与认证配置按 npm 的规则从 `.npmrc`（项目目录、用户目录）和环境变量读取：
+//! `registry=`、`@scope:registry=`、`//host/path/:_authToken=`、`//host/path/:_auth=`，
+//! 值中的 `${ENV}` 会被替换为环境变量。
+
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use crate::mcp::correlation::Correlated;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const DEFAULT_NPM_REGISTRY: &str = "https://registry.npmjs.org/";
+
+/// package.json 中的依赖声明
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NpmSpecifier {
+    /// 普通版本范围，从注册表安装
+    Registry { range: String },
+    /// `workspace:*`、`workspace:^1.2.0` 等，指向工作区内的本地包
+    Workspace { range: String },
+    /// `npm:real-package@^1.0.0` 别名
+    Alias { package: String, range: String },
+    /// git/file/link/http 等不经过注册表的来源
+    Other(String),
+}
+
+impl NpmSpecifier {
+    pub fn parse(spec: &str) -> Self {
+        let spec = spec.trim();
+        if let Some(range) = spec.strip_prefix("workspace:") {
+            return NpmSpecifier::Workspace { range: range.to_string() };
+        }
+        if let Some(target) = spec.strip_prefix("npm:") {
+            // 作用域包名本身以 @ 开头，版本分隔符是第一个字符之后的 @
+            let (package, range) = match target[1.min(target.len())..].find('@') {
+                Some(pos) => (&target[..pos + 1], &target[pos + 2..]),
+                None => (target, "latest"),
+            };
+            return NpmSpecifier::Alias { package: package.to_string(), range: range.to_string() };
+        }
+        let is_other = ["git+", "git:", "github:", "file:", "link:", "http:", "https:", "portal:", "patch:"]
+            .iter()
+            .any(|prefix| spec.starts_with(prefix))
+            || (spec.contains('/') && !spec.starts_with('@') && !spec.contains(' '));
+        if is_other {
+            NpmSpecifier::Other(spec.to_string())
+        } else {
+            NpmSpecifier::Registry { range: spec.to_string() }
+        }
+    }
+
+    /// 依赖实际对应的注册表包名（别名解析到真实包）
+    pub fn registry_package<'a>(&'a self, dependency_name: &'a str) -> Option<&'a str> {
+        match self {
+            NpmSpecifier::Registry { .. } => Some(dependency_name),
+            NpmSpecifier::Alias { package, .. } => Some(package),
+            NpmSpecifier::Workspace { .. } | NpmSpecifier::Other(_) => None,
+        }
+    }
+
+    /// 版本范围（`workspace:*` 等无具体版本时返回 None）
+    pub fn range(&self) -> Option<&str> {
+        let range = match self {
+            NpmSpecifier::Registry { range } | NpmSpecifier::Workspace { range } | NpmSpecifier::Alias { range, .. } => range,
+            NpmSpecifier::Other(_) => return None,
+        };
+        (!matches!(range.as_str(), "*" | "^" | "~" | "" | "latest")).then_some(range.as_str())
+    }
+}
+
+/// 在 package.json 的依赖中查找某个注册表包的版本范围（包括通过 `npm:` 别名引用的）
+pub fn find_manifest_requirement(manifest: &Value, package: &str) -> Option<String> {
+    ["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"]
+        .iter()
+        .filter_map(|section| manifest[section].as_object())
+        .flat_map(|deps| deps.iter())
+        .find_map(|(dependency, spec)| {
+            let spec = NpmSpecifier::parse(spec.as_str()?);
+            (spec.registry_package(dependency) == Some(package)).then(|| spec.range().map(String::from)).flatten()
+        })
+}
+
+/// `.npmrc` 注册表与认证配置
+#[derive(Debug, Clone, Default)]
+pub struct NpmRegistryConfig {
+    default_registry: Option<String>,
+    scoped_registries: HashMap<String, String>,
+    /// key 为去掉协议的注册表前缀，例如 `//npm.pkg.github.com/`
+    auth_tokens: HashMap<String, String>,
+    basic_auth: HashMap<String, String>,
+}
+
+impl NpmRegistryConfig {
+    /// 读取用户目录和项目目录的 `.npmrc`（项目配置优先）以及 `NPM_CONFIG_REGISTRY`
+    pub fn load(project_dir: Option<&Path>) -> Self {
+        let mut config = Self::default();
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+        let mut files: Vec<std::path::PathBuf> = home.map(|h| Path::new(&h).join(".npmrc")).into_iter().collect();
+        if let Some(dir) = project_dir {
+            files.push(dir.join(".npmrc"));
+        }
+        for file in files {
+            if let Ok(content) = std::fs::read_to_string(&file) {
+                config.merge_npmrc(&content);
+            }
+        }
+        if let Ok(registry) = std::env::var("NPM_CONFIG_REGISTRY") {
+            config.default_registry = Some(normalize_registry(&registry));
+        }
+        config
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+        config.merge_npmrc(content);
+        config
+    }
+
+    fn merge_npmrc(&mut self, content: &str) {
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let (key, value) = (key.trim(), expand_env(value.trim().trim_matches('"')));
+            if key == "registry" {
+                self.default_registry = Some(normalize_registry(&value));
+            } else if let Some(scope) = key.strip_suffix(":registry").filter(|s| s.starts_with('@')) {
+                self.scoped_registries.insert(scope.to_string(), normalize_registry(&value));
+            } else if let Some(prefix) = key.strip_suffix(":_authToken") {
+                self.auth_tokens.insert(prefix.to_string(), value);
+            } else if let Some(prefix) = key.strip_suffix(":_auth") {
+                self.basic_auth.insert(prefix.to_string(), value);
+            }
+        }
+    }
+
+    /// 包对应的注册表地址（作用域包可配置私有注册表）
+    pub fn registry_for(&self, package: &str) -> String {
+        package.strip_prefix('@')
+            .and_then(|rest| rest.split_once('/'))
+            .and_then(|(scope, _)| self.scoped_registries.get(&format!("@{}", scope)))
+            .or(self.default_registry.as_ref())
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_NPM_REGISTRY.to_string())
+    }
+
+    /// 注册表的 Authorization 头；按最长前缀匹配 `//host/path/`
+    pub fn authorization_for(&self, registry: &str) -> Option<String> {
+        let nerfed = registry.split_once("://").map_or(registry, |(_, rest)| rest);
+        let nerfed = format!("//{}", nerfed);
+        let best = |entries: &HashMap<String, String>| {
+            entries.iter()
+                .filter(|(prefix, _)| nerfed.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, value)| value.clone())
+        };
+        best(&self.auth_tokens)
+            .map(|token| format!("Bearer {}", token))
+            .or_else(|| best(&self.basic_auth).map(|auth| format!("Basic {}", auth)))
+    }
+}
+
+fn normalize_registry(registry: &str) -> String {
+    format!("{}/", registry.trim().trim_end_matches('/'))
+}
+
+/// 替换 `${VAR}` 环境变量引用
+fn expand_env(value: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else { break };
+        result.push_str(&rest[..start]);
+        result.push_str(&std::env::var(&rest[start + 2..start + end]).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 作用域包名在注册表URL中需要转义斜杠
+pub fn encode_package_name(package: &str) -> String {
+    package.replace('/', "%2f")
+}
+
+/// npm 注册表客户端
+pub struct NpmRegistryClient {
+    client: Client,
+    config: NpmRegistryConfig,
+}
+
+impl NpmRegistryClient {
+    pub fn new(client: Client, config: NpmRegistryConfig) -> Self {
+        Self { client, config }
+    }
+
+    /// 包元数据（packument）的地址
+    pub fn packument_url(&self, package: &str) -> String {
+        format!("{}{}", self.config.registry_for(package), encode_package_name(package))
+    }
+
+    /// 获取包的完整元数据（packument）
+    pub async fn fetch_packument(&self, package: &str) -> Result<Value> {
+        let registry = self.config.registry_for(package);
+        let url = self.packument_url(package);
+        let mut request = self.client.get(&url);
+        if let Some(authorization) = self.config.authorization_for(&registry) {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization);
+        }
+        let response = request.correlated().send().await?;
+        match response.status() {
+            status if status.is_success() => Ok(response.json().await?),
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => Err(anyhow!(
+                "访问 {} 被拒绝，请在 .npmrc 中为 {} 配置 _authToken",
+                package, registry
+            )),
+            status => Err(anyhow!("npm注册表返回错误状态 {}: {}", status, package)),
+        }
+    }
+
+    /// 获取 dist-tags（latest、next、lts 等）
+    pub async fn dist_tags(&self, package: &str) -> Result<BTreeMap<String, String>> {
+        Ok(dist_tags_of(&self.fetch_packument(package).await?))
+    }
+}
+
+pub fn dist_tags_of(packument: &Value) -> BTreeMap<String, String> {
+    packument["dist-tags"]
+        .as_object()
+        .map(|tags| tags.iter().filter_map(|(tag, v)| Some((tag.clone(), v.as_str()?.to_string()))).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_specifiers() {
+        assert_eq!(NpmSpecifier::parse("workspace:*"), NpmSpecifier::Workspace { range: "*".to_string() });
+        assert_eq!(NpmSpecifier::parse("workspace:^1.2.0").range(), Some("^1.2.0"));
+        assert_eq!(NpmSpecifier::parse("npm:@scope/real@^2.0.0"), NpmSpecifier::Alias {
+            package: "@scope/real".to_string(),
+            range: "^2.0.0".to_string(),
+        });
+        assert_eq!(NpmSpecifier::parse("npm:lodash@4.17.21").registry_package("lodash-alias"), Some("lodash"));
+        assert!(matches!(NpmSpecifier::parse("github:user/repo"), NpmSpecifier::Other(_)));
+        assert_eq!(NpmSpecifier::parse("^18.2.0").range(), Some("^18.2.0"));
+
+        let manifest = json!({
+            "dependencies": { "lodash-es-alias": "npm:lodash@^4.17.0", "local-lib": "workspace:*" }
+        });
+        assert_eq!(find_manifest_requirement(&manifest, "lodash").as_deref(), Some("^4.17.0"));
+        assert_eq!(find_manifest_requirement(&manifest, "local-lib"), None);
+    }
+
+    #[test]
+    fn test_npmrc_scoped_registry_and_auth() {
+        let config = NpmRegistryConfig::parse(
+            "registry=https://registry.example.com\n\
+             @corp:registry=https://npm.pkg.github.com/\n\
+             //npm.pkg.github.com/:_authToken=secret\n",
+        );
+        assert_eq!(config.registry_for("@corp/ui"), "https://npm.pkg.github.com/");
+        assert_eq!(config.registry_for("react"), "https://registry.example.com/");
+        assert_eq!(config.authorization_for("https://npm.pkg.github.com/").as_deref(), Some("Bearer secret"));
+        assert_eq!(config.authorization_for("https://registry.example.com/"), None);
+        assert_eq!(encode_package_name("@corp/ui"), "@corp%2fui");
+    }
+}
@@ -0,0 +1,418 @@
+//! Packagist（Composer）注册表支持
+//!
+//! 包坐标为 `vendor/package`。版本元数据来自 Packagist 的 p2 接口：`/p2/{vendor}/{package}.json`
+//! 是已打标签的版本，`/p2/{vendor}/{package}~dev.json` 是开发分支。两者都是压缩格式，
+//! 每个版本只列出与前一个版本不同的字段，值为 `__unset` 表示删除该字段。
+//! 版本按 Composer 的稳定性分级（dev < alpha < beta < RC < stable），最新版本按项目的
+//! `minimum-stability`、`prefer-stable` 和包约束上的稳定性标记（如 `^2.0@beta`）解析。
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use crate::versioning::fetcher::RegistryFetcher;
+
+/// Packagist 元数据仓库地址
+pub const PACKAGIST_REPO_URL: &str = "https://repo.packagist.org";
+
+/// 压缩格式中表示删除字段的值
+const UNSET_MARKER: &str = "__unset";
+
+/// 分支别名 `2.x-dev` 中 `x` 的取值，与 Composer 归一化后的 `9999999` 一致
+const WILDCARD_COMPONENT: u64 = 9_999_999;
+
+/// Composer 的稳定性级别，从低到高排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stability {
+    Dev,
+    Alpha,
+    Beta,
+    #[serde(rename = "RC")]
+    Rc,
+    Stable,
+}
+
+impl Stability {
+    /// 解析 `minimum-stability` 的取值或约束上的 `@flag`（不区分大小写）
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "dev" => Some(Stability::Dev),
+            "alpha" => Some(Stability::Alpha),
+            "beta" => Some(Stability::Beta),
+            "rc" => Some(Stability::Rc),
+            "stable" => Some(Stability::Stable),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stability::Dev => "dev",
+            Stability::Alpha => "alpha",
+            Stability::Beta => "beta",
+            Stability::Rc => "RC",
+            Stability::Stable => "stable",
+        }
+    }
+
+    /// 版本号的稳定性：`dev-main` 这类开发分支和 `-dev` 结尾的分支别名为 dev
+    pub fn of_version(version: &str) -> Self {
+        ComposerVersion::parse(version).map_or(Stability::Dev, |v| v.stability)
+    }
+}
+
+/// 可比较的 Composer 版本号；`dev-main` 这类开发分支没有版本号，无法比较
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposerVersion {
+    /// 数字部分，补齐为 4 段
+    pub numbers: [u64; 4],
+    pub stability: Stability,
+    /// 预发布序号（beta2 的 2）；稳定版的补丁级别（`-p1`）记为序号加一
+    pub sequence: u64,
+}
+
+impl ComposerVersion {
+    /// 解析 `v1.2.3`、`1.2.3-beta2`、`2.0.0-RC1`、`1.0.0-p1`、`2.x-dev` 等形式
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().to_ascii_lowercase();
+        if version.starts_with("dev-") {
+            return None;
+        }
+        let version = version.trim_start_matches('v');
+        let core_end = version
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == 'x' || c == '*'))
+            .unwrap_or(version.len());
+        let core = version[..core_end].trim_end_matches('.');
+        if core.is_empty() || !core.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut numbers = [0u64; 4];
+        for (i, part) in core.split('.').enumerate() {
+            if i >= numbers.len() {
+                return None;
+            }
+            numbers[i] = match part {
+                "x" | "*" => WILDCARD_COMPONENT,
+                _ => part.parse().ok()?,
+            };
+        }
+
+        let suffix = version[core_end..].trim_start_matches(['-', '.', '_']);
+        if suffix.is_empty() {
+            return Some(Self { numbers, stability: Stability::Stable, sequence: 0 });
+        }
+        if suffix == "dev" || suffix.ends_with("-dev") {
+            return Some(Self { numbers, stability: Stability::Dev, sequence: 0 });
+        }
+        let label_end = suffix.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(suffix.len());
+        let digits = suffix[label_end..].trim_start_matches(['-', '.', '_']);
+        let sequence = if digits.is_empty() { 0 } else { digits.parse().ok()? };
+        let (stability, sequence) = match &suffix[..label_end] {
+            "alpha" | "a" => (Stability::Alpha, sequence),
+            "beta" | "b" => (Stability::Beta, sequence),
+            "rc" => (Stability::Rc, sequence),
+            "patch" | "pl" | "p" => (Stability::Stable, sequence + 1),
+            _ => return None,
+        };
+        Some(Self { numbers, stability, sequence })
+    }
+}
+
+impl Ord for ComposerVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numbers
+            .cmp(&other.numbers)
+            .then(self.stability.cmp(&other.stability))
+            .then(self.sequence.cmp(&other.sequence))
+    }
+}
+
+impl PartialOrd for ComposerVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `vendor/package` 形式的包坐标
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageCoordinate {
+    pub vendor: String,
+    pub package: String,
+}
+
+impl PackageCoordinate {
+    /// 解析包坐标（Packagist 的包名不区分大小写，统一为小写）
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.trim().to_ascii_lowercase();
+        let valid = |part: &str| {
+            !part.is_empty()
+                && part.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        };
+        match name.split_once('/') {
+            Some((vendor, package)) if valid(vendor) && valid(package) => {
+                Ok(Self { vendor: vendor.to_string(), package: package.to_string() })
+            }
+            _ => Err(anyhow!("Composer 包名应为 vendor/package 形式: {}", name)),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.vendor, self.package)
+    }
+}
+
+/// Packagist 上的一个版本
+#[derive(Debug, Clone, Serialize)]
+pub struct PackagistVersion {
+    pub version: String,
+    pub stability: Stability,
+    pub released: Option<DateTime<Utc>>,
+    /// 该版本的 `require`
+    pub require: Option<Value>,
+    #[serde(skip)]
+    parsed: Option<ComposerVersion>,
+}
+
+impl PackagistVersion {
+    fn from_metadata(metadata: &Map<String, Value>) -> Option<Self> {
+        let version = metadata.get("version")?.as_str()?.to_string();
+        let parsed = ComposerVersion::parse(&version);
+        Some(Self {
+            stability: parsed.as_ref().map_or(Stability::Dev, |v| v.stability),
+            released: metadata
+                .get("time")
+                .and_then(|t| t.as_str())
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .map(|dt| dt.with_timezone(&Utc)),
+            require: metadata.get("require").filter(|r| r.is_object()).cloned(),
+            version,
+            parsed,
+        })
+    }
+}
+
+/// 展开 p2 接口的压缩版本列表：每一项继承前一项的字段，`__unset` 删除字段
+pub fn expand_minified(entries: &[Value]) -> Vec<Map<String, Value>> {
+    let mut expanded = Vec::with_capacity(entries.len());
+    let mut current = Map::new();
+    for entry in entries.iter().filter_map(|e| e.as_object()) {
+        for (key, value) in entry {
+            if value.as_str() == Some(UNSET_MARKER) {
+                current.remove(key);
+            } else {
+                current.insert(key.clone(), value.clone());
+            }
+        }
+        expanded.push(current.clone());
+    }
+    expanded
+}
+
+/// 一个包的全部版本
+#[derive(Debug, Clone)]
+pub struct PackagistPackage {
+    pub coordinate: PackageCoordinate,
+    /// 可比较的版本从新到旧，之后是开发分支
+    pub versions: Vec<PackagistVersion>,
+    pub repository_url: Option<String>,
+}
+
+impl PackagistPackage {
+    fn new(coordinate: PackageCoordinate, mut versions: Vec<PackagistVersion>, repository_url: Option<String>) -> Self {
+        versions.sort_by(|a, b| match (&a.parsed, &b.parsed) {
+            (Some(a), Some(b)) => b.cmp(a),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        versions.dedup_by(|a, b| a.version == b.version);
+        Self { coordinate, versions, repository_url }
+    }
+
+    /// 稳定性不低于 `minimum` 的最高版本（开发分支没有版本号，不参与）
+    pub fn latest(&self, minimum: Stability) -> Option<&PackagistVersion> {
+        self.versions.iter().find(|v| v.parsed.is_some() && v.stability >= minimum)
+    }
+
+    pub fn latest_stable(&self) -> Option<&PackagistVersion> {
+        self.latest(Stability::Stable)
+    }
+
+    /// 高于最新稳定版的预发布版本（alpha / beta / RC）
+    pub fn latest_preview(&self) -> Option<&PackagistVersion> {
+        let preview = self.latest(Stability::Alpha).filter(|v| v.stability != Stability::Stable)?;
+        match self.latest_stable() {
+            Some(stable) if stable.parsed >= preview.parsed => None,
+            _ => Some(preview),
+        }
+    }
+}
+
+/// 项目对某个包的稳定性要求
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StabilityPolicy {
+    /// 允许的最低稳定性：`minimum-stability` 与包约束上的 `@flag` 中较低的一个
+    pub minimum: Stability,
+    /// `prefer-stable`：有稳定版时优先使用稳定版
+    pub prefer_stable: bool,
+}
+
+impl Default for StabilityPolicy {
+    fn default() -> Self {
+        Self { minimum: Stability::Stable, prefer_stable: false }
+    }
+}
+
+impl StabilityPolicy {
+    /// 按 composer.json 的 `minimum-stability`、`prefer-stable` 和该包在 `require`/`require-dev` 中的约束确定
+    pub fn from_manifest(manifest: &Value, name: &str) -> Self {
+        let mut minimum = manifest["minimum-stability"].as_str().and_then(Stability::parse).unwrap_or(Stability::Stable);
+        let constraint = ["require", "require-dev"]
+            .iter()
+            .filter_map(|section| manifest[*section].as_object())
+            .find_map(|deps| deps.iter().find(|(dep, _)| dep.eq_ignore_ascii_case(name)))
+            .and_then(|(_, constraint)| constraint.as_str());
+        if let Some(flag) = constraint.and_then(stability_flag) {
+            minimum = minimum.min(flag);
+        }
+        Self { minimum, prefer_stable: manifest["prefer-stable"].as_bool().unwrap_or(false) }
+    }
+
+    /// 按该策略解析应使用的最新版本
+    pub fn resolve<'a>(&self, package: &'a PackagistPackage) -> Option<&'a PackagistVersion> {
+        if self.prefer_stable {
+            if let Some(stable) = package.latest_stable() {
+                return Some(stable);
+            }
+        }
+        package.latest(self.minimum)
+    }
+}
+
+/// 约束隐含的稳定性：`^2.0@beta` 的 `@beta`，`dev-main` 或 `2.x-dev` 为 dev
+pub fn stability_flag(constraint: &str) -> Option<Stability> {
+    let constraint = constraint.trim();
+    if let Some((_, flag)) = constraint.rsplit_once('@') {
+        return Stability::parse(flag.split_whitespace().next().unwrap_or(""));
+    }
+    if constraint.starts_with("dev-") || constraint.ends_with("-dev") {
+        return Some(Stability::Dev);
+    }
+    None
+}
+
+/// Packagist 元数据客户端（也适用于提供同样 p2 接口的私有仓库）
+pub struct PackagistClient {
+    fetcher: Arc<dyn RegistryFetcher>,
+    base_url: String,
+}
+
+impl PackagistClient {
+    pub fn new(fetcher: Arc<dyn RegistryFetcher>) -> Self {
+        Self { fetcher, base_url: PACKAGIST_REPO_URL.to_string() }
+    }
+
+    /// 使用其他元数据仓库（私有 Packagist、镜像）
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// 获取包的全部版本；`include_dev` 时同时读取开发分支（读取失败时忽略）
+    pub async fn fetch_package(&self, coordinate: &PackageCoordinate, include_dev: bool) -> Result<PackagistPackage> {
+        let name = coordinate.name();
+        let response = self.fetcher.get_json(&format!("{}/p2/{}.json", self.base_url, name)).await?;
+        if !response.is_success() {
+            return Err(anyhow!("Packagist 返回 {}: {}", response.status, name));
+        }
+        let mut metadata = expand_minified(response.body["packages"][&name].as_array().map_or(&[][..], |v| v.as_slice()));
+        if metadata.is_empty() {
+            return Err(anyhow!("Packagist 上没有 {} 的版本", name));
+        }
+        if include_dev {
+            match self.fetcher.get_json(&format!("{}/p2/{}~dev.json", self.base_url, name)).await {
+                Ok(dev) if dev.is_success() => {
+                    metadata.extend(expand_minified(dev.body["packages"][&name].as_array().map_or(&[][..], |v| v.as_slice())));
+                }
+                Ok(dev) => tracing::debug!("获取 {} 的开发分支失败: HTTP {}", name, dev.status),
+                Err(e) => tracing::debug!("获取 {} 的开发分支失败: {}", name, e),
+            }
+        }
+        let repository_url = metadata
+            .iter()
+            .find_map(|m| m.get("source").and_then(|s| s["url"].as_str()).map(String::from));
+        let versions = metadata.iter().filter_map(PackagistVersion::from_metadata).collect();
+        Ok(PackagistPackage::new(coordinate.clone(), versions, repository_url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::fetcher::testing::MockRegistry;
+    use serde_json::json;
+
+    #[test]
+    fn test_version_ordering_and_stability() {
+        let v = |s: &str| ComposerVersion::parse(s).unwrap();
+        assert!(v("v2.0.0") > v("2.0.0-RC1"));
+        assert!(v("2.0.0-RC1") > v("2.0.0-beta3"));
+        assert!(v("2.0.0-beta10") > v("2.0.0-beta9"));
+        assert!(v("1.0.0-p1") > v("1.0.0"));
+        assert!(v("2.x-dev") > v("2.9.0"));
+        assert_eq!(ComposerVersion::parse("dev-main"), None);
+        assert_eq!(Stability::of_version("3.1.0-alpha1"), Stability::Alpha);
+        assert_eq!(Stability::of_version("dev-feature/foo"), Stability::Dev);
+        assert_eq!(stability_flag("^6.0@beta"), Some(Stability::Beta));
+        assert_eq!(stability_flag("^6.0"), None);
+        assert!(PackageCoordinate::parse("monolog").is_err());
+        assert_eq!(PackageCoordinate::parse("Symfony/Console").unwrap().name(), "symfony/console");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_expands_minified_metadata_and_resolves_by_stability() {
+        let registry = Arc::new(
+            MockRegistry::new()
+                .with_json(
+                    "https://repo.packagist.org/p2/acme/widget.json",
+                    json!({ "minified": "composer/2.0", "packages": { "acme/widget": [
+                        { "version": "3.0.0-beta1", "time": "2024-06-01T00:00:00+00:00",
+                          "require": { "php": ">=8.2" }, "source": { "url": "https://github.com/acme/widget.git" } },
+                        { "version": "2.5.1", "time": "2024-05-01T00:00:00+00:00" },
+                        { "version": "2.5.0", "require": "__unset" },
+                    ] } }),
+                )
+                .with_json(
+                    "https://repo.packagist.org/p2/acme/widget~dev.json",
+                    json!({ "packages": { "acme/widget": [{ "version": "dev-main" }, { "version": "3.x-dev" }] } }),
+                ),
+        );
+        let coordinate = PackageCoordinate::parse("acme/widget").unwrap();
+        let package = PackagistClient::new(registry).fetch_package(&coordinate, true).await.unwrap();
+
+        let versions: Vec<&str> = package.versions.iter().map(|v| v.version.as_str()).collect();
+        assert_eq!(versions, vec!["3.x-dev", "3.0.0-beta1", "2.5.1", "2.5.0", "dev-main"]);
+        // 压缩格式：2.5.1 继承了 require，2.5.0 删除了 require
+        assert_eq!(package.versions[2].require, Some(json!({ "php": ">=8.2" })));
+        assert_eq!(package.versions[3].require, None);
+        assert_eq!(package.repository_url.as_deref(), Some("https://github.com/acme/widget.git"));
+
+        assert_eq!(package.latest_stable().unwrap().version, "2.5.1");
+        assert_eq!(package.latest_preview().unwrap().version, "3.0.0-beta1");
+
+        let manifest = json!({ "minimum-stability": "stable", "require": { "acme/widget": "^3.0@beta" } });
+        let policy = StabilityPolicy::from_manifest(&manifest, "acme/widget");
+        assert_eq!(policy.minimum, Stability::Beta);
+        assert_eq!(policy.resolve(&package).unwrap().version, "3.0.0-beta1");
+
+        let manifest = json!({ "minimum-stability": "dev", "prefer-stable": true });
+        assert_eq!(StabilityPolicy::from_manifest(&manifest, "acme/widget").resolve(&package).unwrap().version, "2.5.1");
+        let dev = StabilityPolicy { minimum: Stability::Dev, prefer_stable: false };
+        assert_eq!(dev.resolve(&package).unwrap().version, "3.x-dev");
+    }
+}
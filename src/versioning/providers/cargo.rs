@@ -6,6 +6,7 @@ use serde_json::Value;
 
 use crate::versioning::{
     base::VersionChecker,
+    crate_features::{fetch_crate_features, CrateFeatures},
     models::{Package, VersionInfo, Registry},
 };
 
@@ -14,6 +15,11 @@ pub struct CratesIoChecker {
 }
 
 impl CratesIoChecker {
+    /// 获取指定版本的 feature 列表与默认 feature
+    pub async fn features(&self, name: &str, version: &str) -> Result<CrateFeatures> {
+        fetch_crate_features(&self.client, name, version).await
+    }
+
     async fn fetch_crate_data(&self, name: &str) -> Result<Value> {
         let url = format!("{}/crates/{}", Registry::Cargo.base_url(), name);
         let response = self.client.get(&url).send().await?;
@@ -22,6 +28,47 @@ impl CratesIoChecker {
     }
 }
 
+/// 将 `/crates/{name}` 响应转换为规范化版本信息
+fn normalize_crate(package: &Package, data: &Value) -> Result<VersionInfo> {
+    let crate_data = data["crate"].as_object()
+        .ok_or_else(|| anyhow::anyhow!("无效的crates.io响应"))?;
+    let version = crate_data["max_stable_version"]
+        .as_str()
+        .or_else(|| crate_data["max_version"].as_str())
+        .unwrap_or("0.0.0")
+        .to_string();
+    let version_entry = data["versions"]
+        .as_array()
+        .and_then(|versions| versions.iter().find(|v| v["num"].as_str() == Some(version.as_str())));
+    let published_at = version_entry
+        .and_then(|v| v["created_at"].as_str())
+        .or_else(|| crate_data["updated_at"].as_str())
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+
+    let mut info = VersionInfo::from_package(
+        package.clone(),
+        Registry::Cargo,
+        format!("{}/crates/{}", Registry::Cargo.base_url(), package.name),
+    );
+    info.version = version.clone();
+    info.latest_preview = crate_data["max_version"]
+        .as_str()
+        .filter(|v| v.contains('-'))
+        .map(String::from);
+    info.published_at = published_at;
+    info.yanked = version_entry.and_then(|v| v["yanked"].as_bool()).unwrap_or(false);
+    info.license = version_entry.and_then(|v| v["license"].as_str()).map(String::from).or(info.license);
+    info.links.homepage = crate_data["homepage"].as_str().map(String::from).or(info.links.homepage);
+    info.links.repository = crate_data["repository"].as_str().map(String::from).or(info.links.repository);
+    info.links.documentation = crate_data["documentation"].as_str().map(String::from)
+        .or_else(|| Some(format!("https://docs.rs/{}/{}", package.name, version)));
+    info.links.download = Some(format!("{}/crates/{}/{}/download", Registry::Cargo.base_url(), package.name, version));
+    info.downloads = crate_data["downloads"].as_u64();
+    Ok(info)
+}
+
 #[async_trait]
 impl VersionChecker for CratesIoChecker {
     fn registry(&self) -> Registry {
@@ -30,30 +77,10 @@ impl VersionChecker for CratesIoChecker {
 
     async fn check_version(&self, package: &Package) -> Result<VersionInfo> {
         let data = self.fetch_crate_data(&package.name).await?;
-        let crate_data = data["crate"].as_object()
-            .ok_or_else(|| anyhow::anyhow!("无效的crates.io响应"))?;
-
-        Ok(VersionInfo {
-            package: package.clone(),
-            latest_stable: crate_data["max_stable_version"]
-                .as_str()
-                .unwrap_or("0.0.0")
-                .to_string(),
-            latest_preview: crate_data["max_version"]
-                .as_str()
-                .filter(|v| v.contains("-"))
-                .map(String::from),
-            release_date: DateTime::parse_from_rfc3339(
-                crate_data["updated_at"]
-                    .as_str()
-                    .unwrap_or_default()
-            )?.with_timezone(&Utc),
-            eol_date: None,
-            available_versions: self.list_versions(package).await?,
-            dependencies: self.get_dependencies(package).await?,
-            downloads: crate_data["downloads"]
-                .as_u64(),
-        })
+        let mut info = normalize_crate(package, &data)?;
+        info.available_versions = self.list_versions(package).await?;
+        info.dependencies = self.get_dependencies(package).await?;
+        Ok(info)
     }
 
     async fn list_versions(&self, package: &Package) -> Result<Vec<String>> {
@@ -84,3 +111,43 @@ impl VersionChecker for CratesIoChecker {
         Ok(latest_version)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::models::version::assert_normalized_shape;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_crate_shape() {
+        let package = Package {
+            name: "tokio".to_string(),
+            version: String::new(),
+            description: String::new(),
+            license: String::new(),
+            homepage: None,
+            repository: None,
+            author: None,
+            release_date: Utc::now(),
+            download_count: None,
+            available_versions: Vec::new(),
+        };
+        let data = json!({
+            "crate": {
+                "max_stable_version": "1.38.0",
+                "max_version": "1.38.0",
+                "updated_at": "2024-05-30T00:00:00Z",
+                "repository": "https://github.com/tokio-rs/tokio",
+                "downloads": 100
+            },
+            "versions": [
+                { "num": "1.38.0", "yanked": false, "license": "MIT", "created_at": "2024-05-30T12:00:00Z" }
+            ]
+        });
+        let info = normalize_crate(&package, &data).unwrap();
+        assert_eq!(info.version, "1.38.0");
+        assert_eq!(info.license.as_deref(), Some("MIT"));
+        assert_eq!(info.provenance.registry, Registry::Cargo);
+        assert_normalized_shape(&info);
+    }
+}
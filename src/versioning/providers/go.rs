@@ -1,7 +1,7 @@
-use crate::versioning::models::package::Package;
+use crate::versioning::models::{Package, Registry};
 use anyhow::Result;
 use reqwest::Client;
-use serde_json::Value;
+use crate::versioning::goproxy::{GoModuleInfo, GoProxyClient};
 use chrono::Utc;
 use async_trait::async_trait;
 
@@ -9,28 +9,58 @@ pub struct GoProvider {
     client: Client,
 }
 
+/// 由 GOPROXY 查询结果构建包信息
+fn module_to_package(package_name: &str, module: &GoModuleInfo) -> Package {
+    Package {
+        name: package_name.to_string(),
+        version: module.latest().map(|v| v.raw.clone()).unwrap_or_else(|| "unknown".to_string()),
+        description: "".to_string(),
+        license: "".to_string(),
+        homepage: None,
+        repository: Some(format!("https://pkg.go.dev/{}", package_name)),
+        author: None,
+        release_date: module.release_date.unwrap_or_else(Utc::now),
+        download_count: None,
+        available_versions: module.resolved.versions.iter().map(|v| v.raw.clone()).collect(),
+    }
+}
+
 #[async_trait]
 impl crate::versioning::traits::PackageProvider for GoProvider {
+    fn registry(&self) -> Registry {
+        Registry::Go
+    }
+
     async fn get_package_info(&self, package_name: &str) -> Result<Package> {
-        // Go proxy API
-        let url = format!("https://proxy.golang.org/{}/latest", package_name);
-        let response: Value = self.client.get(&url).send().await?.json().await?;
-        
-        Ok(Package {
-            name: package_name.to_string(),
-            version: response["Version"].as_str().unwrap_or("unknown").to_string(),
-            description: "".to_string(),
-            license: "".to_string(),
-            homepage: None,
-            repository: None,
-            author: None,
-            release_date: Utc::now(),
-            download_count: None,
-            available_versions: Vec::new(),
-        })
+        // 按 GOPROXY 配置查询，正确处理伪版本和 +incompatible 版本
+        let module = GoProxyClient::new(self.client.clone()).fetch_module(package_name).await?;
+        Ok(module_to_package(package_name, &module))
     }
     
     async fn get_dependencies(&self, _package: &Package) -> Result<Option<serde_json::Value>> {
         Ok(None)
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::goproxy::resolve_versions;
+    use crate::versioning::models::VersionInfo;
+    use crate::versioning::models::version::assert_normalized_shape;
+
+    #[test]
+    fn test_module_to_package_shape() {
+        let module = GoModuleInfo {
+            module: "github.com/gin-gonic/gin".to_string(),
+            proxy: "https://proxy.golang.org".to_string(),
+            resolved: resolve_versions(&["v1.9.0".to_string(), "v1.9.1".to_string(), "v1.10.0-rc1".to_string()]),
+            latest_fallback: None,
+            release_date: None,
+        };
+        let package = module_to_package("github.com/gin-gonic/gin", &module);
+        let info = VersionInfo::from_package(package, Registry::Go, "https://proxy.golang.org/github.com/gin-gonic/gin/@v/list");
+        assert_eq!(info.version, "v1.9.1");
+        assert_normalized_shape(&info);
+    }
+}
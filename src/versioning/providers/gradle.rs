@@ -1,36 +1,106 @@
-use crate::versioning::models::package::Package;
+use crate::versioning::models::{Package, Registry};
 use anyhow::Result;
 use reqwest::Client;
 use serde_json::Value;
 use chrono::Utc;
+use crate::versioning::maven_bom::{BomResolver, MavenCoordinate};
 use async_trait::async_trait;
 
 pub struct GradleProvider {
     client: Client,
+    /// 项目导入的 BOM，按声明顺序
+    boms: Vec<MavenCoordinate>,
+}
+
+impl GradleProvider {
+    pub fn new(client: Client) -> Self {
+        Self { client, boms: Vec::new() }
+    }
+
+    /// 设置项目导入的 BOM，查询时返回 BOM 管理的生效版本
+    pub fn with_boms(mut self, boms: Vec<MavenCoordinate>) -> Self {
+        self.boms = boms;
+        self
+    }
+
+    async fn fetch_plugin(&self, url: &str) -> Result<Value> {
+        Ok(self.client.get(url).send().await?.error_for_status()?.json().await?)
+    }
+
+    async fn managed_version(&self, key: &str) -> Option<String> {
+        if self.boms.is_empty() {
+            return None;
+        }
+        BomResolver::new(self.client.clone())
+            .effective_version(&self.boms, key)
+            .await
+            .ok()
+            .flatten()
+            .map(|managed| managed.version)
+    }
+}
+
+/// 由插件门户响应构建包信息；`managed` 为 BOM 管理的生效版本
+fn plugin_to_package(package_name: &str, response: &Value, managed: Option<String>) -> Package {
+    Package {
+        name: package_name.to_string(),
+        version: managed.unwrap_or_else(|| response["version"].as_str().unwrap_or("unknown").to_string()),
+        description: response["description"].as_str().unwrap_or("").to_string(),
+        license: "".to_string(),
+        homepage: response["website"].as_str().map(|s| s.to_string()),
+        repository: response["vcs"].as_str().map(|s| s.to_string()),
+        author: None,
+        release_date: Utc::now(),
+        download_count: None,
+        available_versions: Vec::new(),
+    }
 }
 
 #[async_trait]
 impl crate::versioning::traits::PackageProvider for GradleProvider {
+    fn registry(&self) -> Registry {
+        Registry::Gradle
+    }
+
+    fn source_url(&self, package_name: &str) -> String {
+        format!("{}/gradle/{}", Registry::Gradle.base_url(), package_name)
+    }
+
     async fn get_package_info(&self, package_name: &str) -> Result<Package> {
         // Gradle plugins portal API
-        let url = format!("https://plugins.gradle.org/api/gradle/{}", package_name);
-        let response: Value = self.client.get(&url).send().await?.json().await?;
-        
-        Ok(Package {
-            name: package_name.to_string(),
-            version: response["version"].as_str().unwrap_or("unknown").to_string(),
-            description: response["description"].as_str().unwrap_or("").to_string(),
-            license: "".to_string(),
-            homepage: response["website"].as_str().map(|s| s.to_string()),
-            repository: response["vcs"].as_str().map(|s| s.to_string()),
-            author: None,
-            release_date: Utc::now(),
-            download_count: None,
-            available_versions: Vec::new(),
-        })
+        let url = self.source_url(package_name);
+        // platform()/mavenBom 管理的依赖以 BOM 中的版本为准，此时插件门户查询失败不影响结果
+        let managed = self.managed_version(package_name).await;
+        let response: Value = match self.fetch_plugin(&url).await {
+            Ok(response) => response,
+            Err(_) if managed.is_some() => Value::Null,
+            Err(e) => return Err(e),
+        };
+        Ok(plugin_to_package(package_name, &response, managed))
     }
     
     async fn get_dependencies(&self, _package: &Package) -> Result<Option<serde_json::Value>> {
         Ok(None)
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::models::VersionInfo;
+    use crate::versioning::models::version::assert_normalized_shape;
+    use serde_json::json;
+
+    #[test]
+    fn test_plugin_to_package_shape() {
+        let response = json!({ "version": "8.5.0", "website": "https://github.com/GradleUp/shadow", "vcs": "https://github.com/GradleUp/shadow" });
+        let package = plugin_to_package("com.gradleup.shadow", &response, None);
+        let info = VersionInfo::from_package(package, Registry::Gradle, "https://plugins.gradle.org/api/gradle/com.gradleup.shadow");
+        assert_eq!(info.links.homepage.as_deref(), Some("https://github.com/GradleUp/shadow"));
+        assert_normalized_shape(&info);
+
+        // 门户不可用但有 BOM 管理版本时仍返回有效信息
+        let managed = plugin_to_package("org.slf4j:slf4j-api", &Value::Null, Some("2.0.13".to_string()));
+        assert_eq!(managed.version, "2.0.13");
+    }
+}
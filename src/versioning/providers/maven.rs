@@ -1,19 +1,74 @@
-use crate::versioning::models::package::Package;
+use crate::versioning::models::{Package, Registry};
 use anyhow::Result;
 use reqwest::Client;
 use serde_json::Value;
-use chrono::Utc;
+use chrono::{TimeZone, Utc};
+use crate::versioning::maven_bom::{BomResolver, MavenCoordinate};
 use async_trait::async_trait;
 
 pub struct MavenProvider {
     client: Client,
+    /// 项目导入的 BOM，按声明顺序
+    boms: Vec<MavenCoordinate>,
+}
+
+impl MavenProvider {
+    pub fn new(client: Client) -> Self {
+        Self { client, boms: Vec::new() }
+    }
+
+    /// 设置项目导入的 BOM，查询时返回 BOM 管理的生效版本
+    pub fn with_boms(mut self, boms: Vec<MavenCoordinate>) -> Self {
+        self.boms = boms;
+        self
+    }
+
+    async fn managed_version(&self, key: &str) -> Option<String> {
+        if self.boms.is_empty() {
+            return None;
+        }
+        BomResolver::new(self.client.clone())
+            .effective_version(&self.boms, key)
+            .await
+            .ok()
+            .flatten()
+            .map(|managed| managed.version)
+    }
+}
+
+/// 由 Maven Central 搜索结果构建包信息；`managed` 为 BOM 管理的生效版本
+fn search_doc_to_package(package_name: &str, doc: &Value, managed: Option<String>) -> Package {
+    Package {
+        name: package_name.to_string(),
+        version: managed.unwrap_or_else(|| doc["latestVersion"].as_str().unwrap_or("unknown").to_string()),
+        description: doc["p"].as_str().unwrap_or("").to_string(),
+        license: "".to_string(),
+        homepage: None,
+        repository: None,
+        author: None,
+        // timestamp 为毫秒时间戳
+        release_date: doc["timestamp"]
+            .as_i64()
+            .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+            .unwrap_or_else(Utc::now),
+        download_count: None,
+        available_versions: Vec::new(),
+    }
 }
 
 #[async_trait]
 impl crate::versioning::traits::PackageProvider for MavenProvider {
+    fn registry(&self) -> Registry {
+        Registry::Maven
+    }
+
+    fn source_url(&self, package_name: &str) -> String {
+        format!("{}?q=g:%22{}%22&rows=1&wt=json", Registry::Maven.base_url(), package_name)
+    }
+
     async fn get_package_info(&self, package_name: &str) -> Result<Package> {
         // Maven Central API URL
-        let url = format!("https://search.maven.org/solrsearch/select?q=g:%22{}%22&rows=1&wt=json", package_name);
+        let url = self.source_url(package_name);
         let response: Value = self.client.get(&url).send().await?.json().await?;
         
         let docs = response["response"]["docs"].as_array();
@@ -21,18 +76,9 @@ impl crate::versioning::traits::PackageProvider for MavenProvider {
         let docs = docs.unwrap_or(&empty_vec);
         
         if let Some(doc) = docs.first() {
-            Ok(Package {
-                name: package_name.to_string(),
-                version: doc["latestVersion"].as_str().unwrap_or("unknown").to_string(),
-                description: doc["p"].as_str().unwrap_or("").to_string(),
-                license: "".to_string(),
-                homepage: None,
-                repository: None,
-                author: None,
-                release_date: Utc::now(),
-                download_count: None,
-                available_versions: Vec::new(),
-            })
+            // BOM 管理的依赖以 BOM 中的版本为准
+            let managed = self.managed_version(package_name).await;
+            Ok(search_doc_to_package(package_name, doc, managed))
         } else {
             Err(anyhow::anyhow!("Package not found: {}", package_name))
         }
@@ -41,4 +87,23 @@ impl crate::versioning::traits::PackageProvider for MavenProvider {
     async fn get_dependencies(&self, _package: &Package) -> Result<Option<serde_json::Value>> {
         Ok(None)
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::models::VersionInfo;
+    use crate::versioning::models::version::assert_normalized_shape;
+    use serde_json::json;
+
+    #[test]
+    fn test_search_doc_to_package_shape() {
+        let doc = json!({ "latestVersion": "3.14.0", "p": "jar", "timestamp": 1_700_000_000_000i64 });
+        let package = search_doc_to_package("org.apache.commons", &doc, None);
+        assert_eq!(package.release_date.timestamp(), 1_700_000_000);
+        assert_eq!(search_doc_to_package("org.apache.commons", &doc, Some("3.12.0".to_string())).version, "3.12.0");
+
+        let info = VersionInfo::from_package(package, Registry::Maven, "https://search.maven.org/solrsearch/select?q=g:%22org.apache.commons%22&rows=1&wt=json");
+        assert_normalized_shape(&info);
+    }
+}
@@ -1,40 +1,109 @@
-use crate::versioning::models::package::Package;
+use crate::versioning::models::{Package, Registry};
 use anyhow::Result;
 use reqwest::Client;
 use serde_json::Value;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use crate::versioning::npm_registry::{NpmRegistryClient, NpmRegistryConfig, NpmSpecifier};
 use async_trait::async_trait;
 
 pub struct NpmProvider {
-    client: Client,
+    registry: NpmRegistryClient,
+}
+
+impl NpmProvider {
+    pub fn new(client: Client) -> Self {
+        Self::with_config(client, NpmRegistryConfig::load(None))
+    }
+
+    /// 使用指定的 .npmrc 配置（作用域私有注册表及认证）
+    pub fn with_config(client: Client, config: NpmRegistryConfig) -> Self {
+        Self {
+            registry: NpmRegistryClient::new(client, config),
+        }
+    }
+
+    /// 获取 dist-tags（latest、next、lts 等）
+    pub async fn dist_tags(&self, package_name: &str) -> Result<BTreeMap<String, String>> {
+        self.registry.dist_tags(package_name).await
+    }
+
+    /// 解析清单中的依赖声明，返回实际的注册表包名；`workspace:` 等本地依赖返回 None
+    pub fn resolve_manifest_dependency(dependency_name: &str, spec: &str) -> Option<String> {
+        NpmSpecifier::parse(spec).registry_package(dependency_name).map(String::from)
+    }
+}
+
+/// 由 packument 构建包信息，发布时间取 `time` 中 latest 版本的记录
+fn packument_to_package(package_name: &str, response: &Value) -> Package {
+    let latest_version = response["dist-tags"]["latest"]
+        .as_str()
+        .unwrap_or("unknown")
+        .to_string();
+    let release_date = response["time"][latest_version.as_str()]
+        .as_str()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+
+    Package {
+        name: package_name.to_string(),
+        version: latest_version,
+        description: response["description"].as_str().unwrap_or("").to_string(),
+        license: response["license"].as_str().unwrap_or("").to_string(),
+        homepage: response["homepage"].as_str().map(|s| s.to_string()),
+        repository: response["repository"]["url"].as_str().map(|s| s.to_string()),
+        author: response["author"]["name"].as_str().map(|s| s.to_string()),
+        release_date,
+        download_count: None,
+        available_versions: response["versions"]
+            .as_object()
+            .map(|versions| versions.keys().cloned().collect())
+            .unwrap_or_default(),
+    }
 }
 
 #[async_trait]
 impl crate::versioning::traits::PackageProvider for NpmProvider {
+    fn registry(&self) -> Registry {
+        Registry::Npm
+    }
+
+    fn source_url(&self, package_name: &str) -> String {
+        self.registry.packument_url(package_name)
+    }
+
     async fn get_package_info(&self, package_name: &str) -> Result<Package> {
-        let url = format!("https://registry.npmjs.org/{}", package_name);
-        let response: Value = self.client.get(&url).send().await?.json().await?;
-        
-        let latest_version = response["dist-tags"]["latest"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string();
-            
-        Ok(Package {
-            name: package_name.to_string(),
-            version: latest_version,
-            description: response["description"].as_str().unwrap_or("").to_string(),
-            license: response["license"].as_str().unwrap_or("").to_string(),
-            homepage: response["homepage"].as_str().map(|s| s.to_string()),
-            repository: response["repository"]["url"].as_str().map(|s| s.to_string()),
-            author: response["author"]["name"].as_str().map(|s| s.to_string()),
-            release_date: Utc::now(),
-            download_count: None,
-            available_versions: Vec::new(),
-        })
+        let response: Value = self.registry.fetch_packument(package_name).await?;
+        Ok(packument_to_package(package_name, &response))
     }
     
     async fn get_dependencies(&self, _package: &Package) -> Result<Option<serde_json::Value>> {
         Ok(None)
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::models::VersionInfo;
+    use crate::versioning::models::version::assert_normalized_shape;
+    use serde_json::json;
+
+    #[test]
+    fn test_packument_to_package_shape() {
+        let packument = json!({
+            "dist-tags": { "latest": "18.3.1", "next": "19.0.0-rc.1" },
+            "license": "MIT",
+            "repository": { "url": "git+https://github.com/facebook/react.git" },
+            "time": { "18.3.1": "2024-04-26T16:42:00.000Z" },
+            "versions": { "18.3.1": {}, "19.0.0-rc.1": {} }
+        });
+        let package = packument_to_package("react", &packument);
+        assert_eq!(package.release_date.to_rfc3339(), "2024-04-26T16:42:00+00:00");
+
+        let info = VersionInfo::from_package(package, Registry::Npm, "https://registry.npmjs.org/react");
+        assert_eq!(info.license.as_deref(), Some("MIT"));
+        assert_normalized_shape(&info);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::versioning::models::package::Package;
+use crate::versioning::models::{Package, Registry};
 use anyhow::Result;
 use reqwest::Client;
 use serde_json::Value;
@@ -9,36 +9,70 @@ pub struct NugetProvider {
     client: Client,
 }
 
+/// 由 flatcontainer 版本索引构建包信息，最新版本取最后一个非预发布版本
+fn index_to_package(package_name: &str, response: &Value) -> Package {
+    let versions: Vec<String> = response["versions"]
+        .as_array()
+        .map(|versions| versions.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    let latest_version = versions.iter()
+        .rev()
+        .find(|v| !v.contains('-'))
+        .or(versions.last())
+        .cloned()
+        .unwrap_or_else(|| "unknown".to_string());
+
+    Package {
+        name: package_name.to_string(),
+        version: latest_version,
+        description: "".to_string(),
+        license: "".to_string(),
+        homepage: None,
+        repository: None,
+        author: None,
+        release_date: Utc::now(),
+        download_count: None,
+        available_versions: versions,
+    }
+}
+
 #[async_trait]
 impl crate::versioning::traits::PackageProvider for NugetProvider {
+    fn registry(&self) -> Registry {
+        Registry::NuGet
+    }
+
+    fn source_url(&self, package_name: &str) -> String {
+        format!("https://api.nuget.org/v3-flatcontainer/{}/index.json", package_name.to_lowercase())
+    }
+
     async fn get_package_info(&self, package_name: &str) -> Result<Package> {
         // NuGet API
-        let url = format!("https://api.nuget.org/v3-flatcontainer/{}/index.json", package_name.to_lowercase());
+        let url = self.source_url(package_name);
         let response: Value = self.client.get(&url).send().await?.json().await?;
-        
-        let versions = response["versions"].as_array();
-        let empty_vec = vec![];
-        let versions = versions.unwrap_or(&empty_vec);
-        let latest_version = versions.last()
-            .and_then(|v| v.as_str())
-            .unwrap_or("unknown")
-            .to_string();
-        
-        Ok(Package {
-            name: package_name.to_string(),
-            version: latest_version,
-            description: "".to_string(),
-            license: "".to_string(),
-            homepage: None,
-            repository: None,
-            author: None,
-            release_date: Utc::now(),
-            download_count: None,
-            available_versions: Vec::new(),
-        })
+        Ok(index_to_package(package_name, &response))
     }
     
     async fn get_dependencies(&self, _package: &Package) -> Result<Option<serde_json::Value>> {
         Ok(None)
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::models::VersionInfo;
+    use crate::versioning::models::version::assert_normalized_shape;
+    use serde_json::json;
+
+    #[test]
+    fn test_index_to_package_shape() {
+        let index = json!({ "versions": ["13.0.2", "13.0.3", "13.0.4-beta1"] });
+        let package = index_to_package("Newtonsoft.Json", &index);
+        assert_eq!(package.version, "13.0.3");
+
+        let info = VersionInfo::from_package(package, Registry::NuGet, "https://api.nuget.org/v3-flatcontainer/newtonsoft.json/index.json");
+        assert_eq!(info.available_versions.len(), 3);
+        assert_normalized_shape(&info);
+    }
+}
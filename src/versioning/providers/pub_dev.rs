@@ -1,8 +1,8 @@
-use crate::versioning::models::package::Package;
+use crate::versioning::models::{Package, Registry};
 use anyhow::Result;
 use reqwest::Client;
 use serde_json::Value;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use async_trait::async_trait;
 
 pub struct PubDevProvider {
@@ -13,30 +13,75 @@ impl PubDevProvider {
     // 移除未使用的new方法
 }
 
+/// 由 pub.dev 包接口响应构建包信息
+fn api_to_package(package_name: &str, response: &Value) -> Package {
+    let latest = &response["latest"];
+
+    Package {
+        name: package_name.to_string(),
+        version: latest["version"].as_str().unwrap_or("unknown").to_string(),
+        description: latest["pubspec"]["description"].as_str().unwrap_or("").to_string(),
+        license: "".to_string(),
+        homepage: latest["pubspec"]["homepage"].as_str().map(|s| s.to_string()),
+        repository: latest["pubspec"]["repository"].as_str().map(|s| s.to_string()),
+        author: None,
+        release_date: latest["published"]
+            .as_str()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now),
+        download_count: None,
+        available_versions: response["versions"]
+            .as_array()
+            .map(|versions| versions.iter().filter_map(|v| v["version"].as_str().map(String::from)).collect())
+            .unwrap_or_default(),
+    }
+}
+
 #[async_trait]
 impl crate::versioning::traits::PackageProvider for PubDevProvider {
+    fn registry(&self) -> Registry {
+        Registry::Pub
+    }
+
+    fn source_url(&self, package_name: &str) -> String {
+        format!("{}/packages/{}", Registry::Pub.base_url(), package_name)
+    }
+
     async fn get_package_info(&self, package_name: &str) -> Result<Package> {
         // pub.dev API
-        let url = format!("https://pub.dev/api/packages/{}", package_name);
+        let url = self.source_url(package_name);
         let response: Value = self.client.get(&url).send().await?.json().await?;
-        
-        let latest = &response["latest"];
-        
-        Ok(Package {
-            name: package_name.to_string(),
-            version: latest["version"].as_str().unwrap_or("unknown").to_string(),
-            description: latest["pubspec"]["description"].as_str().unwrap_or("").to_string(),
-            license: "".to_string(),
-            homepage: latest["pubspec"]["homepage"].as_str().map(|s| s.to_string()),
-            repository: latest["pubspec"]["repository"].as_str().map(|s| s.to_string()),
-            author: None,
-            release_date: Utc::now(),
-            download_count: None,
-            available_versions: Vec::new(),
-        })
+        Ok(api_to_package(package_name, &response))
     }
     
     async fn get_dependencies(&self, _package: &Package) -> Result<Option<serde_json::Value>> {
         Ok(None)
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::models::VersionInfo;
+    use crate::versioning::models::version::assert_normalized_shape;
+    use serde_json::json;
+
+    #[test]
+    fn test_api_to_package_shape() {
+        let response = json!({
+            "latest": {
+                "version": "1.2.1",
+                "published": "2024-02-20T10:00:00.000Z",
+                "pubspec": { "repository": "https://github.com/dart-lang/http" }
+            },
+            "versions": [{ "version": "1.2.0" }, { "version": "1.2.1" }]
+        });
+        let package = api_to_package("http", &response);
+        assert_eq!(package.available_versions, vec!["1.2.0", "1.2.1"]);
+
+        let info = VersionInfo::from_package(package, Registry::Pub, "https://pub.dev/api/packages/http");
+        assert_eq!(info.published_at.to_rfc3339(), "2024-02-20T10:00:00+00:00");
+        assert_normalized_shape(&info);
+    }
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use chrono::{Utc, DateTime};
 use crate::versioning::base::VersionChecker;
 use crate::versioning::models::{Package, VersionInfo, Registry};
+use crate::versioning::wheels::{analyze_release_files, WheelCompatibility};
 
 /// PyPI 包信息
 #[derive(Debug, Deserialize, Serialize)]
@@ -15,6 +16,10 @@ pub struct PyPIPackageInfo {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PyPIInfo {
     pub name: String,
+    #[serde(default)]
+    pub yanked: bool,
+    #[serde(default)]
+    pub project_urls: Option<std::collections::HashMap<String, String>>,
     pub version: String,
     pub summary: String,
     pub description: String,
@@ -30,6 +35,12 @@ pub struct PyPIRelease {
     pub upload_time: String,
 }
 
+/// 单个版本的 PyPI 信息（`/{name}/{version}/json`）
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PyPIVersionInfo {
+    pub urls: Vec<PyPIRelease>,
+}
+
 /// PyPI 版本检查器
 pub struct PyPIChecker {
     client: reqwest::Client,
@@ -37,6 +48,75 @@ pub struct PyPIChecker {
 }
 
 impl PyPIChecker {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            base_url: "https://pypi.org/pypi".to_string(),
+        }
+    }
+
+    /// 报告指定版本（默认最新版）各平台/Python版本可用的wheel，标记只能源码构建的平台
+    pub async fn wheel_compatibility(&self, package: &Package, version: Option<&str>) -> Result<WheelCompatibility> {
+        let version = match version {
+            Some(version) => version.to_string(),
+            None => self.check_version(package).await?.version,
+        };
+        let url = format!("{}/{}/{}/json", self.base_url, package.name, version);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("PyPI API请求失败: {}", response.status()));
+        }
+
+        let release: PyPIVersionInfo = response.json().await?;
+        Ok(analyze_release_files(&version, release.urls.iter().map(|f| f.filename.as_str())))
+    }
+
+    /// 将 `/{name}/json` 响应转换为规范化版本信息
+    fn normalize(&self, package: &Package, package_info: PyPIPackageInfo) -> VersionInfo {
+        // 解析最新版本的发布日期
+        let release_date = package_info.releases
+            .get(&package_info.info.version)
+            .and_then(|releases| releases.first())
+            .map(|first_release| self.parse_release_date(&first_release.upload_time))
+            .unwrap_or_else(Utc::now);
+
+        // 获取所有版本列表
+        let mut versions: Vec<String> = package_info.releases.keys().cloned().collect();
+        versions.sort_by(|a, b| {
+            // 尝试按语义版本排序
+            match version_compare::compare(a, b) {
+                Ok(version_compare::Cmp::Lt) => std::cmp::Ordering::Less,
+                Ok(version_compare::Cmp::Eq) => std::cmp::Ordering::Equal,
+                Ok(version_compare::Cmp::Gt) => std::cmp::Ordering::Greater,
+                Ok(version_compare::Cmp::Ne) => std::cmp::Ordering::Equal, // 不相等但无法比较大小，视为相等
+                Ok(version_compare::Cmp::Le) => std::cmp::Ordering::Less,  // 小于等于，视为小于
+                Ok(version_compare::Cmp::Ge) => std::cmp::Ordering::Greater, // 大于等于，视为大于
+                Err(_) => std::cmp::Ordering::Equal,
+            }
+        });
+
+        let info = package_info.info;
+        let mut normalized = VersionInfo::from_package(
+            package.clone(),
+            Registry::PyPI,
+            format!("{}/{}/json", self.base_url, package.name),
+        );
+        normalized.version = info.version.clone();
+        normalized.published_at = release_date;
+        normalized.yanked = info.yanked;
+        normalized.license = Some(info.license).filter(|l| !l.is_empty()).or(normalized.license);
+        let project_url = |key: &str| info.project_urls.as_ref().and_then(|urls| urls.get(key).cloned());
+        normalized.links.homepage = Some(info.home_page).filter(|h| !h.is_empty()).or_else(|| project_url("Homepage"));
+        normalized.links.repository = project_url("Source").or_else(|| project_url("Repository"));
+        normalized.links.documentation = project_url("Documentation");
+        normalized.available_versions = versions;
+        normalized
+    }
+
     /// 解析PyPI的发布时间
     fn parse_release_date(&self, upload_time: &str) -> chrono::DateTime<Utc> {
         // PyPI的时间格式: "2023-10-20T14:30:15"
@@ -65,43 +145,7 @@ impl VersionChecker for PyPIChecker {
         }
         
         let package_info: PyPIPackageInfo = response.json().await?;
-        
-        // 解析最新版本的发布日期
-        let release_date = if let Some(releases) = package_info.releases.get(&package_info.info.version) {
-            if let Some(first_release) = releases.first() {
-                self.parse_release_date(&first_release.upload_time)
-            } else {
-                Utc::now()
-            }
-        } else {
-            Utc::now()
-        };
-
-        // 获取所有版本列表
-        let mut versions: Vec<String> = package_info.releases.keys().cloned().collect();
-        versions.sort_by(|a, b| {
-            // 尝试按语义版本排序
-            match version_compare::compare(a, b) {
-                Ok(version_compare::Cmp::Lt) => std::cmp::Ordering::Less,
-                Ok(version_compare::Cmp::Eq) => std::cmp::Ordering::Equal,
-                Ok(version_compare::Cmp::Gt) => std::cmp::Ordering::Greater,
-                Ok(version_compare::Cmp::Ne) => std::cmp::Ordering::Equal, // 不相等但无法比较大小，视为相等
-                Ok(version_compare::Cmp::Le) => std::cmp::Ordering::Less,  // 小于等于，视为小于
-                Ok(version_compare::Cmp::Ge) => std::cmp::Ordering::Greater, // 大于等于，视为大于
-                Err(_) => std::cmp::Ordering::Equal,
-            }
-        });
-        
-        Ok(VersionInfo {
-            package: package.clone(),
-            latest_stable: package_info.info.version,
-            latest_preview: None, // PyPI不区分预览版
-            release_date,
-            eol_date: None,
-            available_versions: versions,
-            dependencies: None,
-            downloads: None,
-        })
+        Ok(self.normalize(package, package_info))
     }
     
     async fn list_versions(&self, package: &Package) -> Result<Vec<String>> {
@@ -138,4 +182,46 @@ impl VersionChecker for PyPIChecker {
         // PyPI API不直接提供依赖信息，需要解析setup.py或requirements.txt
         Ok(None)
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::versioning::models::version::assert_normalized_shape;
+
+    #[test]
+    fn test_normalize_pypi_shape() {
+        let checker = PyPIChecker::new(reqwest::Client::new());
+        let package_info: PyPIPackageInfo = serde_json::from_value(serde_json::json!({
+            "info": {
+                "name": "requests",
+                "version": "2.31.0",
+                "summary": "",
+                "description": "",
+                "author": "",
+                "license": "Apache 2.0",
+                "home_page": "",
+                "project_urls": { "Source": "https://github.com/psf/requests" }
+            },
+            "releases": {
+                "2.31.0": [{ "filename": "requests-2.31.0.tar.gz", "python_version": "source", "upload_time": "2023-05-22T15:12:44" }]
+            }
+        })).unwrap();
+        let package = Package {
+            name: "requests".to_string(),
+            version: String::new(),
+            description: String::new(),
+            license: String::new(),
+            homepage: None,
+            repository: None,
+            author: None,
+            release_date: Utc::now(),
+            download_count: None,
+            available_versions: Vec::new(),
+        };
+        let info = checker.normalize(&package, package_info);
+        assert_eq!(info.version, "2.31.0");
+        assert_eq!(info.links.repository.as_deref(), Some("https://github.com/psf/requests"));
+        assert_normalized_shape(&info);
+    }
+}
@@ -1,9 +1,26 @@
 use async_trait::async_trait;
 use anyhow::Result;
-use crate::versioning::models::Package;
+use crate::versioning::models::{Package, Registry, VersionInfo};
 
 #[async_trait]
 pub trait PackageProvider: Send + Sync {
+    /// 包所属的注册表
+    fn registry(&self) -> Registry;
+
     async fn get_package_info(&self, package_name: &str) -> Result<Package>;
     async fn get_dependencies(&self, package: &Package) -> Result<Option<serde_json::Value>>;
-} 
\ No newline at end of file
+
+    /// 查询包信息时实际请求的API地址，用于记录来源
+    fn source_url(&self, package_name: &str) -> String {
+        format!("{}/{}", self.registry().base_url(), package_name)
+    }
+
+    /// 规范化版本信息：由包信息构建并校验，各提供者返回统一结构
+    async fn get_version_info(&self, package_name: &str) -> Result<VersionInfo> {
+        let package = self.get_package_info(package_name).await?;
+        let mut info = VersionInfo::from_package(package, self.registry(), self.source_url(package_name));
+        info.dependencies = self.get_dependencies(&info.package).await?;
+        info.validate()?;
+        Ok(info)
+    }
+}
@@ -0,0 +1,224 @@
+//! PyPI wheel / ABI 兼容性分析
+//!
+//! 根据发行文件名中的 wheel 标签（PEP 427/425）统计每个平台、每个 Python 版本
+//! 可用的预编译 wheel，并标记只能从源码构建的平台（例如 Windows 上缺少 wheel，
+//! 安装时需要本地编译器，经常导致安装失败）。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 平台族
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlatformFamily {
+    Windows,
+    Macos,
+    Linux,
+}
+
+impl PlatformFamily {
+    pub const ALL: [PlatformFamily; 3] = [PlatformFamily::Windows, PlatformFamily::Macos, PlatformFamily::Linux];
+
+    /// 识别平台标签，`any` 返回 None
+    pub fn from_platform_tag(tag: &str) -> Option<Self> {
+        if tag.starts_with("win") {
+            Some(PlatformFamily::Windows)
+        } else if tag.starts_with("macosx") {
+            Some(PlatformFamily::Macos)
+        } else if tag.starts_with("manylinux") || tag.starts_with("musllinux") || tag.starts_with("linux") {
+            Some(PlatformFamily::Linux)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlatformFamily::Windows => "windows",
+            PlatformFamily::Macos => "macos",
+            PlatformFamily::Linux => "linux",
+        }
+    }
+}
+
+/// 解析后的 wheel 文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WheelFile {
+    pub filename: String,
+    pub python_tags: Vec<String>,
+    pub abi_tags: Vec<String>,
+    pub platform_tags: Vec<String>,
+}
+
+impl WheelFile {
+    /// 解析 `{name}-{ver}(-{build})?-{python}-{abi}-{platform}.whl`，标签可用 `.` 压缩多个
+    pub fn parse(filename: &str) -> Option<Self> {
+        let stem = filename.strip_suffix(".whl")?;
+        let parts: Vec<&str> = stem.split('-').collect();
+        if parts.len() < 5 {
+            return None;
+        }
+        let split = |tag: &str| tag.split('.').map(String::from).collect::<Vec<_>>();
+        let n = parts.len();
+        Some(Self {
+            filename: filename.to_string(),
+            python_tags: split(parts[n - 3]),
+            abi_tags: split(parts[n - 2]),
+            platform_tags: split(parts[n - 1]),
+        })
+    }
+
+    /// 纯 Python wheel（`py3-none-any`）
+    pub fn is_pure(&self) -> bool {
+        self.platform_tags.iter().all(|t| t == "any")
+    }
+
+    pub fn is_abi3(&self) -> bool {
+        self.abi_tags.iter().any(|t| t == "abi3")
+    }
+
+    pub fn platforms(&self) -> BTreeSet<PlatformFamily> {
+        self.platform_tags.iter().filter_map(|t| PlatformFamily::from_platform_tag(t)).collect()
+    }
+
+    /// wheel 支持的 Python 版本描述：`cp311` -> `3.11`，abi3 wheel 为 `3.8+`，通用 wheel 为 `py3`
+    pub fn python_versions(&self) -> Vec<String> {
+        self.python_tags.iter().map(|tag| {
+            let version = tag.strip_prefix("cp").or_else(|| tag.strip_prefix("pp")).and_then(|digits| {
+                let (major, minor) = digits.split_at(1.min(digits.len()));
+                (!minor.is_empty() && digits.chars().all(|c| c.is_ascii_digit())).then(|| format!("{}.{}", major, minor))
+            });
+            match version {
+                Some(version) if self.is_abi3() => format!("{}+", version),
+                Some(version) if tag.starts_with("pp") => format!("pypy{}", version),
+                Some(version) => version,
+                None => tag.clone(),
+            }
+        }).collect()
+    }
+}
+
+/// 单个平台的 wheel 覆盖情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlatformWheels {
+    /// 有预编译 wheel 的 Python 版本
+    pub python_versions: Vec<String>,
+    pub wheel_count: usize,
+}
+
+/// 一个发布版本的 wheel 兼容性报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WheelCompatibility {
+    pub version: String,
+    pub has_sdist: bool,
+    /// 存在 `none-any` 纯 Python wheel，所有平台均可直接安装
+    pub pure_python: bool,
+    pub platforms: BTreeMap<String, PlatformWheels>,
+    /// 没有任何 wheel、只能从源码构建的平台
+    pub source_only_platforms: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl WheelCompatibility {
+    pub fn is_source_only_on(&self, platform: PlatformFamily) -> bool {
+        self.source_only_platforms.iter().any(|p| p == platform.as_str())
+    }
+}
+
+/// 分析一个版本的发行文件列表
+pub fn analyze_release_files<'a>(version: &str, filenames: impl IntoIterator<Item = &'a str>) -> WheelCompatibility {
+    let mut has_sdist = false;
+    let mut wheels = Vec::new();
+    for filename in filenames {
+        if filename.ends_with(".whl") {
+            wheels.extend(WheelFile::parse(filename));
+        } else if filename.ends_with(".tar.gz") || filename.ends_with(".zip") || filename.ends_with(".tar.bz2") {
+            has_sdist = true;
+        }
+    }
+
+    let pure_python = wheels.iter().any(|w| w.is_pure());
+    let mut platforms: BTreeMap<PlatformFamily, (BTreeSet<String>, usize)> = BTreeMap::new();
+    for wheel in &wheels {
+        for platform in wheel.platforms() {
+            let entry = platforms.entry(platform).or_default();
+            entry.0.extend(wheel.python_versions());
+            entry.1 += 1;
+        }
+    }
+
+    let mut source_only_platforms = Vec::new();
+    let mut warnings = Vec::new();
+    if !pure_python {
+        for platform in PlatformFamily::ALL {
+            if platforms.contains_key(&platform) {
+                continue;
+            }
+            if has_sdist {
+                source_only_platforms.push(platform.as_str().to_string());
+                if platform == PlatformFamily::Windows && !wheels.is_empty() {
+                    warnings.push(format!(
+                        "{} 在 Windows 上没有预编译wheel，只能从源码构建，通常需要安装 Visual C++ Build Tools",
+                        version
+                    ));
+                }
+            } else if !wheels.is_empty() {
+                warnings.push(format!("{} 没有 {} 平台的wheel，也没有源码包，无法在该平台安装", version, platform.as_str()));
+            }
+        }
+        if wheels.is_empty() && has_sdist {
+            warnings.push(format!("{} 只发布了源码包，所有平台都需要本地构建", version));
+        }
+    }
+
+    WheelCompatibility {
+        version: version.to_string(),
+        has_sdist,
+        pure_python,
+        platforms: platforms
+            .into_iter()
+            .map(|(platform, (versions, wheel_count))| {
+                (platform.as_str().to_string(), PlatformWheels { python_versions: versions.into_iter().collect(), wheel_count })
+            })
+            .collect(),
+        source_only_platforms,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wheel_tags() {
+        let wheel = WheelFile::parse("numpy-1.26.2-cp311-cp311-manylinux_2_17_x86_64.manylinux2014_x86_64.whl").unwrap();
+        assert_eq!(wheel.python_versions(), vec!["3.11"]);
+        assert_eq!(wheel.platform_tags.len(), 2);
+        assert_eq!(wheel.platforms().into_iter().collect::<Vec<_>>(), vec![PlatformFamily::Linux]);
+
+        let abi3 = WheelFile::parse("cryptography-41.0.7-cp37-abi3-win_amd64.whl").unwrap();
+        assert_eq!(abi3.python_versions(), vec!["3.7+"]);
+
+        let pure = WheelFile::parse("requests-2.31.0-py3-none-any.whl").unwrap();
+        assert!(pure.is_pure());
+        assert!(WheelFile::parse("requests-2.31.0.tar.gz").is_none());
+    }
+
+    #[test]
+    fn test_source_only_on_windows() {
+        let report = analyze_release_files("0.9.0", [
+            "pkg-0.9.0.tar.gz",
+            "pkg-0.9.0-cp311-cp311-manylinux_2_17_x86_64.whl",
+            "pkg-0.9.0-cp312-cp312-macosx_11_0_arm64.whl",
+        ]);
+        assert!(report.is_source_only_on(PlatformFamily::Windows));
+        assert!(!report.is_source_only_on(PlatformFamily::Linux));
+        assert_eq!(report.platforms["linux"].python_versions, vec!["3.11"]);
+        assert_eq!(report.warnings.len(), 1);
+
+        let pure = analyze_release_files("2.31.0", ["requests-2.31.0.tar.gz", "requests-2.31.0-py3-none-any.whl"]);
+        assert!(pure.pure_python);
+        assert!(pure.source_only_platforms.is_empty());
+    }
+}
@@ -1,10 +1,16 @@
 //! 批量嵌入功能测试
 
 use anyhow::Result;
+use grape_mcp_devtools::tools::embedder::embedder_from_env;
 use grape_mcp_devtools::tools::vector_docs_tool::VectorDocsTool;
 use std::env;
 use tokio;
 
+/// 在临时目录中打开向量工具并使用环境变量配置的嵌入服务，不读写用户目录下的全局缓存
+fn open_tool(dir: &tempfile::TempDir) -> Result<VectorDocsTool> {
+    Ok(VectorDocsTool::open_local(dir.path().to_path_buf())?.with_embedder(embedder_from_env(reqwest::Client::new())?))
+}
+
 #[tokio::test]
 async fn test_batch_embedding_performance() -> Result<()> {
     // 设置测试环境
@@ -13,7 +19,8 @@ async fn test_batch_embedding_performance() -> Result<()> {
         return Ok(());
     }
 
-    let vector_tool = VectorDocsTool::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let vector_tool = open_tool(&dir)?;
     
     // 测试单个文本的嵌入
     let start_time = std::time::Instant::now();
@@ -60,7 +67,8 @@ async fn test_embedding_cache_mechanism() -> Result<()> {
         return Ok(());
     }
 
-    let vector_tool = VectorDocsTool::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let vector_tool = open_tool(&dir)?;
     
     let test_content = "缓存测试内容：这段文本将被用来测试嵌入向量的缓存机制。";
     
@@ -89,7 +97,8 @@ async fn test_embedding_cache_mechanism() -> Result<()> {
 
 #[tokio::test] 
 async fn test_hybrid_search_functionality() -> Result<()> {
-    let vector_tool = VectorDocsTool::new().await?;
+    let dir = tempfile::TempDir::new()?;
+    let vector_tool = VectorDocsTool::open_local(dir.path().to_path_buf())?;
     
     // 测试混合搜索功能（即使没有嵌入向量也应该工作）
     let query_text = "Rust编程语言";